@@ -0,0 +1,51 @@
+//! Audio pipeline benches on generated fixtures: 5 s of speech-like audio
+//! at common native rates. `cargo bench -p forgeai-companion-core`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use forgeai_companion_core::audio::{self, TARGET_RATE};
+use std::hint::black_box;
+
+const SECS: f32 = 5.0;
+const RATES: [u32; 3] = [16000, 44100, 48000];
+
+fn bench_downmix(c: &mut Criterion) {
+    let mono = audio::speech(48000, SECS);
+    let mut group = c.benchmark_group("downmix");
+    for channels in [2, 6] {
+        let input = audio::interleave(&mono, channels);
+        group.throughput(Throughput::Elements(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(channels), &input, |b, input| {
+            b.iter(|| audio::downmix(black_box(input), channels))
+        });
+    }
+    group.finish();
+}
+
+fn bench_vad(c: &mut Criterion) {
+    let input = audio::speech(48000, SECS);
+    c.bench_function("vad/10ms-chunks", |b| {
+        b.iter(|| black_box(&input).chunks(480).filter(|c| audio::is_voice(c, 0.01)).count())
+    });
+}
+
+fn bench_resample(c: &mut Criterion) {
+    let mut group = c.benchmark_group("resample");
+    for rate in RATES {
+        let input = audio::speech(rate, SECS);
+        group.throughput(Throughput::Elements(input.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(rate), &input, |b, input| {
+            b.iter(|| audio::resample(black_box(input), rate, TARGET_RATE))
+        });
+    }
+    group.finish();
+}
+
+fn bench_encode_wav(c: &mut Criterion) {
+    let input = audio::speech(TARGET_RATE, SECS);
+    c.bench_function("encode_wav/16k", |b| {
+        b.iter(|| audio::encode_wav(black_box(&input), TARGET_RATE).unwrap())
+    });
+}
+
+criterion_group!(benches, bench_downmix, bench_vad, bench_resample, bench_encode_wav);
+criterion_main!(benches);
@@ -0,0 +1,482 @@
+//! # Audio Pipeline
+//!
+//! The steps between the microphone callback and the Gateway upload:
+//! downmix interleaved frames to mono, gate on RMS energy (the voice
+//! activity check that ends a recording on silence), resample to 16 kHz
+//! and encode 16-bit PCM WAV.
+//!
+//! For the streamed duplex conversation there is raw 16-bit PCM framing and
+//! an [`EchoCanceller`] that removes the companion's own playback from the
+//! microphone signal. [`voiceprint`] summarizes a speaker's timbre for the
+//! speaker verification gate.
+//!
+//! The generated fixtures (`sine`, `speech`, `interleave`) feed the criterion
+//! benches in `benches/audio_pipeline.rs` and [`bench_pipeline`], which the
+//! app exposes as the `voice_pipeline_bench` command to time each stage on
+//! the user's own hardware.
+
+use serde::Serialize;
+use std::io::Cursor;
+use std::time::Instant;
+
+/// Rate the Gateway expects for transcription
+pub const TARGET_RATE: u32 = 16000;
+
+/// Average interleaved `channels` frames into mono
+pub fn downmix(samples: &[f32], channels: usize) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks(channels)
+        .map(|ch| ch.iter().sum::<f32>() / ch.len() as f32)
+        .collect()
+}
+
+/// Root mean square of `samples` (0 when empty)
+pub fn rms(samples: &[f32]) -> f32 {
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len().max(1) as f32).sqrt()
+}
+
+/// Whether a mono chunk holds voice, i.e. its energy is above `threshold`
+pub fn is_voice(samples: &[f32], threshold: f32) -> bool {
+    rms(samples) > threshold
+}
+
+/// Simple linear interpolation resampler (from_rate → to_rate)
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+    let ratio = from_rate as f64 / to_rate as f64;
+    let out_len = (samples.len() as f64 / ratio) as usize;
+    let mut output = Vec::with_capacity(out_len);
+    for i in 0..out_len {
+        let src_pos = i as f64 * ratio;
+        let idx = src_pos as usize;
+        let frac = src_pos - idx as f64;
+        let s0 = samples[idx.min(samples.len() - 1)];
+        let s1 = samples[(idx + 1).min(samples.len() - 1)];
+        output.push(s0 + (s1 - s0) * frac as f32);
+    }
+    output
+}
+
+/// Encode mono f32 samples to 16-bit WAV bytes
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>, String> {
+    let mut buffer = Vec::with_capacity(44 + samples.len() * 2);
+    {
+        let cursor = Cursor::new(&mut buffer);
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut writer =
+            hound::WavWriter::new(cursor, spec).map_err(|e| format!("WAV writer error: {}", e))?;
+
+        for &sample in samples {
+            let s16 = (sample * 32767.0).clamp(-32768.0, 32767.0) as i16;
+            writer
+                .write_sample(s16)
+                .map_err(|e| format!("WAV write error: {}", e))?;
+        }
+
+        writer
+            .finalize()
+            .map_err(|e| format!("WAV finalize error: {}", e))?;
+    }
+    Ok(buffer)
+}
+
+// ─── Streaming ──────────────────────────────────────
+
+/// Mono f32 samples as 16-bit little-endian PCM
+pub fn encode_pcm16(samples: &[f32]) -> Vec<u8> {
+    samples
+        .iter()
+        .flat_map(|s| ((s * 32767.0).clamp(-32768.0, 32767.0) as i16).to_le_bytes())
+        .collect()
+}
+
+/// 16-bit little-endian PCM as f32 samples (a trailing odd byte is dropped)
+pub fn decode_pcm16(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(2)
+        .map(|b| i16::from_le_bytes([b[0], b[1]]) as f32 / 32768.0)
+        .collect()
+}
+
+/// Acoustic echo canceller: an NLMS adaptive filter that learns the path
+/// from the speaker to the microphone and subtracts the estimated echo of
+/// the far-end (played) signal from the near-end (captured) one.
+///
+/// Feed it time-aligned chunks at one rate; echoes delayed by more than
+/// `taps` samples are not cancelled.
+pub struct EchoCanceller {
+    weights: Vec<f32>,
+    /// Most recent far-end samples, newest last, `taps` long
+    history: Vec<f32>,
+    /// Adaptation step (0..2)
+    step: f32,
+}
+
+impl EchoCanceller {
+    /// Far-end energy below which the filter does not adapt
+    const REGULARIZATION: f32 = 1e-3;
+
+    pub fn new(taps: usize) -> Self {
+        let taps = taps.max(1);
+        Self { weights: vec![0.0; taps], history: vec![0.0; taps], step: 0.5 }
+    }
+
+    /// Cancel the echo of `far` in `near`; both must have the same length
+    /// (missing far-end samples count as silence)
+    pub fn process(&mut self, near: &[f32], far: &[f32]) -> Vec<f32> {
+        let taps = self.weights.len();
+        let mut energy: f32 = self.history.iter().map(|x| x * x).sum();
+        near.iter()
+            .enumerate()
+            .map(|(i, &mic)| {
+                let incoming = far.get(i).copied().unwrap_or(0.0);
+                energy += incoming * incoming - self.history[0] * self.history[0];
+                self.history.rotate_left(1);
+                self.history[taps - 1] = incoming;
+
+                // history[taps - 1 - k] is the far-end sample k steps ago
+                let estimate: f32 = self.weights.iter().zip(self.history.iter().rev()).map(|(w, x)| w * x).sum();
+                let error = mic - estimate;
+                let gain = self.step * error / (energy.max(0.0) + Self::REGULARIZATION);
+                for (w, x) in self.weights.iter_mut().zip(self.history.iter().rev()) {
+                    *w += gain * x;
+                }
+                error
+            })
+            .collect()
+    }
+}
+
+/// Mono f32 samples and the sample rate of 16-bit WAV bytes (channels are
+/// averaged)
+pub fn decode_wav(bytes: &[u8]) -> Result<(Vec<f32>, u32), String> {
+    let reader = hound::WavReader::new(Cursor::new(bytes)).map_err(|e| format!("WAV reader error: {}", e))?;
+    let spec = reader.spec();
+    if spec.bits_per_sample != 16 || spec.sample_format != hound::SampleFormat::Int {
+        return Err("Only 16-bit PCM WAV is supported".into());
+    }
+    let samples: Vec<f32> = reader
+        .into_samples::<i16>()
+        .map(|s| s.map(|s| s as f32 / 32768.0))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("WAV read error: {}", e))?;
+    Ok((downmix(&samples, spec.channels as usize), spec.sample_rate))
+}
+
+// ─── Speaker embedding ──────────────────────────────
+
+/// Analysis frame and hop, in milliseconds
+const FRAME_MS: u32 = 25;
+const HOP_MS: u32 = 10;
+const MEL_BANDS: usize = 26;
+/// Cepstral coefficients kept (c1..=CEPSTRA; c0 is loudness)
+const CEPSTRA: usize = 12;
+/// Frames quieter than this fraction of the loudest one are not speech
+const VOICED_FRACTION: f32 = 0.1;
+
+fn hz_to_mel(hz: f32) -> f32 {
+    2595.0 * (1.0 + hz / 700.0).log10()
+}
+
+fn mel_to_hz(mel: f32) -> f32 {
+    700.0 * (10f32.powf(mel / 2595.0) - 1.0)
+}
+
+/// Mel-frequency cepstral coefficients c1..=CEPSTRA of one windowed frame
+fn mfcc(frame: &[f32], rate: u32) -> [f32; CEPSTRA] {
+    // Direct DFT of the lower half of the spectrum: frames are short and
+    // this runs once per utterance, so no FFT dependency is needed
+    let n = frame.len();
+    let bins = n / 2;
+    let power: Vec<f32> = (0..bins)
+        .map(|k| {
+            let (mut re, mut im) = (0f32, 0f32);
+            for (i, x) in frame.iter().enumerate() {
+                let angle = std::f32::consts::TAU * (k * i % n) as f32 / n as f32;
+                re += x * angle.cos();
+                im -= x * angle.sin();
+            }
+            re * re + im * im
+        })
+        .collect();
+
+    let bin_hz = rate as f32 / n as f32;
+    let (low, high) = (hz_to_mel(100.0), hz_to_mel((rate as f32 / 2.0).min(7600.0)));
+    let edges: Vec<f32> = (0..MEL_BANDS + 2)
+        .map(|i| mel_to_hz(low + (high - low) * i as f32 / (MEL_BANDS + 1) as f32))
+        .collect();
+    let bands: Vec<f32> = edges
+        .windows(3)
+        .map(|e| {
+            let energy: f32 = power
+                .iter()
+                .enumerate()
+                .map(|(k, p)| {
+                    let hz = k as f32 * bin_hz;
+                    let weight = if hz <= e[0] || hz >= e[2] {
+                        0.0
+                    } else if hz <= e[1] {
+                        (hz - e[0]) / (e[1] - e[0])
+                    } else {
+                        (e[2] - hz) / (e[2] - e[1])
+                    };
+                    weight * p
+                })
+                .sum();
+            (energy + 1e-10).ln()
+        })
+        .collect();
+
+    let mut cepstra = [0f32; CEPSTRA];
+    for (c, out) in cepstra.iter_mut().enumerate() {
+        *out = bands
+            .iter()
+            .enumerate()
+            .map(|(m, b)| b * (std::f32::consts::PI * (c + 1) as f32 * (m as f32 + 0.5) / MEL_BANDS as f32).cos())
+            .sum();
+    }
+    cepstra
+}
+
+/// A fixed-length description of the voice in mono `samples`: the mean and
+/// spread of the MFCCs over the voiced frames. `None` when there is less
+/// than half a second of speech.
+///
+/// This is a lightweight timbre signature, not a neural speaker model: it
+/// tells apart voices with clearly different vocal tracts and channels
+/// (another person, a TV across the room), not a determined impersonator.
+pub fn voiceprint(samples: &[f32], rate: u32) -> Option<Vec<f32>> {
+    let frame_len = (rate * FRAME_MS / 1000) as usize;
+    let hop = (rate * HOP_MS / 1000) as usize;
+    if frame_len == 0 || hop == 0 || samples.len() < frame_len {
+        return None;
+    }
+    let window: Vec<f32> = (0..frame_len)
+        .map(|i| 0.54 - 0.46 * (std::f32::consts::TAU * i as f32 / (frame_len - 1) as f32).cos())
+        .collect();
+    let frames: Vec<&[f32]> = samples.windows(frame_len).step_by(hop).collect();
+    let loudest = frames.iter().map(|f| rms(f)).fold(0f32, f32::max);
+    let features: Vec<[f32; CEPSTRA]> = frames
+        .iter()
+        .filter(|f| loudest > 0.0 && rms(f) >= loudest * VOICED_FRACTION)
+        .map(|f| {
+            // Pre-emphasis flattens the spectral tilt before windowing
+            let shaped: Vec<f32> = f
+                .iter()
+                .enumerate()
+                .map(|(i, x)| (x - if i > 0 { 0.97 * f[i - 1] } else { 0.0 }) * window[i])
+                .collect();
+            mfcc(&shaped, rate)
+        })
+        .collect();
+    if features.len() * (HOP_MS as usize) < 500 {
+        return None;
+    }
+
+    let count = features.len() as f32;
+    let mean: Vec<f32> = (0..CEPSTRA).map(|c| features.iter().map(|f| f[c]).sum::<f32>() / count).collect();
+    let spread = (0..CEPSTRA).map(|c| (features.iter().map(|f| (f[c] - mean[c]).powi(2)).sum::<f32>() / count).sqrt());
+    Some(mean.iter().copied().chain(spread).collect())
+}
+
+/// How alike two voiceprints are, 0..=1: one minus their distance relative
+/// to their lengths (0 when the lengths differ). Cosine similarity is not
+/// used because the first coefficient, shared by all voices, dominates it.
+pub fn similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() || a.is_empty() {
+        return 0.0;
+    }
+    let length = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let distance = a.iter().zip(b).map(|(x, y)| (x - y).powi(2)).sum::<f32>().sqrt();
+    let scale = length(a) + length(b);
+    if scale > 0.0 { (1.0 - distance / scale).max(0.0) } else { 0.0 }
+}
+
+// ─── Fixtures ───────────────────────────────────────
+
+/// `secs` of a sine at `freq` Hz and amplitude 0.5
+pub fn sine(freq: f32, rate: u32, secs: f32) -> Vec<f32> {
+    let n = (rate as f32 * secs) as usize;
+    (0..n)
+        .map(|i| 0.5 * (std::f32::consts::TAU * freq * i as f32 / rate as f32).sin())
+        .collect()
+}
+
+/// `secs` of speech-like audio: 300 ms bursts of a few harmonics separated
+/// by 200 ms of low noise, deterministic
+pub fn speech(rate: u32, secs: f32) -> Vec<f32> {
+    let mut seed = 0x2545_f491u32;
+    let mut noise = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 17;
+        seed ^= seed << 5;
+        seed as f32 / u32::MAX as f32 - 0.5
+    };
+    let n = (rate as f32 * secs) as usize;
+    (0..n)
+        .map(|i| {
+            let t = i as f32 / rate as f32;
+            if (t % 0.5) < 0.3 {
+                let pitch = 140.0 + 40.0 * (t * 3.0).sin();
+                (1..=4)
+                    .map(|h| 0.3 / h as f32 * (std::f32::consts::TAU * pitch * h as f32 * t).sin())
+                    .sum::<f32>()
+                    + 0.02 * noise()
+            } else {
+                0.002 * noise()
+            }
+        })
+        .collect()
+}
+
+/// Interleave mono samples into `channels` identical channels
+pub fn interleave(mono: &[f32], channels: usize) -> Vec<f32> {
+    mono.iter().flat_map(|s| std::iter::repeat_n(*s, channels)).collect()
+}
+
+// ─── Runtime benchmark ──────────────────────────────
+
+/// Average time per stage for one recording, in microseconds
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineTimings {
+    pub audio_ms: u64,
+    pub native_rate: u32,
+    pub channels: usize,
+    pub iterations: u32,
+    pub downmix_us: f64,
+    pub vad_us: f64,
+    pub resample_us: f64,
+    pub encode_wav_us: f64,
+    pub total_us: f64,
+    /// Processing time per second of audio
+    pub real_time_factor: f64,
+}
+
+/// Run the pipeline `iterations` times on `secs` of generated speech at
+/// `rate` with `channels` channels and average each stage. Blocking.
+pub fn bench_pipeline(rate: u32, channels: usize, secs: f32, iterations: u32) -> Result<PipelineTimings, String> {
+    let iterations = iterations.max(1);
+    let input = interleave(&speech(rate, secs), channels);
+    // The capture loop checks voice on each ~10 ms callback buffer
+    let chunk = (rate as usize / 100).max(1);
+    let mut stages = [0f64; 4];
+    let mut time = |stage: usize, start: Instant| stages[stage] += start.elapsed().as_secs_f64() * 1e6;
+
+    for _ in 0..iterations {
+        let start = Instant::now();
+        let mono = downmix(&input, channels);
+        time(0, start);
+
+        let start = Instant::now();
+        let voiced = mono.chunks(chunk).filter(|c| is_voice(c, 0.01)).count();
+        std::hint::black_box(voiced);
+        time(1, start);
+
+        let start = Instant::now();
+        let resampled = resample(&mono, rate, TARGET_RATE);
+        time(2, start);
+
+        let start = Instant::now();
+        std::hint::black_box(encode_wav(&resampled, TARGET_RATE)?);
+        time(3, start);
+    }
+
+    let [downmix_us, vad_us, resample_us, encode_wav_us] = stages.map(|s| s / iterations as f64);
+    let total_us = downmix_us + vad_us + resample_us + encode_wav_us;
+    Ok(PipelineTimings {
+        audio_ms: (secs * 1000.0) as u64,
+        native_rate: rate,
+        channels,
+        iterations,
+        downmix_us,
+        vad_us,
+        resample_us,
+        encode_wav_us,
+        total_us,
+        real_time_factor: total_us / (secs as f64 * 1e6),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pipeline_stages() {
+        let mono = sine(440.0, 48000, 1.0);
+        assert_eq!(downmix(&interleave(&mono, 2), 2), mono);
+        assert!((rms(&mono) - 0.5 / 2f32.sqrt()).abs() < 1e-3);
+
+        // Bursts are voice, the gaps between them are not
+        let speech = speech(16000, 0.5);
+        assert!(is_voice(&speech[..1600], 0.01));
+        assert!(!is_voice(&speech[5600..7200], 0.01));
+
+        let resampled = resample(&mono, 48000, TARGET_RATE);
+        assert_eq!(resampled.len(), 16000);
+        assert!(resample(&[], 48000, TARGET_RATE).is_empty());
+
+        let wav = encode_wav(&resampled, TARGET_RATE).unwrap();
+        assert_eq!(wav.len(), 44 + 2 * 16000);
+        assert_eq!(&wav[..4], b"RIFF");
+
+        let timings = bench_pipeline(44100, 2, 0.2, 2).unwrap();
+        assert_eq!(timings.audio_ms, 200);
+        assert!(timings.total_us > 0.0);
+    }
+
+    #[test]
+    fn test_streaming() {
+        let pcm = encode_pcm16(&[0.0, 0.5, -1.0]);
+        assert_eq!(pcm.len(), 6);
+        let back = decode_pcm16(&pcm);
+        assert!((back[1] - 0.5).abs() < 1e-3 && (back[2] + 1.0).abs() < 1e-3);
+
+        // The microphone hears the played speech 40 samples late and quieter:
+        // after converging, the residual echo is far below the input
+        let far = speech(TARGET_RATE, 2.0);
+        let near: Vec<f32> = (0..far.len()).map(|i| if i >= 40 { 0.6 * far[i - 40] } else { 0.0 }).collect();
+        let mut aec = EchoCanceller::new(128);
+        let out: Vec<f32> = near.chunks(320).zip(far.chunks(320)).flat_map(|(n, f)| aec.process(n, f)).collect();
+        let tail = out.len() / 2;
+        assert!(rms(&out[tail..]) < rms(&near[tail..]) / 10.0);
+    }
+
+    #[test]
+    fn test_voiceprint() {
+        // A voice: a pitch with harmonics rolling off by `tilt`
+        let voice = |pitch: f32, tilt: f32, secs: f32| -> Vec<f32> {
+            let n = (TARGET_RATE as f32 * secs) as usize;
+            (0..n)
+                .map(|i| {
+                    let t = i as f32 / TARGET_RATE as f32;
+                    let f0 = pitch * (1.0 + 0.05 * (t * 4.0).sin());
+                    (1..=12).map(|h| tilt.powi(h) * (std::f32::consts::TAU * f0 * h as f32 * t).sin()).sum::<f32>() * 0.1
+                })
+                .collect()
+        };
+        let enrolled = voiceprint(&voice(120.0, 0.8, 2.0), TARGET_RATE).unwrap();
+        let again = voiceprint(&voice(122.0, 0.8, 1.5).iter().map(|s| s * 0.4).collect::<Vec<_>>(), TARGET_RATE).unwrap();
+        let other = voiceprint(&voice(230.0, 0.5, 1.5), TARGET_RATE).unwrap();
+        let same = similarity(&enrolled, &again);
+        let different = similarity(&enrolled, &other);
+        assert!(same > 0.9, "same voice scored {}", same);
+        assert!(different < 0.85, "other voice scored {}", different);
+
+        assert!(voiceprint(&voice(120.0, 0.8, 0.2), TARGET_RATE).is_none());
+        let wav = encode_wav(&sine(440.0, TARGET_RATE, 0.1), TARGET_RATE).unwrap();
+        let (decoded, rate) = decode_wav(&wav).unwrap();
+        assert_eq!((decoded.len(), rate), (1600, TARGET_RATE));
+    }
+}
@@ -0,0 +1,70 @@
+//! # Events
+//!
+//! Engines report progress and state changes through an [`EventSink`]
+//! without knowing who listens. The desktop app installs a sink that
+//! forwards to its window and the OS notification center; without one —
+//! in headless mode and in tests — events are dropped.
+
+use serde::Serialize;
+use std::sync::OnceLock;
+
+/// Receiver of engine events
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: &str, payload: serde_json::Value);
+
+    /// Show an OS notification
+    fn notify(&self, _title: &str, _body: &str) {}
+}
+
+static SINK: OnceLock<Box<dyn EventSink>> = OnceLock::new();
+
+/// Install the sink (once; later calls are ignored and return false)
+pub fn set_sink(sink: impl EventSink + 'static) -> bool {
+    SINK.set(Box::new(sink)).is_ok()
+}
+
+/// Emit an event (no-op without a sink)
+pub fn emit<S: Serialize>(event: &str, payload: S) {
+    let Some(sink) = SINK.get() else {
+        return;
+    };
+    match serde_json::to_value(payload) {
+        Ok(value) => sink.emit(event, value),
+        Err(e) => tracing::warn!("Failed to serialize '{}': {}", event, e),
+    }
+}
+
+/// Show an OS notification (no-op without a sink)
+pub fn notify(title: &str, body: &str) {
+    if let Some(sink) = SINK.get() {
+        sink.notify(title, body);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    struct Recorder(Arc<Mutex<Vec<(String, serde_json::Value)>>>);
+
+    impl EventSink for Recorder {
+        fn emit(&self, event: &str, payload: serde_json::Value) {
+            self.0.lock().unwrap().push((event.to_string(), payload));
+        }
+    }
+
+    #[test]
+    fn test_sink_receives_events() {
+        emit("dropped", 1);
+        let events = Arc::new(Mutex::new(Vec::new()));
+        assert!(set_sink(Recorder(events.clone())));
+        assert!(!set_sink(Recorder(events.clone())));
+        emit("wake-word-toggled", serde_json::json!({ "enabled": true }));
+        notify("ignored", "by default");
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].0, "wake-word-toggled");
+        assert_eq!(events[0].1["enabled"], true);
+    }
+}
@@ -0,0 +1,307 @@
+//! # Temporary Grants
+//!
+//! Lets the user lift confirmation prompts for a while, within a scope:
+//! "allow shell commands in ~/Projects/foo for the next 30 minutes". The
+//! safety checks consult the grants before their confirmation rules — but
+//! after the hard blocks, which no grant lifts. A grant only removes the
+//! prompt for what would otherwise be allowed after confirming.
+//!
+//! | Kind           | Scope                       | Covers                                  |
+//! |----------------|-----------------------------|-----------------------------------------|
+//! | `shell`        | a directory                 | commands run with their `cwd` inside it, naming no path outside it |
+//! | `files`        | a directory                 | file operations and downloads inside it |
+//! | `kill_process` | a process name (`.exe` optional) | ending that process                |
+//!
+//! Directories are compared after resolving symlinks, and a shell grant
+//! only applies when the command's `cwd` exists. A command that names an
+//! absolute or home path outside the grant, climbs with `..`, or uses an
+//! environment variable (which could point anywhere) still asks.
+//!
+//! Grants live in memory only: they end when they expire, when revoked, or
+//! when the companion quits, whichever comes first.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Longest a grant can last
+pub const MAX_MINUTES: u32 = 8 * 60;
+
+static GRANTS: Mutex<Vec<Grant>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GrantKind {
+    Shell,
+    Files,
+    KillProcess,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Grant {
+    pub id: String,
+    pub kind: GrantKind,
+    /// Directory, or process name for `kill_process`
+    pub scope: String,
+    pub created_at: String,
+    pub expires_at: String,
+    #[serde(skip)]
+    expires: Instant,
+}
+
+/// `path` lowercased with backslashes and `~` expanded, for prefix checks
+fn normalize_dir(path: &str) -> String {
+    let path = path.trim();
+    let expanded = match path.strip_prefix('~') {
+        Some(rest) => format!("{}{}", crate::safety::get_user_home().to_string_lossy(), rest),
+        None => path.to_string(),
+    };
+    let expanded = expanded.strip_prefix(r"\\?\").unwrap_or(&expanded);
+    expanded.replace('/', "\\").to_lowercase().trim_end_matches('\\').to_string()
+}
+
+/// `path` normalized with symlinks resolved: its deepest existing ancestor
+/// is canonicalized and the rest appended, so a path that does not exist
+/// yet still resolves to where it would be created
+fn resolve_dir(path: &str) -> String {
+    let normalized = normalize_dir(path);
+    if normalized.split('\\').any(|part| part == "..") {
+        return normalized;
+    }
+    let expanded = match path.trim().strip_prefix('~') {
+        Some(rest) => format!("{}{}", crate::safety::get_user_home().to_string_lossy(), rest),
+        None => path.trim().to_string(),
+    };
+    let mut existing = std::path::Path::new(&expanded);
+    let mut rest = Vec::new();
+    loop {
+        if let Ok(real) = std::fs::canonicalize(existing) {
+            let mut real = real;
+            real.extend(rest.iter().rev());
+            return normalize_dir(&real.to_string_lossy());
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                rest.push(name.to_os_string());
+                existing = parent;
+            }
+            _ => return normalized,
+        }
+    }
+}
+
+/// What a shell command names beyond its working directory: absolute and
+/// home paths (`C:\x`, `/x`, `\\\\server`, `~`), drive changes (`D:`),
+/// `..` climbs and environment variables. The paths are returned as
+/// written; anything that cannot be resolved to a path is `None`.
+fn referenced_paths(command: &str) -> Vec<Option<String>> {
+    let is_drive = |t: &[char]| t.len() >= 2 && t[0].is_ascii_alphabetic() && t[1] == ':' && (t.len() == 2 || t[2] == '\\' || t[2] == '/');
+    let mut found = Vec::new();
+    for token in command.split(|c: char| c.is_whitespace() || "'\"`;|&()<>,=".contains(c)) {
+        let lower = token.to_lowercase();
+        if lower.contains("$env:") || lower.contains("${") || lower.starts_with("$home") || lower.starts_with("$profile")
+            || lower.starts_with("$pshome") || lower.matches('%').count() >= 2
+            || token.split(['\\', '/']).any(|part| part == "..")
+        {
+            found.push(None);
+            continue;
+        }
+        let chars: Vec<char> = token.chars().collect();
+        // A path can follow a parameter name, as in `-Path:C:\x`
+        let start = (0..chars.len()).find(|&i| {
+            let after_parameter = i > 0 && chars[0] == '-' && chars[i - 1] == ':';
+            (i == 0 && matches!(chars[0], '/' | '\\' | '~'))
+                || (after_parameter && matches!(chars[i], '/' | '\\' | '~'))
+                || ((i == 0 || !chars[i - 1].is_ascii_alphanumeric()) && is_drive(&chars[i..]))
+        });
+        if let Some(start) = start {
+            found.push(Some(chars[start..].iter().collect()));
+        }
+    }
+    found
+}
+
+fn normalize_process(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+}
+
+/// Whether `grant` covers `subject` (a path, or a process name)
+fn covers(grant: &Grant, subject: &str) -> bool {
+    match grant.kind {
+        GrantKind::KillProcess => normalize_process(subject) == grant.scope,
+        GrantKind::Shell | GrantKind::Files => {
+            let subject = resolve_dir(subject);
+            // `..` could climb out of the granted directory
+            !subject.split('\\').any(|part| part == "..")
+                && (subject == grant.scope || subject.starts_with(&format!("{}\\", grant.scope)))
+        }
+    }
+}
+
+fn prune(grants: &mut Vec<Grant>) {
+    grants.retain(|g| g.expires > Instant::now());
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Grant `kind` within `scope` for `minutes`
+pub fn add(kind: GrantKind, scope: &str, minutes: u32) -> Result<Grant, String> {
+    if !(1..=MAX_MINUTES).contains(&minutes) {
+        return Err(format!("A grant lasts between 1 and {} minutes", MAX_MINUTES));
+    }
+    if scope.trim().is_empty() {
+        return Err("A grant needs a scope: a directory, or a process name".into());
+    }
+    let scope = match kind {
+        GrantKind::KillProcess => {
+            if crate::safety::is_protected_process(scope.trim()) {
+                return Err(format!("'{}' is a protected process and cannot be granted", scope.trim()));
+            }
+            normalize_process(scope)
+        }
+        GrantKind::Shell | GrantKind::Files => {
+            let dir = resolve_dir(scope);
+            if dir.split('\\').any(|part| part == "..") {
+                return Err("Grant a directory without '..' in it".into());
+            }
+            if crate::safety::is_protected_path(&dir) || !crate::safety::is_user_directory(&dir) {
+                return Err(format!("Cannot grant '{}': only user directories can be granted", scope.trim()));
+            }
+            dir
+        }
+    };
+    let now = chrono::Utc::now();
+    let duration = Duration::from_secs(minutes as u64 * 60);
+    let grant = Grant {
+        id: format!("grant-{}", now.timestamp_micros()),
+        kind,
+        scope,
+        created_at: now.to_rfc3339(),
+        expires_at: (now + duration).to_rfc3339(),
+        expires: Instant::now() + duration,
+    };
+    let mut grants = GRANTS.lock().map_err(|e| e.to_string())?;
+    prune(&mut grants);
+    grants.push(grant.clone());
+    tracing::info!("[Safety] Granted {:?} in '{}' for {} min ({})", grant.kind, grant.scope, minutes, grant.id);
+    Ok(grant)
+}
+
+/// Grants in force, oldest first
+pub fn list() -> Vec<Grant> {
+    let Ok(mut grants) = GRANTS.lock() else { return Vec::new() };
+    prune(&mut grants);
+    grants.clone()
+}
+
+/// End grant `id` early; false when there is no such grant in force
+pub fn revoke(id: &str) -> bool {
+    let Ok(mut grants) = GRANTS.lock() else { return false };
+    prune(&mut grants);
+    let before = grants.len();
+    grants.retain(|g| g.id != id);
+    if grants.len() < before {
+        tracing::info!("[Safety] Grant {} revoked", id);
+    }
+    grants.len() < before
+}
+
+/// The grant in force covering `kind` on `subject`, if any
+pub fn covering(kind: GrantKind, subject: &str) -> Option<Grant> {
+    let mut grants = GRANTS.lock().ok()?;
+    prune(&mut grants);
+    grants.iter().find(|g| g.kind == kind && covers(g, subject)).cloned()
+}
+
+/// `verdict`, without its confirmation prompt when a grant covers `subject`
+pub fn apply(kind: GrantKind, subject: Option<&str>, verdict: crate::safety::SafetyVerdict) -> crate::safety::SafetyVerdict {
+    if !verdict.allowed || !verdict.requires_confirmation {
+        return verdict;
+    }
+    match subject.and_then(|s| covering(kind, s)) {
+        Some(grant) => crate::safety::SafetyVerdict {
+            requires_confirmation: false,
+            reason: format!("{} (allowed by {} until {})", verdict.reason, grant.id, grant.expires_at),
+            ..verdict
+        },
+        None => verdict,
+    }
+}
+
+/// `verdict` for shell `command`, without its confirmation prompt when a
+/// shell grant covers `cwd` — which must exist — and every path the command
+/// names
+pub fn apply_shell(command: &str, cwd: Option<&str>, verdict: crate::safety::SafetyVerdict) -> crate::safety::SafetyVerdict {
+    if !verdict.allowed || !verdict.requires_confirmation {
+        return verdict;
+    }
+    let Some(cwd) = cwd.filter(|c| std::path::Path::new(c.trim()).is_dir()) else {
+        return verdict;
+    };
+    let outside = referenced_paths(command)
+        .into_iter()
+        .any(|path| path.is_none_or(|p| covering(GrantKind::Shell, &p).is_none()));
+    if outside {
+        return verdict;
+    }
+    apply(GrantKind::Shell, Some(cwd), verdict)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grant(kind: GrantKind, scope: &str) -> Grant {
+        Grant {
+            id: "g".into(),
+            kind,
+            scope: scope.into(),
+            created_at: String::new(),
+            expires_at: String::new(),
+            expires: Instant::now() + Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn test_grant_scopes() {
+        let projects = grant(GrantKind::Shell, &normalize_dir("D:/Projects/foo/"));
+        assert!(covers(&projects, "d:\\projects\\foo"));
+        assert!(covers(&projects, "D:/Projects/foo/src"));
+        assert!(!covers(&projects, "D:/Projects/foobar"));
+        assert!(!covers(&projects, "D:/Projects/foo/../bar"));
+
+        let notepad = grant(GrantKind::KillProcess, &normalize_process("Notepad.exe"));
+        assert!(covers(&notepad, "notepad"));
+        assert!(!covers(&notepad, "notepad++.exe"));
+
+        assert!(add(GrantKind::Shell, "C:\\Windows\\System32", 30).is_err());
+        assert!(add(GrantKind::KillProcess, "lsass.exe", 30).is_err());
+        assert!(add(GrantKind::Files, "D:\\Data", 0).is_err());
+
+        let home = crate::safety::get_user_home().join(format!(".forgeai-grant-test-{}", std::process::id()));
+        std::fs::create_dir_all(home.join("logs")).unwrap();
+        let (dir, logs) = (home.to_string_lossy().to_string(), home.join("logs").to_string_lossy().to_string());
+        let added = add(GrantKind::Shell, &dir, 5).unwrap();
+        let asks = |command: &str, cwd: Option<&str>| crate::safety::shell_command_verdict(command, cwd).requires_confirmation;
+        assert!(!asks("del old.log", Some(&logs)));
+        assert!(!asks(&format!("del {}", home.join("logs").join("old.log").display()), Some(&dir)));
+        // Hard blocks are never lifted, and other directories still ask
+        assert!(!crate::safety::shell_command_verdict("format C:", Some(&dir)).allowed);
+        assert!(asks("del old.log", None));
+        // So do a cwd that does not exist, and paths outside the grant
+        assert!(asks("del old.log", Some(&home.join("missing").to_string_lossy())));
+        assert!(asks("del C:\\Users\\me\\notes.txt", Some(&dir)));
+        assert!(asks("Remove-Item -Path:/etc/hosts", Some(&dir)));
+        assert!(asks("del ..\\other\\old.log", Some(&logs)));
+        assert!(asks("del $env:USERPROFILE\\old.log", Some(&dir)));
+        assert!(asks("del ~/old.log", Some(&dir)));
+        assert!(revoke(&added.id));
+        assert!(asks("del old.log", Some(&dir)));
+        std::fs::remove_dir_all(&home).unwrap();
+
+        assert_eq!(referenced_paths("git log HEAD~1 -- src/main.rs"), Vec::<Option<String>>::new());
+        assert_eq!(referenced_paths("copy a.txt 'D:\\Backup\\a.txt'"), vec![Some("D:\\Backup\\a.txt".to_string())]);
+        assert_eq!(referenced_paths("curl https://example.com/x"), Vec::<Option<String>>::new());
+    }
+}
@@ -0,0 +1,24 @@
+//! # ForgeAI Companion Core
+//!
+//! The parts of the companion that need no window, shared by the desktop
+//! app and its headless mode and testable without a GUI:
+//!
+//! - `audio` — downmix, voice gate, resampling, WAV and PCM encoding, echo
+//!   cancellation and speaker voiceprints
+//! - `safety` — the guardrails every local action passes through
+//! - `grants` — temporary, scoped permissions that lift `safety`
+//!   confirmations
+//! - `events` — the [`events::EventSink`] engines report to; the desktop app
+//!   installs one that forwards to its window
+//!
+//! Voice, wake word, local actions and the Gateway connection are not in
+//! this crate and are not extracted yet. Each reaches, directly or through
+//! the modules it calls, app state such as settings, status, usage metrics
+//! and the Tauri handle, so moving one means first moving or abstracting
+//! those modules behind `events`. Until then the headless CLI reuses them
+//! from the app crate, and their tests run there.
+
+pub mod audio;
+pub mod events;
+pub mod grants;
+pub mod safety;
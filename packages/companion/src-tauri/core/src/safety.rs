@@ -0,0 +1,939 @@
+//! # ForgeAI Safety System — Anti-Disaster Guardrails
+//!
+//! This module enforces hard safety boundaries that CANNOT be bypassed,
+//! even if the LLM or the user explicitly requests it.
+//!
+//! ## Rules:
+//! 1. NEVER delete system-critical files or directories
+//! 2. NEVER format, wipe, or partition any disk
+//! 3. NEVER modify Windows registry boot/security keys
+//! 4. NEVER disable antivirus, firewall, or Windows Defender
+//! 5. NEVER access other users' private data
+//! 6. ALL destructive actions require explicit user confirmation
+//! 7. File operations are sandboxed to user directories by default
+//!
+//! Temporary grants (see `grants`) can lift rule 6 for a scope and a while;
+//! nothing lifts the others.
+
+use regex::Regex;
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Verdicts kept for diagnostics
+const VERDICT_HISTORY: usize = 50;
+
+/// Version of the rules below, reported in the companion status; bump it
+/// whenever a rule is added, removed or loosened
+///
+/// - 2: temporary grants; a shell grant needs an existing `cwd` and covers
+///   only commands naming no path outside it
+/// - 3: Winlogon, LSA and credential provider keys are never read
+/// - 4: Shortcuts run without confirmation; AppleScript asks and never
+///   reaches a shell
+/// - 5: Linux media control, screen lock and idle inhibition run without
+///   confirmation
+pub const POLICY_VERSION: u32 = 5;
+
+static RECENT_VERDICTS: Mutex<VecDeque<RecordedVerdict>> = Mutex::new(VecDeque::new());
+
+/// Risk level for an action
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub enum RiskLevel {
+    Safe,       // Read-only, no side effects
+    Low,        // Minor changes, easily reversible
+    Medium,     // File modifications, app launches
+    High,       // Bulk operations, elevated actions
+    Blocked,    // NEVER allowed, hard block
+}
+
+/// Result of a safety check
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SafetyVerdict {
+    pub allowed: bool,
+    pub risk: RiskLevel,
+    pub reason: String,
+    pub requires_confirmation: bool,
+}
+
+/// A past safety check, for diagnostics
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct RecordedVerdict {
+    pub timestamp: String,
+    /// `file:<operation>`, `mqtt:<operation>`, `shell`, `process`, `registry`,
+    /// `shortcut`, `applescript`, `desktop`, `script` or `secrets`
+    pub check: String,
+    pub subject: String,
+    pub verdict: SafetyVerdict,
+}
+
+/// Keep `verdict` in the history under `subject`. The `check_*` functions
+/// record what they check; callers that must not record it (a command with
+/// its secrets resolved) take the `*_verdict` and record what to show.
+pub fn record(check: String, subject: &str, verdict: SafetyVerdict) -> SafetyVerdict {
+    if let Ok(mut recent) = RECENT_VERDICTS.lock() {
+        if recent.len() >= VERDICT_HISTORY {
+            recent.pop_front();
+        }
+        recent.push_back(RecordedVerdict {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            check,
+            subject: subject.to_string(),
+            verdict: verdict.clone(),
+        });
+    }
+    verdict
+}
+
+/// The most recent safety verdicts, oldest first
+pub fn recent_verdicts() -> Vec<RecordedVerdict> {
+    RECENT_VERDICTS.lock().map(|r| r.iter().cloned().collect()).unwrap_or_default()
+}
+
+/// Directories that are ALWAYS protected (hard block)
+const PROTECTED_DIRS: &[&str] = &[
+    "C:\\Windows",
+    "C:\\Windows\\System32",
+    "C:\\Windows\\SysWOW64",
+    "C:\\Windows\\WinSxS",
+    "C:\\Windows\\Boot",
+    "C:\\Windows\\Fonts",
+    "C:\\Windows\\Installer",
+    "C:\\Windows\\servicing",
+    "C:\\Program Files",
+    "C:\\Program Files (x86)",
+    "C:\\ProgramData\\Microsoft",
+    "C:\\Recovery",
+    "C:\\$Recycle.Bin",
+    "C:\\System Volume Information",
+    "C:\\Boot",
+    "C:\\EFI",
+];
+
+/// File extensions that are ALWAYS protected from deletion
+const PROTECTED_EXTENSIONS: &[&str] = &[
+    "sys", "dll", "exe", "drv", "ocx", "cpl", "scr",
+    "msi", "msp", "mst", "cat", "inf", "mui",
+];
+
+/// Commands/patterns that are ALWAYS blocked
+const BLOCKED_COMMANDS: &[&str] = &[
+    "format ",
+    "format.com",
+    "diskpart",
+    "clean all",
+    "rd /s /q c:\\",
+    "rd /s /q c:/",
+    "rmdir /s /q c:\\",
+    "rmdir /s /q c:/",
+    "del /f /s /q c:\\",
+    "del /f /s /q c:/",
+    "cipher /w:",
+    "sfc /scannow",          // Can cause issues if misused
+    "bcdedit",               // Boot config
+    "bcdboot",
+    "bootrec",
+    "reagentc",
+    "dism /online /cleanup", // Can break Windows
+    "powershell -ep bypass", // Execution policy bypass
+    "set-executionpolicy unrestricted",
+    "disable-windowsoptionalfeature",
+    "reg delete hklm\\system",
+    "reg delete hklm\\software\\microsoft",
+    "reg delete hklm\\sam",
+    "reg delete hklm\\security",
+    "net stop windefend",
+    "sc stop windefend",
+    "sc delete",
+    "netsh advfirewall set allprofiles state off",
+    "wmic os delete",
+    "shutdown /s",
+    "shutdown /r",
+    "shutdown /f",
+    "taskkill /f /im csrss",
+    "taskkill /f /im lsass",
+    "taskkill /f /im winlogon",
+    "taskkill /f /im svchost",
+    "taskkill /f /im smss",
+    "takeown /f c:\\windows",
+    "icacls c:\\windows",
+    "mklink /d c:\\windows",
+];
+
+/// Registry paths that are NEVER writable
+const PROTECTED_REGISTRY: &[&str] = &[
+    "HKLM\\SYSTEM\\CurrentControlSet",
+    "HKLM\\SAM",
+    "HKLM\\SECURITY",
+    "HKLM\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Run",
+    "HKLM\\SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Winlogon",
+    "HKLM\\SOFTWARE\\Policies",
+    "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run",
+];
+
+/// Hives `registry_read` may read, by short and long name. HKU is left out:
+/// it holds other users' settings.
+const READABLE_HIVES: &[(&str, &str)] = &[
+    ("HKCU", "HKEY_CURRENT_USER"),
+    ("HKLM", "HKEY_LOCAL_MACHINE"),
+    ("HKCR", "HKEY_CLASSES_ROOT"),
+];
+
+/// Registry paths that are NEVER readable: account and secret stores, the
+/// LSA keys (which hold the boot key), autologon and credential provider
+/// settings, and apps known to keep passwords in the registry. Checked
+/// after [`registry_canonical`], so `CurrentControlSet` also covers the
+/// numbered control sets and 32-bit (`WOW6432Node`) views are included.
+const UNREADABLE_REGISTRY: &[&str] = &[
+    "HKLM\\SAM",
+    "HKLM\\SECURITY",
+    "HKLM\\SYSTEM\\CurrentControlSet\\Control\\Lsa",
+    "HKLM\\SYSTEM\\CurrentControlSet\\Services\\SNMP\\Parameters",
+    "HKLM\\SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Winlogon",
+    "HKLM\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Authentication",
+    "HKLM\\SOFTWARE\\RealVNC",
+    "HKLM\\SOFTWARE\\TightVNC",
+    "HKCU\\Software\\ORL\\WinVNC3",
+    "HKCU\\Software\\SimonTatham\\PuTTY\\Sessions",
+];
+
+/// AppleScript that is NEVER run: whatever reaches a shell (`do shell
+/// script`, Terminal's `do script`) or runs code the script builds (`run
+/// script`, AppleScriptObjC), since a shell command assembled at run time
+/// cannot be checked. Shell commands go through the `shell` action and its
+/// rules instead. Also elevation, turning off System Integrity Protection
+/// and reading the keychain. Matched against [`applescript_normalized`].
+const BLOCKED_APPLESCRIPT: &[&str] = &[
+    "do shell script",
+    "do script",
+    "run script",
+    "load script",
+    "use framework",
+    "current application's",
+    "with administrator privileges",
+    "csrutil",
+    "spctl --master-disable",
+    "security dump-keychain",
+    "security find-generic-password",
+    "security find-internet-password",
+];
+
+/// Processes that can NEVER be killed
+const PROTECTED_PROCESSES: &[&str] = &[
+    "csrss.exe", "lsass.exe", "smss.exe", "wininit.exe",
+    "winlogon.exe", "services.exe", "svchost.exe", "dwm.exe",
+    "explorer.exe", "taskmgr.exe", "msmpeng.exe", "securityhealthservice.exe",
+];
+
+/// Check if a file path is in a protected directory
+pub fn is_protected_path(path: &str) -> bool {
+    let normalized = path.replace('/', "\\").to_lowercase();
+    for dir in PROTECTED_DIRS {
+        if normalized.starts_with(&dir.to_lowercase()) {
+            return true;
+        }
+    }
+    // Check protected extensions for system-level paths
+    if let Some(ext) = Path::new(&normalized).extension() {
+        let ext_str = ext.to_string_lossy().to_lowercase();
+        if PROTECTED_EXTENSIONS.contains(&ext_str.as_str()) {
+            // Only block if it's in a system location
+            if normalized.starts_with("c:\\windows")
+                || normalized.starts_with("c:\\program files")
+                || normalized.starts_with("c:\\programdata\\microsoft")
+            {
+                return true;
+            }
+        }
+    }
+    false
+}
+
+/// Check if a shell command is blocked
+pub fn is_blocked_command(command: &str) -> Option<String> {
+    let cmd_lower = command.to_lowercase().trim().to_string();
+
+    for blocked in BLOCKED_COMMANDS {
+        if cmd_lower.contains(&blocked.to_lowercase()) {
+            return Some(format!(
+                "BLOCKED: Command contains '{}' which could damage the system",
+                blocked
+            ));
+        }
+    }
+
+    // Check for recursive delete on root or system dirs
+    // NOTE: these only block drive-root targets (e.g. C:\), NOT subdirectories
+    let dangerous_patterns = vec![
+        Regex::new(r"(?i)rm\s+-rf?\s+/\s*$").ok(),
+        Regex::new(r"(?i)del\s+/[sfq]+\s+[a-z]:\\\s*$").ok(),
+        Regex::new(r"(?i)rmdir\s+/[sq]+\s+[a-z]:\\\s*$").ok(),
+        Regex::new(r#"(?i)remove-item\s+["']?[a-z]:\\["']?\s+-recurse"#).ok(),
+        Regex::new(r"(?i)format\s+[a-z]:").ok(),
+        Regex::new(r"(?i)(?:remove-item|del|rd|rmdir)\s+.*(?:c:\\windows|system32)").ok(),
+    ];
+
+    for pattern in dangerous_patterns.iter().flatten() {
+        if pattern.is_match(&cmd_lower) {
+            return Some(format!(
+                "BLOCKED: Pattern matches a dangerous operation: {}",
+                pattern.as_str()
+            ));
+        }
+    }
+
+    None
+}
+
+/// Check if a process name is protected
+pub fn is_protected_process(name: &str) -> bool {
+    let name_lower = name.to_lowercase();
+    PROTECTED_PROCESSES
+        .iter()
+        .any(|p| name_lower == p.to_lowercase() || name_lower == p.replace(".exe", "").to_lowercase())
+}
+
+/// Check if a registry path is protected
+pub fn is_protected_registry(path: &str) -> bool {
+    let path_lower = path.to_lowercase();
+    PROTECTED_REGISTRY
+        .iter()
+        .any(|p| path_lower.starts_with(&p.to_lowercase()))
+}
+
+/// Get the user's home directory for sandboxing
+pub fn get_user_home() -> PathBuf {
+    dirs::home_dir().unwrap_or_else(|| PathBuf::from("C:\\Users\\Default"))
+}
+
+/// Check if a path is within allowed user directories
+pub fn is_user_directory(path: &str) -> bool {
+    let normalized = path.replace('/', "\\").to_lowercase();
+    let home = get_user_home()
+        .to_string_lossy()
+        .to_lowercase()
+        .replace('/', "\\");
+
+    // Allowed: user home, temp, and any non-system drive
+    normalized.starts_with(&home)
+        || normalized.starts_with(&std::env::temp_dir().to_string_lossy().to_lowercase())
+        || (normalized.len() >= 3
+            && normalized.chars().nth(0).map_or(false, |c| c.is_ascii_alphabetic())
+            && normalized.chars().nth(1) == Some(':')
+            && !is_protected_path(&normalized))
+}
+
+/// Main safety check for file operations
+pub fn check_file_operation(operation: &str, path: &str) -> SafetyVerdict {
+    record(format!("file:{}", operation), path, file_operation_verdict(operation, path))
+}
+
+/// Verdict for a file operation without recording it (confirmation pre-checks)
+pub fn file_operation_verdict(operation: &str, path: &str) -> SafetyVerdict {
+    crate::grants::apply(crate::grants::GrantKind::Files, Some(path), file_rules(operation, path))
+}
+
+fn file_rules(operation: &str, path: &str) -> SafetyVerdict {
+    let op = operation.to_lowercase();
+
+    // Read operations are always safe
+    if op == "read" || op == "list" || op == "stat" || op == "exists" {
+        return SafetyVerdict {
+            allowed: true,
+            risk: RiskLevel::Safe,
+            reason: "Read operations are always allowed".into(),
+            requires_confirmation: false,
+        };
+    }
+
+    // Check protected paths for any write/delete operation
+    if is_protected_path(path) {
+        return SafetyVerdict {
+            allowed: false,
+            risk: RiskLevel::Blocked,
+            reason: format!("BLOCKED: '{}' is a system-protected path. This operation is never allowed.", path),
+            requires_confirmation: false,
+        };
+    }
+
+    // Delete operations: allowed anywhere that's not a protected system path
+    // The is_protected_path check above already blocks C:\Windows, System32, etc.
+    if op == "delete" || op == "remove" || op == "rmdir" {
+        return SafetyVerdict {
+            allowed: true,
+            risk: RiskLevel::Medium,
+            reason: format!("Delete operation on '{}' — path is not system-protected", path),
+            requires_confirmation: false,
+        };
+    }
+
+    // Write/create/move operations
+    if op == "write" || op == "create" || op == "move" || op == "copy" || op == "rename" {
+        if !is_user_directory(path) {
+            return SafetyVerdict {
+                allowed: false,
+                risk: RiskLevel::Blocked,
+                reason: format!("BLOCKED: Cannot write to '{}' — outside user directory", path),
+                requires_confirmation: false,
+            };
+        }
+
+        return SafetyVerdict {
+            allowed: true,
+            risk: RiskLevel::Medium,
+            reason: "File operation within user directory".into(),
+            requires_confirmation: false,
+        };
+    }
+
+    // Default: require confirmation for unknown operations
+    SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::Medium,
+        reason: format!("Unknown operation '{}' — requires confirmation", operation),
+        requires_confirmation: true,
+    }
+}
+
+/// Main safety check for shell commands (`cwd` is where they would run)
+pub fn check_shell_command(command: &str, cwd: Option<&str>) -> SafetyVerdict {
+    record("shell".into(), command, shell_command_verdict(command, cwd))
+}
+
+/// Verdict for a shell command without recording it; a shell grant covering
+/// `cwd` and the paths the command names lifts the confirmation
+pub fn shell_command_verdict(command: &str, cwd: Option<&str>) -> SafetyVerdict {
+    crate::grants::apply_shell(command, cwd, shell_rules(command))
+}
+
+fn shell_rules(command: &str) -> SafetyVerdict {
+    // Check blocked commands first
+    if let Some(reason) = is_blocked_command(command) {
+        return SafetyVerdict {
+            allowed: false,
+            risk: RiskLevel::Blocked,
+            reason,
+            requires_confirmation: false,
+        };
+    }
+
+    let cmd_lower = command.to_lowercase();
+
+    // Safe read-only commands
+    let safe_commands = [
+        "dir ", "ls ", "type ", "cat ", "echo ", "where ", "whoami",
+        "hostname", "ipconfig", "systeminfo", "tasklist", "wmic cpu",
+        "wmic memorychip", "wmic diskdrive", "ver", "date /t", "time /t",
+        "set ", "path", "tree ",
+    ];
+    for safe in &safe_commands {
+        if cmd_lower.starts_with(safe) || cmd_lower == safe.trim() {
+            return SafetyVerdict {
+                allowed: true,
+                risk: RiskLevel::Safe,
+                reason: "Read-only command".into(),
+                requires_confirmation: false,
+            };
+        }
+    }
+
+    // Medium-risk: app launches, file operations
+    let medium_commands = [
+        "start ", "open ", "code ", "notepad", "calc", "mspaint",
+        "mkdir ", "md ", "copy ", "xcopy ", "move ", "ren ",
+        "npm ", "pnpm ", "node ", "python ", "pip ",
+        "git ", "curl ", "wget ",
+    ];
+    for med in &medium_commands {
+        if cmd_lower.starts_with(med) {
+            return SafetyVerdict {
+                allowed: true,
+                risk: RiskLevel::Medium,
+                reason: format!("Application/file command: {}", med.trim()),
+                requires_confirmation: false,
+            };
+        }
+    }
+
+    // High-risk: needs confirmation
+    let high_commands = [
+        "del ", "erase ", "rmdir ", "rd ", "taskkill ",
+        "net ", "netsh ", "sc ", "reg ",
+        "powershell ", "pwsh ", "cmd /c",
+        "wmic ", "runas ",
+    ];
+    for high in &high_commands {
+        if cmd_lower.starts_with(high) {
+            return SafetyVerdict {
+                allowed: true,
+                risk: RiskLevel::High,
+                reason: format!("High-risk command '{}' requires user confirmation", high.trim()),
+                requires_confirmation: true,
+            };
+        }
+    }
+
+    // Default: unknown commands require confirmation
+    SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::High,
+        reason: "Unknown command — requires user confirmation".into(),
+        requires_confirmation: true,
+    }
+}
+
+/// Check process kill operation
+pub fn check_process_kill(process_name: &str) -> SafetyVerdict {
+    record("process".into(), process_name, process_kill_verdict(process_name))
+}
+
+/// Verdict for killing a process without recording it
+pub fn process_kill_verdict(process_name: &str) -> SafetyVerdict {
+    crate::grants::apply(crate::grants::GrantKind::KillProcess, Some(process_name), process_kill_rules(process_name))
+}
+
+fn process_kill_rules(process_name: &str) -> SafetyVerdict {
+    if is_protected_process(process_name) {
+        return SafetyVerdict {
+            allowed: false,
+            risk: RiskLevel::Blocked,
+            reason: format!("BLOCKED: '{}' is a critical system process and cannot be terminated", process_name),
+            requires_confirmation: false,
+        };
+    }
+
+    SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::High,
+        reason: format!("Killing process '{}' requires confirmation", process_name),
+        requires_confirmation: true,
+    }
+}
+
+/// `key` (with a short hive name) lowercased, with numbered control sets
+/// read as `currentcontrolset` and the `wow6432node` level dropped
+fn registry_canonical(key: &str) -> String {
+    let parts: Vec<String> = key
+        .to_lowercase()
+        .split('\\')
+        .filter(|part| *part != "wow6432node")
+        .enumerate()
+        .map(|(i, part)| {
+            let numbered = part.strip_prefix("controlset").is_some_and(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()));
+            if i == 2 && numbered { "currentcontrolset".to_string() } else { part.to_string() }
+        })
+        .collect();
+    parts.join("\\")
+}
+
+/// Check a registry read
+pub fn check_registry_read(key: &str) -> SafetyVerdict {
+    record("registry".into(), key, registry_read_verdict(key))
+}
+
+/// Verdict for reading a registry key without recording it
+pub fn registry_read_verdict(key: &str) -> SafetyVerdict {
+    let blocked = |reason: String| SafetyVerdict {
+        allowed: false,
+        risk: RiskLevel::Blocked,
+        reason,
+        requires_confirmation: false,
+    };
+    let key = key.trim().trim_end_matches('\\');
+    if key.is_empty() || key.contains(['"', '*', '?', '\n', '\r']) || key.contains("\\\\") {
+        return blocked(format!("BLOCKED: '{}' is not a registry key", key));
+    }
+    let (hive, rest) = key.split_once('\\').unwrap_or((key, ""));
+    let Some((short, _)) = READABLE_HIVES
+        .iter()
+        .find(|(short, long)| hive.eq_ignore_ascii_case(short) || hive.eq_ignore_ascii_case(long))
+    else {
+        return blocked(format!("BLOCKED: Only HKCU, HKLM and HKCR can be read, not '{}'", hive));
+    };
+    let short_key = registry_canonical(&format!("{}\\{}", short, rest));
+    if UNREADABLE_REGISTRY.iter().any(|p| {
+        let p = p.to_lowercase();
+        short_key == p || short_key.starts_with(&format!("{}\\", p))
+    }) {
+        return blocked(format!("BLOCKED: '{}' holds account secrets and is never read", key));
+    }
+    SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::Safe,
+        reason: "Reading a registry key".into(),
+        requires_confirmation: false,
+    }
+}
+
+/// Check running a macOS Shortcuts automation
+pub fn check_shortcut(name: &str) -> SafetyVerdict {
+    record("shortcut".into(), name, shortcut_verdict(name))
+}
+
+/// Verdict for running the Shortcut `name` without recording it. The user
+/// built their Shortcuts themselves, so running one is like opening an app.
+pub fn shortcut_verdict(name: &str) -> SafetyVerdict {
+    let name = name.trim();
+    if name.is_empty() || name.len() > 256 || name.chars().any(char::is_control) {
+        return SafetyVerdict {
+            allowed: false,
+            risk: RiskLevel::Blocked,
+            reason: format!("BLOCKED: '{}' is not a Shortcut name", name),
+            requires_confirmation: false,
+        };
+    }
+    SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::Medium,
+        reason: format!("Running the Shortcut '{}'", name),
+        requires_confirmation: false,
+    }
+}
+
+/// Check an AppleScript before running it
+pub fn check_applescript(script: &str) -> SafetyVerdict {
+    record("applescript".into(), script, applescript_verdict(script))
+}
+
+/// `script` lowercased with its string joins (`"do shell" & " script"`) and
+/// line continuations (`¬`) removed and whitespace collapsed, so splitting
+/// a blocked phrase does not hide it
+fn applescript_normalized(script: &str) -> String {
+    let lower = script.to_lowercase().replace('¬', " ");
+    let joins = Regex::new(r#""\s*&\s*""#).expect("valid regex");
+    let joined = joins.replace_all(&lower, "");
+    joined.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Verdict for an AppleScript without recording it. AppleScript can drive
+/// any app, so it is always high-risk.
+pub fn applescript_verdict(script: &str) -> SafetyVerdict {
+    let blocked = |reason: String| SafetyVerdict {
+        allowed: false,
+        risk: RiskLevel::Blocked,
+        reason,
+        requires_confirmation: false,
+    };
+    if script.trim().is_empty() {
+        return blocked("BLOCKED: The AppleScript is empty".into());
+    }
+    let normalized = applescript_normalized(script);
+    if let Some(pattern) = BLOCKED_APPLESCRIPT.iter().find(|p| normalized.contains(*p)) {
+        return blocked(format!("BLOCKED: AppleScript contains '{}'", pattern));
+    }
+    if let Some(reason) = is_blocked_command(&normalized) {
+        return blocked(reason);
+    }
+    SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::High,
+        reason: "Running an AppleScript, which can control any app".into(),
+        requires_confirmation: true,
+    }
+}
+
+/// Check a desktop automation sub-action (`text` is the keys or text to send)
+pub fn check_desktop_action(action: &str, text: &str) -> SafetyVerdict {
+    record("desktop".into(), action, desktop_action_verdict(action, text))
+}
+
+/// Verdict for a desktop automation sub-action without recording it
+pub fn desktop_action_verdict(action: &str, text: &str) -> SafetyVerdict {
+    let verdict = |risk: RiskLevel, reason: &str, requires_confirmation: bool| SafetyVerdict {
+        allowed: risk != RiskLevel::Blocked,
+        risk,
+        reason: reason.to_string(),
+        requires_confirmation,
+    };
+    match action {
+        "list_windows" | "list_monitors" | "select_region" | "screenshot" | "read_screen" | "read_window_text"
+        | "wait" => {
+            verdict(RiskLevel::Safe, "Read-only desktop action", false)
+        }
+        "focus_window" | "click" => verdict(RiskLevel::Low, "Desktop input", false),
+        // Typed text may land in a terminal, so it gets the shell blocklist
+        "send_keys" | "key_combo" | "type_text" | "open_app" => match is_blocked_command(text) {
+            Some(reason) => verdict(RiskLevel::Blocked, &reason, false),
+            None => verdict(RiskLevel::Medium, "Desktop input", false),
+        },
+        // The clipboard often holds passwords and tokens
+        "get_clipboard" => verdict(RiskLevel::Medium, "Reading the clipboard requires confirmation", true),
+        _ => verdict(RiskLevel::Blocked, &format!("BLOCKED: Unknown desktop action '{}'", action), false),
+    }
+}
+
+/// Check a user script action against the risk its script declares
+pub fn check_script(name: &str, declared: &RiskLevel) -> SafetyVerdict {
+    record("script".into(), name, script_verdict(name, declared))
+}
+
+/// Verdict for a user script action without recording it
+pub fn script_verdict(name: &str, declared: &RiskLevel) -> SafetyVerdict {
+    match declared {
+        RiskLevel::Blocked => SafetyVerdict {
+            allowed: false,
+            risk: RiskLevel::Blocked,
+            reason: format!("BLOCKED: Script '{}' declares itself blocked", name),
+            requires_confirmation: false,
+        },
+        RiskLevel::High => SafetyVerdict {
+            allowed: true,
+            risk: RiskLevel::High,
+            reason: format!("Script '{}' is high-risk and requires confirmation", name),
+            requires_confirmation: true,
+        },
+        risk => SafetyVerdict {
+            allowed: true,
+            risk: risk.clone(),
+            reason: format!("User script '{}'", name),
+            requires_confirmation: false,
+        },
+    }
+}
+
+/// Words in an MQTT topic that mark a device better not switched unasked
+const SENSITIVE_MQTT_WORDS: &[&str] = &[
+    "lock", "alarm", "garage", "door", "gate", "oven", "stove", "heater", "valve", "siren",
+];
+
+/// Whether `topic` falls under the MQTT topic filter `filter` (`+` is one
+/// level, a trailing `#` any number). A `#` level in `topic` itself is only
+/// covered by `#`, so a subscription is never broader than its filter.
+pub fn mqtt_topic_matches(filter: &str, topic: &str) -> bool {
+    let mut topic_levels = topic.split('/');
+    for level in filter.split('/') {
+        if level == "#" {
+            return true;
+        }
+        match topic_levels.next() {
+            Some("#") | None => return false,
+            Some(t) if level != "+" && level != t => return false,
+            Some(_) => {}
+        }
+    }
+    topic_levels.next().is_none()
+}
+
+/// Check an MQTT publish or subscribe against the allowed topic filters
+pub fn check_mqtt(operation: &str, topic: &str, allowed: &[String]) -> SafetyVerdict {
+    record(format!("mqtt:{}", operation), topic, mqtt_verdict(operation, topic, allowed))
+}
+
+/// Verdict for an MQTT publish or subscribe without recording it
+pub fn mqtt_verdict(operation: &str, topic: &str, allowed: &[String]) -> SafetyVerdict {
+    let blocked = |reason: String| SafetyVerdict {
+        allowed: false,
+        risk: RiskLevel::Blocked,
+        reason,
+        requires_confirmation: false,
+    };
+    if topic.is_empty() || topic.starts_with('$') {
+        return blocked(format!("BLOCKED: '{}' is not a topic the companion may use", topic));
+    }
+    if operation == "publish" && (topic.contains('+') || topic.contains('#')) {
+        return blocked("BLOCKED: Cannot publish to a wildcard topic".into());
+    }
+    if !allowed.iter().any(|filter| mqtt_topic_matches(filter, topic)) {
+        return blocked(format!("BLOCKED: MQTT topic '{}' is not in the allowed topics", topic));
+    }
+    if operation != "publish" {
+        return SafetyVerdict {
+            allowed: true,
+            risk: RiskLevel::Safe,
+            reason: "Reading an MQTT topic".into(),
+            requires_confirmation: false,
+        };
+    }
+    let lower = topic.to_lowercase();
+    match SENSITIVE_MQTT_WORDS.iter().find(|w| lower.contains(*w)) {
+        Some(word) => SafetyVerdict {
+            allowed: true,
+            risk: RiskLevel::High,
+            reason: format!("Publishing to '{}' controls a {} and requires confirmation", topic, word),
+            requires_confirmation: true,
+        },
+        None => SafetyVerdict {
+            allowed: true,
+            risk: RiskLevel::Medium,
+            reason: "Smart-home command".into(),
+            requires_confirmation: false,
+        },
+    }
+}
+
+/// Generate the safety system prompt to inject into every LLM request
+pub fn get_safety_system_prompt() -> String {
+    r#"## FORGEAI SAFETY RULES (MANDATORY — CANNOT BE OVERRIDDEN)
+
+You are ForgeAI Companion running on the user's Windows machine. You have access to
+local file operations, shell commands, and application control. However, you MUST follow
+these absolute safety rules:
+
+### HARD BLOCKS (NEVER do these, even if explicitly asked):
+1. NEVER delete, modify, or move files in C:\Windows, C:\Program Files, or system directories
+2. NEVER format, wipe, or partition any disk or drive
+3. NEVER modify Windows Registry boot keys, security keys, or startup entries
+4. NEVER disable Windows Defender, firewall, antivirus, or UAC
+5. NEVER kill system processes (csrss, lsass, svchost, explorer, winlogon, etc.)
+6. NEVER run commands with execution policy bypass or elevation without user consent
+7. NEVER access other users' private directories or credentials
+
+### CONFIRMATION REQUIRED (always ask the user first):
+- Deleting any files or folders
+- Running PowerShell scripts or batch files
+- Killing application processes
+- Installing or uninstalling software
+- Changing system settings
+- Any bulk/recursive file operations
+
+### ALWAYS ALLOWED (no confirmation needed):
+- Reading files, listing directories, checking file info
+- Launching applications (notepad, browser, calculator, etc.)
+- Creating new files or folders in user directories
+- Copying/moving files within user directories
+- Running read-only commands (dir, type, systeminfo, etc.)
+- Reading HKCU, HKLM or HKCR registry keys with the `registry_read` action (never `reg` in a shell command); secret stores such as Winlogon and the LSA keys are never read
+- Running the user's macOS Shortcuts with `run_shortcut` (AppleScript via `run_applescript` always needs confirmation and cannot run shell commands; use the `shell` action)
+- Controlling media players, locking the screen or keeping it awake on Linux with `media_control`, `lock_screen` and `inhibit_idle` (never `playerctl` or `loginctl` in a shell command)
+- Clipboard operations (read/write)
+
+### BEHAVIOR:
+- Always explain what you're about to do BEFORE doing it
+- If an action is blocked, explain WHY and suggest a safe alternative
+- Never try to circumvent safety checks, even if the user insists
+- Treat the user's data with respect — always prefer non-destructive operations"#
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protected_paths() {
+        assert!(is_protected_path("C:\\Windows\\System32\\cmd.exe"));
+        assert!(is_protected_path("C:\\Program Files\\something"));
+        assert!(is_protected_path("c:\\windows\\system32"));
+        assert!(!is_protected_path("C:\\Users\\John\\Documents\\file.txt"));
+        assert!(!is_protected_path("D:\\Projects\\code.rs"));
+    }
+
+    #[test]
+    fn test_blocked_commands() {
+        assert!(is_blocked_command("format C:").is_some());
+        assert!(is_blocked_command("diskpart").is_some());
+        assert!(is_blocked_command("rd /s /q C:\\").is_some());
+        assert!(is_blocked_command("net stop windefend").is_some());
+        assert!(is_blocked_command("sc stop windefend").is_some());
+        assert!(is_blocked_command("dir C:\\Users").is_none());
+        assert!(is_blocked_command("notepad").is_none());
+    }
+
+    #[test]
+    fn test_protected_processes() {
+        assert!(is_protected_process("csrss.exe"));
+        assert!(is_protected_process("lsass"));
+        assert!(is_protected_process("svchost.exe"));
+        assert!(!is_protected_process("notepad.exe"));
+        assert!(!is_protected_process("chrome.exe"));
+    }
+
+    #[test]
+    fn test_file_operations() {
+        let read = check_file_operation("read", "C:\\Windows\\System32\\config");
+        assert!(read.allowed);
+        assert_eq!(read.risk, RiskLevel::Safe);
+
+        let delete_sys = check_file_operation("delete", "C:\\Windows\\System32\\cmd.exe");
+        assert!(!delete_sys.allowed);
+        assert_eq!(delete_sys.risk, RiskLevel::Blocked);
+
+        let write_sys = check_file_operation("write", "C:\\Windows\\test.txt");
+        assert!(!write_sys.allowed);
+        assert_eq!(write_sys.risk, RiskLevel::Blocked);
+    }
+
+    #[test]
+    fn test_shell_commands() {
+        let safe = check_shell_command("dir C:\\Users", None);
+        assert!(safe.allowed);
+        assert_eq!(safe.risk, RiskLevel::Safe);
+
+        let blocked = check_shell_command("format C:", None);
+        assert!(!blocked.allowed);
+        assert_eq!(blocked.risk, RiskLevel::Blocked);
+
+        let high = check_shell_command("del somefile.txt", None);
+        assert!(high.allowed);
+        assert!(high.requires_confirmation);
+    }
+
+    #[test]
+    fn test_desktop_actions() {
+        let read = check_desktop_action("screenshot", "");
+        assert!(read.allowed && !read.requires_confirmation);
+
+        let typed = check_desktop_action("type_text", "hello");
+        assert!(typed.allowed && !typed.requires_confirmation);
+
+        let typed_wipe = check_desktop_action("type_text", "format C:");
+        assert!(!typed_wipe.allowed);
+        assert_eq!(typed_wipe.risk, RiskLevel::Blocked);
+
+        assert!(check_desktop_action("get_clipboard", "").requires_confirmation);
+        assert!(!check_desktop_action("run_anything", "").allowed);
+    }
+
+    #[test]
+    fn test_mqtt_topics() {
+        let allowed = vec!["home/+/light/#".to_string(), "home/garage/door".to_string()];
+        assert!(mqtt_topic_matches("home/#", "home/office/light/set"));
+        assert!(!mqtt_topic_matches("home/+", "home/office/light"));
+        assert!(!mqtt_topic_matches("home/+/light", "home/#"));
+
+        let light = check_mqtt("publish", "home/office/light/set", &allowed);
+        assert!(light.allowed && !light.requires_confirmation);
+        assert!(check_mqtt("publish", "home/garage/door", &allowed).requires_confirmation);
+        assert!(!check_mqtt("publish", "home/office/heater/set", &allowed).allowed);
+        assert!(!check_mqtt("publish", "home/+/light/set", &allowed).allowed);
+        assert!(!check_mqtt("subscribe", "$SYS/broker/clients", &allowed).allowed);
+        assert!(check_mqtt("subscribe", "home/+/light/state", &allowed).allowed);
+        assert!(!check_mqtt("subscribe", "home/#", &allowed).allowed);
+    }
+
+    #[test]
+    fn test_registry_reads() {
+        assert!(check_registry_read("HKCU\\Software\\Microsoft\\Office").allowed);
+        assert!(check_registry_read("HKEY_LOCAL_MACHINE\\SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion").allowed);
+        assert!(check_registry_read("hklm").allowed);
+        assert!(!check_registry_read("HKU\\S-1-5-21-1000\\Software").allowed);
+        assert!(!check_registry_read("HKLM\\SAM\\SAM\\Domains").allowed);
+        assert!(!check_registry_read("HKEY_LOCAL_MACHINE\\security").allowed);
+        assert!(check_registry_read("HKLM\\SAMPLE").allowed);
+        assert!(!check_registry_read("HKCU\\Software\" & calc").allowed);
+        assert!(!check_registry_read("HKLM\\SOFTWARE\\Microsoft\\Windows NT\\CurrentVersion\\Winlogon").allowed);
+        assert!(!check_registry_read("HKLM\\SOFTWARE\\WOW6432Node\\Microsoft\\Windows NT\\CurrentVersion\\Winlogon\\").allowed);
+        assert!(!check_registry_read("HKLM\\SYSTEM\\ControlSet001\\Control\\Lsa\\JD").allowed);
+        assert!(!check_registry_read("HKEY_LOCAL_MACHINE\\SYSTEM\\CurrentControlSet\\Control\\LSA").allowed);
+        assert!(!check_registry_read("HKLM\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\Authentication\\Credential Providers").allowed);
+        assert!(check_registry_read("HKLM\\SYSTEM\\CurrentControlSet\\Control\\Lsass").allowed);
+    }
+
+    #[test]
+    fn test_shortcuts_and_applescript() {
+        assert!(check_shortcut("Morning Routine").allowed);
+        assert!(!check_shortcut("  ").allowed);
+        assert!(!check_shortcut("Lights\nOff").allowed);
+
+        let verdict = check_applescript("tell application \"Music\" to play");
+        assert!(verdict.allowed && verdict.requires_confirmation);
+        assert!(!check_applescript("do shell script \"rm -rf ~/Documents\"").allowed);
+        assert!(!check_applescript("do shell script \"ls\" with administrator privileges").allowed);
+        assert!(!check_applescript("do shell script \"diskutil eraseDisk APFS X disk2\"").allowed);
+        assert!(!check_applescript("do shell script \"ls ~\"").allowed);
+        assert!(!check_applescript("run script (\"do shell\" & \" script \\\"ls\\\"\")").allowed);
+        assert!(!check_applescript("do  shell ¬\n  script \"ls\"").allowed);
+        assert!(!check_applescript("tell application \"Terminal\" to do script \"ls\"").allowed);
+        assert_eq!(applescript_normalized("\"do shell\" & \" script\""), "\"do shell script\"");
+    }
+}
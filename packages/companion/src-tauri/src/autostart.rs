@@ -0,0 +1,216 @@
+//! # Autostart
+//!
+//! Registers the companion as a login item so the tray icon, hotkeys and
+//! wake word are available right after boot:
+//!
+//! - **Windows** — `HKCU\Software\Microsoft\Windows\CurrentVersion\Run`
+//! - **macOS** — `~/Library/LaunchAgents/ai.forge.companion.plist`
+//! - **Linux** — `~/.config/autostart/forgeai-companion.desktop` (XDG)
+//!
+//! The registered command line carries `--minimized` when the companion
+//! should start hidden in the tray instead of opening its window.
+
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// Command-line flag that starts the companion hidden in the tray
+pub const MINIMIZED_FLAG: &str = "--minimized";
+
+#[cfg(target_os = "windows")]
+const RUN_KEY: &str = "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\Run";
+#[cfg(target_os = "windows")]
+const RUN_VALUE: &str = "ForgeAI Companion";
+#[cfg(target_os = "macos")]
+const LAUNCH_AGENT_LABEL: &str = "ai.forge.companion";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutostartStatus {
+    pub enabled: bool,
+    /// Starts hidden in the tray
+    pub minimized: bool,
+}
+
+/// Whether this process was started with `--minimized`
+pub fn launched_minimized() -> bool {
+    std::env::args().any(|a| a == MINIMIZED_FLAG)
+}
+
+fn current_exe() -> Result<PathBuf, String> {
+    std::env::current_exe().map_err(|e| format!("Cannot locate executable: {}", e))
+}
+
+#[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+fn desktop_entry(exe: &str, minimized: bool) -> String {
+    let mut exec = format!("\"{}\"", exe.replace('\\', "\\\\").replace('"', "\\\""));
+    if minimized {
+        exec.push(' ');
+        exec.push_str(MINIMIZED_FLAG);
+    }
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=ForgeAI Companion\n\
+         Exec={}\n\
+         Terminal=false\n\
+         X-GNOME-Autostart-enabled=true\n",
+        exec
+    )
+}
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn launch_agent(label: &str, exe: &str, minimized: bool) -> String {
+    let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;");
+    let mut args = format!("        <string>{}</string>\n", escape(exe));
+    if minimized {
+        args.push_str(&format!("        <string>{}</string>\n", MINIMIZED_FLAG));
+    }
+    format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+         <plist version=\"1.0\">\n\
+         <dict>\n\
+         \x20   <key>Label</key>\n\
+         \x20   <string>{}</string>\n\
+         \x20   <key>ProgramArguments</key>\n\
+         \x20   <array>\n\
+         {}\
+         \x20   </array>\n\
+         \x20   <key>RunAtLoad</key>\n\
+         \x20   <true/>\n\
+         </dict>\n\
+         </plist>\n",
+        label, args
+    )
+}
+
+/// Path of the login-item file (macOS / Linux)
+#[cfg(any(target_os = "macos", target_os = "linux"))]
+fn entry_path() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    return dirs::home_dir().map(|h| {
+        h.join("Library/LaunchAgents")
+            .join(format!("{}.plist", LAUNCH_AGENT_LABEL))
+    });
+    #[cfg(target_os = "linux")]
+    return dirs::config_dir().map(|c| c.join("autostart").join("forgeai-companion.desktop"));
+}
+
+/// Registered command line, if the companion is a login item
+fn registered() -> Option<String> {
+    #[cfg(target_os = "windows")]
+    {
+        let out = std::process::Command::new("reg")
+            .args(["query", RUN_KEY, "/v", RUN_VALUE])
+            .output()
+            .ok()?;
+        if !out.status.success() {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&out.stdout).to_string();
+        text.lines()
+            .find(|l| l.contains("REG_SZ"))
+            .and_then(|l| l.split("REG_SZ").nth(1))
+            .map(|v| v.trim().to_string())
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        std::fs::read_to_string(entry_path()?).ok()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    None
+}
+
+/// Current login-item registration
+pub fn status() -> AutostartStatus {
+    match registered() {
+        Some(entry) => AutostartStatus { enabled: true, minimized: entry.contains(MINIMIZED_FLAG) },
+        None => AutostartStatus { enabled: false, minimized: false },
+    }
+}
+
+/// Register the companion to start at login
+pub fn enable(minimized: bool) -> Result<(), String> {
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    return Err("Autostart is not supported on this platform".into());
+
+    let exe = current_exe()?;
+    let exe = exe.to_string_lossy();
+
+    #[cfg(target_os = "windows")]
+    {
+        let mut command = format!("\"{}\"", exe);
+        if minimized {
+            command.push(' ');
+            command.push_str(MINIMIZED_FLAG);
+        }
+        let out = std::process::Command::new("reg")
+            .args(["add", RUN_KEY, "/v", RUN_VALUE, "/t", "REG_SZ", "/d", &command, "/f"])
+            .output()
+            .map_err(|e| format!("Autostart error: {}", e))?;
+        if !out.status.success() {
+            return Err(format!("Autostart error: {}", String::from_utf8_lossy(&out.stderr).trim()));
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    {
+        let path = entry_path().ok_or("Cannot determine autostart directory")?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Autostart error: {}", e))?;
+        }
+        #[cfg(target_os = "macos")]
+        let contents = launch_agent(LAUNCH_AGENT_LABEL, &exe, minimized);
+        #[cfg(target_os = "linux")]
+        let contents = desktop_entry(&exe, minimized);
+        std::fs::write(&path, contents).map_err(|e| format!("Autostart error: {}", e))?;
+    }
+
+    tracing::info!("[Autostart] Enabled for {}{}", exe, if minimized { " (minimized)" } else { "" });
+    Ok(())
+}
+
+/// Remove the login item (no-op if not registered)
+pub fn disable() -> Result<(), String> {
+    if registered().is_none() {
+        return Ok(());
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let out = std::process::Command::new("reg")
+            .args(["delete", RUN_KEY, "/v", RUN_VALUE, "/f"])
+            .output()
+            .map_err(|e| format!("Autostart error: {}", e))?;
+        if !out.status.success() {
+            return Err(format!("Autostart error: {}", String::from_utf8_lossy(&out.stderr).trim()));
+        }
+    }
+
+    #[cfg(any(target_os = "macos", target_os = "linux"))]
+    if let Some(path) = entry_path() {
+        std::fs::remove_file(path).map_err(|e| format!("Autostart error: {}", e))?;
+    }
+
+    tracing::info!("[Autostart] Disabled");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_login_item_contents() {
+        let entry = desktop_entry("/opt/ForgeAI Companion/forgeai", true);
+        assert!(entry.contains("Exec=\"/opt/ForgeAI Companion/forgeai\" --minimized\n"));
+        assert!(!desktop_entry("/usr/bin/forgeai", false).contains(MINIMIZED_FLAG));
+
+        let plist = launch_agent("ai.forge.companion", "/Applications/A&B.app/forgeai", true);
+        assert!(plist.contains("<string>/Applications/A&amp;B.app/forgeai</string>"));
+        assert!(plist.contains("<string>--minimized</string>"));
+        assert!(plist.contains("<key>RunAtLoad</key>"));
+    }
+}
@@ -0,0 +1,204 @@
+//! # Capability Negotiation
+//!
+//! Right after the live channel connects, the companion sends a
+//! `capabilities` message with its manifest; the Gateway answers with its
+//! own. Feature code checks the Gateway's manifest up front (e.g. whether
+//! it accepts compressed uploads or pushes actions) instead of discovering
+//! a missing feature through a failed request.
+//!
+//! Until a manifest arrives (older Gateways never send one), [`gateway`]
+//! returns None and callers keep their legacy behaviour; [`gateway_supports`]
+//! takes that legacy answer explicitly. Features branched on the manifest:
+//! result paging, push event subscription, outbox acks, E2E pairing checks,
+//! duplex audio, compression codecs, accepted audio codecs and the speech
+//! providers a user can pick.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Version of the companion ⇄ Gateway message protocol
+pub const PROTOCOL_VERSION: u32 = 1;
+
+static GATEWAY: Mutex<Option<Manifest>> = Mutex::new(None);
+
+/// Named optional features
+pub mod feature {
+    pub const ACTION_PUSH: &str = "action_push";
+    pub const RESULT_PAGES: &str = "result_pages";
+    pub const E2E: &str = "e2e";
+    pub const JOB_CONTROL: &str = "job_control";
+    pub const EVENT_PUSH: &str = "event_push";
+    pub const OUTBOX_ACK: &str = "outbox_ack";
+    pub const DUPLEX_AUDIO: &str = "duplex_audio";
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Manifest {
+    pub protocol_version: u32,
+    pub actions: Vec<String>,
+    pub audio_codecs: Vec<String>,
+    pub compression: Vec<String>,
+    pub features: Vec<String>,
+    /// Speech providers a request may ask for (Gateway manifests only)
+    pub stt_providers: Vec<String>,
+    pub tts_providers: Vec<String>,
+    /// User script actions with their descriptions (companion manifests only)
+    pub scripts: Vec<crate::scripts::ScriptInfo>,
+}
+
+impl Manifest {
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+}
+
+/// What this companion build can do
+pub fn local() -> Manifest {
+    let mut actions: Vec<String> = crate::local_actions::SUPPORTED_ACTIONS
+        .iter()
+        .map(|a| a.to_string())
+        .collect();
+    actions.extend(
+        crate::local_actions::DESKTOP_ACTIONS
+            .iter()
+            .map(|a| format!("desktop.{}", a)),
+    );
+    if crate::embeddings::available() {
+        actions.push("semantic_search".into());
+    }
+    if crate::mqtt::enabled() {
+        actions.extend(["mqtt_publish".to_string(), "mqtt_subscribe".to_string()]);
+    }
+    if crate::clipboard_history::enabled() {
+        actions.extend(["clipboard_history_list".to_string(), "clipboard_history_paste".to_string()]);
+    }
+    let scripts = crate::scripts::list();
+    actions.extend(scripts.iter().map(|s| s.action.clone()));
+
+    Manifest {
+        protocol_version: PROTOCOL_VERSION,
+        actions,
+        audio_codecs: vec!["wav".into()],
+        compression: vec!["zstd".into(), "gzip".into()],
+        features: [
+            feature::ACTION_PUSH,
+            feature::RESULT_PAGES,
+            feature::E2E,
+            feature::JOB_CONTROL,
+            feature::EVENT_PUSH,
+            feature::OUTBOX_ACK,
+            feature::DUPLEX_AUDIO,
+        ]
+        .iter()
+        .map(|f| f.to_string())
+        .collect(),
+        scripts,
+        ..Default::default()
+    }
+}
+
+/// The `capabilities` message sent when the live channel connects
+pub fn hello_message() -> serde_json::Value {
+    serde_json::json!({
+        "type": "capabilities",
+        "manifest": local(),
+    })
+}
+
+fn parse(raw: &serde_json::Value) -> Result<Manifest, String> {
+    serde_json::from_value(raw["manifest"].clone()).map_err(|e| format!("Invalid Gateway manifest: {}", e))
+}
+
+/// Store the manifest from a Gateway `capabilities` message
+pub fn handle_gateway_message(raw: &serde_json::Value) {
+    let manifest = match parse(raw) {
+        Ok(m) => m,
+        Err(e) => {
+            tracing::warn!("[Capabilities] {}", e);
+            return;
+        }
+    };
+    if manifest.protocol_version != PROTOCOL_VERSION {
+        tracing::warn!(
+            "[Capabilities] Protocol mismatch: companion v{}, Gateway v{}",
+            PROTOCOL_VERSION,
+            manifest.protocol_version
+        );
+    }
+    tracing::info!(
+        "[Capabilities] Gateway v{} features: {}",
+        manifest.protocol_version,
+        manifest.features.join(", ")
+    );
+    let paired_with_e2e = crate::connection::GatewayConnection::load_credentials()
+        .is_some_and(|c| c.e2e_key.is_some());
+    if manifest.supports(feature::E2E) && !paired_with_e2e {
+        tracing::warn!("[Capabilities] Gateway supports end-to-end encryption — pair again to enable it");
+    }
+    crate::events::emit("gateway-capabilities", &manifest);
+    let push = manifest.supports(feature::EVENT_PUSH);
+    if let Ok(mut g) = GATEWAY.lock() {
+        *g = Some(manifest);
+    }
+    // Now that acks are known to be (un)supported
+    crate::outbox::flush();
+    if push {
+        if let Err(e) = crate::connection::send_live(crate::push::subscribe_message().to_string()) {
+            tracing::warn!("[Capabilities] Cannot subscribe to push events: {}", e);
+        }
+    }
+}
+
+/// Forget the Gateway manifest (on disconnect)
+pub fn clear() {
+    if let Ok(mut g) = GATEWAY.lock() {
+        *g = None;
+    }
+}
+
+/// The negotiated Gateway manifest, if the Gateway sent one
+pub fn gateway() -> Option<Manifest> {
+    GATEWAY.lock().ok().and_then(|g| g.clone())
+}
+
+/// Whether the Gateway supports `feature` according to its manifest, or
+/// `legacy` if it sent none
+pub fn gateway_supports(feature: &str, legacy: bool) -> bool {
+    gateway().map_or(legacy, |m| m.supports(feature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest_negotiation() {
+        let raw = serde_json::json!({
+            "type": "capabilities",
+            "manifest": { "protocolVersion": 1, "features": ["result_pages", "outbox_ack"], "compression": ["zstd"] },
+        });
+        let manifest = parse(&raw).unwrap();
+        assert!(manifest.supports(feature::RESULT_PAGES));
+        assert!(!manifest.supports(feature::EVENT_PUSH));
+        // Missing lists default to empty
+        assert!(manifest.actions.is_empty() && manifest.audio_codecs.is_empty());
+        assert!(parse(&serde_json::json!({ "manifest": { "features": "e2e" } })).is_err());
+
+        // Our own manifest advertises every feature and round-trips
+        let local = local();
+        assert!(local.supports(feature::E2E) && local.supports(feature::OUTBOX_ACK));
+        let hello = hello_message();
+        assert_eq!(hello["type"], "capabilities");
+        assert_eq!(parse(&hello).unwrap().features, local.features);
+
+        // Legacy answer until a manifest arrives, then the manifest decides
+        clear();
+        assert!(gateway_supports(feature::RESULT_PAGES, true));
+        assert!(!gateway_supports(feature::OUTBOX_ACK, false));
+        *GATEWAY.lock().unwrap() = Some(manifest);
+        assert!(gateway_supports(feature::OUTBOX_ACK, false));
+        assert!(!gateway_supports(feature::EVENT_PUSH, true));
+        clear();
+    }
+}
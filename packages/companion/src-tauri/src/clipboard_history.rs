@@ -0,0 +1,263 @@
+//! # Clipboard History
+//!
+//! An opt-in record of what the user copied (`clipboardHistory.enabled`),
+//! so "paste the thing I copied before this one" has something to go by.
+//! The clipboard is polled for text while enabled; the last `maxEntries`
+//! copies are kept newest first, and copying something already in the
+//! history moves it to the top instead of adding it twice.
+//!
+//! The history is stored encrypted in the user profile and cleared when
+//! the feature is turned off. Copies made while a password manager (any
+//! app in `ignoreApps`) is focused are never recorded.
+//!
+//! The Gateway reaches it through two actions: `clipboard_history_list`,
+//! which needs confirmation like reading the clipboard does, and
+//! `clipboard_history_paste` with an `index` (0 is the newest), which puts
+//! that entry on the clipboard and, on Windows, pastes it into the focused
+//! application.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Characters of each entry shown by `clipboard_history_list`
+const PREVIEW_CHARS: usize = 200;
+
+static CONFIG: Mutex<Option<ClipboardHistoryConfig>> = Mutex::new(None);
+/// Newest first; None until loaded from disk
+static HISTORY: Mutex<Option<Vec<ClipboardEntry>>> = Mutex::new(None);
+/// Clipboard text at the last check
+static LAST_SEEN: Mutex<Option<String>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ClipboardHistoryConfig {
+    pub enabled: bool,
+    pub max_entries: usize,
+    /// Longer copies are not recorded
+    pub max_entry_chars: usize,
+    /// Apps (by part of their name) whose copies are never recorded
+    pub ignore_apps: Vec<String>,
+}
+
+impl Default for ClipboardHistoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 25,
+            max_entry_chars: 20_000,
+            ignore_apps: ["1password", "bitwarden", "keepass", "lastpass", "dashlane", "enpass"]
+                .iter()
+                .map(|a| a.to_string())
+                .collect(),
+        }
+    }
+}
+
+impl ClipboardHistoryConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=200).contains(&self.max_entries) {
+            return Err("Clipboard history must keep between 1 and 200 entries".into());
+        }
+        if self.max_entry_chars == 0 {
+            return Err("Clipboard entries need a maximum length".into());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ClipboardEntry {
+    pub text: String,
+    pub copied_at: String,
+    /// App focused when it was copied, when known
+    #[serde(default)]
+    pub app: Option<String>,
+}
+
+/// An entry as listed to the Gateway
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EntryPreview {
+    pub index: usize,
+    pub preview: String,
+    pub chars: usize,
+    pub copied_at: String,
+    pub app: Option<String>,
+}
+
+fn config() -> ClipboardHistoryConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+/// Use `config` from now on; turning the history off deletes it
+pub fn set_config(config: &ClipboardHistoryConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+    if !config.enabled {
+        clear();
+    } else {
+        with_history(|history| history.truncate(config.max_entries));
+    }
+}
+
+/// Whether the history actions are offered
+pub fn enabled() -> bool {
+    config().enabled
+}
+
+// ─── Storage ────────────────────────────────────────
+
+fn history_path() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("clipboard_history.enc"))
+}
+
+fn load() -> Vec<ClipboardEntry> {
+    let Some(path) = history_path().filter(|p| p.exists()) else { return Vec::new() };
+    match crate::secure_store::read_encrypted(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!("[Clipboard] History unreadable, starting over: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+fn save(history: &[ClipboardEntry]) {
+    let Some(path) = history_path() else { return };
+    let result = serde_json::to_vec(history)
+        .map_err(|e| e.to_string())
+        .and_then(|json| crate::secure_store::write_encrypted(&path, &json));
+    if let Err(e) = result {
+        tracing::warn!("[Clipboard] Cannot save history: {}", e);
+    }
+}
+
+/// Run `change` on the history and save it when it changed
+fn with_history<T>(change: impl FnOnce(&mut Vec<ClipboardEntry>) -> T) -> Option<T> {
+    let mut guard = HISTORY.lock().ok()?;
+    let history = guard.get_or_insert_with(load);
+    let before = history.clone();
+    let result = change(history);
+    if *history != before {
+        save(history);
+    }
+    Some(result)
+}
+
+/// Put `text` at the top of `history`, moving it there if already present
+fn record_in(history: &mut Vec<ClipboardEntry>, text: &str, app: Option<String>, max_entries: usize) {
+    history.retain(|e| e.text != text);
+    history.insert(0, ClipboardEntry { text: text.to_string(), copied_at: chrono::Local::now().to_rfc3339(), app });
+    history.truncate(max_entries);
+}
+
+fn preview(index: usize, entry: &ClipboardEntry) -> EntryPreview {
+    let flat = entry.text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut preview: String = flat.chars().take(PREVIEW_CHARS).collect();
+    if flat.chars().count() > PREVIEW_CHARS {
+        preview.push('…');
+    }
+    EntryPreview { index, preview, chars: entry.text.chars().count(), copied_at: entry.copied_at.clone(), app: entry.app.clone() }
+}
+
+fn read_clipboard() -> Option<String> {
+    crate::events::app_handle()?.clipboard().read_text().ok()
+}
+
+/// Record the clipboard's text if it changed since the last check
+fn check() {
+    let Some(text) = read_clipboard() else { return };
+    {
+        let Ok(mut last) = LAST_SEEN.lock() else { return };
+        if last.as_deref() == Some(text.as_str()) {
+            return;
+        }
+        *last = Some(text.clone());
+    }
+    let config = config();
+    if text.trim().is_empty() || text.chars().count() > config.max_entry_chars {
+        return;
+    }
+    let app = crate::screen_context::capture(false).ok().map(|c| c.app);
+    if let Some(app) = &app {
+        let lower = app.to_lowercase();
+        if config.ignore_apps.iter().any(|a| !a.is_empty() && lower.contains(&a.to_lowercase())) {
+            return;
+        }
+    }
+    with_history(|history| record_in(history, &text, app, config.max_entries));
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// The history, newest first, with each entry shortened
+pub fn list() -> Vec<EntryPreview> {
+    with_history(|history| history.iter().enumerate().map(|(i, e)| preview(i, e)).collect()).unwrap_or_default()
+}
+
+/// Full text of entry `index` (0 is the newest)
+pub fn entry(index: usize) -> Result<String, String> {
+    with_history(|history| history.get(index).map(|e| e.text.clone()))
+        .flatten()
+        .ok_or_else(|| format!("No clipboard history entry {}", index))
+}
+
+/// Put `text` on the clipboard
+pub fn copy(text: &str) -> Result<(), String> {
+    let app = crate::events::app_handle().ok_or("The clipboard is not available in this mode")?;
+    app.clipboard().write_text(text.to_string()).map_err(|e| format!("Cannot write the clipboard: {}", e))
+}
+
+/// Forget every entry
+pub fn clear() {
+    if let Ok(mut history) = HISTORY.lock() {
+        *history = Some(Vec::new());
+    }
+    if let Some(path) = history_path().filter(|p| p.exists()) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Watch the clipboard while the history is enabled
+pub fn spawn_monitor() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            if enabled() {
+                let _ = tauri::async_runtime::spawn_blocking(check).await;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_history_order_and_preview() {
+        let mut history = Vec::new();
+        for text in ["first", "second", "third", "second"] {
+            record_in(&mut history, text, None, 3);
+        }
+        let texts: Vec<_> = history.iter().map(|e| e.text.as_str()).collect();
+        assert_eq!(texts, ["second", "third", "first"]);
+        record_in(&mut history, "fourth", Some("Code".into()), 3);
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.last().unwrap().text, "third");
+
+        let long = ClipboardEntry { text: format!("line one\n\n{}", "x".repeat(300)), copied_at: String::new(), app: None };
+        let shown = preview(4, &long);
+        assert!(shown.preview.starts_with("line one xxx") && shown.preview.ends_with('…'));
+        assert_eq!((shown.index, shown.chars), (4, 310));
+
+        assert!(ClipboardHistoryConfig::default().validate().is_ok());
+        assert!(ClipboardHistoryConfig { max_entries: 0, ..Default::default() }.validate().is_err());
+    }
+}
@@ -4,9 +4,13 @@
 //! Every command that performs a local action goes through the safety system.
 
 use base64::Engine as _;
+use crate::i18n::{CompanionError, UserError};
+use crate::jobs;
 use crate::local_actions::{self, ActionRequest, ActionResult};
+use crate::pagination;
+use crate::remote_actions;
 use crate::safety;
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::OnceLock;
 use tokio::sync::Notify;
@@ -18,26 +22,6 @@ fn get_reconnect_notify() -> &'static Notify {
     RECONNECT_NOTIFY.get_or_init(|| Notify::new())
 }
 
-/// Build a reqwest::RequestBuilder with auth cookie if available
-fn with_auth(builder: reqwest::RequestBuilder, creds: &crate::connection::CompanionCredentials) -> reqwest::RequestBuilder {
-    if let Some(ref token) = creds.auth_token {
-        builder.header("Cookie", format!("forgeai_session={}", token))
-    } else {
-        builder
-    }
-}
-
-/// Status response for the frontend
-#[derive(Debug, Clone, Serialize)]
-pub struct CompanionStatus {
-    pub connected: bool,
-    pub gateway_url: Option<String>,
-    pub companion_id: Option<String>,
-    pub auth_token: Option<String>,
-    pub safety_active: bool,
-    pub version: String,
-}
-
 /// Pairing request from frontend
 #[derive(Debug, Clone, Deserialize)]
 pub struct PairRequest {
@@ -47,10 +31,13 @@ pub struct PairRequest {
 
 /// Execute a local action (called by the Gateway via LLM tool calls)
 #[tauri::command]
-pub fn execute_action(request: ActionRequest) -> ActionResult {
-    log::info!("Executing action: {} (confirmed: {})", request.action, request.confirmed);
-    let result = local_actions::execute(&request);
-    log::info!(
+pub async fn execute_action(request: ActionRequest) -> ActionResult {
+    tracing::info!("Executing action: {} (confirmed: {})", request.action, request.confirmed);
+    if request.confirmed {
+        crate::rollback::snapshot(std::slice::from_ref(&request));
+    }
+    let result = local_actions::execute_async(request).await;
+    tracing::info!(
         "Action result: success={}, risk={:?}",
         result.success,
         result.safety.risk
@@ -58,11 +45,190 @@ pub fn execute_action(request: ActionRequest) -> ActionResult {
     result
 }
 
+/// Send a local file to the Gateway (chunked and resumable)
+#[tauri::command]
+pub async fn upload_file_to_gateway(path: String) -> Result<crate::transfer::TransferResult, UserError> {
+    let verdict = safety::check_file_operation("read", &path);
+    if !verdict.allowed {
+        return Err(verdict.reason.into());
+    }
+    let creds = crate::connection::GatewayConnection::load_credentials()
+        .ok_or(CompanionError::NotPaired)?;
+    crate::transfer::upload(&creds, &path, None).await.map_err(UserError::from)
+}
+
+/// Download a Gateway file to a local path (resumes a previous partial download)
+#[tauri::command]
+pub async fn download_file_from_gateway(file_id: String, dest_path: String) -> Result<crate::transfer::TransferResult, UserError> {
+    let verdict = safety::check_file_operation("write", &dest_path);
+    if !verdict.allowed {
+        return Err(verdict.reason.into());
+    }
+    let creds = crate::connection::GatewayConnection::load_credentials()
+        .ok_or(CompanionError::NotPaired)?;
+    crate::transfer::download(&creds, &file_id, &dest_path, None).await.map_err(UserError::from)
+}
+
+/// Fetch the next page of a large action result by its continuation token
+#[tauri::command]
+pub fn fetch_result_page(continuation: String) -> Result<pagination::ResultPage, UserError> {
+    pagination::fetch_page(&continuation).map_err(UserError::from)
+}
+
+/// List shell jobs with their CPU/memory usage
+#[tauri::command]
+pub fn list_jobs() -> Vec<jobs::JobInfo> {
+    jobs::list()
+}
+
+/// Kill a running shell job and its child processes
+#[tauri::command]
+pub fn kill_job(job_id: u64) -> Result<String, UserError> {
+    jobs::kill(job_id)?;
+    Ok(format!("Job {} terminated", job_id))
+}
+
+/// Get the resource limits applied to shell jobs
+#[tauri::command]
+pub fn get_job_limits() -> jobs::JobLimits {
+    jobs::limits()
+}
+
+/// Set the resource limits applied to shell jobs
+#[tauri::command]
+pub fn set_job_limits(app_handle: tauri::AppHandle, limits: jobs::JobLimits) -> Result<String, UserError> {
+    crate::settings::update(&app_handle, |s| s.jobs = limits)?;
+    Ok("Job limits updated".into())
+}
+
+/// Create the key a phone signs remote approvals with; returns it for the
+/// Gateway's mobile UI
+#[tauri::command]
+pub fn remote_approval_enroll() -> Result<String, UserError> {
+    crate::remote_approval::enroll().map_err(UserError::from)
+}
+
+#[tauri::command]
+pub fn remote_approval_forget() -> Result<(), UserError> {
+    crate::remote_approval::forget().map_err(UserError::from)
+}
+
+#[tauri::command]
+pub fn remote_approval_status() -> crate::remote_approval::RemoteApprovalStatus {
+    crate::remote_approval::status()
+}
+
+/// Space taken by files the assistant created, against the quota
+#[tauri::command]
+pub fn quota_status() -> Result<crate::quota::QuotaStatus, UserError> {
+    crate::quota::status().map_err(UserError::from)
+}
+
+/// Delete files the assistant created (only those older than
+/// `older_than_hours`, when given)
+#[tauri::command]
+pub fn quota_cleanup(older_than_hours: Option<u32>) -> Result<crate::quota::CleanupReport, UserError> {
+    crate::quota::cleanup(older_than_hours).map_err(UserError::from)
+}
+
+/// Store a secret actions can use as `{{secret:NAME}}`
+#[tauri::command]
+pub fn secret_set(name: String, value: String) -> Result<String, UserError> {
+    crate::secrets::set(&name, &value)?;
+    Ok(format!("Secret {} stored", name))
+}
+
+/// Names of the stored secrets (never their values)
+#[tauri::command]
+pub fn secret_list() -> Result<Vec<crate::secrets::SecretInfo>, UserError> {
+    crate::secrets::list().map_err(UserError::from)
+}
+
+#[tauri::command]
+pub fn secret_delete(name: String) -> Result<String, UserError> {
+    if !crate::secrets::delete(&name)? {
+        return Err(UserError::from(format!("No secret named {}", name)));
+    }
+    Ok(format!("Secret {} deleted", name))
+}
+
+/// Batches of file actions that can still be rolled back, newest first
+#[tauri::command]
+pub fn list_rollbacks() -> Vec<crate::rollback::RollbackBatch> {
+    crate::rollback::list()
+}
+
+/// Put back the files a batch changed
+#[tauri::command]
+pub fn rollback(batch_id: String) -> Result<crate::rollback::RollbackReport, UserError> {
+    crate::rollback::rollback(&batch_id).map_err(UserError::from)
+}
+
+/// Turn simulation mode on or off: actions that would change the machine
+/// return fabricated results instead of running
+#[tauri::command]
+pub fn set_simulation_mode(app_handle: tauri::AppHandle, enabled: bool) -> Result<String, UserError> {
+    crate::settings::update(&app_handle, |s| s.simulation = enabled)?;
+    Ok(if enabled { "Simulation mode on".into() } else { "Simulation mode off".into() })
+}
+
+/// Actions simulated lately, oldest first
+#[tauri::command]
+pub fn simulation_log() -> Vec<crate::simulation::SimulatedAction> {
+    crate::simulation::log()
+}
+
+/// Allow `kind` actions within `scope` without confirmation for `minutes`
+#[tauri::command]
+pub fn create_grant(kind: crate::grants::GrantKind, scope: String, minutes: u32) -> Result<crate::grants::Grant, UserError> {
+    crate::grants::add(kind, &scope, minutes).map_err(UserError::from)
+}
+
+/// Grants in force, oldest first
+#[tauri::command]
+pub fn list_grants() -> Vec<crate::grants::Grant> {
+    crate::grants::list()
+}
+
+/// End a grant before it expires
+#[tauri::command]
+pub fn revoke_grant(id: String) -> Result<String, UserError> {
+    if !crate::grants::revoke(&id) {
+        return Err(UserError::from(format!("No grant {} is in force", id)));
+    }
+    Ok("Grant revoked".into())
+}
+
+/// Gateway-pushed actions waiting for confirmation, oldest first
+#[tauri::command]
+pub fn list_pending_approvals() -> Vec<remote_actions::ConfirmationPrompt> {
+    remote_actions::pending_approvals()
+}
+
+/// Approve several waiting actions at once; returns those still waiting
+#[tauri::command]
+pub fn approve_actions(request_ids: Vec<String>) -> Vec<remote_actions::ConfirmationPrompt> {
+    remote_actions::resolve_all(&request_ids, true)
+}
+
+/// Deny several waiting actions at once; returns those still waiting
+#[tauri::command]
+pub fn deny_actions(request_ids: Vec<String>) -> Vec<remote_actions::ConfirmationPrompt> {
+    remote_actions::resolve_all(&request_ids, false)
+}
+
+/// Approve or deny a Gateway-pushed action that is waiting for confirmation
+#[tauri::command]
+pub fn confirm_pushed_action(request_id: String, approved: bool) -> Result<String, UserError> {
+    remote_actions::resolve_confirmation(&request_id, approved)?;
+    Ok(if approved { "Action approved".into() } else { "Action denied".into() })
+}
+
 /// Check if an action is safe without executing it
 #[tauri::command]
 pub fn check_safety(action: String, path: Option<String>, command: Option<String>) -> safety::SafetyVerdict {
     if let Some(cmd) = &command {
-        return safety::check_shell_command(cmd);
+        return safety::check_shell_command(cmd, None);
     }
     if let Some(p) = &path {
         return safety::check_file_operation(&action, p);
@@ -78,44 +244,41 @@ pub fn check_safety(action: String, path: Option<String>, command: Option<String
 /// Get the safety system prompt (injected into every LLM request)
 #[tauri::command]
 pub fn get_safety_prompt() -> String {
-    safety::get_safety_system_prompt()
+    format!("{}\n\n{}", safety::get_safety_system_prompt(), crate::exec_context::prompt_section())
 }
 
 /// Get companion status
 #[tauri::command]
-pub fn get_status() -> CompanionStatus {
-    let creds = crate::connection::GatewayConnection::load_credentials();
-    CompanionStatus {
-        connected: creds.is_some(),
-        gateway_url: creds.as_ref().map(|c| c.gateway_url.clone()),
-        companion_id: creds.as_ref().map(|c| c.companion_id.clone()),
-        auth_token: creds.as_ref().and_then(|c| c.auth_token.clone()),
-        safety_active: true,
-        version: env!("CARGO_PKG_VERSION").to_string(),
-    }
+pub fn get_status() -> crate::status::CompanionStatus {
+    crate::status::snapshot()
 }
 
-/// Pair with a ForgeAI Gateway by redeeming a pairing code
+/// What still works while the Gateway is unreachable
 #[tauri::command]
-pub async fn pair_with_gateway(gateway_url: String, pairing_code: String) -> Result<String, String> {
-    let url = format!("{}/api/companion/pair", gateway_url.trim_end_matches('/'));
+pub fn offline_state() -> crate::offline::OfflineState {
+    crate::offline::current()
+}
 
-    let client = reqwest::Client::new();
-    let resp = client
-        .post(&url)
-        .json(&serde_json::json!({
+/// Pair with a ForgeAI Gateway by redeeming a pairing code, then check the
+/// new credentials (see `setup::after_pairing`). A report that is not ready
+/// still means paired.
+#[tauri::command]
+pub async fn pair_with_gateway(gateway_url: String, pairing_code: String) -> Result<crate::setup::SetupReport, UserError> {
+    let handshake = crate::e2e::Handshake::new();
+    let gw = crate::http::gateway(&gateway_url)?;
+    let request = gw
+        .post("/api/companion/pair")
+        .json(&crate::device::pairing_body(serde_json::json!({
             "code": pairing_code,
-            "deviceName": hostname::get()
-                .map(|h| h.to_string_lossy().to_string())
-                .unwrap_or_else(|_| "Unknown".into()),
-        }))
-        .timeout(std::time::Duration::from_secs(10))
-        .send()
+            "e2ePublicKey": handshake.public_key,
+        })))
+        .timeout(std::time::Duration::from_secs(10));
+    let resp = crate::netstats::send(request)
         .await
         .map_err(|e| format!("Connection failed: {}", e))?;
 
     if !resp.status().is_success() {
-        return Err(format!("Gateway returned HTTP {}", resp.status()));
+        return Err(format!("Gateway returned HTTP {}", resp.status()).into());
     }
 
     let body: serde_json::Value = resp
@@ -123,6 +286,19 @@ pub async fn pair_with_gateway(gateway_url: String, pairing_code: String) -> Res
         .await
         .map_err(|e| format!("Invalid response: {}", e))?;
 
+    finish_pairing(&gateway_url, &body, handshake, &pairing_code)?;
+    let creds = crate::connection::GatewayConnection::load_credentials().ok_or("Paired, but the credentials were not saved")?;
+    Ok(crate::setup::after_pairing(&creds).await)
+}
+
+/// Store the credentials from a Gateway pairing response
+/// (shared by code pairing and reverse pairing)
+pub(crate) fn finish_pairing(
+    gateway_url: &str,
+    body: &serde_json::Value,
+    handshake: crate::e2e::Handshake,
+    pairing_code: &str,
+) -> Result<(), String> {
     let success = body["success"].as_bool().unwrap_or(false);
     if !success {
         let msg = body["message"].as_str().unwrap_or("Pairing failed");
@@ -138,98 +314,331 @@ pub async fn pair_with_gateway(gateway_url: String, pairing_code: String) -> Res
     let auth_token = body["authToken"]
         .as_str()
         .map(|s| s.to_string());
+    let refresh_token = body["refreshToken"]
+        .as_str()
+        .map(|s| s.to_string());
+
+    // Gateways that support E2E answer with their half of the key exchange.
+    // A Gateway known to support it must not silently come back without one.
+    let e2e_key = match body["e2ePublicKey"].as_str() {
+        Some(peer) => {
+            let session = handshake.derive(peer, &companion_id, pairing_code)?;
+            session.confirmed_by(body["e2eConfirm"].as_str())?;
+            Some(session.key)
+        }
+        None if e2e_expected(gateway_url, body) => {
+            return Err("Pairing failed: the Gateway supports end-to-end encryption but sent no key — refusing to pair without it".into());
+        }
+        None => {
+            tracing::warn!("Gateway does not support end-to-end encryption; action payloads stay TLS-only");
+            None
+        }
+    };
 
     let creds = crate::connection::CompanionCredentials {
         gateway_url: gateway_url.trim_end_matches('/').to_string(),
         companion_id,
         role,
         auth_token,
+        refresh_token,
+        e2e_key,
+        lan_url: None,
+        remote_url: None,
+        stored_gateway_url: None,
     };
 
     crate::connection::GatewayConnection::save_credentials(&creds)?;
-    log::info!("Paired with Gateway at {}", creds.gateway_url);
+    crate::e2e::reset_sequence();
+    tracing::info!("Paired with Gateway at {}", creds.gateway_url);
+    crate::status::publish();
+    Ok(())
+}
+
+/// Whether the Gateway at `gateway_url` is known to support E2E: it says so
+/// in the pairing response, or it did when this companion was last paired
+/// with or connected to it
+fn e2e_expected(gateway_url: &str, body: &serde_json::Value) -> bool {
+    use crate::capabilities::feature;
+    let advertised = body["features"]
+        .as_array()
+        .is_some_and(|f| f.iter().any(|v| v.as_str() == Some(feature::E2E)));
+    let previous = crate::connection::GatewayConnection::load_credentials()
+        .map(|c| crate::roaming::unapplied(&c))
+        .filter(|c| c.gateway_url == gateway_url.trim_end_matches('/'));
+    advertised
+        || previous.is_some_and(|c| {
+            c.e2e_key.is_some() || crate::capabilities::gateway().is_some_and(|m| m.supports(feature::E2E))
+        })
+}
+
+/// Pair using the Gateway's QR payload (URL + one-time code), scanned or pasted
+#[tauri::command]
+pub async fn pair_with_qr(payload: String) -> Result<crate::setup::SetupReport, UserError> {
+    let parsed = crate::pairing::parse_qr_payload(&payload)?;
+    tracing::info!("Pairing from QR payload with {}", parsed.gateway_url);
+    pair_with_gateway(parsed.gateway_url, parsed.pairing_code).await
+}
+
+/// Pair from an image of the Gateway's QR (base64, optionally as a `data:` URL),
+/// e.g. a screenshot of the Dashboard
+#[tauri::command]
+pub async fn pair_with_qr_image(image: String) -> Result<crate::setup::SetupReport, UserError> {
+    let encoded = image.split_once(";base64,").map_or(image.as_str(), |(_, data)| data);
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(encoded.trim())
+        .map_err(|e| format!("Unreadable QR image: {}", e))?;
+    let payload = tokio::task::spawn_blocking(move || crate::qr::decode_image(&bytes))
+        .await
+        .map_err(|e| format!("QR decode task failed: {}", e))??;
+    pair_with_qr(payload).await
+}
+
+/// Send this device's OS, architecture, app version and capabilities to the Gateway
+#[tauri::command]
+pub async fn update_device_info() -> Result<String, UserError> {
+    crate::device::update_on_gateway().await?;
+    Ok("Device info updated".into())
+}
+
+/// Generate a code to enter on the Gateway (reverse pairing); completion is
+/// reported through `reverse-pairing` events
+#[tauri::command]
+pub async fn start_reverse_pairing(gateway_url: String) -> Result<crate::reverse_pairing::ReverseOffer, UserError> {
+    crate::reverse_pairing::start(&gateway_url).await.map_err(UserError::from)
+}
+
+/// Abandon the pending reverse-pairing code
+#[tauri::command]
+pub fn cancel_reverse_pairing() {
+    crate::reverse_pairing::cancel();
+}
+
+/// Show the certificate fingerprint of an https Gateway before trusting it
+#[tauri::command]
+pub async fn inspect_gateway_certificate(gateway_url: String) -> Result<crate::tls_trust::CertInfo, UserError> {
+    crate::tls_trust::inspect(&gateway_url).await.map_err(UserError::from)
+}
+
+/// Trust and pin a Gateway's (self-signed) certificate after the user verified its fingerprint
+#[tauri::command]
+pub async fn trust_gateway_certificate(gateway_url: String, fingerprint: String) -> Result<crate::tls_trust::CertInfo, UserError> {
+    crate::tls_trust::trust(&gateway_url, &fingerprint).await.map_err(UserError::from)
+}
+
+/// Forget the pinned certificate of a Gateway
+#[tauri::command]
+pub fn untrust_gateway_certificate(gateway_url: String) -> Result<String, UserError> {
+    crate::tls_trust::untrust(&gateway_url)?;
+    Ok("Certificate trust removed".into())
+}
+
+/// Set the LAN and remote URLs of the paired Gateway; the reachable one is used
+#[tauri::command]
+pub fn set_gateway_routes(lan_url: Option<String>, remote_url: Option<String>) -> Result<String, UserError> {
+    let mut creds = crate::connection::GatewayConnection::load_credentials()
+        .ok_or(CompanionError::NotPaired)?;
+    let normalize = |u: Option<String>| {
+        u.map(|u| u.trim().trim_end_matches('/').to_string())
+            .filter(|u| !u.is_empty())
+    };
+    creds.lan_url = normalize(lan_url);
+    creds.remote_url = normalize(remote_url);
+    crate::roaming::reset();
+    crate::connection::GatewayConnection::save_credentials(&creds)?;
+    request_ws_reconnect();
+    Ok("Gateway routes updated".into())
+}
+
+/// Get the proxy configuration (password masked)
+#[tauri::command]
+pub fn get_proxy_config() -> crate::proxy::ProxyConfig {
+    crate::proxy::config().redacted()
+}
+
+/// Set the proxy used for all Gateway HTTP requests
+#[tauri::command]
+pub fn set_proxy_config(config: crate::proxy::ProxyConfig) -> Result<String, UserError> {
+    crate::proxy::set_config(config)?;
+    Ok("Proxy settings updated".into())
+}
+
+/// Get the local and negotiated Gateway capability manifests
+#[tauri::command]
+pub fn get_capabilities() -> serde_json::Value {
+    serde_json::json!({
+        "local": crate::capabilities::local(),
+        "gateway": crate::capabilities::gateway(),
+    })
+}
+
+/// Get the payload compression settings
+#[tauri::command]
+pub fn get_compression_config() -> crate::compression::CompressionConfig {
+    crate::compression::config()
+}
+
+/// Set the payload compression settings
+#[tauri::command]
+pub fn set_compression_config(
+    app_handle: tauri::AppHandle,
+    config: crate::compression::CompressionConfig,
+) -> Result<String, UserError> {
+    crate::settings::update(&app_handle, |s| s.compression = config)?;
+    Ok("Compression settings updated".into())
+}
 
-    Ok("Paired successfully!".into())
+/// Bytes saved by payload compression since startup
+#[tauri::command]
+pub fn get_compression_stats() -> crate::compression::CompressionStats {
+    crate::compression::stats()
+}
+
+/// Requests and bytes sent/received per Gateway endpoint since startup (or last reset)
+#[tauri::command]
+pub fn network_stats() -> crate::netstats::NetworkStats {
+    crate::netstats::snapshot()
+}
+
+/// Reset the network statistics counters
+#[tauri::command]
+pub fn reset_network_stats() -> Result<String, UserError> {
+    crate::netstats::reset();
+    Ok("Network statistics reset".into())
+}
+
+/// Pull new conversation history from the Gateway into the local store
+#[tauri::command]
+pub async fn sync_history() -> Result<crate::history::SyncReport, UserError> {
+    crate::history::sync().await.map_err(UserError::from)
+}
+
+/// List locally stored sessions (works offline)
+#[tauri::command]
+pub fn list_local_sessions(limit: Option<u32>, offset: Option<u32>) -> Result<Vec<crate::history::SessionSummary>, UserError> {
+    crate::history::sessions(limit.unwrap_or(50), offset.unwrap_or(0)).map_err(UserError::from)
+}
+
+/// Page through a locally stored session, newest first (works offline)
+#[tauri::command]
+pub fn get_local_messages(session_id: String, before: Option<String>, limit: Option<u32>) -> Result<Vec<crate::history::Message>, UserError> {
+    crate::history::messages(&session_id, before.as_deref(), limit.unwrap_or(50)).map_err(UserError::from)
+}
+
+/// Search locally stored messages (works offline)
+#[tauri::command]
+pub fn search_history(query: String, limit: Option<u32>, offset: Option<u32>) -> Result<Vec<crate::history::Message>, UserError> {
+    crate::history::search_messages(&query, limit.unwrap_or(50), offset.unwrap_or(0)).map_err(UserError::from)
+}
+
+/// Get the Gateway push event settings (subscribed topics, OS notifications)
+#[tauri::command]
+pub fn get_push_config() -> crate::push::PushConfig {
+    crate::push::config()
+}
+
+/// Set the Gateway push event settings
+#[tauri::command]
+pub fn set_push_config(app_handle: tauri::AppHandle, config: crate::push::PushConfig) -> Result<String, UserError> {
+    crate::settings::update(&app_handle, |s| s.push = config)?;
+    Ok("Push settings updated".into())
+}
+
+/// Browse the local network for Gateways advertised over mDNS
+#[tauri::command]
+pub async fn discover_gateways(timeout_ms: Option<u64>) -> Result<Vec<crate::discovery::DiscoveredGateway>, UserError> {
+    let timeout = std::time::Duration::from_millis(timeout_ms.unwrap_or(3000).clamp(500, 15_000));
+    tokio::task::spawn_blocking(move || crate::discovery::discover(timeout))
+        .await
+        .map_err(|e| format!("Discovery task failed: {}", e))?
+        .map_err(UserError::from)
 }
 
 /// Start dragging the window
 #[tauri::command]
-pub fn window_start_drag(window: tauri::Window) -> Result<(), String> {
-    window.start_dragging().map_err(|e| e.to_string())
+pub fn window_start_drag(window: tauri::Window) -> Result<(), UserError> {
+    window.start_dragging().map_err(|e| e.to_string()).map_err(UserError::from)
 }
 
 /// Minimize the main window
 #[tauri::command]
-pub fn window_minimize(window: tauri::Window) -> Result<(), String> {
-    window.minimize().map_err(|e| e.to_string())
+pub fn window_minimize(window: tauri::Window) -> Result<(), UserError> {
+    window.minimize().map_err(|e| e.to_string()).map_err(UserError::from)
 }
 
 /// Hide the main window (close to tray)
 #[tauri::command]
-pub fn window_hide(window: tauri::Window) -> Result<(), String> {
-    window.hide().map_err(|e| e.to_string())
+pub fn window_hide(window: tauri::Window) -> Result<(), UserError> {
+    window.hide().map_err(|e| e.to_string()).map_err(UserError::from)
 }
 
 /// Toggle maximize/restore
 #[tauri::command]
-pub fn window_maximize(window: tauri::Window) -> Result<(), String> {
+pub fn window_maximize(window: tauri::Window) -> Result<(), UserError> {
     if window.is_maximized().unwrap_or(false) {
-        window.unmaximize().map_err(|e| e.to_string())
+        window.unmaximize().map_err(|e| e.to_string()).map_err(UserError::from)
     } else {
-        window.maximize().map_err(|e| e.to_string())
+        window.maximize().map_err(|e| e.to_string()).map_err(UserError::from)
     }
 }
 
+/// Tries for a chat or voice request: a second one only helps a busy
+/// Gateway, not one already known to be unreachable
+fn gateway_attempts() -> u32 {
+    if crate::offline::is_offline() { 1 } else { 2 }
+}
+
 /// Send a chat message to the Gateway and get AI response.
 /// Uses streaming mode: Gateway sends heartbeat spaces to keep connection alive
 /// during long agent runs, then the final JSON result at the end.
 #[tauri::command]
-pub async fn chat_send(message: String, session_id: Option<String>) -> Result<serde_json::Value, String> {
-    let creds = crate::connection::GatewayConnection::load_credentials()
-        .ok_or("Not connected — pair first")?;
+pub async fn chat_send(message: String, session_id: Option<String>) -> Result<serde_json::Value, UserError> {
+    crate::speaker::end_voice_context();
+    if let Some(reply) = answer_locally(&message).await {
+        return Ok(serde_json::json!({ "content": reply, "sessionId": session_id, "local": true }));
+    }
 
-    let url = format!("{}/api/chat", creds.gateway_url);
+    let creds = crate::connection::GatewayConnection::load_credentials()
+        .ok_or(CompanionError::NotPaired)?;
 
     // No total timeout — Gateway sends heartbeat spaces every 10s to keep alive.
-    // Only connect_timeout to fail fast if server is unreachable.
-    let client = reqwest::Client::builder()
-        .connect_timeout(std::time::Duration::from_secs(15))
-        .build()
-        .map_err(|e| format!("HTTP client error: {}", e))?;
+    // The shared client's connect/read timeouts fail fast if it is unreachable.
+    let gw = crate::http::gateway(&creds.gateway_url)?;
 
-    let payload = serde_json::json!({
+    let mut payload = serde_json::json!({
         "message": message,
         "sessionId": session_id,
         "userId": creds.companion_id,
         "channelType": "companion",
         "stream": true,
     });
+    if let Some(context) = crate::screen_context::for_request().await {
+        payload["screenContext"] = serde_json::to_value(context).unwrap_or_default();
+    }
 
     let mut last_err = String::new();
     let mut resp_opt = None;
-    for attempt in 0..2 {
-        let mut req = client.post(&url).json(&payload);
-        if let Some(ref token) = creds.auth_token {
-            req = req.header("Cookie", format!("forgeai_session={}", token));
-        }
-        match req.send().await {
+    let attempts = gateway_attempts();
+    for attempt in 0..attempts {
+        let build = || gw.post("/api/chat").json(&payload);
+        match crate::connection::GatewayConnection::send_authenticated(&creds, build).await {
             Ok(r) => { resp_opt = Some(r); break; }
+            Err(crate::connection::GatewayError::RateLimited(limited)) => return Err(UserError::new(CompanionError::RateLimited, limited.to_string())),
             Err(e) => {
-                last_err = format!("{}", e);
-                log::warn!("chat_send: Gateway request attempt {} failed: {}", attempt + 1, last_err);
-                if attempt == 0 {
+                last_err = e.to_string();
+                tracing::warn!("chat_send: Gateway request attempt {} failed: {}", attempt + 1, last_err);
+                if attempt + 1 < attempts {
                     tokio::time::sleep(std::time::Duration::from_secs(3)).await;
                 }
             }
         }
     }
 
-    let resp = resp_opt.ok_or(format!("Gateway unreachable after 2 attempts: {}", last_err))?;
+    let resp = resp_opt.ok_or(format!("Gateway unreachable after {} attempts: {}", attempts, last_err))?;
 
     if !resp.status().is_success() {
         let status = resp.status();
         let body = resp.text().await.unwrap_or_default();
-        return Err(format!("Gateway HTTP {}: {}", status, body));
+        return Err(format!("Gateway HTTP {}: {}", status, body).into());
     }
 
     // Response is streamed: heartbeat spaces followed by JSON.
@@ -246,77 +655,414 @@ pub async fn chat_send(message: String, session_id: Option<String>) -> Result<se
 
     // Check for server-side error in response
     if let Some(err) = body.get("error").and_then(|v| v.as_str()) {
-        return Err(format!("Gateway error: {}", err));
+        return Err(format!("Gateway error: {}", err).into());
     }
 
     Ok(body)
 }
 
+/// Reply to `text` on the device — a voice shortcut or a local intent — or
+/// None to send it to the Gateway
+async fn answer_locally(text: &str) -> Option<String> {
+    if let Some(shortcut) = crate::shortcuts::find(text) {
+        return Some(crate::shortcuts::run(&shortcut).await);
+    }
+    crate::intents::respond(text)
+}
+
+/// Recordings kept in the outbox while the Gateway is unreachable
+const MAX_DEFERRED_TRANSCRIPTIONS: usize = 10;
+
 /// Full voice pipeline: record mic → send to Gateway STT+AI+TTS → play response → return text
 /// This is the "Jarvis" command — speak to ForgeAI, get a spoken answer back.
 /// Emits events: voice-state (listening/processing/speaking/idle), voice-audio-level
 #[tauri::command]
-pub async fn chat_voice(
+pub async fn chat_voice(state: State<'_, VoiceState>, session_id: Option<String>) -> Result<serde_json::Value, UserError> {
+    voice_turn(&state.0, session_id).await
+}
+
+/// Record speech, translate it into `target_lang` via the Gateway and speak the result
+#[tauri::command]
+pub async fn voice_translate(state: State<'_, VoiceState>, target_lang: String) -> Result<crate::translate::Translation, UserError> {
+    crate::translate::voice_translate(&state.0, &target_lang).await.map_err(UserError::from)
+}
+
+/// Start a full-duplex conversation: the mic streams to the Gateway and replies stream back
+#[tauri::command]
+pub fn duplex_start(session_id: Option<String>) -> Result<(), UserError> {
+    crate::duplex::start(session_id).map_err(UserError::from)
+}
+
+#[tauri::command]
+pub fn duplex_stop() {
+    crate::duplex::stop();
+}
+
+#[tauri::command]
+pub fn duplex_active() -> bool {
+    crate::duplex::active()
+}
+
+/// Start dictating into the focused application; "stop dictation" or `dictation_stop` ends it
+#[tauri::command]
+pub fn dictation_start() -> Result<(), UserError> {
+    crate::dictation::start().map_err(UserError::from)
+}
+
+#[tauri::command]
+pub fn dictation_stop() {
+    crate::dictation::stop();
+}
+
+#[tauri::command]
+pub fn dictation_active() -> bool {
+    crate::dictation::active()
+}
+
+/// Send a `test` event to the webhook called `name`
+#[tauri::command]
+pub async fn test_webhook(name: String) -> Result<String, UserError> {
+    crate::webhooks::test(&name).await?;
+    Ok(format!("Webhook '{}' answered", name))
+}
+
+/// The clipboard history, newest first, with entries shortened
+#[tauri::command]
+pub fn clipboard_history() -> Vec<crate::clipboard_history::EntryPreview> {
+    crate::clipboard_history::list()
+}
+
+#[tauri::command]
+pub fn clear_clipboard_history() {
+    crate::clipboard_history::clear();
+}
+
+/// Per-day recording, upload and speech minutes, wake detections and
+/// action runs over the last `days` days (default 30)
+#[tauri::command]
+pub async fn get_usage_stats(days: Option<u32>) -> Result<crate::usage::UsageStats, UserError> {
+    let days = days.unwrap_or(30).clamp(1, 366);
+    tauri::async_runtime::spawn_blocking(move || crate::usage::stats(days))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(UserError::from)
+}
+
+/// Write settings, scripts and (with a passphrase, encrypted) secrets to one file
+#[tauri::command]
+pub async fn export_config(path: String, passphrase: Option<String>) -> Result<crate::config_export::ExportReport, UserError> {
+    tauri::async_runtime::spawn_blocking(move || crate::config_export::export(&path, passphrase.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(UserError::from)
+}
+
+/// Apply a file written by `export_config`
+#[tauri::command]
+pub async fn import_config(
     app_handle: tauri::AppHandle,
+    path: String,
+    passphrase: Option<String>,
+) -> Result<crate::config_export::ImportReport, UserError> {
+    tauri::async_runtime::spawn_blocking(move || crate::config_export::import(&app_handle, &path, passphrase.as_deref()))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(UserError::from)
+}
+
+/// Loaded user scripts, the folder they are loaded from and files that failed
+#[tauri::command]
+pub fn list_scripts() -> crate::scripts::ScriptStatus {
+    crate::scripts::status()
+}
+
+/// Load the user scripts again without waiting for the folder watcher
+#[tauri::command]
+pub async fn reload_scripts() -> Result<crate::scripts::ScriptStatus, UserError> {
+    tauri::async_runtime::spawn_blocking(crate::scripts::reload)
+        .await
+        .map_err(|e| UserError::from(e.to_string()))
+}
+
+/// Store the MQTT broker password (encrypted), or forget it with None
+#[tauri::command]
+pub fn set_mqtt_password(password: Option<String>) -> Result<(), UserError> {
+    crate::mqtt::set_password(password.as_deref()).map_err(UserError::from)
+}
+
+/// Connect to the configured MQTT broker to check the settings
+#[tauri::command]
+pub async fn test_mqtt_connection() -> Result<String, UserError> {
+    tauri::async_runtime::spawn_blocking(crate::mqtt::test_connection)
+        .await
+        .map_err(|e| e.to_string())??;
+    Ok("Connected to the MQTT broker".into())
+}
+
+/// Record a few phrases and store the user's voiceprint for speaker verification
+#[tauri::command]
+pub async fn speaker_enroll(state: State<'_, VoiceState>) -> Result<crate::speaker::SpeakerStatus, UserError> {
+    crate::speaker::enroll(&state.0).map_err(UserError::from)
+}
+
+/// Record takes of a custom wake phrase and package them for training a model
+#[tauri::command]
+pub async fn wake_word_collect_samples(
     state: State<'_, VoiceState>,
+    phrase: String,
+    count: Option<usize>,
+) -> Result<crate::wake_samples::SampleSet, UserError> {
+    crate::wake_samples::collect(&state.0, &phrase, count).map_err(UserError::from)
+}
+
+#[tauri::command]
+pub fn speaker_forget() -> Result<(), UserError> {
+    crate::speaker::forget().map_err(UserError::from)
+}
+
+#[tauri::command]
+pub fn speaker_status() -> crate::speaker::SpeakerStatus {
+    crate::speaker::status()
+}
+
+/// Start recording a meeting; chunks are transcribed with speaker labels in the background
+#[tauri::command]
+pub fn meeting_start(title: Option<String>) -> Result<crate::meeting::Meeting, UserError> {
+    crate::meeting::start(title).map_err(UserError::from)
+}
+
+#[tauri::command]
+pub fn meeting_pause() -> Result<crate::meeting::Meeting, UserError> {
+    crate::meeting::pause().map_err(UserError::from)
+}
+
+#[tauri::command]
+pub fn meeting_resume() -> Result<crate::meeting::Meeting, UserError> {
+    crate::meeting::resume().map_err(UserError::from)
+}
+
+/// Stop the recording; returns the meeting id
+#[tauri::command]
+pub fn meeting_stop() -> Result<String, UserError> {
+    crate::meeting::stop().map_err(UserError::from)
+}
+
+/// Recorded meetings, newest first
+#[tauri::command]
+pub fn meeting_list() -> Vec<crate::meeting::Meeting> {
+    crate::meeting::list()
+}
+
+/// The timestamped transcript of a meeting, as far as it is transcribed
+#[tauri::command]
+pub fn meeting_transcript(id: String) -> Result<String, UserError> {
+    crate::meeting::transcript(&id).map_err(UserError::from)
+}
+
+/// Write a conversation or meeting transcript to `path` as `md`, `txt` or `srt`
+#[tauri::command]
+pub fn export_transcript(session_id: String, format: String, path: String) -> Result<usize, UserError> {
+    crate::transcript_export::export(&session_id, &format, &path).map_err(UserError::from)
+}
+
+#[tauri::command]
+pub fn meeting_delete(id: String) -> Result<(), UserError> {
+    crate::meeting::delete(&id).map_err(UserError::from)
+}
+
+/// Run one voice turn and break down where its latency went, from the end
+/// of speech to the first audio of the reply
+#[tauri::command]
+pub async fn measure_voice_latency(state: State<'_, VoiceState>) -> Result<crate::latency::LatencyReport, UserError> {
+    crate::latency::begin();
+    let turn = voice_turn(&state.0, None).await;
+    let report = crate::latency::finish();
+    turn?;
+    tracing::info!("[Latency] {:.0} ms, slowest stage: {}", report.total_ms, report.slowest.unwrap_or("none"));
+    Ok(report)
+}
+
+fn emit_voice_state(state: &str) {
+    crate::events::emit("voice-state", serde_json::json!({ "state": state }));
+}
+
+/// The voice pipeline behind `chat_voice`, usable without a window (see `headless`)
+pub async fn voice_turn(
+    engine: &std::sync::Mutex<VoiceEngine>,
     session_id: Option<String>,
-) -> Result<serde_json::Value, String> {
-    use tauri::Emitter;
+) -> Result<serde_json::Value, UserError> {
     let creds = crate::connection::GatewayConnection::load_credentials()
-        .ok_or("Not connected — pair first")?;
+        .ok_or(CompanionError::NotPaired)?;
+
+    let started_at = chrono::Utc::now().to_rfc3339();
 
     // Emit: LISTENING
-    let _ = app_handle.emit("voice-state", serde_json::json!({ "state": "listening" }));
+    emit_voice_state("listening");
 
     // Step 1: Record audio from microphone (emits audio levels in real-time)
-    log::info!("Jarvis: recording...");
+    tracing::info!("Jarvis: recording...");
     let audio = {
-        let engine = state.0.lock().map_err(|e| {
-            let _ = app_handle.emit("voice-state", serde_json::json!({ "state": "idle" }));
+        let engine = engine.lock().map_err(|e| {
+            emit_voice_state("idle");
             e.to_string()
         })?;
-        match engine.record_with_events(&app_handle) {
+        match engine.record_with_events() {
             Ok(a) => a,
             Err(e) => {
-                let _ = app_handle.emit("voice-state", serde_json::json!({ "state": "idle" }));
-                return Err(e);
+                emit_voice_state("idle");
+                return Err(e.into());
             }
         }
     };
-    log::info!("Jarvis: recorded {}ms of audio", audio.duration_ms);
+    tracing::info!("Jarvis: recorded {}ms of audio", audio.duration_ms);
+    crate::speaker::observe(&audio);
 
     // Emit: PROCESSING
-    let _ = app_handle.emit("voice-state", serde_json::json!({ "state": "processing" }));
+    emit_voice_state("processing");
+
+    // Trivial requests are answered on the device once the words are known
+    let mut transcript = None;
+    if crate::intents::enabled() || crate::shortcuts::configured() {
+        crate::latency::mark(crate::latency::Stage::RequestSent);
+        match VoiceEngine::new().transcribe_detailed(&creds, &audio).await {
+            Ok(heard) => match answer_locally(&heard.text).await {
+                Some(reply) => {
+                    crate::latency::mark(crate::latency::Stage::Replied);
+                    if !reply.is_empty() {
+                        emit_voice_state("speaking");
+                        if let Err(e) = crate::voice::announce(&reply).await {
+                            tracing::error!("Jarvis: local reply playback failed: {}", e);
+                        }
+                    }
+                    emit_voice_state("idle");
+                    remember_turn(session_id.clone(), &heard.text, &reply, started_at, &audio);
+                    return Ok(serde_json::json!({
+                        "transcription": heard.text,
+                        "language": heard.language,
+                        "content": reply,
+                        "sessionId": session_id,
+                        "local": true,
+                    }));
+                }
+                None => transcript = Some(heard),
+            },
+            Err(e) => tracing::warn!("Jarvis: transcription for local intents failed: {}", e),
+        }
+    }
+
+    // Streamed replies: speak each sentence as soon as it is written
+    if crate::speech_stream::enabled() {
+        let heard = match transcript.take() {
+            Some(heard) => Some(heard),
+            None => VoiceEngine::new()
+                .transcribe_detailed(&creds, &audio)
+                .await
+                .map_err(|e| tracing::warn!("Jarvis: transcription for a streamed reply failed: {}", e))
+                .ok(),
+        };
+        if let Some(heard) = heard {
+            let language = heard.language.as_deref().filter(|_| crate::language::enabled());
+            match crate::speech_stream::reply(&creds, &heard.text, language, session_id.clone()).await {
+                Ok(reply) => {
+                    emit_voice_state("idle");
+                    remember_turn(reply.session_id.clone(), &heard.text, &reply.content, started_at, &audio);
+                    return Ok(serde_json::json!({
+                        "transcription": heard.text,
+                        "language": heard.language,
+                        "content": reply.content,
+                        "sessionId": reply.session_id,
+                        "streamed": true,
+                    }));
+                }
+                Err(crate::speech_stream::StreamError::Unsupported) => transcript = Some(heard),
+                Err(crate::speech_stream::StreamError::Failed(e)) => {
+                    emit_voice_state("idle");
+                    return Err(e.into());
+                }
+            }
+        }
+    }
 
     // Step 2: Send audio to Gateway /api/chat/voice for STT → AI → TTS
     // Retry once on connection errors (server may be busy with agent tools)
-    let url = format!("{}/api/chat/voice", creds.gateway_url);
-    let client = reqwest::Client::builder()
-        .timeout(std::time::Duration::from_secs(180))
-        .build()
-        .map_err(|e| format!("HTTP client error: {}", e))?;
+    let gw = crate::http::gateway(&creds.gateway_url)?;
 
-    let payload = serde_json::json!({
+    let mut payload = serde_json::json!({
         "audio": audio.wav_base64,
         "format": "wav",
         "sessionId": session_id,
         "userId": creds.companion_id,
         "ttsResponse": true,
     });
+    if let Some(context) = crate::screen_context::for_request().await {
+        payload["screenContext"] = serde_json::to_value(context).unwrap_or_default();
+    }
+    // Already transcribed for local intents; Gateways that know the field skip STT
+    let known_language = transcript.as_ref().and_then(|heard| heard.language.clone());
+    if let Some(heard) = transcript {
+        payload["transcription"] = heard.text.into();
+    }
+    // Reply in the language the user spoke, or let the Gateway work it out
+    if crate::language::enabled() {
+        match known_language {
+            Some(language) => payload["language"] = language.into(),
+            None => payload["detectLanguage"] = true.into(),
+        }
+    }
+    let vocabulary = crate::vocabulary::hint();
+    if !vocabulary.is_empty() {
+        payload["vocabulary"] = vocabulary.into();
+    }
+    if let Some(voice) = crate::voice::tts_voice() {
+        payload["voice"] = voice.into();
+    }
+    let lexicon = crate::lexicon::entries();
+    if !lexicon.is_empty() {
+        payload["lexicon"] = serde_json::to_value(lexicon).unwrap_or_default();
+    }
+    let providers = crate::voice::providers();
+    if let Some(stt) = providers.stt {
+        payload["sttProvider"] = stt.into();
+    }
+    if let Some(tts) = providers.tts {
+        payload["ttsProvider"] = tts.into();
+    }
+
+    if let Some(gw) = crate::capabilities::gateway() {
+        if !gw.audio_codecs.is_empty() && !gw.audio_codecs.iter().any(|c| c == "wav") {
+            emit_voice_state("idle");
+            return Err(format!("Gateway does not accept WAV audio (supports: {})", gw.audio_codecs.join(", ")).into());
+        }
+    }
 
+    // Audio is the bulk of the request — compress it when the Gateway accepts that
+    let mut body = crate::compression::json_upload(&payload);
+
+    let started = std::time::Instant::now();
+    crate::latency::mark(crate::latency::Stage::RequestSent);
     let mut last_err = String::new();
     let mut resp_opt = None;
-    for attempt in 0..2 {
-        let mut req = client.post(&url).json(&payload);
-        if let Some(ref token) = creds.auth_token {
-            req = req.header("Cookie", format!("forgeai_session={}", token));
-        }
-        match req.send().await {
+    let attempts = gateway_attempts();
+    for attempt in 0..attempts {
+        let build = || crate::compression::with_json_body(
+            gw.post("/api/chat/voice").timeout(std::time::Duration::from_secs(180)),
+            &body,
+        );
+        match crate::connection::GatewayConnection::send_authenticated(&creds, build).await {
+            Ok(r) if crate::compression::upload_rejected(r.status(), &body) => {
+                body = crate::compression::json_upload(&payload);
+                last_err = "Compressed upload rejected".into();
+            }
             Ok(r) => { resp_opt = Some(r); break; }
+            // Retrying a rate-limited voice request would only be refused again
+            Err(crate::connection::GatewayError::RateLimited(limited)) => {
+                emit_voice_state("idle");
+                return Err(UserError::new(CompanionError::RateLimited, limited.to_string()));
+            }
             Err(e) => {
-                last_err = format!("{}", e);
-                log::warn!("Jarvis: Gateway request attempt {} failed: {}", attempt + 1, last_err);
-                if attempt == 0 {
+                last_err = e.to_string();
+                tracing::warn!("Jarvis: Gateway request attempt {} failed: {}", attempt + 1, last_err);
+                if attempt + 1 < attempts {
                     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
                 }
             }
@@ -324,55 +1070,105 @@ pub async fn chat_voice(
     }
 
     let resp = match resp_opt {
-        Some(r) => r,
+        Some(r) => {
+            crate::usage::record(crate::usage::Metric::AudioSentMs, audio.duration_ms);
+            r
+        }
         None => {
-            let _ = app_handle.emit("voice-state", serde_json::json!({ "state": "idle" }));
-            return Err(format!("Gateway unreachable after 2 attempts: {}", last_err));
+            emit_voice_state("idle");
+            // Keep the recording for later; the Gateway's reply then arrives as a push event
+            if crate::outbox::pending_with_prefix("transcription:") < MAX_DEFERRED_TRANSCRIPTIONS {
+                crate::usage::record(crate::usage::Metric::AudioSentMs, audio.duration_ms);
+                crate::outbox::enqueue(
+                    &format!("transcription:{}", chrono::Utc::now().timestamp_millis()),
+                    crate::e2e::wrap_outgoing(serde_json::json!({
+                        "type": "transcription.deferred",
+                        "audio": payload["audio"],
+                        "format": "wav",
+                        "sessionId": session_id,
+                        "recordedAt": chrono::Utc::now().to_rfc3339(),
+                    })),
+                );
+                return Err(format!(
+                    "Gateway unreachable after {} attempts: {} — the recording will be sent when it is back",
+                    attempts, last_err
+                ).into());
+            }
+            return Err(format!("Gateway unreachable after {} attempts: {}", attempts, last_err).into());
         }
     };
 
     if !resp.status().is_success() {
         let status = resp.status();
         let body = resp.text().await.unwrap_or_default();
-        let _ = app_handle.emit("voice-state", serde_json::json!({ "state": "idle" }));
-        return Err(format!("Gateway HTTP {}: {}", status, body));
+        emit_voice_state("idle");
+        return Err(format!("Gateway HTTP {}: {}", status, body).into());
     }
 
-    let body: serde_json::Value = resp
+    let mut body: serde_json::Value = resp
         .json()
         .await
         .map_err(|e| {
-            let _ = app_handle.emit("voice-state", serde_json::json!({ "state": "idle" }));
+            emit_voice_state("idle");
             format!("Invalid response: {}", e)
         })?;
+    crate::metrics::observe_since(crate::metrics::timing::VOICE_TURN, "", started);
+    crate::latency::mark(crate::latency::Stage::Replied);
+    crate::latency::gateway_timings(&body["timings"]);
+    if let Some(stt_ms) = body["timings"]["sttMs"].as_f64() {
+        crate::metrics::observe(crate::metrics::timing::STT, "", stt_ms);
+    }
 
-    let transcription = body["transcription"].as_str().unwrap_or("").to_string();
+    let transcription = crate::vocabulary::correct(body["transcription"].as_str().unwrap_or(""));
+    let transcription = crate::transcript_filter::apply(&transcription);
+    body["transcription"] = transcription.clone().into();
+    body["language"] = crate::language::resolve(body["language"].as_str(), &transcription).into();
     let content = body["content"].as_str().unwrap_or("").to_string();
-    log::info!("Jarvis: user said '{}', AI replied '{}'",
+    tracing::info!("Jarvis: user said '{}', AI replied '{}'",
         transcription.chars().take(50).collect::<String>(),
         content.chars().take(50).collect::<String>());
 
+    remember_turn(body["sessionId"].as_str().map(str::to_string).or(session_id), &transcription, &content, started_at, &audio);
+
     // Step 3: Play TTS audio response if available
     if let Some(tts_audio) = body["ttsAudio"].as_str() {
         if let Ok(audio_bytes) = base64::engine::general_purpose::STANDARD.decode(tts_audio) {
-            log::info!("Jarvis: playing TTS response ({} bytes)", audio_bytes.len());
+            tracing::info!("Jarvis: playing TTS response ({} bytes)", audio_bytes.len());
             // Emit: SPEAKING
-            let _ = app_handle.emit("voice-state", serde_json::json!({ "state": "speaking" }));
-            if let Err(e) = crate::voice::play_audio_bytes(&audio_bytes) {
-                log::error!("Jarvis: TTS playback failed: {}", e);
+            emit_voice_state("speaking");
+            if let Err(e) = crate::voice::play_output(&audio_bytes, crate::quiet_hours::Output::Speech(Some(&content))) {
+                tracing::error!("Jarvis: TTS playback failed: {}", e);
             }
         }
     }
 
     // Emit: IDLE
-    let _ = app_handle.emit("voice-state", serde_json::json!({ "state": "idle" }));
+    emit_voice_state("idle");
 
     Ok(body)
 }
 
+/// Keep a finished voice turn in the local memory
+fn remember_turn(session_id: Option<String>, transcript: &str, reply: &str, started_at: String, audio: &CapturedAudio) {
+    let turn = crate::memory::VoiceTurn {
+        id: 0,
+        session_id,
+        transcript: transcript.to_string(),
+        reply: reply.to_string(),
+        started_at,
+        finished_at: chrono::Utc::now().to_rfc3339(),
+        audio_ms: audio.duration_ms,
+        audio_ref: None,
+    };
+    let wav = base64::engine::general_purpose::STANDARD.decode(&audio.wav_base64).ok();
+    if let Err(e) = crate::memory::record(turn, wav.as_deref()) {
+        tracing::warn!("[Memory] Could not store the voice turn: {}", e);
+    }
+}
+
 /// Play base64-encoded audio through speakers (for TTS responses)
 #[tauri::command]
-pub async fn play_tts(audio_base64: String) -> Result<String, String> {
+pub async fn play_tts(audio_base64: String) -> Result<String, UserError> {
     use base64::Engine as _;
     let audio_bytes = base64::engine::general_purpose::STANDARD
         .decode(&audio_base64)
@@ -383,8 +1179,11 @@ pub async fn play_tts(audio_base64: String) -> Result<String, String> {
 
 /// Delete stored credentials (disconnect)
 #[tauri::command]
-pub fn disconnect() -> Result<String, String> {
+pub fn disconnect() -> Result<String, UserError> {
     crate::connection::GatewayConnection::delete_credentials()?;
+    crate::history::clear()?;
+    crate::events::emit("disconnected", ());
+    crate::status::publish();
     Ok("Disconnected and credentials removed".into())
 }
 
@@ -393,7 +1192,7 @@ pub fn disconnect() -> Result<String, String> {
 /// Spawn the persistent Gateway WebSocket loop (idempotent — only one loop runs)
 pub fn spawn_gateway_ws() {
     if GATEWAY_WS_ACTIVE.swap(true, Ordering::SeqCst) {
-        log::info!("[GatewayWS] Loop already active");
+        tracing::info!("[GatewayWS] Loop already active");
         return;
     }
     tauri::async_runtime::spawn(async {
@@ -404,15 +1203,21 @@ pub fn spawn_gateway_ws() {
 
 /// Tauri command: ensure the Gateway WS is running (called after pairing)
 #[tauri::command]
-pub async fn connect_gateway_ws() -> Result<String, String> {
+pub async fn connect_gateway_ws() -> Result<String, UserError> {
     spawn_gateway_ws();
     Ok("Gateway WS connection started".into())
 }
 
+/// Ask the running WS loop to drop its connection and reconnect with the
+/// current credentials (e.g. after the Gateway route changed)
+pub fn request_ws_reconnect() {
+    get_reconnect_notify().notify_one();
+}
+
 /// Tauri command: force the WS loop to reconnect with fresh credentials (call after re-pairing)
 #[tauri::command]
-pub async fn force_reconnect_gateway_ws() -> Result<String, String> {
-    log::info!("[GatewayWS] Force reconnect requested");
+pub async fn force_reconnect_gateway_ws() -> Result<String, UserError> {
+    tracing::info!("[GatewayWS] Force reconnect requested");
     get_reconnect_notify().notify_one();
     // Wait for old loop to exit, then start fresh
     tokio::time::sleep(std::time::Duration::from_millis(500)).await;
@@ -423,7 +1228,7 @@ pub async fn force_reconnect_gateway_ws() -> Result<String, String> {
 
 async fn gateway_ws_loop() {
     use futures_util::{SinkExt, StreamExt};
-    use tokio_tungstenite::{connect_async, tungstenite::Message};
+    use tokio_tungstenite::tungstenite::Message;
 
     // Brief delay so the app is fully initialized
     tokio::time::sleep(std::time::Duration::from_secs(2)).await;
@@ -447,19 +1252,25 @@ async fn gateway_ws_loop() {
             ws_url.push_str(&format!("&token={}", token));
         }
 
-        log::info!("[GatewayWS] Connecting: companionId={}", creds.companion_id);
+        tracing::info!("[GatewayWS] Connecting: companionId={}", creds.companion_id);
 
-        match connect_async(&ws_url).await {
-            Ok((ws_stream, _)) => {
-                log::info!("[GatewayWS] Connected to {}", creds.gateway_url);
+        let connector = crate::tls_trust::ws_connector(&creds.gateway_url);
+        match crate::proxy::connect_ws(&ws_url, connector).await {
+            Ok(ws_stream) => {
+                tracing::info!("[GatewayWS] Connected to {}", creds.gateway_url);
                 let (mut write, mut read) = ws_stream.split();
                 let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+                crate::connection::set_live_sender(Some(tx.clone()));
+                // Push events are subscribed once the Gateway's manifest offers them
+                let _ = tx.send(crate::capabilities::hello_message().to_string());
+                crate::history::sync_in_background();
 
                 // Send task: forwards outgoing messages to WS
                 let send_handle = tokio::spawn(async move {
                     while let Some(msg) = rx.recv().await {
+                        crate::netstats::record_channel(true, msg.len());
                         if write.send(Message::Text(msg.into())).await.is_err() {
-                            log::error!("[GatewayWS] Write failed, send task exiting");
+                            tracing::error!("[GatewayWS] Write failed, send task exiting");
                             break;
                         }
                     }
@@ -477,118 +1288,164 @@ async fn gateway_ws_loop() {
                             match msg {
                                 Some(Ok(Message::Text(text))) => {
                                     let text_str: String = text.to_string();
+                                    crate::netstats::record_channel(false, text_str.len());
                                     let raw: serde_json::Value = match serde_json::from_str(&text_str) {
                                         Ok(v) => v,
                                         Err(e) => {
-                                            log::warn!("[GatewayWS] JSON parse error: {}", e);
+                                            tracing::warn!("[GatewayWS] JSON parse error: {}", e);
                                             continue;
                                         }
                                     };
                                     let msg_type = raw.get("type").and_then(|t| t.as_str()).unwrap_or("");
 
                                     match msg_type {
+                                        "companion_revoked" => {
+                                            let reason = raw.get("reason")
+                                                .and_then(|v| v.as_str()).unwrap_or("Companion revoked");
+                                            crate::connection::GatewayConnection::handle_revocation(reason);
+                                            alive = false;
+                                        }
+                                        "capabilities" => {
+                                            crate::capabilities::handle_gateway_message(&raw);
+                                        }
+                                        "event" => {
+                                            crate::push::handle_gateway_event(&raw);
+                                        }
+                                        "outbox.ack" => {
+                                            crate::outbox::handle_gateway_ack(&raw);
+                                        }
                                         "action_request" => {
+                                            remote_actions::handle_action_request(&raw);
+                                        }
+                                        "action_confirm" => {
+                                            let raw = match crate::e2e::unwrap_incoming(&raw) {
+                                                Ok(v) => v,
+                                                Err(e) => {
+                                                    tracing::warn!("[GatewayWS] action_confirm rejected: {}", e);
+                                                    continue;
+                                                }
+                                            };
                                             let request_id = raw.get("requestId")
-                                                .and_then(|v| v.as_str()).unwrap_or("").to_string();
-                                            let action = raw.get("action")
-                                                .and_then(|v| v.as_str()).unwrap_or("").to_string();
-                                            let params = raw.get("params")
-                                                .cloned().unwrap_or(serde_json::json!({}));
-
-                                            log::info!("[GatewayWS] >>> Action request: {} (id={})", action, request_id);
-
-                                            // Execute in a blocking thread so we don't stall the async loop
-                                            let action_clone = action.clone();
-                                            let tx_clone = tx.clone();
-                                            let req_id = request_id.clone();
-                                            tokio::task::spawn_blocking(move || {
-                                                // Desktop actions get raw params; others use ActionRequest
-                                                let result = if action_clone == "desktop" {
-                                                    local_actions::execute_desktop(&params)
-                                                } else {
-                                                    let action_req = ActionRequest {
-                                                        action: action_clone.clone(),
-                                                        path: params.get("path").and_then(|v| v.as_str()).map(String::from),
-                                                        command: params.get("command").and_then(|v| v.as_str()).map(String::from),
-                                                        content: params.get("content").and_then(|v| v.as_str()).map(String::from),
-                                                        process_name: params.get("process_name").and_then(|v| v.as_str()).map(String::from),
-                                                        app_name: params.get("app_name").and_then(|v| v.as_str()).map(String::from),
-                                                        cwd: params.get("cwd").and_then(|v| v.as_str()).map(String::from),
-                                                        confirmed: true,
-                                                    };
-                                                    local_actions::execute(&action_req)
-                                                };
-                                                log::info!("[GatewayWS] <<< Action result: {} success={} output_len={}",
-                                                    action_clone, result.success, result.output.len());
-
-                                                let response = serde_json::json!({
-                                                    "type": "action_result",
-                                                    "requestId": req_id,
-                                                    "success": result.success,
-                                                    "output": result.output,
-                                                });
-                                                if let Ok(json) = serde_json::to_string(&response) {
-                                                    if let Err(e) = tx_clone.send(json) {
-                                                        log::error!("[GatewayWS] Failed to queue response: {}", e);
-                                                    } else {
-                                                        log::info!("[GatewayWS] Response queued for {}", req_id);
-                                                    }
+                                                .and_then(|v| v.as_str()).unwrap_or("");
+                                            let approved = raw.get("approved")
+                                                .and_then(|v| v.as_bool()).unwrap_or(false);
+                                            let signature = raw.get("signature").and_then(|v| v.as_str());
+                                            if let Err(e) = remote_actions::handle_gateway_confirm(request_id, approved, signature) {
+                                                tracing::warn!("[GatewayWS] action_confirm: {}", e);
+                                            }
+                                        }
+                                        "result_page_request" => {
+                                            let raw = match crate::e2e::unwrap_incoming(&raw) {
+                                                Ok(v) => v,
+                                                Err(e) => {
+                                                    tracing::warn!("[GatewayWS] result_page_request rejected: {}", e);
+                                                    continue;
                                                 }
-                                            });
+                                            };
+                                            let request_id = raw.get("requestId")
+                                                .and_then(|v| v.as_str()).unwrap_or("").to_string();
+                                            let token = raw.get("continuation")
+                                                .and_then(|v| v.as_str()).unwrap_or("");
+                                            let response = match pagination::fetch_page(token) {
+                                                Ok(page) => serde_json::json!({
+                                                    "type": "result_page",
+                                                    "requestId": request_id,
+                                                    "success": true,
+                                                    "output": page.output,
+                                                    "offset": page.offset,
+                                                    "totalLen": page.total_len,
+                                                    "continuation": page.continuation,
+                                                }),
+                                                Err(e) => serde_json::json!({
+                                                    "type": "result_page",
+                                                    "requestId": request_id,
+                                                    "success": false,
+                                                    "output": e,
+                                                }),
+                                            };
+                                            let _ = tx.send(crate::e2e::wrap_outgoing(response).to_string());
                                         }
                                         "health.pong" => {
-                                            log::debug!("[GatewayWS] Keepalive pong received");
+                                            tracing::debug!("[GatewayWS] Keepalive pong received");
+                                            // Gateways without a manifest get the outbox here instead
+                                            if crate::capabilities::gateway().is_none() {
+                                                crate::outbox::flush();
+                                            }
                                         }
                                         _ => {
-                                            log::debug!("[GatewayWS] Received: {}", msg_type);
+                                            tracing::debug!("[GatewayWS] Received: {}", msg_type);
                                         }
                                     }
                                 }
                                 Some(Ok(Message::Ping(data))) => {
-                                    log::debug!("[GatewayWS] Ping frame received");
+                                    tracing::debug!("[GatewayWS] Ping frame received");
                                     let pong = serde_json::json!({"type":"pong"}).to_string();
                                     let _ = tx.send(pong);
                                     let _ = data; // auto-pong handled by tungstenite
                                 }
                                 Some(Ok(Message::Close(_))) => {
-                                    log::warn!("[GatewayWS] Server closed connection");
+                                    tracing::warn!("[GatewayWS] Server closed connection");
                                     alive = false;
                                 }
                                 Some(Err(e)) => {
-                                    log::error!("[GatewayWS] Read error: {}", e);
+                                    tracing::error!("[GatewayWS] Read error: {}", e);
                                     alive = false;
                                 }
                                 None => {
-                                    log::warn!("[GatewayWS] Stream ended");
+                                    tracing::warn!("[GatewayWS] Stream ended");
                                     alive = false;
                                 }
                                 _ => {}
                             }
                         }
                         _ = ping_interval.tick() => {
+                            if crate::idle::heartbeat_paused() {
+                                continue;
+                            }
                             let ping = serde_json::json!({
                                 "type": "health.ping",
                                 "id": "keepalive",
                             }).to_string();
                             if tx.send(ping).is_err() {
-                                log::warn!("[GatewayWS] Ping send failed — connection dead");
+                                tracing::warn!("[GatewayWS] Ping send failed — connection dead");
                                 alive = false;
                             } else {
-                                log::debug!("[GatewayWS] Keepalive ping sent");
+                                tracing::debug!("[GatewayWS] Keepalive ping sent");
                             }
                         }
                         _ = get_reconnect_notify().notified() => {
-                            log::info!("[GatewayWS] Reconnect signal received, closing current connection");
+                            tracing::info!("[GatewayWS] Reconnect signal received, closing current connection");
                             alive = false;
                         }
                     }
                 }
 
+                crate::connection::set_live_sender(None);
+                crate::capabilities::clear();
                 send_handle.abort();
-                log::warn!("[GatewayWS] Disconnected, reconnecting in 5s...");
+                tracing::warn!("[GatewayWS] Disconnected, reconnecting in 5s...");
+            }
+            Err(tokio_tungstenite::tungstenite::Error::Http(resp))
+                if resp.status() == 403
+                    && resp.body().as_deref()
+                        .and_then(|b| serde_json::from_slice::<serde_json::Value>(b).ok())
+                        .is_some_and(|b| crate::connection::revocation_reason(&b).is_some()) =>
+            {
+                crate::connection::GatewayConnection::handle_revocation("Handshake refused");
+            }
+            Err(tokio_tungstenite::tungstenite::Error::Http(resp)) if resp.status() == 401 => {
+                // Token expired — refresh and retry; the next iteration reloads the new creds
+                tracing::warn!("[GatewayWS] Handshake rejected (401), refreshing session");
+                if let Err(e) = crate::connection::GatewayConnection::refresh_session(&creds).await {
+                    tracing::error!("[GatewayWS] Session refresh failed: {}", e);
+                    if e != crate::connection::REVOKED_MESSAGE {
+                        crate::events::emit("reauth-required", serde_json::json!({ "reason": e }));
+                        tokio::time::sleep(std::time::Duration::from_secs(30)).await;
+                    }
+                }
             }
             Err(e) => {
-                log::error!("[GatewayWS] Connection failed: {}, retry in 5s...", e);
+                tracing::error!("[GatewayWS] Connection failed: {}, retry in 5s...", e);
             }
         }
 
@@ -607,6 +1464,12 @@ pub fn get_system_info() -> ActionResult {
         process_name: None,
         app_name: None,
         cwd: None,
+        file_id: None,
+        query: None,
+        ocr: false,
+        topic: None,
+        retain: false,
+        params: serde_json::Value::Null,
         confirmed: false,
     })
 }
@@ -628,79 +1491,549 @@ pub struct VoiceState(pub Mutex<VoiceEngine>);
 #[tauri::command]
 pub fn wake_word_configure(
     state: State<'_, WakeWordState>,
+    app_handle: tauri::AppHandle,
     access_key: String,
     sensitivity: f32,
     keyword_path: Option<String>,
-) -> Result<String, String> {
-    let mut engine = state.0.lock().map_err(|e| e.to_string())?;
-    engine.configure(access_key, sensitivity);
-    if let Some(kw) = keyword_path {
-        engine.set_keyword_path(kw);
+) -> Result<String, UserError> {
+    {
+        let mut engine = state.0.lock().map_err(|e| e.to_string())?;
+        engine.configure(access_key, sensitivity);
+        if let Some(kw) = keyword_path {
+            engine.set_keyword_path(kw);
+        }
     }
+    crate::settings::update(&app_handle, |s| s.wake_word.sensitivity = sensitivity.clamp(0.0, 1.0))?;
     Ok("Wake word configured".into())
 }
 
-/// Start wake word detection
+/// Start wake word detection; emits `wake-word-toggled`
 #[tauri::command]
 pub fn wake_word_start(
     state: State<'_, WakeWordState>,
     app_handle: tauri::AppHandle,
-) -> Result<String, String> {
+) -> Result<String, UserError> {
     let engine = state.0.lock().map_err(|e| e.to_string())?;
     engine.start(app_handle)?;
+    crate::events::emit("wake-word-toggled", serde_json::json!({ "enabled": true }));
     Ok("Wake word detection started".into())
 }
 
-/// Stop wake word detection
+/// Stop wake word detection; emits `wake-word-toggled`
 #[tauri::command]
-pub fn wake_word_stop(state: State<'_, WakeWordState>) -> Result<String, String> {
+pub fn wake_word_stop(state: State<'_, WakeWordState>) -> Result<String, UserError> {
     let engine = state.0.lock().map_err(|e| e.to_string())?;
     engine.stop();
+    crate::events::emit("wake-word-toggled", serde_json::json!({ "enabled": false }));
     Ok("Wake word detection stopped".into())
 }
 
 /// Get wake word engine status
 #[tauri::command]
-pub fn wake_word_status(state: State<'_, WakeWordState>) -> Result<WakeWordStatus, String> {
+pub fn wake_word_status(state: State<'_, WakeWordState>) -> Result<WakeWordStatus, UserError> {
     let engine = state.0.lock().map_err(|e| e.to_string())?;
     Ok(engine.status())
 }
 
+/// Turn wake-word listening on or off; emits `wake-word-toggled`.
+/// Shared by the tray menu and the global hotkey.
+pub fn toggle_wake_word(app: &tauri::AppHandle) -> Result<bool, String> {
+    use tauri::Manager;
+    let state = app.state::<WakeWordState>();
+    let engine = state.0.lock().map_err(|e| e.to_string())?;
+    let enabled = if engine.is_running() {
+        engine.stop();
+        false
+    } else {
+        engine.start(app.clone())?;
+        true
+    };
+    crate::events::emit("wake-word-toggled", serde_json::json!({ "enabled": enabled }));
+    Ok(enabled)
+}
+
+/// Pause wake-word listening on behalf of a policy; emits
+/// `listening-suspended` (and `wake-word-toggled` if it was on)
+pub fn suspend_listening(app: &tauri::AppHandle, source: &'static str, reason: String) {
+    use tauri::Manager;
+    let state = app.state::<WakeWordState>();
+    let Ok(engine) = state.0.lock() else {
+        return;
+    };
+    tracing::info!("[Listening] Suspended by {}: {}", source, reason);
+    if engine.suspend(source, reason) {
+        crate::events::emit("wake-word-toggled", serde_json::json!({ "enabled": false }));
+    }
+    crate::events::emit("listening-suspended", serde_json::json!({ "reason": wake_word::suspension() }));
+}
+
+/// Lift a policy's pause, restarting listening if it was on before
+pub fn resume_listening(app: &tauri::AppHandle, source: &'static str) {
+    use tauri::Manager;
+    let state = app.state::<WakeWordState>();
+    let Ok(engine) = state.0.lock() else {
+        return;
+    };
+    match engine.resume(source, app.clone()) {
+        Ok(true) => crate::events::emit("wake-word-toggled", serde_json::json!({ "enabled": true })),
+        Ok(false) => {}
+        Err(e) => tracing::warn!("[Listening] Cannot resume after {}: {}", source, e),
+    }
+    tracing::info!("[Listening] {} lifted its suspension", source);
+    crate::events::emit("listening-suspended", serde_json::json!({ "reason": wake_word::suspension() }));
+}
+
+/// Mute or unmute the microphone: stops wake-word listening and any
+/// recording in progress; emits `mic-muted`
+pub fn apply_mic_mute(app: &tauri::AppHandle, muted: bool) {
+    use tauri::Manager;
+    voice::set_mic_muted(muted);
+    if muted {
+        if let Ok(engine) = app.state::<WakeWordState>().0.try_lock() {
+            if engine.is_running() {
+                engine.stop();
+                crate::events::emit("wake-word-toggled", serde_json::json!({ "enabled": false }));
+            }
+        }
+        crate::hotkeys::stop_recording();
+    }
+    tracing::info!("Microphone {}", if muted { "muted" } else { "unmuted" });
+    crate::events::emit("mic-muted", serde_json::json!({ "muted": muted }));
+    crate::status::publish();
+}
+
+/// Mute or unmute the microphone
+#[tauri::command]
+pub fn set_mic_muted(app_handle: tauri::AppHandle, muted: bool) {
+    apply_mic_mute(&app_handle, muted);
+}
+
 // ─── Voice Commands ──────────────────────────────────
 
 /// Record audio from microphone (stops on silence or manual stop)
 #[tauri::command]
-pub fn voice_record(state: State<'_, VoiceState>) -> Result<CapturedAudio, String> {
+pub fn voice_record(state: State<'_, VoiceState>) -> Result<CapturedAudio, UserError> {
     let engine = state.0.lock().map_err(|e| e.to_string())?;
-    engine.record()
+    engine.record().map_err(UserError::from)
+}
+
+/// Microphone, screen recording and accessibility permission states
+#[tauri::command]
+pub async fn get_permissions() -> Result<Vec<crate::permissions::PermissionStatus>, UserError> {
+    tauri::async_runtime::spawn_blocking(crate::permissions::all)
+        .await
+        .map_err(|e| UserError::from(e.to_string()))
+}
+
+/// Prompt for a permission, or open its settings page; returns the new state
+#[tauri::command]
+pub async fn request_permission(
+    kind: crate::permissions::PermissionKind,
+) -> Result<crate::permissions::PermissionStatus, UserError> {
+    tauri::async_runtime::spawn_blocking(move || crate::permissions::request(kind))
+        .await
+        .map_err(|e| UserError::from(e.to_string()))
+}
+
+/// Saved audio profiles
+#[tauri::command]
+pub fn list_audio_profiles() -> Vec<crate::profiles::AudioProfile> {
+    crate::profiles::list()
+}
+
+/// Create or replace an audio profile; returns all profiles
+#[tauri::command]
+pub fn save_audio_profile(
+    app_handle: tauri::AppHandle,
+    profile: crate::profiles::AudioProfile,
+) -> Result<Vec<crate::profiles::AudioProfile>, UserError> {
+    crate::profiles::save(&app_handle, profile).map_err(UserError::from)
+}
+
+/// Delete an audio profile; returns the remaining ones
+#[tauri::command]
+pub fn delete_audio_profile(
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<Vec<crate::profiles::AudioProfile>, UserError> {
+    crate::profiles::delete(&app_handle, &name).map_err(UserError::from)
+}
+
+/// Who holds the microphone, whether speech is playing, and the last
+/// arbitration decisions
+#[tauri::command]
+pub fn mic_arbitration() -> crate::mic_arbiter::ArbitrationState {
+    crate::mic_arbiter::state()
+}
+
+/// Capture settings saved per input device
+#[tauri::command]
+pub fn list_input_profiles() -> Vec<crate::input_profiles::InputProfile> {
+    crate::input_profiles::list()
+}
+
+/// Create or replace the profile of an input device; returns all profiles
+#[tauri::command]
+pub fn save_input_profile(
+    app_handle: tauri::AppHandle,
+    profile: crate::input_profiles::InputProfile,
+) -> Result<Vec<crate::input_profiles::InputProfile>, UserError> {
+    crate::input_profiles::save(&app_handle, profile).map_err(UserError::from)
+}
+
+/// Delete the profile of an input device; returns the remaining ones
+#[tauri::command]
+pub fn delete_input_profile(
+    app_handle: tauri::AppHandle,
+    device: String,
+) -> Result<Vec<crate::input_profiles::InputProfile>, UserError> {
+    crate::input_profiles::delete(&app_handle, &device).map_err(UserError::from)
+}
+
+/// Phrases mapped to local actions
+#[tauri::command]
+pub fn list_voice_shortcuts() -> Vec<crate::shortcuts::VoiceShortcut> {
+    crate::shortcuts::list()
+}
+
+/// Create or replace a voice shortcut; returns all shortcuts
+#[tauri::command]
+pub fn save_voice_shortcut(
+    app_handle: tauri::AppHandle,
+    shortcut: crate::shortcuts::VoiceShortcut,
+) -> Result<Vec<crate::shortcuts::VoiceShortcut>, UserError> {
+    crate::shortcuts::save(&app_handle, shortcut).map_err(UserError::from)
+}
+
+/// Delete the voice shortcut for `phrase`; returns the remaining ones
+#[tauri::command]
+pub fn delete_voice_shortcut(
+    app_handle: tauri::AppHandle,
+    phrase: String,
+) -> Result<Vec<crate::shortcuts::VoiceShortcut>, UserError> {
+    crate::shortcuts::delete(&app_handle, &phrase).map_err(UserError::from)
+}
+
+/// User profiles on this machine and the active one
+#[tauri::command]
+pub fn list_profiles() -> crate::users::UserProfiles {
+    crate::users::list()
+}
+
+/// Switch to another user profile (created if new); restarts the companion
+#[tauri::command]
+pub fn switch_profile(app_handle: tauri::AppHandle, name: String) -> Result<(), UserError> {
+    crate::users::switch(&app_handle, &name).map_err(UserError::from)
+}
+
+/// Delete an inactive user profile with its pairing and data
+#[tauri::command]
+pub fn delete_profile(name: String) -> Result<crate::users::UserProfiles, UserError> {
+    crate::users::delete(&name).map_err(UserError::from)
+}
+
+/// Switch devices, thresholds and wake sensitivity to a saved profile
+#[tauri::command]
+pub fn switch_audio_profile(
+    app_handle: tauri::AppHandle,
+    name: String,
+) -> Result<crate::profiles::AudioProfile, UserError> {
+    crate::profiles::switch(&app_handle, &name).map_err(UserError::from)
+}
+
+/// Record three seconds, play them back on `output_device` (the configured
+/// output when omitted) and return the measured input level
+#[tauri::command]
+pub async fn voice_mic_test(output_device: Option<String>) -> Result<voice::MicTestResult, UserError> {
+    let threshold = crate::settings::get().voice.silence_threshold;
+    tauri::async_runtime::spawn_blocking(move || voice::mic_test(output_device.as_deref(), threshold))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(UserError::from)
+}
+
+/// Time each local stage of the voice pipeline (downmix, voice gate,
+/// resample, WAV encoding) on generated audio in the input device's native
+/// format, or the given one
+#[tauri::command]
+pub async fn voice_pipeline_bench(
+    native_rate: Option<u32>,
+    channels: Option<usize>,
+    seconds: Option<f32>,
+    iterations: Option<u32>,
+) -> Result<forgeai_companion_core::audio::PipelineTimings, UserError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let (rate, ch) = voice::native_input_format().unwrap_or((48000, 2));
+        forgeai_companion_core::audio::bench_pipeline(
+            native_rate.unwrap_or(rate),
+            channels.unwrap_or(ch).clamp(1, 8),
+            seconds.unwrap_or(5.0).clamp(0.1, 60.0),
+            iterations.unwrap_or(10).min(1000),
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(UserError::from)
+}
+
+/// Onboarding checklist: microphone, speaker (plays a test tone unless
+/// `play_tone` is false), Gateway and wake word model
+#[tauri::command]
+pub async fn run_setup_checks(
+    state: State<'_, WakeWordState>,
+    play_tone: Option<bool>,
+) -> Result<crate::setup::SetupReport, UserError> {
+    let wake_model = state.0.lock().map_err(|e| e.to_string())?.check_model();
+    Ok(crate::setup::run(wake_model, play_tone.unwrap_or(true)).await)
 }
 
 /// Stop an ongoing recording
 #[tauri::command]
-pub fn voice_stop(state: State<'_, VoiceState>) -> Result<String, String> {
+pub fn voice_stop(state: State<'_, VoiceState>) -> Result<String, UserError> {
     let engine = state.0.lock().map_err(|e| e.to_string())?;
     engine.stop_recording();
     Ok("Recording stopped".into())
 }
 
+/// Read back log entries for the log viewer (newest last)
+#[tauri::command]
+pub fn query_logs(
+    level: Option<String>,
+    module: Option<String>,
+    since: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<crate::logging::LogEntry>, UserError> {
+    crate::logging::query(&crate::logging::LogQuery { level, module, since, limit }).map_err(UserError::from)
+}
+
+/// Language used for user-facing messages (`en`, `pt`, `es`)
+#[tauri::command]
+pub fn get_language() -> &'static str {
+    crate::i18n::language()
+}
+
+/// Latency and usage metrics since startup (or the last reset)
+#[tauri::command]
+pub fn get_metrics() -> crate::metrics::MetricsSnapshot {
+    crate::metrics::snapshot()
+}
+
+/// Clear all metrics
+#[tauri::command]
+pub fn reset_metrics() {
+    crate::metrics::reset();
+}
+
+/// Write a diagnostics zip for bug reports (default: Downloads); returns its path
+#[tauri::command]
+pub fn export_diagnostics(path: Option<String>) -> Result<String, UserError> {
+    crate::diagnostics::export(path).map_err(UserError::from)
+}
+
+/// Crash reports saved on this machine, newest first
+#[tauri::command]
+pub fn list_crash_reports() -> Vec<crate::crash::CrashReport> {
+    crate::crash::list()
+}
+
+/// Delete all saved crash reports
+#[tauri::command]
+pub fn clear_crash_reports() -> Result<(), UserError> {
+    crate::crash::clear().map_err(UserError::from)
+}
+
+/// Ask the Gateway for a newer companion release (with changelog)
+#[tauri::command]
+pub async fn check_for_update() -> Result<Option<crate::update::UpdateInfo>, UserError> {
+    let creds = crate::connection::GatewayConnection::load_credentials()
+        .ok_or(CompanionError::NotPaired)?;
+    crate::update::check(&creds).await.map_err(UserError::from)
+}
+
+/// Download, verify and install the update found by `check_for_update`
+#[tauri::command]
+pub async fn install_update(app_handle: tauri::AppHandle) -> Result<String, UserError> {
+    let creds = crate::connection::GatewayConnection::load_credentials()
+        .ok_or(CompanionError::NotPaired)?;
+    if crate::update::install(&creds).await? {
+        // The installer replaces the running binary
+        app_handle.exit(0);
+        return Ok("Installing update — the companion will restart".into());
+    }
+    Ok("Update downloaded — follow the installer to finish".into())
+}
+
+/// Get the login-item registration
+#[tauri::command]
+pub fn get_autostart() -> crate::autostart::AutostartStatus {
+    crate::autostart::status()
+}
+
+/// Start the companion at login (optionally hidden in the tray, the default)
+#[tauri::command]
+pub fn set_autostart(enabled: bool, minimized: Option<bool>) -> Result<crate::autostart::AutostartStatus, UserError> {
+    if enabled {
+        crate::autostart::enable(minimized.unwrap_or(true))?;
+    } else {
+        crate::autostart::disable()?;
+    }
+    Ok(crate::autostart::status())
+}
+
+/// Get the global hotkey bindings
+#[tauri::command]
+pub fn get_hotkeys() -> crate::hotkeys::HotkeyConfig {
+    crate::hotkeys::config()
+}
+
+/// Change the global hotkey bindings (rejected if invalid or already taken)
+#[tauri::command]
+pub fn set_hotkeys(app_handle: tauri::AppHandle, config: crate::hotkeys::HotkeyConfig) -> Result<String, UserError> {
+    crate::settings::update(&app_handle, |s| s.hotkeys = config)?;
+    Ok("Hotkeys updated".into())
+}
+
+/// Get all companion settings
+#[tauri::command]
+pub fn settings_get() -> crate::settings::Settings {
+    crate::settings::get()
+}
+
+/// Validate, apply and persist all companion settings
+#[tauri::command]
+pub fn settings_set(
+    app_handle: tauri::AppHandle,
+    settings: crate::settings::Settings,
+) -> Result<crate::settings::Settings, UserError> {
+    crate::settings::set(&app_handle, settings).map_err(UserError::from)
+}
+
+/// Restore the default settings
+#[tauri::command]
+pub fn settings_reset(app_handle: tauri::AppHandle) -> Result<crate::settings::Settings, UserError> {
+    crate::settings::reset(&app_handle).map_err(UserError::from)
+}
+
+/// The Gateway's TTS voices
+#[tauri::command]
+pub async fn list_tts_voices() -> Result<Vec<crate::voice::TtsVoice>, UserError> {
+    let creds = crate::connection::GatewayConnection::load_credentials()
+        .ok_or(CompanionError::NotPaired)?;
+    crate::voice::list_tts_voices(&creds).await.map_err(UserError::from)
+}
+
+/// Use `voice_id` for all speech from now on (None: the Gateway's default)
+#[tauri::command]
+pub fn select_tts_voice(app_handle: tauri::AppHandle, voice_id: Option<String>) -> Result<String, UserError> {
+    let voice_id = voice_id.filter(|v| !v.trim().is_empty());
+    crate::settings::update(&app_handle, |s| s.tts_voice = voice_id)?;
+    Ok("TTS voice updated".into())
+}
+
+/// Preferred output devices, the one playback uses and what is connected
+#[tauri::command]
+pub async fn output_priority() -> Result<crate::output_priority::OutputSelection, UserError> {
+    tauri::async_runtime::spawn_blocking(crate::output_priority::current)
+        .await
+        .map_err(|e| UserError::from(format!("Device query failed: {}", e)))
+}
+
+/// Order preferred output devices, highest priority first
+#[tauri::command]
+pub fn set_output_priority(app_handle: tauri::AppHandle, devices: Vec<String>) -> Result<String, UserError> {
+    crate::settings::update(&app_handle, |s| s.output_priority = devices)?;
+    Ok(crate::output_priority::selected()
+        .map(|d| format!("Playing on {}", d))
+        .unwrap_or_else(|| "No preferred output device connected".into()))
+}
+
+/// Speech providers the Gateway offers and the ones selected
+#[tauri::command]
+pub fn voice_providers() -> serde_json::Value {
+    let offered = crate::capabilities::gateway().unwrap_or_default();
+    serde_json::json!({
+        "stt": offered.stt_providers,
+        "tts": offered.tts_providers,
+        "selected": crate::voice::providers(),
+    })
+}
+
+/// Ask the Gateway for these STT/TTS providers (None: its default)
+#[tauri::command]
+pub fn select_voice_providers(
+    app_handle: tauri::AppHandle,
+    stt: Option<String>,
+    tts: Option<String>,
+) -> Result<String, UserError> {
+    let providers = crate::voice::VoiceProviders {
+        stt: stt.filter(|p| !p.trim().is_empty()),
+        tts: tts.filter(|p| !p.trim().is_empty()),
+    };
+    if let Some(gw) = crate::capabilities::gateway() {
+        for (chosen, offered) in [(&providers.stt, &gw.stt_providers), (&providers.tts, &gw.tts_providers)] {
+            if let Some(name) = chosen.as_ref().filter(|n| !offered.is_empty() && !offered.contains(n)) {
+                return Err(format!("The Gateway does not offer the speech provider '{}'", name).into());
+            }
+        }
+    }
+    crate::settings::update(&app_handle, |s| s.voice_providers = providers)?;
+    Ok("Speech providers updated".into())
+}
+
+/// Play a sample of a TTS voice without selecting it
+#[tauri::command]
+pub async fn preview_tts_voice(voice_id: String) -> Result<String, UserError> {
+    let creds = crate::connection::GatewayConnection::load_credentials()
+        .ok_or(CompanionError::NotPaired)?;
+    crate::voice::preview_tts_voice(&creds, &voice_id).await?;
+    Ok("Sample played".into())
+}
+
+/// Mute speech and earcons until turned off again
+#[tauri::command]
+pub fn set_do_not_disturb(app_handle: tauri::AppHandle, enabled: bool) -> Result<String, UserError> {
+    crate::settings::update(&app_handle, |s| s.quiet_hours.do_not_disturb = enabled)?;
+    Ok(if enabled { "Do not disturb on" } else { "Do not disturb off" }.into())
+}
+
+/// Whether quiet hours or do-not-disturb mute output right now
+#[tauri::command]
+pub fn quiet_hours_active() -> bool {
+    crate::quiet_hours::active()
+}
+
 /// Send text to Gateway TTS and play the response audio
 #[tauri::command]
-pub async fn voice_speak(text: String) -> Result<String, String> {
+pub async fn voice_speak(text: String) -> Result<String, UserError> {
     let creds = crate::connection::GatewayConnection::load_credentials()
-        .ok_or("Not connected — pair first")?;
+        .ok_or(CompanionError::NotPaired)?;
 
     let engine = VoiceEngine::new();
-    engine
-        .speak(&creds.gateway_url, &creds.companion_id, &text)
-        .await?;
+    engine.speak(&creds, &text).await?;
 
     Ok("Speech played".into())
 }
 
+/// The screen context the next chat or voice request will carry, if any
+#[tauri::command]
+pub async fn get_screen_context() -> Option<crate::screen_context::ScreenContext> {
+    crate::screen_context::for_request().await
+}
+
+/// Dim the screens and let the user drag out a region, e.g. to pick what a
+/// screenshot captures; `None` when they press Esc
+#[tauri::command]
+pub async fn select_screen_region() -> Result<Option<crate::local_actions::ScreenRegion>, UserError> {
+    tauri::async_runtime::spawn_blocking(crate::local_actions::select_screen_region)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(UserError::from)
+}
+
 /// Read a screenshot and return it as a base64 data URL.
 /// Strategy: try local file first (fast), then fall back to Gateway HTTP (remote VPS).
 #[tauri::command]
-pub async fn read_screenshot(path: String, gateway_url: Option<String>) -> Result<String, String> {
+pub async fn read_screenshot(path: String, gateway_url: Option<String>) -> Result<String, UserError> {
     let ext = path.rsplit('.').next().unwrap_or("png").to_lowercase();
     let mime = match ext.as_str() {
         "jpg" | "jpeg" => "image/jpeg",
@@ -711,7 +2044,7 @@ pub async fn read_screenshot(path: String, gateway_url: Option<String>) -> Resul
 
     // 1) Try local file first (works when Gateway runs on same machine)
     if let Ok(data) = tokio::fs::read(&path).await {
-        log::info!("Screenshot loaded locally: {}", path);
+        tracing::info!("Screenshot loaded locally: {}", path);
         let b64 = base64::engine::general_purpose::STANDARD.encode(&data);
         return Ok(format!("data:{};base64,{}", mime, b64));
     }
@@ -722,54 +2055,47 @@ pub async fn read_screenshot(path: String, gateway_url: Option<String>) -> Resul
         if let Some(idx) = normalized.find(".forgeai/") {
             let rel_path = &normalized[idx + 9..]; // after ".forgeai/"
             let url = format!("{}/api/files/{}", gw_url.trim_end_matches('/'), rel_path);
-            log::info!("Screenshot not local, fetching from Gateway: {}", url);
+            tracing::info!("Screenshot not local, fetching from Gateway: {}", url);
 
-            let client = reqwest::Client::new();
-            let mut req = client
+            let gw = crate::http::gateway(&gw_url)?;
+            let build = || gw.http()
                 .get(&url)
                 .timeout(std::time::Duration::from_secs(15));
-            // Try to add auth if credentials are available
-            if let Some(creds) = crate::connection::GatewayConnection::load_credentials() {
-                if let Some(ref token) = creds.auth_token {
-                    req = req.header("Cookie", format!("forgeai_session={}", token));
-                }
-            }
-            let resp = req
-                .send()
-                .await
-                .map_err(|e| format!("Gateway fetch failed: {}", e))?;
+            // Authenticate (with session refresh) if credentials are available
+            let resp = match crate::connection::GatewayConnection::load_credentials() {
+                Some(creds) => crate::connection::GatewayConnection::send_authenticated(&creds, build).await?,
+                None => crate::netstats::send(build())
+                    .await
+                    .map_err(|e| format!("Gateway fetch failed: {}", e))?,
+            };
 
             if resp.status().is_success() {
                 let bytes = resp.bytes().await.map_err(|e| format!("Read bytes failed: {}", e))?;
                 let b64 = base64::engine::general_purpose::STANDARD.encode(&bytes);
                 return Ok(format!("data:{};base64,{}", mime, b64));
             } else {
-                return Err(format!("Gateway returned {}: {}", resp.status(), url));
+                return Err(format!("Gateway returned {}: {}", resp.status(), url).into());
             }
         }
     }
 
-    Err(format!("Screenshot not found locally or via Gateway: {}", path))
+    Err(format!("Screenshot not found locally or via Gateway: {}", path).into())
 }
 
 /// List chat sessions from Gateway (companion-only)
 #[tauri::command]
-pub async fn list_sessions() -> Result<serde_json::Value, String> {
+pub async fn list_sessions() -> Result<serde_json::Value, UserError> {
     let creds = crate::connection::GatewayConnection::load_credentials()
-        .ok_or("Not connected — pair first")?;
+        .ok_or(CompanionError::NotPaired)?;
 
-    let url = format!("{}/api/chat/sessions", creds.gateway_url);
-    let client = reqwest::Client::new();
-    let req = client
-        .get(&url)
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let build = || gw
+        .get("/api/chat/sessions")
         .timeout(std::time::Duration::from_secs(10));
-    let resp = with_auth(req, &creds)
-        .send()
-        .await
-        .map_err(|e| format!("Gateway request failed: {}", e))?;
+    let resp = crate::connection::GatewayConnection::send_authenticated(&creds, build).await?;
 
     if !resp.status().is_success() {
-        return Err(format!("Gateway HTTP {}", resp.status()));
+        return Err(format!("Gateway HTTP {}", resp.status()).into());
     }
 
     let body: serde_json::Value = resp.json().await
@@ -791,55 +2117,107 @@ pub async fn list_sessions() -> Result<serde_json::Value, String> {
     }
 }
 
+/// Locally stored voice turns, newest first; pass the `id` of the last
+/// turn of the previous page as `before`
+#[tauri::command]
+pub fn history_list(before: Option<i64>, limit: Option<u32>) -> Result<Vec<crate::memory::VoiceTurn>, UserError> {
+    crate::memory::turns(before, limit.unwrap_or(50)).map_err(UserError::from)
+}
+
+/// Search local voice turns by text, optionally within `[since, until)`
+/// (RFC 3339)
+#[tauri::command]
+pub fn history_search(
+    query: String,
+    since: Option<String>,
+    until: Option<String>,
+    limit: Option<u32>,
+    offset: Option<u32>,
+) -> Result<Vec<crate::memory::VoiceTurn>, UserError> {
+    crate::memory::search_turns(&query, since.as_deref(), until.as_deref(), limit.unwrap_or(50), offset.unwrap_or(0))
+        .map_err(UserError::from)
+}
+
+/// Schedule a reminder for `due_at` (RFC 3339); it fires locally even
+/// when the Gateway is offline
+#[tauri::command]
+pub fn set_reminder(text: String, due_at: String) -> Result<crate::reminders::Reminder, UserError> {
+    crate::reminders::add(&text, &due_at).map_err(UserError::from)
+}
+
+/// Pending reminders, soonest first
+#[tauri::command]
+pub fn list_reminders() -> Vec<crate::reminders::Reminder> {
+    crate::reminders::list()
+}
+
+/// Cancel a pending reminder; false when it already fired or never existed
+#[tauri::command]
+pub fn cancel_reminder(id: String) -> Result<bool, UserError> {
+    crate::reminders::cancel(&id).map_err(UserError::from)
+}
+
+/// Start a named countdown of `seconds`, or an alarm at `at` (RFC 3339)
+#[tauri::command]
+pub fn timer_start(name: Option<String>, seconds: Option<u64>, at: Option<String>) -> Result<crate::timers::Timer, UserError> {
+    crate::timers::start(name.as_deref(), seconds, at.as_deref()).map_err(UserError::from)
+}
+
+/// Running timers and alarms, soonest first
+#[tauri::command]
+pub fn timer_list() -> Vec<crate::timers::Timer> {
+    crate::timers::list()
+}
+
+/// Cancel a timer or alarm by id or name
+#[tauri::command]
+pub fn timer_cancel(id: String) -> bool {
+    crate::timers::cancel(&id)
+}
+
 /// Get session history from Gateway
 #[tauri::command]
-pub async fn get_session_history(session_id: String) -> Result<serde_json::Value, String> {
+pub async fn get_session_history(session_id: String) -> Result<serde_json::Value, UserError> {
     let creds = crate::connection::GatewayConnection::load_credentials()
-        .ok_or("Not connected — pair first")?;
+        .ok_or(CompanionError::NotPaired)?;
 
-    let url = format!("{}/api/chat/history/{}", creds.gateway_url, session_id);
-    let client = reqwest::Client::new();
-    let req = client
-        .get(&url)
+    let path = format!("/api/chat/history/{}", session_id);
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let build = || gw
+        .get(&path)
         .timeout(std::time::Duration::from_secs(10));
-    let resp = with_auth(req, &creds)
-        .send()
-        .await
-        .map_err(|e| format!("Gateway request failed: {}", e))?;
+    let resp = crate::connection::GatewayConnection::send_authenticated(&creds, build).await?;
 
     if !resp.status().is_success() {
-        return Err(format!("Gateway HTTP {}", resp.status()));
+        return Err(format!("Gateway HTTP {}", resp.status()).into());
     }
 
-    resp.json().await.map_err(|e| format!("Invalid response: {}", e))
+    resp.json().await.map_err(|e| UserError::from(format!("Invalid response: {}", e)))
 }
 
 /// Delete a session from Gateway
 #[tauri::command]
-pub async fn delete_session(session_id: String) -> Result<serde_json::Value, String> {
+pub async fn delete_session(session_id: String) -> Result<serde_json::Value, UserError> {
     let creds = crate::connection::GatewayConnection::load_credentials()
-        .ok_or("Not connected — pair first")?;
+        .ok_or(CompanionError::NotPaired)?;
 
-    let url = format!("{}/api/chat/sessions/{}", creds.gateway_url, session_id);
-    let client = reqwest::Client::new();
-    let req = client
-        .delete(&url)
+    let path = format!("/api/chat/sessions/{}", session_id);
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let build = || gw
+        .delete(&path)
         .timeout(std::time::Duration::from_secs(10));
-    let resp = with_auth(req, &creds)
-        .send()
-        .await
-        .map_err(|e| format!("Gateway request failed: {}", e))?;
+    let resp = crate::connection::GatewayConnection::send_authenticated(&creds, build).await?;
 
     if !resp.status().is_success() {
-        return Err(format!("Gateway HTTP {}", resp.status()));
+        return Err(format!("Gateway HTTP {}", resp.status()).into());
     }
 
-    resp.json().await.map_err(|e| format!("Invalid response: {}", e))
+    resp.json().await.map_err(|e| UserError::from(format!("Invalid response: {}", e)))
 }
 
 /// List available audio input/output devices
 #[tauri::command]
-pub fn list_audio_devices() -> Result<serde_json::Value, String> {
+pub fn list_audio_devices() -> Result<serde_json::Value, UserError> {
     let inputs = wake_word::list_audio_devices();
     let outputs = voice::list_output_devices();
     Ok(serde_json::json!({
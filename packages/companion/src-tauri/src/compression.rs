@@ -0,0 +1,252 @@
+//! # Payload Compression
+//!
+//! Large uploads (voice audio, file transfer chunks) and large action
+//! results (file contents, command output) are compressed with gzip or zstd.
+//!
+//! - HTTP uploads carry `Content-Encoding`. If the Gateway answers
+//!   `415 Unsupported Media Type`, compression is turned off for the rest
+//!   of the session and the request is resent uncompressed.
+//! - HTTP downloads are decompressed transparently by reqwest
+//!   (`Accept-Encoding: gzip, zstd`).
+//! - Results on the live channel are only compressed when the request
+//!   listed the encoding in `acceptEncoding` (or the Gateway's capability
+//!   manifest does); the output is then base64 and `outputEncoding` names
+//!   the codec.
+//!
+//! The settings are saved with the other app settings (`compression` in
+//! `settings.json`, see `settings`) and applied at startup.
+
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+static CONFIG: OnceLock<Mutex<CompressionConfig>> = OnceLock::new();
+/// Set once the Gateway rejected a compressed upload
+static UPLOAD_UNSUPPORTED: AtomicBool = AtomicBool::new(false);
+static BYTES_BEFORE: AtomicU64 = AtomicU64::new(0);
+static BYTES_AFTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Codec {
+    Gzip,
+    Zstd,
+}
+
+impl Codec {
+    pub fn name(self) -> &'static str {
+        match self {
+            Codec::Gzip => "gzip",
+            Codec::Zstd => "zstd",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompressionConfig {
+    pub enabled: bool,
+    pub codec: Codec,
+    /// Payloads smaller than this are sent as-is
+    pub min_size: usize,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            codec: Codec::Gzip,
+            min_size: 8 * 1024,
+        }
+    }
+}
+
+/// Bytes before/after compression since startup
+#[derive(Debug, Clone, Serialize)]
+pub struct CompressionStats {
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub upload_supported: bool,
+}
+
+fn state() -> &'static Mutex<CompressionConfig> {
+    CONFIG.get_or_init(|| Mutex::new(CompressionConfig::default()))
+}
+
+pub fn config() -> CompressionConfig {
+    state().lock().map(|c| c.clone()).unwrap_or_default()
+}
+
+pub fn set_config(config: CompressionConfig) {
+    if let Ok(mut c) = state().lock() {
+        *c = config;
+    }
+    // Give the Gateway another chance after a settings change
+    UPLOAD_UNSUPPORTED.store(false, Ordering::Relaxed);
+}
+
+pub fn stats() -> CompressionStats {
+    CompressionStats {
+        bytes_before: BYTES_BEFORE.load(Ordering::Relaxed),
+        bytes_after: BYTES_AFTER.load(Ordering::Relaxed),
+        upload_supported: !UPLOAD_UNSUPPORTED.load(Ordering::Relaxed),
+    }
+}
+
+/// Compress `data` with `codec`
+pub fn compress(codec: Codec, data: &[u8]) -> Result<Vec<u8>, String> {
+    match codec {
+        Codec::Gzip => {
+            let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+            encoder.write_all(data).map_err(|e| format!("gzip error: {}", e))?;
+            encoder.finish().map_err(|e| format!("gzip error: {}", e))
+        }
+        Codec::Zstd => zstd::encode_all(data, 3).map_err(|e| format!("zstd error: {}", e)),
+    }
+}
+
+/// Compress if enabled, large enough, and actually smaller
+fn maybe_compress(codec: Codec, data: &[u8]) -> Option<Vec<u8>> {
+    let cfg = config();
+    if !cfg.enabled || data.len() < cfg.min_size {
+        return None;
+    }
+    let packed = compress(codec, data).ok()?;
+    if packed.len() >= data.len() {
+        return None;
+    }
+    BYTES_BEFORE.fetch_add(data.len() as u64, Ordering::Relaxed);
+    BYTES_AFTER.fetch_add(packed.len() as u64, Ordering::Relaxed);
+    Some(packed)
+}
+
+/// Pick a codec the Gateway advertised, preferring `preferred`.
+/// Without a manifest the preferred codec is tried (415 fallback applies).
+fn negotiated(preferred: Codec) -> Option<Codec> {
+    let Some(gw) = crate::capabilities::gateway() else {
+        return Some(preferred);
+    };
+    [preferred, Codec::Zstd, Codec::Gzip]
+        .into_iter()
+        .find(|c| gw.compression.iter().any(|a| a.eq_ignore_ascii_case(c.name())))
+}
+
+/// Upload body, compressed when worthwhile
+fn upload_body(raw: Vec<u8>) -> (Vec<u8>, Option<&'static str>) {
+    if UPLOAD_UNSUPPORTED.load(Ordering::Relaxed) {
+        return (raw, None);
+    }
+    let Some(codec) = negotiated(config().codec) else {
+        return (raw, None);
+    };
+    match maybe_compress(codec, &raw) {
+        Some(packed) => (packed, Some(codec.name())),
+        None => (raw, None),
+    }
+}
+
+/// Serialized JSON upload body, compressed when worthwhile.
+/// Returns the bytes and the `Content-Encoding` to send, if any.
+pub fn json_upload(payload: &serde_json::Value) -> (Vec<u8>, Option<&'static str>) {
+    upload_body(serde_json::to_vec(payload).unwrap_or_default())
+}
+
+/// Binary upload body (e.g. a file chunk), compressed when worthwhile
+pub fn bytes_upload(data: Vec<u8>) -> (Vec<u8>, Option<&'static str>) {
+    upload_body(data)
+}
+
+/// Attach a body built by [`json_upload`] or [`bytes_upload`] to a request
+pub fn with_body(
+    builder: reqwest::RequestBuilder,
+    content_type: &str,
+    body: &(Vec<u8>, Option<&'static str>),
+) -> reqwest::RequestBuilder {
+    let builder = builder
+        .header(reqwest::header::CONTENT_TYPE, content_type)
+        .body(body.0.clone());
+    match body.1 {
+        Some(encoding) => builder.header(reqwest::header::CONTENT_ENCODING, encoding),
+        None => builder,
+    }
+}
+
+/// Attach a JSON body built by [`json_upload`] to a request
+pub fn with_json_body(
+    builder: reqwest::RequestBuilder,
+    body: &(Vec<u8>, Option<&'static str>),
+) -> reqwest::RequestBuilder {
+    with_body(builder, "application/json", body)
+}
+
+/// Record that the Gateway rejected a compressed upload; returns true if the
+/// request should be retried uncompressed
+pub fn upload_rejected(status: reqwest::StatusCode, body: &(Vec<u8>, Option<&'static str>)) -> bool {
+    if status != reqwest::StatusCode::UNSUPPORTED_MEDIA_TYPE || body.1.is_none() {
+        return false;
+    }
+    tracing::warn!("[Compression] Gateway rejected compressed upload, sending uncompressed from now on");
+    UPLOAD_UNSUPPORTED.store(true, Ordering::Relaxed);
+    true
+}
+
+/// Compress an action result output for the live channel if the request
+/// accepted one of our codecs. Returns the base64 output and its encoding.
+pub fn encode_output(output: &str, accepted: &[String]) -> Option<(String, &'static str)> {
+    use base64::Engine as _;
+    // Per-request list wins; otherwise fall back to the negotiated manifest
+    let gateway_list = crate::capabilities::gateway().map(|m| m.compression).unwrap_or_default();
+    let accepted = if accepted.is_empty() { gateway_list.as_slice() } else { accepted };
+    let preferred = config().codec;
+    let codec = [preferred, Codec::Zstd, Codec::Gzip]
+        .into_iter()
+        .find(|c| accepted.iter().any(|a| a.eq_ignore_ascii_case(c.name())))?;
+    let packed = maybe_compress(codec, output.as_bytes())?;
+    Some((base64::engine::general_purpose::STANDARD.encode(packed), codec.name()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_compress_round_trip_and_thresholds() {
+        let text = "file contents ".repeat(2000);
+        let gz = compress(Codec::Gzip, text.as_bytes()).unwrap();
+        let mut unpacked = String::new();
+        flate2::read::GzDecoder::new(&gz[..]).read_to_string(&mut unpacked).unwrap();
+        assert_eq!(unpacked, text);
+        let zst = compress(Codec::Zstd, text.as_bytes()).unwrap();
+        assert_eq!(zstd::decode_all(&zst[..]).unwrap(), text.as_bytes());
+
+        // Default config: gzip from 8 KiB, only when it saves bytes
+        assert!(maybe_compress(Codec::Gzip, b"short").is_none());
+        assert!(maybe_compress(Codec::Gzip, &[0u8; 16 * 1024]).is_some());
+        let mut x = 0x2545_f491_4f6c_dd1du64;
+        let noise: Vec<u8> = (0..16 * 1024)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                (x >> 32) as u8
+            })
+            .collect();
+        assert!(maybe_compress(Codec::Gzip, &noise).is_none());
+
+        // Without a Gateway manifest the configured codec is tried
+        let (body, encoding) = bytes_upload(text.clone().into_bytes());
+        assert_eq!(encoding, Some("gzip"));
+        assert!(body.len() < text.len());
+        let (body, encoding) = json_upload(&serde_json::json!({ "a": 1 }));
+        assert_eq!((body.as_slice(), encoding), (&b"{\"a\":1}"[..], None));
+
+        // Live-channel output only in a codec the request accepted
+        assert!(encode_output(&text, &["br".into()]).is_none());
+        let (encoded, encoding) = encode_output(&text, &["ZSTD".into()]).unwrap();
+        assert_eq!(encoding, "zstd");
+        use base64::Engine as _;
+        let packed = base64::engine::general_purpose::STANDARD.decode(encoded).unwrap();
+        assert_eq!(zstd::decode_all(&packed[..]).unwrap(), text.as_bytes());
+    }
+}
@@ -0,0 +1,265 @@
+//! # Configuration Export
+//!
+//! Moves a companion setup to a new machine in one file. `export_config`
+//! writes the settings (voice and audio profiles, shortcuts, speaker
+//! verification, webhooks and everything else in `settings.json`), the
+//! user scripts and the proxy configuration; `import_config` applies them
+//! on the other side, validating the settings as a whole before anything
+//! is written.
+//!
+//! Secrets (the Gateway pairing, the proxy and MQTT passwords and the
+//! enrolled voiceprint) are only included when a passphrase is given. They
+//! are sealed with ChaCha20-Poly1305 under a key derived from it with
+//! PBKDF2-SHA256, since the local secure-store key does not travel with the
+//! file. Importing a pairing takes effect after a restart.
+
+use crate::connection::{CompanionCredentials, GatewayConnection};
+use base64::Engine as _;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use ring::rand::SecureRandom;
+use serde::{Deserialize, Serialize};
+use std::num::NonZeroU32;
+use tauri::AppHandle;
+
+const FORMAT: &str = "forgeai-companion-config";
+const VERSION: u32 = 1;
+const PBKDF2_ITERATIONS: u32 = 600_000;
+const MIN_PASSPHRASE_CHARS: usize = 8;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Bundle {
+    format: String,
+    version: u32,
+    exported_at: String,
+    companion_version: String,
+    settings: crate::settings::Settings,
+    /// File name → source of each user script
+    #[serde(default)]
+    scripts: Vec<(String, String)>,
+    /// Proxy configuration without its password
+    #[serde(default)]
+    proxy: Option<crate::proxy::ProxyConfig>,
+    #[serde(default)]
+    secrets: Option<Sealed>,
+}
+
+/// [`Secrets`] encrypted under the passphrase
+#[derive(Debug, Serialize, Deserialize)]
+struct Sealed {
+    salt: String,
+    nonce: String,
+    data: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Secrets {
+    credentials: Option<CompanionCredentials>,
+    proxy_password: Option<String>,
+    mqtt_password: Option<String>,
+    /// The enrolled voiceprint file, base64
+    voiceprint: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportReport {
+    pub path: String,
+    pub scripts: usize,
+    /// Whether secrets were included
+    pub secrets: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub exported_at: String,
+    pub scripts: usize,
+    pub credentials: bool,
+    /// The file has secrets but no passphrase was given
+    pub secrets_skipped: bool,
+    /// A pairing was imported and takes effect after a restart
+    pub restart_required: bool,
+}
+
+// ─── Passphrase encryption ──────────────────────────
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Key {
+    let mut key = [0u8; 32];
+    let iterations = NonZeroU32::new(PBKDF2_ITERATIONS).expect("non-zero iterations");
+    ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+    key.into()
+}
+
+fn seal(secrets: &Secrets, passphrase: &str) -> Result<Sealed, String> {
+    let rng = ring::rand::SystemRandom::new();
+    let mut salt = [0u8; 16];
+    let mut nonce = [0u8; 12];
+    rng.fill(&mut salt).and_then(|_| rng.fill(&mut nonce)).map_err(|_| "No random source".to_string())?;
+    let plaintext = serde_json::to_vec(secrets).map_err(|e| e.to_string())?;
+    let data = ChaCha20Poly1305::new(&derive_key(passphrase, &salt))
+        .encrypt(Nonce::from_slice(&nonce), plaintext.as_slice())
+        .map_err(|_| "Encryption failed".to_string())?;
+    let b64 = base64::engine::general_purpose::STANDARD;
+    Ok(Sealed { salt: b64.encode(salt), nonce: b64.encode(nonce), data: b64.encode(data) })
+}
+
+fn open(sealed: &Sealed, passphrase: &str) -> Result<Secrets, String> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let decode = |s: &str| b64.decode(s).map_err(|_| "Damaged secrets in the configuration file".to_string());
+    let (salt, nonce, data) = (decode(&sealed.salt)?, decode(&sealed.nonce)?, decode(&sealed.data)?);
+    if nonce.len() != 12 {
+        return Err("Damaged secrets in the configuration file".into());
+    }
+    let plaintext = ChaCha20Poly1305::new(&derive_key(passphrase, &salt))
+        .decrypt(Nonce::from_slice(&nonce), data.as_slice())
+        .map_err(|_| "Wrong passphrase or damaged configuration file".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Damaged secrets: {}", e))
+}
+
+// ─── Gathering and applying ─────────────────────────
+
+fn script_sources() -> Vec<(String, String)> {
+    let Some(dir) = crate::scripts::dir() else { return Vec::new() };
+    let mut sources: Vec<(String, String)> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter(|e| e.path().extension().is_some_and(|x| x == "rhai"))
+                .filter_map(|e| Some((e.file_name().to_str()?.to_string(), std::fs::read_to_string(e.path()).ok()?)))
+                .collect()
+        })
+        .unwrap_or_default();
+    sources.sort();
+    sources
+}
+
+fn gather_secrets() -> Secrets {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    Secrets {
+        credentials: GatewayConnection::load_credentials(),
+        proxy_password: crate::proxy::config().password,
+        mqtt_password: crate::mqtt::password(),
+        voiceprint: crate::speaker::enrollment_path()
+            .filter(|p| p.exists())
+            .and_then(|p| crate::secure_store::read_encrypted(&p).ok())
+            .map(|bytes| b64.encode(bytes)),
+    }
+}
+
+fn apply_secrets(secrets: Secrets, proxy: &mut Option<crate::proxy::ProxyConfig>) -> Result<bool, String> {
+    if let Some(password) = secrets.proxy_password {
+        if let Some(proxy) = proxy.as_mut() {
+            proxy.password = Some(password);
+        }
+    }
+    if let Some(password) = &secrets.mqtt_password {
+        crate::mqtt::set_password(Some(password))?;
+    }
+    if let Some(voiceprint) = &secrets.voiceprint {
+        let bytes = base64::engine::general_purpose::STANDARD.decode(voiceprint).map_err(|e| e.to_string())?;
+        let path = crate::speaker::enrollment_path().ok_or("Cannot determine data directory")?;
+        crate::secure_store::write_encrypted(&path, &bytes)?;
+    }
+    match &secrets.credentials {
+        Some(creds) => GatewayConnection::save_credentials(creds).map(|_| true),
+        None => Ok(false),
+    }
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Write the configuration to `path`, with the secrets sealed under
+/// `passphrase` when one is given. Blocking.
+pub fn export(path: &str, passphrase: Option<&str>) -> Result<ExportReport, String> {
+    let secrets = match passphrase {
+        Some(p) if p.chars().count() < MIN_PASSPHRASE_CHARS => {
+            return Err(format!("The passphrase needs at least {} characters", MIN_PASSPHRASE_CHARS));
+        }
+        Some(p) => Some(seal(&gather_secrets(), p)?),
+        None => None,
+    };
+    let bundle = Bundle {
+        format: FORMAT.into(),
+        version: VERSION,
+        exported_at: chrono::Local::now().to_rfc3339(),
+        companion_version: env!("CARGO_PKG_VERSION").into(),
+        settings: crate::settings::get(),
+        scripts: script_sources(),
+        proxy: Some(crate::proxy::ProxyConfig { password: None, ..crate::proxy::config() }),
+        secrets,
+    };
+    let json = serde_json::to_vec_pretty(&bundle).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+    tracing::info!("[Config] Exported to {} (secrets: {})", path, bundle.secrets.is_some());
+    Ok(ExportReport { path: path.to_string(), scripts: bundle.scripts.len(), secrets: bundle.secrets.is_some() })
+}
+
+/// Apply the configuration file at `path`. Secrets are imported only with
+/// the passphrase they were exported with. Blocking.
+pub fn import(app: &AppHandle, path: &str, passphrase: Option<&str>) -> Result<ImportReport, String> {
+    let raw = std::fs::read(path).map_err(|e| format!("Cannot read {}: {}", path, e))?;
+    let bundle: Bundle = serde_json::from_slice(&raw).map_err(|e| format!("Not a companion configuration file: {}", e))?;
+    if bundle.format != FORMAT {
+        return Err("Not a companion configuration file".into());
+    }
+    if bundle.version > VERSION {
+        return Err("This configuration was exported by a newer companion; update first".into());
+    }
+    // Everything that can fail on its own is checked before anything is written
+    let secrets = match (&bundle.secrets, passphrase) {
+        (Some(sealed), Some(p)) => Some(open(sealed, p)?),
+        _ => None,
+    };
+    for (file, _) in &bundle.scripts {
+        if file.contains(['/', '\\']) || !file.ends_with(".rhai") {
+            return Err(format!("Invalid script name '{}' in the configuration file", file));
+        }
+    }
+
+    crate::settings::set(app, bundle.settings)?;
+
+    let mut proxy = bundle.proxy;
+    let credentials = match secrets {
+        Some(secrets) => apply_secrets(secrets, &mut proxy)?,
+        None => false,
+    };
+    if let Some(proxy) = proxy {
+        crate::proxy::set_config(proxy)?;
+    }
+    if !bundle.scripts.is_empty() {
+        let dir = crate::scripts::dir().ok_or("Cannot determine data directory")?;
+        std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        for (file, source) in &bundle.scripts {
+            std::fs::write(dir.join(file), source).map_err(|e| format!("Cannot write script {}: {}", file, e))?;
+        }
+    }
+
+    tracing::info!("[Config] Imported {} (credentials: {})", path, credentials);
+    Ok(ImportReport {
+        exported_at: bundle.exported_at,
+        scripts: bundle.scripts.len(),
+        credentials,
+        secrets_skipped: bundle.secrets.is_some() && passphrase.is_none(),
+        restart_required: credentials,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secrets_round_trip() {
+        let secrets = Secrets { mqtt_password: Some("hunter2".into()), voiceprint: Some("AAEC".into()), ..Default::default() };
+        let sealed = seal(&secrets, "correct horse").unwrap();
+        let opened = open(&sealed, "correct horse").unwrap();
+        assert_eq!(opened.mqtt_password.as_deref(), Some("hunter2"));
+        assert_eq!(opened.voiceprint.as_deref(), Some("AAEC"));
+        assert!(open(&sealed, "wrong horse").unwrap_err().contains("Wrong passphrase"));
+        // Fresh salt and nonce every time
+        assert_ne!(seal(&secrets, "correct horse").unwrap().data, sealed.data);
+    }
+}
@@ -7,7 +7,87 @@ use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex};
-use tokio_tungstenite::{connect_async, tungstenite::Message};
+use tokio_tungstenite::tungstenite::Message;
+
+/// OS keychain service under which credentials are stored, one account
+/// per user profile (see `users::credentials_account`)
+const KEYRING_SERVICE: &str = "forgeai-companion";
+
+/// Outgoing queue of the live Gateway WebSocket, set while connected
+static LIVE_SENDER: std::sync::Mutex<Option<mpsc::UnboundedSender<String>>> =
+    std::sync::Mutex::new(None);
+
+/// Register (or clear) the outgoing queue of the live Gateway channel
+pub fn set_live_sender(tx: Option<mpsc::UnboundedSender<String>>) {
+    if let Ok(mut lock) = LIVE_SENDER.lock() {
+        *lock = tx;
+    }
+    crate::status::publish();
+}
+
+/// Held while the session is refreshed: a Gateway that rotates refresh
+/// tokens accepts each one once, so concurrent 401s must not all refresh
+static REFRESHING: Mutex<()> = Mutex::const_new(());
+
+/// Error returned by Gateway calls once the companion has been revoked
+pub const REVOKED_MESSAGE: &str = "This companion was removed from the Gateway — please pair again";
+
+/// Error from an authenticated Gateway call. Converts into the `String`
+/// errors used by commands, so `?` keeps working at call sites.
+#[derive(Debug, Clone)]
+pub enum GatewayError {
+    /// 429 that could not (or should not) be retried transparently
+    RateLimited(crate::rate_limit::RateLimited),
+    Failed(String),
+}
+
+impl std::fmt::Display for GatewayError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RateLimited(r) => r.fmt(f),
+            Self::Failed(e) => f.write_str(e),
+        }
+    }
+}
+
+impl From<String> for GatewayError {
+    fn from(e: String) -> Self {
+        Self::Failed(e)
+    }
+}
+
+impl From<&str> for GatewayError {
+    fn from(e: &str) -> Self {
+        Self::Failed(e.to_string())
+    }
+}
+
+impl From<GatewayError> for String {
+    fn from(e: GatewayError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Revocation reason from a Gateway error body
+/// (`{"code": "companion_revoked", "message": ...}` or `{"revoked": true}`)
+pub fn revocation_reason(body: &serde_json::Value) -> Option<&str> {
+    let revoked = body["code"].as_str() == Some("companion_revoked")
+        || body["error"].as_str() == Some("companion_revoked")
+        || body["revoked"].as_bool() == Some(true);
+    revoked.then(|| body["message"].as_str().unwrap_or("Companion revoked"))
+}
+
+/// Whether the live Gateway channel is currently connected
+pub fn is_live() -> bool {
+    LIVE_SENDER.lock().map(|l| l.is_some()).unwrap_or(false)
+}
+
+/// Send a raw JSON message over the live Gateway channel
+pub fn send_live(json: String) -> Result<(), String> {
+    let lock = LIVE_SENDER.lock().map_err(|e| e.to_string())?;
+    let tx = lock.as_ref().ok_or("Gateway channel not connected")?;
+    tx.send(json).map_err(|e| format!("Send error: {}", e))
+}
 
 /// Connection state
 #[derive(Debug, Clone, PartialEq, Serialize)]
@@ -41,7 +121,7 @@ pub struct OutgoingMessage {
     pub channel: String,
 }
 
-/// Credentials stored in Windows Credential Manager
+/// Credentials stored in the OS keychain (Credential Manager / Keychain / Secret Service)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompanionCredentials {
     pub gateway_url: String,
@@ -49,6 +129,31 @@ pub struct CompanionCredentials {
     pub role: String,
     #[serde(default)]
     pub auth_token: Option<String>,
+    /// Long-lived token used to obtain a new `auth_token` when it expires
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    /// Base64 key shared with the Gateway for end-to-end payload encryption
+    #[serde(default)]
+    pub e2e_key: Option<String>,
+    /// Optional LAN and remote/tunnel URLs of the same Gateway; when set,
+    /// `gateway_url` is resolved to the reachable one (see `roaming`)
+    #[serde(default)]
+    pub lan_url: Option<String>,
+    #[serde(default)]
+    pub remote_url: Option<String>,
+    /// The stored `gateway_url` while `gateway_url` points at the active
+    /// route; never persisted
+    #[serde(skip)]
+    pub stored_gateway_url: Option<String>,
+}
+
+/// Attach the session cookie to a Gateway request, if a token is available
+pub fn with_auth(builder: reqwest::RequestBuilder, creds: &CompanionCredentials) -> reqwest::RequestBuilder {
+    if let Some(ref token) = creds.auth_token {
+        builder.header("Cookie", format!("forgeai_session={}", token))
+    } else {
+        builder
+    }
 }
 
 /// ForgeAI Gateway connection manager
@@ -74,37 +179,68 @@ impl GatewayConnection {
         self.state.lock().await.clone()
     }
 
-    /// Get the file path for credential storage fallback
-    fn creds_file_path() -> Option<std::path::PathBuf> {
-        dirs::data_local_dir().map(|d| d.join("forgeai-companion").join("credentials.json"))
+    /// Keychain entry of the active user profile
+    fn keyring_entry() -> Option<keyring::Entry> {
+        Self::keyring_entry_of(crate::users::active())
     }
 
-    /// Save credentials to Windows Credential Manager + file fallback
-    pub fn save_credentials(creds: &CompanionCredentials) -> Result<(), String> {
-        let json = serde_json::to_string(creds).map_err(|e| format!("Serialize error: {}", e))?;
+    fn keyring_entry_of(profile: &str) -> Option<keyring::Entry> {
+        keyring::Entry::new(KEYRING_SERVICE, &crate::users::credentials_account(profile)).ok()
+    }
 
-        // Try keyring first
-        if let Ok(entry) = keyring::Entry::new("forgeai-companion", "credentials") {
-            let _ = entry.set_password(&json);
-        }
+    /// Plaintext file used before credentials moved to the keychain (migrated on load)
+    fn legacy_creds_file_path() -> Option<std::path::PathBuf> {
+        crate::users::data_dir().map(|d| d.join("credentials.json"))
+    }
+
+    /// Encrypted file used when no OS keychain is available (e.g. headless Linux)
+    fn encrypted_creds_file_path() -> Option<std::path::PathBuf> {
+        crate::users::data_dir().map(|d| d.join("credentials.enc"))
+    }
 
-        // Always save to file as fallback
-        if let Some(path) = Self::creds_file_path() {
-            if let Some(parent) = path.parent() {
-                let _ = std::fs::create_dir_all(parent);
+    /// Save credentials to the OS keychain, or to the encrypted file if no keychain is available
+    pub fn save_credentials(creds: &CompanionCredentials) -> Result<(), String> {
+        // The active roaming route is runtime state, not part of the pairing
+        let json = serde_json::to_string(&crate::roaming::unapplied(creds))
+            .map_err(|e| format!("Serialize error: {}", e))?;
+
+        let keychain = Self::keyring_entry()
+            .ok_or_else(|| "keychain entry unavailable".to_string())
+            .and_then(|entry| entry.set_password(&json).map_err(|e| e.to_string()));
+
+        match keychain {
+            Ok(()) => {
+                // Keychain is authoritative — drop any file copies
+                if let Some(path) = Self::encrypted_creds_file_path() {
+                    let _ = std::fs::remove_file(path);
+                }
+                tracing::info!("Credentials saved to OS keychain");
+            }
+            Err(e) => {
+                let path = Self::encrypted_creds_file_path().ok_or("No data directory for credentials")?;
+                tracing::warn!("OS keychain unavailable ({}), using encrypted file", e);
+                crate::secure_store::write_encrypted(&path, json.as_bytes())?;
+                tracing::info!("Credentials saved to {}", path.display());
             }
-            std::fs::write(&path, &json)
-                .map_err(|e| format!("File save error: {}", e))?;
-            log::info!("Credentials saved to {}", path.display());
         }
 
+        if let Some(path) = Self::legacy_creds_file_path() {
+            let _ = std::fs::remove_file(path);
+        }
+        crate::e2e::forget_key();
         Ok(())
     }
 
-    /// Load credentials from Windows Credential Manager, fallback to file
+    /// Load credentials from the OS keychain, falling back to the encrypted file.
+    /// A plaintext file from older versions is migrated and then removed.
     pub fn load_credentials() -> Option<CompanionCredentials> {
-        // Try keyring first
-        if let Ok(entry) = keyring::Entry::new("forgeai-companion", "credentials") {
+        let mut creds = Self::load_stored_credentials()?;
+        crate::roaming::apply(&mut creds);
+        Some(creds)
+    }
+
+    fn load_stored_credentials() -> Option<CompanionCredentials> {
+        if let Some(entry) = Self::keyring_entry() {
             if let Ok(json) = entry.get_password() {
                 if let Ok(creds) = serde_json::from_str::<CompanionCredentials>(&json) {
                     return Some(creds);
@@ -112,91 +248,193 @@ impl GatewayConnection {
             }
         }
 
-        // Fallback to file
-        if let Some(path) = Self::creds_file_path() {
+        if let Some(path) = Self::encrypted_creds_file_path() {
+            if path.exists() {
+                match crate::secure_store::read_encrypted(&path) {
+                    Ok(data) => {
+                        if let Ok(creds) = serde_json::from_slice::<CompanionCredentials>(&data) {
+                            return Some(creds);
+                        }
+                    }
+                    Err(e) => tracing::warn!("Encrypted credentials unreadable: {}", e),
+                }
+            }
+        }
+
+        if let Some(path) = Self::legacy_creds_file_path() {
             if let Ok(json) = std::fs::read_to_string(&path) {
                 if let Ok(creds) = serde_json::from_str::<CompanionCredentials>(&json) {
-                    log::info!("Credentials loaded from file fallback");
+                    tracing::info!("Migrating plaintext credentials to secure storage");
+                    if let Err(e) = Self::save_credentials(&creds) {
+                        tracing::error!("Credential migration failed, keeping old file: {}", e);
+                    }
                     return Some(creds);
                 }
             }
         }
 
-        log::warn!("No credentials found in keyring or file");
+        tracing::warn!("No credentials found in keychain or encrypted file");
         None
     }
 
-    /// Delete stored credentials from both keyring and file
+    /// Delete stored credentials from the keychain and all file locations
     pub fn delete_credentials() -> Result<(), String> {
-        // Try keyring
-        if let Ok(entry) = keyring::Entry::new("forgeai-companion", "credentials") {
+        if let Some(entry) = Self::keyring_entry() {
             let _ = entry.delete_credential();
         }
 
-        // Delete file
-        if let Some(path) = Self::creds_file_path() {
-            let _ = std::fs::remove_file(&path);
+        for path in [Self::encrypted_creds_file_path(), Self::legacy_creds_file_path()]
+            .into_iter()
+            .flatten()
+        {
+            let _ = std::fs::remove_file(path);
         }
+        crate::e2e::forget_key();
 
         Ok(())
     }
 
-    /// Pair with Gateway using a pairing code from the Dashboard
-    pub async fn pair(
-        &mut self,
-        gateway_url: &str,
-        pairing_code: &str,
-    ) -> Result<(), String> {
-        *self.state.lock().await = ConnectionState::Connecting;
+    /// Forget the keychain credentials of another user profile (its files
+    /// go with the profile directory)
+    pub fn delete_profile_credentials(profile: &str) {
+        if let Some(entry) = Self::keyring_entry_of(profile) {
+            let _ = entry.delete_credential();
+        }
+    }
+
+    /// The Gateway revoked this companion: forget the credentials and send
+    /// the UI back to the pairing screen via a `paired-revoked` event
+    pub fn handle_revocation(reason: &str) {
+        tracing::warn!("Companion revoked by Gateway: {}", reason);
+        let _ = Self::delete_credentials();
+        let _ = crate::history::clear();
+        crate::events::emit("paired-revoked", serde_json::json!({ "reason": reason }));
+        crate::status::publish();
+    }
+
+    /// Exchange the stored refresh token for a new session token.
+    /// The refreshed credentials are persisted before being returned.
+    ///
+    /// One refresh runs at a time. A caller that waited for another's
+    /// refresh gets the credentials it stored instead of refreshing again
+    /// with a token that was just rotated away.
+    pub async fn refresh_session(creds: &CompanionCredentials) -> Result<CompanionCredentials, String> {
+        let _refreshing = REFRESHING.lock().await;
+        if let Some(stored) = Self::load_credentials() {
+            if stored.companion_id == creds.companion_id && stored.auth_token.is_some() && stored.auth_token != creds.auth_token {
+                return Ok(stored);
+            }
+        }
+        Self::exchange_refresh_token(creds).await
+    }
 
-        let base_url = gateway_url.trim_end_matches('/');
-        let url = format!("{}/api/pairing/claim", base_url);
+    async fn exchange_refresh_token(creds: &CompanionCredentials) -> Result<CompanionCredentials, String> {
+        let refresh_token = creds
+            .refresh_token
+            .as_ref()
+            .ok_or("No refresh token stored")?;
 
-        let client = reqwest::Client::new();
-        let resp = client
-            .post(&url)
+        let request = crate::http::gateway(&creds.gateway_url)?
+            .post("/api/companion/refresh")
             .json(&serde_json::json!({
-                "code": pairing_code,
-                "deviceName": "ForgeAI Companion (Windows)",
-                "deviceType": "desktop"
+                "companionId": creds.companion_id,
+                "refreshToken": refresh_token,
             }))
-            .timeout(std::time::Duration::from_secs(10))
-            .send()
+            .timeout(std::time::Duration::from_secs(10));
+        let resp = crate::netstats::send(request)
             .await
-            .map_err(|e| format!("Connection failed: {}", e))?;
-
-        if !resp.status().is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            *self.state.lock().await = ConnectionState::Error(text.clone());
-            return Err(format!("Pairing failed: {}", text));
+            .map_err(|e| format!("Refresh request failed: {}", e))?;
+
+        let status = resp.status();
+        if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+            // Only an explicit revocation forgets the pairing; anything else
+            // (a proxy, a Gateway restart mid-migration) keeps the credentials
+            let body: serde_json::Value = resp.json().await.unwrap_or_default();
+            if let Some(reason) = revocation_reason(&body) {
+                Self::handle_revocation(reason);
+                return Err(REVOKED_MESSAGE.into());
+            }
+            return Err(format!("Refresh rejected: HTTP {}", status));
+        }
+        if !status.is_success() {
+            return Err(format!("Refresh rejected: HTTP {}", status));
         }
 
         let data: serde_json::Value = resp
             .json()
             .await
             .map_err(|e| format!("Parse error: {}", e))?;
+        let auth_token = data["authToken"]
+            .as_str()
+            .ok_or("Refresh response has no authToken")?;
+
+        let mut fresh = creds.clone();
+        fresh.auth_token = Some(auth_token.to_string());
+        if let Some(rotated) = data["refreshToken"].as_str() {
+            fresh.refresh_token = Some(rotated.to_string());
+        }
+        Self::save_credentials(&fresh)?;
+        tracing::info!("Gateway session refreshed");
+        Ok(fresh)
+    }
 
-        let creds = CompanionCredentials {
-            gateway_url: base_url.to_string(),
-            companion_id: data["companionId"]
-                .as_str()
-                .unwrap_or_default()
-                .to_string(),
-            role: data["role"]
-                .as_str()
-                .unwrap_or("user")
-                .to_string(),
-            auth_token: data["authToken"]
-                .as_str()
-                .map(|s| s.to_string()),
+    /// Send an authenticated Gateway request. On 401 the session is refreshed
+    /// and the request retried once; if that fails a `reauth-required` event
+    /// prompts the UI to re-pair. Credentials are only deleted when the
+    /// Gateway explicitly reports the companion as revoked.
+    ///
+    /// Rate limits (429) are handled by [`crate::rate_limit::send`]. Whether
+    /// the request got through is reported to [`crate::offline`].
+    pub async fn send_authenticated<F>(creds: &CompanionCredentials, build: F) -> Result<reqwest::Response, GatewayError>
+    where
+        F: Fn() -> reqwest::RequestBuilder,
+    {
+        let resp = match crate::rate_limit::send(|| with_auth(build(), creds)).await {
+            Ok(resp) => {
+                crate::offline::reachable();
+                resp
+            }
+            Err(GatewayError::Failed(e)) => {
+                crate::offline::unreachable(&e);
+                return Err(GatewayError::Failed(e));
+            }
+            Err(e) => return Err(e),
         };
+        if resp.status() != reqwest::StatusCode::UNAUTHORIZED {
+            return Ok(resp);
+        }
 
-        Self::save_credentials(&creds)?;
-        *self.credentials.lock().await = Some(creds);
-        *self.state.lock().await = ConnectionState::Authenticated;
+        let body: serde_json::Value = resp.json().await.unwrap_or_default();
+        if let Some(reason) = revocation_reason(&body) {
+            Self::handle_revocation(reason);
+            return Err(REVOKED_MESSAGE.into());
+        }
 
-        log::info!("Paired with Gateway at {}", base_url);
-        Ok(())
+        tracing::warn!("Gateway rejected session (401), attempting refresh");
+        let fresh = match Self::refresh_session(creds).await {
+            Ok(fresh) => fresh,
+            Err(e) if e == REVOKED_MESSAGE => return Err(e.into()),
+            Err(e) => {
+                tracing::warn!("Session refresh failed: {}", e);
+                crate::events::emit("reauth-required", serde_json::json!({ "reason": e }));
+                return Err("Session expired — please pair again".into());
+            }
+        };
+
+        let resp = crate::rate_limit::send(|| with_auth(build(), &fresh)).await?;
+        if resp.status() == reqwest::StatusCode::UNAUTHORIZED {
+            // A brand-new token is refused too. Unless the Gateway says the
+            // companion was revoked, keep the credentials and ask to re-pair.
+            let body: serde_json::Value = resp.json().await.unwrap_or_default();
+            if let Some(reason) = revocation_reason(&body) {
+                Self::handle_revocation(reason);
+                return Err(REVOKED_MESSAGE.into());
+            }
+            tracing::warn!("Gateway rejected the refreshed session (401)");
+            crate::events::emit("reauth-required", serde_json::json!({ "reason": "Refreshed session rejected" }));
+            return Err("Session expired — please pair again".into());
+        }
+        Ok(resp)
     }
 
     /// Connect to Gateway WebSocket
@@ -217,7 +455,8 @@ impl GatewayConnection {
             .replace("http://", "ws://");
         let ws_url = format!("{}/ws?companionId={}", ws_url, creds.companion_id);
 
-        let (ws_stream, _) = connect_async(&ws_url)
+        let connector = crate::tls_trust::ws_connector(&creds.gateway_url);
+        let ws_stream = crate::proxy::connect_ws(&ws_url, connector)
             .await
             .map_err(|e| format!("WebSocket connection failed: {}", e))?;
 
@@ -225,7 +464,6 @@ impl GatewayConnection {
 
         // Outgoing channel
         let (tx, mut rx) = mpsc::unbounded_channel::<String>();
-        let action_tx = tx.clone(); // Clone before moving tx into self
         self.outgoing_tx = Some(tx);
 
         // Send task — forwards outgoing messages to WebSocket
@@ -248,39 +486,8 @@ impl GatewayConnection {
                         // Try to parse as a raw JSON value first to check type
                         if let Ok(raw) = serde_json::from_str::<serde_json::Value>(&text) {
                             if raw.get("type").and_then(|t| t.as_str()) == Some("action_request") {
-                                // Handle action request from Gateway agent
-                                let request_id = raw.get("requestId").and_then(|v| v.as_str()).unwrap_or("").to_string();
-                                let action = raw.get("action").and_then(|v| v.as_str()).unwrap_or("").to_string();
-                                let params = raw.get("params").cloned().unwrap_or(serde_json::json!({}));
-
-                                log::info!("Action request from Gateway: {} ({})", action, request_id);
-
-                                // Build ActionRequest from the params
-                                let action_req = crate::local_actions::ActionRequest {
-                                    action: action.clone(),
-                                    path: params.get("path").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                                    command: params.get("command").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                                    content: params.get("content").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                                    process_name: params.get("process_name").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                                    app_name: params.get("app_name").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                                    cwd: params.get("cwd").and_then(|v| v.as_str()).map(|s| s.to_string()),
-                                    confirmed: true, // Agent-initiated actions are pre-confirmed
-                                };
-
-                                // Execute locally on Windows
-                                let result = crate::local_actions::execute(&action_req);
-                                log::info!("Action result: {} success={}", action, result.success);
-
-                                // Send result back via WebSocket
-                                let response = serde_json::json!({
-                                    "type": "action_result",
-                                    "requestId": request_id,
-                                    "success": result.success,
-                                    "output": result.output,
-                                });
-                                if let Ok(json) = serde_json::to_string(&response) {
-                                    let _ = action_tx.send(json);
-                                }
+                                // Same safety routing and confirmation as the live channel
+                                crate::remote_actions::handle_action_request(&raw);
                                 continue;
                             }
                         }
@@ -304,7 +511,7 @@ impl GatewayConnection {
         *self.state.lock().await = ConnectionState::Connected;
         *self.credentials.lock().await = Some(creds);
 
-        log::info!("Connected to Gateway WebSocket");
+        tracing::info!("Connected to Gateway WebSocket");
         Ok(())
     }
 
@@ -338,6 +545,6 @@ impl GatewayConnection {
     pub async fn disconnect(&mut self) {
         self.outgoing_tx = None;
         *self.state.lock().await = ConnectionState::Disconnected;
-        log::info!("Disconnected from Gateway");
+        tracing::info!("Disconnected from Gateway");
     }
 }
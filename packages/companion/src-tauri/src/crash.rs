@@ -0,0 +1,251 @@
+//! # Crash Reports
+//!
+//! A panic hook, installed before anything else starts, writes one JSON
+//! report per panic to `crashes/` in the data directory. The hook is
+//! process-wide, so panics on background threads and in async tasks — which
+//! only take down one feature and otherwise leave no trace — are captured
+//! too. A report holds what is needed to debug "it just closed":
+//!
+//! | Field       | Contents                                               |
+//! |-------------|--------------------------------------------------------|
+//! | `message`   | panic payload                                          |
+//! | `location`  | `file:line:column` of the panic                        |
+//! | `thread`    | thread name (`main` means the app exited)              |
+//! | `backtrace` | forced backtrace, symbolized when debug info is there  |
+//! | `logs`      | the last log entries before the panic                  |
+//!
+//! Reports stay on this machine unless `crashReports.upload` is turned on;
+//! then they are sent to the paired Gateway at start-up (and when the option
+//! is enabled) and deleted once accepted. At most 20 reports are kept.
+
+use serde::{Deserialize, Serialize};
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// Reports kept on disk; the oldest are deleted first
+const MAX_REPORTS: usize = 20;
+/// Log entries attached to a report
+const LOG_ENTRIES: usize = 50;
+
+/// Whether uploading was enabled by the last applied settings
+static UPLOAD: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    /// Set while this thread runs the hook, so a panic in the hook itself
+    /// goes straight to the previous hook instead of recursing
+    static IN_HOOK: Cell<bool> = const { Cell::new(false) };
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CrashConfig {
+    /// Send reports to the paired Gateway (off unless the user opts in)
+    pub upload: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CrashReport {
+    pub id: String,
+    pub time: String,
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    pub thread: String,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: String,
+    #[serde(default)]
+    pub logs: Vec<crate::logging::LogEntry>,
+}
+
+fn crash_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("forgeai-companion").join("crashes"))
+}
+
+fn panic_message(payload: &(dyn std::any::Any + Send)) -> String {
+    if let Some(s) = payload.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = payload.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "Box<dyn Any>".into()
+    }
+}
+
+fn build_report(info: &std::panic::PanicHookInfo) -> CrashReport {
+    let now = chrono::Utc::now();
+    let thread = std::thread::current();
+    let logs = crate::logging::tail(LOG_ENTRIES);
+    CrashReport {
+        id: format!("{}-{}", now.format("%Y%m%d-%H%M%S%.3f"), std::process::id()),
+        time: now.to_rfc3339(),
+        version: env!("CARGO_PKG_VERSION").into(),
+        os: std::env::consts::OS.into(),
+        arch: std::env::consts::ARCH.into(),
+        thread: thread.name().unwrap_or("<unnamed>").into(),
+        message: panic_message(info.payload()),
+        location: info.location().map(|l| format!("{}:{}:{}", l.file(), l.line(), l.column())),
+        backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+        logs,
+    }
+}
+
+/// Write `report` into `dir` and delete the oldest beyond [`MAX_REPORTS`]
+fn write_report(dir: &Path, report: &CrashReport) -> Result<PathBuf, String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.json", report.id));
+    let json = serde_json::to_string_pretty(report).map_err(|e| format!("Serialize error: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+
+    let reports = report_files(dir);
+    for old in reports.iter().take(reports.len().saturating_sub(MAX_REPORTS)) {
+        let _ = std::fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// Report files in `dir`, oldest first (ids start with a timestamp)
+fn report_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    files.retain(|p| p.extension().is_some_and(|e| e == "json"));
+    files.sort();
+    files
+}
+
+fn read_reports(dir: &Path) -> Vec<(PathBuf, CrashReport)> {
+    report_files(dir)
+        .into_iter()
+        .filter_map(|p| {
+            let report = serde_json::from_str(&std::fs::read_to_string(&p).ok()?).ok()?;
+            Some((p, report))
+        })
+        .collect()
+}
+
+/// Install the panic hook. Call first thing in `main`, after logging.
+pub fn install() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if IN_HOOK.with(|in_hook| in_hook.replace(true)) {
+            previous(info);
+            return;
+        }
+        let report = build_report(info);
+        tracing::error!(
+            "[Crash] Panic on thread '{}' at {}: {}",
+            report.thread,
+            report.location.as_deref().unwrap_or("?"),
+            report.message
+        );
+        if let Some(dir) = crash_dir() {
+            if let Err(e) = write_report(&dir, &report) {
+                tracing::error!("[Crash] Cannot save report: {}", e);
+            }
+        }
+        IN_HOOK.with(|in_hook| in_hook.set(false));
+        previous(info);
+    }));
+}
+
+/// Saved reports, newest first
+pub fn list() -> Vec<CrashReport> {
+    let mut reports: Vec<CrashReport> = crash_dir()
+        .map(|dir| read_reports(&dir).into_iter().map(|(_, r)| r).collect())
+        .unwrap_or_default();
+    reports.reverse();
+    reports
+}
+
+/// Delete every saved report
+pub fn clear() -> Result<(), String> {
+    let Some(dir) = crash_dir() else {
+        return Ok(());
+    };
+    for path in report_files(&dir) {
+        std::fs::remove_file(&path).map_err(|e| format!("Cannot delete {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Send saved reports to the Gateway, deleting each one it accepts
+async fn upload_pending() -> Result<usize, String> {
+    let Some(creds) = crate::connection::GatewayConnection::load_credentials() else {
+        return Ok(0);
+    };
+    let Some(dir) = crash_dir() else {
+        return Ok(0);
+    };
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let mut sent = 0;
+    for (path, report) in read_reports(&dir) {
+        let body = serde_json::json!({ "type": "crash_report", "report": report });
+        let build = || gw.post("/api/companion/crash-reports").json(&body).timeout(Duration::from_secs(30));
+        let resp = crate::connection::GatewayConnection::send_authenticated(&creds, build).await?;
+        if !resp.status().is_success() {
+            return Err(format!("Gateway HTTP {}", resp.status()));
+        }
+        let _ = std::fs::remove_file(&path);
+        sent += 1;
+    }
+    Ok(sent)
+}
+
+/// Upload pending reports in the background when uploading gets turned on
+/// (including at start-up)
+pub fn set_config(config: &CrashConfig) {
+    if UPLOAD.swap(config.upload, Ordering::Relaxed) || !config.upload {
+        return;
+    }
+    tauri::async_runtime::spawn(async {
+        match upload_pending().await {
+            Ok(0) => {}
+            Ok(n) => tracing::info!("[Crash] Uploaded {} report(s)", n),
+            Err(e) => tracing::warn!("[Crash] Upload failed, will retry next start: {}", e),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(id: &str) -> CrashReport {
+        CrashReport {
+            id: id.into(),
+            time: String::new(),
+            version: "1.0.0".into(),
+            os: "linux".into(),
+            arch: "x86_64".into(),
+            thread: "tokio-runtime-worker".into(),
+            message: "index out of bounds".into(),
+            location: Some("src/voice.rs:10:5".into()),
+            backtrace: String::new(),
+            logs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_reports_are_saved_and_pruned() {
+        let dir = std::env::temp_dir().join(format!("forgeai-crash-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        for i in 0..MAX_REPORTS + 3 {
+            write_report(&dir, &report(&format!("20260101-000000.{:03}-1", i))).unwrap();
+        }
+        let saved = read_reports(&dir);
+        assert_eq!(saved.len(), MAX_REPORTS);
+        // The oldest three were pruned
+        assert_eq!(saved[0].1.id, "20260101-000000.003-1");
+        assert_eq!(saved[0].1.message, "index out of bounds");
+
+        assert_eq!(panic_message(&"static str"), "static str");
+        assert_eq!(panic_message(&String::from("owned")), "owned");
+        assert_eq!(panic_message(&42), "Box<dyn Any>");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
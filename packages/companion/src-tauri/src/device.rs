@@ -0,0 +1,72 @@
+//! # Device Registration Metadata
+//!
+//! Describes this machine to the Gateway — OS, architecture, app version and
+//! the capability manifest — so it can route actions to the device that can
+//! actually run them. Sent with every pairing request and refreshed on
+//! startup (the OS or app may have been upgraded since pairing).
+
+use crate::capabilities::Manifest;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeviceInfo {
+    pub device_name: String,
+    pub device_type: &'static str,
+    pub os: &'static str,
+    pub os_version: Option<String>,
+    pub arch: &'static str,
+    pub app_version: &'static str,
+    pub capabilities: Manifest,
+}
+
+/// Metadata of this device
+pub fn info() -> DeviceInfo {
+    DeviceInfo {
+        device_name: hostname::get()
+            .map(|h| h.to_string_lossy().to_string())
+            .unwrap_or_else(|_| "Unknown".into()),
+        device_type: "desktop",
+        os: std::env::consts::OS,
+        os_version: sysinfo::System::long_os_version(),
+        arch: std::env::consts::ARCH,
+        app_version: env!("CARGO_PKG_VERSION"),
+        capabilities: crate::capabilities::local(),
+    }
+}
+
+/// Pairing request body: device metadata plus the given fields
+pub fn pairing_body(fields: serde_json::Value) -> serde_json::Value {
+    let mut body = serde_json::to_value(info()).unwrap_or_default();
+    if let (Some(body), serde_json::Value::Object(fields)) = (body.as_object_mut(), fields) {
+        body.extend(fields);
+    }
+    body
+}
+
+/// Send the current device metadata to the paired Gateway
+pub async fn update_on_gateway() -> Result<(), String> {
+    let creds = crate::connection::GatewayConnection::load_credentials()
+        .ok_or("Not connected — pair first")?;
+    let device = info();
+
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let build = || gw
+        .put("/api/companion/device")
+        .json(&device)
+        .timeout(std::time::Duration::from_secs(10));
+    let resp = crate::connection::GatewayConnection::send_authenticated(&creds, build).await?;
+
+    match resp.status() {
+        s if s.is_success() => {
+            tracing::info!("[Device] Registration metadata updated ({} {}, v{})", device.os, device.arch, device.app_version);
+            Ok(())
+        }
+        // Older Gateways only learn the device at pairing time
+        reqwest::StatusCode::NOT_FOUND => {
+            tracing::debug!("[Device] Gateway has no device endpoint, skipping update");
+            Ok(())
+        }
+        s => Err(format!("Gateway HTTP {}", s)),
+    }
+}
@@ -0,0 +1,138 @@
+//! # Diagnostics Bundle
+//!
+//! `export_diagnostics` writes a zip the user can attach to a bug report:
+//!
+//! | File            | Contents                                                   |
+//! |-----------------|------------------------------------------------------------|
+//! | `summary.json`  | version, OS, companion status, network counters            |
+//! | `settings.json` | all settings and the proxy config, secrets redacted        |
+//! | `devices.json`  | audio input / output devices                               |
+//! | `safety.json`   | the most recent safety verdicts                            |
+//! | `audio.json`    | audio pipeline counters and the last capture format        |
+//! | `metrics.json`  | latency and usage metrics (see `metrics`)                  |
+//! | `crashes.json`  | saved crash reports (see `crash`)                          |
+//! | `logs.jsonl`    | the most recent log entries (see `logging`)                |
+
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Log entries included in the bundle
+const LOG_ENTRIES: usize = 2000;
+
+fn recent_logs() -> String {
+    let query = crate::logging::LogQuery { limit: Some(LOG_ENTRIES), ..Default::default() };
+    crate::logging::query(&query)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|e| serde_json::to_string(e).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
+
+// ─── Zip ────────────────────────────────────────────
+
+/// Deflated zip archive of `(name, contents)` entries
+pub fn zip(entries: &[(&str, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    use chrono::{Datelike, Timelike};
+    let zip_error = |e: ::zip::result::ZipError| format!("Zip error: {}", e);
+    let now = chrono::Local::now().naive_local();
+    let modified = ::zip::DateTime::from_date_and_time(
+        now.year().clamp(1980, 2107) as u16,
+        now.month() as u8,
+        now.day() as u8,
+        now.hour() as u8,
+        now.minute() as u8,
+        now.second().min(59) as u8,
+    )
+    .unwrap_or_default();
+    let options = ::zip::write::SimpleFileOptions::default()
+        .compression_method(::zip::CompressionMethod::Deflated)
+        .last_modified_time(modified);
+
+    let mut archive = ::zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (name, data) in entries {
+        archive.start_file(*name, options).map_err(zip_error)?;
+        archive.write_all(data).map_err(|e| format!("Compression error: {}", e))?;
+    }
+    Ok(archive.finish().map_err(zip_error)?.into_inner())
+}
+
+// ─── Bundle ─────────────────────────────────────────
+
+fn json<T: Serialize>(value: &T) -> Vec<u8> {
+    serde_json::to_vec_pretty(value).unwrap_or_default()
+}
+
+fn default_path() -> Option<PathBuf> {
+    let dir = dirs::download_dir().or_else(dirs::home_dir)?;
+    let stamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    Some(dir.join(format!("forgeai-diagnostics-{}.zip", stamp)))
+}
+
+/// Collect the bundle and write it to `path` (default: Downloads). Returns the path.
+pub fn export(path: Option<String>) -> Result<String, String> {
+    let path = match path {
+        Some(p) => PathBuf::from(p),
+        None => default_path().ok_or("Cannot determine the Downloads directory")?,
+    };
+
+    let mut status = crate::status::snapshot();
+    if status.auth_token.is_some() {
+        status.auth_token = Some("********".into());
+    }
+    let summary = serde_json::json!({
+        "generatedAt": chrono::Local::now().to_rfc3339(),
+        "device": crate::device::info(),
+        "status": status,
+        "network": crate::netstats::snapshot(),
+    });
+    let settings = serde_json::json!({
+        "settings": crate::settings::get(),
+        "proxy": crate::proxy::config().redacted(),
+    });
+    let devices = serde_json::json!({
+        "inputs": crate::wake_word::list_audio_devices(),
+        "outputs": crate::voice::list_output_devices(),
+    });
+
+    let bundle = zip(&[
+        ("summary.json", json(&summary)),
+        ("settings.json", json(&settings)),
+        ("devices.json", json(&devices)),
+        ("safety.json", json(&crate::safety::recent_verdicts())),
+        ("audio.json", json(&crate::voice::stats())),
+        ("metrics.json", json(&crate::metrics::snapshot())),
+        ("crashes.json", json(&crate::crash::list())),
+        ("logs.jsonl", recent_logs().into_bytes()),
+    ])?;
+
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    std::fs::write(&path, bundle).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    tracing::info!("[Diagnostics] Bundle written to {}", path.display());
+    Ok(path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+
+    #[test]
+    fn test_zip_round_trip() {
+        let data = b"hello diagnostics ".repeat(50);
+        let archive = zip(&[("logs.txt", data.clone()), ("empty.json", Vec::new())]).unwrap();
+
+        let mut archive = ::zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+        assert_eq!(archive.len(), 2);
+        let mut logs = archive.by_name("logs.txt").unwrap();
+        assert_eq!(logs.compression(), ::zip::CompressionMethod::Deflated);
+        let mut inflated = Vec::new();
+        logs.read_to_end(&mut inflated).unwrap();
+        assert_eq!(inflated, data);
+        drop(logs);
+        assert_eq!(archive.by_name("empty.json").unwrap().size(), 0);
+    }
+}
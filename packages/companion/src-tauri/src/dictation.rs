@@ -0,0 +1,285 @@
+//! # Dictation
+//!
+//! System-wide dictation: while it runs, the companion records utterance
+//! after utterance, transcribes each through the Gateway and types the text
+//! into whatever application has focus, through the desktop automation
+//! layer (`type_text`, `send_keys`).
+//!
+//! Spoken commands, in English and Portuguese, are applied instead of typed:
+//!
+//! | Say                                    | Types          |
+//! |----------------------------------------|----------------|
+//! | "period", "comma", "question mark", …  | `.` `,` `?` …  |
+//! | "new line" / "nova linha"              | Enter          |
+//! | "new paragraph" / "novo parágrafo"     | Enter twice    |
+//! | "stop dictation" / "parar ditado"      | ends dictation |
+//!
+//! Sentences are capitalized after `.`, `?`, `!` and line breaks. Emits
+//! `dictation-state` when it starts and stops, and `dictation-text` with
+//! every typed piece.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use tauri::Manager;
+
+/// Stop flag of the running dictation
+static SESSION: std::sync::Mutex<Option<Arc<AtomicBool>>> = std::sync::Mutex::new(None);
+
+/// What a transcript turns into
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "text", rename_all = "camelCase")]
+pub enum Piece {
+    Text(String),
+    NewLine,
+    Stop,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Command {
+    Punctuation(char),
+    NewLine,
+    NewParagraph,
+    Stop,
+}
+
+/// Spoken commands, compared word by word after `intents::normalize`
+const COMMANDS: &[(&str, Command)] = &[
+    ("period", Command::Punctuation('.')),
+    ("full stop", Command::Punctuation('.')),
+    ("ponto final", Command::Punctuation('.')),
+    ("ponto", Command::Punctuation('.')),
+    ("comma", Command::Punctuation(',')),
+    ("virgula", Command::Punctuation(',')),
+    ("question mark", Command::Punctuation('?')),
+    ("ponto de interrogacao", Command::Punctuation('?')),
+    ("exclamation mark", Command::Punctuation('!')),
+    ("exclamation point", Command::Punctuation('!')),
+    ("ponto de exclamacao", Command::Punctuation('!')),
+    ("colon", Command::Punctuation(':')),
+    ("dois pontos", Command::Punctuation(':')),
+    ("semicolon", Command::Punctuation(';')),
+    ("ponto e virgula", Command::Punctuation(';')),
+    ("new line", Command::NewLine),
+    ("nova linha", Command::NewLine),
+    ("new paragraph", Command::NewParagraph),
+    ("novo paragrafo", Command::NewParagraph),
+    ("stop dictation", Command::Stop),
+    ("parar ditado", Command::Stop),
+    ("pare o ditado", Command::Stop),
+];
+/// Longest command, in words
+const MAX_COMMAND_WORDS: usize = 4;
+
+/// Formatting state carried from one utterance to the next
+#[derive(Debug, Clone)]
+pub struct Formatter {
+    capitalize: bool,
+    line_start: bool,
+}
+
+impl Default for Formatter {
+    fn default() -> Self {
+        Self { capitalize: true, line_start: true }
+    }
+}
+
+/// The command the words start with, and how many words it takes
+fn command_at(words: &[&str]) -> Option<(Command, usize)> {
+    // Word by word, so a filler such as "ok" is never swallowed by a command
+    let folded: Vec<String> = words.iter().take(MAX_COMMAND_WORDS).map(|w| crate::intents::normalize(w)).collect();
+    (1..=folded.len()).rev().find_map(|n| {
+        if folded[..n].iter().any(|w| w.is_empty()) {
+            return None;
+        }
+        let spoken = folded[..n].join(" ");
+        COMMANDS.iter().find(|(phrase, _)| *phrase == spoken).map(|(_, c)| (*c, n))
+    })
+}
+
+impl Formatter {
+    /// The pieces to type for one transcribed utterance
+    pub fn format(&mut self, transcript: &str) -> Vec<Piece> {
+        let words: Vec<&str> = transcript.split_whitespace().collect();
+        let mut pieces = Vec::new();
+        let mut text = String::new();
+        let mut i = 0;
+        while i < words.len() {
+            if let Some((command, n)) = command_at(&words[i..]) {
+                i += n;
+                match command {
+                    Command::Punctuation(mark) => {
+                        // The recognizer may have punctuated already
+                        let kept = text.trim_end_matches(['.', ',', '?', '!', ':', ';']).len();
+                        text.truncate(kept);
+                        text.push(mark);
+                        self.capitalize = matches!(mark, '.' | '?' | '!');
+                    }
+                    Command::NewLine | Command::NewParagraph => {
+                        if !text.is_empty() {
+                            pieces.push(Piece::Text(std::mem::take(&mut text)));
+                        }
+                        pieces.push(Piece::NewLine);
+                        if command == Command::NewParagraph {
+                            pieces.push(Piece::NewLine);
+                        }
+                        self.capitalize = true;
+                        self.line_start = true;
+                    }
+                    Command::Stop => {
+                        if !text.is_empty() {
+                            pieces.push(Piece::Text(text));
+                        }
+                        pieces.push(Piece::Stop);
+                        return pieces;
+                    }
+                }
+                continue;
+            }
+            let word = words[i];
+            i += 1;
+            if !self.line_start {
+                text.push(' ');
+            }
+            let mut chars = word.chars();
+            match (self.capitalize, chars.next()) {
+                (true, Some(first)) => {
+                    text.extend(first.to_uppercase());
+                    text.push_str(chars.as_str());
+                }
+                _ => text.push_str(word),
+            }
+            self.line_start = false;
+            self.capitalize = word.ends_with(['.', '?', '!']);
+        }
+        if !text.is_empty() {
+            pieces.push(Piece::Text(text));
+        }
+        pieces
+    }
+}
+
+// ─── Session ────────────────────────────────────────
+
+fn type_piece(piece: &Piece) -> Result<(), String> {
+    let params = match piece {
+        Piece::Text(text) => serde_json::json!({ "action": "type_text", "text": text }),
+        Piece::NewLine => serde_json::json!({ "action": "send_keys", "text": "{ENTER}" }),
+        Piece::Stop => return Ok(()),
+    };
+    // Starting dictation is the user's confirmation; blocked input still is
+    let result = crate::local_actions::execute_desktop(&params, true);
+    if result.success {
+        Ok(())
+    } else {
+        Err(format!("Typing failed: {}", result.output))
+    }
+}
+
+async fn run(app: &'static tauri::AppHandle, stop: Arc<AtomicBool>) {
+    let mut formatter = Formatter::default();
+    while !stop.load(Ordering::Relaxed) {
+        let Some(creds) = crate::connection::GatewayConnection::load_credentials() else {
+            tracing::warn!("[Dictation] Not paired, stopping");
+            break;
+        };
+        let recorded = tauri::async_runtime::spawn_blocking(move || {
+            let state = app.state::<crate::commands::VoiceState>();
+            let engine = state.0.lock().map_err(|e| e.to_string())?;
+            engine.record()
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r);
+        if stop.load(Ordering::Relaxed) {
+            break;
+        }
+        let audio = match recorded {
+            Ok(audio) => audio,
+            // Pauses between sentences make short recordings
+            Err(e) if e.contains("too short") => continue,
+            Err(e) => {
+                tracing::warn!("[Dictation] Recording failed, stopping: {}", e);
+                break;
+            }
+        };
+        let transcript = match crate::voice::VoiceEngine::new().transcribe(&creds, &audio).await {
+            Ok(text) => text,
+            Err(e) => {
+                tracing::warn!("[Dictation] Transcription failed: {}", e);
+                continue;
+            }
+        };
+        for piece in formatter.format(&transcript) {
+            if piece == Piece::Stop {
+                stop.store(true, Ordering::Relaxed);
+                break;
+            }
+            if let Err(e) = type_piece(&piece) {
+                tracing::warn!("[Dictation] {}", e);
+            }
+            crate::events::emit("dictation-text", &piece);
+        }
+    }
+    stop.store(true, Ordering::Relaxed);
+    crate::events::emit("dictation-state", serde_json::json!({ "active": false }));
+    tracing::info!("[Dictation] Stopped");
+}
+
+// ─── API ────────────────────────────────────────────
+
+pub fn active() -> bool {
+    SESSION.lock().ok().is_some_and(|s| s.as_ref().is_some_and(|stop| !stop.load(Ordering::Relaxed)))
+}
+
+/// Start typing what the user says into the focused application
+pub fn start() -> Result<(), String> {
+    if crate::voice::mic_muted() {
+        return Err("Microphone is muted".into());
+    }
+    let app = crate::events::app_handle().ok_or("Dictation needs the desktop app")?;
+    let stop = {
+        let mut session = SESSION.lock().map_err(|e| e.to_string())?;
+        if session.as_ref().is_some_and(|s| !s.load(Ordering::Relaxed)) {
+            return Err("Dictation is already running".into());
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        *session = Some(stop.clone());
+        stop
+    };
+    tracing::info!("[Dictation] Started");
+    crate::events::emit("dictation-state", serde_json::json!({ "active": true }));
+    tauri::async_runtime::spawn(run(app, stop));
+    Ok(())
+}
+
+/// Stop dictation, dropping the utterance being recorded
+pub fn stop() {
+    if let Ok(session) = SESSION.lock() {
+        if let Some(stop) = session.as_ref() {
+            stop.store(true, Ordering::Relaxed);
+            crate::hotkeys::stop_recording();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spoken_formatting() {
+        let mut f = Formatter::default();
+        assert_eq!(f.format("dear Ana comma"), vec![Piece::Text("Dear Ana,".into())]);
+        assert_eq!(
+            f.format("the report is ready. Period new paragraph thanks"),
+            vec![Piece::Text(" the report is ready.".into()), Piece::NewLine, Piece::NewLine, Piece::Text("Thanks".into())]
+        );
+        assert_eq!(f.format("is that ok question mark"), vec![Piece::Text(" is that ok?".into())]);
+        assert_eq!(f.format("yes. Stop dictation. and more"), vec![Piece::Text(" Yes.".into()), Piece::Stop]);
+
+        let mut pt = Formatter::default();
+        assert_eq!(pt.format("olá vírgula tudo bem ponto de interrogação"), vec![Piece::Text("Olá, tudo bem?".into())]);
+        assert_eq!(pt.format("Nova linha."), vec![Piece::NewLine]);
+    }
+}
@@ -0,0 +1,93 @@
+//! # LAN Gateway Discovery (mDNS / zeroconf)
+//!
+//! Browses the local network for `_forgeai-gateway._tcp` services so the
+//! pairing screen can offer a list of Gateways instead of asking the user
+//! to type an IP and port.
+//!
+//! Gateways advertise optional TXT records:
+//! - `name`    — display name (defaults to the mDNS instance name)
+//! - `version` — Gateway version
+//! - `tls`     — `1` when the Gateway serves HTTPS
+
+use mdns_sd::{ServiceDaemon, ServiceEvent, ServiceInfo};
+use serde::Serialize;
+use std::net::IpAddr;
+use std::time::{Duration, Instant};
+
+/// mDNS service type advertised by the Gateway
+const SERVICE_TYPE: &str = "_forgeai-gateway._tcp.local.";
+
+/// A Gateway found on the local network
+#[derive(Debug, Clone, Serialize)]
+pub struct DiscoveredGateway {
+    pub name: String,
+    pub url: String,
+    pub version: Option<String>,
+    pub host: String,
+    pub port: u16,
+}
+
+impl DiscoveredGateway {
+    fn from_info(info: &ServiceInfo) -> Option<Self> {
+        // Prefer IPv4 — link-local IPv6 addresses need a scope id to be usable in a URL
+        let addrs = info.get_addresses();
+        let ip = addrs
+            .iter()
+            .find(|a| a.is_ipv4())
+            .or_else(|| addrs.iter().next())?;
+        let host = match ip {
+            IpAddr::V4(v4) => v4.to_string(),
+            IpAddr::V6(v6) => format!("[{}]", v6),
+        };
+
+        let scheme = if info.get_property_val_str("tls") == Some("1") { "https" } else { "http" };
+        let instance = info
+            .get_fullname()
+            .strip_suffix(SERVICE_TYPE)
+            .unwrap_or(info.get_fullname())
+            .trim_end_matches('.')
+            .to_string();
+
+        Some(Self {
+            name: info
+                .get_property_val_str("name")
+                .map(String::from)
+                .unwrap_or(instance),
+            url: format!("{}://{}:{}", scheme, host, info.get_port()),
+            version: info.get_property_val_str("version").map(String::from),
+            host,
+            port: info.get_port(),
+        })
+    }
+}
+
+/// Browse the LAN for Gateways for `timeout`, returning every one resolved.
+/// Blocking — call from a blocking task.
+pub fn discover(timeout: Duration) -> Result<Vec<DiscoveredGateway>, String> {
+    let daemon = ServiceDaemon::new().map_err(|e| format!("mDNS unavailable: {}", e))?;
+    let receiver = daemon
+        .browse(SERVICE_TYPE)
+        .map_err(|e| format!("mDNS browse failed: {}", e))?;
+
+    let deadline = Instant::now() + timeout;
+    let mut found: Vec<DiscoveredGateway> = Vec::new();
+
+    while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+        match receiver.recv_timeout(remaining) {
+            Ok(ServiceEvent::ServiceResolved(info)) => {
+                if let Some(gw) = DiscoveredGateway::from_info(&info) {
+                    if !found.iter().any(|g| g.url == gw.url) {
+                        tracing::info!("[Discovery] Found Gateway '{}' at {}", gw.name, gw.url);
+                        found.push(gw);
+                    }
+                }
+            }
+            Ok(_) => {}
+            Err(_) => break,
+        }
+    }
+
+    let _ = daemon.stop_browse(SERVICE_TYPE);
+    let _ = daemon.shutdown();
+    Ok(found)
+}
@@ -0,0 +1,352 @@
+//! # Duplex Conversation
+//!
+//! Natural conversation instead of strict record → upload → answer turns:
+//! `duplex_start` opens an audio channel to the Gateway (`/ws/voice`) that
+//! streams the microphone upstream continuously and plays the synthesized
+//! reply as it streams down, so the user can talk over it at any moment.
+//!
+//! Wire format, 16 kHz mono in both directions:
+//!
+//! - binary messages — 16-bit little-endian PCM (20 ms frames upstream)
+//! - text messages — JSON control. The companion sends `duplex.start` and
+//!   `duplex.stop`; the Gateway sends `transcript` and `reply` (re-emitted
+//!   as `duplex-event`) and `playback.clear` when the user interrupts, which
+//!   drops the reply audio not yet played.
+//!
+//! The microphone passes an [`EchoCanceller`] fed with what the speakers
+//! play, so the reply is not heard back as user speech. Needs a Gateway
+//! that advertises `duplex_audio`; `chat_voice` stays the turn-based
+//! fallback.
+
+use crate::connection::CompanionCredentials;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use forgeai_companion_core::audio::{self, EchoCanceller};
+use futures_util::{SinkExt, StreamExt};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+
+/// Upstream frame: 20 ms at 16 kHz
+const FRAME: usize = 320;
+/// Echo paths up to 128 ms are cancelled
+const ECHO_TAPS: usize = 2048;
+/// Longest reply audio buffered ahead of the speakers
+const MAX_BUFFERED_SECS: usize = 60;
+
+/// Stop flag of the running session
+static SESSION: Mutex<Option<Arc<AtomicBool>>> = Mutex::new(None);
+
+/// Audio shared between the stream callbacks, the audio thread and the
+/// channel reader; mono at the output device's rate
+struct Speaker {
+    rate: u32,
+    /// Reply audio waiting to be played
+    pending: VecDeque<f32>,
+    /// What was played and not yet given to the echo canceller
+    played: VecDeque<f32>,
+}
+
+type Shared = Arc<Mutex<Speaker>>;
+
+fn voice_url(creds: &CompanionCredentials) -> String {
+    let base = creds.gateway_url.replace("https://", "wss://").replace("http://", "ws://");
+    let mut url = format!("{}/ws/voice?companionId={}", base, creds.companion_id);
+    if let Some(token) = &creds.auth_token {
+        url.push_str(&format!("&token={}", token));
+    }
+    url
+}
+
+/// Split `samples` into upstream frames, keeping the remainder in `carry`
+fn frames(carry: &mut Vec<f32>, samples: &[f32]) -> Vec<Vec<u8>> {
+    carry.extend_from_slice(samples);
+    let whole = carry.len() / FRAME * FRAME;
+    let out = carry[..whole].chunks(FRAME).map(audio::encode_pcm16).collect();
+    carry.drain(..whole);
+    out
+}
+
+// ─── Audio thread ───────────────────────────────────
+
+/// Capture, echo-cancel and frame the microphone; play `speaker.pending`.
+/// cpal streams cannot leave their thread, so this owns both until `stop`.
+fn run_audio(
+    stop: Arc<AtomicBool>,
+    speaker: Shared,
+    upstream: mpsc::UnboundedSender<Vec<u8>>,
+    ready: std::sync::mpsc::Sender<Result<(), String>>,
+) {
+    let input = crate::voice::input_device().ok_or("No audio input device".to_string());
+    let output = crate::voice::output_device().ok_or("No audio output device".to_string());
+    let (input, output) = match (input, output) {
+        (Ok(i), Ok(o)) => (i, o),
+        (Err(e), _) | (_, Err(e)) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+    let configs = input.default_input_config().and_then(|i| Ok((i, output.default_output_config()?)));
+    let (in_config, out_config) = match configs {
+        Ok(c) => c,
+        Err(e) => {
+            let _ = ready.send(Err(format!("No supported audio config: {}", e)));
+            return;
+        }
+    };
+    let (in_rate, in_channels) = (in_config.sample_rate().0, in_config.channels() as usize);
+    let (out_rate, out_channels) = (out_config.sample_rate().0, out_config.channels() as usize);
+    if let Ok(mut s) = speaker.lock() {
+        s.rate = out_rate;
+    }
+
+    let (mic_tx, mic_rx) = std::sync::mpsc::sync_channel::<Vec<f32>>(128);
+    let input_stream = input.build_input_stream(
+        &in_config.into(),
+        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            let _ = mic_tx.try_send(data.to_vec());
+        },
+        |err| tracing::error!("[Duplex] Capture error: {}", err),
+        None,
+    );
+    let feed = speaker.clone();
+    let output_stream = output.build_output_stream(
+        &out_config.into(),
+        move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+            let Ok(mut s) = feed.lock() else {
+                data.fill(0.0);
+                return;
+            };
+            for frame in data.chunks_mut(out_channels) {
+                let sample = s.pending.pop_front().unwrap_or(0.0);
+                s.played.push_back(sample);
+                frame.fill(sample);
+            }
+        },
+        |err| tracing::error!("[Duplex] Playback error: {}", err),
+        None,
+    );
+    let streams = input_stream
+        .map_err(|e| format!("Failed to build input stream: {}", e))
+        .and_then(|i| Ok((i, output_stream.map_err(|e| format!("Failed to build output stream: {}", e))?)));
+    let (input_stream, output_stream) = match streams {
+        Ok(s) => s,
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+    if let Err(e) = input_stream.play().and_then(|_| output_stream.play()) {
+        let _ = ready.send(Err(format!("Failed to start audio: {}", e)));
+        return;
+    }
+    let _ = ready.send(Ok(()));
+    tracing::info!("[Duplex] Audio running: mic {} Hz × {}, speakers {} Hz × {}", in_rate, in_channels, out_rate, out_channels);
+
+    let mut aec = EchoCanceller::new(ECHO_TAPS);
+    let mut carry = Vec::new();
+    while !stop.load(Ordering::Relaxed) {
+        let chunk = match mic_rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(chunk) => chunk,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(_) => break,
+        };
+        let near = audio::resample(&audio::downmix(&chunk, in_channels), in_rate, audio::TARGET_RATE);
+        // The far end over the same span: what the speakers played meanwhile
+        let wanted = (near.len() as u64 * out_rate as u64 / audio::TARGET_RATE as u64) as usize;
+        let played: Vec<f32> = match speaker.lock() {
+            Ok(mut s) => {
+                let n = wanted.min(s.played.len());
+                let taken = s.played.drain(..n).collect();
+                // Playback running ahead of capture would skew the alignment
+                let excess = s.played.len().saturating_sub(wanted * 4);
+                s.played.drain(..excess);
+                taken
+            }
+            Err(_) => Vec::new(),
+        };
+        let far = audio::resample(&played, out_rate, audio::TARGET_RATE);
+        let clean = if crate::voice::mic_muted() { vec![0.0; near.len()] } else { aec.process(&near, &far) };
+        for frame in frames(&mut carry, &clean) {
+            if upstream.send(frame).is_err() {
+                stop.store(true, Ordering::Relaxed);
+                break;
+            }
+        }
+    }
+    drop(input_stream);
+    drop(output_stream);
+    tracing::info!("[Duplex] Audio stopped");
+}
+
+// ─── Channel ────────────────────────────────────────
+
+/// Handle a control message from the Gateway
+fn on_control(text: &str, speaker: &Shared) {
+    let Ok(message) = serde_json::from_str::<serde_json::Value>(text) else {
+        return;
+    };
+    match message["type"].as_str().unwrap_or("") {
+        "playback.clear" => {
+            if let Ok(mut s) = speaker.lock() {
+                s.pending.clear();
+            }
+            crate::events::emit("duplex-event", &message);
+        }
+        "transcript" | "reply" | "error" => crate::events::emit("duplex-event", &message),
+        _ => {}
+    }
+}
+
+/// Queue a downstream PCM chunk for the speakers
+fn on_audio(bytes: &[u8], speaker: &Shared) {
+    let samples = audio::decode_pcm16(bytes);
+    if let Ok(mut s) = speaker.lock() {
+        let resampled = audio::resample(&samples, audio::TARGET_RATE, s.rate);
+        let room = (s.rate as usize * MAX_BUFFERED_SECS).saturating_sub(s.pending.len());
+        s.pending.extend(resampled.into_iter().take(room));
+    }
+}
+
+async fn run(stop: Arc<AtomicBool>, creds: CompanionCredentials, session_id: Option<String>) -> Result<(), String> {
+    let connector = crate::tls_trust::ws_connector(&creds.gateway_url);
+    let ws = crate::proxy::connect_ws(&voice_url(&creds), connector)
+        .await
+        .map_err(|e| format!("Cannot open the audio channel: {}", e))?;
+    let (mut write, mut read) = ws.split();
+    let start = serde_json::json!({
+        "type": "duplex.start",
+        "sampleRate": audio::TARGET_RATE,
+        "encoding": "pcm_s16le",
+        "sessionId": session_id,
+        "echoCancellation": true,
+        "voice": crate::voice::tts_voice(),
+        "lexicon": crate::lexicon::entries(),
+        "sttProvider": crate::voice::providers().stt,
+        "ttsProvider": crate::voice::providers().tts,
+    });
+    write.send(Message::Text(start.to_string())).await.map_err(|e| e.to_string())?;
+
+    let speaker: Shared = Arc::new(Mutex::new(Speaker { rate: audio::TARGET_RATE, pending: VecDeque::new(), played: VecDeque::new() }));
+    let (upstream_tx, mut upstream_rx) = mpsc::unbounded_channel::<Vec<u8>>();
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    {
+        let (stop, speaker) = (stop.clone(), speaker.clone());
+        std::thread::spawn(move || run_audio(stop, speaker, upstream_tx, ready_tx));
+    }
+    let started = tauri::async_runtime::spawn_blocking(move || ready_rx.recv().unwrap_or(Err("Audio thread exited".into())))
+        .await
+        .map_err(|e| e.to_string())?;
+    if let Err(e) = started {
+        stop.store(true, Ordering::Relaxed);
+        return Err(e);
+    }
+    crate::events::emit("voice-state", serde_json::json!({ "state": "duplex" }));
+
+    let mut check = tokio::time::interval(Duration::from_millis(200));
+    // PCM bytes each way, for the usage statistics
+    let (mut sent, mut received) = (0usize, 0usize);
+    let result = loop {
+        tokio::select! {
+            frame = upstream_rx.recv() => match frame {
+                Some(frame) => {
+                    sent += frame.len();
+                    if let Err(e) = write.send(Message::Binary(frame)).await {
+                        break Err(format!("Audio channel write failed: {}", e));
+                    }
+                }
+                None => break Ok(()),
+            },
+            incoming = read.next() => match incoming {
+                Some(Ok(Message::Binary(bytes))) => {
+                    received += bytes.len();
+                    on_audio(&bytes, &speaker)
+                }
+                Some(Ok(Message::Text(text))) => on_control(&text, &speaker),
+                Some(Ok(Message::Close(_))) | None => break Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => break Err(format!("Audio channel closed: {}", e)),
+            },
+            _ = check.tick() => {
+                if stop.load(Ordering::Relaxed) {
+                    let _ = write.send(Message::Text(serde_json::json!({ "type": "duplex.stop" }).to_string())).await;
+                    let _ = write.send(Message::Close(None)).await;
+                    break Ok(());
+                }
+            }
+        }
+    };
+    stop.store(true, Ordering::Relaxed);
+    let ms = |bytes: usize| (bytes / 2 * 1000 / audio::TARGET_RATE as usize) as u64;
+    crate::usage::record(crate::usage::Metric::RecordingMs, ms(sent));
+    crate::usage::record(crate::usage::Metric::AudioSentMs, ms(sent));
+    crate::usage::record(crate::usage::Metric::SpeechMs, ms(received));
+    result
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Whether a duplex conversation is running
+pub fn active() -> bool {
+    SESSION.lock().ok().is_some_and(|s| s.as_ref().is_some_and(|stop| !stop.load(Ordering::Relaxed)))
+}
+
+/// Start a duplex conversation; it runs until [`stop`] or until the
+/// Gateway closes the channel
+pub fn start(session_id: Option<String>) -> Result<(), String> {
+    if crate::voice::mic_muted() {
+        return Err("Microphone is muted".into());
+    }
+    let creds = crate::connection::GatewayConnection::load_credentials().ok_or("Not paired with a Gateway")?;
+    if !crate::capabilities::gateway_supports(crate::capabilities::feature::DUPLEX_AUDIO, false) {
+        return Err("The Gateway does not support duplex audio — use push-to-talk".into());
+    }
+    let (stop, mic) = {
+        let mut session = SESSION.lock().map_err(|e| e.to_string())?;
+        if session.as_ref().is_some_and(|s| !s.load(Ordering::Relaxed)) {
+            return Err("A duplex conversation is already running".into());
+        }
+        let mic = crate::mic_arbiter::claim(crate::mic_arbiter::Owner::Duplex, "duplex conversation")?;
+        let stop = Arc::new(AtomicBool::new(false));
+        *session = Some(stop.clone());
+        (stop, mic)
+    };
+    tracing::info!("[Duplex] Starting");
+    tauri::async_runtime::spawn(async move {
+        // The microphone is the conversation's until it ends
+        let _mic = mic;
+        if let Err(e) = run(stop, creds, session_id).await {
+            tracing::warn!("[Duplex] {}", e);
+            crate::events::emit("duplex-event", serde_json::json!({ "type": "error", "message": e }));
+        }
+        crate::events::emit("voice-state", serde_json::json!({ "state": "idle" }));
+        tracing::info!("[Duplex] Ended");
+    });
+    Ok(())
+}
+
+/// End the running duplex conversation, if any
+pub fn stop() {
+    if let Ok(session) = SESSION.lock() {
+        if let Some(stop) = session.as_ref() {
+            stop.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upstream_framing() {
+        let mut carry = Vec::new();
+        assert!(frames(&mut carry, &[0.1; 300]).is_empty());
+        let out = frames(&mut carry, &[0.1; 400]);
+        assert_eq!(out.len(), 2);
+        assert!(out.iter().all(|f| f.len() == FRAME * 2));
+        assert_eq!(carry.len(), 60);
+    }
+}
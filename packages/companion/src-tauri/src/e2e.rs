@@ -0,0 +1,369 @@
+//! # End-to-End Encryption of Action Payloads
+//!
+//! A reverse proxy in front of the Gateway terminates TLS and could read
+//! file contents or inject commands. To prevent that, the companion and the
+//! Gateway agree on a key at pairing time (X25519 + HKDF-SHA256) and seal
+//! action payloads with ChaCha20-Poly1305.
+//!
+//! The pairing code and both public keys are mixed into the key derivation,
+//! and the Gateway proves it derived the same key by answering with
+//! `e2eConfirm`. A proxy that swaps the public keys without knowing the code
+//! therefore ends up with mismatched keys and the pairing is refused. The
+//! code itself still crosses the proxy when it is typed into the companion,
+//! so pair over a network you trust for full protection.
+//!
+//! On the wire only `type` and `requestId` stay in clear; everything else
+//! moves into `encrypted: { nonce, ciphertext }` (base64). Both clear fields
+//! are bound as associated data, so a sealed payload cannot be replayed
+//! under another request or message type. Each direction seals with its own
+//! key, derived from the session key with HKDF, so a message cannot be
+//! reflected back to its sender. Every sealed body carries a `seq` counter
+//! that must increase, so it cannot be replayed at all. Once a key exists,
+//! plaintext action requests are rejected.
+
+use base64::engine::general_purpose::STANDARD as B64;
+use base64::Engine as _;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use hkdf::Hkdf;
+use sha2::Sha256;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use x25519_dalek::{EphemeralSecret, PublicKey};
+
+const HKDF_INFO: &[u8] = b"forgeai-companion:e2e:v2";
+/// HKDF labels of the per-direction keys derived from the session key
+const TO_GATEWAY_INFO: &[u8] = b"forgeai-companion:e2e:companion-to-gateway";
+const TO_COMPANION_INFO: &[u8] = b"forgeai-companion:e2e:gateway-to-companion";
+
+/// Keys of the paired session, derived from the stored credentials on first
+/// use: `None` until loaded, then `None` inside when not paired with E2E
+static SESSION_KEYS: Mutex<Option<Option<Result<DirectionKeys, String>>>> = Mutex::new(None);
+/// Last `seq` sent to the Gateway (loaded from disk on first use)
+static SENT_SEQ: Mutex<Option<u64>> = Mutex::new(None);
+/// Highest `seq` accepted from the Gateway (loaded from disk on first use)
+static RECEIVED_SEQ: Mutex<Option<u64>> = Mutex::new(None);
+
+const SENT_SEQ_FILE: &str = "e2e_sent_seq";
+const RECEIVED_SEQ_FILE: &str = "e2e_seq";
+
+/// Session key plus the tag the Gateway must echo as `e2eConfirm`
+pub struct SessionKey {
+    pub key: String,
+    pub confirmation: String,
+}
+
+/// Companion half of the pairing key exchange
+pub struct Handshake {
+    secret: EphemeralSecret,
+    /// Base64 public key sent to the Gateway as `e2ePublicKey`
+    pub public_key: String,
+}
+
+impl Handshake {
+    pub fn new() -> Self {
+        let secret = EphemeralSecret::random_from_rng(OsRng);
+        let public_key = B64.encode(PublicKey::from(&secret).as_bytes());
+        Self { secret, public_key }
+    }
+
+    /// Derive the session key from the Gateway's public key. The pairing code
+    /// and both public keys are bound into it, so both sides only agree if
+    /// they saw the same exchange and know the same code.
+    pub fn derive(self, peer_public_key: &str, companion_id: &str, pairing_code: &str) -> Result<SessionKey, String> {
+        let peer: [u8; 32] = B64
+            .decode(peer_public_key)
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or("Invalid Gateway E2E public key")?;
+        let own = B64.decode(&self.public_key).map_err(|e| format!("Invalid E2E public key: {}", e))?;
+        let shared = self.secret.diffie_hellman(&PublicKey::from(peer));
+        if !shared.was_contributory() {
+            return Err("Rejected weak Gateway E2E public key".into());
+        }
+
+        // Keys in a fixed order so both ends build the same transcript
+        let (first, second) = if own.as_slice() < peer.as_slice() { (&own[..], &peer[..]) } else { (&peer[..], &own[..]) };
+        let code = pairing_code.trim().to_ascii_uppercase();
+        let mut info = HKDF_INFO.to_vec();
+        info.extend_from_slice(&(code.len() as u32).to_be_bytes());
+        info.extend_from_slice(code.as_bytes());
+        info.extend_from_slice(first);
+        info.extend_from_slice(second);
+
+        let mut okm = [0u8; 64];
+        Hkdf::<Sha256>::new(Some(companion_id.as_bytes()), shared.as_bytes())
+            .expand(&info, &mut okm)
+            .map_err(|e| format!("Key derivation error: {}", e))?;
+        Ok(SessionKey {
+            key: B64.encode(&okm[..32]),
+            confirmation: B64.encode(&okm[32..]),
+        })
+    }
+}
+
+impl SessionKey {
+    /// Check the Gateway's `e2eConfirm`
+    pub fn confirmed_by(&self, gateway_confirmation: Option<&str>) -> Result<(), String> {
+        let expected = self.confirmation.as_bytes();
+        let matches = gateway_confirmation.is_some_and(|c| {
+            c.len() == expected.len() && c.bytes().zip(expected).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+        });
+        if matches {
+            Ok(())
+        } else {
+            Err("Pairing failed: the Gateway could not confirm the end-to-end key (outdated Gateway or tampered exchange)".into())
+        }
+    }
+}
+
+/// The keys each direction seals with
+#[derive(Clone)]
+struct DirectionKeys {
+    send: [u8; 32],
+    receive: [u8; 32],
+}
+
+impl DirectionKeys {
+    fn derive(session_key_b64: &str) -> Result<Self, String> {
+        let key = B64.decode(session_key_b64).map_err(|e| format!("Invalid E2E key: {}", e))?;
+        if key.len() != 32 {
+            return Err("Invalid E2E key length".into());
+        }
+        let hkdf = Hkdf::<Sha256>::new(None, &key);
+        let (mut send, mut receive) = ([0u8; 32], [0u8; 32]);
+        hkdf.expand(TO_GATEWAY_INFO, &mut send)
+            .and_then(|_| hkdf.expand(TO_COMPANION_INFO, &mut receive))
+            .map_err(|e| format!("Key derivation error: {}", e))?;
+        Ok(Self { send, receive })
+    }
+}
+
+/// Associated data binding a sealed body to its clear `type` and `requestId`
+fn associated_data(message_type: &str, request_id: &str) -> Vec<u8> {
+    let mut aad = (message_type.len() as u32).to_be_bytes().to_vec();
+    aad.extend_from_slice(message_type.as_bytes());
+    aad.extend_from_slice(request_id.as_bytes());
+    aad
+}
+
+/// Seal a JSON value, binding it to `aad`
+fn seal(key: &[u8; 32], aad: &[u8], value: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let plaintext = serde_json::to_vec(value).map_err(|e| format!("Serialize error: {}", e))?;
+    let ciphertext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .encrypt(&nonce, Payload { msg: &plaintext, aad })
+        .map_err(|e| format!("Encrypt error: {}", e))?;
+    Ok(serde_json::json!({
+        "nonce": B64.encode(nonce),
+        "ciphertext": B64.encode(ciphertext),
+    }))
+}
+
+/// Open a value produced by [`seal`] with the same `aad`
+fn open(key: &[u8; 32], aad: &[u8], sealed: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let field = |name: &str| {
+        sealed[name]
+            .as_str()
+            .and_then(|s| B64.decode(s).ok())
+            .ok_or(format!("Encrypted payload missing {}", name))
+    };
+    let nonce = field("nonce")?;
+    if nonce.len() != 12 {
+        return Err("Invalid E2E nonce".into());
+    }
+    let ciphertext = field("ciphertext")?;
+    let plaintext = ChaCha20Poly1305::new(Key::from_slice(key))
+        .decrypt(Nonce::from_slice(&nonce), Payload { msg: &ciphertext, aad })
+        .map_err(|_| "E2E decrypt failed (tampered or wrong key)".to_string())?;
+    serde_json::from_slice(&plaintext).map_err(|e| format!("Invalid decrypted payload: {}", e))
+}
+
+/// The session's keys, or `None` when not paired with E2E. Cached: the
+/// credentials live in the OS keychain, too slow to ask on every message.
+fn session_keys() -> Option<Result<DirectionKeys, String>> {
+    let load = || {
+        crate::connection::GatewayConnection::load_credentials()
+            .and_then(|c| c.e2e_key)
+            .map(|key| DirectionKeys::derive(&key))
+    };
+    match SESSION_KEYS.lock() {
+        Ok(mut cached) => cached.get_or_insert_with(load).clone(),
+        Err(_) => load(),
+    }
+}
+
+/// Drop the cached session keys; called whenever the credentials change
+pub fn forget_key() {
+    if let Ok(mut cached) = SESSION_KEYS.lock() {
+        *cached = None;
+    }
+}
+
+fn seq_path(name: &str) -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join(name))
+}
+
+fn load_seq(name: &str) -> u64 {
+    seq_path(name)
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+fn store_seq(name: &str, seq: u64) {
+    if let Some(path) = seq_path(name) {
+        if let Err(e) = std::fs::write(&path, seq.to_string()) {
+            tracing::warn!("[E2E] Cannot persist sequence number: {}", e);
+        }
+    }
+}
+
+/// Next outgoing `seq`: one more than the last, kept on disk so it keeps
+/// increasing across restarts
+fn next_seq() -> Result<u64, String> {
+    let mut sent = SENT_SEQ.lock().map_err(|_| "E2E state poisoned".to_string())?;
+    let seq = *sent.get_or_insert_with(|| load_seq(SENT_SEQ_FILE)) + 1;
+    *sent = Some(seq);
+    store_seq(SENT_SEQ_FILE, seq);
+    Ok(seq)
+}
+
+/// Whether `seq` is newer than everything accepted so far
+fn is_fresh(last: u64, seq: Option<u64>) -> Result<u64, String> {
+    match seq {
+        Some(seq) if seq > last => Ok(seq),
+        Some(_) => Err("Replayed E2E message rejected".into()),
+        None => Err("E2E message without sequence number rejected — pair again".into()),
+    }
+}
+
+/// Record an incoming `seq`, refusing replays
+fn accept_seq(seq: Option<u64>) -> Result<(), String> {
+    let mut received = RECEIVED_SEQ.lock().map_err(|_| "E2E state poisoned".to_string())?;
+    let last = *received.get_or_insert_with(|| load_seq(RECEIVED_SEQ_FILE));
+    let seq = is_fresh(last, seq)?;
+    *received = Some(seq);
+    store_seq(RECEIVED_SEQ_FILE, seq);
+    Ok(())
+}
+
+/// Start fresh sequences in both directions and reload the key (after
+/// pairing, with a new key)
+pub fn reset_sequence() {
+    forget_key();
+    for (seq, file) in [(&SENT_SEQ, SENT_SEQ_FILE), (&RECEIVED_SEQ, RECEIVED_SEQ_FILE)] {
+        if let Ok(mut seq) = seq.lock() {
+            *seq = Some(0);
+        }
+        if let Some(path) = seq_path(file) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+/// Decrypt an incoming Gateway message if E2E is active.
+/// Plaintext messages are refused once a key has been established.
+pub fn unwrap_incoming(raw: &serde_json::Value) -> Result<serde_json::Value, String> {
+    let aad = associated_data(raw["type"].as_str().unwrap_or(""), raw["requestId"].as_str().unwrap_or(""));
+    match (session_keys(), raw.get("encrypted")) {
+        (Some(Err(e)), _) => Err(e),
+        (Some(Ok(keys)), Some(sealed)) => {
+            let mut inner = open(&keys.receive, &aad, sealed)?;
+            accept_seq(inner["seq"].as_u64())?;
+            if let Some(obj) = inner.as_object_mut() {
+                obj.remove("seq");
+                obj.insert("type".into(), raw["type"].clone());
+                obj.insert("requestId".into(), raw["requestId"].clone());
+            }
+            Ok(inner)
+        }
+        (Some(Ok(_)), None) => Err("Plaintext action rejected — end-to-end encryption is active".into()),
+        (None, Some(_)) => Err("Encrypted action received but no E2E key is established — pair again".into()),
+        (None, None) => Ok(raw.clone()),
+    }
+}
+
+/// Encrypt an outgoing message if E2E is active, keeping `type` and
+/// `requestId` in clear for routing
+pub fn wrap_outgoing(message: serde_json::Value) -> serde_json::Value {
+    let Some(keys) = session_keys() else {
+        return message;
+    };
+    let message_type = message["type"].as_str().unwrap_or("").to_string();
+    let request_id = message["requestId"].as_str().unwrap_or("").to_string();
+    let sealed = keys.and_then(|keys| {
+        let mut body = message.clone();
+        if let Some(obj) = body.as_object_mut() {
+            obj.remove("type");
+            obj.remove("requestId");
+            obj.insert("seq".into(), next_seq()?.into());
+        }
+        seal(&keys.send, &associated_data(&message_type, &request_id), &body)
+    });
+    match sealed {
+        Ok(sealed) => serde_json::json!({
+            "type": message["type"],
+            "requestId": request_id,
+            "encrypted": sealed,
+        }),
+        Err(e) => {
+            // Never fall back to plaintext once E2E is active
+            tracing::error!("[E2E] Failed to seal {}: {}", message["type"], e);
+            serde_json::json!({
+                "type": message["type"],
+                "requestId": request_id,
+                "error": "E2E encryption failed",
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handshake_agrees_on_key() {
+        let companion = Handshake::new();
+        let gateway = Handshake::new();
+        let (c_pub, g_pub) = (companion.public_key.clone(), gateway.public_key.clone());
+        let k1 = companion.derive(&g_pub, "comp-1", "ABCD-1234").unwrap();
+        let k2 = gateway.derive(&c_pub, "comp-1", "abcd-1234").unwrap();
+        assert_eq!(k1.key, k2.key);
+        assert!(k1.confirmed_by(Some(&k2.confirmation)).is_ok());
+        assert!(k1.confirmed_by(None).is_err());
+
+        // A different code (or a swapped key) gives a different key and no confirmation
+        let companion = Handshake::new();
+        let gateway = Handshake::new();
+        let (c_pub, g_pub) = (companion.public_key.clone(), gateway.public_key.clone());
+        let k1 = companion.derive(&g_pub, "comp-1", "ABCD-1234").unwrap();
+        let k2 = gateway.derive(&c_pub, "comp-1", "ABCD-9999").unwrap();
+        assert_ne!(k1.key, k2.key);
+        assert!(k1.confirmed_by(Some(&k2.confirmation)).is_err());
+    }
+
+    #[test]
+    fn test_sequence_rejects_replays() {
+        assert_eq!(is_fresh(0, Some(5)), Ok(5));
+        assert!(is_fresh(5, Some(5)).is_err());
+        assert!(is_fresh(5, Some(4)).is_err());
+        assert!(is_fresh(5, None).is_err());
+    }
+
+    #[test]
+    fn test_seal_open_binds_type_request_and_direction() {
+        let keys = DirectionKeys::derive(&B64.encode([7u8; 32])).unwrap();
+        assert_ne!(keys.send, keys.receive);
+        let value = serde_json::json!({ "action": "read_file", "params": { "path": "a.txt" } });
+        let aad = associated_data("action_request", "req-1");
+        let sealed = seal(&keys.receive, &aad, &value).unwrap();
+        assert_eq!(open(&keys.receive, &aad, &sealed).unwrap(), value);
+        assert!(open(&keys.receive, &associated_data("action_request", "req-2"), &sealed).is_err());
+        assert!(open(&keys.receive, &associated_data("action_result", "req-1"), &sealed).is_err());
+        // A message the companion sent cannot be reflected back to it
+        let sent = seal(&keys.send, &aad, &value).unwrap();
+        assert!(open(&keys.receive, &aad, &sent).is_err());
+    }
+}
@@ -0,0 +1,81 @@
+//! # Earcons
+//!
+//! Short generated sounds for events the user should hear rather than read.
+//! They are synthesized on the fly (no bundled audio files) and played
+//! through the playback queue like any other audio, so quiet hours mute
+//! them too (alarms only when configured to).
+//!
+//! | Earcon  | Sound                                   |
+//! |---------|-----------------------------------------|
+//! | `Timer` | two rising notes                        |
+//! | `Alarm` | three bursts of four short beeps        |
+
+use forgeai_companion_core::audio;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Earcon {
+    /// A countdown timer finished
+    Timer,
+    /// An alarm went off
+    Alarm,
+}
+
+/// Notes as (frequency in Hz, seconds); frequency 0 is a pause
+fn notes(earcon: Earcon) -> Vec<(f32, f32)> {
+    match earcon {
+        Earcon::Timer => vec![(660.0, 0.15), (0.0, 0.05), (880.0, 0.3)],
+        Earcon::Alarm => {
+            let burst = [(1000.0, 0.08), (0.0, 0.06)].repeat(4);
+            [burst, vec![(0.0, 0.5)]].concat().repeat(3)
+        }
+    }
+}
+
+/// Mono samples of `earcon` at `rate`
+fn render(earcon: Earcon, rate: u32) -> Vec<f32> {
+    notes(earcon)
+        .into_iter()
+        .flat_map(|(freq, secs)| {
+            if freq > 0.0 {
+                let mut tone = audio::sine(freq, rate, secs);
+                // 5 ms fades so notes don't click
+                let fade = (rate as usize / 200).min(tone.len() / 2);
+                for i in 0..fade {
+                    let gain = i as f32 / fade as f32;
+                    let last = tone.len() - 1 - i;
+                    tone[i] *= gain;
+                    tone[last] *= gain;
+                }
+                tone
+            } else {
+                vec![0.0; (rate as f32 * secs) as usize]
+            }
+        })
+        .collect()
+}
+
+/// Play `earcon`. Blocking.
+pub fn play(earcon: Earcon) -> Result<(), String> {
+    let wav = audio::encode_wav(&render(earcon, audio::TARGET_RATE), audio::TARGET_RATE)?;
+    let output = match earcon {
+        Earcon::Alarm => crate::quiet_hours::Output::Alarm,
+        Earcon::Timer => crate::quiet_hours::Output::Earcon,
+    };
+    crate::voice::play_output(&wav, output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_earcons() {
+        let timer = render(Earcon::Timer, 16000);
+        assert_eq!(timer.len(), 2400 + 800 + 4800);
+        assert_eq!(timer[0], 0.0);
+        assert!(timer.iter().all(|s| s.abs() <= 0.5));
+
+        // 3 × (4 × (beep + gap) + pause) = 3 × 1.06 s
+        assert_eq!(render(Earcon::Alarm, 16000).len(), 3 * (4 * (1280 + 960) + 8000));
+    }
+}
@@ -0,0 +1,414 @@
+//! # Semantic Search
+//!
+//! An opt-in index of the user's own documents, so the Gateway LLM can
+//! ground answers in them through the `semantic_search` action. Text files
+//! under `embeddings.paths` are split into overlapping chunks, embedded
+//! locally (fastembed, all-MiniLM-L6-v2 on ONNX Runtime) and stored with
+//! their vectors in `embeddings.db`. Nothing leaves the machine except the
+//! chunks a search returns.
+//!
+//! The indexer runs at startup, every 30 minutes and whenever the settings
+//! change; only files modified since the last run are embedded again, and
+//! files that disappeared are dropped. Paths the safety rules protect are
+//! never indexed.
+//!
+//! Embedding needs the `semantic-search` build feature (the model is
+//! downloaded into the data directory on first use). Without it the action
+//! is not advertised and fails with an explanation.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::sync::Notify;
+
+/// Interval between index refreshes
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// Chunk length and overlap, in characters
+const CHUNK_CHARS: usize = 1000;
+const CHUNK_OVERLAP: usize = 200;
+/// Chunks embedded per model call
+#[cfg_attr(not(feature = "semantic-search"), allow(dead_code))]
+const EMBED_BATCH: usize = 32;
+/// Directories deeper than this below a root are not walked
+const MAX_DEPTH: usize = 12;
+
+static DB: OnceLock<Mutex<Connection>> = OnceLock::new();
+static CONFIG: Mutex<Option<EmbeddingConfig>> = Mutex::new(None);
+/// Wakes the indexer when the configuration changes
+static CHANGED: OnceLock<Notify> = OnceLock::new();
+
+fn changed() -> &'static Notify {
+    CHANGED.get_or_init(Notify::new)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EmbeddingConfig {
+    pub enabled: bool,
+    /// Directories (or single files) to index
+    pub paths: Vec<String>,
+    /// File extensions treated as text
+    pub extensions: Vec<String>,
+    /// Larger files are skipped
+    pub max_file_kb: u64,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            paths: Vec::new(),
+            extensions: ["md", "markdown", "txt", "rst", "org", "csv"].map(String::from).to_vec(),
+            max_file_kb: 1024,
+        }
+    }
+}
+
+impl EmbeddingConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for path in &self.paths {
+            if !Path::new(path).is_absolute() {
+                return Err(format!("Index path '{}' must be absolute", path));
+            }
+            if crate::safety::is_protected_path(path) {
+                return Err(format!("BLOCKED: '{}' is a system-protected path", path));
+            }
+        }
+        Ok(())
+    }
+
+    fn is_text(&self, path: &Path) -> bool {
+        path.extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| self.extensions.iter().any(|x| x.eq_ignore_ascii_case(e)))
+    }
+}
+
+/// A chunk matching a query
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub path: String,
+    pub text: String,
+    /// Cosine similarity, 1 is identical
+    pub score: f32,
+}
+
+/// Result of an indexing run
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexReport {
+    pub files: usize,
+    pub embedded: usize,
+    pub removed: usize,
+    pub chunks: usize,
+}
+
+fn config() -> EmbeddingConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+/// Use `config` from now on; the indexer re-runs at once
+pub fn set_config(config: &EmbeddingConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+    changed().notify_one();
+}
+
+/// Whether `semantic_search` can run in this build with these settings
+pub fn available() -> bool {
+    cfg!(feature = "semantic-search") && config().enabled
+}
+
+// ─── Chunks and vectors ─────────────────────────────
+
+/// Split `text` into chunks of about `size` characters that overlap by
+/// `overlap`, breaking at whitespace where possible
+fn chunk_text(text: &str, size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = (start + size).min(chars.len());
+        if end < chars.len() {
+            // Back up to the last whitespace in the second half of the chunk
+            if let Some(ws) = (start + size / 2..end).rev().find(|&i| chars[i].is_whitespace()) {
+                end = ws;
+            }
+        }
+        let chunk: String = chars[start..end].iter().collect();
+        if !chunk.trim().is_empty() {
+            chunks.push(chunk.trim().to_string());
+        }
+        if end == chars.len() {
+            break;
+        }
+        start = end.saturating_sub(overlap).max(start + 1);
+    }
+    chunks
+}
+
+fn to_blob(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect()
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let denom = norm(a) * norm(b);
+    if denom == 0.0 {
+        0.0
+    } else {
+        dot / denom
+    }
+}
+
+// ─── Embedding model ────────────────────────────────
+
+#[cfg(feature = "semantic-search")]
+fn embed(texts: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+    use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
+    static MODEL: OnceLock<TextEmbedding> = OnceLock::new();
+    let model = match MODEL.get() {
+        Some(model) => model,
+        None => {
+            let options = InitOptions::new(EmbeddingModel::AllMiniLML6V2)
+                .with_cache_dir(crate::users::shared_dir().ok_or("Cannot determine data directory")?.join("models"))
+                .with_show_download_progress(false);
+            let model = TextEmbedding::try_new(options).map_err(|e| format!("Embedding model error: {}", e))?;
+            MODEL.get_or_init(|| model)
+        }
+    };
+    model.embed(texts, Some(EMBED_BATCH)).map_err(|e| format!("Embedding error: {}", e))
+}
+
+#[cfg(not(feature = "semantic-search"))]
+fn embed(_texts: Vec<String>) -> Result<Vec<Vec<f32>>, String> {
+    Err("This build has no embedding support (feature semantic-search)".into())
+}
+
+// ─── Store ──────────────────────────────────────────
+
+fn data_dir() -> Result<PathBuf, String> {
+    let dir = crate::users::data_dir().ok_or("Cannot determine data directory")?;
+    let _ = std::fs::create_dir_all(&dir);
+    Ok(dir)
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS files (
+             path TEXT PRIMARY KEY,
+             modified INTEGER NOT NULL
+         );
+         CREATE TABLE IF NOT EXISTS chunks (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             path TEXT NOT NULL,
+             text TEXT NOT NULL,
+             vector BLOB NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS chunks_by_path ON chunks (path);",
+    )
+}
+
+fn with_db<T>(f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let db = match DB.get() {
+        Some(db) => db,
+        None => {
+            let conn = Connection::open(data_dir()?.join("embeddings.db"))
+                .map_err(|e| format!("Index DB error: {}", e))?;
+            init_schema(&conn).map_err(|e| format!("Index DB error: {}", e))?;
+            DB.get_or_init(|| Mutex::new(conn))
+        }
+    };
+    let mut conn = db.lock().map_err(|e| e.to_string())?;
+    f(&mut conn).map_err(|e| format!("Index DB error: {}", e))
+}
+
+fn indexed_files(conn: &Connection) -> rusqlite::Result<Vec<(String, i64)>> {
+    let mut stmt = conn.prepare("SELECT path, modified FROM files")?;
+    let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+    rows.collect()
+}
+
+/// Replace the chunks of `path` (none removes the file)
+fn store_file(conn: &mut Connection, path: &str, modified: Option<i64>, chunks: &[(String, Vec<f32>)]) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    tx.execute("DELETE FROM chunks WHERE path = ?1", params![path])?;
+    match modified {
+        Some(modified) => {
+            tx.execute(
+                "INSERT INTO files (path, modified) VALUES (?1, ?2)
+                 ON CONFLICT(path) DO UPDATE SET modified = excluded.modified",
+                params![path, modified],
+            )?;
+            for (text, vector) in chunks {
+                tx.execute(
+                    "INSERT INTO chunks (path, text, vector) VALUES (?1, ?2, ?3)",
+                    params![path, text, to_blob(vector)],
+                )?;
+            }
+        }
+        None => {
+            tx.execute("DELETE FROM files WHERE path = ?1", params![path])?;
+        }
+    }
+    tx.commit()
+}
+
+/// The `limit` chunks closest to `query`, best first
+fn nearest(conn: &Connection, query: &[f32], limit: usize) -> rusqlite::Result<Vec<SearchHit>> {
+    let mut stmt = conn.prepare("SELECT path, text, vector FROM chunks")?;
+    let mut hits = stmt
+        .query_map([], |r| {
+            let vector: Vec<u8> = r.get(2)?;
+            Ok(SearchHit { path: r.get(0)?, text: r.get(1)?, score: cosine(query, &from_blob(&vector)) })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    hits.sort_by(|a, b| b.score.total_cmp(&a.score));
+    hits.truncate(limit);
+    Ok(hits)
+}
+
+// ─── Indexer ────────────────────────────────────────
+
+/// Text files under the configured paths with their modification time
+fn collect_files(config: &EmbeddingConfig) -> Vec<(PathBuf, i64)> {
+    fn walk(dir: &Path, depth: usize, config: &EmbeddingConfig, out: &mut Vec<(PathBuf, i64)>) {
+        let Ok(meta) = std::fs::metadata(dir) else {
+            return;
+        };
+        if meta.is_file() {
+            if config.is_text(dir) && meta.len() <= config.max_file_kb * 1024 {
+                let modified = meta.modified().ok().and_then(|t| t.duration_since(UNIX_EPOCH).ok());
+                out.push((dir.to_path_buf(), modified.map_or(0, |d| d.as_secs() as i64)));
+            }
+            return;
+        }
+        if depth > MAX_DEPTH || crate::safety::is_protected_path(&dir.to_string_lossy()) {
+            return;
+        }
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            // Skip dot directories and files (.git, .cache, …)
+            if !entry.file_name().to_string_lossy().starts_with('.') {
+                walk(&entry.path(), depth + 1, config, out);
+            }
+        }
+    }
+    let mut files = Vec::new();
+    for root in &config.paths {
+        walk(Path::new(root), 0, config, &mut files);
+    }
+    files.sort();
+    files.dedup_by(|a, b| a.0 == b.0);
+    files
+}
+
+/// Bring the index up to date with the configured paths. Blocking.
+pub fn index() -> Result<IndexReport, String> {
+    let config = config();
+    if !available() {
+        return Err("Semantic search is off".into());
+    }
+    let files = collect_files(&config);
+    let known: std::collections::HashMap<String, i64> = with_db(|c| indexed_files(c))?.into_iter().collect();
+    let mut report = IndexReport { files: files.len(), ..Default::default() };
+
+    for (path, modified) in &files {
+        let key = path.to_string_lossy().to_string();
+        if known.get(&key) == Some(modified) {
+            continue;
+        }
+        let Ok(text) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        let chunks = chunk_text(&text, CHUNK_CHARS, CHUNK_OVERLAP);
+        let vectors = embed(chunks.clone())?;
+        let rows: Vec<_> = chunks.into_iter().zip(vectors).collect();
+        report.chunks += rows.len();
+        with_db(|c| store_file(c, &key, Some(*modified), &rows))?;
+        report.embedded += 1;
+    }
+
+    let current: std::collections::HashSet<String> = files.iter().map(|(p, _)| p.to_string_lossy().to_string()).collect();
+    for gone in known.keys().filter(|k| !current.contains(*k)) {
+        with_db(|c| store_file(c, gone, None, &[]))?;
+        report.removed += 1;
+    }
+    tracing::info!(
+        "[Embeddings] {} files, {} embedded ({} chunks), {} removed",
+        report.files, report.embedded, report.chunks, report.removed
+    );
+    Ok(report)
+}
+
+/// The indexed chunks closest in meaning to `query`. Blocking.
+pub fn search(query: &str, limit: usize) -> Result<Vec<SearchHit>, String> {
+    if !available() {
+        return Err("Semantic search is off — enable it and choose folders to index in the settings".into());
+    }
+    let vector = embed(vec![query.to_string()])?.pop().ok_or("Embedding error: no vector")?;
+    with_db(|c| nearest(c, &vector, limit))
+}
+
+/// Keep the index fresh while semantic search is on
+pub fn spawn_indexer() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            if available() {
+                if let Ok(Err(e)) = tauri::async_runtime::spawn_blocking(index).await {
+                    tracing::warn!("[Embeddings] Indexing failed: {}", e);
+                }
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(REFRESH_INTERVAL) => {}
+                _ = changed().notified() => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks_vectors_and_ranking() {
+        let text = "word ".repeat(500);
+        let chunks = chunk_text(&text, 1000, 200);
+        assert!(chunks.len() >= 3);
+        assert!(chunks.iter().all(|c| c.len() <= 1000 && !c.ends_with(' ')));
+        assert_eq!(chunk_text("short note", 1000, 200), ["short note"]);
+        assert!(chunk_text("   ", 1000, 200).is_empty());
+
+        let v = vec![0.25f32, -1.5, 3.0];
+        assert_eq!(from_blob(&to_blob(&v)), v);
+        assert!((cosine(&v, &v) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine(&v, &[0.0; 3]), 0.0);
+
+        let mut conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        store_file(&mut conn, "/docs/a.md", Some(1), &[("cats".into(), vec![1.0, 0.0]), ("dogs".into(), vec![0.0, 1.0])]).unwrap();
+        store_file(&mut conn, "/docs/b.md", Some(2), &[("kittens".into(), vec![0.9, 0.1])]).unwrap();
+        let hits = nearest(&conn, &[1.0, 0.0], 2).unwrap();
+        assert_eq!(hits.iter().map(|h| h.text.as_str()).collect::<Vec<_>>(), ["cats", "kittens"]);
+
+        store_file(&mut conn, "/docs/a.md", None, &[]).unwrap();
+        assert_eq!(indexed_files(&conn).unwrap(), [("/docs/b.md".to_string(), 2)]);
+        assert_eq!(nearest(&conn, &[1.0, 0.0], 5).unwrap().len(), 1);
+
+        let config = EmbeddingConfig { paths: vec!["docs".into()], ..Default::default() };
+        assert!(config.validate().is_err());
+        assert!(config.is_text(Path::new("/x/Notes.MD")) && !config.is_text(Path::new("/x/a.exe")));
+    }
+}
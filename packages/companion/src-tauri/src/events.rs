@@ -0,0 +1,51 @@
+//! # Frontend Events
+//!
+//! Background subsystems (Gateway channel, monitors) have no `AppHandle` of
+//! their own. The handle is registered once at startup as the core crate's
+//! event sink, so they can emit Tauri events to the frontend from anywhere,
+//! and raise OS notifications. Without it (headless mode) events are dropped.
+
+use forgeai_companion_core::events::EventSink;
+use std::sync::OnceLock;
+use tauri::{AppHandle, Emitter, Manager};
+use tauri_plugin_notification::NotificationExt;
+
+pub use forgeai_companion_core::events::{emit, notify};
+
+static APP_HANDLE: OnceLock<AppHandle> = OnceLock::new();
+
+/// Forwards engine events to the window and the notification center
+struct WindowSink(AppHandle);
+
+impl EventSink for WindowSink {
+    fn emit(&self, event: &str, payload: serde_json::Value) {
+        if let Err(e) = self.0.emit(event, payload) {
+            tracing::warn!("Failed to emit '{}': {}", event, e);
+        }
+    }
+
+    fn notify(&self, title: &str, body: &str) {
+        if let Err(e) = self.0.notification().builder().title(title).body(body).show() {
+            tracing::warn!("Failed to show notification '{}': {}", title, e);
+        }
+    }
+}
+
+/// Register the app handle (called once from `setup`)
+pub fn init(app_handle: AppHandle) {
+    forgeai_companion_core::events::set_sink(WindowSink(app_handle.clone()));
+    let _ = APP_HANDLE.set(app_handle);
+}
+
+/// The app handle, unless running headless
+pub fn app_handle() -> Option<&'static AppHandle> {
+    APP_HANDLE.get()
+}
+
+/// Whether the main window is visible and focused (the user is looking at it)
+pub fn window_focused() -> bool {
+    APP_HANDLE
+        .get()
+        .and_then(|h| h.get_webview_window("main"))
+        .is_some_and(|w| w.is_visible().unwrap_or(false) && w.is_focused().unwrap_or(false))
+}
@@ -0,0 +1,96 @@
+//! # Execution Context
+//!
+//! Facts about this machine that shell commands and user scripts get for
+//! free, so the model uses them instead of guessing paths for the wrong
+//! platform:
+//!
+//! | Variable              | Value                                        |
+//! |-----------------------|----------------------------------------------|
+//! | `FORGEAI_HOME`        | the user's home directory                    |
+//! | `FORGEAI_PROJECT_DIR` | the selected project (`projectDir`), if any  |
+//! | `FORGEAI_OS`          | `windows`, `macos` or `linux`                |
+//! | `FORGEAI_SHELL`       | the shell commands run in                    |
+//!
+//! Shell actions receive them as environment variables (`$env:FORGEAI_HOME`
+//! in PowerShell), scripts through `context()`, which returns them as a
+//! map. The safety prompt lists them with their values (see
+//! [`prompt_section`]). Nothing secret goes in here: the values are sent
+//! to the Gateway with the prompt.
+
+use std::sync::Mutex;
+
+/// Shell `shell` actions run in (see `local_actions`)
+const SHELL: &str = "powershell";
+
+static PROJECT_DIR: Mutex<Option<String>> = Mutex::new(None);
+
+/// Check a `projectDir` setting
+pub fn validate_project_dir(dir: &Option<String>) -> Result<(), String> {
+    let Some(dir) = dir else { return Ok(()) };
+    if crate::safety::is_protected_path(dir) || !crate::safety::is_user_directory(dir) {
+        return Err(format!("The project directory must be a user directory, not '{}'", dir));
+    }
+    Ok(())
+}
+
+pub fn set_project_dir(dir: Option<String>) {
+    if let Ok(mut current) = PROJECT_DIR.lock() {
+        *current = dir.filter(|d| !d.trim().is_empty());
+    }
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Variables and their values; the project directory only when one is set
+pub fn vars() -> Vec<(&'static str, String)> {
+    let mut vars = vec![("FORGEAI_HOME", crate::safety::get_user_home().to_string_lossy().to_string())];
+    if let Some(project) = PROJECT_DIR.lock().ok().and_then(|p| p.clone()) {
+        vars.push(("FORGEAI_PROJECT_DIR", project));
+    }
+    vars.push(("FORGEAI_OS", std::env::consts::OS.to_string()));
+    vars.push(("FORGEAI_SHELL", SHELL.to_string()));
+    vars
+}
+
+/// The variables as a script map, keyed without the prefix (`home`, `os`…)
+pub fn script_map() -> rhai::Map {
+    vars()
+        .into_iter()
+        .map(|(name, value)| (name.trim_start_matches("FORGEAI_").to_lowercase().into(), value.into()))
+        .collect()
+}
+
+/// Section appended to the safety prompt
+pub fn prompt_section() -> String {
+    let mut section = String::from(
+        "### EXECUTION CONTEXT:\n\
+         Shell commands run in PowerShell with these environment variables set, and\n\
+         scripts get them from `context()`. Build paths from them instead of hardcoding\n\
+         paths for another platform or user:\n",
+    );
+    for (name, value) in vars() {
+        section.push_str(&format!("- $env:{} = {}\n", name, value));
+    }
+    if !vars().iter().any(|(name, _)| *name == "FORGEAI_PROJECT_DIR") {
+        section.push_str("- No project directory is selected; ask before assuming one\n");
+    }
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vars() {
+        set_project_dir(Some("  ".into()));
+        assert!(!vars().iter().any(|(name, _)| *name == "FORGEAI_PROJECT_DIR"));
+        assert!(prompt_section().contains("No project directory"));
+
+        let project = crate::safety::get_user_home().join("Projects").join("site");
+        set_project_dir(Some(project.to_string_lossy().to_string()));
+        assert!(prompt_section().contains(&format!("$env:FORGEAI_PROJECT_DIR = {}", project.display())));
+        assert_eq!(script_map()["os"].clone().into_string().unwrap(), std::env::consts::OS);
+        assert!(validate_project_dir(&Some("C:\\Windows\\System32".into())).is_err());
+    }
+}
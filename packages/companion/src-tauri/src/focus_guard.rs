@@ -0,0 +1,124 @@
+//! # Focus Guard
+//!
+//! Keeps typed text, keystrokes and clicks out of the wrong window. Desktop
+//! `type_text`, `send_keys`, `key_combo` and `click` can name the window
+//! they are meant for:
+//!
+//! ```json
+//! { "action": "type_text", "text": "On my way", "expect_window": "WhatsApp", "expect_app": "WhatsApp.exe" }
+//! ```
+//!
+//! `expect_window` matches part of the focused window's title and
+//! `expect_app` its process name (with or without `.exe`). Without them, the
+//! window a `focus_window` brought forward in the last two minutes is
+//! expected. The focused window is checked before the input is sent, which
+//! is aborted if another window has taken focus, and again after it, so a
+//! switch while typing is reported instead of passing silently.
+
+use crate::screen_context::ScreenContext;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How long a `focus_window` target stays the expected window
+const FOCUS_MEMORY: Duration = Duration::from_secs(120);
+
+/// The last window `focus_window` brought forward, and when
+static LAST_FOCUSED: Mutex<Option<(String, Instant)>> = Mutex::new(None);
+
+/// The window input is meant for
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expectation {
+    /// Part of the window title
+    pub window: Option<String>,
+    /// Process name
+    pub app: Option<String>,
+}
+
+impl std::fmt::Display for Expectation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.window, &self.app) {
+            (Some(window), Some(app)) => write!(f, "'{}' ({})", window, app),
+            (Some(window), None) => write!(f, "'{}'", window),
+            (None, Some(app)) => write!(f, "{}", app),
+            (None, None) => write!(f, "any window"),
+        }
+    }
+}
+
+fn app_name(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    name.strip_suffix(".exe").map(str::to_string).unwrap_or(name)
+}
+
+/// Whether the focused window `focused` is the expected one
+fn matches(expected: &Expectation, focused: &ScreenContext) -> bool {
+    let title_ok = expected
+        .window
+        .as_ref()
+        .is_none_or(|w| focused.title.to_lowercase().contains(&w.trim().to_lowercase()));
+    let app_ok = expected.app.as_ref().is_none_or(|a| app_name(&focused.app) == app_name(a));
+    title_ok && app_ok
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Note that `focus_window` brought forward a window matching `target`
+pub fn remember_focus(target: &str) {
+    if let Ok(mut last) = LAST_FOCUSED.lock() {
+        *last = Some((target.to_string(), Instant::now()));
+    }
+}
+
+/// The window the desktop action `params` is meant for: the one it names,
+/// else the last `focus_window` target; `None` when neither is known
+pub fn expectation(params: &serde_json::Value) -> Option<Expectation> {
+    let field = |name: &str| {
+        params.get(name).and_then(|v| v.as_str()).map(str::trim).filter(|s| !s.is_empty()).map(str::to_string)
+    };
+    let (window, app) = (field("expect_window"), field("expect_app"));
+    if window.is_some() || app.is_some() {
+        return Some(Expectation { window, app });
+    }
+    let last = LAST_FOCUSED.lock().ok()?.clone();
+    last.filter(|(_, at)| at.elapsed() < FOCUS_MEMORY)
+        .map(|(target, _)| Expectation { window: Some(target), app: None })
+}
+
+/// Fail unless the expected window has focus. `when` says at which point
+/// (`before typing`, `after clicking`…), for the error.
+pub fn check(expected: &Expectation, when: &str) -> Result<(), String> {
+    let focused = crate::screen_context::capture(false)
+        .map_err(|e| format!("FOCUS_UNKNOWN: Cannot confirm {} has focus {}: {}", expected, when, e))?;
+    if matches(expected, &focused) {
+        return Ok(());
+    }
+    tracing::warn!("[FocusGuard] Expected {} {}, but '{}' ({}) has focus", expected, when, focused.title, focused.app);
+    Err(format!(
+        "FOCUS_CHANGED: Expected {} to have focus {}, but '{}' ({}) has it",
+        expected, when, focused.title, focused.app
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn focused(app: &str, title: &str) -> ScreenContext {
+        ScreenContext { app: app.into(), title: title.into(), pid: None, ocr: None }
+    }
+
+    #[test]
+    fn test_matches_and_expectation() {
+        let whatsapp = focused("WhatsApp.exe", "WhatsApp - Ana");
+        let expect = |window: Option<&str>, app: Option<&str>| Expectation { window: window.map(Into::into), app: app.map(Into::into) };
+        assert!(matches(&expect(Some("whatsapp"), None), &whatsapp));
+        assert!(matches(&expect(Some("WhatsApp"), Some("whatsapp")), &whatsapp));
+        assert!(!matches(&expect(Some("Notepad"), None), &whatsapp));
+        assert!(!matches(&expect(None, Some("notepad.exe")), &whatsapp));
+
+        remember_focus("Notepad");
+        let named = expectation(&serde_json::json!({ "expect_app": "Code.exe" })).unwrap();
+        assert_eq!(named, expect(None, Some("Code.exe")));
+        assert_eq!(expectation(&serde_json::json!({ "text": "hi" })).unwrap(), expect(Some("Notepad"), None));
+    }
+}
@@ -0,0 +1,367 @@
+//! # Headless Mode
+//!
+//! For Raspberry Pi and server-closet installs the companion runs without
+//! its window and tray, and is driven from the command line:
+//!
+//! ```text
+//! forgeai-companion --headless           run the engines, controlled over a local socket
+//! forgeai-companion pair <url> <code>    redeem a pairing code
+//! forgeai-companion status               print the companion status
+//! forgeai-companion record [session-id]  one voice turn: record, send, play the reply
+//! forgeai-companion speak <text>         speak text through the Gateway's TTS
+//! forgeai-companion execute <json>       run a local action (an `ActionRequest`)
+//! ```
+//!
+//! Subcommands go to a running headless instance through its control
+//! socket — `control.sock` in the data directory, owner-only, or the
+//! `\\.\pipe\forgeai-companion` named pipe on Windows — and run in-process
+//! when none is running. The protocol is one JSON line each way:
+//!
+//! ```json
+//! {"cmd":"speak","text":"Backup finished"}
+//! {"ok":true,"result":"Speech played"}
+//! ```
+//!
+//! A headless instance keeps the Gateway channel, heartbeat and metrics
+//! running. Wake word, hotkeys, the tray and the battery and idle policies
+//! need the desktop app; events meant for the window are dropped.
+
+use crate::local_actions::ActionRequest;
+use crate::voice::VoiceEngine;
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+
+#[cfg(windows)]
+const PIPE_NAME: &str = r"\\.\pipe\forgeai-companion";
+
+const USAGE: &str = "Usage: forgeai-companion [--headless | pair <url> <code> | status | record [session-id] | speak <text> | execute <json>]";
+
+/// A request to the companion, from the command line or the control socket
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "cmd", rename_all = "camelCase", rename_all_fields = "camelCase")]
+pub enum CliCommand {
+    Pair { gateway_url: String, pairing_code: String },
+    Status,
+    Record { session_id: Option<String> },
+    Speak { text: String },
+    Execute { request: Box<ActionRequest> },
+}
+
+/// How the process was started
+#[derive(Debug)]
+pub enum Mode {
+    /// The desktop app with window and tray
+    App,
+    Headless,
+    Command(CliCommand),
+    /// `mock-gateway [port]`: serve a fake Gateway (see `mock_gateway`)
+    #[cfg(feature = "mock-gateway")]
+    MockGateway(u16),
+}
+
+/// Reply line on the control socket
+#[derive(Debug, Serialize, Deserialize)]
+struct Reply {
+    ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl From<Result<serde_json::Value, String>> for Reply {
+    fn from(result: Result<serde_json::Value, String>) -> Self {
+        match result {
+            Ok(value) => Reply { ok: true, result: Some(value), error: None },
+            Err(e) => Reply { ok: false, result: None, error: Some(e) },
+        }
+    }
+}
+
+impl From<Reply> for Result<serde_json::Value, String> {
+    fn from(reply: Reply) -> Self {
+        match reply.ok {
+            true => Ok(reply.result.unwrap_or_default()),
+            false => Err(reply.error.unwrap_or_else(|| "Unknown error".into())),
+        }
+    }
+}
+
+/// Mode from the command line (`args` without the program name). Flags
+/// the app does not know, like `--minimized`, start the app as usual.
+pub fn mode(args: &[String]) -> Result<Mode, String> {
+    let arg = |i: usize| args.get(i).cloned().ok_or_else(|| USAGE.to_string());
+    let command = match args.first().map(String::as_str) {
+        Some("--headless") => return Ok(Mode::Headless),
+        #[cfg(feature = "mock-gateway")]
+        Some("mock-gateway") => {
+            let port = args.get(1).map(|p| p.parse().map_err(|_| USAGE.to_string())).transpose()?;
+            return Ok(Mode::MockGateway(port.unwrap_or(18800)));
+        }
+        Some("pair") => CliCommand::Pair { gateway_url: arg(1)?, pairing_code: arg(2)? },
+        Some("status") => CliCommand::Status,
+        Some("record") => CliCommand::Record { session_id: args.get(1).cloned() },
+        Some("speak") if args.len() > 1 => CliCommand::Speak { text: args[1..].join(" ") },
+        Some("execute") => CliCommand::Execute {
+            request: serde_json::from_str(&arg(1)?).map_err(|e| format!("Invalid action JSON: {}", e))?,
+        },
+        Some("speak") | Some("help") | Some("--help") => return Err(USAGE.into()),
+        _ => return Ok(Mode::App),
+    };
+    Ok(Mode::Command(command))
+}
+
+/// Voice engine of this process, configured from the settings
+fn voice() -> &'static Mutex<VoiceEngine> {
+    static VOICE: OnceLock<Mutex<VoiceEngine>> = OnceLock::new();
+    VOICE.get_or_init(|| {
+        let settings = crate::settings::get().voice;
+        let mut engine = VoiceEngine::new();
+        engine.configure(settings.max_duration_secs, settings.silence_threshold, settings.silence_timeout_ms);
+        engine.set_endpointing(settings.endpointing());
+        Mutex::new(engine)
+    })
+}
+
+async fn dispatch(command: CliCommand) -> Result<serde_json::Value, String> {
+    let to_value = |v: Result<_, crate::i18n::UserError>| v.map(serde_json::Value::String).map_err(|e| e.to_string());
+    match command {
+        CliCommand::Pair { gateway_url, pairing_code } => {
+            let report = crate::commands::pair_with_gateway(gateway_url, pairing_code).await.map_err(|e| e.to_string())?;
+            serde_json::to_value(report).map_err(|e| e.to_string())
+        }
+        CliCommand::Status => serde_json::to_value(crate::status::snapshot()).map_err(|e| e.to_string()),
+        CliCommand::Record { session_id } => {
+            crate::commands::voice_turn(voice(), session_id).await.map_err(|e| e.to_string())
+        }
+        CliCommand::Speak { text } => to_value(crate::commands::voice_speak(text).await),
+        CliCommand::Execute { request } => {
+            tracing::info!("[Headless] Executing action: {}", request.action);
+            serde_json::to_value(crate::local_actions::execute_async(*request).await).map_err(|e| e.to_string())
+        }
+    }
+}
+
+/// Answer requests on one control connection, one JSON line each
+async fn handle<S: AsyncRead + AsyncWrite>(stream: S) {
+    let (read, mut write) = tokio::io::split(stream);
+    let mut lines = BufReader::new(read).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        let reply = match serde_json::from_str::<CliCommand>(&line) {
+            Ok(command) => Reply::from(dispatch(command).await),
+            Err(e) => Reply::from(Err(format!("Invalid command: {}", e))),
+        };
+        let mut json = serde_json::to_string(&reply).unwrap_or_default();
+        json.push('\n');
+        if write.write_all(json.as_bytes()).await.is_err() {
+            break;
+        }
+    }
+}
+
+/// Send `command` over `stream` and read the reply
+async fn request<S: AsyncRead + AsyncWrite>(stream: S, command: &CliCommand) -> Result<serde_json::Value, String> {
+    let (read, mut write) = tokio::io::split(stream);
+    let mut json = serde_json::to_string(command).map_err(|e| e.to_string())?;
+    json.push('\n');
+    write.write_all(json.as_bytes()).await.map_err(|e| format!("Control socket: {}", e))?;
+    let line = BufReader::new(read)
+        .lines()
+        .next_line()
+        .await
+        .map_err(|e| format!("Control socket: {}", e))?
+        .ok_or("The headless companion closed the connection")?;
+    serde_json::from_str::<Reply>(&line).map_err(|e| format!("Invalid reply: {}", e))?.into()
+}
+
+#[cfg(unix)]
+fn socket_path() -> Result<std::path::PathBuf, String> {
+    dirs::data_local_dir()
+        .map(|d| d.join("forgeai-companion").join("control.sock"))
+        .ok_or_else(|| "Cannot determine data directory".into())
+}
+
+/// Send to a running headless instance; None when there is none
+#[cfg(unix)]
+async fn send(command: &CliCommand) -> Option<Result<serde_json::Value, String>> {
+    let stream = tokio::net::UnixStream::connect(socket_path().ok()?).await.ok()?;
+    Some(request(stream, command).await)
+}
+
+#[cfg(windows)]
+async fn send(command: &CliCommand) -> Option<Result<serde_json::Value, String>> {
+    let pipe = tokio::net::windows::named_pipe::ClientOptions::new().open(PIPE_NAME).ok()?;
+    Some(request(pipe, command).await)
+}
+
+#[cfg(unix)]
+async fn serve() -> Result<(), String> {
+    use std::os::unix::fs::PermissionsExt;
+    let path = socket_path()?;
+    if tokio::net::UnixStream::connect(&path).await.is_ok() {
+        return Err("Another headless companion is already running".into());
+    }
+    if let Some(dir) = path.parent() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    // Left over from an instance that did not shut down cleanly
+    let _ = std::fs::remove_file(&path);
+    let listener = tokio::net::UnixListener::bind(&path).map_err(|e| format!("Cannot bind {}: {}", path.display(), e))?;
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))
+        .map_err(|e| format!("Cannot restrict {}: {}", path.display(), e))?;
+    tracing::info!("[Headless] Listening on {}", path.display());
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(handle(stream));
+            }
+            Err(e) => tracing::warn!("[Headless] Accept failed: {}", e),
+        }
+    }
+}
+
+#[cfg(windows)]
+async fn serve() -> Result<(), String> {
+    use tokio::net::windows::named_pipe::ServerOptions;
+    let mut server = ServerOptions::new()
+        .first_pipe_instance(true)
+        .create(PIPE_NAME)
+        .map_err(|_| "Another headless companion is already running".to_string())?;
+    tracing::info!("[Headless] Listening on {}", PIPE_NAME);
+    loop {
+        server.connect().await.map_err(|e| format!("Control pipe: {}", e))?;
+        let client = server;
+        server = ServerOptions::new().create(PIPE_NAME).map_err(|e| format!("Control pipe: {}", e))?;
+        tokio::spawn(handle(client));
+    }
+}
+
+/// Release builds have no console on Windows; use the one the command
+/// was typed into
+fn attach_console() {
+    #[cfg(windows)]
+    {
+        #[link(name = "kernel32")]
+        extern "system" {
+            fn AttachConsole(process_id: u32) -> i32;
+        }
+        // ATTACH_PARENT_PROCESS
+        unsafe { AttachConsole(u32::MAX) };
+    }
+}
+
+/// Run without a window until Ctrl+C. Returns the exit code.
+pub fn run() -> i32 {
+    attach_console();
+    crate::settings::init_headless();
+    tracing::info!("[Headless] ForgeAI Companion started without a window");
+    println!("ForgeAI Companion running headless — Ctrl+C to stop");
+
+    tauri::async_runtime::block_on(async {
+        crate::commands::spawn_gateway_ws();
+        crate::heartbeat::spawn();
+        crate::screen_context::spawn_tracker();
+        crate::reminders::spawn_scheduler();
+        crate::timers::spawn_ticker();
+        crate::embeddings::spawn_indexer();
+        crate::scripts::spawn_watcher();
+        crate::metrics::spawn_reporter();
+        tauri::async_runtime::spawn(async {
+            if crate::connection::GatewayConnection::load_credentials().is_some() {
+                if let Err(e) = crate::device::update_on_gateway().await {
+                    tracing::warn!("[Device] Could not update device info: {}", e);
+                }
+            }
+        });
+
+        let code = tokio::select! {
+            result = serve() => {
+                let e = result.err().unwrap_or_default();
+                eprintln!("Error: {}", e);
+                tracing::error!("[Headless] {}", e);
+                1
+            }
+            _ = tokio::signal::ctrl_c() => 0,
+        };
+        #[cfg(unix)]
+        if code == 0 {
+            if let Ok(path) = socket_path() {
+                let _ = std::fs::remove_file(path);
+            }
+        }
+        tracing::info!("[Headless] Stopped");
+        code
+    })
+}
+
+/// Run one subcommand and print its result. Returns the exit code.
+pub fn run_command(command: CliCommand) -> i32 {
+    attach_console();
+    let result = tauri::async_runtime::block_on(async {
+        match send(&command).await {
+            Some(result) => result,
+            None => {
+                crate::settings::init_headless();
+                dispatch(command).await
+            }
+        }
+    });
+    match result {
+        Ok(serde_json::Value::String(text)) => println!("{}", text),
+        Ok(value) => println!("{}", serde_json::to_string_pretty(&value).unwrap_or_default()),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return 1;
+        }
+    }
+    0
+}
+
+/// Print `message` (usage or an argument error)
+pub fn usage(message: &str) -> i32 {
+    attach_console();
+    eprintln!("{}", message);
+    2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_arguments_and_protocol() {
+        assert!(matches!(mode(&[]), Ok(Mode::App)));
+        assert!(matches!(mode(&args("--minimized")), Ok(Mode::App)));
+        assert!(matches!(mode(&args("--headless")), Ok(Mode::Headless)));
+        assert!(mode(&args("pair https://gw.local")).is_err());
+        assert!(mode(&args("speak")).is_err());
+        assert!(mode(&args("execute {not-json")).unwrap_err().contains("Invalid action JSON"));
+
+        let Ok(Mode::Command(command)) = mode(&args("speak Backup finished")) else {
+            panic!("speak not parsed");
+        };
+        assert_eq!(serde_json::to_string(&command).unwrap(), r#"{"cmd":"speak","text":"Backup finished"}"#);
+        let Ok(Mode::Command(command)) = mode(&args("pair https://gw.local ABC123")) else {
+            panic!("pair not parsed");
+        };
+        let json = serde_json::to_value(&command).unwrap();
+        assert_eq!(json["gatewayUrl"], "https://gw.local");
+        assert_eq!(json["pairingCode"], "ABC123");
+
+        let line = r#"{"cmd":"execute","request":{"action":"list_dir","path":"/tmp","confirmed":false}}"#;
+        let CliCommand::Execute { request } = serde_json::from_str(line).unwrap() else {
+            panic!("execute not parsed");
+        };
+        assert_eq!(request.action, "list_dir");
+
+        let reply = serde_json::to_string(&Reply::from(Err("Not connected".to_string()))).unwrap();
+        assert_eq!(reply, r#"{"ok":false,"error":"Not connected"}"#);
+        let back: Result<serde_json::Value, String> = serde_json::from_str::<Reply>(&reply).unwrap().into();
+        assert_eq!(back, Err("Not connected".to_string()));
+    }
+}
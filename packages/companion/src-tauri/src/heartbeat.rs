@@ -0,0 +1,143 @@
+//! # Gateway Heartbeat
+//!
+//! Periodically probes the Gateway's `/health` endpoint and tracks whether
+//! the companion is actually reachable, rather than just paired.
+//!
+//! - `online`   — health check OK, fast, and the live channel is connected
+//! - `degraded` — reachable but slow, unhealthy, or the live channel is down
+//! - `offline`  — no credentials, or the Gateway did not answer
+//!
+//! Every state change is emitted as a `connection-status` event and fed to
+//! `offline`. Probes are skipped while the user is idle (see `idle`).
+
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Interval between health probes
+const INTERVAL: Duration = Duration::from_secs(15);
+/// Probe timeout — anything slower counts as a failure
+const TIMEOUT: Duration = Duration::from_secs(5);
+/// Round-trip above which the link is reported as degraded
+const DEGRADED_LATENCY_MS: u64 = 1500;
+/// Consecutive failed probes before going from degraded to offline
+const OFFLINE_AFTER_FAILURES: u32 = 2;
+
+static STATUS: OnceLock<Mutex<ConnectionStatus>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkState {
+    Online,
+    Degraded,
+    Offline,
+}
+
+/// Payload of the `connection-status` event
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionStatus {
+    pub state: LinkState,
+    pub latency_ms: Option<u64>,
+    pub live_channel: bool,
+    pub consecutive_failures: u32,
+    pub last_checked: Option<String>,
+    pub detail: Option<String>,
+}
+
+impl Default for ConnectionStatus {
+    fn default() -> Self {
+        Self {
+            state: LinkState::Offline,
+            latency_ms: None,
+            live_channel: false,
+            consecutive_failures: 0,
+            last_checked: None,
+            detail: None,
+        }
+    }
+}
+
+fn status() -> &'static Mutex<ConnectionStatus> {
+    STATUS.get_or_init(|| Mutex::new(ConnectionStatus::default()))
+}
+
+/// Last known connection status
+pub fn current() -> ConnectionStatus {
+    status().lock().map(|s| s.clone()).unwrap_or_default()
+}
+
+/// Start the background heartbeat (called once from `setup`)
+pub fn spawn() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            if !crate::idle::heartbeat_paused() {
+                probe().await;
+            }
+            tokio::time::sleep(INTERVAL).await;
+        }
+    });
+}
+
+async fn probe() {
+    let previous = current();
+    let mut next = ConnectionStatus {
+        live_channel: crate::connection::is_live(),
+        last_checked: Some(chrono::Local::now().to_rfc3339()),
+        ..ConnectionStatus::default()
+    };
+
+    match crate::connection::GatewayConnection::load_credentials() {
+        None => next.detail = Some("Not paired".into()),
+        Some(mut creds) => {
+            crate::roaming::probe(&creds).await;
+            crate::roaming::apply(&mut creds);
+            let started = Instant::now();
+            let result = match crate::http::gateway(&creds.gateway_url) {
+                Ok(gw) => crate::netstats::send(gw.get("/health").timeout(TIMEOUT)).await.map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+            match result {
+                Ok(resp) => {
+                    let latency = started.elapsed().as_millis() as u64;
+                    next.latency_ms = Some(latency);
+                    next.state = if !resp.status().is_success() {
+                        next.detail = Some(format!("Gateway health returned {}", resp.status()));
+                        LinkState::Degraded
+                    } else if latency > DEGRADED_LATENCY_MS {
+                        next.detail = Some(format!("High latency ({} ms)", latency));
+                        LinkState::Degraded
+                    } else if !next.live_channel {
+                        next.detail = Some("Live channel disconnected".into());
+                        LinkState::Degraded
+                    } else {
+                        LinkState::Online
+                    };
+                }
+                Err(e) => {
+                    next.consecutive_failures = previous.consecutive_failures + 1;
+                    next.detail = Some(format!("Health check failed: {}", e));
+                    next.state = if next.consecutive_failures >= OFFLINE_AFTER_FAILURES {
+                        LinkState::Offline
+                    } else {
+                        LinkState::Degraded
+                    };
+                }
+            }
+        }
+    }
+
+    if let Ok(mut s) = status().lock() {
+        *s = next.clone();
+    }
+    crate::offline::set_link(next.state);
+    if next.state != previous.state || previous.last_checked.is_none() {
+        tracing::info!(
+            "[Heartbeat] Gateway {:?} (latency={:?}ms, live={})",
+            next.state,
+            next.latency_ms,
+            next.live_channel
+        );
+        crate::events::emit("connection-status", next);
+        crate::status::publish();
+    }
+}
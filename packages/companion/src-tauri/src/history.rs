@@ -0,0 +1,353 @@
+//! # Conversation History Sync
+//!
+//! Mirrors the companion's conversations from the Gateway into a local
+//! SQLite store (`history.db`), so past chats can be searched and paged
+//! through offline.
+//!
+//! Sync is incremental: `GET /api/companion/history/sync?cursor=...` returns
+//! the sessions and messages changed since the cursor, ids of deleted
+//! sessions, the next cursor and whether more pages follow. The cursor is
+//! stored alongside the data, so each sync only transfers the delta.
+
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+
+/// Changes requested per sync page
+const SYNC_PAGE_SIZE: u32 = 200;
+
+static DB: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub id: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Message {
+    pub id: String,
+    pub session_id: String,
+    pub role: String,
+    pub content: String,
+    /// RFC 3339 timestamp
+    #[serde(default)]
+    pub created_at: String,
+}
+
+/// One page of changes from the Gateway
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct Delta {
+    sessions: Vec<Session>,
+    messages: Vec<Message>,
+    deleted_sessions: Vec<String>,
+    cursor: Option<String>,
+    has_more: bool,
+}
+
+/// Session row with local message count, for listing
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionSummary {
+    pub id: String,
+    pub title: String,
+    pub updated_at: String,
+    pub message_count: u32,
+}
+
+/// Result of a sync run
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncReport {
+    pub sessions: usize,
+    pub messages: usize,
+    pub deleted: usize,
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS sessions (
+             id TEXT PRIMARY KEY,
+             title TEXT NOT NULL,
+             updated_at TEXT NOT NULL
+         );
+         CREATE TABLE IF NOT EXISTS messages (
+             id TEXT PRIMARY KEY,
+             session_id TEXT NOT NULL,
+             role TEXT NOT NULL,
+             content TEXT NOT NULL,
+             created_at TEXT NOT NULL
+         );
+         CREATE INDEX IF NOT EXISTS messages_by_session ON messages (session_id, created_at);
+         CREATE TABLE IF NOT EXISTS sync_state (
+             key TEXT PRIMARY KEY,
+             value TEXT NOT NULL
+         );",
+    )
+}
+
+fn open() -> Result<Connection, String> {
+    let dir = crate::users::data_dir().ok_or("Cannot determine data directory")?;
+    let _ = std::fs::create_dir_all(&dir);
+    let conn = Connection::open(dir.join("history.db")).map_err(|e| format!("History DB error: {}", e))?;
+    init_schema(&conn).map_err(|e| format!("History DB error: {}", e))?;
+    Ok(conn)
+}
+
+fn with_db<T>(f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let db = match DB.get() {
+        Some(db) => db,
+        None => {
+            let conn = open()?;
+            DB.get_or_init(|| Mutex::new(conn))
+        }
+    };
+    let mut conn = db.lock().map_err(|e| e.to_string())?;
+    f(&mut conn).map_err(|e| format!("History DB error: {}", e))
+}
+
+fn cursor(conn: &Connection) -> rusqlite::Result<Option<String>> {
+    conn.query_row("SELECT value FROM sync_state WHERE key = 'cursor'", [], |r| r.get(0))
+        .optional()
+}
+
+/// Store one page of changes (and its cursor) atomically
+fn apply_delta(conn: &mut Connection, delta: &Delta) -> rusqlite::Result<()> {
+    let tx = conn.transaction()?;
+    for s in &delta.sessions {
+        tx.execute(
+            "INSERT INTO sessions (id, title, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(id) DO UPDATE SET title = excluded.title, updated_at = excluded.updated_at",
+            params![s.id, s.title, s.updated_at],
+        )?;
+    }
+    for m in &delta.messages {
+        tx.execute(
+            "INSERT INTO messages (id, session_id, role, content, created_at) VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(id) DO UPDATE SET role = excluded.role, content = excluded.content",
+            params![m.id, m.session_id, m.role, m.content, m.created_at],
+        )?;
+    }
+    for id in &delta.deleted_sessions {
+        tx.execute("DELETE FROM messages WHERE session_id = ?1", params![id])?;
+        tx.execute("DELETE FROM sessions WHERE id = ?1", params![id])?;
+    }
+    if let Some(cursor) = &delta.cursor {
+        tx.execute(
+            "INSERT INTO sync_state (key, value) VALUES ('cursor', ?1)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value",
+            params![cursor],
+        )?;
+    }
+    tx.commit()
+}
+
+/// Pull all changes since the last sync from the Gateway
+pub async fn sync() -> Result<SyncReport, String> {
+    let creds = crate::connection::GatewayConnection::load_credentials()
+        .ok_or("Not connected — pair first")?;
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let mut report = SyncReport { sessions: 0, messages: 0, deleted: 0 };
+
+    loop {
+        let since = with_db(|c| cursor(c))?;
+        let mut query = vec![("limit", SYNC_PAGE_SIZE.to_string())];
+        if let Some(since) = since {
+            query.push(("cursor", since));
+        }
+        let build = || gw
+            .get("/api/companion/history/sync")
+            .query(&query)
+            .timeout(std::time::Duration::from_secs(30));
+        let resp = crate::connection::GatewayConnection::send_authenticated(&creds, build).await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Err("This Gateway does not support history sync".into());
+        }
+        if !resp.status().is_success() {
+            return Err(format!("Gateway HTTP {}", resp.status()));
+        }
+        let delta: Delta = resp.json().await.map_err(|e| format!("Invalid response: {}", e))?;
+
+        with_db(|c| apply_delta(c, &delta))?;
+        report.sessions += delta.sessions.len();
+        report.messages += delta.messages.len();
+        report.deleted += delta.deleted_sessions.len();
+
+        // A page without a new cursor cannot advance — stop rather than loop
+        if !delta.has_more || delta.cursor.is_none() {
+            break;
+        }
+    }
+
+    tracing::info!(
+        "[History] Synced {} sessions, {} messages, {} deletions",
+        report.sessions, report.messages, report.deleted
+    );
+    Ok(report)
+}
+
+/// Sync without waiting for the result (on connect, on new-message events)
+pub fn sync_in_background() {
+    tauri::async_runtime::spawn(async {
+        if let Err(e) = sync().await {
+            tracing::warn!("[History] Sync failed: {}", e);
+        }
+    });
+}
+
+fn list_sessions(conn: &Connection, limit: u32, offset: u32) -> rusqlite::Result<Vec<SessionSummary>> {
+    let mut stmt = conn.prepare(
+        "SELECT s.id, s.title, s.updated_at, COUNT(m.id)
+         FROM sessions s LEFT JOIN messages m ON m.session_id = s.id
+         GROUP BY s.id ORDER BY s.updated_at DESC LIMIT ?1 OFFSET ?2",
+    )?;
+    let rows = stmt.query_map(params![limit, offset], |r| {
+        Ok(SessionSummary {
+            id: r.get(0)?,
+            title: r.get(1)?,
+            updated_at: r.get(2)?,
+            message_count: r.get(3)?,
+        })
+    })?;
+    rows.collect()
+}
+
+fn row_to_message(r: &rusqlite::Row) -> rusqlite::Result<Message> {
+    Ok(Message {
+        id: r.get(0)?,
+        session_id: r.get(1)?,
+        role: r.get(2)?,
+        content: r.get(3)?,
+        created_at: r.get(4)?,
+    })
+}
+
+/// Messages of a session, newest page first; pass the oldest `created_at`
+/// of the previous page as `before` to page further back
+fn session_messages(conn: &Connection, session_id: &str, before: Option<&str>, limit: u32) -> rusqlite::Result<Vec<Message>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, role, content, created_at FROM messages
+         WHERE session_id = ?1 AND (?2 IS NULL OR created_at < ?2)
+         ORDER BY created_at DESC LIMIT ?3",
+    )?;
+    let mut messages: Vec<Message> = stmt
+        .query_map(params![session_id, before, limit], row_to_message)?
+        .collect::<rusqlite::Result<_>>()?;
+    messages.reverse();
+    Ok(messages)
+}
+
+fn search(conn: &Connection, query: &str, limit: u32, offset: u32) -> rusqlite::Result<Vec<Message>> {
+    let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let mut stmt = conn.prepare(
+        "SELECT id, session_id, role, content, created_at FROM messages
+         WHERE content LIKE ?1 ESCAPE '\\'
+         ORDER BY created_at DESC LIMIT ?2 OFFSET ?3",
+    )?;
+    let rows = stmt.query_map(params![format!("%{}%", escaped), limit, offset], row_to_message)?;
+    rows.collect()
+}
+
+/// Locally stored sessions, most recently updated first
+pub fn sessions(limit: u32, offset: u32) -> Result<Vec<SessionSummary>, String> {
+    with_db(|c| list_sessions(c, limit, offset))
+}
+
+/// A page of a locally stored session
+pub fn messages(session_id: &str, before: Option<&str>, limit: u32) -> Result<Vec<Message>, String> {
+    with_db(|c| session_messages(c, session_id, before, limit))
+}
+
+/// Case-insensitive text search over locally stored messages
+pub fn search_messages(query: &str, limit: u32, offset: u32) -> Result<Vec<Message>, String> {
+    if query.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+    with_db(|c| search(c, query.trim(), limit, offset))
+}
+
+/// Drop the local copy (after unpairing)
+pub fn clear() -> Result<(), String> {
+    with_db(|c| c.execute_batch("DELETE FROM messages; DELETE FROM sessions; DELETE FROM sync_state;"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn msg(id: &str, session: &str, content: &str, at: &str) -> Message {
+        Message {
+            id: id.into(),
+            session_id: session.into(),
+            role: "user".into(),
+            content: content.into(),
+            created_at: at.into(),
+        }
+    }
+
+    fn db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_delta_sync_upserts_and_deletes() {
+        let mut conn = db();
+        let delta = Delta {
+            sessions: vec![Session { id: "s1".into(), title: "Plans".into(), updated_at: "2026-01-01T10:00:00Z".into() }],
+            messages: vec![
+                msg("m1", "s1", "book a flight", "2026-01-01T09:00:00Z"),
+                msg("m2", "s1", "to Lisbon", "2026-01-01T09:01:00Z"),
+            ],
+            cursor: Some("c1".into()),
+            ..Default::default()
+        };
+        apply_delta(&mut conn, &delta).unwrap();
+        assert_eq!(cursor(&conn).unwrap().as_deref(), Some("c1"));
+        assert_eq!(list_sessions(&conn, 10, 0).unwrap()[0].message_count, 2);
+
+        let edit = Delta {
+            messages: vec![msg("m2", "s1", "to Porto", "2026-01-01T09:01:00Z")],
+            cursor: Some("c2".into()),
+            ..Default::default()
+        };
+        apply_delta(&mut conn, &edit).unwrap();
+        let page = session_messages(&conn, "s1", None, 10).unwrap();
+        assert_eq!(page.len(), 2);
+        assert_eq!(page[1].content, "to Porto");
+
+        let delete = Delta { deleted_sessions: vec!["s1".into()], ..Default::default() };
+        apply_delta(&mut conn, &delete).unwrap();
+        assert!(list_sessions(&conn, 10, 0).unwrap().is_empty());
+        assert_eq!(cursor(&conn).unwrap().as_deref(), Some("c2"));
+    }
+
+    #[test]
+    fn test_paging_and_search() {
+        let mut conn = db();
+        let delta = Delta {
+            messages: (0..5)
+                .map(|i| msg(&format!("m{}", i), "s1", &format!("note 100% #{}", i), &format!("2026-01-01T09:0{}:00Z", i)))
+                .collect(),
+            ..Default::default()
+        };
+        apply_delta(&mut conn, &delta).unwrap();
+
+        let newest = session_messages(&conn, "s1", None, 2).unwrap();
+        assert_eq!(newest.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), ["m3", "m4"]);
+        let older = session_messages(&conn, "s1", Some(&newest[0].created_at), 2).unwrap();
+        assert_eq!(older.iter().map(|m| m.id.as_str()).collect::<Vec<_>>(), ["m1", "m2"]);
+
+        assert_eq!(search(&conn, "NOTE", 10, 0).unwrap().len(), 5);
+        assert_eq!(search(&conn, "100%", 10, 0).unwrap().len(), 5);
+        assert_eq!(search(&conn, "#3", 10, 0).unwrap()[0].id, "m3");
+        assert!(search(&conn, "_", 10, 0).unwrap().is_empty());
+    }
+}
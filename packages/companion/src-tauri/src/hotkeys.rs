@@ -0,0 +1,180 @@
+//! # Global Hotkeys
+//!
+//! System-wide shortcuts that work while the window is hidden in the tray:
+//!
+//! - **push-to-talk** — starts a voice turn, or stops the recording in progress
+//! - **wake word toggle** — turns wake-word listening on or off
+//!
+//! Bindings use accelerator syntax (`CommandOrControl+Shift+Space`), are
+//! persisted with the other settings and validated before they replace the active
+//! ones: unparsable accelerators, the same binding for both actions, and
+//! shortcuts already taken by another application are rejected, leaving the
+//! previous bindings in place.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+static ACTIVE: Mutex<Option<HotkeyConfig>> = Mutex::new(None);
+/// Recording flag of the managed voice engine (see `VoiceEngine::stop_handle`)
+static RECORDING: OnceLock<Arc<AtomicBool>> = OnceLock::new();
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HotkeyConfig {
+    /// `None` disables the shortcut
+    pub push_to_talk: Option<String>,
+    pub wake_word_toggle: Option<String>,
+}
+
+impl Default for HotkeyConfig {
+    fn default() -> Self {
+        Self {
+            push_to_talk: Some("CommandOrControl+Shift+Space".into()),
+            wake_word_toggle: Some("CommandOrControl+Shift+W".into()),
+        }
+    }
+}
+
+impl HotkeyConfig {
+    fn bindings(&self) -> Vec<(&'static str, &str)> {
+        [("push-to-talk", &self.push_to_talk), ("wake word toggle", &self.wake_word_toggle)]
+            .into_iter()
+            .filter_map(|(name, b)| b.as_deref().map(|b| (name, b)))
+            .collect()
+    }
+
+    /// Parse all bindings and reject duplicates
+    fn parse(&self) -> Result<Vec<Shortcut>, String> {
+        let mut parsed: Vec<(&str, Shortcut)> = Vec::new();
+        for (name, binding) in self.bindings() {
+            let shortcut = Shortcut::from_str(binding)
+                .map_err(|e| format!("Invalid {} shortcut '{}': {}", name, binding, e))?;
+            if let Some((other, _)) = parsed.iter().find(|(_, s)| *s == shortcut) {
+                return Err(format!("'{}' is assigned to both {} and {}", binding, other, name));
+            }
+            parsed.push((name, shortcut));
+        }
+        Ok(parsed.into_iter().map(|(_, s)| s).collect())
+    }
+}
+
+/// Currently active bindings
+pub fn config() -> HotkeyConfig {
+    ACTIVE.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+/// Register `new` in place of the active bindings. On any failure the
+/// previous bindings are restored.
+fn activate(app: &AppHandle, new: &HotkeyConfig) -> Result<(), String> {
+    let shortcuts = new.parse()?;
+    let previous = ACTIVE.lock().ok().and_then(|c| c.clone());
+    let gs = app.global_shortcut();
+
+    if let Some(prev) = &previous {
+        for s in prev.parse().unwrap_or_default() {
+            let _ = gs.unregister(s);
+        }
+    }
+
+    for (i, shortcut) in shortcuts.iter().enumerate() {
+        if let Err(e) = gs.register(*shortcut) {
+            // Taken by another application (or the OS) — roll back
+            for s in &shortcuts[..i] {
+                let _ = gs.unregister(*s);
+            }
+            if let Some(prev) = &previous {
+                for s in prev.parse().unwrap_or_default() {
+                    let _ = gs.register(s);
+                }
+            }
+            return Err(format!(
+                "Shortcut '{}' is already in use by another application: {}",
+                shortcut.into_string(),
+                e
+            ));
+        }
+    }
+
+    *ACTIVE.lock().map_err(|e| e.to_string())? = Some(new.clone());
+    Ok(())
+}
+
+/// Register the persisted bindings (called once from `settings::init`)
+pub fn init(app: &AppHandle, config: &HotkeyConfig) {
+    if let Ok(voice) = app.state::<crate::commands::VoiceState>().0.lock() {
+        let _ = RECORDING.set(voice.stop_handle());
+    }
+    match activate(app, config) {
+        Ok(()) => tracing::info!("[Hotkeys] Registered {:?}", config.bindings()),
+        Err(e) => tracing::warn!("[Hotkeys] {}", e),
+    }
+}
+
+/// Validate and register new bindings (persisted by `settings`)
+pub fn set_config(app: &AppHandle, new: HotkeyConfig) -> Result<(), String> {
+    activate(app, &new)
+}
+
+fn matches(binding: Option<&str>, shortcut: &Shortcut) -> bool {
+    binding
+        .and_then(|b| Shortcut::from_str(b).ok())
+        .is_some_and(|s| s == *shortcut)
+}
+
+/// Global shortcut handler (registered with the plugin in `main`)
+pub fn handle(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state() != ShortcutState::Pressed {
+        return;
+    }
+    let config = config();
+
+    if matches(config.push_to_talk.as_deref(), shortcut) {
+        let recording = RECORDING.get().filter(|r| r.load(Ordering::Relaxed));
+        if let Some(recording) = recording {
+            tracing::info!("[Hotkeys] Push-to-talk: stopping recording");
+            recording.store(false, Ordering::Relaxed);
+        } else {
+            // The frontend runs the full voice pipeline (record → STT → AI → TTS)
+            tracing::info!("[Hotkeys] Push-to-talk: starting voice turn");
+            crate::events::emit("hotkey-push-to-talk", ());
+        }
+    } else if matches(config.wake_word_toggle.as_deref(), shortcut) {
+        match crate::commands::toggle_wake_word(app) {
+            Ok(enabled) => tracing::info!("[Hotkeys] Wake word {}", if enabled { "enabled" } else { "disabled" }),
+            Err(e) => tracing::warn!("[Hotkeys] Wake word could not start: {}", e),
+        }
+    }
+}
+
+/// Stop the recording in progress, if any
+pub fn stop_recording() {
+    if let Some(recording) = RECORDING.get() {
+        recording.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rejects_conflicts() {
+        assert_eq!(HotkeyConfig::default().parse().unwrap().len(), 2);
+
+        let same = HotkeyConfig {
+            push_to_talk: Some("Ctrl+Shift+K".into()),
+            wake_word_toggle: Some("shift+ctrl+k".into()),
+        };
+        assert!(same.parse().unwrap_err().contains("both"));
+
+        let invalid = HotkeyConfig { push_to_talk: Some("Ctrl+Nope".into()), wake_word_toggle: None };
+        assert!(invalid.parse().is_err());
+
+        let disabled = HotkeyConfig { push_to_talk: None, wake_word_toggle: None };
+        assert!(disabled.parse().unwrap().is_empty());
+    }
+}
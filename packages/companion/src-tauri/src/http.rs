@@ -0,0 +1,98 @@
+//! # Shared Gateway HTTP Client
+//!
+//! One pooled `reqwest::Client` per Gateway, so voice, pairing and action
+//! requests reuse keep-alive connections instead of paying a TCP + TLS
+//! handshake on every call. Each client carries the Gateway's pinned
+//! certificate and the proxy settings, a cookie jar, and default timeouts.
+//! Responses are decompressed by `netstats::send` rather than by reqwest,
+//! so the bandwidth statistics see the bytes actually transferred.
+//!
+//! Clients are rebuilt after certificate or proxy changes ([`invalidate`]).
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Fail fast when the Gateway is unreachable
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+/// Max silence while reading a response (streamed chat replies send
+/// heartbeat spaces every 10s, so this never cuts off a live answer)
+const READ_TIMEOUT: Duration = Duration::from_secs(60);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+static CLIENTS: OnceLock<Mutex<HashMap<String, reqwest::Client>>> = OnceLock::new();
+
+fn clients() -> &'static Mutex<HashMap<String, reqwest::Client>> {
+    CLIENTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Pooled client bound to one Gateway base URL
+#[derive(Clone)]
+pub struct GatewayClient {
+    base_url: String,
+    client: reqwest::Client,
+}
+
+impl GatewayClient {
+    /// Absolute URL for a Gateway path (`/api/...`)
+    pub fn url(&self, path: &str) -> String {
+        format!("{}{}", self.base_url, path)
+    }
+
+    pub fn get(&self, path: &str) -> reqwest::RequestBuilder {
+        self.client.get(self.url(path))
+    }
+
+    pub fn post(&self, path: &str) -> reqwest::RequestBuilder {
+        self.client.post(self.url(path))
+    }
+
+    pub fn put(&self, path: &str) -> reqwest::RequestBuilder {
+        self.client.put(self.url(path))
+    }
+
+    pub fn delete(&self, path: &str) -> reqwest::RequestBuilder {
+        self.client.delete(self.url(path))
+    }
+
+    /// The underlying client, for absolute URLs on the same Gateway
+    pub fn http(&self) -> &reqwest::Client {
+        &self.client
+    }
+}
+
+fn build(base_url: &str) -> Result<reqwest::Client, String> {
+    crate::tls_trust::client_builder(base_url)
+        .connect_timeout(CONNECT_TIMEOUT)
+        .read_timeout(READ_TIMEOUT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .tcp_keepalive(Duration::from_secs(60))
+        .cookie_store(true)
+        .no_gzip()
+        .no_zstd()
+        .user_agent(concat!("ForgeAI-Companion/", env!("CARGO_PKG_VERSION")))
+        .build()
+        .map_err(|e| format!("HTTP client error: {}", e))
+}
+
+/// Shared client for a Gateway, created on first use
+pub fn gateway(gateway_url: &str) -> Result<GatewayClient, String> {
+    let base_url = gateway_url.trim_end_matches('/').to_string();
+    let mut map = clients().lock().map_err(|e| e.to_string())?;
+    let client = match map.get(&base_url) {
+        Some(client) => client.clone(),
+        None => {
+            let client = build(&base_url)?;
+            map.insert(base_url.clone(), client.clone());
+            client
+        }
+    };
+    Ok(GatewayClient { base_url, client })
+}
+
+/// Drop all pooled clients (after certificate pin or proxy changes)
+pub fn invalidate() {
+    if let Ok(mut map) = clients().lock() {
+        map.clear();
+    }
+}
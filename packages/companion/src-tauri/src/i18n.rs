@@ -0,0 +1,289 @@
+//! # Localized Errors
+//!
+//! Commands fail with a [`UserError`] of some [`CompanionError`] kind, which
+//! reaches the frontend as
+//!
+//! ```json
+//! {"code": "not_paired", "message": "Não conectado — faça o pareamento primeiro", "details": "Not connected — pair first"}
+//! ```
+//!
+//! `code` is a stable key the UI can branch on, `message` is a short
+//! translation for the user and `details` keeps the original technical text
+//! for logs and bug reports. Commands that know what failed return the kind
+//! directly (`.ok_or(CompanionError::NotPaired)?`); `String` errors from
+//! internal code are classified from their text at the command boundary.
+//!
+//! The language comes from the `locale` setting, else the OS. English,
+//! Portuguese and Spanish are translated; anything else falls back to English.
+
+use serde::ser::SerializeStruct;
+use std::sync::Mutex;
+
+/// Languages with a catalog, first is the fallback
+pub const LANGUAGES: &[&str] = &["en", "pt", "es"];
+
+static LOCALE: Mutex<Option<String>> = Mutex::new(None);
+
+/// Primary language subtag of `locale`, if it has a catalog
+/// (`pt-BR` → `pt`, `es_ES.UTF-8` → `es`)
+fn language_of(locale: &str) -> Option<&'static str> {
+    let lang = locale.split(['-', '_', '.']).next()?.to_ascii_lowercase();
+    LANGUAGES.iter().copied().find(|l| *l == lang)
+}
+
+/// Use `locale` instead of the OS language (`None` restores the OS language)
+pub fn set_locale(locale: Option<String>) {
+    if let Ok(mut current) = LOCALE.lock() {
+        *current = locale;
+    }
+}
+
+/// Whether `locale` has a catalog
+pub fn is_supported(locale: &str) -> bool {
+    language_of(locale).is_some()
+}
+
+/// The language messages are shown in
+pub fn language() -> &'static str {
+    let chosen = LOCALE.lock().ok().and_then(|l| l.clone());
+    chosen
+        .or_else(sys_locale::get_locale)
+        .as_deref()
+        .and_then(language_of)
+        .unwrap_or(LANGUAGES[0])
+}
+
+// ─── Error kinds ────────────────────────────────────
+
+/// What went wrong, as far as the frontend needs to know
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompanionError {
+    NotPaired,
+    SessionExpired,
+    RateLimited,
+    GatewayUnreachable,
+    GatewayError,
+    MicMuted,
+    DeviceUnavailable,
+    RecordingTooShort,
+    SafetyDenied,
+    PairingFailed,
+    UpdateUnavailable,
+    UpdateFailed,
+    InvalidSetting,
+    StorageError,
+    Unexpected,
+}
+
+impl CompanionError {
+    /// Stable key sent to the frontend
+    pub fn code(self) -> &'static str {
+        match self {
+            Self::NotPaired => "not_paired",
+            Self::SessionExpired => "session_expired",
+            Self::RateLimited => "rate_limited",
+            Self::GatewayUnreachable => "gateway_unreachable",
+            Self::GatewayError => "gateway_error",
+            Self::MicMuted => "mic_muted",
+            Self::DeviceUnavailable => "device_unavailable",
+            Self::RecordingTooShort => "recording_too_short",
+            Self::SafetyDenied => "safety_denied",
+            Self::PairingFailed => "pairing_failed",
+            Self::UpdateUnavailable => "update_unavailable",
+            Self::UpdateFailed => "update_failed",
+            Self::InvalidSetting => "invalid_setting",
+            Self::StorageError => "storage_error",
+            Self::Unexpected => "unexpected",
+        }
+    }
+
+    /// Translation in `lang`
+    pub fn message(self, lang: &str) -> &'static str {
+        use CompanionError::*;
+        match (self, lang) {
+            (NotPaired, "pt") => "Não conectado — faça o pareamento primeiro",
+            (NotPaired, "es") => "No conectado — vincula el dispositivo primero",
+            (NotPaired, _) => "Not connected — pair first",
+
+            (SessionExpired, "pt") => "Sessão expirada — faça o pareamento novamente",
+            (SessionExpired, "es") => "Sesión caducada — vuelve a vincular el dispositivo",
+            (SessionExpired, _) => "Session expired — please pair again",
+
+            (RateLimited, "pt") => "Muitas solicitações — tente novamente em instantes",
+            (RateLimited, "es") => "Demasiadas solicitudes — inténtalo de nuevo en un momento",
+            (RateLimited, _) => "Too many requests — try again in a moment",
+
+            (GatewayUnreachable, "pt") => "Não foi possível alcançar o Gateway",
+            (GatewayUnreachable, "es") => "No se pudo contactar con el Gateway",
+            (GatewayUnreachable, _) => "Cannot reach the Gateway",
+
+            (GatewayError, "pt") => "O Gateway não conseguiu atender a solicitação",
+            (GatewayError, "es") => "El Gateway no pudo atender la solicitud",
+            (GatewayError, _) => "The Gateway could not handle the request",
+
+            (MicMuted, "pt") => "O microfone está silenciado",
+            (MicMuted, "es") => "El micrófono está silenciado",
+            (MicMuted, _) => "The microphone is muted",
+
+            (DeviceUnavailable, "pt") => "Nenhum microfone disponível",
+            (DeviceUnavailable, "es") => "No hay ningún micrófono disponible",
+            (DeviceUnavailable, _) => "No microphone available",
+
+            (RecordingTooShort, "pt") => "Gravação muito curta — fale um pouco mais",
+            (RecordingTooShort, "es") => "Grabación demasiado corta — habla un poco más",
+            (RecordingTooShort, _) => "Recording too short — speak a little longer",
+
+            (SafetyDenied, "pt") => "Ação bloqueada pelas regras de segurança",
+            (SafetyDenied, "es") => "Acción bloqueada por las reglas de seguridad",
+            (SafetyDenied, _) => "Action blocked by the safety rules",
+
+            (PairingFailed, "pt") => "Falha no pareamento",
+            (PairingFailed, "es") => "No se pudo vincular el dispositivo",
+            (PairingFailed, _) => "Pairing failed",
+
+            (UpdateUnavailable, "pt") => "Nenhuma atualização disponível",
+            (UpdateUnavailable, "es") => "No hay ninguna actualización disponible",
+            (UpdateUnavailable, _) => "No update available",
+
+            (UpdateFailed, "pt") => "Falha na atualização",
+            (UpdateFailed, "es") => "No se pudo actualizar",
+            (UpdateFailed, _) => "Update failed",
+
+            (InvalidSetting, "pt") => "Valor de configuração inválido",
+            (InvalidSetting, "es") => "Valor de configuración no válido",
+            (InvalidSetting, _) => "Invalid setting value",
+
+            (StorageError, "pt") => "Não foi possível salvar os dados locais",
+            (StorageError, "es") => "No se pudieron guardar los datos locales",
+            (StorageError, _) => "Cannot save local data",
+
+            (Unexpected, "pt") => "Algo deu errado",
+            (Unexpected, "es") => "Algo salió mal",
+            (Unexpected, _) => "Something went wrong",
+        }
+    }
+}
+
+/// Kinds recognized in internal error text, in the order they are matched
+const RULES: &[(CompanionError, &[&str])] = &[
+    (CompanionError::NotPaired, &["Not connected"]),
+    (CompanionError::SessionExpired, &["Session expired", "Refresh rejected"]),
+    (CompanionError::RateLimited, &["Gateway rate limit reached"]),
+    (CompanionError::GatewayUnreachable, &["Gateway unreachable", "Connection failed", "WebSocket connection failed", "Request failed", "Refresh request failed"]),
+    (CompanionError::GatewayError, &["Gateway HTTP", "Gateway returned HTTP", "Invalid response", "This Gateway does not support"]),
+    (CompanionError::MicMuted, &["Microphone is muted"]),
+    (CompanionError::DeviceUnavailable, &["No audio input device", "No supported input config"]),
+    (CompanionError::RecordingTooShort, &["Recording too short"]),
+    (CompanionError::SafetyDenied, &["BLOCKED"]),
+    (CompanionError::PairingFailed, &["Pairing failed", "Pairing payload", "Unsupported pairing URL scheme", "QR code", "QR image"]),
+    (CompanionError::UpdateUnavailable, &["No update available", "This build has no update signing key"]),
+    (CompanionError::UpdateFailed, &["Update check failed", "Update signature verification failed", "Download failed"]),
+    (CompanionError::InvalidSetting, &["must be between", "must be 1024 or higher", "Unsupported language"]),
+    (CompanionError::StorageError, &["Cannot determine data directory", "Write error", "Settings write error", "File save error", "History DB error", "Outbox write error"]),
+];
+
+fn classify(details: &str) -> CompanionError {
+    RULES
+        .iter()
+        .find(|(_, patterns)| patterns.iter().any(|p| details.contains(p)))
+        .map(|(kind, _)| *kind)
+        .unwrap_or(CompanionError::Unexpected)
+}
+
+// ─── Error type ─────────────────────────────────────
+
+/// Error returned by commands: its kind plus the technical text
+#[derive(Debug, Clone)]
+pub struct UserError {
+    pub kind: CompanionError,
+    pub details: String,
+}
+
+impl UserError {
+    pub fn new(kind: CompanionError, details: impl Into<String>) -> Self {
+        Self { kind, details: details.into() }
+    }
+
+    /// The message for the current language
+    pub fn message(&self) -> &'static str {
+        self.kind.message(language())
+    }
+}
+
+impl std::fmt::Display for UserError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.details)
+    }
+}
+
+impl From<CompanionError> for UserError {
+    fn from(kind: CompanionError) -> Self {
+        Self::new(kind, kind.message(LANGUAGES[0]))
+    }
+}
+
+impl From<String> for UserError {
+    fn from(details: String) -> Self {
+        Self { kind: classify(&details), details }
+    }
+}
+
+impl From<&str> for UserError {
+    fn from(details: &str) -> Self {
+        details.to_string().into()
+    }
+}
+
+impl From<crate::connection::GatewayError> for UserError {
+    fn from(e: crate::connection::GatewayError) -> Self {
+        match e {
+            crate::connection::GatewayError::RateLimited(r) => Self::new(CompanionError::RateLimited, r.to_string()),
+            crate::connection::GatewayError::Failed(e) => e.into(),
+        }
+    }
+}
+
+impl serde::Serialize for UserError {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("UserError", 3)?;
+        s.serialize_field("code", self.kind.code())?;
+        s.serialize_field("message", self.message())?;
+        s.serialize_field("details", &self.details)?;
+        s.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_and_translate() {
+        assert_eq!(language_of("pt-BR"), Some("pt"));
+        assert_eq!(language_of("es_ES.UTF-8"), Some("es"));
+        assert_eq!(language_of("de-DE"), None);
+
+        let e = UserError::from("Not connected — pair first");
+        assert_eq!(e.kind, CompanionError::NotPaired);
+        assert_eq!(UserError::from(format!("Gateway HTTP 502: {}", "bad")).kind, CompanionError::GatewayError);
+        assert_eq!(UserError::from("BLOCKED: '/etc' is a system-protected path").kind, CompanionError::SafetyDenied);
+        assert_eq!(UserError::from("Serialize error: eof").kind, CompanionError::Unexpected);
+
+        let e = UserError::from(CompanionError::DeviceUnavailable);
+        assert_eq!(e.details, "No microphone available");
+        let json = serde_json::to_value(&e).unwrap();
+        assert_eq!(json["code"], "device_unavailable");
+        assert_eq!(json["message"], e.message());
+
+        assert_eq!(CompanionError::MicMuted.message("pt"), "O microfone está silenciado");
+        assert_eq!(CompanionError::MicMuted.message("fr"), "The microphone is muted");
+        assert_eq!(CompanionError::Unexpected.message("es"), "Algo salió mal");
+
+        // Every kind has a translation of its own in every language
+        for (kind, _) in RULES {
+            for lang in LANGUAGES {
+                assert_ne!(kind.message(lang), CompanionError::Unexpected.message(lang), "{:?} / {}", kind, lang);
+            }
+        }
+    }
+}
@@ -0,0 +1,248 @@
+//! # Idle Detection
+//!
+//! When nobody has touched the keyboard or mouse for `idle.minutes`, the
+//! companion can go quiet: wake-word listening and any recording stop, and
+//! the Gateway health probe and WebSocket keepalive pause. The first input
+//! afterwards resumes everything — while idle the input timer is checked
+//! every second. Off by default.
+//!
+//! | OS      | Source                                                     |
+//! |---------|------------------------------------------------------------|
+//! | Windows | `GetLastInputInfo`                                         |
+//! | macOS   | `CGEventSourceSecondsSinceLastEventType`                   |
+//! | Linux   | `xprintidle` (X11), else Mutter's `IdleMonitor` over D-Bus |
+//!
+//! Where the idle time cannot be read the user is always considered active.
+
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Check interval while idle, so activity is noticed right away
+const IDLE_POLL: Duration = Duration::from_secs(1);
+/// Longest gap between checks while active
+const ACTIVE_POLL: Duration = Duration::from_secs(60);
+/// Suspension source name (see `wake_word`)
+const SOURCE: &str = "idle";
+
+static CONFIG: Mutex<Option<IdleConfig>> = Mutex::new(None);
+static IDLE: AtomicBool = AtomicBool::new(false);
+/// Wakes the monitor when the configuration changes
+static CHANGED: OnceLock<Notify> = OnceLock::new();
+
+fn changed() -> &'static Notify {
+    CHANGED.get_or_init(Notify::new)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IdleConfig {
+    pub enabled: bool,
+    /// Minutes without input before the user counts as idle
+    pub minutes: u32,
+    /// Stop wake-word listening and recording while idle
+    pub pause_listening: bool,
+    /// Skip Gateway health probes and WebSocket keepalives while idle
+    pub pause_heartbeat: bool,
+}
+
+impl Default for IdleConfig {
+    fn default() -> Self {
+        Self { enabled: false, minutes: 15, pause_listening: true, pause_heartbeat: true }
+    }
+}
+
+impl IdleConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.minutes == 0 {
+            return Err("Idle time must be at least one minute".into());
+        }
+        Ok(())
+    }
+
+    fn threshold(&self) -> Duration {
+        Duration::from_secs(self.minutes as u64 * 60)
+    }
+}
+
+fn config() -> IdleConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+/// Whether the user is idle right now (always false when disabled)
+pub fn is_idle() -> bool {
+    IDLE.load(Ordering::Relaxed)
+}
+
+/// Whether heartbeats should be skipped right now
+pub fn heartbeat_paused() -> bool {
+    is_idle() && config().pause_heartbeat
+}
+
+/// Whether `idle_for` counts as idle under `config`
+fn idle_under(config: &IdleConfig, idle_for: Option<Duration>) -> bool {
+    config.enabled && idle_for.is_some_and(|d| d >= config.threshold())
+}
+
+/// How long to wait before the next check
+fn next_check(config: &IdleConfig, idle_for: Option<Duration>) -> Duration {
+    match idle_for {
+        _ if !config.enabled => ACTIVE_POLL,
+        Some(d) if d >= config.threshold() => IDLE_POLL,
+        Some(d) => (config.threshold() - d).clamp(IDLE_POLL, ACTIVE_POLL),
+        None => ACTIVE_POLL,
+    }
+}
+
+// ─── Platform readers ───────────────────────────────
+
+/// Milliseconds from `xprintidle` ("1234") or Mutter's `GetIdletime`
+/// ("(uint64 1234,)")
+#[cfg_attr(not(any(target_os = "linux", test)), allow(dead_code))]
+fn parse_idle_ms(output: &str) -> Option<u64> {
+    output
+        .replace("uint64", "")
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|w| !w.is_empty())?
+        .parse()
+        .ok()
+}
+
+#[cfg(target_os = "linux")]
+fn read() -> Option<Duration> {
+    let run = |cmd: &str, args: &[&str]| {
+        let out = std::process::Command::new(cmd).args(args).output().ok()?;
+        out.status.success().then(|| String::from_utf8_lossy(&out.stdout).to_string())
+    };
+    let output = run("xprintidle", &[]).or_else(|| {
+        run(
+            "gdbus",
+            &[
+                "call",
+                "--session",
+                "--dest",
+                "org.gnome.Mutter.IdleMonitor",
+                "--object-path",
+                "/org/gnome/Mutter/IdleMonitor/Core",
+                "--method",
+                "org.gnome.Mutter.IdleMonitor.GetIdletime",
+            ],
+        )
+    })?;
+    parse_idle_ms(&output).map(Duration::from_millis)
+}
+
+#[cfg(target_os = "macos")]
+fn read() -> Option<Duration> {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGEventSourceSecondsSinceLastEventType(state: i32, event_type: u32) -> f64;
+    }
+    // kCGEventSourceStateCombinedSessionState, kCGAnyInputEventType
+    let secs = unsafe { CGEventSourceSecondsSinceLastEventType(0, u32::MAX) };
+    (secs.is_finite() && secs >= 0.0).then(|| Duration::from_secs_f64(secs))
+}
+
+#[cfg(target_os = "windows")]
+fn read() -> Option<Duration> {
+    #[repr(C)]
+    struct LastInputInfo {
+        cb_size: u32,
+        dw_time: u32,
+    }
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetLastInputInfo(plii: *mut LastInputInfo) -> i32;
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetTickCount() -> u32;
+    }
+    let mut info = LastInputInfo { cb_size: std::mem::size_of::<LastInputInfo>() as u32, dw_time: 0 };
+    if unsafe { GetLastInputInfo(&mut info) } == 0 {
+        return None;
+    }
+    let now = unsafe { GetTickCount() };
+    Some(Duration::from_millis(now.wrapping_sub(info.dw_time) as u64))
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read() -> Option<Duration> {
+    None
+}
+
+/// Time since the last keyboard or mouse input, if the OS tells. Blocking.
+pub fn idle_time() -> Option<Duration> {
+    read()
+}
+
+/// Use `config` from now on; the monitor re-checks at once
+pub fn set_config(config: &IdleConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+    changed().notify_one();
+}
+
+fn set_idle(app: &tauri::AppHandle, config: &IdleConfig, idle: bool) {
+    IDLE.store(idle, Ordering::Relaxed);
+    tracing::info!("[Idle] User is {}", if idle { "idle" } else { "active" });
+    if idle && config.pause_listening {
+        crate::commands::suspend_listening(app, SOURCE, format!("No activity for {} min", config.minutes));
+        crate::hotkeys::stop_recording();
+    } else if !idle {
+        crate::commands::resume_listening(app, SOURCE);
+    }
+    crate::events::emit("idle-changed", serde_json::json!({ "idle": idle }));
+    crate::status::publish();
+}
+
+/// Track idle time and pause or resume on changes
+pub fn spawn_monitor(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let config = config();
+            let idle_for = if config.enabled {
+                tauri::async_runtime::spawn_blocking(idle_time).await.ok().flatten()
+            } else {
+                None
+            };
+            let idle = idle_under(&config, idle_for);
+            if idle != is_idle() {
+                set_idle(&app, &config, idle);
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(next_check(&config, idle_for)) => {}
+                _ = changed().notified() => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_detection() {
+        let mins = |m: u64| Some(Duration::from_secs(m * 60));
+        let config = IdleConfig { enabled: true, minutes: 10, ..Default::default() };
+        assert!(!idle_under(&config, mins(9)));
+        assert!(idle_under(&config, mins(10)));
+        assert!(!idle_under(&config, None));
+        assert!(!idle_under(&IdleConfig::default(), mins(600)));
+
+        // Check right when the threshold will be crossed, every second once idle
+        assert_eq!(next_check(&config, Some(Duration::from_secs(590))), Duration::from_secs(10));
+        assert_eq!(next_check(&config, mins(0)), ACTIVE_POLL);
+        assert_eq!(next_check(&config, mins(11)), IDLE_POLL);
+        assert_eq!(next_check(&IdleConfig::default(), mins(11)), ACTIVE_POLL);
+
+        assert_eq!(parse_idle_ms("48213\n"), Some(48213));
+        assert_eq!(parse_idle_ms("(uint64 907,)\n"), Some(907));
+        assert_eq!(parse_idle_ms("couldn't open display"), None);
+        assert!(IdleConfig { minutes: 0, ..Default::default() }.validate().is_err());
+    }
+}
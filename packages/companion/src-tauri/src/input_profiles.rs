@@ -0,0 +1,206 @@
+//! # Input Device Profiles
+//!
+//! Capture settings per microphone, since one threshold fits neither a
+//! webcam mic nor an XLR interface. Each entry of `inputProfiles` in the
+//! settings names a device (its full name or a part of it, like output
+//! priorities) and can carry:
+//!
+//! - `silenceThreshold` — replaces `voice.silenceThreshold` for endpointing
+//! - `gateThreshold` — a noise gate: input quieter than this is muted,
+//!   held open for `gateHoldMs` after the voice drops so words are not
+//!   clipped
+//! - `agc` — automatic gain control toward `agcTarget` RMS, at most
+//!   `agcMaxGain`; quiet mics are lifted, hot ones brought down
+//!
+//! The profile is looked up whenever a recording opens an input device, so
+//! it follows the selected device, an audio profile switch, and a mic that
+//! is hot-plugged or taken over mid-recording.
+
+use forgeai_companion_core::audio;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use tauri::AppHandle;
+
+/// Share of the way the gain moves toward its target per chunk (~50 ms)
+const AGC_SMOOTHING: f32 = 0.2;
+
+static PROFILES: Mutex<Vec<InputProfile>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InputProfile {
+    /// Input device, by its full name or a part of it
+    pub device: String,
+    /// RMS below which input counts as silence (the voice setting when unset)
+    pub silence_threshold: Option<f32>,
+    /// RMS below which input is muted (no gate when unset)
+    pub gate_threshold: Option<f32>,
+    pub gate_hold_ms: u64,
+    pub agc: bool,
+    /// RMS the gain control aims for
+    pub agc_target: f32,
+    pub agc_max_gain: f32,
+}
+
+impl Default for InputProfile {
+    fn default() -> Self {
+        Self {
+            device: String::new(),
+            silence_threshold: None,
+            gate_threshold: None,
+            gate_hold_ms: 250,
+            agc: false,
+            agc_target: 0.1,
+            agc_max_gain: 8.0,
+        }
+    }
+}
+
+impl InputProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.device.trim().is_empty() {
+            return Err("Input profiles need a device name".into());
+        }
+        let level = |v: Option<f32>| v.is_none_or(|v| (0.0..=1.0).contains(&v));
+        if !level(self.silence_threshold) || !level(self.gate_threshold) {
+            return Err(format!("Thresholds for '{}' must be between 0 and 1", self.device));
+        }
+        if self.gate_hold_ms > 5_000 {
+            return Err("Noise gate hold must be at most 5000 ms".into());
+        }
+        if self.agc && (!(0.01..=0.5).contains(&self.agc_target) || !(1.0..=32.0).contains(&self.agc_max_gain)) {
+            return Err("AGC target must be 0.01–0.5 and its maximum gain 1–32".into());
+        }
+        Ok(())
+    }
+}
+
+/// The entry of `profiles` for input `device`: an exact name first, then
+/// the first one naming a part of it
+fn find<'a>(profiles: &'a [InputProfile], device: &str) -> Option<&'a InputProfile> {
+    let name = device.to_lowercase();
+    let wanted = |p: &&InputProfile| p.device.trim().to_lowercase();
+    profiles
+        .iter()
+        .find(|p| wanted(p) == name)
+        .or_else(|| profiles.iter().find(|p| !wanted(p).is_empty() && name.contains(&wanted(p))))
+}
+
+/// Use `profiles` from the next device opened on
+pub fn set_profiles(profiles: &[InputProfile]) {
+    if let Ok(mut current) = PROFILES.lock() {
+        *current = profiles.to_vec();
+    }
+}
+
+pub fn list() -> Vec<InputProfile> {
+    crate::settings::get().input_profiles
+}
+
+/// Create a profile, or replace the one for the same device
+pub fn save(app: &AppHandle, profile: InputProfile) -> Result<Vec<InputProfile>, String> {
+    profile.validate()?;
+    let settings = crate::settings::update(app, |s| {
+        match s.input_profiles.iter_mut().find(|p| p.device.eq_ignore_ascii_case(&profile.device)) {
+            Some(existing) => *existing = profile.clone(),
+            None => s.input_profiles.push(profile.clone()),
+        }
+    })?;
+    Ok(settings.input_profiles)
+}
+
+pub fn delete(app: &AppHandle, device: &str) -> Result<Vec<InputProfile>, String> {
+    let settings = crate::settings::update(app, |s| s.input_profiles.retain(|p| !p.device.eq_ignore_ascii_case(device)))?;
+    Ok(settings.input_profiles)
+}
+
+/// Noise gate and gain control state for one input device
+#[derive(Debug, Clone)]
+pub struct Processor {
+    profile: InputProfile,
+    gain: f32,
+    /// Samples (per channel) the gate stays open for
+    hold_left: usize,
+}
+
+impl Processor {
+    /// The processing for input `device`, None when it has no profile
+    pub fn for_device(device: &str) -> Option<Processor> {
+        let profiles = PROFILES.lock().ok()?;
+        let profile = find(&profiles, device)?.clone();
+        tracing::info!("Voice: input profile '{}' applies to '{}'", profile.device, device);
+        Some(Processor { profile, gain: 1.0, hold_left: 0 })
+    }
+
+    /// Threshold for endpointing, when the profile sets one
+    pub fn silence_threshold(&self) -> Option<f32> {
+        self.profile.silence_threshold
+    }
+
+    /// Gate and level the interleaved chunk `data` captured at `rate`
+    pub fn process(&mut self, data: &mut [f32], channels: usize, rate: u32) {
+        let frames = data.len() / channels.max(1);
+        let level = audio::rms(&audio::downmix(data, channels));
+        if let Some(threshold) = self.profile.gate_threshold {
+            if level >= threshold {
+                self.hold_left = rate as usize * self.profile.gate_hold_ms as usize / 1000;
+            } else if self.hold_left > 0 {
+                self.hold_left = self.hold_left.saturating_sub(frames);
+            } else {
+                data.fill(0.0);
+                return;
+            }
+        }
+        if self.profile.agc {
+            // Only voice moves the gain, so pauses are not pumped up
+            let voiced = level > self.profile.gate_threshold.or(self.profile.silence_threshold).unwrap_or(0.005);
+            if voiced {
+                let wanted = (self.profile.agc_target / level).clamp(1.0 / self.profile.agc_max_gain, self.profile.agc_max_gain);
+                self.gain += (wanted - self.gain) * AGC_SMOOTHING;
+            }
+            for sample in data.iter_mut() {
+                *sample = (*sample * self.gain).clamp(-1.0, 1.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matching_gate_and_agc() {
+        let profiles = vec![
+            InputProfile { device: "Webcam".into(), ..Default::default() },
+            InputProfile { device: "Focusrite USB".into(), ..Default::default() },
+        ];
+        assert_eq!(find(&profiles, "Microphone (Logitech Webcam C920)").unwrap().device, "Webcam");
+        assert_eq!(find(&profiles, "focusrite usb").unwrap().device, "Focusrite USB");
+        assert!(find(&profiles, "MacBook Pro Microphone").is_none());
+
+        let profile = InputProfile { device: "x".into(), gate_threshold: Some(0.05), gate_hold_ms: 100, ..Default::default() };
+        let mut gate = Processor { profile, gain: 1.0, hold_left: 0 };
+        let mut hum = vec![0.01; 800];
+        gate.process(&mut hum, 1, 16_000);
+        assert!(hum.iter().all(|s| *s == 0.0));
+        // Held open for 100 ms (1600 samples) after the voice
+        let mut voice = vec![0.2; 800];
+        gate.process(&mut voice, 1, 16_000);
+        let mut tail = vec![0.01; 800];
+        gate.process(&mut tail, 1, 16_000);
+        assert_eq!(tail[0], 0.01);
+
+        let profile = InputProfile { device: "x".into(), agc: true, agc_target: 0.2, ..Default::default() };
+        let mut agc = Processor { profile, gain: 1.0, hold_left: 0 };
+        let mut chunk = vec![0.05; 800];
+        for _ in 0..40 {
+            chunk = vec![0.05; 800];
+            agc.process(&mut chunk, 1, 16_000);
+        }
+        assert!((chunk[0] - 0.2).abs() < 0.01);
+
+        assert!(InputProfile::default().validate().is_err());
+        assert!(InputProfile { device: "x".into(), gate_threshold: Some(2.0), ..Default::default() }.validate().is_err());
+    }
+}
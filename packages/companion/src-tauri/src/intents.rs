@@ -0,0 +1,257 @@
+//! # Local Intents
+//!
+//! Trivial requests are answered on the device instead of going through the
+//! Gateway's LLM: once a voice turn is transcribed (or a message typed), the
+//! text is matched against a small keyword grammar, in English and
+//! Portuguese:
+//!
+//! | Intent     | Examples                                         | Action                      |
+//! |------------|--------------------------------------------------|-----------------------------|
+//! | `SetTimer` | "set a timer for ten minutes", "timer de 5 minutos" | `timers::start`          |
+//! | `Mute`     | "mute", "stop listening", "silenciar"            | mutes the microphone        |
+//! | `WhatTime` | "what time is it", "que horas são"               | answers with the local time |
+//! | `Stop`     | "stop", "cancel", "pare"                         | cuts off playback           |
+//!
+//! `Mute`, `Stop` and `WhatTime` only match when they are the whole
+//! utterance, so "stop the music in the kitchen" still reaches the Gateway.
+//! Turn it off with `intents.enabled`.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+static CONFIG: Mutex<Option<IntentConfig>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IntentConfig {
+    /// Answer trivial requests on the device
+    pub enabled: bool,
+}
+
+impl Default for IntentConfig {
+    fn default() -> Self {
+        Self { enabled: true }
+    }
+}
+
+/// Use `config` from now on
+pub fn set_config(config: &IntentConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+/// Whether local intents are on
+pub fn enabled() -> bool {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default().enabled
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Intent {
+    SetTimer { seconds: u64 },
+    Mute,
+    WhatTime,
+    Stop,
+}
+
+// ─── Grammar ────────────────────────────────────────
+
+const STOP: &[&str] = &["stop", "stop it", "cancel", "be quiet", "shut up", "enough", "pare", "parar", "para", "chega", "cancelar", "silencio"];
+const MUTE: &[&str] = &[
+    "mute", "mute yourself", "mute the microphone", "mute microphone", "mute the mic", "stop listening",
+    "silenciar", "mudo", "silenciar microfone", "desligar microfone", "pare de ouvir",
+];
+const WHAT_TIME: &[&str] = &[
+    "what time is it", "whats the time", "what is the time", "tell me the time", "time",
+    "que horas sao", "que hora e", "que horas e", "me diga as horas", "horas",
+];
+const TIMER_WORDS: &[&str] = &["timer", "temporizador", "cronometro", "countdown"];
+/// Filler words dropped from the front and back of an utterance
+const FILLER: &[&str] = &["please", "hey", "ok", "okay", "forge", "now", "por", "favor", "agora"];
+
+/// Lowercase, without accents, apostrophes, punctuation and leading or
+/// trailing fillers, and with single spaces
+pub fn normalize(text: &str) -> String {
+    let folded: String = text
+        .to_lowercase()
+        .chars()
+        .filter(|c| *c != '\'' && *c != '’')
+        .map(|c| match c {
+            'á' | 'à' | 'â' | 'ã' | 'ä' => 'a',
+            'é' | 'ê' | 'è' | 'ë' => 'e',
+            'í' | 'ì' | 'î' | 'ï' => 'i',
+            'ó' | 'ò' | 'ô' | 'õ' | 'ö' => 'o',
+            'ú' | 'ù' | 'û' | 'ü' => 'u',
+            'ç' => 'c',
+            c if c.is_alphanumeric() => c,
+            _ => ' ',
+        })
+        .collect();
+    let mut words: Vec<&str> = folded.split_whitespace().collect();
+    while words.first().is_some_and(|w| FILLER.contains(w)) {
+        words.remove(0);
+    }
+    while words.last().is_some_and(|w| FILLER.contains(w)) {
+        words.pop();
+    }
+    words.join(" ")
+}
+
+fn number(word: &str) -> Option<u64> {
+    if let Ok(n) = word.parse() {
+        return Some(n);
+    }
+    Some(match word {
+        "a" | "an" | "one" | "um" | "uma" => 1,
+        "two" | "dois" | "duas" => 2,
+        "three" | "tres" => 3,
+        "four" | "quatro" => 4,
+        "five" | "cinco" => 5,
+        "six" | "seis" => 6,
+        "seven" | "sete" => 7,
+        "eight" | "oito" => 8,
+        "nine" | "nove" => 9,
+        "ten" | "dez" => 10,
+        "eleven" | "onze" => 11,
+        "twelve" | "doze" => 12,
+        "fifteen" | "quinze" => 15,
+        "twenty" | "vinte" => 20,
+        "thirty" | "trinta" => 30,
+        "forty" | "quarenta" => 40,
+        "fifty" | "cinquenta" => 50,
+        "sixty" | "sessenta" => 60,
+        "ninety" | "noventa" => 90,
+        _ => return None,
+    })
+}
+
+fn unit(word: &str) -> Option<u64> {
+    match word.trim_end_matches('s') {
+        "second" | "sec" | "segundo" => Some(1),
+        "minute" | "min" | "minuto" => Some(60),
+        "hour" | "hr" | "hora" => Some(3600),
+        _ => None,
+    }
+}
+
+/// Total seconds of the "<number> <unit>" pairs in `words` ("twenty five
+/// minutes", "1 hour and 30 minutes"); 0 when there are none
+fn duration(words: &[&str]) -> u64 {
+    let mut total = 0;
+    let mut pending: Option<u64> = None;
+    for word in words {
+        if let Some(n) = number(word) {
+            pending = Some(pending.unwrap_or(0) + n);
+        } else if let (Some(n), Some(unit)) = (pending, unit(word)) {
+            total += n * unit;
+            pending = None;
+        } else if !matches!(*word, "and" | "e") {
+            pending = None;
+        }
+    }
+    total
+}
+
+/// The intent of `text`, if it is one handled locally
+pub fn parse(text: &str) -> Option<Intent> {
+    let text = normalize(text);
+    let words: Vec<&str> = text.split(' ').collect();
+    if STOP.contains(&text.as_str()) {
+        return Some(Intent::Stop);
+    }
+    if MUTE.contains(&text.as_str()) {
+        return Some(Intent::Mute);
+    }
+    if WHAT_TIME.contains(&text.as_str()) {
+        return Some(Intent::WhatTime);
+    }
+    let cancelling = words.iter().any(|w| matches!(*w, "cancel" | "stop" | "cancelar" | "pare" | "parar"));
+    if !cancelling && words.iter().any(|w| TIMER_WORDS.contains(w)) {
+        let seconds = duration(&words);
+        if seconds > 0 {
+            return Some(Intent::SetTimer { seconds });
+        }
+    }
+    None
+}
+
+// ─── Handling ───────────────────────────────────────
+
+fn format_duration(seconds: u64, lang: &str) -> String {
+    let names: [(&str, &str); 3] = if lang == "pt" {
+        [("hora", "horas"), ("minuto", "minutos"), ("segundo", "segundos")]
+    } else {
+        [("hour", "hours"), ("minute", "minutes"), ("second", "seconds")]
+    };
+    let parts = [seconds / 3600, seconds % 3600 / 60, seconds % 60];
+    parts
+        .iter()
+        .zip(names)
+        .filter(|(n, _)| **n > 0)
+        .map(|(n, (one, many))| format!("{} {}", n, if *n == 1 { one } else { many }))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn run(intent: &Intent) -> Result<String, String> {
+    let pt = crate::i18n::language() == "pt";
+    match intent {
+        Intent::SetTimer { seconds } => {
+            crate::timers::start(None, Some(*seconds), None)?;
+            let span = format_duration(*seconds, crate::i18n::language());
+            Ok(if pt { format!("Timer de {} definido.", span) } else { format!("Timer set for {}.", span) })
+        }
+        Intent::Mute => {
+            match crate::events::app_handle() {
+                Some(app) => crate::commands::apply_mic_mute(app, true),
+                None => crate::voice::set_mic_muted(true),
+            }
+            Ok(if pt { "Microfone silenciado." } else { "Microphone muted." }.into())
+        }
+        Intent::WhatTime => {
+            let now = chrono::Local::now().format("%H:%M");
+            Ok(if pt { format!("São {}.", now) } else { format!("It's {}.", now) })
+        }
+        Intent::Stop => {
+            crate::voice::stop_playback();
+            Ok(String::new())
+        }
+    }
+}
+
+/// Handle `text` on the device if it is a local intent: returns the reply
+/// to show and speak (empty for `Stop`), or None to send it to the Gateway
+pub fn respond(text: &str) -> Option<String> {
+    if !enabled() {
+        return None;
+    }
+    let intent = parse(text)?;
+    tracing::info!("[Intents] Handling {:?} locally", intent);
+    Some(run(&intent).unwrap_or_else(|e| e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_intents() {
+        let timer = |seconds| Some(Intent::SetTimer { seconds });
+        assert_eq!(parse("Set a timer for ten minutes, please."), timer(600));
+        assert_eq!(parse("timer for 1 hour and 30 minutes"), timer(5400));
+        assert_eq!(parse("twenty five minute timer"), timer(1500));
+        assert_eq!(parse("Timer de 5 minutos"), timer(300));
+        assert_eq!(parse("cancel the timer"), None);
+        assert_eq!(parse("set a timer"), None);
+
+        assert_eq!(parse("Stop!"), Some(Intent::Stop));
+        assert_eq!(parse("Hey Forge, mute"), Some(Intent::Mute));
+        assert_eq!(parse("What's the time?"), Some(Intent::WhatTime));
+        assert_eq!(parse("Que horas são?"), Some(Intent::WhatTime));
+        assert_eq!(parse("stop the music in the kitchen"), None);
+        assert_eq!(parse("what time is it in Tokyo"), None);
+
+        assert_eq!(format_duration(5400, "en"), "1 hour 30 minutes");
+        assert_eq!(format_duration(61, "pt"), "1 minuto 1 segundo");
+    }
+}
@@ -0,0 +1,393 @@
+//! # Job Manager — Spawned Process Monitoring
+//!
+//! Every process started by a shell action is registered here as a job.
+//! While it runs, a monitor samples CPU and memory of the whole process
+//! tree (e.g. `npm install` and all its node children). Exceeding a limit
+//! is logged; with auto-kill enabled (off by default) the tree is terminated.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Read;
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+
+/// How often a running job is sampled
+const SAMPLE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// CPU must stay above the limit this long before the job is killed
+const CPU_GRACE: Duration = Duration::from_secs(10);
+
+/// Number of finished jobs kept for inspection
+const MAX_FINISHED_JOBS: usize = 50;
+
+static JOBS: OnceLock<Mutex<HashMap<u64, JobInfo>>> = OnceLock::new();
+static LIMITS: OnceLock<Mutex<JobLimits>> = OnceLock::new();
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Resource limits applied to every job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobLimits {
+    /// Kill the job when a limit is exceeded (otherwise only log a warning)
+    pub auto_kill: bool,
+    /// Resident memory of the whole process tree, in MB
+    pub max_memory_mb: Option<u64>,
+    /// CPU usage of the whole process tree (100 = one full core), sustained
+    pub max_cpu_percent: Option<f32>,
+    /// Wall-clock runtime
+    pub max_runtime_secs: Option<u64>,
+}
+
+impl Default for JobLimits {
+    fn default() -> Self {
+        Self {
+            auto_kill: false,
+            max_memory_mb: Some(4096),
+            max_cpu_percent: None,
+            max_runtime_secs: Some(15 * 60),
+        }
+    }
+}
+
+/// Job lifecycle state
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum JobStatus {
+    Running,
+    Completed,
+    Failed,
+    Killed,
+}
+
+/// Snapshot of a job and its resource usage
+#[derive(Debug, Clone, Serialize)]
+pub struct JobInfo {
+    pub id: u64,
+    pub command: String,
+    pub pid: u32,
+    pub status: JobStatus,
+    pub started_at: String,
+    pub runtime_ms: u64,
+    /// Number of processes in the tree at the last sample
+    pub process_count: usize,
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub peak_memory_bytes: u64,
+    pub exit_code: Option<i32>,
+    pub kill_reason: Option<String>,
+}
+
+fn jobs() -> &'static Mutex<HashMap<u64, JobInfo>> {
+    JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn limits_lock() -> &'static Mutex<JobLimits> {
+    LIMITS.get_or_init(|| Mutex::new(JobLimits::default()))
+}
+
+/// Current job limits
+pub fn limits() -> JobLimits {
+    limits_lock().lock().map(|l| l.clone()).unwrap_or_default()
+}
+
+/// Replace the job limits (applies to running jobs at their next sample)
+pub fn set_limits(limits: JobLimits) {
+    if let Ok(mut l) = limits_lock().lock() {
+        *l = limits;
+    }
+}
+
+/// All known jobs, running first, newest first
+pub fn list() -> Vec<JobInfo> {
+    let mut list: Vec<JobInfo> = jobs()
+        .lock()
+        .map(|j| j.values().cloned().collect())
+        .unwrap_or_default();
+    list.sort_by(|a, b| {
+        (b.status == JobStatus::Running)
+            .cmp(&(a.status == JobStatus::Running))
+            .then(b.id.cmp(&a.id))
+    });
+    list
+}
+
+/// Request termination of a running job
+pub fn kill(id: u64) -> Result<(), String> {
+    let pid = {
+        let mut map = jobs().lock().map_err(|e| e.to_string())?;
+        let job = map.get_mut(&id).ok_or(format!("Job {} not found", id))?;
+        if job.status != JobStatus::Running {
+            return Err(format!("Job {} is not running", id));
+        }
+        job.kill_reason = Some("Killed by user".into());
+        job.pid
+    };
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::All, true);
+    kill_tree(&sys, Pid::from_u32(pid));
+    Ok(())
+}
+
+fn update(id: u64, f: impl FnOnce(&mut JobInfo)) {
+    if let Ok(mut map) = jobs().lock() {
+        if let Some(job) = map.get_mut(&id) {
+            f(job);
+        }
+    }
+}
+
+fn prune_finished(map: &mut HashMap<u64, JobInfo>) {
+    let mut finished: Vec<u64> = map
+        .values()
+        .filter(|j| j.status != JobStatus::Running)
+        .map(|j| j.id)
+        .collect();
+    if finished.len() <= MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort_unstable();
+    for id in &finished[..finished.len() - MAX_FINISHED_JOBS] {
+        map.remove(id);
+    }
+}
+
+/// PIDs of `root` and all of its descendants
+fn process_tree(sys: &System, root: Pid) -> Vec<Pid> {
+    let mut tree = vec![root];
+    let mut i = 0;
+    while i < tree.len() {
+        let parent = tree[i];
+        for (pid, proc_) in sys.processes() {
+            if proc_.parent() == Some(parent) && !tree.contains(pid) {
+                tree.push(*pid);
+            }
+        }
+        i += 1;
+    }
+    tree.retain(|pid| sys.process(*pid).is_some());
+    tree
+}
+
+/// Kill children before the parent so nothing gets re-parented and escapes
+fn kill_tree(sys: &System, root: Pid) {
+    for pid in process_tree(sys, root).iter().rev() {
+        if let Some(p) = sys.process(*pid) {
+            p.kill();
+        }
+    }
+}
+
+fn drain<R: Read + Send + 'static>(reader: Option<R>) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut r) = reader {
+            let _ = r.read_to_end(&mut buf);
+        }
+        buf
+    })
+}
+
+/// Check a sample against the limits; returns the kill reason when exceeded
+fn exceeded(limits: &JobLimits, runtime: Duration, memory: u64, cpu_over_since: Option<Instant>) -> Option<String> {
+    if let Some(max) = limits.max_memory_mb {
+        if memory > max * 1024 * 1024 {
+            return Some(format!("memory {}MB exceeded limit of {}MB", memory / (1024 * 1024), max));
+        }
+    }
+    if let Some(max) = limits.max_runtime_secs {
+        if runtime.as_secs() > max {
+            return Some(format!("runtime exceeded limit of {}s", max));
+        }
+    }
+    if let (Some(max), Some(since)) = (limits.max_cpu_percent, cpu_over_since) {
+        if since.elapsed() >= CPU_GRACE {
+            return Some(format!("CPU above {:.0}% for {}s", max, CPU_GRACE.as_secs()));
+        }
+    }
+    None
+}
+
+/// Spawn `cmd` as a monitored job and wait for it to finish.
+/// Behaves like `Command::output()`, but the process tree is sampled while it
+/// runs and killed if it exceeds the job limits. Returns the job snapshot too.
+pub fn run_monitored(mut cmd: Command, label: &str) -> std::io::Result<(Output, JobInfo)> {
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped()).stdin(Stdio::null());
+    let mut child: Child = cmd.spawn()?;
+
+    let id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+    let pid = child.id();
+    let started = Instant::now();
+
+    if let Ok(mut map) = jobs().lock() {
+        prune_finished(&mut map);
+        map.insert(id, JobInfo {
+            id,
+            command: label.chars().take(200).collect(),
+            pid,
+            status: JobStatus::Running,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            runtime_ms: 0,
+            process_count: 1,
+            cpu_percent: 0.0,
+            memory_bytes: 0,
+            peak_memory_bytes: 0,
+            exit_code: None,
+            kill_reason: None,
+        });
+    }
+    tracing::info!("[Jobs] Job {} started (pid {}): {}", id, pid, label);
+
+    // Drain pipes on separate threads so a chatty process can't block on a full pipe
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+
+    let mut sys = System::new();
+    let root = Pid::from_u32(pid);
+    let mut cpu_over_since: Option<Instant> = None;
+    let mut warned = false;
+
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status,
+            Ok(None) => {}
+            Err(e) => {
+                // The process can no longer be observed — don't leave it behind as "Running"
+                tracing::warn!("[Jobs] Job {} lost track of its process: {}", id, e);
+                kill_tree(&sys, root);
+                let _ = child.kill();
+                update(id, |job| {
+                    job.runtime_ms = started.elapsed().as_millis() as u64;
+                    job.status = JobStatus::Failed;
+                    job.kill_reason = Some(format!("process wait failed: {}", e));
+                });
+                return Err(e);
+            }
+        }
+
+        sys.refresh_processes(ProcessesToUpdate::All, true);
+        let tree = process_tree(&sys, root);
+        let (cpu, memory) = tree
+            .iter()
+            .filter_map(|p| sys.process(*p))
+            .fold((0.0f32, 0u64), |(c, m), p| (c + p.cpu_usage(), m + p.memory()));
+        let runtime = started.elapsed();
+
+        let limits = limits();
+        cpu_over_since = match limits.max_cpu_percent {
+            Some(max) if cpu > max => cpu_over_since.or(Some(Instant::now())),
+            _ => None,
+        };
+
+        update(id, |job| {
+            job.runtime_ms = runtime.as_millis() as u64;
+            job.process_count = tree.len();
+            job.cpu_percent = cpu;
+            job.memory_bytes = memory;
+            job.peak_memory_bytes = job.peak_memory_bytes.max(memory);
+        });
+
+        if let Some(reason) = exceeded(&limits, runtime, memory, cpu_over_since) {
+            if limits.auto_kill {
+                tracing::warn!("[Jobs] Job {} killed: {}", id, reason);
+                update(id, |job| job.kill_reason = Some(reason));
+                kill_tree(&sys, root);
+            } else if !warned {
+                tracing::warn!("[Jobs] Job {} over limit (auto-kill off): {}", id, reason);
+                warned = true;
+            }
+        }
+
+        std::thread::sleep(SAMPLE_INTERVAL);
+    };
+
+    let output = Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    };
+
+    update(id, |job| {
+        job.runtime_ms = started.elapsed().as_millis() as u64;
+        job.exit_code = status.code();
+        job.status = if job.kill_reason.is_some() {
+            JobStatus::Killed
+        } else if status.success() {
+            JobStatus::Completed
+        } else {
+            JobStatus::Failed
+        };
+    });
+
+    let info = jobs()
+        .lock()
+        .ok()
+        .and_then(|m| m.get(&id).cloned())
+        .ok_or_else(|| std::io::Error::other("job record lost"))?;
+    tracing::info!(
+        "[Jobs] Job {} finished: {:?} in {}ms (peak {}MB)",
+        id,
+        info.status,
+        info.runtime_ms,
+        info.peak_memory_bytes / (1024 * 1024)
+    );
+    Ok((output, info))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(id: u64, status: JobStatus) -> JobInfo {
+        JobInfo {
+            id,
+            command: String::new(),
+            pid: 0,
+            status,
+            started_at: String::new(),
+            runtime_ms: 0,
+            process_count: 1,
+            cpu_percent: 0.0,
+            memory_bytes: 0,
+            peak_memory_bytes: 0,
+            exit_code: None,
+            kill_reason: None,
+        }
+    }
+
+    #[test]
+    fn test_limits_and_pruning() {
+        assert!(!JobLimits::default().auto_kill);
+
+        let limits = JobLimits {
+            auto_kill: true,
+            max_memory_mb: Some(100),
+            max_cpu_percent: Some(50.0),
+            max_runtime_secs: Some(60),
+        };
+        let mb = 1024 * 1024;
+        assert!(exceeded(&limits, Duration::from_secs(1), 50 * mb, None).is_none());
+        assert!(exceeded(&limits, Duration::from_secs(1), 101 * mb, None).unwrap().contains("memory"));
+        assert!(exceeded(&limits, Duration::from_secs(61), 0, None).unwrap().contains("runtime"));
+        // CPU only counts once it has stayed over the limit for the grace period
+        assert!(exceeded(&limits, Duration::from_secs(1), 0, Some(Instant::now())).is_none());
+        let long_ago = Instant::now().checked_sub(CPU_GRACE + Duration::from_secs(1));
+        if long_ago.is_some() {
+            assert!(exceeded(&limits, Duration::from_secs(1), 0, long_ago).unwrap().contains("CPU"));
+        }
+        let none = JobLimits { max_memory_mb: None, max_runtime_secs: None, ..limits };
+        assert!(exceeded(&none, Duration::from_secs(3600), u64::MAX / 2, None).is_none());
+
+        // Oldest finished jobs go first; running jobs are never pruned
+        let mut map = HashMap::new();
+        map.insert(0, job(0, JobStatus::Running));
+        for id in 1..=(MAX_FINISHED_JOBS as u64 + 5) {
+            map.insert(id, job(id, JobStatus::Completed));
+        }
+        prune_finished(&mut map);
+        assert_eq!(map.len(), MAX_FINISHED_JOBS + 1);
+        assert!(map.contains_key(&0));
+        assert!(!map.contains_key(&5));
+        assert!(map.contains_key(&6));
+    }
+}
@@ -0,0 +1,154 @@
+//! # Spoken Language Detection
+//!
+//! For households that speak more than one language. With
+//! `autoLanguage.enabled`, every utterance is tagged with the language it
+//! was spoken in: the one the Gateway's STT reports when it does, otherwise
+//! one detected locally from the transcript by counting the common words of
+//! each of the household's `languages`. Voice turns then send that language
+//! with the request, so the reply is written and synthesized in it; when
+//! it is not known yet, the Gateway is asked to detect it.
+//!
+//! Local detection only knows the languages in [`KNOWN`] and gives up on
+//! utterances too short or too mixed to tell.
+
+use crate::translate::same_language;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Languages the local detector recognizes, with words common in speech
+/// and rare in the others
+const KNOWN: &[(&str, &[&str])] = &[
+    ("en", &[
+        "the", "is", "are", "what", "how", "you", "my", "i", "it", "to", "and", "of", "this", "that", "please",
+        "can", "with", "for", "me", "time", "turn", "on", "off", "was", "be", "have", "tell", "about", "today",
+    ]),
+    ("pt", &[
+        "o", "os", "é", "são", "você", "meu", "minha", "não", "que", "uma", "um", "do", "da", "dos", "das", "para",
+        "com", "isso", "isto", "está", "por", "favor", "hoje", "horas", "como", "qual", "quanto", "ligar", "desligar",
+        "também", "então", "me", "diga", "obrigado",
+    ]),
+    ("es", &[
+        "el", "los", "las", "es", "son", "usted", "tú", "mi", "no", "que", "una", "un", "del", "para", "con", "esto",
+        "eso", "está", "por", "favor", "hoy", "hora", "cómo", "cuál", "cuánto", "encender", "apagar", "también",
+        "entonces", "dime", "gracias", "y", "qué",
+    ]),
+    ("fr", &[
+        "le", "la", "les", "est", "sont", "vous", "tu", "mon", "ma", "ne", "pas", "une", "un", "du", "des", "pour",
+        "avec", "ce", "cette", "c'est", "s'il", "plaît", "aujourd'hui", "heure", "comment", "quel", "quelle",
+        "allumer", "éteindre", "aussi", "alors", "dis", "merci", "et", "je", "qu'est-ce",
+    ]),
+    ("de", &[
+        "der", "die", "das", "ist", "sind", "sie", "du", "mein", "meine", "nicht", "ein", "eine", "und", "für", "mit",
+        "bitte", "heute", "uhr", "wie", "was", "welche", "einschalten", "ausschalten", "auch", "dann", "sag",
+        "danke", "ich", "es", "zu",
+    ]),
+    ("it", &[
+        "il", "lo", "gli", "è", "sono", "lei", "tu", "mio", "mia", "non", "che", "una", "un", "del", "della", "per",
+        "con", "questo", "quello", "favore", "oggi", "ore", "come", "quale", "quanto", "accendi", "spegni", "anche",
+        "allora", "dimmi", "grazie", "e", "io",
+    ]),
+];
+
+/// Fewest common words a transcript needs for a local guess
+const MIN_HITS: usize = 2;
+
+static CONFIG: Mutex<Option<AutoLanguageConfig>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AutoLanguageConfig {
+    pub enabled: bool,
+    /// Languages spoken in the household (BCP 47 primary tags); local
+    /// detection picks among these
+    pub languages: Vec<String>,
+}
+
+impl Default for AutoLanguageConfig {
+    fn default() -> Self {
+        Self { enabled: false, languages: vec!["en".into(), "pt".into()] }
+    }
+}
+
+impl AutoLanguageConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for language in &self.languages {
+            if !KNOWN.iter().any(|(tag, _)| same_language(tag, language)) {
+                let known: Vec<_> = KNOWN.iter().map(|(tag, _)| *tag).collect();
+                return Err(format!("Cannot detect '{}'; known languages: {}", language, known.join(", ")));
+            }
+        }
+        if self.enabled && self.languages.len() < 2 {
+            return Err("Automatic language detection needs at least two languages".into());
+        }
+        Ok(())
+    }
+}
+
+fn config() -> AutoLanguageConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+pub fn set_config(config: &AutoLanguageConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+/// Whether utterances are tagged and replied to in their own language
+pub fn enabled() -> bool {
+    config().enabled
+}
+
+/// The language among `candidates` whose common words `text` uses most,
+/// when one clearly does
+fn detect_among(text: &str, candidates: &[String]) -> Option<&'static str> {
+    let lower = text.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .filter(|w| !w.is_empty())
+        .collect();
+    let mut scores: Vec<(&'static str, usize)> = KNOWN
+        .iter()
+        .filter(|(tag, _)| candidates.iter().any(|c| same_language(tag, c)))
+        .map(|(tag, common)| (*tag, words.iter().filter(|w| common.contains(w)).count()))
+        .collect();
+    scores.sort_by_key(|(_, hits)| std::cmp::Reverse(*hits));
+    match scores.as_slice() {
+        [(best, hits), rest @ ..] if *hits >= MIN_HITS && rest.first().is_none_or(|(_, next)| next < hits) => Some(best),
+        _ => None,
+    }
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Language of an utterance: the one the STT `reported`, or with automatic
+/// detection on, a local guess from its `text`
+pub fn resolve(reported: Option<&str>, text: &str) -> Option<String> {
+    let reported = reported.map(str::trim).filter(|l| !l.is_empty()).map(str::to_string);
+    let config = config();
+    if !config.enabled {
+        return reported;
+    }
+    reported.or_else(|| detect_among(text, &config.languages).map(str::to_string))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_detection() {
+        let household = vec!["en".to_string(), "pt-BR".to_string()];
+        assert_eq!(detect_among("What time is it?", &household), Some("en"));
+        assert_eq!(detect_among("Que horas são agora?", &household), Some("pt"));
+        assert_eq!(detect_among("Desligar a luz da sala, por favor", &household), Some("pt"));
+        // Too short to tell
+        assert_eq!(detect_among("Spotify", &household), None);
+        // Spanish is not spoken here
+        assert_eq!(detect_among("¿Qué hora es hoy?", &household), None);
+
+        assert!(AutoLanguageConfig::default().validate().is_ok());
+        assert!(AutoLanguageConfig { languages: vec!["xx".into(), "en".into()], ..Default::default() }.validate().is_err());
+        assert!(AutoLanguageConfig { enabled: true, languages: vec!["en".into()] }.validate().is_err());
+    }
+}
@@ -0,0 +1,184 @@
+//! # Voice Latency Measurement
+//!
+//! Where the time between the end of a spoken request and the first sound
+//! of the reply goes. `measure_voice_latency` runs one voice turn with a
+//! [`Trace`] active; the pipeline drops a marker at each [`Stage`] it
+//! passes (capture, encoding, Gateway request, playback) and the trace is
+//! turned into a breakdown of the gaps between consecutive markers.
+//!
+//! The Gateway round trip is split further when the reply carries
+//! `timings` (`sttMs`, `chatMs`, `ttsMs`); what those do not account for is
+//! reported as `network` (upload and download). Markers outside a
+//! measurement cost a lock and nothing else.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Instant;
+
+static TRACE: Mutex<Option<Trace>> = Mutex::new(None);
+
+/// Points of a voice turn, in pipeline order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    /// Last audio above the silence threshold
+    SpeechEnd,
+    CaptureStop,
+    /// WAV encoded
+    Encoded,
+    /// Request on its way to the Gateway
+    RequestSent,
+    /// Transcript back, for turns answered on the device
+    Transcribed,
+    /// Gateway reply (or local answer) received
+    Replied,
+    /// Speech synthesized, when that is a separate request
+    Synthesized,
+    FirstAudio,
+}
+
+impl Stage {
+    /// Name of the gap that ends at this stage
+    fn gap(self) -> &'static str {
+        match self {
+            Stage::SpeechEnd => "speech",
+            Stage::CaptureStop => "endpointing",
+            Stage::Encoded => "encode",
+            Stage::RequestSent => "prepare",
+            Stage::Transcribed => "stt",
+            Stage::Replied => "gateway",
+            Stage::Synthesized => "tts",
+            Stage::FirstAudio => "playbackStart",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Trace {
+    marks: Vec<(Stage, Instant)>,
+    /// Gateway-reported (stt, chat, tts) milliseconds
+    gateway: Option<(f64, f64, f64)>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageTiming {
+    pub stage: &'static str,
+    pub ms: f64,
+}
+
+/// Result of `measure_voice_latency`
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LatencyReport {
+    pub stages: Vec<StageTiming>,
+    /// End of speech to first audio out
+    pub total_ms: f64,
+    /// The stage that took longest
+    pub slowest: Option<&'static str>,
+}
+
+impl Trace {
+    fn report(&self) -> LatencyReport {
+        let mut marks = self.marks.clone();
+        marks.sort_by_key(|(stage, _)| *stage);
+        let mut stages = Vec::new();
+        for pair in marks.windows(2) {
+            let (from, (stage, to)) = (pair[0].1, pair[1]);
+            let ms = to.saturating_duration_since(from).as_secs_f64() * 1000.0;
+            match (stage, self.gateway) {
+                (Stage::Replied, Some((stt, chat, tts))) if pair[0].0 == Stage::RequestSent => {
+                    for (name, part) in [("stt", stt), ("chat", chat), ("tts", tts)] {
+                        if part > 0.0 {
+                            stages.push(StageTiming { stage: name, ms: part });
+                        }
+                    }
+                    stages.push(StageTiming { stage: "network", ms: (ms - stt - chat - tts).max(0.0) });
+                }
+                _ => stages.push(StageTiming { stage: stage.gap(), ms }),
+            }
+        }
+        let total_ms = match (marks.first(), marks.last()) {
+            (Some((_, first)), Some((_, last))) => last.saturating_duration_since(*first).as_secs_f64() * 1000.0,
+            _ => 0.0,
+        };
+        let slowest = stages.iter().max_by(|a, b| a.ms.total_cmp(&b.ms)).map(|s| s.stage);
+        LatencyReport { stages, total_ms, slowest }
+    }
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Start recording markers
+pub fn begin() {
+    if let Ok(mut trace) = TRACE.lock() {
+        *trace = Some(Trace::default());
+    }
+}
+
+/// Stop recording markers and break down what was recorded
+pub fn finish() -> LatencyReport {
+    let trace = TRACE.lock().ok().and_then(|mut t| t.take()).unwrap_or_default();
+    trace.report()
+}
+
+/// `stage` was reached now
+pub fn mark(stage: Stage) {
+    mark_at(stage, Instant::now());
+}
+
+/// `stage` was reached at `at`; only the first time counts
+pub fn mark_at(stage: Stage, at: Instant) {
+    if let Ok(mut guard) = TRACE.lock() {
+        if let Some(trace) = guard.as_mut().filter(|t| !t.marks.iter().any(|(s, _)| *s == stage)) {
+            trace.marks.push((stage, at));
+        }
+    }
+}
+
+/// The `timings` object of a Gateway reply
+pub fn gateway_timings(timings: &serde_json::Value) {
+    let ms = |key: &str| timings[key].as_f64().unwrap_or(0.0);
+    if ms("sttMs") + ms("chatMs") + ms("ttsMs") <= 0.0 {
+        return;
+    }
+    if let Ok(mut guard) = TRACE.lock() {
+        if let Some(trace) = guard.as_mut() {
+            trace.gateway = Some((ms("sttMs"), ms("chatMs"), ms("ttsMs")));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_latency_breakdown() {
+        let t0 = Instant::now();
+        let at = |ms| t0 + Duration::from_millis(ms);
+        let mut trace = Trace {
+            marks: vec![
+                (Stage::FirstAudio, at(3900)),
+                (Stage::SpeechEnd, at(0)),
+                (Stage::CaptureStop, at(800)),
+                (Stage::Encoded, at(820)),
+                (Stage::RequestSent, at(900)),
+                (Stage::Replied, at(3800)),
+            ],
+            gateway: None,
+        };
+        let report = trace.report();
+        let names: Vec<_> = report.stages.iter().map(|s| s.stage).collect();
+        assert_eq!(names, ["endpointing", "encode", "prepare", "gateway", "playbackStart"]);
+        assert_eq!(report.total_ms.round(), 3900.0);
+        assert_eq!(report.slowest, Some("gateway"));
+
+        // Gateway timings split the round trip
+        trace.gateway = Some((400.0, 2000.0, 300.0));
+        let report = trace.report();
+        let gateway: Vec<_> = report.stages.iter().skip(3).take(4).map(|s| (s.stage, s.ms.round())).collect();
+        assert_eq!(gateway, [("stt", 400.0), ("chat", 2000.0), ("tts", 300.0), ("network", 200.0)]);
+        assert_eq!(report.slowest, Some("chat"));
+    }
+}
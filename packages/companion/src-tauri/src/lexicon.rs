@@ -0,0 +1,136 @@
+//! # Pronunciation Lexicon
+//!
+//! Words the speech synthesizer says wrong, kept in the settings as
+//! `lexicon.entries`, each with how to say it: a respelling such as
+//! "engine x" for "nginx" or "Lee-AH-nah" for "Liana".
+//!
+//! Text is rewritten before every synthesis request and before the offline
+//! fallback synthesizer speaks it. Replies the Gateway synthesizes itself
+//! (voice turns, duplex conversations) carry the entries as `lexicon`, for
+//! Gateways that apply them.
+//!
+//! Matching is case-insensitive on whole words; longer entries win.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Longest accepted word or pronunciation
+const MAX_ENTRY_LEN: usize = 100;
+
+/// Compiled replacements of the current lexicon
+static RULES: Mutex<Vec<(Regex, String)>> = Mutex::new(Vec::new());
+static ENTRIES: Mutex<Vec<LexiconEntry>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LexiconConfig {
+    pub entries: Vec<LexiconEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LexiconEntry {
+    /// As written, e.g. "nginx"
+    pub word: String,
+    /// What to say instead, e.g. "engine x"
+    pub say: String,
+}
+
+impl LexiconConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for entry in &self.entries {
+            for text in [&entry.word, &entry.say] {
+                if text.trim().is_empty() || text.len() > MAX_ENTRY_LEN {
+                    return Err(format!("Lexicon entries must be 1 to {} characters", MAX_ENTRY_LEN));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn compile(config: &LexiconConfig) -> Vec<(Regex, String)> {
+    let mut entries: Vec<&LexiconEntry> = config.entries.iter().collect();
+    entries.sort_by_key(|e| std::cmp::Reverse(e.word.len()));
+    entries
+        .into_iter()
+        .filter_map(|e| {
+            let words: Vec<String> = e.word.split_whitespace().map(regex::escape).collect();
+            Some((Regex::new(&format!("(?i){}", words.join(r"\s+"))).ok()?, e.say.trim().to_string()))
+        })
+        .collect()
+}
+
+fn is_word_char(c: Option<char>) -> bool {
+    c.is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+/// Replace whole-word matches only. Checked by hand rather than with `\b`,
+/// which needs a word character at the edge and so misses "C++".
+fn replace_words(pattern: &Regex, text: &str, say: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    for m in pattern.find_iter(text) {
+        let before = text[..m.start()].chars().next_back();
+        let after = text[m.end()..].chars().next();
+        if is_word_char(before) || is_word_char(after) {
+            continue;
+        }
+        out.push_str(&text[copied..m.start()]);
+        out.push_str(say);
+        copied = m.end();
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+fn apply(rules: &[(Regex, String)], text: &str) -> String {
+    rules.iter().fold(text.to_string(), |text, (pattern, say)| replace_words(pattern, &text, say))
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Use `config` from now on
+pub fn set_config(config: &LexiconConfig) {
+    if let Ok(mut rules) = RULES.lock() {
+        *rules = compile(config);
+    }
+    if let Ok(mut entries) = ENTRIES.lock() {
+        *entries = config.entries.clone();
+    }
+}
+
+/// The entries, for requests the Gateway synthesizes itself
+pub fn entries() -> Vec<LexiconEntry> {
+    ENTRIES.lock().map(|e| e.clone()).unwrap_or_default()
+}
+
+/// `text` as it should be spoken
+pub fn pronounce(text: &str) -> String {
+    match RULES.lock() {
+        Ok(rules) if !rules.is_empty() => apply(&rules, text),
+        _ => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pronunciation() {
+        let entry = |word: &str, say: &str| LexiconEntry { word: word.into(), say: say.into() };
+        let config = LexiconConfig {
+            entries: vec![entry("nginx", "engine x"), entry("SQL", "sequel"), entry("SQL Server", "sequel server"), entry("C++", "C plus plus")],
+        };
+        let rules = compile(&config);
+        assert_eq!(apply(&rules, "Restart NGINX, then check SQL Server."), "Restart engine x, then check sequel server.");
+        assert_eq!(apply(&rules, "I like C++ and SQL"), "I like C plus plus and sequel");
+        // Whole words only
+        assert_eq!(apply(&rules, "MySQL and nginxes"), "MySQL and nginxes");
+
+        assert!(config.validate().is_ok());
+        assert!(LexiconConfig { entries: vec![entry("x", " ")] }.validate().is_err());
+    }
+}
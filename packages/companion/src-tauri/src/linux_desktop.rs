@@ -0,0 +1,237 @@
+//! # Linux Desktop
+//!
+//! Desktop controls on Linux, spoken to over D-Bus instead of through shell
+//! commands (`playerctl`, `loginctl`, `xdg-screensaver`) that may not be
+//! installed and whose output differs between versions:
+//!
+//! - `media_control` — play, pause, next… on an MPRIS media player: the one
+//!   whose name contains `player`, else the one playing, else the first
+//! - `media_status` — the players and what they are playing
+//! - `lock_screen` — the session's screensaver (`org.freedesktop.ScreenSaver`,
+//!   then GNOME's), falling back to logind
+//! - `inhibit_idle` — a logind `idle` inhibitor for `minutes` (0 releases
+//!   it), so the screen does not blank or lock during a presentation
+//!
+//! Elsewhere the actions fail with an error saying they are Linux-only.
+
+use serde::Serialize;
+#[cfg(target_os = "linux")]
+use std::sync::atomic::{AtomicU64, Ordering};
+#[cfg(target_os = "linux")]
+use std::sync::Mutex;
+
+/// `media_control` commands and their MPRIS methods
+pub const MEDIA_COMMANDS: &[(&str, &str)] = &[
+    ("play", "Play"),
+    ("pause", "Pause"),
+    ("play_pause", "PlayPause"),
+    ("next", "Next"),
+    ("previous", "Previous"),
+    ("stop", "Stop"),
+];
+
+/// Longest idle inhibition, in minutes
+pub const MAX_INHIBIT_MINUTES: u64 = 480;
+
+#[cfg(target_os = "linux")]
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+#[cfg(target_os = "linux")]
+const MPRIS_PATH: &str = "/org/mpris/MediaPlayer2";
+#[cfg(target_os = "linux")]
+const MPRIS_PLAYER: &str = "org.mpris.MediaPlayer2.Player";
+
+/// The held idle inhibitor and its generation; dropping the fd releases it
+#[cfg(target_os = "linux")]
+static INHIBITOR: Mutex<Option<(zbus::zvariant::OwnedFd, u64)>> = Mutex::new(None);
+#[cfg(target_os = "linux")]
+static INHIBIT_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MediaPlayer {
+    /// Bus name without the MPRIS prefix, e.g. `spotify` or `firefox.instance_1_42`
+    pub name: String,
+    /// `Playing`, `Paused` or `Stopped`
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist: Option<String>,
+}
+
+/// The MPRIS method for a `media_control` command
+fn media_method(command: &str) -> Option<&'static str> {
+    let command = command.trim().to_lowercase().replace(['-', ' '], "_");
+    MEDIA_COMMANDS.iter().find(|(c, _)| *c == command).map(|(_, method)| *method)
+}
+
+/// The player to control: the one whose name contains `wanted`, else the
+/// one playing, else the first
+fn pick_player<'a>(players: &'a [MediaPlayer], wanted: Option<&str>) -> Option<&'a MediaPlayer> {
+    match wanted.map(str::to_lowercase).filter(|w| !w.trim().is_empty()) {
+        Some(wanted) => players.iter().find(|p| p.name.to_lowercase().contains(wanted.trim())),
+        None => players.iter().find(|p| p.status == "Playing").or_else(|| players.first()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn bus_error(e: impl std::fmt::Display) -> String {
+    format!("D-Bus error: {}", e)
+}
+
+#[cfg(target_os = "linux")]
+fn read_players(conn: &zbus::blocking::Connection) -> Result<Vec<MediaPlayer>, String> {
+    use zbus::zvariant::OwnedValue;
+    let names = zbus::blocking::fdo::DBusProxy::new(conn).map_err(bus_error)?.list_names().map_err(bus_error)?;
+    let mut players = Vec::new();
+    for name in names.iter().filter_map(|n| n.as_str().strip_prefix(MPRIS_PREFIX)) {
+        let Ok(proxy) = zbus::blocking::Proxy::new(conn, format!("{}{}", MPRIS_PREFIX, name), MPRIS_PATH, MPRIS_PLAYER) else {
+            continue;
+        };
+        let metadata: std::collections::HashMap<String, OwnedValue> = proxy.get_property("Metadata").unwrap_or_default();
+        let text = |key: &str| metadata.get(key).and_then(|v| <&str>::try_from(v).ok()).map(str::to_string);
+        let artist = metadata
+            .get("xesam:artist")
+            .and_then(|v| v.try_clone().ok())
+            .and_then(|v| Vec::<String>::try_from(v).ok())
+            .map(|artists| artists.join(", "));
+        players.push(MediaPlayer {
+            name: name.to_string(),
+            status: proxy.get_property("PlaybackStatus").unwrap_or_default(),
+            title: text("xesam:title"),
+            artist: artist.filter(|a| !a.is_empty()),
+        });
+    }
+    Ok(players)
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// MPRIS players on the session bus
+#[cfg(target_os = "linux")]
+pub fn media_players() -> Result<Vec<MediaPlayer>, String> {
+    let conn = zbus::blocking::Connection::session().map_err(bus_error)?;
+    read_players(&conn)
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn media_players() -> Result<Vec<MediaPlayer>, String> {
+    Err("Media players are controlled over D-Bus, on Linux only".into())
+}
+
+/// Send `command` (see [`MEDIA_COMMANDS`]) to a player; returns which one
+pub fn media_control(command: &str, player: Option<&str>) -> Result<String, String> {
+    let method = media_method(command).ok_or_else(|| {
+        let known: Vec<&str> = MEDIA_COMMANDS.iter().map(|(c, _)| *c).collect();
+        format!("Unknown media command '{}' (expected one of: {})", command, known.join(", "))
+    })?;
+    send_media(method, player)
+}
+
+#[cfg(target_os = "linux")]
+fn send_media(method: &str, player: Option<&str>) -> Result<String, String> {
+    let conn = zbus::blocking::Connection::session().map_err(bus_error)?;
+    let players = read_players(&conn)?;
+    let target = pick_player(&players, player).ok_or_else(|| match player {
+        Some(wanted) => format!("No media player named '{}' is running", wanted),
+        None => "No media player is running".to_string(),
+    })?;
+    let proxy = zbus::blocking::Proxy::new(&conn, format!("{}{}", MPRIS_PREFIX, target.name), MPRIS_PATH, MPRIS_PLAYER)
+        .map_err(bus_error)?;
+    proxy.call_method(method, &()).map_err(bus_error)?;
+    tracing::info!("[LinuxDesktop] {} sent to {}", method, target.name);
+    Ok(target.name.clone())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn send_media(_method: &str, _player: Option<&str>) -> Result<String, String> {
+    Err("Media players are controlled over D-Bus, on Linux only".into())
+}
+
+/// Lock the screen
+#[cfg(target_os = "linux")]
+pub fn lock_screen() -> Result<(), String> {
+    use zbus::blocking::{Connection, Proxy};
+    let session = Connection::session().map_err(bus_error)?;
+    let screensavers = [
+        ("org.freedesktop.ScreenSaver", "/org/freedesktop/ScreenSaver", "org.freedesktop.ScreenSaver"),
+        ("org.gnome.ScreenSaver", "/org/gnome/ScreenSaver", "org.gnome.ScreenSaver"),
+    ];
+    for (dest, path, iface) in screensavers {
+        let locked = Proxy::new(&session, dest, path, iface).and_then(|p| p.call_method("Lock", &()));
+        match locked {
+            Ok(_) => return Ok(()),
+            Err(e) => tracing::debug!("[LinuxDesktop] {} could not lock: {}", dest, e),
+        }
+    }
+    let system = Connection::system().map_err(bus_error)?;
+    Proxy::new(&system, "org.freedesktop.login1", "/org/freedesktop/login1/session/auto", "org.freedesktop.login1.Session")
+        .and_then(|p| p.call_method("Lock", &()))
+        .map(|_| ())
+        .map_err(|e| format!("Could not lock the screen: {}", e))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn lock_screen() -> Result<(), String> {
+    Err("The screen is locked over D-Bus, on Linux only".into())
+}
+
+/// Keep the screen from blanking or locking for `minutes`; 0 releases the
+/// inhibitor. A new inhibition replaces the previous one.
+pub fn inhibit_idle(minutes: u64, why: &str) -> Result<String, String> {
+    if minutes > MAX_INHIBIT_MINUTES {
+        return Err(format!("Idle can be inhibited for at most {} minutes", MAX_INHIBIT_MINUTES));
+    }
+    let why = if why.trim().is_empty() { "Requested by voice" } else { why.trim() };
+    hold_inhibitor(minutes, why)
+}
+
+#[cfg(target_os = "linux")]
+fn hold_inhibitor(minutes: u64, why: &str) -> Result<String, String> {
+    if minutes == 0 {
+        let released = INHIBITOR.lock().map_err(|e| e.to_string())?.take().is_some();
+        return Ok(if released { "Idle inhibitor released".into() } else { "No idle inhibitor was held".into() });
+    }
+    let system = zbus::blocking::Connection::system().map_err(bus_error)?;
+    let manager = zbus::blocking::Proxy::new(&system, "org.freedesktop.login1", "/org/freedesktop/login1", "org.freedesktop.login1.Manager")
+        .map_err(bus_error)?;
+    let fd: zbus::zvariant::OwnedFd = manager.call("Inhibit", &("idle", "ForgeAI", why, "block")).map_err(bus_error)?;
+
+    let generation = INHIBIT_GENERATION.fetch_add(1, Ordering::Relaxed) + 1;
+    *INHIBITOR.lock().map_err(|e| e.to_string())? = Some((fd, generation));
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_secs(minutes * 60));
+        if let Ok(mut held) = INHIBITOR.lock() {
+            if held.as_ref().is_some_and(|(_, g)| *g == generation) {
+                *held = None;
+                tracing::info!("[LinuxDesktop] Idle inhibitor expired");
+            }
+        }
+    });
+    tracing::info!("[LinuxDesktop] Idle inhibited for {} min: {}", minutes, why);
+    Ok(format!("Screen kept awake for {} minutes", minutes))
+}
+
+#[cfg(not(target_os = "linux"))]
+fn hold_inhibitor(_minutes: u64, _why: &str) -> Result<String, String> {
+    Err("Idle is inhibited over D-Bus, on Linux only".into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_media_commands_and_player_choice() {
+        assert_eq!(media_method("Play-Pause"), Some("PlayPause"));
+        assert_eq!(media_method("next"), Some("Next"));
+        assert_eq!(media_method("rewind"), None);
+
+        let player = |name: &str, status: &str| MediaPlayer { name: name.into(), status: status.into(), ..Default::default() };
+        let players = [player("firefox.instance_1_42", "Paused"), player("spotify", "Playing")];
+        assert_eq!(pick_player(&players, None).unwrap().name, "spotify");
+        assert_eq!(pick_player(&players, Some("Firefox")).unwrap().name, "firefox.instance_1_42");
+        assert!(pick_player(&players, Some("vlc")).is_none());
+        assert!(pick_player(&[], None).is_none());
+    }
+}
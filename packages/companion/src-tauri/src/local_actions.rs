@@ -1,8 +1,18 @@
 //! # Local Actions Module
 //!
-//! Executes local machine actions (files, shell, apps, clipboard, processes)
-//! with mandatory safety checks before every operation.
-
+//! Executes local machine actions (files, shell, apps, clipboard, processes,
+//! Gateway file transfers, MQTT smart-home commands, user scripts) with
+//! mandatory safety checks before every operation.
+//! Copies, transfers and shell commands report `action-progress` (see `progress`).
+//! Files they create count against the file quota (see `quota`).
+//! `{{secret:NAME}}` placeholders are resolved from the vault just before an
+//! action runs, and masked again in its result (see `secrets`).
+//! In simulation mode, actions that would change the machine return a
+//! fabricated result once their safety checks pass (see `simulation`).
+
+use crate::jobs;
+use crate::pagination;
+use crate::progress::Tracker;
 use crate::safety::{self, RiskLevel, SafetyVerdict};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -14,10 +24,19 @@ pub struct ActionResult {
     pub success: bool,
     pub output: String,
     pub safety: SafetyVerdict,
+    /// Token for `fetch_result_page` when `output` is only the first page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub continuation: Option<String>,
+    /// Full output length in bytes, set when the output is paged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_len: Option<usize>,
+    /// Set when simulation mode fabricated this result (see `simulation`)
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub simulated: bool,
 }
 
 /// Local action request from the LLM
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ActionRequest {
     pub action: String,
     pub path: Option<String>,
@@ -26,15 +45,64 @@ pub struct ActionRequest {
     pub process_name: Option<String>,
     pub app_name: Option<String>,
     pub cwd: Option<String>,
+    /// Gateway file id for `download_file`
+    #[serde(default)]
+    pub file_id: Option<String>,
+    /// Search text for `semantic_search`
+    #[serde(default)]
+    pub query: Option<String>,
+    /// Include the window's OCR text in `get_screen_context`
+    #[serde(default)]
+    pub ocr: bool,
+    /// Topic for `mqtt_publish` (payload in `content`) and `mqtt_subscribe`
+    #[serde(default)]
+    pub topic: Option<String>,
+    /// Publish as a retained message
+    #[serde(default)]
+    pub retain: bool,
+    /// All params as sent, for `script.*` and `clipboard_history_paste`
+    #[serde(default)]
+    pub params: serde_json::Value,
     pub confirmed: bool,
 }
 
+impl ActionRequest {
+    /// Build a request from the raw JSON params of a Gateway-pushed action
+    pub fn from_params(action: &str, params: &serde_json::Value, confirmed: bool) -> Self {
+        let field = |name: &str| params.get(name).and_then(|v| v.as_str()).map(String::from);
+        ActionRequest {
+            action: action.to_string(),
+            path: field("path"),
+            command: field("command"),
+            content: field("content"),
+            process_name: field("process_name"),
+            app_name: field("app_name"),
+            cwd: field("cwd"),
+            file_id: field("file_id"),
+            query: field("query"),
+            ocr: params.get("ocr").and_then(|v| v.as_bool()).unwrap_or(false),
+            topic: field("topic"),
+            retain: params.get("retain").and_then(|v| v.as_bool()).unwrap_or(false),
+            params: params.clone(),
+            confirmed,
+        }
+    }
+}
+
 impl ActionResult {
+    /// True when the action was not run because it needs user confirmation
+    pub fn awaiting_confirmation(&self) -> bool {
+        !self.success && self.safety.allowed && self.safety.requires_confirmation
+    }
+
     fn blocked(verdict: SafetyVerdict) -> Self {
         ActionResult {
             success: false,
             output: verdict.reason.clone(),
             safety: verdict,
+            continuation: None,
+            total_len: None,
+            simulated: false,
         }
     }
 
@@ -43,6 +111,9 @@ impl ActionResult {
             success: true,
             output,
             safety: verdict,
+            continuation: None,
+            total_len: None,
+            simulated: false,
         }
     }
 
@@ -51,6 +122,58 @@ impl ActionResult {
             success: false,
             output: error,
             safety: verdict,
+            continuation: None,
+            total_len: None,
+            simulated: false,
+        }
+    }
+
+    /// Successful result whose output is split into pages if too large
+    /// and the Gateway can fetch the rest
+    fn paged(output: String, verdict: SafetyVerdict) -> Self {
+        use crate::capabilities::{feature, gateway_supports};
+        // Masked before the rest is kept for later pages
+        let output = crate::secrets::redact(&output);
+        // Gateways from before manifests already understand pages
+        if !gateway_supports(feature::RESULT_PAGES, true) {
+            return ActionResult {
+                success: true,
+                output,
+                safety: verdict,
+                continuation: None,
+                total_len: None,
+                simulated: false,
+            };
+        }
+        let page = pagination::paginate(output);
+        let total_len = page.continuation.as_ref().map(|_| page.total_len);
+        ActionResult {
+            success: true,
+            output: page.output,
+            safety: verdict,
+            continuation: page.continuation,
+            total_len,
+            simulated: false,
+        }
+    }
+
+    /// With stored secrets masked (see `secrets`)
+    fn redacted(mut self) -> Self {
+        self.output = crate::secrets::redact(&self.output);
+        self.safety.reason = crate::secrets::redact(&self.safety.reason);
+        self
+    }
+
+    /// What the action would have returned, in simulation mode
+    fn simulated(action: &str, output: String, verdict: SafetyVerdict) -> Self {
+        crate::simulation::record(action, &output);
+        ActionResult {
+            success: true,
+            output,
+            safety: verdict,
+            continuation: None,
+            total_len: None,
+            simulated: true,
         }
     }
 
@@ -62,12 +185,155 @@ impl ActionResult {
                 verdict.reason
             ),
             safety: verdict,
+            continuation: None,
+            total_len: None,
+            simulated: false,
         }
     }
 }
 
-/// Execute a local action with safety checks
+/// Actions handled by [`execute`] (advertised in the capability manifest)
+pub const SUPPORTED_ACTIONS: &[&str] = &[
+    "read_file", "write_file", "delete_file", "list_dir", "create_dir", "file_exists",
+    "file_info", "move_file", "copy_file", "shell", "open_app", "open_url",
+    "list_processes", "kill_process", "system_info", "disk_usage", "upload_file",
+    "download_file", "get_screen_context", "registry_read", "run_shortcut", "run_applescript",
+    "media_control", "media_status", "lock_screen", "inhibit_idle",
+];
+
+/// Sub-actions handled by [`execute_desktop`]
+pub const DESKTOP_ACTIONS: &[&str] = &[
+    "list_windows", "focus_window", "open_app", "send_keys", "key_combo", "type_text",
+    "click", "screenshot", "read_screen", "read_window_text", "get_clipboard", "wait",
+    "list_monitors", "select_region",
+];
+
+/// The verdict of an action that will stop for user confirmation, checked
+/// before it starts. Mirrors the confirmation gates of the actions below.
+pub fn confirmation_needed(request: &ActionRequest) -> Option<SafetyVerdict> {
+    if request.confirmed {
+        return None;
+    }
+    if let Some(verdict) = secrets_verdict(&request.action, request_secrets(request)) {
+        return Some(verdict);
+    }
+    let verdict = match (request.action.as_str(), &request.path, &request.command, &request.process_name) {
+        ("delete_file", Some(path), _, _) => safety::file_operation_verdict("delete", path),
+        ("shell", _, Some(command), _) => safety::shell_command_verdict(command, request.cwd.as_deref()),
+        ("kill_process", _, _, Some(name)) => safety::process_kill_verdict(name),
+        ("download_file", Some(path), _, _) if request.file_id.is_some() => download_verdict(path, safety::file_operation_verdict("write", path)),
+        ("get_screen_context", _, _, _) if request.ocr => screen_text_verdict(),
+        (action, _, _, _) if action.starts_with(crate::scripts::PREFIX) => {
+            let name = &action[crate::scripts::PREFIX.len()..];
+            match crate::scripts::risk(name) {
+                Some(risk) => safety::script_verdict(name, &risk),
+                None => return None,
+            }
+        }
+        ("run_applescript", _, _, _) => match &request.content {
+            Some(script) => safety::applescript_verdict(script),
+            None => return None,
+        },
+        ("clipboard_history_list", _, _, _) => clipboard_history_verdict(),
+        ("mqtt_publish", _, _, _) => match &request.topic {
+            Some(topic) => safety::mqtt_verdict("publish", topic, &crate::mqtt::allowed_topics()),
+            None => return None,
+        },
+        _ => return None,
+    };
+    Some(verdict).filter(|v| v.allowed && v.requires_confirmation)
+}
+
+/// [`confirmation_needed`] for a desktop action's raw `params`
+pub fn desktop_confirmation_needed(params: &serde_json::Value) -> Option<SafetyVerdict> {
+    let (sub_action, input) = desktop_input(params);
+    secrets_verdict(&format!("desktop.{}", sub_action), crate::secrets::referenced_json(params))
+        .or_else(|| Some(safety::desktop_action_verdict(sub_action, input)).filter(|v| v.allowed && v.requires_confirmation))
+}
+
+/// Names of the vault secrets `request` refers to
+fn request_secrets(request: &ActionRequest) -> Vec<String> {
+    let fields = [&request.command, &request.content].into_iter().flatten();
+    let mut names: Vec<String> = fields.flat_map(|f| crate::secrets::referenced(f)).collect();
+    names.extend(crate::secrets::referenced_json(&request.params));
+    names
+}
+
+/// An action that uses vault secrets asks first: once resolved, a secret is
+/// only masked as stored, and the action could send it away encoded. No
+/// grant lifts this.
+fn secrets_verdict(action: &str, mut names: Vec<String>) -> Option<SafetyVerdict> {
+    if names.is_empty() {
+        return None;
+    }
+    names.sort();
+    names.dedup();
+    let verdict = SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::High,
+        reason: format!("{} uses the secret{} {}", action, if names.len() == 1 { "" } else { "s" }, names.join(", ")),
+        requires_confirmation: true,
+    };
+    Some(safety::record("secrets".into(), &format!("{}: {}", action, names.join(", ")), verdict))
+}
+
+/// Execute a local action with safety checks. Blocking; file transfers
+/// need [`execute_async`].
 pub fn execute(request: &ActionRequest) -> ActionResult {
+    let started = std::time::Instant::now();
+    let tracker = Tracker::start(&request.action);
+    let unconfirmed_secrets = if request.confirmed { None } else { secrets_verdict(&request.action, request_secrets(request)) };
+    let result = match (unconfirmed_secrets, with_secrets(request)) {
+        (Some(verdict), _) => ActionResult::needs_confirm(verdict),
+        (None, Ok(request)) => dispatch(&request, &tracker).redacted(),
+        (None, Err(e)) => ActionResult::err(e, safe_verdict()),
+    };
+    tracker.finish(result.success);
+    if !result.awaiting_confirmation() {
+        crate::usage::record_action(&request.action);
+    }
+    crate::metrics::observe_since(crate::metrics::timing::ACTION, &request.action, started);
+    result
+}
+
+/// Execute a local action without blocking the caller: file transfers run
+/// on the async runtime, everything else on a blocking thread
+pub async fn execute_async(request: ActionRequest) -> ActionResult {
+    let started = std::time::Instant::now();
+    let tracker = Tracker::start(&request.action);
+    let result = match request.action.as_str() {
+        "upload_file" => upload_file(&request, &tracker).await,
+        "download_file" => download_file(&request, &tracker).await,
+        _ => {
+            let action = request.action.clone();
+            return tauri::async_runtime::spawn_blocking(move || execute(&request))
+                .await
+                .unwrap_or_else(|e| ActionResult::err(format!("Action {} failed: {}", action, e), safe_verdict()));
+        }
+    };
+    tracker.finish(result.success);
+    crate::usage::record_action(&request.action);
+    crate::metrics::observe_since(crate::metrics::timing::ACTION, &request.action, started);
+    result
+}
+
+/// `request` with its `{{secret:NAME}}` placeholders resolved (see
+/// `secrets`). A shell command and an AppleScript are resolved by their
+/// actions, after recording the safety check with the placeholders.
+fn with_secrets(request: &ActionRequest) -> Result<ActionRequest, String> {
+    let resolve = |field: &Option<String>| field.as_deref().map(crate::secrets::resolve).transpose();
+    let content = match request.action.as_str() {
+        "run_applescript" => request.content.clone(),
+        _ => resolve(&request.content)?,
+    };
+    Ok(ActionRequest {
+        content,
+        params: crate::secrets::resolve_json(&request.params)?,
+        ..request.clone()
+    })
+}
+
+fn dispatch(request: &ActionRequest, tracker: &Tracker) -> ActionResult {
     match request.action.as_str() {
         // ─── File Operations ───
         "read_file" => read_file(request),
@@ -78,10 +344,10 @@ pub fn execute(request: &ActionRequest) -> ActionResult {
         "file_exists" => file_exists(request),
         "file_info" => file_info(request),
         "move_file" => move_file(request),
-        "copy_file" => copy_file(request),
+        "copy_file" => copy_file(request, tracker),
 
         // ─── Shell Commands ───
-        "shell" => run_shell(request),
+        "shell" => run_shell(request, tracker),
 
         // ─── Application Control ───
         "open_app" => open_app(request),
@@ -92,6 +358,34 @@ pub fn execute(request: &ActionRequest) -> ActionResult {
         // ─── System Info ───
         "system_info" => system_info(),
         "disk_usage" => disk_usage(),
+        "get_screen_context" => get_screen_context(request),
+        "registry_read" => registry_read(request),
+        "run_shortcut" => run_shortcut(request, tracker),
+        "run_applescript" => run_applescript(request, tracker),
+        "media_control" => media_control(request),
+        "media_status" => media_status(),
+        "lock_screen" => lock_screen(request),
+        "inhibit_idle" => inhibit_idle(request),
+
+        // ─── Documents ───
+        "semantic_search" => semantic_search(request),
+
+        // ─── Clipboard History ───
+        "clipboard_history_list" => clipboard_history_list(request),
+        "clipboard_history_paste" => clipboard_history_paste(request),
+
+        // ─── Smart Home ───
+        "mqtt_publish" => mqtt_publish(request),
+        "mqtt_subscribe" => mqtt_subscribe(request),
+
+        // ─── User Scripts ───
+        action if action.starts_with(crate::scripts::PREFIX) => run_script(request),
+
+        // ─── File Transfer ───
+        "upload_file" | "download_file" => ActionResult::err(
+            format!("{} is a network transfer and only runs through execute_async", request.action),
+            safe_verdict(),
+        ),
 
         _ => ActionResult {
             success: false,
@@ -102,6 +396,9 @@ pub fn execute(request: &ActionRequest) -> ActionResult {
                 reason: "Unknown action".into(),
                 requires_confirmation: false,
             },
+            continuation: None,
+            total_len: None,
+            simulated: false,
         },
     }
 }
@@ -119,15 +416,8 @@ fn read_file(req: &ActionRequest) -> ActionResult {
     }
 
     match std::fs::read_to_string(path) {
-        Ok(content) => {
-            // Limit output to 50KB to avoid overwhelming the LLM
-            let truncated = if content.len() > 50_000 {
-                format!("{}...\n\n[Truncated: {} bytes total]", &content[..50_000], content.len())
-            } else {
-                content
-            };
-            ActionResult::ok(truncated, verdict)
-        }
+        // Large files are paged to stay within IPC/Gateway payload limits
+        Ok(content) => ActionResult::paged(content, verdict),
         Err(e) => ActionResult::err(format!("Failed to read: {}", e), verdict),
     }
 }
@@ -145,6 +435,14 @@ fn write_file(req: &ActionRequest) -> ActionResult {
     if !verdict.allowed {
         return ActionResult::blocked(verdict);
     }
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, format!("Written {} bytes to {}", content.len(), path), verdict);
+    }
+
+    if let Err(e) = crate::quota::check_write(path, content.len() as u64) {
+        return ActionResult::err(e, verdict);
+    }
+    let existed = Path::new(path).exists();
 
     // Create parent directories if needed
     if let Some(parent) = Path::new(path).parent() {
@@ -152,7 +450,10 @@ fn write_file(req: &ActionRequest) -> ActionResult {
     }
 
     match std::fs::write(path, content) {
-        Ok(()) => ActionResult::ok(format!("Written {} bytes to {}", content.len(), path), verdict),
+        Ok(()) => {
+            crate::quota::record(&req.action, path, existed);
+            ActionResult::ok(format!("Written {} bytes to {}", content.len(), path), verdict)
+        }
         Err(e) => ActionResult::err(format!("Failed to write: {}", e), verdict),
     }
 }
@@ -169,6 +470,9 @@ fn delete_file(req: &ActionRequest) -> ActionResult {
     if verdict.requires_confirmation && !req.confirmed {
         return ActionResult::needs_confirm(verdict);
     }
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, format!("Deleted: {}", path), verdict);
+    }
 
     let p = Path::new(path);
     let result = if p.is_dir() {
@@ -208,7 +512,7 @@ fn list_dir(req: &ActionRequest) -> ActionResult {
             if items.is_empty() {
                 ActionResult::ok("(empty directory)".into(), verdict)
             } else {
-                ActionResult::ok(items.join("\n"), verdict)
+                ActionResult::paged(items.join("\n"), verdict)
             }
         }
         Err(e) => ActionResult::err(format!("Failed to list: {}", e), verdict),
@@ -224,6 +528,9 @@ fn create_dir(req: &ActionRequest) -> ActionResult {
     if !verdict.allowed {
         return ActionResult::blocked(verdict);
     }
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, format!("Created directory: {}", path), verdict);
+    }
 
     match std::fs::create_dir_all(path) {
         Ok(()) => ActionResult::ok(format!("Created directory: {}", path), verdict),
@@ -281,6 +588,9 @@ fn move_file(req: &ActionRequest) -> ActionResult {
     if !verdict_to.allowed {
         return ActionResult::blocked(verdict_to);
     }
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, format!("Moved {} → {}", from, to), verdict_from);
+    }
 
     match std::fs::rename(&from, &to) {
         Ok(()) => ActionResult::ok(format!("Moved {} → {}", from, to), verdict_from),
@@ -288,7 +598,28 @@ fn move_file(req: &ActionRequest) -> ActionResult {
     }
 }
 
-fn copy_file(req: &ActionRequest) -> ActionResult {
+/// `std::fs::copy` in chunks, reporting progress
+fn copy_with_progress(from: &str, to: &str, tracker: &Tracker) -> std::io::Result<u64> {
+    use std::io::{Read, Write};
+    let mut src = std::fs::File::open(from)?;
+    let total = src.metadata()?.len();
+    let mut dst = std::fs::File::create(to)?;
+    let mut buf = vec![0u8; 1024 * 1024];
+    let mut copied = 0u64;
+    loop {
+        let n = src.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        dst.write_all(&buf[..n])?;
+        copied += n as u64;
+        tracker.bytes("Copying", copied, total);
+    }
+    dst.set_permissions(src.metadata()?.permissions())?;
+    Ok(copied)
+}
+
+fn copy_file(req: &ActionRequest, tracker: &Tracker) -> ActionResult {
     let from = match &req.path {
         Some(p) => p.clone(),
         None => return ActionResult::err("path (source) is required".into(), safe_verdict()),
@@ -301,57 +632,79 @@ fn copy_file(req: &ActionRequest) -> ActionResult {
     if !verdict.allowed {
         return ActionResult::blocked(verdict);
     }
+    if crate::simulation::enabled() {
+        let bytes = std::fs::metadata(&from).map(|m| m.len()).unwrap_or(0);
+        return ActionResult::simulated(&req.action, format!("Copied {} → {} ({} bytes)", from, to, bytes), verdict);
+    }
 
-    match std::fs::copy(&from, &to) {
-        Ok(bytes) => ActionResult::ok(
-            format!("Copied {} → {} ({} bytes)", from, to, bytes),
-            verdict,
-        ),
+    let size = std::fs::metadata(&from).map(|m| m.len()).unwrap_or(0);
+    if let Err(e) = crate::quota::check_write(&to, size) {
+        return ActionResult::err(e, verdict);
+    }
+    let existed = Path::new(&to).exists();
+
+    match copy_with_progress(&from, &to, tracker) {
+        Ok(bytes) => {
+            crate::quota::record(&req.action, &to, existed);
+            ActionResult::ok(format!("Copied {} → {} ({} bytes)", from, to, bytes), verdict)
+        }
         Err(e) => ActionResult::err(format!("Failed to copy: {}", e), verdict),
     }
 }
 
 // ─── Shell Commands ──────────────────────────────────
 
-fn run_shell(req: &ActionRequest) -> ActionResult {
-    let command = match &req.command {
+fn run_shell(req: &ActionRequest, tracker: &Tracker) -> ActionResult {
+    let shown = match &req.command {
         Some(c) => c,
         None => return ActionResult::err("command is required".into(), safe_verdict()),
     };
-    let verdict = safety::check_shell_command(command);
+    // Running somewhere else than asked would also escape a grant on `cwd`
+    if let Some(cwd) = req.cwd.as_deref().filter(|c| !std::path::Path::new(c).is_dir()) {
+        return ActionResult::err(format!("Working directory not found: {}", cwd), safe_verdict());
+    }
+    let command = &match crate::secrets::resolve(shown) {
+        Ok(command) => command,
+        Err(e) => return ActionResult::err(e, safe_verdict()),
+    };
+    // The command that runs is checked; the history keeps its placeholders
+    let verdict = safety::record("shell".into(), shown, safety::shell_command_verdict(command, req.cwd.as_deref()));
     if !verdict.allowed {
         return ActionResult::blocked(verdict);
     }
     if verdict.requires_confirmation && !req.confirmed {
         return ActionResult::needs_confirm(verdict);
     }
+    if crate::simulation::enabled() {
+        // A command that succeeds quietly, as most do
+        return ActionResult::simulated(&req.action, String::new(), verdict);
+    }
 
     let mut cmd = Command::new("powershell.exe");
     cmd.args(["-NoProfile", "-NonInteractive", "-Command", command]);
+    cmd.envs(crate::exec_context::vars());
     if let Some(cwd) = &req.cwd {
-        let cwd_path = std::path::Path::new(cwd);
-        if cwd_path.exists() {
-            cmd.current_dir(cwd_path);
-        }
+        cmd.current_dir(cwd);
     }
-    let output = cmd.output();
+    // Run as a monitored job so a runaway process tree can be killed
+    let heartbeat = tracker.heartbeat("Running command");
+    let output = jobs::run_monitored(cmd, shown);
+    drop(heartbeat);
 
     match output {
-        Ok(out) => {
+        Ok((out, job)) => {
             let stdout = String::from_utf8_lossy(&out.stdout).to_string();
             let stderr = String::from_utf8_lossy(&out.stderr).to_string();
-            let combined = if stderr.is_empty() {
+            let mut combined = if stderr.is_empty() {
                 stdout
             } else {
                 format!("{}\n[STDERR]\n{}", stdout, stderr)
             };
-            // Limit output
-            let truncated = if combined.len() > 30_000 {
-                format!("{}...\n[Truncated: {} chars]", &combined[..30_000], combined.len())
-            } else {
-                combined
-            };
-            ActionResult::ok(truncated, verdict)
+            if let Some(reason) = &job.kill_reason {
+                combined.push_str(&format!("\n[KILLED: job {} — {}]", job.id, reason));
+                return ActionResult::err(combined, verdict);
+            }
+            ActionResult::paged(combined, verdict)
         }
         Err(e) => ActionResult::err(format!("Failed to execute: {}", e), verdict),
     }
@@ -371,6 +724,9 @@ fn open_app(req: &ActionRequest) -> ActionResult {
         reason: format!("Opening application: {}", app),
         requires_confirmation: false,
     };
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, format!("Launched: {}", app), verdict);
+    }
 
     let result = Command::new("cmd")
         .args(["/C", "start", "", app])
@@ -387,21 +743,22 @@ fn open_url(req: &ActionRequest) -> ActionResult {
         Some(u) => u,
         None => return ActionResult::err("path (URL) is required".into(), safe_verdict()),
     };
+    let verdict = SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::Low,
+        reason: "Opening URL in default browser".into(),
+        requires_confirmation: false,
+    };
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, format!("Opened URL: {}", url), verdict);
+    }
 
     let result = Command::new("cmd")
         .args(["/C", "start", "", url])
         .spawn();
 
     match result {
-        Ok(_) => ActionResult::ok(
-            format!("Opened URL: {}", url),
-            SafetyVerdict {
-                allowed: true,
-                risk: RiskLevel::Low,
-                reason: "Opening URL in default browser".into(),
-                requires_confirmation: false,
-            },
-        ),
+        Ok(_) => ActionResult::ok(format!("Opened URL: {}", url), verdict),
         Err(e) => ActionResult::err(format!("Failed: {}", e), safe_verdict()),
     }
 }
@@ -433,9 +790,17 @@ fn kill_process(req: &ActionRequest) -> ActionResult {
     if !verdict.allowed {
         return ActionResult::blocked(verdict);
     }
-    if !req.confirmed {
+    if verdict.requires_confirmation && !req.confirmed {
         return ActionResult::needs_confirm(verdict);
     }
+    if crate::simulation::enabled() {
+        let output = format!(
+            "SUCCESS: The process \"{}\" with PID {} has been terminated.",
+            name,
+            crate::simulation::fake_id()
+        );
+        return ActionResult::simulated(&req.action, output, verdict);
+    }
 
     let result = Command::new("taskkill")
         .args(["/IM", name, "/F"])
@@ -480,11 +845,459 @@ fn disk_usage() -> ActionResult {
     }
 }
 
+/// Reading what is on screen always needs the user's approval
+fn screen_text_verdict() -> SafetyVerdict {
+    SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::Medium,
+        reason: "The Gateway wants to read the text on your screen".into(),
+        requires_confirmation: true,
+    }
+}
+
+/// The focused app and window title, plus its OCR text when asked and confirmed
+fn get_screen_context(req: &ActionRequest) -> ActionResult {
+    if req.ocr && !req.confirmed {
+        return ActionResult::needs_confirm(screen_text_verdict());
+    }
+    let verdict = if req.ocr { screen_text_verdict() } else { safe_verdict() };
+    match crate::screen_context::capture(req.ocr) {
+        Ok(context) => ActionResult::ok(serde_json::to_string_pretty(&context).unwrap_or_default(), verdict),
+        Err(e) => ActionResult::err(e, verdict),
+    }
+}
+
+/// Read a registry key, or one of its values (`value`). Read-only and
+/// limited to the hives `safety` allows, unlike `reg` through `shell`.
+fn registry_read(req: &ActionRequest) -> ActionResult {
+    let field = |name: &str| req.params.get(name).and_then(|v| v.as_str()).map(str::to_string);
+    let Some(key) = field("key").or_else(|| req.path.clone()) else {
+        return ActionResult::err("key is required".into(), safe_verdict());
+    };
+    let verdict = safety::check_registry_read(&key);
+    if !verdict.allowed {
+        return ActionResult::blocked(verdict);
+    }
+    if !cfg!(target_os = "windows") {
+        return ActionResult::err("The registry only exists on Windows".into(), verdict);
+    }
+
+    let mut cmd = Command::new("reg");
+    cmd.args(["query", key.trim().trim_end_matches('\\')]);
+    match field("value") {
+        Some(value) if !value.is_empty() => cmd.args(["/v", &value]),
+        _ => &mut cmd,
+    };
+    match cmd.output() {
+        Ok(out) if out.status.success() => ActionResult::paged(String::from_utf8_lossy(&out.stdout).trim().to_string(), verdict),
+        Ok(out) => ActionResult::err(
+            format!("Registry read failed: {}", String::from_utf8_lossy(&out.stderr).trim()),
+            verdict,
+        ),
+        Err(e) => ActionResult::err(format!("Registry read failed: {}", e), verdict),
+    }
+}
+
+// ─── macOS Automation ────────────────────────────────
+
+/// Result of a monitored macOS automation job; `returned` replaces its
+/// stdout when the tool wrote its result elsewhere
+fn automation_result(
+    output: std::io::Result<(std::process::Output, jobs::JobInfo)>,
+    returned: Option<String>,
+    what: &str,
+    verdict: SafetyVerdict,
+) -> ActionResult {
+    match output {
+        Ok((_, jobs::JobInfo { id, kill_reason: Some(reason), .. })) => {
+            ActionResult::err(format!("{} killed: job {} — {}", what, id, reason), verdict)
+        }
+        Ok((out, _)) if out.status.success() => {
+            let stdout = returned.unwrap_or_else(|| String::from_utf8_lossy(&out.stdout).to_string());
+            ActionResult::paged(stdout.trim().to_string(), verdict)
+        }
+        Ok((out, _)) => ActionResult::err(format!("{} failed: {}", what, String::from_utf8_lossy(&out.stderr).trim()), verdict),
+        Err(e) => ActionResult::err(format!("{} failed: {}", what, e), verdict),
+    }
+}
+
+/// Run one of the user's Shortcuts (`name`, or `app_name`) with the
+/// `shortcuts` tool, passing `content` as its input when given
+fn run_shortcut(req: &ActionRequest, tracker: &Tracker) -> ActionResult {
+    let name = req.params.get("name").and_then(|v| v.as_str()).map(str::to_string).or_else(|| req.app_name.clone());
+    let Some(name) = name else {
+        return ActionResult::err("name is required".into(), safe_verdict());
+    };
+    let verdict = safety::check_shortcut(&name);
+    if !verdict.allowed {
+        return ActionResult::blocked(verdict);
+    }
+    if !cfg!(target_os = "macos") {
+        return ActionResult::err("Shortcuts only run on macOS".into(), verdict);
+    }
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, String::new(), verdict);
+    }
+
+    let dir = std::env::temp_dir();
+    let stamp = format!("{}-{}", std::process::id(), chrono::Utc::now().timestamp_millis());
+    let output_path = dir.join(format!("forgeai-shortcut-{}.out", stamp));
+    let mut cmd = Command::new("shortcuts");
+    cmd.args(["run", name.trim(), "--output-path"]).arg(&output_path);
+    let input_path = req.content.as_ref().map(|input| {
+        let path = dir.join(format!("forgeai-shortcut-{}.in", stamp));
+        let _ = std::fs::write(&path, input);
+        path
+    });
+    if let Some(path) = &input_path {
+        cmd.arg("--input-path").arg(path);
+    }
+    let heartbeat = tracker.heartbeat("Running Shortcut");
+    let output = jobs::run_monitored(cmd, &format!("shortcuts run {}", name.trim()));
+    drop(heartbeat);
+
+    // What the Shortcut returned, when it returns anything
+    let returned = std::fs::read_to_string(&output_path).ok();
+    for path in std::iter::once(output_path).chain(input_path) {
+        let _ = std::fs::remove_file(path);
+    }
+    automation_result(output, returned, "Shortcut", verdict)
+}
+
+/// Run an AppleScript (`content`) with `osascript`. Always high-risk.
+fn run_applescript(req: &ActionRequest, tracker: &Tracker) -> ActionResult {
+    let Some(shown) = &req.content else {
+        return ActionResult::err("content (the script) is required".into(), safe_verdict());
+    };
+    let script = &match crate::secrets::resolve(shown) {
+        Ok(script) => script,
+        Err(e) => return ActionResult::err(e, safe_verdict()),
+    };
+    let verdict = safety::record("applescript".into(), shown, safety::applescript_verdict(script));
+    if !verdict.allowed {
+        return ActionResult::blocked(verdict);
+    }
+    if verdict.requires_confirmation && !req.confirmed {
+        return ActionResult::needs_confirm(verdict);
+    }
+    if !cfg!(target_os = "macos") {
+        return ActionResult::err("AppleScript only runs on macOS".into(), verdict);
+    }
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, String::new(), verdict);
+    }
+
+    let mut cmd = Command::new("osascript");
+    cmd.args(["-e", script]);
+    let heartbeat = tracker.heartbeat("Running AppleScript");
+    let output = jobs::run_monitored(cmd, "osascript");
+    drop(heartbeat);
+    automation_result(output, None, "AppleScript", verdict)
+}
+
+// ─── Linux Desktop ───────────────────────────────────
+
+/// Play, pause, skip… on a media player (`command`; `player` picks one by name)
+fn media_control(req: &ActionRequest) -> ActionResult {
+    let Some(command) = &req.command else {
+        return ActionResult::err("command is required (play, pause, play_pause, next, previous, stop)".into(), safe_verdict());
+    };
+    let player = req.params.get("player").and_then(|v| v.as_str());
+    let verdict = low_verdict(format!("Media control: {}", command));
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, format!("Sent {} to {}", command, player.unwrap_or("the media player")), verdict);
+    }
+    match crate::linux_desktop::media_control(command, player) {
+        Ok(name) => ActionResult::ok(format!("Sent {} to {}", command, name), verdict),
+        Err(e) => ActionResult::err(e, verdict),
+    }
+}
+
+/// Media players and what they are playing
+fn media_status() -> ActionResult {
+    match crate::linux_desktop::media_players() {
+        Ok(players) if players.is_empty() => ActionResult::ok("No media player is running".into(), safe_verdict()),
+        Ok(players) => ActionResult::ok(serde_json::to_string_pretty(&players).unwrap_or_default(), safe_verdict()),
+        Err(e) => ActionResult::err(e, safe_verdict()),
+    }
+}
+
+fn lock_screen(req: &ActionRequest) -> ActionResult {
+    let verdict = low_verdict("Locking the screen".into());
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, "Screen locked".into(), verdict);
+    }
+    match crate::linux_desktop::lock_screen() {
+        Ok(()) => ActionResult::ok("Screen locked".into(), verdict),
+        Err(e) => ActionResult::err(e, verdict),
+    }
+}
+
+/// Keep the screen awake for `minutes` (0 lets it sleep again); `content`
+/// says why, for the desktop's inhibitor list
+fn inhibit_idle(req: &ActionRequest) -> ActionResult {
+    let Some(minutes) = req.params.get("minutes").and_then(|v| v.as_u64()) else {
+        return ActionResult::err("minutes is required (0 releases the inhibitor)".into(), safe_verdict());
+    };
+    let verdict = low_verdict(format!("Keeping the screen awake for {} minutes", minutes));
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, format!("Screen kept awake for {} minutes", minutes), verdict);
+    }
+    match crate::linux_desktop::inhibit_idle(minutes, req.content.as_deref().unwrap_or_default()) {
+        Ok(message) => ActionResult::ok(message, verdict),
+        Err(e) => ActionResult::err(e, verdict),
+    }
+}
+
+// ─── Documents ───────────────────────────────────────
+
+/// Passages from the user's indexed documents closest to `query` (see `embeddings`)
+fn semantic_search(req: &ActionRequest) -> ActionResult {
+    let query = match req.query.as_deref().or(req.content.as_deref()) {
+        Some(q) if !q.trim().is_empty() => q,
+        _ => return ActionResult::err("query is required".into(), safe_verdict()),
+    };
+    match crate::embeddings::search(query, 5) {
+        Ok(hits) => ActionResult::paged(serde_json::to_string_pretty(&hits).unwrap_or_default(), safe_verdict()),
+        Err(e) => ActionResult::err(e, safe_verdict()),
+    }
+}
+
+// ─── Clipboard History ───────────────────────────────
+
+fn clipboard_history_verdict() -> SafetyVerdict {
+    SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::Medium,
+        reason: "The Gateway wants to read your clipboard history".into(),
+        requires_confirmation: true,
+    }
+}
+
+fn clipboard_history_list(req: &ActionRequest) -> ActionResult {
+    if !crate::clipboard_history::enabled() {
+        return ActionResult::err("Clipboard history is turned off".into(), safe_verdict());
+    }
+    if !req.confirmed {
+        return ActionResult::needs_confirm(clipboard_history_verdict());
+    }
+    let entries = crate::clipboard_history::list();
+    ActionResult::paged(serde_json::to_string_pretty(&entries).unwrap_or_default(), clipboard_history_verdict())
+}
+
+/// Put a history entry back on the clipboard and paste it where the user is typing
+fn clipboard_history_paste(req: &ActionRequest) -> ActionResult {
+    if !crate::clipboard_history::enabled() {
+        return ActionResult::err("Clipboard history is turned off".into(), safe_verdict());
+    }
+    let Some(index) = req.params.get("index").and_then(|v| v.as_u64()) else {
+        return ActionResult::err("index is required".into(), safe_verdict());
+    };
+    let text = match crate::clipboard_history::entry(index as usize) {
+        Ok(text) => text,
+        Err(e) => return ActionResult::err(e, safe_verdict()),
+    };
+    // Pasted text may land in a terminal, like typed text
+    let verdict = safety::check_desktop_action("type_text", &text);
+    if !verdict.allowed {
+        return ActionResult::blocked(verdict);
+    }
+    if crate::simulation::enabled() {
+        let output = format!("Pasted entry {} ({} characters)", index, text.chars().count());
+        return ActionResult::simulated(&req.action, output, verdict);
+    }
+    if let Err(e) = crate::clipboard_history::copy(&text) {
+        return ActionResult::err(e, verdict);
+    }
+    if !cfg!(target_os = "windows") {
+        return ActionResult::ok(format!("Entry {} is on the clipboard, ready to paste", index), verdict);
+    }
+    let pasted = desktop_send_keys("^v");
+    if !pasted.success {
+        return ActionResult::err(format!("Entry {} is on the clipboard but pasting failed: {}", index, pasted.output), verdict);
+    }
+    ActionResult::ok(format!("Pasted entry {} ({} characters)", index, text.chars().count()), verdict)
+}
+
+// ─── Smart Home ──────────────────────────────────────
+
+fn mqtt_publish(req: &ActionRequest) -> ActionResult {
+    let Some(topic) = req.topic.as_deref() else {
+        return ActionResult::err("topic is required".into(), safe_verdict());
+    };
+    let verdict = safety::check_mqtt("publish", topic, &crate::mqtt::allowed_topics());
+    if !verdict.allowed {
+        return ActionResult::blocked(verdict);
+    }
+    if verdict.requires_confirmation && !req.confirmed {
+        return ActionResult::needs_confirm(verdict);
+    }
+    let payload = req.content.as_deref().unwrap_or("");
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, format!("Published '{}' to {}", payload, topic), verdict);
+    }
+    match crate::mqtt::publish(topic, payload, req.retain) {
+        Ok(()) => ActionResult::ok(format!("Published '{}' to {}", payload, topic), verdict),
+        Err(e) => ActionResult::err(format!("MQTT publish failed: {}", e), verdict),
+    }
+}
+
+fn mqtt_subscribe(req: &ActionRequest) -> ActionResult {
+    let Some(topic) = req.topic.as_deref() else {
+        return ActionResult::err("topic is required".into(), safe_verdict());
+    };
+    let verdict = safety::check_mqtt("subscribe", topic, &crate::mqtt::allowed_topics());
+    if !verdict.allowed {
+        return ActionResult::blocked(verdict);
+    }
+    // Seconds to wait for a message, in `content`
+    let wait = req.content.as_deref().and_then(|c| c.trim().parse().ok()).unwrap_or(5);
+    match crate::mqtt::subscribe(topic, std::time::Duration::from_secs(wait)) {
+        Ok(messages) if messages.is_empty() => ActionResult::ok(format!("No message on {} within {}s", topic, wait), verdict),
+        Ok(messages) => ActionResult::paged(serde_json::to_string_pretty(&messages).unwrap_or_default(), verdict),
+        Err(e) => ActionResult::err(format!("MQTT subscribe failed: {}", e), verdict),
+    }
+}
+
+// ─── User Scripts ────────────────────────────────────
+
+fn run_script(req: &ActionRequest) -> ActionResult {
+    let name = &req.action[crate::scripts::PREFIX.len()..];
+    let Some(risk) = crate::scripts::risk(name) else {
+        return ActionResult::err(format!("No script named '{}'", name), safe_verdict());
+    };
+    let verdict = safety::check_script(name, &risk);
+    if !verdict.allowed {
+        return ActionResult::blocked(verdict);
+    }
+    if verdict.requires_confirmation && !req.confirmed {
+        return ActionResult::needs_confirm(verdict);
+    }
+    if crate::simulation::enabled() {
+        return ActionResult::simulated(&req.action, String::new(), verdict);
+    }
+    match crate::scripts::run(name, &req.params, req.confirmed) {
+        Ok(output) => ActionResult::paged(output, verdict),
+        Err(e) => ActionResult::err(format!("Script '{}' failed: {}", name, e), verdict),
+    }
+}
+
+// ─── File Transfer ───────────────────────────────────
+
+async fn upload_file(req: &ActionRequest, tracker: &Tracker) -> ActionResult {
+    let path = match &req.path {
+        Some(p) => p,
+        None => return ActionResult::err("path is required".into(), safe_verdict()),
+    };
+    let verdict = safety::check_file_operation("read", path);
+    if !verdict.allowed {
+        return ActionResult::blocked(verdict);
+    }
+    let Some(creds) = crate::connection::GatewayConnection::load_credentials() else {
+        return ActionResult::err("Not connected — pair first".into(), verdict);
+    };
+
+    // The Gateway confirms with its user before accepting the file
+    match crate::transfer::upload(&creds, path, Some(tracker)).await {
+        Ok(done) => ActionResult::ok(
+            format!("Uploaded {} ({}) to the Gateway as {} (id {})", path, format_size(done.size), done.path, done.file_id),
+            verdict,
+        ),
+        Err(e) => ActionResult::err(format!("Upload failed: {}", e), verdict),
+    }
+}
+
+/// Receiving a file needs the user's approval, unless a files grant covers
+/// `path`
+fn download_verdict(path: &str, verdict: SafetyVerdict) -> SafetyVerdict {
+    crate::grants::apply(
+        crate::grants::GrantKind::Files,
+        Some(path),
+        SafetyVerdict {
+            reason: format!("The Gateway wants to save a file to '{}'", path),
+            requires_confirmation: true,
+            ..verdict
+        },
+    )
+}
+
+async fn download_file(req: &ActionRequest, tracker: &Tracker) -> ActionResult {
+    let (file_id, path) = match (&req.file_id, &req.path) {
+        (Some(id), Some(p)) => (id, p),
+        _ => return ActionResult::err("file_id and path are required".into(), safe_verdict()),
+    };
+    let verdict = safety::check_file_operation("write", path);
+    if !verdict.allowed {
+        return ActionResult::blocked(verdict);
+    }
+    let verdict = download_verdict(path, verdict);
+    if verdict.requires_confirmation && !req.confirmed {
+        return ActionResult::needs_confirm(verdict);
+    }
+    if crate::simulation::enabled() {
+        let output = format!("Downloaded {} to {}", format_size(128 * 1024), path);
+        return ActionResult::simulated(&req.action, output, verdict);
+    }
+    let Some(creds) = crate::connection::GatewayConnection::load_credentials() else {
+        return ActionResult::err("Not connected — pair first".into(), verdict);
+    };
+    // The size is only known once it arrives
+    if let Err(e) = crate::quota::check_write(path, 0) {
+        return ActionResult::err(e, verdict);
+    }
+    let existed = Path::new(path).exists();
+
+    match crate::transfer::download(&creds, file_id, path, Some(tracker)).await {
+        Ok(done) => {
+            crate::quota::record(&req.action, &done.path, existed);
+            let resumed = if done.resumed_from > 0 {
+                format!(", resumed at {}", format_size(done.resumed_from))
+            } else {
+                String::new()
+            };
+            ActionResult::ok(format!("Downloaded {} to {}{}", format_size(done.size), done.path, resumed), verdict)
+        }
+        Err(e) => ActionResult::err(format!("Download failed: {}", e), verdict),
+    }
+}
+
 // ─── Desktop Automation (Windows PowerShell) ─────────
 
+/// Text a desktop sub-action would type or launch, for the safety check
+pub fn desktop_input(params: &serde_json::Value) -> (&str, &str) {
+    let action = params.get("action").and_then(|v| v.as_str()).unwrap_or("");
+    let field = if action == "open_app" { "target" } else { "text" };
+    (action, params.get(field).and_then(|v| v.as_str()).unwrap_or(""))
+}
+
+/// [`execute_desktop`] on a blocking thread
+pub async fn execute_desktop_async(params: serde_json::Value, confirmed: bool) -> ActionResult {
+    let action = format!("desktop.{}", params.get("action").and_then(|v| v.as_str()).unwrap_or(""));
+    let result = tauri::async_runtime::spawn_blocking(move || execute_desktop(&params, confirmed))
+        .await
+        .unwrap_or_else(|e| ActionResult::err(format!("Desktop action failed: {}", e), safe_verdict()));
+    if !result.awaiting_confirmation() {
+        crate::usage::record_action(&action);
+    }
+    result
+}
+
 /// Execute a desktop automation action with raw JSON params.
-/// Called directly from the WS loop for action="desktop".
-pub fn execute_desktop(params: &serde_json::Value) -> ActionResult {
+/// Called for Gateway-pushed actions with action="desktop".
+pub fn execute_desktop(params: &serde_json::Value, confirmed: bool) -> ActionResult {
+    let action = format!("desktop.{}", params.get("action").and_then(|v| v.as_str()).unwrap_or(""));
+    if !confirmed {
+        if let Some(verdict) = secrets_verdict(&action, crate::secrets::referenced_json(params)) {
+            return ActionResult::needs_confirm(verdict);
+        }
+    }
+    match crate::secrets::resolve_json(params) {
+        Ok(params) => desktop(&params, confirmed).redacted(),
+        Err(e) => ActionResult::err(e, safe_verdict()),
+    }
+}
+
+fn desktop(params: &serde_json::Value, confirmed: bool) -> ActionResult {
     let action = params.get("action").and_then(|v| v.as_str()).unwrap_or("");
     let target = params.get("target").and_then(|v| v.as_str()).unwrap_or("");
     let text = params.get("text").and_then(|v| v.as_str()).unwrap_or("");
@@ -496,20 +1309,60 @@ pub fn execute_desktop(params: &serde_json::Value) -> ActionResult {
     if action.is_empty() {
         return ActionResult::err("desktop action is required".into(), safe_verdict());
     }
+    let (_, input) = desktop_input(params);
+    let verdict = safety::check_desktop_action(action, input);
+    if !verdict.allowed {
+        return ActionResult::blocked(verdict);
+    }
+    if verdict.requires_confirmation && !confirmed {
+        return ActionResult::needs_confirm(verdict);
+    }
+    if crate::simulation::enabled() {
+        let output = match action {
+            "focus_window" => Some(format!("FOCUSED: {}", target)),
+            "open_app" => Some(format!("OPENED: {}", target)),
+            "send_keys" | "key_combo" => Some(format!("SENT_KEYS: {}", text.chars().take(50).collect::<String>())),
+            "type_text" => Some(format!("TYPED: {}", text.chars().take(60).collect::<String>())),
+            "click" => Some(format!("CLICKED: ({}, {}) {}", x, y, button)),
+            _ => None,
+        };
+        if let Some(output) = output {
+            return ActionResult::simulated(&format!("desktop.{}", action), output, verdict);
+        }
+    }
 
     // Optional delay before action
     if delay > 0 && action != "wait" {
         std::thread::sleep(std::time::Duration::from_millis(delay.min(10_000)));
     }
 
-    match action {
+    // Input lands in whatever has focus: make sure it is the expected window
+    let expected = matches!(action, "type_text" | "send_keys" | "key_combo" | "click")
+        .then(|| crate::focus_guard::expectation(params))
+        .flatten();
+    if let Some(expected) = &expected {
+        if let Err(e) = crate::focus_guard::check(expected, "before the input") {
+            return ActionResult::err(e, verdict.clone());
+        }
+    }
+
+    let result = match action {
         "list_windows" => desktop_list_windows(),
         "focus_window" => desktop_focus_window(target),
         "open_app" => desktop_open_app(target),
         "send_keys" | "key_combo" => desktop_send_keys(text),
         "type_text" => desktop_type_text(text),
         "click" => desktop_click(x, y, button),
-        "screenshot" => desktop_screenshot(target),
+        "screenshot" => match screenshot_capture(params) {
+            Ok(capture) => desktop_screenshot(target, &capture),
+            Err(e) => ActionResult::err(e, safe_verdict()),
+        },
+        "list_monitors" => desktop_list_monitors(),
+        "select_region" => match select_screen_region() {
+            Ok(Some(region)) => ActionResult::ok(serde_json::to_string(&region).unwrap_or_default(), safe_verdict()),
+            Ok(None) => ActionResult::ok("CANCELLED: No region was selected".into(), safe_verdict()),
+            Err(e) => ActionResult::err(e, safe_verdict()),
+        },
         "read_screen" => desktop_read_screen(target),
         "read_window_text" => desktop_read_window_text(target),
         "get_clipboard" => desktop_get_clipboard(),
@@ -521,6 +1374,20 @@ pub fn execute_desktop(params: &serde_json::Value) -> ActionResult {
             ActionResult::ok(format!("WAITED: {}ms", ms), safe_verdict())
         }
         _ => ActionResult::err(format!("Unknown desktop action: {}", action), safe_verdict()),
+    };
+
+    if action == "focus_window" && result.output.contains("FOCUSED:") {
+        crate::focus_guard::remember_focus(target);
+    }
+    // Keys may close or open windows on purpose; text and clicks should not
+    match expected {
+        Some(expected) if result.success && matches!(action, "type_text" | "click") => {
+            match crate::focus_guard::check(&expected, "after the input") {
+                Ok(()) => result,
+                Err(e) => ActionResult::err(format!("{}\n{}", result.output.trim(), e), verdict),
+            }
+        }
+        _ => result,
     }
 }
 
@@ -540,12 +1407,7 @@ fn run_powershell(script: &str) -> ActionResult {
             } else {
                 format!("{}\n[STDERR] {}", stdout, stderr)
             };
-            let truncated = if combined.len() > 30_000 {
-                format!("{}...[truncated]", &combined[..30_000])
-            } else {
-                combined
-            };
-            ActionResult::ok(truncated, safe_verdict())
+            ActionResult::paged(combined, safe_verdict())
         }
         Err(e) => ActionResult::err(format!("PowerShell failed: {}", e), safe_verdict()),
     }
@@ -678,28 +1540,172 @@ Write-Output "CLICKED: ({x}, {y}) {button}"
     run_powershell(&script)
 }
 
-fn desktop_screenshot(target: &str) -> ActionResult {
+/// Makes the PowerShell process DPI-aware, so screen coordinates are
+/// physical pixels on every monitor
+const DPI_AWARE: &str = r#"
+Add-Type -Name Dpi -Namespace ForgeAI -MemberDefinition '[DllImport("user32.dll")] public static extern bool SetProcessDPIAware();'
+[void][ForgeAI.Dpi]::SetProcessDPIAware()
+"#;
+
+/// Dims every screen and lets the user drag a rectangle; Esc, or a minute
+/// without a selection, cancels
+const SELECT_REGION_SCRIPT: &str = r#"
+Add-Type -AssemblyName System.Windows.Forms; Add-Type -AssemblyName System.Drawing
+$v=[System.Windows.Forms.SystemInformation]::VirtualScreen
+$f=New-Object System.Windows.Forms.Form
+$f.FormBorderStyle='None'; $f.StartPosition='Manual'; $f.Bounds=$v; $f.TopMost=$true; $f.ShowInTaskbar=$false
+$f.BackColor=[System.Drawing.Color]::Black; $f.Opacity=0.35; $f.KeyPreview=$true
+$f.Cursor=[System.Windows.Forms.Cursors]::Cross
+$script:start=$null; $script:rect=$null
+$f.Add_MouseDown({ param($s,$e) $script:start=$e.Location })
+$f.Add_MouseMove({ param($s,$e)
+    if($script:start) {
+        $script:rect=[System.Drawing.Rectangle]::FromLTRB([Math]::Min($script:start.X,$e.X),[Math]::Min($script:start.Y,$e.Y),[Math]::Max($script:start.X,$e.X),[Math]::Max($script:start.Y,$e.Y))
+        $f.Invalidate()
+    }
+})
+$f.Add_MouseUp({ $f.Close() })
+$f.Add_KeyDown({ param($s,$e) if($e.KeyCode -eq 'Escape') { $script:rect=$null; $f.Close() } })
+$f.Add_Paint({ param($s,$e)
+    if($script:rect) { $pen=New-Object System.Drawing.Pen([System.Drawing.Color]::White,2); $e.Graphics.DrawRectangle($pen,$script:rect); $pen.Dispose() }
+})
+$t=New-Object System.Windows.Forms.Timer; $t.Interval=60000; $t.Add_Tick({ $script:rect=$null; $f.Close() }); $t.Start()
+[void]$f.ShowDialog(); $t.Stop()
+if($script:rect -and $script:rect.Width -gt 2 -and $script:rect.Height -gt 2) {
+    Write-Output ("REGION: " + (@{x=$script:rect.X+$v.X; y=$script:rect.Y+$v.Y; width=$script:rect.Width; height=$script:rect.Height} | ConvertTo-Json -Compress))
+} else { Write-Output "CANCELLED" }
+"#;
+
+/// A rectangle of the virtual screen (all monitors), in physical pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ScreenRegion {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// What a `screenshot` without a window `target` captures
+#[derive(Debug, Clone, PartialEq)]
+enum Capture {
+    /// The primary monitor
+    Primary,
+    /// A monitor by its `list_monitors` index
+    Monitor(usize),
+    /// Every monitor, as one image
+    AllMonitors,
+    Region(ScreenRegion),
+    /// A region the user drags out first (see [`select_screen_region`])
+    Select,
+}
+
+/// The capture asked for by `region` (`{x, y, width, height}` or `"select"`)
+/// or `monitor` (an index or `"all"`)
+fn parse_capture(params: &serde_json::Value) -> Result<Capture, String> {
+    use serde_json::Value;
+    match params.get("region") {
+        Some(Value::String(s)) if s == "select" => return Ok(Capture::Select),
+        Some(region @ Value::Object(_)) => {
+            let region: ScreenRegion =
+                serde_json::from_value(region.clone()).map_err(|e| format!("Invalid region: {}", e))?;
+            if region.width == 0 || region.height == 0 {
+                return Err("The region must be at least 1×1 pixels".into());
+            }
+            return Ok(Capture::Region(region));
+        }
+        Some(Value::Null) | None => {}
+        Some(_) => return Err("region must be {x, y, width, height} or \"select\"".into()),
+    }
+    match params.get("monitor") {
+        Some(Value::String(s)) if s == "all" => Ok(Capture::AllMonitors),
+        Some(Value::Number(n)) => n.as_u64().map(|n| Capture::Monitor(n as usize)).ok_or("monitor must be an index or \"all\"".into()),
+        Some(Value::String(s)) => s.parse().map(Capture::Monitor).map_err(|_| "monitor must be an index or \"all\"".into()),
+        Some(Value::Null) | None => Ok(Capture::Primary),
+        Some(_) => Err("monitor must be an index or \"all\"".into()),
+    }
+}
+
+/// [`parse_capture`], with a `"select"` region selected
+fn screenshot_capture(params: &serde_json::Value) -> Result<Capture, String> {
+    match parse_capture(params)? {
+        Capture::Select => select_screen_region()?.map(Capture::Region).ok_or("Region selection was cancelled".into()),
+        capture => Ok(capture),
+    }
+}
+
+/// Let the user drag out a rectangle over the screens. `None` when they
+/// cancel. Windows only.
+pub fn select_screen_region() -> Result<Option<ScreenRegion>, String> {
+    if !cfg!(target_os = "windows") {
+        return Err("Selecting a screen region is only available on Windows".into());
+    }
+    let result = run_powershell(&format!("{}{}", DPI_AWARE, SELECT_REGION_SCRIPT));
+    if !result.success {
+        return Err(result.output);
+    }
+    match result.output.lines().find_map(|l| l.trim().strip_prefix("REGION:")) {
+        Some(json) => serde_json::from_str(json.trim()).map(Some).map_err(|e| format!("Unreadable region: {}", e)),
+        None => Ok(None),
+    }
+}
+
+/// Monitors with their index, device name and bounds, as JSON
+fn desktop_list_monitors() -> ActionResult {
+    let script = format!(r#"{dpi}
+Add-Type -AssemblyName System.Windows.Forms
+$all=[System.Windows.Forms.Screen]::AllScreens
+$list=@(for($i=0; $i -lt $all.Count; $i++) {{
+    $b=$all[$i].Bounds
+    [pscustomobject]@{{index=$i; device=$all[$i].DeviceName; primary=$all[$i].Primary; x=$b.X; y=$b.Y; width=$b.Width; height=$b.Height}}
+}})
+ConvertTo-Json -InputObject $list -Compress
+"#, dpi=DPI_AWARE);
+    run_powershell(&script)
+}
+
+fn desktop_screenshot(target: &str, capture: &Capture) -> ActionResult {
+    let filename = format!("screenshot_{}.png", std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis());
+    desktop_screenshot_as(target, capture, &filename)
+}
+
+/// Screenshot saved as `filename` in the screenshots directory. A window
+/// `target` takes precedence over `capture`. The result says what was
+/// captured (`capture`: kind, monitor, bounds, window).
+fn desktop_screenshot_as(target: &str, capture: &Capture, filename: &str) -> ActionResult {
     use base64::Engine;
 
     let dir = std::env::temp_dir().join("forgeai_screenshots");
     let _ = std::fs::create_dir_all(&dir);
-    let filename = format!("screenshot_{}.png", std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH).unwrap_or_default().as_millis());
-    let path = dir.join(&filename);
+    let path = dir.join(filename);
     let path_str = path.to_string_lossy().replace('\\', "\\\\");
 
     let script = if target.is_empty() {
-        // Full screen
-        format!(r#"
+        let (kind, bounds) = match capture {
+            Capture::Primary => ("monitor", "$m=0; for($i=0; $i -lt $all.Count; $i++) { if($all[$i].Primary) { $m=$i } }\n$s=$all[$m].Bounds".to_string()),
+            Capture::Monitor(n) => ("monitor", format!(
+                "$m={n}\nif($m -ge $all.Count) {{ Write-Output \"NOT_FOUND: No monitor {n} (there are $($all.Count); see list_monitors)\"; return }}\n$s=$all[$m].Bounds",
+                n = n
+            )),
+            Capture::AllMonitors => ("all", "$m=$null; $s=[System.Windows.Forms.SystemInformation]::VirtualScreen".to_string()),
+            Capture::Region(r) => ("region", format!(
+                "$m=$null; $s=New-Object System.Drawing.Rectangle({}, {}, {}, {})",
+                r.x, r.y, r.width, r.height
+            )),
+            Capture::Select => return ActionResult::err("Select the region before capturing it".into(), safe_verdict()),
+        };
+        format!(r#"{dpi}
 Add-Type -AssemblyName System.Windows.Forms; Add-Type -AssemblyName System.Drawing
-$s=[System.Windows.Forms.Screen]::PrimaryScreen.Bounds
+$all=[System.Windows.Forms.Screen]::AllScreens
+{bounds}
 $b=New-Object System.Drawing.Bitmap($s.Width,$s.Height)
 $g=[System.Drawing.Graphics]::FromImage($b)
-$g.CopyFromScreen(0,0,0,0,[System.Drawing.Size]::new($s.Width,$s.Height))
+$g.CopyFromScreen($s.X,$s.Y,0,0,$s.Size)
 $b.Save("{path}")
 $g.Dispose(); $b.Dispose()
 Write-Output "SCREENSHOT: {path} ($($s.Width)x$($s.Height))"
-"#, path=path_str)
+Write-Output ("CAPTURE: " + (@{{kind="{kind}"; monitor=$m; x=$s.X; y=$s.Y; width=$s.Width; height=$s.Height}} | ConvertTo-Json -Compress))
+"#, dpi=DPI_AWARE, bounds=bounds, path=path_str, kind=kind)
     } else {
         // Window screenshot using PrintWindow
         let safe = target.replace('\'', "''");
@@ -719,6 +1725,7 @@ public class WinAPI {{
 }}
 "@
 Add-Type -AssemblyName System.Drawing
+{dpi}
 $script:found=$false
 [WinAPI]::EnumWindows({{ param($h,$l)
     if([WinAPI]::IsWindowVisible($h)) {{
@@ -734,13 +1741,14 @@ $script:found=$false
                 $g.ReleaseHdc($hdc); $g.Dispose()
                 $bmp.Save("{path}"); $bmp.Dispose()
                 Write-Output "SCREENSHOT: {path} (${{w}}x${{ht}}) [window: $t]"
+                Write-Output ("CAPTURE: " + (@{{kind="window"; window=$t; x=$r.Left; y=$r.Top; width=$w; height=$ht}} | ConvertTo-Json -Compress))
                 $script:found=$true; return $false
             }}
         }}
     }}; $true
 }}, [IntPtr]::Zero)|Out-Null
 if(-not $found) {{ Write-Output "NOT_FOUND: No window matching '*{safe}*'" }}
-"#, safe=safe, path=path_str)
+"#, safe=safe, path=path_str, dpi=DPI_AWARE)
     };
 
     let ps_result = run_powershell(&script);
@@ -753,16 +1761,22 @@ if(-not $found) {{ Write-Output "NOT_FOUND: No window matching '*{safe}*'" }}
     match std::fs::read(&path) {
         Ok(bytes) => {
             let b64 = base64::engine::general_purpose::STANDARD.encode(&bytes);
+            let (captured, output): (Vec<&str>, Vec<&str>) =
+                ps_result.output.lines().partition(|l| l.trim_start().starts_with("CAPTURE:"));
+            let captured = captured
+                .first()
+                .and_then(|l| serde_json::from_str::<serde_json::Value>(l.trim_start()["CAPTURE:".len()..].trim()).ok());
             let json_output = serde_json::json!({
-                "output": ps_result.output.trim(),
+                "output": output.join("\n").trim(),
                 "filename": filename,
                 "image_base64": b64,
+                "capture": captured,
             });
             ActionResult::ok(json_output.to_string(), safe_verdict())
         }
         Err(e) => {
             // File not found — return the PS output anyway
-            log::warn!("[desktop_screenshot] Could not read {}: {}", real_path, e);
+            tracing::warn!("[desktop_screenshot] Could not read {}: {}", real_path, e);
             ps_result
         }
     }
@@ -777,12 +1791,8 @@ fn desktop_read_screen(target: &str) -> ActionResult {
     let path = dir.join(&filename);
     let path_str = path.to_string_lossy().replace('\\', "\\\\");
 
-    // First take screenshot
-    let screenshot_result = if target.is_empty() {
-        desktop_screenshot("")
-    } else {
-        desktop_screenshot(target)
-    };
+    // First take screenshot, into the file the OCR reads
+    let screenshot_result = desktop_screenshot_as(target, &Capture::Primary, &filename);
 
     if !screenshot_result.success {
         return screenshot_result;
@@ -816,6 +1826,23 @@ try {{
     )
 }
 
+/// OCR text of the window whose title contains `target` (the whole screen
+/// when empty). Windows only.
+pub fn read_screen_text(target: &str) -> Result<String, String> {
+    if !cfg!(target_os = "windows") {
+        return Err("Reading the screen is only available on Windows".into());
+    }
+    let result = desktop_read_screen(target);
+    if !result.success {
+        return Err(result.output);
+    }
+    let text = result.output.split_once("text:").map(|(_, t)| t.trim()).unwrap_or_default();
+    match text.strip_prefix("OCR_ERROR:") {
+        Some(e) => Err(format!("OCR failed: {}", e.trim())),
+        None => Ok(text.to_string()),
+    }
+}
+
 fn desktop_read_window_text(target: &str) -> ActionResult {
     if target.is_empty() {
         return ActionResult::err("target is required for read_window_text".into(), safe_verdict());
@@ -856,7 +1883,16 @@ fn safe_verdict() -> SafetyVerdict {
     }
 }
 
-fn format_size(bytes: u64) -> String {
+fn low_verdict(reason: String) -> SafetyVerdict {
+    SafetyVerdict {
+        allowed: true,
+        risk: RiskLevel::Low,
+        reason,
+        requires_confirmation: false,
+    }
+}
+
+pub fn format_size(bytes: u64) -> String {
     if bytes < 1024 {
         format!("{}B", bytes)
     } else if bytes < 1024 * 1024 {
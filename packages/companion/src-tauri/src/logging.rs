@@ -0,0 +1,401 @@
+//! # Structured Logging
+//!
+//! All companion code logs through `tracing`. Every event is written as one
+//! JSON object per line to `logs/companion.log` in the data directory:
+//!
+//! ```json
+//! {"ts":"2026-01-01T12:00:00.000+01:00","level":"INFO","module":"forgeai_companion::heartbeat","message":"...","fields":{}}
+//! ```
+//!
+//! The file is rotated at 5 MiB, keeping `companion.1.log` …
+//! `companion.4.log`. Records from dependencies that use the `log` crate
+//! are bridged into the same files. `RUST_LOG` (a level name) sets the
+//! console level (default `error`) and can raise the file level above `info`.
+//!
+//! [`query`] reads the files back for the built-in log viewer; [`tail`]
+//! reads just their end, for crash reports.
+
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Subscriber};
+
+/// Size at which the current file is rotated
+const MAX_FILE_BYTES: u64 = 5 * 1024 * 1024;
+/// Files kept, including the current one
+const MAX_FILES: usize = 5;
+/// Entries returned by a query without a limit
+const DEFAULT_QUERY_LIMIT: usize = 500;
+/// Bytes [`tail`] reads from the end of each file
+const TAIL_BYTES: u64 = 64 * 1024;
+
+static WRITER: Mutex<Option<LogFile>> = Mutex::new(None);
+static CONSOLE_LEVEL: OnceLock<LevelFilter> = OnceLock::new();
+
+/// One log line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub ts: String,
+    pub level: String,
+    pub module: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Filter for [`query`]
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct LogQuery {
+    /// Minimum severity (`error` … `trace`)
+    pub level: Option<String>,
+    /// Module path prefix, e.g. `forgeai_companion::voice`
+    pub module: Option<String>,
+    /// RFC 3339 timestamp; older entries are skipped
+    pub since: Option<String>,
+    /// Newest entries returned (default 500)
+    pub limit: Option<usize>,
+}
+
+struct LogFile {
+    file: File,
+    size: u64,
+}
+
+fn log_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("forgeai-companion").join("logs"))
+}
+
+fn file_path(dir: &std::path::Path, index: usize) -> PathBuf {
+    if index == 0 {
+        dir.join("companion.log")
+    } else {
+        dir.join(format!("companion.{}.log", index))
+    }
+}
+
+fn open_current() -> Option<LogFile> {
+    let dir = log_dir()?;
+    std::fs::create_dir_all(&dir).ok()?;
+    let path = file_path(&dir, 0);
+    let file = std::fs::OpenOptions::new().create(true).append(true).open(path).ok()?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    Some(LogFile { file, size })
+}
+
+/// Shift `companion.N.log` up by one and start a fresh current file
+fn rotate() -> Option<LogFile> {
+    let dir = log_dir()?;
+    let _ = std::fs::remove_file(file_path(&dir, MAX_FILES - 1));
+    for index in (0..MAX_FILES - 1).rev() {
+        let _ = std::fs::rename(file_path(&dir, index), file_path(&dir, index + 1));
+    }
+    open_current()
+}
+
+fn write(entry: &LogEntry) {
+    let Ok(mut line) = serde_json::to_string(entry) else {
+        return;
+    };
+    line.push('\n');
+    // A thread that panicked while writing still holds the lock; what its
+    // panic hook logs is dropped rather than waiting for it forever
+    let writer = if std::thread::panicking() { WRITER.try_lock().ok() } else { WRITER.lock().ok() };
+    let Some(mut writer) = writer else {
+        return;
+    };
+    if writer.as_ref().is_some_and(|w| w.size + line.len() as u64 > MAX_FILE_BYTES) {
+        *writer = rotate();
+    }
+    if let Some(w) = writer.as_mut() {
+        if w.file.write_all(line.as_bytes()).is_ok() {
+            w.size += line.len() as u64;
+        }
+    }
+}
+
+fn emit(level: Level, module: &str, message: String, fields: serde_json::Map<String, serde_json::Value>) {
+    let entry = LogEntry {
+        ts: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+        level: level.to_string(),
+        module: module.to_string(),
+        message,
+        fields,
+    };
+    if level <= console_level() {
+        eprintln!("{} {:<5} {}: {}", entry.ts, entry.level, entry.module, entry.message);
+    }
+    write(&entry);
+}
+
+// ─── tracing subscriber ─────────────────────────────
+
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.insert(field.name().into(), value.into());
+        }
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.fields.insert(field.name().into(), value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.fields.insert(field.name().into(), value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.fields.insert(field.name().into(), value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields.insert(field.name().into(), format!("{:?}", value).into());
+        }
+    }
+}
+
+struct JsonFileSubscriber {
+    level: LevelFilter,
+    next_span: AtomicU64,
+}
+
+impl Subscriber for JsonFileSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        self.level >= *metadata.level()
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.level)
+    }
+
+    // Spans are not used by the companion; they only need distinct ids
+    fn new_span(&self, _: &Attributes<'_>) -> Id {
+        Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+    }
+
+    fn record(&self, _: &Id, _: &Record<'_>) {}
+
+    fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+        let meta = event.metadata();
+        emit(*meta.level(), meta.target(), visitor.message, visitor.fields);
+    }
+
+    fn enter(&self, _: &Id) {}
+
+    fn exit(&self, _: &Id) {}
+}
+
+// ─── log crate bridge ───────────────────────────────
+
+struct LogBridge {
+    level: LevelFilter,
+}
+
+fn to_tracing_level(level: log::Level) -> Level {
+    match level {
+        log::Level::Error => Level::ERROR,
+        log::Level::Warn => Level::WARN,
+        log::Level::Info => Level::INFO,
+        log::Level::Debug => Level::DEBUG,
+        log::Level::Trace => Level::TRACE,
+    }
+}
+
+impl log::Log for LogBridge {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.level >= to_tracing_level(metadata.level())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            let level = to_tracing_level(record.level());
+            emit(level, record.target(), record.args().to_string(), Default::default());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+// ─── setup ──────────────────────────────────────────
+
+/// Level named by `RUST_LOG`, if any
+fn env_level() -> Option<LevelFilter> {
+    std::env::var("RUST_LOG").ok()?.trim().parse().ok()
+}
+
+fn console_level() -> LevelFilter {
+    *CONSOLE_LEVEL.get_or_init(|| env_level().unwrap_or(LevelFilter::ERROR))
+}
+
+/// Install the subscriber and the `log` bridge (called first thing in `main`)
+pub fn init() {
+    let level = env_level().unwrap_or(LevelFilter::INFO).max(LevelFilter::INFO);
+    if let Ok(mut writer) = WRITER.lock() {
+        *writer = open_current();
+    }
+    let subscriber = JsonFileSubscriber { level, next_span: AtomicU64::new(1) };
+    if tracing::subscriber::set_global_default(subscriber).is_err() {
+        return;
+    }
+    let log_level = match level {
+        LevelFilter::TRACE => log::LevelFilter::Trace,
+        LevelFilter::DEBUG => log::LevelFilter::Debug,
+        _ => log::LevelFilter::Info,
+    };
+    if log::set_logger(Box::leak(Box::new(LogBridge { level }))).is_ok() {
+        log::set_max_level(log_level);
+    }
+}
+
+// ─── query ──────────────────────────────────────────
+
+fn matches(entry: &LogEntry, min_level: Option<Level>, module: Option<&str>, since: Option<&chrono::DateTime<chrono::FixedOffset>>) -> bool {
+    if let Some(min) = min_level {
+        // Level ordering: ERROR is the "smallest" (most severe)
+        match entry.level.parse::<Level>() {
+            Ok(level) if level <= min => {}
+            _ => return false,
+        }
+    }
+    if module.is_some_and(|m| !entry.module.starts_with(m)) {
+        return false;
+    }
+    if let Some(since) = since {
+        match chrono::DateTime::parse_from_rfc3339(&entry.ts) {
+            Ok(ts) if ts >= *since => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Entries from the rotated files matching `filter`, oldest first
+pub fn query(filter: &LogQuery) -> Result<Vec<LogEntry>, String> {
+    let min_level = match filter.level.as_deref() {
+        Some(l) => Some(l.parse::<Level>().map_err(|_| format!("Unknown log level '{}'", l))?),
+        None => None,
+    };
+    let since = match filter.since.as_deref() {
+        Some(s) => Some(chrono::DateTime::parse_from_rfc3339(s).map_err(|e| format!("Invalid 'since' timestamp: {}", e))?),
+        None => None,
+    };
+    let limit = filter.limit.unwrap_or(DEFAULT_QUERY_LIMIT);
+    let dir = log_dir().ok_or("Cannot determine data directory")?;
+
+    let mut entries = Vec::new();
+    for index in (0..MAX_FILES).rev() {
+        let Ok(file) = File::open(file_path(&dir, index)) else {
+            continue;
+        };
+        for line in std::io::BufReader::new(file).lines().map_while(Result::ok) {
+            if let Ok(entry) = serde_json::from_str::<LogEntry>(&line) {
+                if matches(&entry, min_level, filter.module.as_deref(), since.as_ref()) {
+                    entries.push(entry);
+                }
+            }
+        }
+    }
+    let skip = entries.len().saturating_sub(limit);
+    Ok(entries.split_off(skip))
+}
+
+/// Entries in the last [`TAIL_BYTES`] of `path`, oldest first
+fn entries_at_end(path: &std::path::Path) -> Vec<LogEntry> {
+    let Ok(mut file) = File::open(path) else {
+        return Vec::new();
+    };
+    let start = file.metadata().map(|m| m.len().saturating_sub(TAIL_BYTES)).unwrap_or(0);
+    let mut bytes = Vec::new();
+    if file.seek(SeekFrom::Start(start)).and_then(|_| file.read_to_end(&mut bytes)).is_err() {
+        return Vec::new();
+    }
+    // The first line may be cut; it does not parse and is skipped
+    String::from_utf8_lossy(&bytes).lines().filter_map(|line| serde_json::from_str(line).ok()).collect()
+}
+
+/// The last `limit` entries, oldest first. Reads only the end of the newest
+/// files and takes no lock, so the panic hook can call it.
+pub fn tail(limit: usize) -> Vec<LogEntry> {
+    let Some(dir) = log_dir() else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    for index in 0..MAX_FILES {
+        if entries.len() >= limit {
+            break;
+        }
+        let mut older = entries_at_end(&file_path(&dir, index));
+        older.append(&mut entries);
+        entries = older;
+    }
+    let skip = entries.len().saturating_sub(limit);
+    entries.split_off(skip)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(ts: &str, level: &str, module: &str) -> LogEntry {
+        LogEntry {
+            ts: ts.into(),
+            level: level.into(),
+            module: module.into(),
+            message: String::new(),
+            fields: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_query_filters() {
+        let warn = entry("2026-03-01T10:00:00.000+00:00", "WARN", "forgeai_companion::voice");
+        let info = entry("2026-03-01T09:00:00.000+00:00", "INFO", "forgeai_companion::heartbeat");
+
+        assert!(matches(&warn, Some(Level::WARN), None, None));
+        assert!(!matches(&info, Some(Level::WARN), None, None));
+        assert!(matches(&info, Some(Level::DEBUG), None, None));
+
+        assert!(matches(&warn, None, Some("forgeai_companion::voice"), None));
+        assert!(!matches(&info, None, Some("forgeai_companion::voice"), None));
+
+        let since = chrono::DateTime::parse_from_rfc3339("2026-03-01T11:30:00+02:00").unwrap();
+        assert!(matches(&warn, None, None, Some(&since)));
+        assert!(!matches(&info, None, None, Some(&since)));
+    }
+
+    #[test]
+    fn test_entries_at_end_skips_cut_lines() {
+        let path = std::env::temp_dir().join(format!("forgeai-log-tail-{}.log", std::process::id()));
+        let line = |n: usize| serde_json::to_string(&entry(&format!("2026-03-01T10:00:{:02}.000+00:00", n), "INFO", "m")).unwrap();
+        let padding = "x".repeat(TAIL_BYTES as usize);
+        std::fs::write(&path, format!("{}\n{}\n{}\n{}\n", padding, line(0), line(1), line(2))).unwrap();
+        let entries = entries_at_end(&path);
+        assert_eq!(entries.iter().map(|e| e.ts.as_str()).collect::<Vec<_>>(), [
+            "2026-03-01T10:00:00.000+00:00",
+            "2026-03-01T10:00:01.000+00:00",
+            "2026-03-01T10:00:02.000+00:00",
+        ]);
+        let _ = std::fs::remove_file(&path);
+    }
+}
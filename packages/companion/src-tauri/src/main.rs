@@ -1,25 +1,121 @@
 //! # ForgeAI Desktop Companion — Main Entry Point
 //!
 //! Lightweight native Windows assistant powered by Tauri.
-//! Runs in system tray, activates on wake word or click.
+//! Runs in system tray, activates on wake word or click. Without a display
+//! it runs headless and is driven from the command line (see `headless`).
 
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod autostart;
+mod capabilities;
+mod clipboard_history;
 mod commands;
+mod compression;
+mod config_export;
 mod connection;
+mod crash;
+mod device;
+mod diagnostics;
+mod dictation;
+mod discovery;
+mod duplex;
+mod e2e;
+mod earcons;
+mod embeddings;
+mod events;
+mod exec_context;
+mod focus_guard;
+mod headless;
+mod heartbeat;
+mod history;
+mod hotkeys;
+mod i18n;
+mod idle;
+mod input_profiles;
+mod intents;
+mod http;
+mod jobs;
+mod language;
+mod latency;
+mod lexicon;
+mod linux_desktop;
 mod local_actions;
-mod safety;
+mod logging;
+mod meeting;
+mod memory;
+mod metrics;
+mod mic_arbiter;
+#[cfg(feature = "mock-gateway")]
+mod mock_gateway;
+mod monitor;
+mod mqtt;
+mod netstats;
+mod offline;
+mod outbox;
+mod output_priority;
+mod pagination;
+mod pairing;
+mod permissions;
+mod power;
+mod profiles;
+mod progress;
+mod proxy;
+mod push;
+mod qr;
+mod quota;
+mod quiet_hours;
+mod rate_limit;
+mod reminders;
+mod remote_actions;
+mod remote_approval;
+mod reverse_pairing;
+mod roaming;
+mod rollback;
+mod screen_context;
+mod scripts;
+mod secrets;
+mod secure_store;
+mod settings;
+mod setup;
+mod shortcuts;
+mod simulation;
+mod speaker;
+mod speech_stream;
+mod status;
+mod stt_fallback;
+mod timers;
+mod tls_trust;
+mod transcript_export;
+mod transcript_filter;
+mod transfer;
+mod translate;
+mod tray;
+mod update;
+mod usage;
+mod users;
+mod vocabulary;
 mod voice;
+mod wake_samples;
 mod wake_word;
+mod webhooks;
 
-use tauri::{
-    menu::{Menu, MenuItem},
-    tray::TrayIconBuilder,
-    Manager,
-};
+use forgeai_companion_core::grants;
+use forgeai_companion_core::safety;
+use tauri::Manager;
 
 fn main() {
-    env_logger::init();
+    logging::init();
+    crash::install();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match headless::mode(&args) {
+        Ok(headless::Mode::App) => {}
+        Ok(headless::Mode::Headless) => std::process::exit(headless::run()),
+        Ok(headless::Mode::Command(command)) => std::process::exit(headless::run_command(command)),
+        #[cfg(feature = "mock-gateway")]
+        Ok(headless::Mode::MockGateway(port)) => std::process::exit(mock_gateway::run(port)),
+        Err(usage) => std::process::exit(headless::usage(&usage)),
+    }
 
     tauri::Builder::default()
         .manage(commands::WakeWordState(std::sync::Mutex::new(
@@ -36,6 +132,11 @@ fn main() {
                 let _ = window.set_focus();
             }
         }))
+        .plugin(
+            tauri_plugin_global_shortcut::Builder::new()
+                .with_handler(hotkeys::handle)
+                .build(),
+        )
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_notification::init())
@@ -45,12 +146,93 @@ fn main() {
         .plugin(tauri_plugin_os::init())
         .invoke_handler(tauri::generate_handler![
             commands::execute_action,
+            commands::upload_file_to_gateway,
+            commands::download_file_from_gateway,
+            commands::fetch_result_page,
+            commands::list_jobs,
+            commands::kill_job,
+            commands::get_job_limits,
+            commands::set_job_limits,
+            commands::confirm_pushed_action,
+            commands::list_pending_approvals,
+            commands::approve_actions,
+            commands::deny_actions,
+            commands::remote_approval_enroll,
+            commands::remote_approval_forget,
+            commands::remote_approval_status,
+            commands::quota_status,
+            commands::quota_cleanup,
+            commands::secret_set,
+            commands::secret_list,
+            commands::secret_delete,
+            commands::list_rollbacks,
+            commands::rollback,
+            commands::set_simulation_mode,
+            commands::simulation_log,
+            commands::create_grant,
+            commands::list_grants,
+            commands::revoke_grant,
             commands::check_safety,
             commands::get_safety_prompt,
             commands::get_status,
+            commands::offline_state,
             commands::pair_with_gateway,
+            commands::pair_with_qr,
+            commands::pair_with_qr_image,
+            commands::update_device_info,
+            commands::start_reverse_pairing,
+            commands::cancel_reverse_pairing,
+            commands::discover_gateways,
+            commands::set_gateway_routes,
+            commands::get_proxy_config,
+            commands::set_proxy_config,
+            commands::get_capabilities,
+            commands::get_compression_config,
+            commands::set_compression_config,
+            commands::get_compression_stats,
+            commands::network_stats,
+            commands::sync_history,
+            commands::list_local_sessions,
+            commands::get_local_messages,
+            commands::search_history,
+            commands::get_push_config,
+            commands::set_push_config,
+            commands::reset_network_stats,
+            commands::inspect_gateway_certificate,
+            commands::trust_gateway_certificate,
+            commands::untrust_gateway_certificate,
             commands::chat_send,
             commands::chat_voice,
+            commands::voice_translate,
+            commands::measure_voice_latency,
+            commands::duplex_start,
+            commands::duplex_stop,
+            commands::duplex_active,
+            commands::dictation_start,
+            commands::dictation_stop,
+            commands::dictation_active,
+            commands::test_webhook,
+            commands::clipboard_history,
+            commands::clear_clipboard_history,
+            commands::get_usage_stats,
+            commands::export_config,
+            commands::import_config,
+            commands::list_scripts,
+            commands::reload_scripts,
+            commands::set_mqtt_password,
+            commands::test_mqtt_connection,
+            commands::speaker_enroll,
+            commands::speaker_forget,
+            commands::wake_word_collect_samples,
+            commands::speaker_status,
+            commands::meeting_start,
+            commands::meeting_pause,
+            commands::meeting_resume,
+            commands::meeting_stop,
+            commands::meeting_list,
+            commands::meeting_transcript,
+            commands::meeting_delete,
+            commands::export_transcript,
             commands::play_tts,
             commands::window_start_drag,
             commands::window_minimize,
@@ -64,63 +246,109 @@ fn main() {
             commands::wake_word_configure,
             commands::voice_record,
             commands::voice_stop,
+            commands::voice_mic_test,
+            commands::voice_pipeline_bench,
+            commands::run_setup_checks,
             commands::voice_speak,
+            commands::list_tts_voices,
+            commands::select_tts_voice,
+            commands::preview_tts_voice,
+            commands::voice_providers,
+            commands::select_voice_providers,
+            commands::output_priority,
+            commands::set_output_priority,
+            commands::set_do_not_disturb,
+            commands::quiet_hours_active,
+            commands::set_mic_muted,
+            commands::settings_get,
+            commands::settings_set,
+            commands::settings_reset,
+            commands::query_logs,
+            commands::get_language,
+            commands::get_metrics,
+            commands::reset_metrics,
+            commands::export_diagnostics,
+            commands::list_crash_reports,
+            commands::clear_crash_reports,
+            commands::check_for_update,
+            commands::install_update,
+            commands::get_autostart,
+            commands::set_autostart,
+            commands::get_hotkeys,
+            commands::set_hotkeys,
             commands::read_screenshot,
+            commands::select_screen_region,
+            commands::get_screen_context,
             commands::list_sessions,
             commands::get_session_history,
+            commands::history_list,
+            commands::history_search,
+            commands::set_reminder,
+            commands::list_reminders,
+            commands::cancel_reminder,
+            commands::timer_start,
+            commands::timer_list,
+            commands::timer_cancel,
             commands::delete_session,
             commands::list_audio_devices,
+            commands::get_permissions,
+            commands::request_permission,
+            commands::list_audio_profiles,
+            commands::save_audio_profile,
+            commands::delete_audio_profile,
+            commands::mic_arbitration,
+            commands::list_input_profiles,
+            commands::save_input_profile,
+            commands::delete_input_profile,
+            commands::switch_audio_profile,
+            commands::list_voice_shortcuts,
+            commands::save_voice_shortcut,
+            commands::delete_voice_shortcut,
+            commands::list_profiles,
+            commands::switch_profile,
+            commands::delete_profile,
             commands::connect_gateway_ws,
             commands::force_reconnect_gateway_ws,
         ])
         .setup(|app| {
-            // ─── System Tray ───
-            let toggle = MenuItem::with_id(app, "toggle", "Mostrar/ocultar janela", true, None::<&str>)?;
-            let quit = MenuItem::with_id(app, "quit", "Sair", true, None::<&str>)?;
+            events::init(app.handle().clone());
+            settings::init(app.handle());
+            status::init(app.handle());
 
-            let menu = Menu::with_items(app, &[&toggle, &quit])?;
+            tray::build(app)?;
 
-            let _tray = TrayIconBuilder::new()
-                .menu(&menu)
-                .show_menu_on_left_click(false)
-                .tooltip("ForgeAI Companion")
-                .on_menu_event(|app, event| match event.id.as_ref() {
-                    "toggle" => {
-                        if let Some(window) = app.get_webview_window("main") {
-                            if window.is_visible().unwrap_or(false) {
-                                let _ = window.hide();
-                            } else {
-                                let _ = window.show();
-                                let _ = window.unminimize();
-                                let _ = window.set_focus();
-                            }
-                        }
-                    }
-                    "quit" => {
-                        app.exit(0);
-                    }
-                    _ => {}
-                })
-                .on_tray_icon_event(|tray, event| {
-                    if matches!(event, tauri::tray::TrayIconEvent::Click { .. }) {
-                        let app = tray.app_handle();
-                        if let Some(window) = app.get_webview_window("main") {
-                            if window.is_visible().unwrap_or(false) {
-                                let _ = window.hide();
-                            } else {
-                                let _ = window.show();
-                                let _ = window.unminimize();
-                                let _ = window.set_focus();
-                            }
-                        }
-                    }
-                })
-                .build(app)?;
+            // Launched at login — stay in the tray until the user opens the window
+            if autostart::launched_minimized() {
+                if let Some(window) = app.get_webview_window("main") {
+                    let _ = window.hide();
+                }
+            }
 
-            log::info!("ForgeAI Companion started — system tray active");
+            tracing::info!("ForgeAI Companion started — system tray active (profile {})", users::active());
 
             // Auto-connect Gateway WS if credentials exist
             commands::spawn_gateway_ws();
+            heartbeat::spawn();
+            metrics::spawn_reporter();
+            profiles::spawn_device_watcher(app.handle().clone());
+            power::spawn_monitor(app.handle().clone());
+            idle::spawn_monitor(app.handle().clone());
+            screen_context::spawn_tracker();
+            reminders::spawn_scheduler();
+            timers::spawn_ticker();
+            meeting::spawn_uploader();
+            embeddings::spawn_indexer();
+            scripts::spawn_watcher();
+            clipboard_history::spawn_monitor();
+
+            // Refresh OS / app version / capabilities on the Gateway
+            tauri::async_runtime::spawn(async {
+                if connection::GatewayConnection::load_credentials().is_some() {
+                    if let Err(e) = device::update_on_gateway().await {
+                        tracing::warn!("[Device] Could not update device info: {}", e);
+                    }
+                }
+            });
 
             Ok(())
         })
@@ -0,0 +1,539 @@
+//! # Meeting Recording
+//!
+//! Long-form recording for meetings and lectures. `meeting_start` records
+//! the microphone for up to [`MAX_HOURS`] hours, writing a 16 kHz WAV chunk
+//! to disk every [`CHUNK_SECS`] seconds, so nothing is held in memory and a
+//! crash loses at most one chunk. Recording can be paused and resumed; the
+//! paused time is left out of the timeline.
+//!
+//! Finished chunks are uploaded in the background to the Gateway STT with
+//! speaker diarization (`diarize=true`) and the returned segments are
+//! placed on the meeting's timeline. Chunks that fail (Gateway offline) are
+//! retried later. Once every chunk is in, the timestamped transcript is
+//! assembled locally into `transcript.md`:
+//!
+//! ```text
+//! [00:12:05] Speaker 1: Let's move the launch to Friday.
+//! ```
+//!
+//! Everything lives in `meetings/<id>/` of the profile's data directory.
+//! Emits `meeting-state` on every change.
+
+use chrono::Utc;
+use cpal::traits::{DeviceTrait, StreamTrait};
+use forgeai_companion_core::audio;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Length of one audio chunk on disk
+pub const CHUNK_SECS: u64 = 60;
+/// Longest meeting
+pub const MAX_HOURS: u64 = 8;
+/// Pause between upload attempts while chunks are pending
+const RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// Serializes access to the meeting files
+static LOCK: Mutex<()> = Mutex::new(());
+/// The meeting being recorded
+static RECORDER: Mutex<Option<Recorder>> = Mutex::new(None);
+/// Wakes the uploader when a chunk is written
+static CHUNK_READY: OnceLock<Notify> = OnceLock::new();
+
+fn chunk_ready() -> &'static Notify {
+    CHUNK_READY.get_or_init(Notify::new)
+}
+
+struct Recorder {
+    id: String,
+    paused: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MeetingState {
+    Recording,
+    Paused,
+    /// Recording ended; chunks may still be transcribing
+    Stopped,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TranscriptLine {
+    /// From the start of the meeting
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub speaker: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Chunk {
+    pub file: String,
+    /// Where the chunk starts on the meeting timeline
+    pub offset_ms: u64,
+    pub duration_ms: u64,
+    pub transcribed: bool,
+    #[serde(default)]
+    pub lines: Vec<TranscriptLine>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Meeting {
+    pub id: String,
+    pub title: String,
+    pub started_at: String,
+    pub state: MeetingState,
+    /// Recorded audio, pauses excluded
+    pub duration_ms: u64,
+    pub chunks: Vec<Chunk>,
+}
+
+impl Meeting {
+    /// Every chunk is recorded and transcribed
+    pub fn complete(&self) -> bool {
+        self.state == MeetingState::Stopped && self.chunks.iter().all(|c| c.transcribed)
+    }
+}
+
+fn meetings_dir() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("meetings"))
+}
+
+fn meeting_dir(id: &str) -> Result<PathBuf, String> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Invalid meeting id '{}'", id));
+    }
+    meetings_dir().map(|d| d.join(id)).ok_or_else(|| "Cannot determine data directory".into())
+}
+
+fn load(id: &str) -> Result<Meeting, String> {
+    let json = std::fs::read_to_string(meeting_dir(id)?.join("meeting.json")).map_err(|_| format!("No meeting '{}'", id))?;
+    serde_json::from_str(&json).map_err(|e| format!("Unreadable meeting '{}': {}", id, e))
+}
+
+fn store(meeting: &Meeting) -> Result<(), String> {
+    let dir = meeting_dir(&meeting.id)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create meeting folder: {}", e))?;
+    let json = serde_json::to_string_pretty(meeting).map_err(|e| format!("Serialize error: {}", e))?;
+    std::fs::write(dir.join("meeting.json"), json).map_err(|e| format!("Meeting write error: {}", e))
+}
+
+/// Load, change and store a meeting, then announce it
+fn update(id: &str, change: impl FnOnce(&mut Meeting)) -> Result<Meeting, String> {
+    let meeting = {
+        let _guard = LOCK.lock().map_err(|e| e.to_string())?;
+        let mut meeting = load(id)?;
+        change(&mut meeting);
+        store(&meeting)?;
+        meeting
+    };
+    crate::events::emit("meeting-state", &meeting);
+    Ok(meeting)
+}
+
+fn timestamp(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+/// The timestamped transcript document of `meeting`
+pub fn assemble(meeting: &Meeting) -> String {
+    let mut doc = format!("# {}\n\n{} — {}\n\n", meeting.title, meeting.started_at, timestamp(meeting.duration_ms));
+    let mut lines: Vec<&TranscriptLine> = meeting.chunks.iter().flat_map(|c| &c.lines).collect();
+    lines.sort_by_key(|l| l.start_ms);
+    for line in lines {
+        match &line.speaker {
+            Some(speaker) => doc.push_str(&format!("[{}] {}: {}\n", timestamp(line.start_ms), speaker, line.text)),
+            None => doc.push_str(&format!("[{}] {}\n", timestamp(line.start_ms), line.text)),
+        }
+    }
+    let missing = meeting.chunks.iter().filter(|c| !c.transcribed).count();
+    if missing > 0 {
+        doc.push_str(&format!("\n_{} of {} parts not transcribed yet_\n", missing, meeting.chunks.len()));
+    }
+    doc
+}
+
+/// Diarized segments of a transcription response, placed at `offset_ms`
+fn parse_segments(data: &serde_json::Value, offset_ms: u64, duration_ms: u64) -> Vec<TranscriptLine> {
+    let at = |v: &serde_json::Value| offset_ms + (v.as_f64().unwrap_or(0.0).max(0.0) * 1000.0) as u64;
+    let lines: Vec<TranscriptLine> = data["segments"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|s| {
+            let text = s["text"].as_str()?.trim();
+            (!text.is_empty()).then(|| TranscriptLine {
+                start_ms: at(&s["start"]),
+                end_ms: at(&s["end"]),
+                speaker: s["speaker"].as_str().map(str::to_string),
+                text: crate::transcript_filter::apply(&crate::vocabulary::correct(text)),
+            })
+        })
+        .collect();
+    if !lines.is_empty() {
+        return lines;
+    }
+    // Gateways without diarization answer with plain text
+    match data["text"].as_str().map(str::trim).filter(|t| !t.is_empty()) {
+        Some(text) => vec![TranscriptLine {
+            start_ms: offset_ms,
+            end_ms: offset_ms + duration_ms,
+            speaker: None,
+            text: crate::transcript_filter::apply(&crate::vocabulary::correct(text)),
+        }],
+        None => Vec::new(),
+    }
+}
+
+// ─── Recording ──────────────────────────────────────
+
+/// Write `samples` (16 kHz mono) as the next chunk of meeting `id`
+fn write_chunk(id: &str, samples: &[f32]) -> Result<(), String> {
+    let dir = meeting_dir(id)?;
+    let wav = audio::encode_wav(samples, audio::TARGET_RATE)?;
+    let duration_ms = samples.len() as u64 * 1000 / audio::TARGET_RATE as u64;
+    let mut result: Result<(), String> = Ok(());
+    update(id, |m| {
+        let file = format!("chunk-{:04}.wav", m.chunks.len() + 1);
+        result = std::fs::write(dir.join(&file), &wav).map_err(|e| format!("Chunk write error: {}", e));
+        if result.is_ok() {
+            m.chunks.push(Chunk { file, offset_ms: m.duration_ms, duration_ms, transcribed: false, lines: Vec::new() });
+            m.duration_ms += duration_ms;
+        }
+    })?;
+    chunk_ready().notify_one();
+    result
+}
+
+/// Capture until `stop`, skipping audio while `paused`; owns the cpal
+/// stream, which cannot leave its thread
+fn record(id: String, paused: Arc<AtomicBool>, stop: Arc<AtomicBool>, ready: std::sync::mpsc::Sender<Result<(), String>>) {
+    let device = match crate::voice::input_device() {
+        Some(d) => d,
+        None => {
+            let _ = ready.send(Err("No audio input device".into()));
+            return;
+        }
+    };
+    let config = match device.default_input_config() {
+        Ok(c) => c,
+        Err(e) => {
+            let _ = ready.send(Err(format!("No supported input config: {}", e)));
+            return;
+        }
+    };
+    let (rate, channels) = (config.sample_rate().0, config.channels() as usize);
+    let (tx, rx) = std::sync::mpsc::sync_channel::<Vec<f32>>(256);
+    let stream = device
+        .build_input_stream(
+            &config.into(),
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                let _ = tx.try_send(data.to_vec());
+            },
+            |err| tracing::error!("[Meeting] Capture error: {}", err),
+            None,
+        )
+        .map_err(|e| format!("Failed to build input stream: {}", e))
+        .and_then(|s| s.play().map(|_| s).map_err(|e| format!("Failed to start recording: {}", e)));
+    let stream = match stream {
+        Ok(s) => s,
+        Err(e) => {
+            let _ = ready.send(Err(e));
+            return;
+        }
+    };
+    let _ = ready.send(Ok(()));
+
+    let chunk_len = (CHUNK_SECS * audio::TARGET_RATE as u64) as usize;
+    let max_chunks = MAX_HOURS * 3600 / CHUNK_SECS;
+    let mut buffer: Vec<f32> = Vec::with_capacity(chunk_len);
+    let mut written = 0;
+    while !stop.load(Ordering::Relaxed) {
+        let samples = match rx.recv_timeout(Duration::from_millis(200)) {
+            Ok(s) => s,
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(_) => break,
+        };
+        if paused.load(Ordering::Relaxed) || crate::voice::mic_muted() {
+            continue;
+        }
+        buffer.extend(audio::resample(&audio::downmix(&samples, channels), rate, audio::TARGET_RATE));
+        if buffer.len() >= chunk_len {
+            let rest = buffer.split_off(chunk_len);
+            if let Err(e) = write_chunk(&id, &buffer) {
+                tracing::error!("[Meeting] {}", e);
+            }
+            buffer = rest;
+            written += 1;
+            if written >= max_chunks {
+                tracing::info!("[Meeting] {} reached {} hours, stopping", id, MAX_HOURS);
+                break;
+            }
+        }
+    }
+    drop(stream);
+    // Keep the last partial chunk unless it is under a second
+    if buffer.len() >= audio::TARGET_RATE as usize {
+        if let Err(e) = write_chunk(&id, &buffer) {
+            tracing::error!("[Meeting] {}", e);
+        }
+    }
+    if let Ok(mut recorder) = RECORDER.lock() {
+        if recorder.as_ref().is_some_and(|r| r.id == id) {
+            *recorder = None;
+        }
+    }
+    let _ = update(&id, |m| m.state = MeetingState::Stopped);
+    chunk_ready().notify_one();
+    tracing::info!("[Meeting] Stopped {}", id);
+}
+
+// ─── Transcription ──────────────────────────────────
+
+async fn transcribe_chunk(creds: &crate::connection::CompanionCredentials, id: &str, chunk: &Chunk) -> Result<Vec<TranscriptLine>, String> {
+    let wav = std::fs::read(meeting_dir(id)?.join(&chunk.file)).map_err(|e| format!("Cannot read {}: {}", chunk.file, e))?;
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let build = || {
+        let part = reqwest::multipart::Part::bytes(wav.clone())
+            .file_name(chunk.file.clone())
+            .mime_str("audio/wav")
+            .expect("static MIME type is valid");
+        let mut form = reqwest::multipart::Form::new().part("audio", part).text("diarize", "true");
+        let vocabulary = crate::vocabulary::hint();
+        if !vocabulary.is_empty() {
+            form = form.text("vocabulary", vocabulary.join(", "));
+        }
+        if let Some(provider) = crate::voice::providers().stt {
+            form = form.text("provider", provider);
+        }
+        gw.post("/api/voice/transcribe").multipart(form).timeout(Duration::from_secs(300))
+    };
+    let resp = crate::connection::GatewayConnection::send_authenticated(creds, build).await?;
+    if !resp.status().is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(format!("Transcription failed: {}", text));
+    }
+    let data: serde_json::Value = resp.json().await.map_err(|e| format!("Parse error: {}", e))?;
+    Ok(parse_segments(&data, chunk.offset_ms, chunk.duration_ms))
+}
+
+/// Transcribe the pending chunks of every meeting; false when one failed
+async fn upload_pending() -> bool {
+    let Some(creds) = crate::connection::GatewayConnection::load_credentials() else {
+        return false;
+    };
+    let mut ok = true;
+    for meeting in list() {
+        for (index, chunk) in meeting.chunks.iter().enumerate().filter(|(_, c)| !c.transcribed) {
+            match transcribe_chunk(&creds, &meeting.id, chunk).await {
+                Ok(lines) => {
+                    let _ = update(&meeting.id, |m| {
+                        if let Some(c) = m.chunks.get_mut(index) {
+                            c.lines = lines;
+                            c.transcribed = true;
+                        }
+                    });
+                }
+                Err(e) => {
+                    tracing::warn!("[Meeting] {} {}: {}", meeting.id, chunk.file, e);
+                    ok = false;
+                    break;
+                }
+            }
+        }
+        if let Ok(meeting) = load(&meeting.id) {
+            if meeting.complete() && !meeting_dir(&meeting.id).is_ok_and(|d| d.join("transcript.md").exists()) {
+                if let Err(e) = save_transcript(&meeting) {
+                    tracing::warn!("[Meeting] {}", e);
+                }
+            }
+        }
+    }
+    ok
+}
+
+fn save_transcript(meeting: &Meeting) -> Result<PathBuf, String> {
+    let path = meeting_dir(&meeting.id)?.join("transcript.md");
+    std::fs::write(&path, assemble(meeting)).map_err(|e| format!("Transcript write error: {}", e))?;
+    tracing::info!("[Meeting] Transcript of {} assembled", meeting.id);
+    Ok(path)
+}
+
+/// Upload chunks as they are written, retrying failed ones
+pub fn spawn_uploader() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            let wait = if upload_pending().await { Duration::from_secs(3600) } else { RETRY_WAIT };
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = chunk_ready().notified() => {}
+            }
+        }
+    });
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Start recording a new meeting
+pub fn start(title: Option<String>) -> Result<Meeting, String> {
+    if crate::voice::mic_muted() {
+        return Err("Microphone is muted".into());
+    }
+    let mut recorder = RECORDER.lock().map_err(|e| e.to_string())?;
+    if recorder.is_some() {
+        return Err("A meeting is already being recorded".into());
+    }
+    let now = Utc::now();
+    let meeting = Meeting {
+        id: format!("meeting-{}", now.timestamp_millis()),
+        title: title.map(|t| t.trim().to_string()).filter(|t| !t.is_empty()).unwrap_or_else(|| {
+            format!("Meeting {}", now.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M"))
+        }),
+        started_at: now.to_rfc3339(),
+        state: MeetingState::Recording,
+        duration_ms: 0,
+        chunks: Vec::new(),
+    };
+    {
+        let _guard = LOCK.lock().map_err(|e| e.to_string())?;
+        store(&meeting)?;
+    }
+    let paused = Arc::new(AtomicBool::new(false));
+    let stop = Arc::new(AtomicBool::new(false));
+    let (ready_tx, ready_rx) = std::sync::mpsc::channel();
+    {
+        let (id, paused, stop) = (meeting.id.clone(), paused.clone(), stop.clone());
+        std::thread::spawn(move || record(id, paused, stop, ready_tx));
+    }
+    if let Err(e) = ready_rx.recv().unwrap_or(Err("Recording thread exited".into())) {
+        if let Ok(dir) = meeting_dir(&meeting.id) {
+            let _ = std::fs::remove_dir_all(dir);
+        }
+        return Err(e);
+    }
+    *recorder = Some(Recorder { id: meeting.id.clone(), paused, stop });
+    tracing::info!("[Meeting] Recording {}", meeting.id);
+    crate::events::emit("meeting-state", &meeting);
+    Ok(meeting)
+}
+
+fn set_paused(paused: bool) -> Result<Meeting, String> {
+    let id = {
+        let recorder = RECORDER.lock().map_err(|e| e.to_string())?;
+        let recorder = recorder.as_ref().ok_or("No meeting is being recorded")?;
+        recorder.paused.store(paused, Ordering::Relaxed);
+        recorder.id.clone()
+    };
+    update(&id, |m| m.state = if paused { MeetingState::Paused } else { MeetingState::Recording })
+}
+
+pub fn pause() -> Result<Meeting, String> {
+    set_paused(true)
+}
+
+pub fn resume() -> Result<Meeting, String> {
+    set_paused(false)
+}
+
+/// Stop recording; the last chunk is written and transcription continues
+/// in the background
+pub fn stop() -> Result<String, String> {
+    let recorder = RECORDER.lock().map_err(|e| e.to_string())?;
+    let recorder = recorder.as_ref().ok_or("No meeting is being recorded")?;
+    recorder.stop.store(true, Ordering::Relaxed);
+    Ok(recorder.id.clone())
+}
+
+/// Every meeting, newest first
+pub fn list() -> Vec<Meeting> {
+    let _guard = LOCK.lock();
+    let mut meetings: Vec<Meeting> = meetings_dir()
+        .and_then(|d| std::fs::read_dir(d).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| load(&e.file_name().to_string_lossy()).ok())
+        .collect();
+    meetings.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+    meetings
+}
+
+pub fn get(id: &str) -> Result<Meeting, String> {
+    let _guard = LOCK.lock().map_err(|e| e.to_string())?;
+    load(id)
+}
+
+/// The transcript of meeting `id`, as assembled so far
+pub fn transcript(id: &str) -> Result<String, String> {
+    let _guard = LOCK.lock().map_err(|e| e.to_string())?;
+    load(id).map(|m| assemble(&m))
+}
+
+/// Delete a meeting with its audio and transcript
+pub fn delete(id: &str) -> Result<(), String> {
+    if RECORDER.lock().map_err(|e| e.to_string())?.as_ref().is_some_and(|r| r.id == id) {
+        return Err("Stop the recording before deleting it".into());
+    }
+    let _guard = LOCK.lock().map_err(|e| e.to_string())?;
+    let dir = meeting_dir(id)?;
+    if !dir.exists() {
+        return Err(format!("No meeting '{}'", id));
+    }
+    std::fs::remove_dir_all(dir).map_err(|e| format!("Cannot delete meeting: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diarized_transcript() {
+        let response = serde_json::json!({
+            "text": "ignored",
+            "segments": [
+                { "start": 0.5, "end": 3.0, "speaker": "Speaker 1", "text": " Let's start. " },
+                { "start": 3.2, "end": 4.0, "speaker": "Speaker 2", "text": "" },
+                { "start": 4.0, "end": 9.5, "speaker": "Speaker 2", "text": "Agreed." },
+            ],
+        });
+        let lines = parse_segments(&response, 3_600_000, 60_000);
+        assert_eq!(lines.len(), 2);
+        assert_eq!((lines[0].start_ms, lines[1].end_ms), (3_600_500, 3_609_500));
+
+        let plain = parse_segments(&serde_json::json!({ "text": "Just text" }), 60_000, 60_000);
+        assert_eq!(plain[0].speaker, None);
+        assert_eq!(plain[0].end_ms, 120_000);
+
+        let meeting = Meeting {
+            id: "meeting-1".into(),
+            title: "Planning".into(),
+            started_at: "2026-05-01T14:00:00+00:00".into(),
+            state: MeetingState::Stopped,
+            duration_ms: 3_720_000,
+            chunks: vec![
+                Chunk { file: "chunk-0002.wav".into(), offset_ms: 3_600_000, duration_ms: 120_000, transcribed: true, lines },
+                Chunk { file: "chunk-0001.wav".into(), offset_ms: 60_000, duration_ms: 60_000, transcribed: true, lines: plain },
+                Chunk { file: "chunk-0003.wav".into(), offset_ms: 0, duration_ms: 0, transcribed: false, lines: Vec::new() },
+            ],
+        };
+        assert_eq!(
+            assemble(&meeting),
+            "# Planning\n\n2026-05-01T14:00:00+00:00 — 01:02:00\n\n\
+             [00:01:00] Just text\n\
+             [01:00:00] Speaker 1: Let's start.\n\
+             [01:00:04] Speaker 2: Agreed.\n\
+             \n_1 of 3 parts not transcribed yet_\n"
+        );
+        assert!(!meeting.complete());
+        assert!(meeting_dir("../x").is_err());
+    }
+}
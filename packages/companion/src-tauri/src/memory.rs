@@ -0,0 +1,283 @@
+//! # Voice Memory
+//!
+//! Every completed voice turn — what the user said, what the assistant
+//! answered, when, and optionally the recording — is kept in a local SQLite
+//! store (`memory.db`), so "what did I ask you yesterday?" can be answered
+//! without the Gateway. `history_list` pages through the turns and
+//! `history_search` finds them by text within a time range.
+//!
+//! Turns older than `memory.retentionDays` are pruned at startup and after
+//! each new turn, together with their recordings; `0` keeps them forever.
+//! Recordings are only saved with `memory.keepAudio`.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+static DB: OnceLock<Mutex<Connection>> = OnceLock::new();
+static CONFIG: Mutex<Option<MemoryConfig>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MemoryConfig {
+    pub enabled: bool,
+    /// Days to keep turns; 0 keeps them forever
+    pub retention_days: u32,
+    /// Save each recording as a WAV next to the store
+    pub keep_audio: bool,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self { enabled: true, retention_days: 90, keep_audio: false }
+    }
+}
+
+/// One voice turn
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceTurn {
+    pub id: i64,
+    pub session_id: Option<String>,
+    pub transcript: String,
+    pub reply: String,
+    /// RFC 3339, when recording started
+    pub started_at: String,
+    /// RFC 3339, when the reply arrived
+    pub finished_at: String,
+    pub audio_ms: u64,
+    /// Path of the saved recording
+    pub audio_ref: Option<String>,
+}
+
+fn config() -> MemoryConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+/// Use `config` from now on and prune what it no longer keeps
+pub fn set_config(config: &MemoryConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+    if let Err(e) = prune() {
+        tracing::warn!("[Memory] Pruning failed: {}", e);
+    }
+}
+
+fn data_dir() -> Result<PathBuf, String> {
+    let dir = crate::users::data_dir().ok_or("Cannot determine data directory")?;
+    let _ = std::fs::create_dir_all(&dir);
+    Ok(dir)
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS voice_turns (
+             id INTEGER PRIMARY KEY AUTOINCREMENT,
+             session_id TEXT,
+             transcript TEXT NOT NULL,
+             reply TEXT NOT NULL,
+             started_at TEXT NOT NULL,
+             finished_at TEXT NOT NULL,
+             audio_ms INTEGER NOT NULL,
+             audio_ref TEXT
+         );
+         CREATE INDEX IF NOT EXISTS voice_turns_by_time ON voice_turns (started_at);",
+    )
+}
+
+fn with_db<T>(f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let db = match DB.get() {
+        Some(db) => db,
+        None => {
+            let conn = Connection::open(data_dir()?.join("memory.db"))
+                .map_err(|e| format!("Memory DB error: {}", e))?;
+            init_schema(&conn).map_err(|e| format!("Memory DB error: {}", e))?;
+            DB.get_or_init(|| Mutex::new(conn))
+        }
+    };
+    let mut conn = db.lock().map_err(|e| e.to_string())?;
+    f(&mut conn).map_err(|e| format!("Memory DB error: {}", e))
+}
+
+// ─── Queries ────────────────────────────────────────
+
+fn row_to_turn(r: &rusqlite::Row) -> rusqlite::Result<VoiceTurn> {
+    Ok(VoiceTurn {
+        id: r.get(0)?,
+        session_id: r.get(1)?,
+        transcript: r.get(2)?,
+        reply: r.get(3)?,
+        started_at: r.get(4)?,
+        finished_at: r.get(5)?,
+        audio_ms: r.get(6)?,
+        audio_ref: r.get(7)?,
+    })
+}
+
+const COLUMNS: &str = "id, session_id, transcript, reply, started_at, finished_at, audio_ms, audio_ref";
+
+fn insert(conn: &Connection, turn: &VoiceTurn) -> rusqlite::Result<i64> {
+    conn.execute(
+        "INSERT INTO voice_turns (session_id, transcript, reply, started_at, finished_at, audio_ms, audio_ref)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![turn.session_id, turn.transcript, turn.reply, turn.started_at, turn.finished_at, turn.audio_ms, turn.audio_ref],
+    )?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Newest first; `before` is the `id` of the last turn of the previous page
+fn list(conn: &Connection, before: Option<i64>, limit: u32) -> rusqlite::Result<Vec<VoiceTurn>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM voice_turns WHERE (?1 IS NULL OR id < ?1) ORDER BY id DESC LIMIT ?2",
+        COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![before, limit], row_to_turn)?;
+    rows.collect()
+}
+
+/// The turns of one chat session, oldest first
+fn by_session(conn: &Connection, session_id: &str) -> rusqlite::Result<Vec<VoiceTurn>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM voice_turns WHERE session_id = ?1 ORDER BY started_at",
+        COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![session_id], row_to_turn)?;
+    rows.collect()
+}
+
+/// Turns whose transcript or reply contains `query` (any text when empty),
+/// started within `[since, until)`, newest first
+fn search(conn: &Connection, query: &str, since: Option<&str>, until: Option<&str>, limit: u32, offset: u32) -> rusqlite::Result<Vec<VoiceTurn>> {
+    let escaped = query.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {} FROM voice_turns
+         WHERE (transcript LIKE ?1 ESCAPE '\\' OR reply LIKE ?1 ESCAPE '\\')
+           AND (?2 IS NULL OR started_at >= ?2) AND (?3 IS NULL OR started_at < ?3)
+         ORDER BY started_at DESC LIMIT ?4 OFFSET ?5",
+        COLUMNS
+    ))?;
+    let rows = stmt.query_map(params![format!("%{}%", escaped), since, until, limit, offset], row_to_turn)?;
+    rows.collect()
+}
+
+/// Delete turns started before `cutoff`; returns their recordings
+fn delete_before(conn: &mut Connection, cutoff: &str) -> rusqlite::Result<Vec<String>> {
+    let tx = conn.transaction()?;
+    let audio: Vec<String> = tx
+        .prepare("SELECT audio_ref FROM voice_turns WHERE started_at < ?1 AND audio_ref IS NOT NULL")?
+        .query_map(params![cutoff], |r| r.get(0))?
+        .collect::<rusqlite::Result<_>>()?;
+    tx.execute("DELETE FROM voice_turns WHERE started_at < ?1", params![cutoff])?;
+    tx.commit()?;
+    Ok(audio)
+}
+
+/// Normalize an RFC 3339 bound to UTC so it compares with stored times
+fn utc_bound(bound: Option<&str>) -> Result<Option<String>, String> {
+    bound
+        .map(|b| {
+            chrono::DateTime::parse_from_rfc3339(b)
+                .map(|t| t.with_timezone(&chrono::Utc).to_rfc3339())
+                .map_err(|e| format!("Invalid time '{}': {}", b, e))
+        })
+        .transpose()
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Store a finished voice turn. `wav` is the recording, kept only with
+/// `keepAudio`. Does nothing when memory is off.
+pub fn record(mut turn: VoiceTurn, wav: Option<&[u8]>) -> Result<(), String> {
+    let config = config();
+    if !config.enabled {
+        return Ok(());
+    }
+    if let (true, Some(wav)) = (config.keep_audio, wav) {
+        let dir = data_dir()?.join("voice-turns");
+        let _ = std::fs::create_dir_all(&dir);
+        let path = dir.join(format!("{}.wav", chrono::Utc::now().timestamp_millis()));
+        std::fs::write(&path, wav).map_err(|e| format!("Write error: {}", e))?;
+        turn.audio_ref = Some(path.to_string_lossy().to_string());
+    }
+    with_db(|c| insert(c, &turn))?;
+    prune()
+}
+
+/// Voice turns, newest first
+pub fn turns(before: Option<i64>, limit: u32) -> Result<Vec<VoiceTurn>, String> {
+    with_db(|c| list(c, before, limit))
+}
+
+/// Voice turns of the chat session `session_id`, oldest first
+pub fn session_turns(session_id: &str) -> Result<Vec<VoiceTurn>, String> {
+    with_db(|c| by_session(c, session_id))
+}
+
+/// Case-insensitive text search over transcripts and replies, optionally
+/// limited to turns started in `[since, until)` (RFC 3339)
+pub fn search_turns(query: &str, since: Option<&str>, until: Option<&str>, limit: u32, offset: u32) -> Result<Vec<VoiceTurn>, String> {
+    let (since, until) = (utc_bound(since)?, utc_bound(until)?);
+    with_db(|c| search(c, query.trim(), since.as_deref(), until.as_deref(), limit, offset))
+}
+
+/// Drop turns past the retention period and their recordings
+pub fn prune() -> Result<(), String> {
+    let days = config().retention_days;
+    if days == 0 {
+        return Ok(());
+    }
+    let cutoff = (chrono::Utc::now() - chrono::Duration::days(days as i64)).to_rfc3339();
+    let audio = with_db(|c| delete_before(c, &cutoff))?;
+    for path in &audio {
+        let _ = std::fs::remove_file(path);
+    }
+    if !audio.is_empty() {
+        tracing::info!("[Memory] Pruned turns older than {} days", days);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(transcript: &str, reply: &str, at: &str) -> VoiceTurn {
+        VoiceTurn {
+            id: 0,
+            session_id: None,
+            transcript: transcript.into(),
+            reply: reply.into(),
+            started_at: at.into(),
+            finished_at: at.into(),
+            audio_ms: 1200,
+            audio_ref: None,
+        }
+    }
+
+    #[test]
+    fn test_store_search_and_retention() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        insert(&conn, &turn("What's the weather?", "Sunny, 24°C", "2026-03-01T08:00:00+00:00")).unwrap();
+        insert(&conn, &turn("Remind me to call Ana", "Done", "2026-03-02T09:30:00+00:00")).unwrap();
+        insert(&conn, &VoiceTurn { audio_ref: Some("/tmp/3.wav".into()), ..turn("Play jazz", "Playing", "2026-03-03T20:00:00+00:00") }).unwrap();
+
+        let page = list(&conn, None, 2).unwrap();
+        assert_eq!(page.iter().map(|t| t.transcript.as_str()).collect::<Vec<_>>(), ["Play jazz", "Remind me to call Ana"]);
+        assert_eq!(list(&conn, Some(page[1].id), 2).unwrap()[0].transcript, "What's the weather?");
+
+        // Text matches either side; the range narrows to a day
+        assert_eq!(search(&conn, "SUNNY", None, None, 10, 0).unwrap().len(), 1);
+        let day = utc_bound(Some("2026-03-02T06:00:00+03:00")).unwrap();
+        let yesterday = search(&conn, "", day.as_deref(), Some("2026-03-03T00:00:00+00:00"), 10, 0).unwrap();
+        assert_eq!(yesterday.len(), 1);
+        assert_eq!(yesterday[0].reply, "Done");
+        assert!(utc_bound(Some("yesterday")).is_err());
+
+        assert_eq!(delete_before(&mut conn, "2026-03-03T00:00:00+00:00").unwrap(), Vec::<String>::new());
+        assert_eq!(delete_before(&mut conn, "2026-04-01T00:00:00+00:00").unwrap(), ["/tmp/3.wav"]);
+        assert!(list(&conn, None, 10).unwrap().is_empty());
+    }
+}
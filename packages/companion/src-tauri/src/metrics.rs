@@ -0,0 +1,337 @@
+//! # Latency & Usage Metrics
+//!
+//! In-memory timings for tuning a setup: how long recordings last, how long
+//! the Gateway takes to transcribe and synthesize, how long a full voice
+//! turn takes and how long each local action runs. A voice turn is a single
+//! request that transcribes and answers, so `stt_ms` comes from the
+//! `timings.sttMs` the Gateway reports in its reply (absent on Gateways that
+//! do not report it) rather than from a companion-side clock. Each timing keeps count,
+//! sum, min and max since startup plus a window of the last 512 samples for
+//! percentiles. Wake word detections are a plain counter.
+//!
+//! `get_metrics` returns a [`MetricsSnapshot`]; the same snapshot is sent to
+//! the Gateway as `telemetry` once an hour while paired. Setting
+//! `metrics.prometheusPort` also serves the same data in the Prometheus text
+//! format on `http://127.0.0.1:<port>/metrics` (off by default).
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Samples kept per timing for percentiles
+const WINDOW: usize = 512;
+/// Interval between telemetry reports to the Gateway
+const REPORT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+pub mod timing {
+    pub const RECORDING: &str = "recording_ms";
+    pub const STT: &str = "stt_ms";
+    pub const TTS: &str = "tts_ms";
+    pub const VOICE_TURN: &str = "voice_turn_ms";
+    pub const ACTION: &str = "action_ms";
+}
+
+pub mod counter {
+    pub const WAKE_DETECTIONS: &str = "wake_detections";
+}
+
+static METRICS: OnceLock<Mutex<Registry>> = OnceLock::new();
+static EXPORTER: Mutex<Option<(u16, tauri::async_runtime::JoinHandle<()>)>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MetricsConfig {
+    /// Serve `/metrics` on this loopback port
+    pub prometheus_port: Option<u16>,
+}
+
+#[derive(Default)]
+struct Timing {
+    count: u64,
+    sum: f64,
+    min: f64,
+    max: f64,
+    recent: VecDeque<f64>,
+}
+
+impl Timing {
+    fn observe(&mut self, ms: f64) {
+        if self.count == 0 || ms < self.min {
+            self.min = ms;
+        }
+        self.max = self.max.max(ms);
+        self.count += 1;
+        self.sum += ms;
+        if self.recent.len() == WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(ms);
+    }
+
+    fn summary(&self) -> TimingSummary {
+        let mut sorted: Vec<f64> = self.recent.iter().copied().collect();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        TimingSummary {
+            count: self.count,
+            avg_ms: if self.count > 0 { self.sum / self.count as f64 } else { 0.0 },
+            min_ms: self.min,
+            max_ms: self.max,
+            p50_ms: percentile(&sorted, 0.50),
+            p95_ms: percentile(&sorted, 0.95),
+            sum_ms: self.sum,
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], q: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+struct Registry {
+    since: String,
+    /// Keyed by (name, label) — the label is the action name for `action_ms`
+    timings: BTreeMap<(&'static str, String), Timing>,
+    counters: BTreeMap<&'static str, u64>,
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self {
+            since: chrono::Local::now().to_rfc3339(),
+            timings: BTreeMap::new(),
+            counters: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimingSummary {
+    pub count: u64,
+    pub avg_ms: f64,
+    pub min_ms: f64,
+    pub max_ms: f64,
+    /// Over the last 512 samples
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub sum_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricsSnapshot {
+    pub since: String,
+    /// `recording_ms`, `stt_ms`, `tts_ms`, `voice_turn_ms`
+    pub timings: BTreeMap<String, TimingSummary>,
+    /// `action_ms` per action name
+    pub actions: BTreeMap<String, TimingSummary>,
+    pub counters: BTreeMap<String, u64>,
+}
+
+fn registry() -> &'static Mutex<Registry> {
+    METRICS.get_or_init(|| Mutex::new(Registry::default()))
+}
+
+/// Record a duration in milliseconds
+pub fn observe(name: &'static str, label: &str, ms: f64) {
+    if let Ok(mut r) = registry().lock() {
+        r.timings.entry((name, label.to_string())).or_default().observe(ms);
+    }
+}
+
+/// Record the time elapsed since `start`
+pub fn observe_since(name: &'static str, label: &str, start: Instant) {
+    observe(name, label, start.elapsed().as_secs_f64() * 1000.0);
+}
+
+pub fn increment(name: &'static str) {
+    if let Ok(mut r) = registry().lock() {
+        *r.counters.entry(name).or_default() += 1;
+    }
+}
+
+pub fn snapshot() -> MetricsSnapshot {
+    let Ok(r) = registry().lock() else {
+        return MetricsSnapshot {
+            since: String::new(),
+            timings: BTreeMap::new(),
+            actions: BTreeMap::new(),
+            counters: BTreeMap::new(),
+        };
+    };
+    let mut timings = BTreeMap::new();
+    let mut actions = BTreeMap::new();
+    for ((name, label), timing) in &r.timings {
+        if *name == timing::ACTION {
+            actions.insert(label.clone(), timing.summary());
+        } else {
+            timings.insert(name.to_string(), timing.summary());
+        }
+    }
+    MetricsSnapshot {
+        since: r.since.clone(),
+        timings,
+        actions,
+        counters: r.counters.iter().map(|(k, v)| (k.to_string(), *v)).collect(),
+    }
+}
+
+pub fn reset() {
+    if let Ok(mut r) = registry().lock() {
+        *r = Registry::default();
+    }
+}
+
+/// Send a snapshot to the Gateway every hour while paired. It goes through
+/// the outbox under one key, so only the latest waits while offline.
+pub fn spawn_reporter() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            tokio::time::sleep(REPORT_INTERVAL).await;
+            if crate::connection::GatewayConnection::load_credentials().is_some() {
+                crate::outbox::send_or_queue(
+                    "telemetry",
+                    serde_json::json!({ "type": "telemetry", "metrics": snapshot() }),
+                );
+            }
+        }
+    });
+}
+
+// ─── Prometheus ─────────────────────────────────────
+
+/// The registry in the Prometheus text exposition format (summaries in seconds)
+pub fn prometheus_text() -> String {
+    let Ok(r) = registry().lock() else {
+        return String::new();
+    };
+    let mut out = String::new();
+    let mut last_name = "";
+    for ((name, label), timing) in &r.timings {
+        let metric = format!("forgeai_{}", name.trim_end_matches("_ms"));
+        if *name != last_name {
+            out.push_str(&format!("# TYPE {}_seconds summary\n", metric));
+            last_name = name;
+        }
+        let labels = if label.is_empty() { String::new() } else { format!("action=\"{}\"", label.replace('"', "")) };
+        let with = |extra: &str| match (labels.is_empty(), extra.is_empty()) {
+            (true, true) => String::new(),
+            (true, false) => format!("{{{}}}", extra),
+            (false, true) => format!("{{{}}}", labels),
+            (false, false) => format!("{{{},{}}}", labels, extra),
+        };
+        let s = timing.summary();
+        out.push_str(&format!("{}_seconds{} {}\n", metric, with("quantile=\"0.5\""), s.p50_ms / 1000.0));
+        out.push_str(&format!("{}_seconds{} {}\n", metric, with("quantile=\"0.95\""), s.p95_ms / 1000.0));
+        out.push_str(&format!("{}_seconds_sum{} {}\n", metric, with(""), s.sum_ms / 1000.0));
+        out.push_str(&format!("{}_seconds_count{} {}\n", metric, with(""), s.count));
+    }
+    for (name, value) in &r.counters {
+        out.push_str(&format!("# TYPE forgeai_{}_total counter\nforgeai_{}_total {}\n", name, name, value));
+    }
+    out
+}
+
+async fn serve(listener: tokio::net::TcpListener) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    let mut backoff = Duration::from_millis(100);
+    loop {
+        let mut stream = match listener.accept().await {
+            Ok((stream, _)) => {
+                backoff = Duration::from_millis(100);
+                stream
+            }
+            // e.g. out of file descriptors — retrying at once would spin
+            Err(e) => {
+                tracing::warn!("[Metrics] Accept failed: {} — retrying in {:?}", e, backoff);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(Duration::from_secs(5));
+                continue;
+            }
+        };
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1024];
+            let n = stream.read(&mut buf).await.unwrap_or(0);
+            let request = String::from_utf8_lossy(&buf[..n]);
+            let response = if request.starts_with("GET /metrics ") {
+                let body = prometheus_text();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+        });
+    }
+}
+
+/// Start, move or stop the Prometheus endpoint
+pub fn set_config(config: &MetricsConfig) {
+    let Ok(mut exporter) = EXPORTER.lock() else {
+        return;
+    };
+    if exporter.as_ref().map(|(port, _)| *port) == config.prometheus_port {
+        return;
+    }
+    if let Some((_, task)) = exporter.take() {
+        task.abort();
+    }
+    let Some(port) = config.prometheus_port else {
+        return;
+    };
+    let task = tauri::async_runtime::spawn(async move {
+        match tokio::net::TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => {
+                tracing::info!("[Metrics] Prometheus endpoint on http://127.0.0.1:{}/metrics", port);
+                serve(listener).await;
+            }
+            Err(e) => tracing::warn!("[Metrics] Cannot listen on port {}: {}", port, e),
+        }
+    });
+    *exporter = Some((port, task));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_summary() {
+        let mut t = Timing::default();
+        for ms in 1..=100 {
+            t.observe(ms as f64);
+        }
+        let s = t.summary();
+        assert_eq!(s.count, 100);
+        assert_eq!(s.min_ms, 1.0);
+        assert_eq!(s.max_ms, 100.0);
+        assert_eq!(s.avg_ms, 50.5);
+        assert_eq!(s.p50_ms, 50.0);
+        assert_eq!(s.p95_ms, 95.0);
+
+        // The percentile window slides, the totals do not
+        for _ in 0..WINDOW {
+            t.observe(10.0);
+        }
+        let s = t.summary();
+        assert_eq!(s.count, 100 + WINDOW as u64);
+        assert_eq!(s.p95_ms, 10.0);
+        assert_eq!(s.max_ms, 100.0);
+
+        observe(timing::ACTION, "shell", 20.0);
+        increment(counter::WAKE_DETECTIONS);
+        let text = prometheus_text();
+        assert!(text.contains("forgeai_action_seconds_count{action=\"shell\"} 1"));
+        assert!(text.contains("forgeai_wake_detections_total 1"));
+        assert!(snapshot().actions.contains_key("shell"));
+    }
+}
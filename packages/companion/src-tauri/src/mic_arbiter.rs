@@ -0,0 +1,230 @@
+//! # Microphone Arbitration
+//!
+//! Decides who owns the microphone, so the wake word, a recording and the
+//! playback queue stop tripping over each other when a follow-up question
+//! starts while the reply is still being spoken.
+//!
+//! Exactly one of [`Owner`] holds the microphone at a time: a recording
+//! (voice turn, dictation, translation, enrollment) or a duplex
+//! conversation takes it with [`claim`] and gives it back when the claim
+//! is dropped; a second claim is refused while one is held. The wake word
+//! listens in the background and owns the microphone whenever nobody else
+//! does. Its detections are arbitrated by [`on_wake`]:
+//!
+//! - while a recording or duplex conversation holds the mic, they are the
+//!   user's own speech and are dropped
+//! - while speech plays, only a detection [`BARGE_IN_FACTOR`] times louder
+//!   than the threshold counts, as a barge-in: the playback is stopped and
+//!   the wake word goes through, so the user can cut a reply short
+//! - for [`ECHO_TAIL`] after speech ends, detections are dropped as the
+//!   reply's echo
+//!
+//! `mic_arbitration` returns the current state and the last transitions
+//! for debugging; every change is emitted as `mic-owner-changed`.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How much louder than the wake threshold speech must be to interrupt playback
+pub const BARGE_IN_FACTOR: f32 = 2.0;
+/// After playback ends, detections are taken for its echo this long
+pub const ECHO_TAIL: Duration = Duration::from_millis(500);
+/// Transitions kept for `mic_arbitration`
+const LOG_SIZE: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Owner {
+    WakeWord,
+    Recording,
+    Duplex,
+}
+
+/// What happens to a wake word detection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WakeDecision {
+    Accept,
+    /// Accepted, and the playback is stopped
+    BargeIn,
+    /// Someone else holds the microphone
+    DropBusy,
+    /// Speech is playing and the detection was not loud enough to interrupt it
+    DropSpeaking,
+    /// Too soon after speech ended
+    DropEcho,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transition {
+    pub at: String,
+    pub owner: Owner,
+    pub speaking: bool,
+    /// What caused it
+    pub event: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArbitrationState {
+    pub owner: Owner,
+    /// What holds the claim, e.g. `duplex conversation`
+    pub holder: Option<String>,
+    pub speaking: bool,
+    /// Newest last
+    pub transitions: Vec<Transition>,
+}
+
+struct State {
+    claim: Option<(Owner, String)>,
+    speaking: bool,
+    speech_ended: Option<Instant>,
+    log: VecDeque<Transition>,
+}
+
+static STATE: Mutex<State> = Mutex::new(State { claim: None, speaking: false, speech_ended: None, log: VecDeque::new() });
+
+impl State {
+    fn owner(&self) -> Owner {
+        self.claim.as_ref().map_or(Owner::WakeWord, |(owner, _)| *owner)
+    }
+
+    fn log(&mut self, event: String) {
+        let transition = Transition {
+            at: chrono::Local::now().to_rfc3339(),
+            owner: self.owner(),
+            speaking: self.speaking,
+            event,
+        };
+        tracing::debug!("[Mic] {:?} (speaking: {}): {}", transition.owner, transition.speaking, transition.event);
+        crate::events::emit("mic-owner-changed", &transition);
+        if self.log.len() == LOG_SIZE {
+            self.log.pop_front();
+        }
+        self.log.push_back(transition);
+    }
+}
+
+/// The arbitration rules, for a detection `loudness` times the threshold
+fn decide(owner: Owner, speaking: bool, since_speech: Option<Duration>, loudness: f32) -> WakeDecision {
+    if owner != Owner::WakeWord {
+        WakeDecision::DropBusy
+    } else if speaking {
+        if loudness >= BARGE_IN_FACTOR { WakeDecision::BargeIn } else { WakeDecision::DropSpeaking }
+    } else if since_speech.is_some_and(|d| d < ECHO_TAIL) {
+        WakeDecision::DropEcho
+    } else {
+        WakeDecision::Accept
+    }
+}
+
+/// The microphone held by a recording or duplex conversation; released on drop
+#[derive(Debug)]
+pub struct Claim(());
+
+impl Drop for Claim {
+    fn drop(&mut self) {
+        if let Ok(mut state) = STATE.lock() {
+            if let Some((owner, holder)) = state.claim.take() {
+                state.log(format!("{:?} released by {}", owner, holder));
+            }
+        }
+    }
+}
+
+/// See [`speaking`]
+pub struct Speaking(());
+
+impl Drop for Speaking {
+    fn drop(&mut self) {
+        set_speaking(false);
+    }
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Take the microphone for `owner` on behalf of `holder`, unless another
+/// claim holds it
+pub fn claim(owner: Owner, holder: &str) -> Result<Claim, String> {
+    let mut state = STATE.lock().map_err(|e| e.to_string())?;
+    if let Some((_, current)) = &state.claim {
+        return Err(format!("The microphone is in use ({})", current));
+    }
+    state.claim = Some((owner, holder.to_string()));
+    state.log(format!("{:?} claimed by {}", owner, holder));
+    Ok(Claim(()))
+}
+
+fn set_speaking(speaking: bool) {
+    let Ok(mut state) = STATE.lock() else { return };
+    if state.speaking == speaking {
+        return;
+    }
+    state.speaking = speaking;
+    if !speaking {
+        state.speech_ended = Some(Instant::now());
+    }
+    state.log(if speaking { "speech started" } else { "speech ended" }.into());
+}
+
+/// Speech is playing until the returned guard is dropped
+pub fn speaking() -> Speaking {
+    set_speaking(true);
+    Speaking(())
+}
+
+/// Arbitrate a wake word detection `loudness` times its threshold. A
+/// barge-in stops the playback before returning.
+pub fn on_wake(loudness: f32) -> WakeDecision {
+    let decision = {
+        let Ok(mut state) = STATE.lock() else { return WakeDecision::Accept };
+        let decision = decide(state.owner(), state.speaking, state.speech_ended.map(|t| t.elapsed()), loudness);
+        // A long recording drops a detection every few frames; log it once
+        let event = format!("wake word {:?}", decision).to_lowercase();
+        if state.log.back().is_none_or(|last| last.event != event) {
+            state.log(event);
+        }
+        decision
+    };
+    if decision == WakeDecision::BargeIn {
+        crate::voice::stop_playback();
+    }
+    decision
+}
+
+pub fn state() -> ArbitrationState {
+    let Ok(state) = STATE.lock() else {
+        return ArbitrationState { owner: Owner::WakeWord, holder: None, speaking: false, transitions: Vec::new() };
+    };
+    ArbitrationState {
+        owner: state.owner(),
+        holder: state.claim.as_ref().map(|(_, holder)| holder.clone()),
+        speaking: state.speaking,
+        transitions: state.log.iter().cloned().collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wake_arbitration() {
+        assert_eq!(decide(Owner::Recording, false, None, 5.0), WakeDecision::DropBusy);
+        assert_eq!(decide(Owner::Duplex, true, None, 5.0), WakeDecision::DropBusy);
+        assert_eq!(decide(Owner::WakeWord, true, None, 1.2), WakeDecision::DropSpeaking);
+        assert_eq!(decide(Owner::WakeWord, true, None, 2.5), WakeDecision::BargeIn);
+        assert_eq!(decide(Owner::WakeWord, false, Some(Duration::from_millis(100)), 3.0), WakeDecision::DropEcho);
+        assert_eq!(decide(Owner::WakeWord, false, Some(Duration::from_secs(2)), 1.1), WakeDecision::Accept);
+
+        let held = claim(Owner::Recording, "voice turn").unwrap();
+        assert!(claim(Owner::Duplex, "duplex").unwrap_err().contains("voice turn"));
+        assert_eq!(state().owner, Owner::Recording);
+        drop(held);
+        assert_eq!(state().owner, Owner::WakeWord);
+        assert!(state().transitions.len() >= 2);
+    }
+}
@@ -0,0 +1,442 @@
+//! # Mock Gateway
+//!
+//! An in-process stand-in for the ForgeAI Gateway, built with the
+//! `mock-gateway` feature, so voice and connection code can be tested and
+//! demoed without a real server. It serves just enough of the API:
+//!
+//! | Route                          | Behaviour                                                   |
+//! |--------------------------------|-------------------------------------------------------------|
+//! | `GET /health`                  | always healthy                                              |
+//! | `POST /api/companion/pair`     | accepts [`PAIRING_CODE`], answers with [`AUTH_TOKEN`]       |
+//! | `POST /api/companion/refresh`  | hands out [`AUTH_TOKEN`] again                              |
+//! | `GET /api/companion/me`        | the paired companion's id and role                          |
+//! | `POST /api/voice/transcribe`   | returns the configured transcript, detected as `en`         |
+//! | `POST /api/voice/synthesize`   | returns a short WAV tone                                    |
+//! | `POST /api/voice/translate`    | the text prefixed with the target language, `[pt] …`        |
+//! | `GET /api/voice/voices`        | two voices, one with a sample served as the WAV tone        |
+//! | `POST /api/chat/voice`         | transcript and reply, with `timings.sttMs`                  |
+//! | `POST /api/chat/stream`        | the reply as server-sent `chunk` events, word by word       |
+//! | `GET /ws`                      | the action channel: pushes `action_request`, records replies |
+//!
+//! Everything except pairing and refresh requires the session cookie. Every
+//! request is recorded as `"METHOD /path"` for assertions.
+//!
+//! Run it standalone with `forgeai-companion mock-gateway [port]` and pair
+//! the companion with the printed URL and code.
+
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, Request, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::{SinkExt, StreamExt};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::{broadcast, Notify};
+
+/// The one pairing code the mock accepts
+pub const PAIRING_CODE: &str = "123456";
+/// Session token handed out on pairing
+pub const AUTH_TOKEN: &str = "mock-token";
+pub const COMPANION_ID: &str = "mock-companion";
+
+struct MockState {
+    transcript: Mutex<String>,
+    reply: Mutex<String>,
+    requests: Mutex<Vec<String>>,
+    /// Messages for the connected companion(s)
+    to_companion: broadcast::Sender<String>,
+    /// Messages the companion sent on the action channel
+    from_companion: Mutex<Vec<Value>>,
+    received: Notify,
+}
+
+type Shared = Arc<MockState>;
+
+/// A running mock Gateway; stops when dropped
+#[cfg_attr(not(test), allow(dead_code))]
+pub struct MockGateway {
+    pub url: String,
+    state: Shared,
+    server: tokio::task::JoinHandle<()>,
+}
+
+fn authorized(headers: &HeaderMap) -> bool {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .any(|v| v.split(';').any(|c| c.trim() == format!("forgeai_session={}", AUTH_TOKEN)))
+}
+
+/// Record the request and check the session cookie
+async fn gate(State(state): State<Shared>, request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    if let Ok(mut requests) = state.requests.lock() {
+        requests.push(format!("{} {}", request.method(), path));
+    }
+    let open = matches!(path.as_str(), "/health" | "/ws" | "/api/companion/pair" | "/api/companion/refresh");
+    if !open && !authorized(request.headers()) {
+        return (StatusCode::UNAUTHORIZED, Json(json!({ "error": "Invalid session" }))).into_response();
+    }
+    next.run(request).await
+}
+
+async fn health() -> Json<Value> {
+    Json(json!({ "status": "ok", "mock": true }))
+}
+
+async fn pair(Json(body): Json<Value>) -> Json<Value> {
+    if body["code"].as_str() != Some(PAIRING_CODE) {
+        return Json(json!({ "success": false, "message": "Invalid or expired pairing code" }));
+    }
+    Json(json!({
+        "success": true,
+        "companionId": COMPANION_ID,
+        "role": "user",
+        "authToken": AUTH_TOKEN,
+        "refreshToken": "mock-refresh",
+    }))
+}
+
+async fn refresh() -> Json<Value> {
+    Json(json!({ "authToken": AUTH_TOKEN, "refreshToken": "mock-refresh" }))
+}
+
+async fn me() -> Json<Value> {
+    Json(json!({ "companionId": COMPANION_ID, "role": "user" }))
+}
+
+async fn transcribe(State(state): State<Shared>) -> Json<Value> {
+    let text = state.transcript.lock().map(|t| t.clone()).unwrap_or_default();
+    Json(json!({ "text": text, "language": "en" }))
+}
+
+async fn translate(Json(body): Json<Value>) -> Response {
+    let (Some(text), Some(target)) = (body["text"].as_str(), body["targetLang"].as_str()) else {
+        return (StatusCode::BAD_REQUEST, "text and targetLang are required").into_response();
+    };
+    let source = body["sourceLang"].as_str().unwrap_or("en");
+    Json(json!({ "text": format!("[{}] {}", target, text), "sourceLang": source })).into_response()
+}
+
+/// 200 ms of a 440 Hz tone as 16 kHz mono WAV
+fn tone() -> Vec<u8> {
+    let spec = hound::WavSpec { channels: 1, sample_rate: 16000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+    let mut wav = std::io::Cursor::new(Vec::new());
+    if let Ok(mut writer) = hound::WavWriter::new(&mut wav, spec) {
+        for i in 0..3200 {
+            let t = i as f32 / 16000.0;
+            let _ = writer.write_sample(((t * 440.0 * std::f32::consts::TAU).sin() * 8000.0) as i16);
+        }
+        let _ = writer.finalize();
+    }
+    wav.into_inner()
+}
+
+async fn synthesize(Json(body): Json<Value>) -> Response {
+    if body["text"].as_str().is_none_or(str::is_empty) {
+        return (StatusCode::BAD_REQUEST, "text is required").into_response();
+    }
+    ([(header::CONTENT_TYPE, "audio/wav")], tone()).into_response()
+}
+
+async fn voices() -> Json<Value> {
+    Json(json!({ "voices": [
+        { "id": "aria", "name": "Aria", "language": "en-US", "sampleUrl": "/api/voice/samples/aria" },
+        { "id": "tiago", "name": "Tiago", "language": "pt-BR" },
+    ] }))
+}
+
+async fn sample() -> Response {
+    ([(header::CONTENT_TYPE, "audio/wav")], tone()).into_response()
+}
+
+async fn chat_voice(State(state): State<Shared>) -> Json<Value> {
+    let transcription = state.transcript.lock().map(|t| t.clone()).unwrap_or_default();
+    let content = state.reply.lock().map(|r| r.clone()).unwrap_or_default();
+    Json(json!({ "transcription": transcription, "content": content, "timings": { "sttMs": 5 } }))
+}
+
+async fn chat_stream(State(state): State<Shared>) -> Response {
+    let content = state.reply.lock().map(|r| r.clone()).unwrap_or_default();
+    let mut events: String = content
+        .split_inclusive(' ')
+        .map(|word| format!("data: {}\n\n", json!({ "type": "chunk", "content": word })))
+        .collect();
+    events.push_str(&format!("data: {}\n\n", json!({ "type": "done", "sessionId": "sess_mock" })));
+    ([(header::CONTENT_TYPE, "text/event-stream")], events).into_response()
+}
+
+async fn action_channel(
+    State(state): State<Shared>,
+    Query(query): Query<HashMap<String, String>>,
+    upgrade: WebSocketUpgrade,
+) -> Response {
+    if query.get("token").map(String::as_str) != Some(AUTH_TOKEN) {
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+    upgrade.on_upgrade(move |socket| channel(state, socket))
+}
+
+async fn channel(state: Shared, socket: WebSocket) {
+    let (mut sink, mut stream) = socket.split();
+    let mut outgoing = state.to_companion.subscribe();
+    loop {
+        tokio::select! {
+            message = outgoing.recv() => match message {
+                Ok(text) => {
+                    if sink.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(_)) => {}
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+            incoming = stream.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    let Ok(value) = serde_json::from_str::<Value>(&text) else {
+                        continue;
+                    };
+                    if value["type"] == "health.ping" {
+                        let _ = sink.send(Message::Text(json!({ "type": "health.pong" }).to_string())).await;
+                        continue;
+                    }
+                    if let Ok(mut received) = state.from_companion.lock() {
+                        received.push(value);
+                    }
+                    state.received.notify_waiters();
+                }
+                Some(Ok(_)) => {}
+                _ => break,
+            },
+        }
+    }
+}
+
+// The inspection helpers are for tests; the binary only serves
+#[cfg_attr(not(test), allow(dead_code))]
+impl MockGateway {
+    /// Start on a free port on 127.0.0.1
+    pub async fn start() -> Result<Self, String> {
+        Self::bind(0).await
+    }
+
+    /// Start on `port` of 127.0.0.1 (0 for any free port)
+    pub async fn bind(port: u16) -> Result<Self, String> {
+        let listener = tokio::net::TcpListener::bind(("127.0.0.1", port))
+            .await
+            .map_err(|e| format!("Cannot bind mock Gateway: {}", e))?;
+        let addr = listener.local_addr().map_err(|e| e.to_string())?;
+        let state = Arc::new(MockState {
+            transcript: Mutex::new("what time is it".into()),
+            reply: Mutex::new("It is mock o'clock.".into()),
+            requests: Mutex::new(Vec::new()),
+            to_companion: broadcast::channel(64).0,
+            from_companion: Mutex::new(Vec::new()),
+            received: Notify::new(),
+        });
+        let app = Router::new()
+            .route("/health", get(health))
+            .route("/api/companion/pair", post(pair))
+            .route("/api/companion/refresh", post(refresh))
+            .route("/api/companion/me", get(me))
+            .route("/api/voice/transcribe", post(transcribe))
+            .route("/api/voice/synthesize", post(synthesize))
+            .route("/api/voice/translate", post(translate))
+            .route("/api/voice/voices", get(voices))
+            .route("/api/voice/samples/:id", get(sample))
+            .route("/api/chat/voice", post(chat_voice))
+            .route("/api/chat/stream", post(chat_stream))
+            .route("/ws", get(action_channel))
+            .layer(axum::middleware::from_fn_with_state(state.clone(), gate))
+            .with_state(state.clone());
+        let server = tokio::spawn(async move {
+            if let Err(e) = axum::serve(listener, app).await {
+                tracing::warn!("[MockGateway] Server stopped: {}", e);
+            }
+        });
+        tracing::info!("[MockGateway] Listening on http://{}", addr);
+        Ok(MockGateway { url: format!("http://{}", addr), state, server })
+    }
+
+    /// What `/api/voice/transcribe` and `/api/chat/voice` hear
+    pub fn set_transcript(&self, text: &str) {
+        if let Ok(mut transcript) = self.state.transcript.lock() {
+            *transcript = text.to_string();
+        }
+    }
+
+    /// What `/api/chat/voice` and `/api/chat/stream` answer
+    pub fn set_reply(&self, text: &str) {
+        if let Ok(mut reply) = self.state.reply.lock() {
+            *reply = text.to_string();
+        }
+    }
+
+    /// Requests served so far, as `"METHOD /path"`
+    pub fn requests(&self) -> Vec<String> {
+        self.state.requests.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    /// Credentials of a companion paired with this mock
+    pub fn credentials(&self) -> crate::connection::CompanionCredentials {
+        crate::connection::CompanionCredentials {
+            gateway_url: self.url.clone(),
+            companion_id: COMPANION_ID.into(),
+            role: "user".into(),
+            auth_token: Some(AUTH_TOKEN.into()),
+            refresh_token: Some("mock-refresh".into()),
+            e2e_key: None,
+            lan_url: None,
+            remote_url: None,
+            stored_gateway_url: None,
+        }
+    }
+
+    /// Push an `action_request` to connected companions; returns its id
+    pub fn push_action(&self, action: &str, params: Value) -> String {
+        let request_id = format!("mock-{}", chrono::Utc::now().timestamp_micros());
+        let message = json!({ "type": "action_request", "requestId": request_id, "action": action, "params": params });
+        let _ = self.state.to_companion.send(message.to_string());
+        request_id
+    }
+
+    /// Wait for a message of `msg_type` from the companion (removing it)
+    pub async fn next_message(&self, msg_type: &str, timeout: Duration) -> Option<Value> {
+        let take = || {
+            let mut received = self.state.from_companion.lock().ok()?;
+            let index = received.iter().position(|m| m["type"] == msg_type)?;
+            Some(received.remove(index))
+        };
+        tokio::time::timeout(timeout, async {
+            loop {
+                let notified = self.state.received.notified();
+                if let Some(message) = take() {
+                    return message;
+                }
+                notified.await;
+            }
+        })
+        .await
+        .ok()
+    }
+}
+
+impl Drop for MockGateway {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Serve until Ctrl+C (`forgeai-companion mock-gateway [port]`)
+pub fn run(port: u16) -> i32 {
+    tauri::async_runtime::block_on(async move {
+        let mock = match MockGateway::bind(port).await {
+            Ok(mock) => mock,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return 1;
+            }
+        };
+        println!("Mock Gateway on {} — pairing code {} — Ctrl+C to stop", mock.url, PAIRING_CODE);
+        let _ = tokio::signal::ctrl_c().await;
+        0
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use base64::Engine as _;
+
+    #[tokio::test]
+    async fn test_mock_gateway_round_trips() {
+        let mock = MockGateway::start().await.unwrap();
+        let client = reqwest::Client::new();
+
+        // Pairing
+        let paired: Value = client
+            .post(format!("{}/api/companion/pair", mock.url))
+            .json(&json!({ "code": PAIRING_CODE }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(paired["authToken"], AUTH_TOKEN);
+        let refused: Value = client
+            .post(format!("{}/api/companion/pair", mock.url))
+            .json(&json!({ "code": "000000" }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(refused["success"], false);
+        let readiness = crate::setup::after_pairing(&mock.credentials()).await;
+        assert!(readiness.ready, "{:?}", readiness.checks);
+        assert!(readiness.checks.iter().all(|c| c.state == crate::setup::CheckState::Passed));
+
+        // Transcription through the real voice client, with the session cookie
+        mock.set_transcript("turn on the lights");
+        let audio = crate::voice::CapturedAudio {
+            duration_ms: 200,
+            sample_rate: 16000,
+            samples: 3200,
+            wav_base64: base64::engine::general_purpose::STANDARD.encode(tone()),
+        };
+        let text = crate::voice::VoiceEngine::new().transcribe(&mock.credentials(), &audio).await.unwrap();
+        assert_eq!(text, "turn on the lights");
+        let (translated, source) = crate::translate::translate(&mock.credentials(), &text, None, "pt").await.unwrap();
+        assert_eq!((translated.as_str(), source.as_deref()), ("[pt] turn on the lights", Some("en")));
+        let voices = crate::voice::list_tts_voices(&mock.credentials()).await.unwrap();
+        assert_eq!(voices.len(), 2);
+        assert_eq!(voices[0].sample_url.as_deref(), Some("/api/voice/samples/aria"));
+        assert_eq!(voices[1].language.as_deref(), Some("pt-BR"));
+        let anonymous = client.post(format!("{}/api/voice/synthesize", mock.url)).json(&json!({ "text": "hi" }));
+        assert_eq!(anonymous.send().await.unwrap().status(), StatusCode::UNAUTHORIZED);
+        mock.set_reply("Lights are on.");
+        let voice = client
+            .post(format!("{}/api/chat/voice", mock.url))
+            .header(header::COOKIE, format!("forgeai_session={}", AUTH_TOKEN))
+            .json(&json!({ "audio": audio.wav_base64, "format": "wav" }));
+        let turn: Value = voice.send().await.unwrap().json().await.unwrap();
+        assert_eq!(turn["content"], "Lights are on.");
+        assert_eq!(turn["transcription"], "turn on the lights");
+        let streamed = crate::speech_stream::reply(&mock.credentials(), &text, None, None).await.unwrap();
+        assert_eq!((streamed.content.as_str(), streamed.session_id.as_deref()), ("Lights are on.", Some("sess_mock")));
+
+        // Action channel
+        let ws_url = format!("{}/ws?companionId={}&token={}", mock.url.replace("http", "ws"), COMPANION_ID, AUTH_TOKEN);
+        let (mut ws, _) = tokio_tungstenite::connect_async(ws_url).await.unwrap();
+        let request_id = mock.push_action("system_info", json!({}));
+        let pushed = loop {
+            match ws.next().await {
+                Some(Ok(tokio_tungstenite::tungstenite::Message::Text(text))) => {
+                    break serde_json::from_str::<Value>(&text).expect("pushed action is JSON");
+                }
+                Some(Ok(_)) => continue,
+                Some(Err(e)) => panic!("action channel failed: {}", e),
+                None => panic!("mock gateway closed the action channel"),
+            }
+        };
+        assert_eq!(pushed["requestId"], request_id.as_str());
+        let result = json!({ "type": "action_result", "requestId": request_id, "success": true, "output": "ok" });
+        if let Err(e) = ws.send(tokio_tungstenite::tungstenite::Message::Text(result.to_string())).await {
+            panic!("could not send the action result: {}", e);
+        }
+        let reply = mock.next_message("action_result", Duration::from_secs(5)).await.unwrap();
+        assert_eq!(reply["output"], "ok");
+
+        let requests = mock.requests();
+        assert!(requests.contains(&"POST /api/voice/transcribe".to_string()));
+        assert!(requests.contains(&"GET /ws".to_string()));
+    }
+}
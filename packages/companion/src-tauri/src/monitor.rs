@@ -0,0 +1,143 @@
+//! # Input Monitoring
+//!
+//! Lets podcasters and headset users hear themselves: with
+//! `monitor.enabled`, every recording also plays the microphone on the
+//! selected output device while it captures, at `monitor.volume`. Hearing
+//! the voice come back confirms the right microphone is active.
+//!
+//! The pass-through is kept short: audio waiting for the speakers is
+//! capped at [`MAX_LATENCY_MS`] and older samples are dropped rather than
+//! played late. Volume changes apply to the recording in progress. Use
+//! headphones; on open speakers the monitor feeds back into the mic.
+
+use cpal::traits::{DeviceTrait, StreamTrait};
+use forgeai_companion_core::audio;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+/// Most audio queued between the microphone and the speakers
+const MAX_LATENCY_MS: usize = 60;
+
+static CONFIG: Mutex<Option<MonitorConfig>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MonitorConfig {
+    pub enabled: bool,
+    /// 0.0 (silent) – 2.0 (twice as loud as captured)
+    pub volume: f32,
+}
+
+impl Default for MonitorConfig {
+    fn default() -> Self {
+        Self { enabled: false, volume: 0.5 }
+    }
+}
+
+impl MonitorConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.0..=2.0).contains(&self.volume) {
+            return Err("Monitor volume must be between 0 and 2".into());
+        }
+        Ok(())
+    }
+}
+
+fn config() -> MonitorConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+pub fn set_config(config: &MonitorConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+/// Append `samples` at `volume`, keeping at most `max` samples queued
+fn queue(buffer: &mut VecDeque<f32>, samples: &[f32], volume: f32, max: usize) {
+    buffer.extend(samples.iter().map(|s| (s * volume).clamp(-1.0, 1.0)));
+    let excess = buffer.len().saturating_sub(max);
+    buffer.drain(..excess);
+}
+
+/// Microphone pass-through for one recording; stops when dropped
+pub struct Monitor {
+    pending: Arc<Mutex<VecDeque<f32>>>,
+    rate: u32,
+    _stream: cpal::Stream,
+}
+
+impl Monitor {
+    /// Open the output device when monitoring is on. None when it is off
+    /// or the device cannot be opened (the recording goes on without it).
+    pub fn start() -> Option<Monitor> {
+        if !config().enabled {
+            return None;
+        }
+        match Self::open() {
+            Ok(monitor) => Some(monitor),
+            Err(e) => {
+                tracing::warn!("[Monitor] Not monitoring this recording: {}", e);
+                None
+            }
+        }
+    }
+
+    fn open() -> Result<Monitor, String> {
+        let device = crate::voice::output_device().ok_or("No audio output device")?;
+        let supported = device.default_output_config().map_err(|e| format!("No supported output config: {}", e))?;
+        let (rate, channels) = (supported.sample_rate().0, supported.channels() as usize);
+        let pending = Arc::new(Mutex::new(VecDeque::new()));
+        let feed = pending.clone();
+        let stream = device
+            .build_output_stream(
+                &supported.into(),
+                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                    let Ok(mut pending) = feed.lock() else {
+                        data.fill(0.0);
+                        return;
+                    };
+                    for frame in data.chunks_mut(channels) {
+                        frame.fill(pending.pop_front().unwrap_or(0.0));
+                    }
+                },
+                |err| tracing::error!("[Monitor] Playback error: {}", err),
+                None,
+            )
+            .map_err(|e| format!("Failed to build output stream: {}", e))?;
+        stream.play().map_err(|e| format!("Failed to start monitoring: {}", e))?;
+        tracing::info!("[Monitor] Monitoring the microphone at {} Hz", rate);
+        Ok(Monitor { pending, rate, _stream: stream })
+    }
+
+    /// Play mono microphone `samples` captured at `rate`
+    pub fn push(&self, samples: &[f32], rate: u32) {
+        let resampled = audio::resample(samples, rate, self.rate);
+        let max = self.rate as usize * MAX_LATENCY_MS / 1000;
+        if let Ok(mut pending) = self.pending.lock() {
+            queue(&mut pending, &resampled, config().volume, max);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queue_volume_and_latency() {
+        let mut buffer = VecDeque::new();
+        queue(&mut buffer, &[0.2, -0.4, 0.8], 1.5, 10);
+        let played: Vec<f32> = buffer.iter().copied().collect();
+        assert!((played[0] - 0.3).abs() < 1e-6 && (played[1] + 0.6).abs() < 1e-6);
+        assert_eq!(played[2], 1.0);
+
+        // Only the newest audio is kept when the speakers fall behind
+        queue(&mut buffer, &[0.1; 9], 1.0, 10);
+        assert_eq!(buffer.len(), 10);
+        assert_eq!(buffer[0], 1.0);
+
+        assert!(MonitorConfig { volume: 3.0, ..Default::default() }.validate().is_err());
+    }
+}
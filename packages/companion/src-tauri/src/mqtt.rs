@@ -0,0 +1,390 @@
+//! # MQTT
+//!
+//! Optional smart-home control through the user's own MQTT broker (`mqtt`
+//! in the settings). With it enabled, the Gateway can push `mqtt_publish`
+//! ("turn off the office lights" becomes `OFF` on
+//! `home/office/light/set`) and `mqtt_subscribe` (a sensor reading, a
+//! device's current state) actions, which run here against the broker, so
+//! it never has to be reachable from the Gateway.
+//!
+//! Both go through the safety layer: only topics under `allowedTopics` can
+//! be used, wildcard topics cannot be published to, and publishing to
+//! locks, alarms, doors and the like needs confirmation. Each action opens
+//! a short MQTT 3.1.1 session over TCP or TLS. The broker password stays
+//! in its own encrypted file of the user profile (`set_mqtt_password`).
+
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// Longest `mqtt_subscribe` waits for messages
+pub const MAX_WAIT: Duration = Duration::from_secs(30);
+/// Quiet time after the last message that ends a subscription early
+const SETTLE: Duration = Duration::from_millis(500);
+const MAX_MESSAGES: usize = 50;
+const KEEP_ALIVE_SECS: u16 = 60;
+
+static CONFIG: Mutex<Option<MqttConfig>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct MqttConfig {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub username: Option<String>,
+    /// `forgeai-companion-<pid>` when empty
+    pub client_id: String,
+    /// Topic filters actions may use, e.g. `home/+/light/#`
+    pub allowed_topics: Vec<String>,
+    /// QoS of publishes, 0 or 1
+    pub qos: u8,
+}
+
+impl Default for MqttConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 1883,
+            tls: false,
+            username: None,
+            client_id: String::new(),
+            allowed_topics: Vec::new(),
+            qos: 1,
+        }
+    }
+}
+
+impl MqttConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.qos > 1 {
+            return Err("MQTT QoS must be 0 or 1".into());
+        }
+        for filter in &self.allowed_topics {
+            let levels: Vec<&str> = filter.split('/').collect();
+            let misplaced = levels.iter().enumerate().any(|(i, level)| {
+                (level.contains('#') && (*level != "#" || i + 1 != levels.len())) || (level.contains('+') && *level != "+")
+            });
+            if filter.is_empty() || misplaced {
+                return Err(format!("Invalid MQTT topic filter '{}'", filter));
+            }
+        }
+        if self.enabled && (self.host.trim().is_empty() || self.port == 0) {
+            return Err("MQTT needs a broker host and port".into());
+        }
+        Ok(())
+    }
+}
+
+/// Use `config` from now on
+pub fn set_config(config: &MqttConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+fn config() -> MqttConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+/// Whether the MQTT actions are offered
+pub fn enabled() -> bool {
+    config().enabled
+}
+
+/// Topic filters the actions are limited to
+pub fn allowed_topics() -> Vec<String> {
+    config().allowed_topics
+}
+
+// ─── Password ───────────────────────────────────────
+
+fn password_path() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("mqtt.enc"))
+}
+
+pub fn password() -> Option<String> {
+    let path = password_path().filter(|p| p.exists())?;
+    match crate::secure_store::read_encrypted(&path) {
+        Ok(bytes) => String::from_utf8(bytes).ok(),
+        Err(e) => {
+            tracing::warn!("[MQTT] Stored password unreadable: {}", e);
+            None
+        }
+    }
+}
+
+/// Store the broker password encrypted, or forget it
+pub fn set_password(password: Option<&str>) -> Result<(), String> {
+    let path = password_path().ok_or("Cannot determine data directory")?;
+    match password.filter(|p| !p.is_empty()) {
+        Some(p) => crate::secure_store::write_encrypted(&path, p.as_bytes()),
+        None if path.exists() => std::fs::remove_file(&path).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+// ─── Packets ────────────────────────────────────────
+
+mod packet {
+    pub const CONNACK: u8 = 0x20;
+    pub const PUBLISH: u8 = 0x30;
+    pub const PUBACK: u8 = 0x40;
+    pub const SUBACK: u8 = 0x90;
+
+    fn string(out: &mut Vec<u8>, s: &[u8]) {
+        out.extend_from_slice(&(s.len() as u16).to_be_bytes());
+        out.extend_from_slice(s);
+    }
+
+    /// Fixed header byte, remaining length and body
+    fn frame(header: u8, body: Vec<u8>) -> Vec<u8> {
+        let mut out = vec![header];
+        let mut len = body.len();
+        loop {
+            let mut byte = (len % 128) as u8;
+            len /= 128;
+            if len > 0 {
+                byte |= 0x80;
+            }
+            out.push(byte);
+            if len == 0 {
+                break;
+            }
+        }
+        out.extend(body);
+        out
+    }
+
+    pub fn connect(client_id: &str, username: Option<&str>, password: Option<&str>, keep_alive: u16) -> Vec<u8> {
+        let mut body = Vec::new();
+        string(&mut body, b"MQTT");
+        body.push(4); // protocol level 3.1.1
+        let mut flags = 0x02; // clean session
+        if username.is_some() {
+            flags |= 0x80;
+            if password.is_some() {
+                flags |= 0x40;
+            }
+        }
+        body.push(flags);
+        body.extend_from_slice(&keep_alive.to_be_bytes());
+        string(&mut body, client_id.as_bytes());
+        if let Some(user) = username {
+            string(&mut body, user.as_bytes());
+            if let Some(pass) = password {
+                string(&mut body, pass.as_bytes());
+            }
+        }
+        frame(0x10, body)
+    }
+
+    pub fn publish(topic: &str, payload: &[u8], qos: u8, retain: bool, packet_id: u16) -> Vec<u8> {
+        let mut body = Vec::new();
+        string(&mut body, topic.as_bytes());
+        if qos > 0 {
+            body.extend_from_slice(&packet_id.to_be_bytes());
+        }
+        body.extend_from_slice(payload);
+        frame(PUBLISH | (qos << 1) | retain as u8, body)
+    }
+
+    pub fn subscribe(filter: &str, packet_id: u16) -> Vec<u8> {
+        let mut body = packet_id.to_be_bytes().to_vec();
+        string(&mut body, filter.as_bytes());
+        body.push(0); // QoS 0
+        frame(0x82, body)
+    }
+
+    pub fn disconnect() -> Vec<u8> {
+        vec![0xE0, 0x00]
+    }
+
+    /// Topic and payload of an incoming PUBLISH body
+    pub fn parse_publish(header: u8, body: &[u8]) -> Option<(String, Vec<u8>)> {
+        let len = u16::from_be_bytes([*body.first()?, *body.get(1)?]) as usize;
+        let topic = String::from_utf8(body.get(2..2 + len)?.to_vec()).ok()?;
+        let qos = (header >> 1) & 0x03;
+        let start = 2 + len + if qos > 0 { 2 } else { 0 };
+        Some((topic, body.get(start..)?.to_vec()))
+    }
+}
+
+// ─── Session ────────────────────────────────────────
+
+trait Transport: Read + Write + Send {}
+impl<T: Read + Write + Send> Transport for T {}
+
+struct Session {
+    stream: Box<dyn Transport>,
+    tcp: TcpStream,
+}
+
+impl Session {
+    fn open(config: &MqttConfig) -> Result<Self, String> {
+        if !config.enabled {
+            return Err("MQTT is not enabled".into());
+        }
+        let addr = (config.host.as_str(), config.port)
+            .to_socket_addrs()
+            .map_err(|e| format!("Cannot resolve {}: {}", config.host, e))?
+            .next()
+            .ok_or_else(|| format!("Cannot resolve {}", config.host))?;
+        let tcp = TcpStream::connect_timeout(&addr, CONNECT_TIMEOUT)
+            .map_err(|e| format!("Broker {}:{} unreachable: {}", config.host, config.port, e))?;
+        tcp.set_read_timeout(Some(CONNECT_TIMEOUT)).map_err(|e| e.to_string())?;
+        let handle = tcp.try_clone().map_err(|e| e.to_string())?;
+        let stream: Box<dyn Transport> = if config.tls {
+            let connector = native_tls::TlsConnector::new().map_err(|e| format!("TLS error: {}", e))?;
+            Box::new(connector.connect(&config.host, tcp).map_err(|e| format!("TLS handshake failed: {}", e))?)
+        } else {
+            Box::new(tcp)
+        };
+        let mut session = Session { stream, tcp: handle };
+
+        let client_id = match config.client_id.trim() {
+            "" => format!("forgeai-companion-{}", std::process::id()),
+            id => id.to_string(),
+        };
+        let password = password();
+        let username = config.username.as_deref().filter(|u| !u.is_empty());
+        session.send(&packet::connect(&client_id, username, password.as_deref(), KEEP_ALIVE_SECS))?;
+        let (header, body) = session.read()?;
+        if header & 0xF0 != packet::CONNACK || body.len() < 2 {
+            return Err("Broker did not acknowledge the connection".into());
+        }
+        match body[1] {
+            0 => Ok(session),
+            4 | 5 => Err("Broker refused the username or password".into()),
+            code => Err(format!("Broker refused the connection (code {})", code)),
+        }
+    }
+
+    fn send(&mut self, bytes: &[u8]) -> Result<(), String> {
+        self.stream.write_all(bytes).and_then(|_| self.stream.flush()).map_err(|e| format!("MQTT write failed: {}", e))
+    }
+
+    /// Next packet's header byte and body
+    fn read(&mut self) -> Result<(u8, Vec<u8>), String> {
+        let mut byte = [0u8; 1];
+        let mut next = |stream: &mut Box<dyn Transport>| -> Result<u8, String> {
+            stream.read_exact(&mut byte).map_err(|e| format!("MQTT read failed: {}", e))?;
+            Ok(byte[0])
+        };
+        let header = next(&mut self.stream)?;
+        let (mut len, mut shift) = (0usize, 0);
+        loop {
+            let b = next(&mut self.stream)?;
+            len |= ((b & 0x7F) as usize) << shift;
+            if b & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+            if shift > 21 {
+                return Err("Malformed MQTT packet".into());
+            }
+        }
+        let mut body = vec![0u8; len];
+        self.stream.read_exact(&mut body).map_err(|e| format!("MQTT read failed: {}", e))?;
+        Ok((header, body))
+    }
+
+    fn close(mut self) {
+        let _ = self.send(&packet::disconnect());
+    }
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// A message received by [`subscribe`]
+#[derive(Debug, Clone, Serialize)]
+pub struct Message {
+    pub topic: String,
+    pub payload: String,
+}
+
+/// Publish `payload` to `topic`. Blocking; safety checks are the caller's.
+pub fn publish(topic: &str, payload: &str, retain: bool) -> Result<(), String> {
+    let config = config();
+    let mut session = Session::open(&config)?;
+    session.send(&packet::publish(topic, payload.as_bytes(), config.qos, retain, 1))?;
+    if config.qos > 0 {
+        let (header, _) = session.read()?;
+        if header & 0xF0 != packet::PUBACK {
+            return Err("Broker did not acknowledge the message".into());
+        }
+    }
+    session.close();
+    tracing::info!("[MQTT] Published to {}", topic);
+    Ok(())
+}
+
+/// Messages on `filter` within `wait`, retained ones included. Returns
+/// shortly after the last message once at least one arrived. Blocking.
+pub fn subscribe(filter: &str, wait: Duration) -> Result<Vec<Message>, String> {
+    let mut session = Session::open(&config())?;
+    session.send(&packet::subscribe(filter, 1))?;
+    let deadline = Instant::now() + wait.min(MAX_WAIT);
+    let mut messages = Vec::new();
+    while messages.len() < MAX_MESSAGES {
+        let now = Instant::now();
+        let limit = if messages.is_empty() { deadline } else { deadline.min(now + SETTLE) };
+        if now >= limit {
+            break;
+        }
+        session.tcp.set_read_timeout(Some(limit - now)).map_err(|e| e.to_string())?;
+        let (header, body) = match session.read() {
+            Ok(packet) => packet,
+            Err(_) if Instant::now() >= limit => break,
+            Err(e) => return Err(e),
+        };
+        match header & 0xF0 {
+            packet::SUBACK if body.get(2) == Some(&0x80) => return Err(format!("Broker refused the subscription to {}", filter)),
+            packet::PUBLISH => {
+                if let Some((topic, payload)) = packet::parse_publish(header, &body) {
+                    messages.push(Message { topic, payload: String::from_utf8_lossy(&payload).into_owned() });
+                }
+            }
+            _ => {}
+        }
+    }
+    session.close();
+    Ok(messages)
+}
+
+/// Connect to the broker and hang up again
+pub fn test_connection() -> Result<(), String> {
+    let config = MqttConfig { enabled: true, ..config() };
+    Session::open(&config).map(Session::close)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mqtt_packets() {
+        let connect = packet::connect("fc", Some("u"), Some("p"), 60);
+        assert_eq!(connect, [0x10, 20, 0, 4, b'M', b'Q', b'T', b'T', 4, 0xC2, 0, 60, 0, 2, b'f', b'c', 0, 1, b'u', 0, 1, b'p']);
+
+        let publish = packet::publish("a/b", b"OFF", 1, true, 7);
+        assert_eq!(publish, [0x33, 10, 0, 3, b'a', b'/', b'b', 0, 7, b'O', b'F', b'F']);
+        assert_eq!(packet::parse_publish(publish[0], &publish[2..]), Some(("a/b".to_string(), b"OFF".to_vec())));
+
+        // Remaining length over 127 takes two bytes
+        let long = packet::publish("t", &[0u8; 200], 0, false, 0);
+        assert_eq!(&long[..3], [0x30, 0xCB, 0x01]);
+
+        let config = MqttConfig { enabled: true, host: "broker.lan".into(), allowed_topics: vec!["home/+/light/#".into()], ..Default::default() };
+        assert!(config.validate().is_ok());
+        assert!(MqttConfig { allowed_topics: vec!["home/#/light".into()], ..config.clone() }.validate().is_err());
+        assert!(MqttConfig { allowed_topics: vec!["home/a+".into()], ..config.clone() }.validate().is_err());
+        assert!(MqttConfig { host: " ".into(), ..config }.validate().is_err());
+    }
+}
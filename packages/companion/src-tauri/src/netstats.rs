@@ -0,0 +1,364 @@
+//! # Bandwidth & Request Statistics
+//!
+//! Counts requests and bytes per Gateway endpoint (plus the live channel)
+//! so users on metered connections can see what voice mode costs.
+//!
+//! Gateway HTTP calls go through [`send`]. Bodies are counted as they
+//! stream, without buffering: in-memory request bodies up front, streamed
+//! and multipart ones chunk by chunk, and responses *before* decompression —
+//! the shared Gateway clients leave gzip/zstd decoding to [`send`] so the
+//! counted size is what crossed the network. IDs in paths are collapsed to
+//! `:id` so `/api/chat/history/<uuid>` is counted as one endpoint.
+
+use bytes::Bytes;
+use http_body::Frame;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::{Mutex, OnceLock};
+use std::task::{Context, Poll};
+
+static STATS: OnceLock<Mutex<NetworkStats>> = OnceLock::new();
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EndpointStats {
+    pub requests: u64,
+    pub errors: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ChannelStats {
+    pub messages_sent: u64,
+    pub messages_received: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NetworkStats {
+    pub since: String,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub requests: u64,
+    pub endpoints: BTreeMap<String, EndpointStats>,
+    pub live_channel: ChannelStats,
+    pub compression: Option<crate::compression::CompressionStats>,
+}
+
+impl Default for NetworkStats {
+    fn default() -> Self {
+        Self {
+            since: chrono::Local::now().to_rfc3339(),
+            bytes_sent: 0,
+            bytes_received: 0,
+            requests: 0,
+            endpoints: BTreeMap::new(),
+            live_channel: ChannelStats::default(),
+            compression: None,
+        }
+    }
+}
+
+fn stats() -> &'static Mutex<NetworkStats> {
+    STATS.get_or_init(|| Mutex::new(NetworkStats::default()))
+}
+
+/// Collapse ID-like path segments (UUIDs, long hex/numeric ids) to `:id`
+fn normalize_path(path: &str) -> String {
+    path.split('/')
+        .map(|seg| {
+            let digits = seg.chars().filter(|c| c.is_ascii_digit()).count();
+            let id_like = (seg.len() >= 8 && digits >= 2 && !seg.contains('.'))
+                || (!seg.is_empty() && digits == seg.len());
+            if id_like { ":id" } else { seg }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn record(endpoint: &str, sent: u64, failed: bool) {
+    if let Ok(mut s) = stats().lock() {
+        s.requests += 1;
+        s.bytes_sent += sent;
+        let e = s.endpoints.entry(endpoint.to_string()).or_default();
+        e.requests += 1;
+        e.bytes_sent += sent;
+        if failed {
+            e.errors += 1;
+        }
+    }
+}
+
+/// Add body bytes streamed after the request was recorded
+fn add_bytes(endpoint: &str, sent: u64, received: u64) {
+    if let Ok(mut s) = stats().lock() {
+        s.bytes_sent += sent;
+        s.bytes_received += received;
+        let e = s.endpoints.entry(endpoint.to_string()).or_default();
+        e.bytes_sent += sent;
+        e.bytes_received += received;
+    }
+}
+
+/// Incremental decoder for a compressed response body
+enum Decoder {
+    Gzip(flate2::write::GzDecoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Decoder<'static, Vec<u8>>),
+}
+
+impl Decoder {
+    fn for_encoding(encoding: &str) -> Option<Self> {
+        match encoding.trim().to_ascii_lowercase().as_str() {
+            "gzip" | "x-gzip" => Some(Decoder::Gzip(flate2::write::GzDecoder::new(Vec::new()))),
+            "zstd" => zstd::stream::write::Decoder::new(Vec::new()).ok().map(Decoder::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Feed compressed bytes, returning whatever could be decoded so far
+    fn feed(&mut self, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        match self {
+            Decoder::Gzip(d) => {
+                d.write_all(data)?;
+                Ok(std::mem::take(d.get_mut()))
+            }
+            Decoder::Zstd(d) => {
+                d.write_all(data)?;
+                d.flush()?;
+                Ok(std::mem::take(d.get_mut()))
+            }
+        }
+    }
+
+    fn finish(self) -> std::io::Result<Vec<u8>> {
+        match self {
+            Decoder::Gzip(d) => d.finish(),
+            Decoder::Zstd(mut d) => {
+                d.flush()?;
+                Ok(d.into_inner())
+            }
+        }
+    }
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Body wrapper that counts bytes as they pass and optionally decodes them
+struct Metered {
+    inner: reqwest::Body,
+    endpoint: String,
+    outgoing: bool,
+    // Mutex only to make the body `Sync`, as reqwest requires
+    decoder: Mutex<Option<Decoder>>,
+    done: bool,
+}
+
+impl Metered {
+    fn wrap(inner: reqwest::Body, endpoint: &str, outgoing: bool, decoder: Option<Decoder>) -> reqwest::Body {
+        reqwest::Body::wrap(Metered {
+            inner,
+            endpoint: endpoint.to_string(),
+            outgoing,
+            decoder: Mutex::new(decoder),
+            done: false,
+        })
+    }
+
+    fn count(&self, bytes: usize) {
+        let bytes = bytes as u64;
+        if self.outgoing {
+            add_bytes(&self.endpoint, bytes, 0);
+        } else {
+            add_bytes(&self.endpoint, 0, bytes);
+        }
+    }
+}
+
+impl http_body::Body for Metered {
+    type Data = Bytes;
+    type Error = BoxError;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, BoxError>>> {
+        let this = self.get_mut();
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+            let frame = match Pin::new(&mut this.inner).poll_frame(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(frame) => frame,
+            };
+            let mut decoder = this.decoder.lock().map_err(|_| "Body decoder poisoned")?;
+            match frame {
+                Some(Ok(frame)) => {
+                    let data = match frame.into_data() {
+                        Ok(data) => data,
+                        // Trailers pass through untouched
+                        Err(frame) => return Poll::Ready(Some(Ok(frame))),
+                    };
+                    this.count(data.len());
+                    let Some(d) = decoder.as_mut() else {
+                        return Poll::Ready(Some(Ok(Frame::data(data))));
+                    };
+                    let decoded = d.feed(&data)?;
+                    if !decoded.is_empty() {
+                        return Poll::Ready(Some(Ok(Frame::data(decoded.into()))));
+                    }
+                }
+                Some(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
+                None => {
+                    this.done = true;
+                    if let Some(d) = decoder.take() {
+                        let rest = d.finish()?;
+                        if !rest.is_empty() {
+                            return Poll::Ready(Some(Ok(Frame::data(rest.into()))));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Count (and decode, if compressed) a response body as it is read, keeping
+/// the URL, headers and extensions of the original response
+fn meter_response(resp: reqwest::Response, endpoint: &str) -> reqwest::Response {
+    use reqwest::ResponseBuilderExt as _;
+    let url = resp.url().clone();
+    let decoder = resp
+        .headers()
+        .get(reqwest::header::CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .and_then(Decoder::for_encoding);
+
+    let (mut parts, body) = ::http::Response::<reqwest::Body>::from(resp).into_parts();
+    if decoder.is_some() {
+        // As reqwest does when it decodes: the body no longer matches these
+        parts.headers.remove(reqwest::header::CONTENT_ENCODING);
+        parts.headers.remove(reqwest::header::CONTENT_LENGTH);
+    }
+    if let Ok(with_url) = ::http::Response::builder().url(url).body(()) {
+        parts.extensions.extend(with_url.into_parts().0.extensions);
+    }
+    let body = Metered::wrap(body, endpoint, false, decoder);
+    reqwest::Response::from(::http::Response::from_parts(parts, body))
+}
+
+/// Send a Gateway request and record its traffic. The response is returned
+/// unbuffered; its body is counted (and decompressed) while it is read.
+pub async fn send(builder: reqwest::RequestBuilder) -> reqwest::Result<reqwest::Response> {
+    let (client, request) = builder.build_split();
+    let mut request = request?;
+    let endpoint = format!("{} {}", request.method(), normalize_path(request.url().path()));
+
+    let headers = request.headers_mut();
+    if !headers.contains_key(reqwest::header::ACCEPT_ENCODING) && !headers.contains_key(reqwest::header::RANGE) {
+        headers.insert(reqwest::header::ACCEPT_ENCODING, reqwest::header::HeaderValue::from_static("gzip, zstd"));
+    }
+    let sent = match request.body_mut().take() {
+        Some(body) => match body.as_bytes().map(|b| b.len() as u64) {
+            Some(len) => {
+                *request.body_mut() = Some(body);
+                len
+            }
+            // Streams and multipart forms are counted as they are sent
+            None => {
+                *request.body_mut() = Some(Metered::wrap(body, &endpoint, true, None));
+                0
+            }
+        },
+        None => 0,
+    };
+
+    match client.execute(request).await {
+        Ok(resp) => {
+            record(&endpoint, sent, !resp.status().is_success());
+            Ok(meter_response(resp, &endpoint))
+        }
+        Err(e) => {
+            record(&endpoint, sent, true);
+            Err(e)
+        }
+    }
+}
+
+/// Record a message on the live WebSocket channel
+pub fn record_channel(outgoing: bool, bytes: usize) {
+    if let Ok(mut s) = stats().lock() {
+        let c = &mut s.live_channel;
+        if outgoing {
+            c.messages_sent += 1;
+            c.bytes_sent += bytes as u64;
+        } else {
+            c.messages_received += 1;
+            c.bytes_received += bytes as u64;
+        }
+    }
+}
+
+/// Snapshot of all counters
+pub fn snapshot() -> NetworkStats {
+    let mut snap = stats().lock().map(|s| s.clone()).unwrap_or_default();
+    snap.compression = Some(crate::compression::stats());
+    snap
+}
+
+/// Reset all counters
+pub fn reset() {
+    if let Ok(mut s) = stats().lock() {
+        *s = NetworkStats::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("/api/chat/sessions"), "/api/chat/sessions");
+        assert_eq!(
+            normalize_path("/api/chat/history/3f2b9c1e-8a4d-4e2f-9b1a-0c6d5e7f8a9b"),
+            "/api/chat/history/:id"
+        );
+        assert_eq!(normalize_path("/api/jobs/42"), "/api/jobs/:id");
+        assert_eq!(normalize_path("/api/files/screenshots/shot.png"), "/api/files/screenshots/shot.png");
+    }
+
+    #[test]
+    fn test_response_counted_on_the_wire() {
+        use reqwest::ResponseBuilderExt as _;
+        let text = "hello gateway ".repeat(500);
+        let packed = crate::compression::compress(crate::compression::Codec::Zstd, text.as_bytes()).unwrap();
+        let url = url::Url::parse("https://gw.local/api/test").unwrap();
+        let raw = ::http::Response::builder()
+            .header("content-encoding", "zstd")
+            .header("content-length", packed.len())
+            .header("x-request-id", "abc")
+            .url(url.clone())
+            .body(packed.clone())
+            .unwrap();
+
+        let resp = meter_response(reqwest::Response::from(raw), "GET /api/test-wire");
+        assert_eq!(resp.url(), &url);
+        assert_eq!(resp.headers()["x-request-id"], "abc");
+        assert!(resp.headers().get("content-encoding").is_none());
+
+        let rt = tokio::runtime::Builder::new_current_thread().build().unwrap();
+        assert_eq!(rt.block_on(resp.text()).unwrap(), text);
+        let stats = snapshot();
+        assert_eq!(stats.endpoints["GET /api/test-wire"].bytes_received, packed.len() as u64);
+
+        let mut gzip = Decoder::for_encoding("gzip").unwrap();
+        let packed = crate::compression::compress(crate::compression::Codec::Gzip, text.as_bytes()).unwrap();
+        let (a, b) = packed.split_at(packed.len() / 2);
+        let mut out = gzip.feed(a).unwrap();
+        out.extend(gzip.feed(b).unwrap());
+        out.extend(gzip.finish().unwrap());
+        assert_eq!(out, text.as_bytes());
+        assert!(Decoder::for_encoding("br").is_none());
+    }
+}
@@ -0,0 +1,155 @@
+//! # Offline Degradation
+//!
+//! One place that knows what still works while the Gateway is unreachable,
+//! so the UI shows a single banner instead of each feature failing with its
+//! own error. The Gateway counts as unreachable when the heartbeat reports
+//! it `offline`, or when the last request to it failed to get through; the
+//! next request that does, or an online heartbeat, clears it.
+//!
+//! While offline each capability reports the mode it runs in:
+//!
+//! | Capability | Mode       | Meaning                                                      |
+//! |------------|------------|--------------------------------------------------------------|
+//! | `voice`    | `fallback` | local intents and shortcuts answer, other turns are queued   |
+//! | `stt`      | `queued`   | recordings are transcribed when the Gateway is back          |
+//! | `tts`      | `fallback` | replies are spoken by the OS speech synthesizer              |
+//! | `actions`  | `queued`   | results and audit events wait in the outbox                  |
+//!
+//! Every switch between online and offline is emitted as `offline-state`.
+
+use crate::heartbeat::LinkState;
+use serde::Serialize;
+use std::sync::Mutex;
+
+static TRACKER: Mutex<Tracker> = Mutex::new(Tracker { link: LinkState::Online, failure: None, since: None });
+
+struct Tracker {
+    link: LinkState,
+    /// Why the last Gateway request did not get through
+    failure: Option<String>,
+    /// When the Gateway became unreachable
+    since: Option<String>,
+}
+
+impl Tracker {
+    fn offline(&self) -> bool {
+        self.link == LinkState::Offline || self.failure.is_some()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Mode {
+    /// Works as usual
+    Available,
+    /// Served by a local substitute
+    Fallback,
+    /// Kept and completed once the Gateway is back
+    Queued,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Capability {
+    pub mode: Mode,
+    pub detail: Option<&'static str>,
+}
+
+/// Payload of `offline_state` and the `offline-state` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OfflineState {
+    pub offline: bool,
+    pub reason: Option<String>,
+    pub since: Option<String>,
+    pub voice: Capability,
+    pub stt: Capability,
+    pub tts: Capability,
+    pub actions: Capability,
+    /// Messages waiting in the outbox
+    pub queued_messages: usize,
+}
+
+fn capability(offline: bool, mode: Mode, detail: &'static str) -> Capability {
+    if offline {
+        Capability { mode, detail: Some(detail) }
+    } else {
+        Capability { mode: Mode::Available, detail: None }
+    }
+}
+
+fn snapshot(tracker: &Tracker) -> OfflineState {
+    let offline = tracker.offline();
+    let reason = match (&tracker.failure, tracker.link) {
+        (Some(failure), _) => Some(failure.clone()),
+        (None, LinkState::Offline) => crate::heartbeat::current().detail,
+        _ => None,
+    };
+    OfflineState {
+        offline,
+        reason: reason.filter(|_| offline),
+        since: tracker.since.clone().filter(|_| offline),
+        voice: capability(offline, Mode::Fallback, "Local intents and shortcuts answer; other requests are sent when the Gateway is back"),
+        stt: capability(offline, Mode::Queued, "Recordings are transcribed when the Gateway is back"),
+        tts: capability(offline, Mode::Fallback, "Spoken by the system speech synthesizer"),
+        actions: capability(offline, Mode::Queued, "Results are delivered when the Gateway is back"),
+        queued_messages: crate::outbox::pending(),
+    }
+}
+
+/// Apply `change` and announce a switch between online and offline
+fn update(change: impl FnOnce(&mut Tracker)) {
+    let state = {
+        let Ok(mut tracker) = TRACKER.lock() else { return };
+        let was_offline = tracker.offline();
+        change(&mut tracker);
+        if tracker.offline() == was_offline {
+            return;
+        }
+        tracker.since = tracker.offline().then(|| chrono::Utc::now().to_rfc3339());
+        snapshot(&tracker)
+    };
+    if state.offline {
+        tracing::warn!("[Offline] Gateway unreachable, degraded modes active: {}", state.reason.as_deref().unwrap_or("unknown"));
+    } else {
+        tracing::info!("[Offline] Gateway reachable again");
+    }
+    crate::events::emit("offline-state", state);
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// The heartbeat's view of the link
+pub fn set_link(link: LinkState) {
+    update(|t| {
+        t.link = link;
+        if link == LinkState::Online {
+            t.failure = None;
+        }
+    });
+}
+
+/// A Gateway request did not get through
+pub fn unreachable(error: &str) {
+    update(|t| t.failure = Some(error.to_string()));
+}
+
+/// A Gateway request got an answer
+pub fn reachable() {
+    update(|t| {
+        t.failure = None;
+        if t.link == LinkState::Offline {
+            t.link = LinkState::Degraded;
+        }
+    });
+}
+
+pub fn is_offline() -> bool {
+    TRACKER.lock().map(|t| t.offline()).unwrap_or(false)
+}
+
+pub fn current() -> OfflineState {
+    match TRACKER.lock() {
+        Ok(tracker) => snapshot(&tracker),
+        Err(e) => snapshot(&e.into_inner()),
+    }
+}
@@ -0,0 +1,252 @@
+//! # Offline Outbox
+//!
+//! Non-interactive messages for the Gateway must not be lost when the
+//! Gateway is unreachable or the channel drops right after a send:
+//!
+//! - `action_result`          — results of Gateway-pushed actions
+//! - `audit_event`            — blocked, approved and denied remote actions
+//! - `telemetry`              — periodic metrics snapshot (one pending at a time)
+//! - `transcription.deferred` — voice recordings made while offline
+//!
+//! They are queued on disk and flushed over the live channel once the
+//! Gateway's capability manifest arrives. Every item carries a dedup key:
+//! queueing a key that is already pending replaces the older entry instead
+//! of sending both. The key travels with the message as `outboxKey`.
+//!
+//! A Gateway advertising `outbox_ack` confirms delivery with
+//! `{"type": "outbox.ack", "keys": [...]}`; items stay queued (and are sent
+//! again after a reconnect) until acked. With older Gateways an item is
+//! dropped as soon as it is written to an open channel.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Maximum queued items — the oldest are dropped beyond this
+const MAX_ITEMS: usize = 500;
+/// Items older than this are discarded on flush
+const MAX_AGE_HOURS: i64 = 24;
+
+/// Serializes access to the queue file
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboxItem {
+    pub key: String,
+    pub message: serde_json::Value,
+    pub queued_at: String,
+    /// Written to the channel, waiting for the Gateway's ack
+    #[serde(default)]
+    pub sent: bool,
+}
+
+fn outbox_path() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("outbox.json"))
+}
+
+fn load() -> Vec<OutboxItem> {
+    outbox_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn store(items: &[OutboxItem]) -> Result<(), String> {
+    let path = outbox_path().ok_or("Cannot determine data directory")?;
+    if items.is_empty() {
+        let _ = std::fs::remove_file(&path);
+        return Ok(());
+    }
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let json = serde_json::to_string(items).map_err(|e| format!("Serialize error: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Outbox write error: {}", e))
+}
+
+/// Insert `item`, replacing any pending item with the same key and
+/// capping the queue at `MAX_ITEMS`
+fn push_dedup(items: &mut Vec<OutboxItem>, item: OutboxItem) {
+    items.retain(|i| i.key != item.key);
+    items.push(item);
+    if items.len() > MAX_ITEMS {
+        let excess = items.len() - MAX_ITEMS;
+        items.drain(..excess);
+    }
+}
+
+/// Drop acked items; returns how many were removed
+fn remove_acked(items: &mut Vec<OutboxItem>, keys: &[String]) -> usize {
+    let before = items.len();
+    items.retain(|i| !keys.contains(&i.key));
+    before - items.len()
+}
+
+/// `message` tagged with its dedup key
+fn with_key(key: &str, mut message: serde_json::Value) -> serde_json::Value {
+    if let Some(obj) = message.as_object_mut() {
+        obj.insert("outboxKey".into(), key.into());
+    }
+    message
+}
+
+/// Whether the Gateway acks outbox deliveries
+fn acks_supported() -> bool {
+    crate::capabilities::gateway_supports(crate::capabilities::feature::OUTBOX_ACK, false)
+}
+
+/// Queue a message for delivery once the Gateway is reachable
+pub fn enqueue(key: &str, message: serde_json::Value) {
+    let _guard = LOCK.lock();
+    let mut items = load();
+    push_dedup(
+        &mut items,
+        OutboxItem {
+            key: key.to_string(),
+            message: with_key(key, message),
+            queued_at: chrono::Utc::now().to_rfc3339(),
+            sent: false,
+        },
+    );
+    match store(&items) {
+        Ok(()) => tracing::info!("[Outbox] Queued '{}' ({} pending)", key, items.len()),
+        Err(e) => tracing::error!("[Outbox] Failed to queue '{}': {}", key, e),
+    }
+}
+
+/// Send over the live channel and keep it queued until the Gateway acks it
+/// (or just queue it if the Gateway is unreachable)
+pub fn send_or_queue(key: &str, message: serde_json::Value) {
+    let message = with_key(key, message);
+    let _guard = LOCK.lock();
+    let mut items = load();
+    let sent = crate::connection::send_live(message.to_string()).is_ok();
+    if sent && !acks_supported() {
+        // Nothing will confirm it — an open channel is as good as it gets
+        if remove_acked(&mut items, &[key.to_string()]) > 0 {
+            let _ = store(&items);
+        }
+        return;
+    }
+    push_dedup(
+        &mut items,
+        OutboxItem { key: key.to_string(), message, queued_at: chrono::Utc::now().to_rfc3339(), sent },
+    );
+    if let Err(e) = store(&items) {
+        tracing::error!("[Outbox] Failed to queue '{}': {}", key, e);
+    } else if !sent {
+        tracing::info!("[Outbox] Queued '{}' ({} pending)", key, items.len());
+    }
+}
+
+/// Number of items waiting for delivery or for an ack
+pub fn pending() -> usize {
+    let _guard = LOCK.lock();
+    load().len()
+}
+
+/// Number of pending items whose key starts with `prefix`
+pub fn pending_with_prefix(prefix: &str) -> usize {
+    let _guard = LOCK.lock();
+    load().iter().filter(|i| i.key.starts_with(prefix)).count()
+}
+
+/// Handle an `outbox.ack` message from the Gateway
+pub fn handle_gateway_ack(raw: &serde_json::Value) {
+    let keys: Vec<String> = raw["keys"]
+        .as_array()
+        .map(|a| a.iter().filter_map(|k| k.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+    let _guard = LOCK.lock();
+    let mut items = load();
+    let removed = remove_acked(&mut items, &keys);
+    if removed > 0 {
+        if let Err(e) = store(&items) {
+            tracing::error!("[Outbox] Failed to update queue: {}", e);
+        }
+        tracing::debug!("[Outbox] {} item(s) acked, {} pending", removed, items.len());
+    }
+}
+
+/// Deliver queued items over the live channel (called once the Gateway's
+/// manifest arrives). Unacked items from an earlier connection are sent
+/// again; items that cannot be sent stay queued.
+pub fn flush() {
+    let _guard = LOCK.lock();
+    let items = load();
+    if items.is_empty() {
+        return;
+    }
+
+    let acked = acks_supported();
+    let cutoff = chrono::Utc::now() - chrono::Duration::hours(MAX_AGE_HOURS);
+    let mut remaining = Vec::new();
+    let mut sent = 0;
+    for mut item in items {
+        let fresh = chrono::DateTime::parse_from_rfc3339(&item.queued_at)
+            .map(|t| t > cutoff)
+            .unwrap_or(false);
+        if !fresh {
+            continue;
+        }
+        item.sent = crate::connection::send_live(item.message.to_string()).is_ok();
+        if item.sent {
+            sent += 1;
+        }
+        if !item.sent || acked {
+            remaining.push(item);
+        }
+    }
+
+    if let Err(e) = store(&remaining) {
+        tracing::error!("[Outbox] Failed to update queue: {}", e);
+    }
+    tracing::info!("[Outbox] Flushed {} item(s), {} still pending", sent, remaining.len());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(key: &str, n: i64) -> OutboxItem {
+        OutboxItem {
+            key: key.into(),
+            message: serde_json::json!({ "n": n }),
+            queued_at: String::new(),
+            sent: false,
+        }
+    }
+
+    #[test]
+    fn test_dedup_replaces_older_entry() {
+        let mut items = Vec::new();
+        push_dedup(&mut items, item("a", 1));
+        push_dedup(&mut items, item("b", 2));
+        push_dedup(&mut items, item("a", 3));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].key, "b");
+        assert_eq!(items[1].message["n"], 3);
+    }
+
+    #[test]
+    fn test_queue_is_capped() {
+        let mut items = Vec::new();
+        for n in 0..(MAX_ITEMS as i64 + 10) {
+            push_dedup(&mut items, item(&n.to_string(), n));
+        }
+        assert_eq!(items.len(), MAX_ITEMS);
+        assert_eq!(items[0].key, "10");
+    }
+
+    #[test]
+    fn test_acked_items_are_removed() {
+        let mut items = vec![item("action_result:1", 1), item("telemetry", 2), item("audit:1:blocked", 3)];
+        assert_eq!(remove_acked(&mut items, &["action_result:1".into(), "unknown".into()]), 1);
+        assert_eq!(items.len(), 2);
+        assert_eq!(remove_acked(&mut items, &[]), 0);
+
+        let tagged = with_key("telemetry", serde_json::json!({ "type": "telemetry" }));
+        assert_eq!(tagged["outboxKey"], "telemetry");
+        assert_eq!(tagged["type"], "telemetry");
+    }
+}
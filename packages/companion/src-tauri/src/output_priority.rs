@@ -0,0 +1,133 @@
+//! # Output Device Priority
+//!
+//! An ordered list of preferred playback devices (`outputPriority` in the
+//! settings, e.g. headset before speakers). Playback goes to the first one
+//! that is connected; when none is, to `voice.outputDevice`, then the
+//! system default.
+//!
+//! Entries match a device by its full name or a part of it ("WH-1000XM4"
+//! matches "Headphones (WH-1000XM4 Stereo)"). A Bluetooth headset shows up
+//! as two endpoints, a stereo one and a hands-free one for calls that plays
+//! in telephone quality; the stereo one is picked when both match.
+//!
+//! The choice is made again whenever the device watcher (see `profiles`)
+//! sees a device connect or disconnect, which is how Bluetooth devices
+//! coming and going show up, and every change is emitted as
+//! `output-device-changed`.
+
+use serde::Serialize;
+use std::sync::Mutex;
+
+static PRIORITY: Mutex<Vec<String>> = Mutex::new(Vec::new());
+/// Highest-priority connected device, None when no listed one is connected
+static SELECTED: Mutex<Option<String>> = Mutex::new(None);
+
+/// Payload of `output_priority` and the `output-device-changed` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputSelection {
+    pub priority: Vec<String>,
+    pub selected: Option<String>,
+    pub connected: Vec<OutputDevice>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutputDevice {
+    pub name: String,
+    pub bluetooth: bool,
+    /// The low-quality call endpoint of a Bluetooth headset
+    pub hands_free: bool,
+}
+
+impl OutputDevice {
+    fn new(name: &str) -> Self {
+        let lower = name.to_lowercase();
+        let hands_free = ["hands-free", "handsfree", "hfp"].iter().any(|k| lower.contains(k));
+        let bluetooth = hands_free || ["bluetooth", "airpods", "a2dp"].iter().any(|k| lower.contains(k));
+        Self { name: name.to_string(), bluetooth, hands_free }
+    }
+}
+
+/// The first entry of `priority` with a connected device; among several
+/// devices matching one entry, an exact name, then a non-hands-free one
+fn pick(priority: &[String], connected: &[OutputDevice]) -> Option<String> {
+    priority.iter().find_map(|entry| {
+        let wanted = entry.trim().to_lowercase();
+        connected
+            .iter()
+            .filter(|d| !wanted.is_empty() && d.name.to_lowercase().contains(&wanted))
+            .min_by_key(|d| (d.name.to_lowercase() != wanted, d.hands_free))
+            .map(|d| d.name.clone())
+    })
+}
+
+fn priority() -> Vec<String> {
+    PRIORITY.lock().map(|p| p.clone()).unwrap_or_default()
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Use `priority` from now on and pick among the connected devices
+pub fn set_priority(priority: &[String]) {
+    if let Ok(mut current) = PRIORITY.lock() {
+        *current = priority.to_vec();
+    }
+    refresh();
+}
+
+/// The device playback should use, when a listed one is connected
+pub fn selected() -> Option<String> {
+    SELECTED.lock().ok().and_then(|s| s.clone())
+}
+
+pub fn current() -> OutputSelection {
+    let connected: Vec<OutputDevice> = crate::voice::list_output_devices().iter().map(|n| OutputDevice::new(n)).collect();
+    OutputSelection { priority: priority(), selected: selected(), connected }
+}
+
+/// Pick again among the connected devices. Blocking (enumerates devices).
+pub fn refresh() {
+    let state = current();
+    let selected = pick(&state.priority, &state.connected);
+    {
+        let Ok(mut current) = SELECTED.lock() else { return };
+        if *current == selected {
+            return;
+        }
+        *current = selected.clone();
+    }
+    match &selected {
+        Some(name) => tracing::info!("[Output] Playing on '{}'", name),
+        None => tracing::info!("[Output] No preferred output device connected"),
+    }
+    crate::events::emit("output-device-changed", OutputSelection { selected, ..state });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_pick() {
+        let connected: Vec<OutputDevice> = [
+            "Speakers (Realtek High Definition Audio)",
+            "Headset (WH-1000XM4 Hands-Free AG Audio)",
+            "Headphones (WH-1000XM4 Stereo)",
+        ]
+        .iter()
+        .map(|n| OutputDevice::new(n))
+        .collect();
+        let list = |entries: &[&str]| entries.iter().map(|e| e.to_string()).collect::<Vec<_>>();
+
+        assert!(connected[1].bluetooth && connected[1].hands_free && !connected[0].bluetooth);
+        // Stereo endpoint over hands-free
+        assert_eq!(pick(&list(&["wh-1000xm4", "Speakers"]), &connected).as_deref(), Some("Headphones (WH-1000XM4 Stereo)"));
+        // Disconnected entries are skipped
+        assert_eq!(pick(&list(&["AirPods", "speakers"]), &connected).as_deref(), Some("Speakers (Realtek High Definition Audio)"));
+        // An exact name wins, even the hands-free one
+        let exact = list(&["Headset (WH-1000XM4 Hands-Free AG Audio)"]);
+        assert_eq!(pick(&exact, &connected), Some(exact[0].clone()));
+        assert_eq!(pick(&list(&["HDMI", " "]), &connected), None);
+    }
+}
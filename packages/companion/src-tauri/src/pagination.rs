@@ -0,0 +1,176 @@
+//! # Result Pagination
+//!
+//! Large action outputs (directory trees, shell output, file contents) can
+//! exceed IPC/Gateway payload limits. Instead of truncating them, the first
+//! page is returned inline and the remainder is kept here, addressable by a
+//! continuation token until it is fetched or expires.
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Maximum number of bytes returned in a single page
+pub const PAGE_SIZE: usize = 30_000;
+
+/// How long a paged result stays available
+const RESULT_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// Maximum number of paged results kept at once (oldest evicted first)
+const MAX_STORED_RESULTS: usize = 32;
+
+static STORE: OnceLock<Mutex<HashMap<String, StoredResult>>> = OnceLock::new();
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+struct StoredResult {
+    text: String,
+    created: Instant,
+}
+
+/// A single page of a large result
+#[derive(Debug, Clone, Serialize)]
+pub struct ResultPage {
+    pub output: String,
+    pub offset: usize,
+    pub total_len: usize,
+    /// Token for the next page, `None` when this is the last page
+    pub continuation: Option<String>,
+}
+
+fn store() -> &'static Mutex<HashMap<String, StoredResult>> {
+    STORE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Largest char boundary in `text` that is <= `index`
+fn floor_boundary(text: &str, index: usize) -> usize {
+    if index >= text.len() {
+        return text.len();
+    }
+    let mut i = index;
+    while !text.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+fn make_token(id: &str, offset: usize) -> String {
+    format!("{}:{}", id, offset)
+}
+
+fn parse_token(token: &str) -> Option<(&str, usize)> {
+    let (id, offset) = token.rsplit_once(':')?;
+    Some((id, offset.parse().ok()?))
+}
+
+fn page_of(id: &str, text: &str, offset: usize) -> ResultPage {
+    let end = floor_boundary(text, offset + PAGE_SIZE);
+    // A single char wider than the page would stall pagination — include it whole
+    let end = if end <= offset {
+        text[offset..].chars().next().map_or(text.len(), |c| offset + c.len_utf8())
+    } else {
+        end
+    };
+    ResultPage {
+        output: text[offset..end].to_string(),
+        offset,
+        total_len: text.len(),
+        continuation: if end < text.len() { Some(make_token(id, end)) } else { None },
+    }
+}
+
+fn evict_expired(map: &mut HashMap<String, StoredResult>) {
+    map.retain(|_, r| r.created.elapsed() < RESULT_TTL);
+    while map.len() >= MAX_STORED_RESULTS {
+        let oldest = map
+            .iter()
+            .min_by_key(|(_, r)| r.created)
+            .map(|(k, _)| k.clone());
+        match oldest {
+            Some(k) => {
+                map.remove(&k);
+            }
+            None => break,
+        }
+    }
+}
+
+/// Split `text` into its first page. If it fits in one page it is returned
+/// unchanged with no continuation; otherwise the full text is stored and a
+/// continuation token for the next page is returned.
+pub fn paginate(text: String) -> ResultPage {
+    if text.len() <= PAGE_SIZE {
+        return ResultPage {
+            total_len: text.len(),
+            output: text,
+            offset: 0,
+            continuation: None,
+        };
+    }
+
+    let id = format!(
+        "r{}-{}",
+        chrono::Utc::now().timestamp_millis(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    let page = page_of(&id, &text, 0);
+
+    if let Ok(mut map) = store().lock() {
+        evict_expired(&mut map);
+        map.insert(id, StoredResult { text, created: Instant::now() });
+    }
+    page
+}
+
+/// Fetch the page addressed by a continuation token.
+/// The stored result is dropped once its last page has been served.
+pub fn fetch_page(token: &str) -> Result<ResultPage, String> {
+    let (id, offset) = parse_token(token).ok_or("Invalid continuation token")?;
+
+    let mut map = store().lock().map_err(|e| e.to_string())?;
+    evict_expired(&mut map);
+
+    let stored = map
+        .get(id)
+        .ok_or("Result expired or not found — re-run the action")?;
+    if offset > stored.text.len() || !stored.text.is_char_boundary(offset) {
+        return Err("Invalid continuation offset".into());
+    }
+
+    let page = page_of(id, &stored.text, offset);
+    if page.continuation.is_none() {
+        map.remove(id);
+    }
+    Ok(page)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_output_not_paged() {
+        let page = paginate("hello".into());
+        assert_eq!(page.output, "hello");
+        assert!(page.continuation.is_none());
+    }
+
+    #[test]
+    fn test_large_output_round_trip() {
+        let text = "é".repeat(PAGE_SIZE); // 2 bytes per char
+        let mut page = paginate(text.clone());
+        let mut collected = page.output.clone();
+        while let Some(token) = page.continuation.clone() {
+            page = fetch_page(&token).unwrap();
+            collected.push_str(&page.output);
+        }
+        assert_eq!(collected, text);
+        assert_eq!(page.total_len, text.len());
+    }
+
+    #[test]
+    fn test_invalid_token() {
+        assert!(fetch_page("garbage").is_err());
+        assert!(fetch_page("missing:0").is_err());
+    }
+}
@@ -0,0 +1,137 @@
+//! # Pairing Helpers
+//!
+//! Parsing of the Gateway's pairing QR payload. The Dashboard encodes the
+//! Gateway URL and a one-time code in the QR; the same text can be pasted
+//! when scanning is not possible. Accepted forms:
+//!
+//! - `forgeai://pair?url=https%3A%2F%2Fgw.local%3A3000&code=ABC123`
+//! - `https://gw.local:3000/pair?code=ABC123`
+//! - `{"url": "https://gw.local:3000", "code": "ABC123"}`
+//!
+//! For reverse pairing the companion generates the code instead
+//! ([`generate_code`]) and the user types it into the Gateway.
+
+use serde::{Deserialize, Serialize};
+
+/// Gateway URL + one-time pairing code decoded from a QR payload
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairingPayload {
+    pub gateway_url: String,
+    pub pairing_code: String,
+}
+
+/// Parse a pairing QR payload in any of the supported forms
+pub fn parse_qr_payload(payload: &str) -> Result<PairingPayload, String> {
+    let payload = payload.trim();
+    if payload.is_empty() {
+        return Err("Empty pairing payload".into());
+    }
+
+    if payload.starts_with('{') {
+        return parse_json(payload);
+    }
+
+    let url = url::Url::parse(payload).map_err(|_| "Unrecognized pairing payload".to_string())?;
+    let query = |key: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+    let code = query("code").ok_or("Pairing payload has no code")?;
+
+    let gateway_url = match url.scheme() {
+        "forgeai" => query("url").ok_or("Pairing payload has no Gateway URL")?,
+        "http" | "https" => {
+            let mut base = url.clone();
+            base.set_query(None);
+            base.set_fragment(None);
+            // Drop the `/pair` landing path, keep any reverse-proxy prefix before it
+            let path = base.path().trim_end_matches('/').trim_end_matches("/pair").to_string();
+            base.set_path(&path);
+            base.to_string()
+        }
+        other => return Err(format!("Unsupported pairing URL scheme: {}", other)),
+    };
+
+    validated(gateway_url, code)
+}
+
+fn parse_json(payload: &str) -> Result<PairingPayload, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(payload).map_err(|e| format!("Invalid pairing JSON: {}", e))?;
+    let field = |keys: &[&str]| {
+        keys.iter()
+            .find_map(|k| value.get(*k).and_then(|v| v.as_str()))
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    let gateway_url = field(&["url", "gatewayUrl"]).ok_or("Pairing payload has no Gateway URL")?;
+    let code = field(&["code", "pairingCode"]).ok_or("Pairing payload has no code")?;
+    validated(gateway_url, code)
+}
+
+fn validated(gateway_url: String, code: String) -> Result<PairingPayload, String> {
+    let parsed = url::Url::parse(&gateway_url).map_err(|e| format!("Invalid Gateway URL: {}", e))?;
+    if parsed.scheme() != "http" && parsed.scheme() != "https" {
+        return Err(format!("Gateway URL must be http(s): {}", gateway_url));
+    }
+    Ok(PairingPayload {
+        gateway_url: gateway_url.trim_end_matches('/').to_string(),
+        pairing_code: code,
+    })
+}
+
+/// Characters of companion-generated codes (no 0/O, 1/I/L look-alikes)
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+
+/// Format random bytes as a `XXXX-XXXX` reverse-pairing code
+pub fn generate_code(random: &[u8; 8]) -> String {
+    let chars: String = random
+        .iter()
+        .map(|b| CODE_ALPHABET[*b as usize % CODE_ALPHABET.len()] as char)
+        .collect();
+    format!("{}-{}", &chars[..4], &chars[4..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_custom_scheme() {
+        let p = parse_qr_payload("forgeai://pair?url=https%3A%2F%2Fgw.local%3A3000%2F&code=ABC123").unwrap();
+        assert_eq!(p.gateway_url, "https://gw.local:3000");
+        assert_eq!(p.pairing_code, "ABC123");
+    }
+
+    #[test]
+    fn test_http_landing_url() {
+        let p = parse_qr_payload("http://192.168.1.10:3000/forge/pair?code=XYZ").unwrap();
+        assert_eq!(p.gateway_url, "http://192.168.1.10:3000/forge");
+        assert_eq!(p.pairing_code, "XYZ");
+    }
+
+    #[test]
+    fn test_json_payload() {
+        let p = parse_qr_payload(r#" {"gatewayUrl":"https://gw","pairingCode":"42"} "#).unwrap();
+        assert_eq!(p.gateway_url, "https://gw");
+        assert_eq!(p.pairing_code, "42");
+    }
+
+    #[test]
+    fn test_generate_code() {
+        let code = generate_code(&[0, 1, 2, 3, 30, 31, 200, 255]);
+        assert_eq!(code, "ABCD-9ARH");
+        assert!(code.chars().all(|c| c == '-' || CODE_ALPHABET.contains(&(c as u8))));
+    }
+
+    #[test]
+    fn test_rejects_bad_payloads() {
+        assert!(parse_qr_payload("").is_err());
+        assert!(parse_qr_payload("hello").is_err());
+        assert!(parse_qr_payload("forgeai://pair?code=1").is_err());
+        assert!(parse_qr_payload("https://gw/pair").is_err());
+        assert!(parse_qr_payload(r#"{"url":"ftp://gw","code":"1"}"#).is_err());
+    }
+}
@@ -0,0 +1,276 @@
+//! # OS Permissions
+//!
+//! Voice, screenshots and desktop automation depend on permissions the OS
+//! can withhold. `get_permissions` reports each one and
+//! `request_permission(kind)` asks for it — or, where apps cannot ask
+//! themselves, opens the right settings page.
+//!
+//! | Kind              | macOS                                   | Windows                          | Linux                |
+//! |-------------------|-----------------------------------------|----------------------------------|----------------------|
+//! | `microphone`      | capture probe (denied TCC = silence)    | privacy consent store (registry) | capture probe        |
+//! | `screenRecording` | `CGPreflightScreenCaptureAccess`        | not gated                        | portal on Wayland    |
+//! | `accessibility`   | `AXIsProcessTrusted`                    | not gated                        | not gated            |
+//!
+//! macOS has no public query for microphone access without Objective-C;
+//! instead a short capture is opened. That is also what triggers the
+//! system prompt the first time, and a denied app receives pure digital
+//! silence rather than an error, which is how denial is recognized.
+
+use cpal::traits::{DeviceTrait, StreamTrait};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Length of the microphone probe
+const PROBE_TIME: Duration = Duration::from_millis(400);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionKind {
+    Microphone,
+    ScreenRecording,
+    Accessibility,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PermissionState {
+    Granted,
+    Denied,
+    /// The OS asks per use (the Wayland screenshot portal)
+    #[cfg_attr(not(target_os = "linux"), allow(dead_code))]
+    Prompt,
+    /// No device, or the state cannot be determined
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PermissionStatus {
+    pub kind: PermissionKind,
+    pub state: PermissionState,
+    /// What the user can do about a denied permission
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
+
+#[cfg(target_os = "macos")]
+mod macos {
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> bool;
+    }
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+        fn CGRequestScreenCaptureAccess() -> bool;
+    }
+
+    pub fn accessibility_trusted() -> bool {
+        unsafe { AXIsProcessTrusted() }
+    }
+
+    pub fn screen_capture_allowed() -> bool {
+        unsafe { CGPreflightScreenCaptureAccess() }
+    }
+
+    pub fn request_screen_capture() -> bool {
+        unsafe { CGRequestScreenCaptureAccess() }
+    }
+
+    /// Open a Privacy & Security pane of System Settings
+    pub fn open_privacy_pane(anchor: &str) {
+        let url = format!("x-apple.systempreferences:com.apple.preference.security?{}", anchor);
+        let _ = std::process::Command::new("open").arg(url).spawn();
+    }
+}
+
+/// State from `reg query` output of a CapabilityAccessManager consent key
+#[cfg_attr(not(any(target_os = "windows", test)), allow(dead_code))]
+fn consent_from_reg(output: &str) -> Option<PermissionState> {
+    let line = output.lines().find(|l| l.trim_start().starts_with("Value"))?;
+    match line.split_whitespace().last()? {
+        "Allow" => Some(PermissionState::Granted),
+        "Deny" => Some(PermissionState::Denied),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn windows_consent(capability: &str) -> PermissionState {
+    let base = format!(
+        "HKCU\\Software\\Microsoft\\Windows\\CurrentVersion\\CapabilityAccessManager\\ConsentStore\\{}",
+        capability
+    );
+    // Global switch first, then the one for desktop (non-packaged) apps
+    let mut state = PermissionState::Unknown;
+    for key in [base.clone(), format!("{}\\NonPackaged", base)] {
+        let Ok(out) = std::process::Command::new("reg").args(["query", &key, "/v", "Value"]).output() else {
+            continue;
+        };
+        match consent_from_reg(&String::from_utf8_lossy(&out.stdout)) {
+            Some(PermissionState::Denied) => return PermissionState::Denied,
+            Some(s) => state = s,
+            None => {}
+        }
+    }
+    state
+}
+
+/// Interpret a microphone probe: whether the stream opened, how many
+/// samples arrived and whether any of them was non-zero
+fn probe_state(opened: bool, samples: usize, any_signal: bool) -> PermissionState {
+    match (opened, samples, any_signal) {
+        (false, _, _) => PermissionState::Denied,
+        (true, 0, _) => PermissionState::Unknown,
+        (true, _, true) => PermissionState::Granted,
+        // Real microphones always pick up some noise
+        (true, _, false) => PermissionState::Denied,
+    }
+}
+
+/// Open the input device briefly and look at what it delivers
+fn probe_microphone() -> PermissionState {
+    let Some(device) = crate::voice::input_device() else {
+        return PermissionState::Unknown;
+    };
+    let Ok(supported) = device.default_input_config() else {
+        return PermissionState::Denied;
+    };
+    let (tx, rx) = std::sync::mpsc::channel::<(usize, bool)>();
+    let stream = device.build_input_stream(
+        &supported.config(),
+        move |data: &[f32], _: &cpal::InputCallbackInfo| {
+            let _ = tx.send((data.len(), data.iter().any(|s| *s != 0.0)));
+        },
+        |err| tracing::warn!("[Permissions] Microphone probe error: {}", err),
+        None,
+    );
+    let Ok(stream) = stream else {
+        return probe_state(false, 0, false);
+    };
+    if stream.play().is_err() {
+        return probe_state(false, 0, false);
+    }
+    std::thread::sleep(PROBE_TIME);
+    drop(stream);
+    let (samples, any_signal) = rx.try_iter().fold((0, false), |(n, a), (len, sig)| (n + len, a || sig));
+    probe_state(true, samples, any_signal)
+}
+
+fn microphone() -> PermissionState {
+    #[cfg(target_os = "windows")]
+    if windows_consent("microphone") == PermissionState::Denied {
+        return PermissionState::Denied;
+    }
+    if crate::voice::mic_muted() {
+        // Muted in the companion: do not open the device just to check
+        return PermissionState::Unknown;
+    }
+    probe_microphone()
+}
+
+fn screen_recording() -> PermissionState {
+    #[cfg(target_os = "macos")]
+    return if macos::screen_capture_allowed() { PermissionState::Granted } else { PermissionState::Denied };
+    #[cfg(target_os = "linux")]
+    return if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        PermissionState::Prompt
+    } else {
+        PermissionState::Granted
+    };
+    #[allow(unreachable_code)]
+    PermissionState::Granted
+}
+
+fn accessibility() -> PermissionState {
+    #[cfg(target_os = "macos")]
+    return if macos::accessibility_trusted() { PermissionState::Granted } else { PermissionState::Denied };
+    #[allow(unreachable_code)]
+    PermissionState::Granted
+}
+
+fn hint(kind: PermissionKind, state: PermissionState) -> Option<String> {
+    if state != PermissionState::Denied {
+        return None;
+    }
+    let text = match (kind, std::env::consts::OS) {
+        (PermissionKind::Microphone, "macos") => "System Settings → Privacy & Security → Microphone",
+        (PermissionKind::Microphone, "windows") => "Settings → Privacy & security → Microphone → Let desktop apps access your microphone",
+        (PermissionKind::Microphone, _) => "Check that the input device is connected and not in use or muted",
+        (PermissionKind::ScreenRecording, _) => "System Settings → Privacy & Security → Screen Recording (restart the companion afterwards)",
+        (PermissionKind::Accessibility, _) => "System Settings → Privacy & Security → Accessibility",
+    };
+    Some(text.into())
+}
+
+/// State of one permission. Blocking (the microphone check records briefly).
+pub fn status(kind: PermissionKind) -> PermissionStatus {
+    let state = match kind {
+        PermissionKind::Microphone => microphone(),
+        PermissionKind::ScreenRecording => screen_recording(),
+        PermissionKind::Accessibility => accessibility(),
+    };
+    PermissionStatus { kind, state, hint: hint(kind, state) }
+}
+
+/// State of every permission. Blocking.
+pub fn all() -> Vec<PermissionStatus> {
+    [PermissionKind::Microphone, PermissionKind::ScreenRecording, PermissionKind::Accessibility]
+        .into_iter()
+        .map(status)
+        .collect()
+}
+
+/// Ask for a permission: show the OS prompt where there is one, otherwise
+/// open the settings page. Returns the state afterwards. Blocking.
+pub fn request(kind: PermissionKind) -> PermissionStatus {
+    let before = status(kind);
+    if before.state == PermissionState::Granted {
+        return before;
+    }
+    tracing::info!("[Permissions] Requesting {:?} (currently {:?})", kind, before.state);
+
+    #[cfg(target_os = "macos")]
+    match kind {
+        // The probe in `status` already showed the prompt on first use
+        PermissionKind::Microphone => macos::open_privacy_pane("Privacy_Microphone"),
+        PermissionKind::ScreenRecording => {
+            if !macos::request_screen_capture() {
+                macos::open_privacy_pane("Privacy_ScreenCapture");
+            }
+        }
+        PermissionKind::Accessibility => macos::open_privacy_pane("Privacy_Accessibility"),
+    }
+
+    #[cfg(target_os = "windows")]
+    if kind == PermissionKind::Microphone {
+        let _ = std::process::Command::new("cmd")
+            .args(["/C", "start", "", "ms-settings:privacy-microphone"])
+            .spawn();
+    }
+
+    status(kind)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_detection() {
+        let reg = "\r\nHKEY_CURRENT_USER\\Software\\...\\ConsentStore\\microphone\r\n    Value    REG_SZ    Deny\r\n";
+        assert_eq!(consent_from_reg(reg), Some(PermissionState::Denied));
+        assert_eq!(consent_from_reg(&reg.replace("Deny", "Allow")), Some(PermissionState::Granted));
+        assert_eq!(consent_from_reg("ERROR: The system was unable to find the specified registry key"), None);
+
+        assert_eq!(probe_state(false, 0, false), PermissionState::Denied);
+        assert_eq!(probe_state(true, 0, false), PermissionState::Unknown);
+        assert_eq!(probe_state(true, 4800, true), PermissionState::Granted);
+        assert_eq!(probe_state(true, 4800, false), PermissionState::Denied);
+
+        assert!(hint(PermissionKind::Accessibility, PermissionState::Granted).is_none());
+        assert!(hint(PermissionKind::Microphone, PermissionState::Denied).is_some());
+        let kind: PermissionKind = serde_json::from_str("\"screenRecording\"").unwrap();
+        assert_eq!(kind, PermissionKind::ScreenRecording);
+    }
+}
@@ -0,0 +1,237 @@
+//! # Power Policy
+//!
+//! An always-open microphone keeps the CPU awake. On laptops the policy
+//! suspends wake-word listening while on battery below a threshold or in
+//! power-saver mode, and resumes it on AC power. The state is checked every
+//! 30 seconds and shows up as `suspended` in `WakeWordStatus`; changes are
+//! emitted as `listening-suspended` so the UI can pause continuous
+//! listening too.
+//!
+//! | OS      | Battery                                | Power saver                         |
+//! |---------|----------------------------------------|-------------------------------------|
+//! | Windows | `GetSystemPowerStatus`                 | battery saver flag of the same call |
+//! | macOS   | `pmset -g batt`                        | `lowpowermode` in `pmset -g`        |
+//! | Linux   | `/sys/class/power_supply`              | `platform_profile` = `low-power`    |
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Interval between power checks
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+/// Suspension source name (see `wake_word`)
+const SOURCE: &str = "power";
+
+static POLICY: Mutex<Option<PowerPolicy>> = Mutex::new(None);
+/// Wakes the monitor when the policy changes
+static CHANGED: OnceLock<Notify> = OnceLock::new();
+
+fn changed() -> &'static Notify {
+    CHANGED.get_or_init(Notify::new)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PowerPolicy {
+    pub enabled: bool,
+    /// On battery at or below this charge, listening is suspended
+    pub min_battery_percent: u8,
+    /// Suspend whenever the OS power-saver mode is on
+    pub suspend_in_power_saver: bool,
+}
+
+impl Default for PowerPolicy {
+    fn default() -> Self {
+        Self { enabled: true, min_battery_percent: 20, suspend_in_power_saver: true }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerState {
+    pub on_battery: bool,
+    /// None on desktops without a battery
+    pub battery_percent: Option<u8>,
+    pub power_saver: bool,
+}
+
+/// Why `policy` suspends listening in `state`, if it does
+pub fn suspend_reason(policy: &PowerPolicy, state: &PowerState) -> Option<String> {
+    if !policy.enabled {
+        return None;
+    }
+    if policy.suspend_in_power_saver && state.power_saver {
+        return Some("power-saver mode is on".into());
+    }
+    match state.battery_percent {
+        Some(p) if state.on_battery && p <= policy.min_battery_percent => {
+            Some(format!("on battery at {}%", p))
+        }
+        _ => None,
+    }
+}
+
+// ─── Platform readers ───────────────────────────────
+
+/// `pmset -g batt` and `pmset -g` output
+#[cfg_attr(not(any(target_os = "macos", test)), allow(dead_code))]
+fn parse_pmset(batt: &str, settings: &str) -> PowerState {
+    let battery_percent = batt
+        .split(|c: char| c.is_whitespace() || c == ';')
+        .find_map(|w| w.strip_suffix('%')?.parse().ok());
+    let power_saver = settings.lines().any(|l| {
+        let mut parts = l.split_whitespace();
+        parts.next() == Some("lowpowermode") && parts.next() == Some("1")
+    });
+    PowerState { on_battery: batt.contains("'Battery Power'"), battery_percent, power_saver }
+}
+
+/// `/sys/class/power_supply/*` as (type, status, capacity, online)
+#[cfg_attr(not(any(target_os = "linux", test)), allow(dead_code))]
+fn from_power_supplies(supplies: &[(String, String, Option<u8>, Option<bool>)], profile: &str) -> PowerState {
+    let batteries: Vec<_> = supplies.iter().filter(|s| s.0 == "Battery").collect();
+    let mains_online = supplies.iter().any(|s| s.0 == "Mains" && s.3 == Some(true));
+    PowerState {
+        on_battery: !mains_online && batteries.iter().any(|b| b.1 == "Discharging"),
+        battery_percent: batteries.iter().filter_map(|b| b.2).min(),
+        power_saver: profile.trim() == "low-power",
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn read() -> PowerState {
+    let read = |p: &std::path::Path| std::fs::read_to_string(p).map(|s| s.trim().to_string()).unwrap_or_default();
+    let supplies: Vec<_> = std::fs::read_dir("/sys/class/power_supply")
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| {
+                    let dir = e.path();
+                    (
+                        read(&dir.join("type")),
+                        read(&dir.join("status")),
+                        read(&dir.join("capacity")).parse().ok(),
+                        dir.join("online").exists().then(|| read(&dir.join("online")) == "1"),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    from_power_supplies(&supplies, &read(std::path::Path::new("/sys/firmware/acpi/platform_profile")))
+}
+
+#[cfg(target_os = "macos")]
+fn read() -> PowerState {
+    let pmset = |args: &[&str]| {
+        std::process::Command::new("pmset")
+            .args(args)
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+            .unwrap_or_default()
+    };
+    parse_pmset(&pmset(&["-g", "batt"]), &pmset(&["-g"]))
+}
+
+#[cfg(target_os = "windows")]
+fn read() -> PowerState {
+    #[repr(C)]
+    #[derive(Default)]
+    struct SystemPowerStatus {
+        ac_line_status: u8,
+        battery_flag: u8,
+        battery_life_percent: u8,
+        system_status_flag: u8,
+        battery_life_time: u32,
+        battery_full_life_time: u32,
+    }
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetSystemPowerStatus(status: *mut SystemPowerStatus) -> i32;
+    }
+    let mut status = SystemPowerStatus::default();
+    if unsafe { GetSystemPowerStatus(&mut status) } == 0 {
+        return PowerState::default();
+    }
+    let has_battery = status.battery_flag != 128 && status.battery_life_percent != 255;
+    PowerState {
+        on_battery: status.ac_line_status == 0,
+        battery_percent: Some(status.battery_life_percent).filter(|_| has_battery),
+        power_saver: status.system_status_flag == 1,
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read() -> PowerState {
+    PowerState::default()
+}
+
+/// Current power state. Blocking (may run `pmset`).
+pub fn state() -> PowerState {
+    read()
+}
+
+/// Use `policy` from now on; the monitor re-checks at once
+pub fn set_config(policy: &PowerPolicy) {
+    if let Ok(mut current) = POLICY.lock() {
+        *current = Some(policy.clone());
+    }
+    changed().notify_one();
+}
+
+fn policy() -> PowerPolicy {
+    POLICY.lock().ok().and_then(|p| p.clone()).unwrap_or_default()
+}
+
+/// Apply the policy every 30 seconds, and whenever it changes
+pub fn spawn_monitor(app: tauri::AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut suspended: Option<String> = None;
+        loop {
+            let power = tauri::async_runtime::spawn_blocking(state).await.unwrap_or_default();
+            let reason = suspend_reason(&policy(), &power);
+            if reason != suspended {
+                match &reason {
+                    Some(r) => crate::commands::suspend_listening(&app, SOURCE, format!("Power saving: {}", r)),
+                    None => crate::commands::resume_listening(&app, SOURCE),
+                }
+                suspended = reason;
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+                _ = changed().notified() => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_and_readers() {
+        let policy = PowerPolicy::default();
+        let battery = |on_battery, percent| PowerState { on_battery, battery_percent: Some(percent), power_saver: false };
+        assert_eq!(suspend_reason(&policy, &battery(true, 15)).as_deref(), Some("on battery at 15%"));
+        assert_eq!(suspend_reason(&policy, &battery(true, 50)), None);
+        assert_eq!(suspend_reason(&policy, &battery(false, 5)), None);
+        let saver = PowerState { power_saver: true, ..Default::default() };
+        assert!(suspend_reason(&policy, &saver).is_some());
+        assert_eq!(suspend_reason(&PowerPolicy { enabled: false, ..policy.clone() }, &saver), None);
+
+        let batt = "Now drawing from 'Battery Power'\n -InternalBattery-0 (id=1234)\t18%; discharging; 1:02 remaining present: true\n";
+        let state = parse_pmset(batt, "System-wide power settings:\n lowpowermode         0\n");
+        assert_eq!(state, battery(true, 18));
+        let state = parse_pmset(&batt.replace("Battery Power", "AC Power"), " lowpowermode         1\n");
+        assert!(!state.on_battery && state.power_saver);
+
+        let supply = |t: &str, status: &str, cap, online| (t.to_string(), status.to_string(), cap, online);
+        let laptop = [supply("Mains", "", None, Some(false)), supply("Battery", "Discharging", Some(42), None)];
+        assert_eq!(from_power_supplies(&laptop, "balanced"), battery(true, 42));
+        let plugged = [supply("Mains", "", None, Some(true)), supply("Battery", "Charging", Some(42), None)];
+        assert!(!from_power_supplies(&plugged, "low-power\n").on_battery);
+        assert!(from_power_supplies(&plugged, "low-power\n").power_saver);
+        assert_eq!(from_power_supplies(&[], ""), PowerState::default());
+    }
+}
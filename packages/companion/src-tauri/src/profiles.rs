@@ -0,0 +1,189 @@
+//! # Audio Profiles
+//!
+//! Named bundles of voice settings for the places a companion is used —
+//! "Headset at work", "Living room speaker". A profile holds the input and
+//! output device, the capture thresholds and the wake word sensitivity;
+//! switching to it copies them into the live settings.
+//!
+//! Profiles live in `settings.json` (`audioProfiles`, `activeAudioProfile`).
+//! Profiles with `autoSwitch` are activated when one of their devices is
+//! plugged in: the device lists are polled every few seconds, since cpal
+//! has no hot-plug notifications. Every switch is emitted as
+//! `audio-profile-changed`.
+
+use crate::settings::VoiceSettings;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::Duration;
+use tauri::AppHandle;
+
+/// Interval between device list polls
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AudioProfile {
+    pub name: String,
+    /// Input/output devices and capture thresholds
+    #[serde(default)]
+    pub voice: VoiceSettings,
+    #[serde(default = "default_sensitivity")]
+    pub wake_sensitivity: f32,
+    /// Activate when one of this profile's devices connects
+    #[serde(default)]
+    pub auto_switch: bool,
+}
+
+fn default_sensitivity() -> f32 {
+    0.5
+}
+
+impl AudioProfile {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Profile name must not be empty".into());
+        }
+        if !(0.0..=1.0).contains(&self.wake_sensitivity) {
+            return Err("Wake word sensitivity must be between 0 and 1".into());
+        }
+        self.voice.validate()
+    }
+
+    fn uses(&self, device: &str) -> bool {
+        self.voice.input_device.as_deref() == Some(device) || self.voice.output_device.as_deref() == Some(device)
+    }
+}
+
+/// Input and output device names currently connected
+fn connected_devices() -> HashSet<String> {
+    let mut devices: HashSet<String> = crate::wake_word::list_audio_devices().into_iter().collect();
+    devices.extend(crate::voice::list_output_devices());
+    devices
+}
+
+/// The auto-switch profile to activate after `appeared` devices connected
+fn auto_profile<'a>(
+    profiles: &'a [AudioProfile],
+    appeared: &HashSet<String>,
+    active: Option<&str>,
+) -> Option<&'a AudioProfile> {
+    profiles
+        .iter()
+        .filter(|p| p.auto_switch && Some(p.name.as_str()) != active)
+        .find(|p| appeared.iter().any(|d| p.uses(d)))
+}
+
+pub fn list() -> Vec<AudioProfile> {
+    crate::settings::get().audio_profiles
+}
+
+/// Create a profile, or replace the one with the same name
+pub fn save(app: &AppHandle, profile: AudioProfile) -> Result<Vec<AudioProfile>, String> {
+    profile.validate()?;
+    let settings = crate::settings::update(app, |s| {
+        match s.audio_profiles.iter_mut().find(|p| p.name == profile.name) {
+            Some(existing) => *existing = profile.clone(),
+            None => s.audio_profiles.push(profile.clone()),
+        }
+    })?;
+    Ok(settings.audio_profiles)
+}
+
+pub fn delete(app: &AppHandle, name: &str) -> Result<Vec<AudioProfile>, String> {
+    let settings = crate::settings::update(app, |s| {
+        s.audio_profiles.retain(|p| p.name != name);
+        if s.active_audio_profile.as_deref() == Some(name) {
+            s.active_audio_profile = None;
+        }
+    })?;
+    Ok(settings.audio_profiles)
+}
+
+/// Apply a profile's devices and thresholds to the live settings
+pub fn switch(app: &AppHandle, name: &str) -> Result<AudioProfile, String> {
+    let profile = list()
+        .into_iter()
+        .find(|p| p.name == name)
+        .ok_or_else(|| format!("No audio profile named '{}'", name))?;
+    crate::settings::update(app, |s| {
+        s.voice = profile.voice.clone();
+        s.wake_word.sensitivity = profile.wake_sensitivity;
+        s.active_audio_profile = Some(profile.name.clone());
+    })?;
+    tracing::info!("[Profiles] Switched to '{}'", profile.name);
+    crate::events::emit("audio-profile-changed", &profile);
+    Ok(profile)
+}
+
+/// Poll for connected and disconnected devices: pick the preferred output
+/// again, and switch to a profile matching a newly connected device
+pub fn spawn_device_watcher(app: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        let mut known = tauri::async_runtime::spawn_blocking(connected_devices).await.unwrap_or_default();
+        loop {
+            tokio::time::sleep(POLL_INTERVAL).await;
+            let Ok(now) = tauri::async_runtime::spawn_blocking(connected_devices).await else {
+                continue;
+            };
+            let appeared: HashSet<String> = now.difference(&known).cloned().collect();
+            let changed = now != known;
+            known = now;
+            if changed {
+                // Bluetooth devices connecting or dropping out included
+                let _ = tauri::async_runtime::spawn_blocking(crate::output_priority::refresh).await;
+            }
+            if appeared.is_empty() {
+                continue;
+            }
+            let settings = crate::settings::get();
+            let Some(profile) = auto_profile(&settings.audio_profiles, &appeared, settings.active_audio_profile.as_deref())
+            else {
+                continue;
+            };
+            if let Err(e) = switch(&app, &profile.name) {
+                tracing::warn!("[Profiles] Auto-switch to '{}' failed: {}", profile.name, e);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str, input: Option<&str>, output: Option<&str>, auto_switch: bool) -> AudioProfile {
+        AudioProfile {
+            name: name.into(),
+            voice: VoiceSettings {
+                input_device: input.map(Into::into),
+                output_device: output.map(Into::into),
+                ..Default::default()
+            },
+            wake_sensitivity: 0.5,
+            auto_switch,
+        }
+    }
+
+    #[test]
+    fn test_auto_switch_selection() {
+        let profiles = vec![
+            profile("Desk", Some("USB Mic"), None, false),
+            profile("Headset at work", Some("Jabra Evolve"), Some("Jabra Evolve"), true),
+            profile("Living room", None, Some("HDMI Speaker"), true),
+        ];
+        let appeared = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<HashSet<_>>();
+
+        let hit = auto_profile(&profiles, &appeared(&["Jabra Evolve"]), None).unwrap();
+        assert_eq!(hit.name, "Headset at work");
+        assert_eq!(auto_profile(&profiles, &appeared(&["HDMI Speaker"]), None).unwrap().name, "Living room");
+        // Profiles without auto-switch, and the active one, are left alone
+        assert!(auto_profile(&profiles, &appeared(&["USB Mic"]), None).is_none());
+        assert!(auto_profile(&profiles, &appeared(&["Jabra Evolve"]), Some("Headset at work")).is_none());
+        assert!(auto_profile(&profiles, &appeared(&["Unknown"]), None).is_none());
+
+        assert!(profile(" ", None, None, false).validate().is_err());
+        let mut bad = profile("Loud", None, None, false);
+        bad.voice.silence_threshold = 2.0;
+        assert!(bad.validate().is_err());
+    }
+}
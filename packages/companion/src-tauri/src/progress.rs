@@ -0,0 +1,171 @@
+//! # Action Progress
+//!
+//! Local actions that run longer than a moment — large copies, file
+//! transfers, long shell commands — report progress as `action-progress`
+//! events so the UI can show it instead of a frozen spinner:
+//!
+//! ```json
+//! {"jobId":"action-7","action":"copy_file","percent":42,"phase":"Copying 420.0MB of 1.0GB","done":false}
+//! ```
+//!
+//! `percent` is null when the total is unknown (shell commands). Nothing is
+//! emitted for actions that finish within 300 ms; after that updates are
+//! throttled to four per second, plus one final event with `done: true`.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Actions faster than this never emit progress
+const QUIET_PERIOD: Duration = Duration::from_millis(300);
+/// Minimum gap between updates of the same stage
+const MIN_INTERVAL: Duration = Duration::from_millis(250);
+/// Interval of "still running" updates
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(1);
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Payload of the `action-progress` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionProgress {
+    pub job_id: String,
+    pub action: String,
+    pub percent: Option<u8>,
+    pub phase: String,
+    pub done: bool,
+}
+
+struct Inner {
+    id: String,
+    action: String,
+    started: Instant,
+    /// Time and stage of the last event sent
+    last: Mutex<Option<(Instant, String)>>,
+}
+
+/// Progress reporter for one action run; cheap to clone into worker threads
+#[derive(Clone)]
+pub struct Tracker(Arc<Inner>);
+
+/// Whether an update is worth emitting
+fn due(since_start: Duration, since_last: Option<Duration>, stage_changed: bool) -> bool {
+    since_start >= QUIET_PERIOD && (stage_changed || since_last.is_none_or(|d| d >= MIN_INTERVAL))
+}
+
+fn percent_of(done: u64, total: u64) -> u8 {
+    if total == 0 {
+        return 100;
+    }
+    (done.min(total) as u128 * 100 / total as u128) as u8
+}
+
+impl Tracker {
+    pub fn start(action: &str) -> Self {
+        Tracker(Arc::new(Inner {
+            id: format!("action-{}", NEXT_ID.fetch_add(1, Ordering::Relaxed)),
+            action: action.to_string(),
+            started: Instant::now(),
+            last: Mutex::new(None),
+        }))
+    }
+
+    fn emit(&self, percent: Option<u8>, phase: &str, done: bool) {
+        crate::events::emit(
+            "action-progress",
+            ActionProgress {
+                job_id: self.0.id.clone(),
+                action: self.0.action.clone(),
+                percent,
+                phase: phase.to_string(),
+                done,
+            },
+        );
+    }
+
+    /// Report `phase` of `stage`; only a new stage bypasses the throttle
+    fn report(&self, percent: Option<u8>, stage: &str, phase: impl FnOnce() -> String) {
+        let Ok(mut last) = self.0.last.lock() else {
+            return;
+        };
+        let now = Instant::now();
+        let stage_changed = last.as_ref().is_none_or(|(_, s)| s != stage);
+        if !due(now - self.0.started, last.as_ref().map(|(t, _)| now - *t), stage_changed) {
+            return;
+        }
+        *last = Some((now, stage.to_string()));
+        drop(last);
+        self.emit(percent, &phase(), false);
+    }
+
+    /// Report the current phase; `percent` None when the total is unknown
+    pub fn update(&self, percent: Option<u8>, phase: &str) {
+        self.report(percent, phase, || phase.to_string());
+    }
+
+    /// Report `done` of `total` bytes, e.g. "Copying 1.0MB of 4.0MB"
+    pub fn bytes(&self, verb: &str, done: u64, total: u64) {
+        use crate::local_actions::format_size;
+        self.report(Some(percent_of(done, total)), verb, || {
+            format!("{} {} of {}", verb, format_size(done), format_size(total))
+        });
+    }
+
+    /// Emit "still running" updates from a background thread until the
+    /// returned guard is dropped
+    pub fn heartbeat(&self, phase: &str) -> Heartbeat {
+        let running = Arc::new(AtomicBool::new(true));
+        let (tracker, flag, phase) = (self.clone(), running.clone(), phase.to_string());
+        std::thread::spawn(move || {
+            while flag.load(Ordering::Relaxed) {
+                std::thread::sleep(HEARTBEAT_INTERVAL);
+                if flag.load(Ordering::Relaxed) {
+                    let secs = tracker.0.started.elapsed().as_secs();
+                    tracker.report(None, &phase, || format!("{} ({}s)", phase, secs));
+                }
+            }
+        });
+        Heartbeat(running)
+    }
+
+    /// Close the progress display, if anything was shown
+    pub fn finish(&self, success: bool) {
+        let shown = self.0.last.lock().map(|l| l.is_some()).unwrap_or(false);
+        if shown {
+            self.emit(Some(100).filter(|_| success), if success { "Done" } else { "Failed" }, true);
+        }
+    }
+}
+
+/// Stops a [`Tracker::heartbeat`] when dropped
+pub struct Heartbeat(Arc<AtomicBool>);
+
+impl Drop for Heartbeat {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttling_and_percent() {
+        let ms = Duration::from_millis;
+        // Quiet at first, however often it is called
+        assert!(!due(ms(100), None, true));
+        assert!(due(ms(300), None, true));
+        // Same stage: at most every 250 ms; a new stage goes out at once
+        assert!(!due(ms(900), Some(ms(100)), false));
+        assert!(due(ms(900), Some(ms(250)), false));
+        assert!(due(ms(900), Some(ms(10)), true));
+
+        assert_eq!(percent_of(0, 200), 0);
+        assert_eq!(percent_of(50, 200), 25);
+        assert_eq!(percent_of(300, 200), 100);
+        assert_eq!(percent_of(0, 0), 100);
+        assert_eq!(percent_of(u64::MAX / 2, u64::MAX), 49);
+    }
+}
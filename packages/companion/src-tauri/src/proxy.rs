@@ -0,0 +1,487 @@
+//! # HTTP / SOCKS Proxy Configuration
+//!
+//! Corporate networks often only allow outbound traffic through a proxy.
+//! Every reqwest client used to talk to the Gateway is built through
+//! [`apply`], and the live WebSocket is opened through [`connect_ws`]. Both
+//! honour one of three modes:
+//!
+//! - `system` — proxy from the `HTTP_PROXY` / `HTTPS_PROXY` / `ALL_PROXY` /
+//!   `NO_PROXY` environment variables (default). For HTTP requests reqwest
+//!   also picks up the Windows and macOS proxy settings; the WebSocket only
+//!   sees the environment. Desktop proxy settings on Linux (GNOME, KDE) are
+//!   never read — use `manual` there.
+//! - `none`   — always connect directly
+//! - `manual` — explicit `http://`, `https://`, `socks5://` or `socks5h://`
+//!   proxy, with optional basic authentication and bypass list
+//!
+//! The WebSocket is tunnelled with HTTP `CONNECT` or a SOCKS5 handshake, so
+//! it works with `http://` and `socks5(h)://` proxies but not `https://` ones.
+//!
+//! The configuration may contain a password, so it is stored encrypted.
+
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite;
+
+static CONFIG: OnceLock<Mutex<ProxyConfig>> = OnceLock::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    #[default]
+    System,
+    None,
+    Manual,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    pub mode: ProxyMode,
+    /// Proxy URL for `manual` mode, e.g. `http://proxy.corp:8080`
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Comma-separated hosts that bypass the proxy, e.g. `localhost,.lan`
+    pub no_proxy: Option<String>,
+}
+
+impl ProxyConfig {
+    /// Copy safe to hand to the frontend (password masked)
+    pub fn redacted(&self) -> Self {
+        Self {
+            password: self.password.as_ref().map(|_| "********".into()),
+            ..self.clone()
+        }
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.mode != ProxyMode::Manual {
+            return Ok(());
+        }
+        let url = self.url.as_deref().ok_or("Manual proxy mode needs a proxy URL")?;
+        let parsed = url::Url::parse(url).map_err(|e| format!("Invalid proxy URL: {}", e))?;
+        match parsed.scheme() {
+            "http" | "https" | "socks5" | "socks5h" => Ok(()),
+            other => Err(format!("Unsupported proxy scheme: {}", other)),
+        }
+    }
+}
+
+fn config_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("forgeai-companion").join("proxy.enc"))
+}
+
+fn load() -> ProxyConfig {
+    config_path()
+        .filter(|p| p.exists())
+        .and_then(|p| match crate::secure_store::read_encrypted(&p) {
+            Ok(bytes) => serde_json::from_slice(&bytes).ok(),
+            Err(e) => {
+                tracing::warn!("[Proxy] Config unreadable, using system proxy: {}", e);
+                None
+            }
+        })
+        .unwrap_or_default()
+}
+
+fn state() -> &'static Mutex<ProxyConfig> {
+    CONFIG.get_or_init(|| Mutex::new(load()))
+}
+
+/// Current proxy configuration
+pub fn config() -> ProxyConfig {
+    state().lock().map(|c| c.clone()).unwrap_or_default()
+}
+
+/// Validate, persist and activate a new proxy configuration.
+/// A masked password (as returned by [`ProxyConfig::redacted`]) keeps the stored one.
+pub fn set_config(mut new: ProxyConfig) -> Result<(), String> {
+    new.validate()?;
+    if new.password.as_deref() == Some("********") {
+        new.password = config().password;
+    }
+
+    let path = config_path().ok_or("Cannot determine data directory")?;
+    let json = serde_json::to_vec(&new).map_err(|e| format!("Serialize error: {}", e))?;
+    crate::secure_store::write_encrypted(&path, &json)?;
+
+    tracing::info!("[Proxy] Mode set to {:?}", new.mode);
+    *state().lock().map_err(|e| e.to_string())? = new;
+    crate::http::invalidate();
+    Ok(())
+}
+
+/// Apply the configured proxy to a client builder
+pub fn apply(builder: reqwest::ClientBuilder) -> reqwest::ClientBuilder {
+    let cfg = config();
+    match cfg.mode {
+        // reqwest reads HTTP(S)_PROXY / ALL_PROXY (and the Windows/macOS settings) by default
+        ProxyMode::System => builder,
+        ProxyMode::None => builder.no_proxy(),
+        ProxyMode::Manual => {
+            let Some(url) = cfg.url.as_deref() else {
+                return builder;
+            };
+            match reqwest::Proxy::all(url) {
+                Ok(mut proxy) => {
+                    if let Some(user) = cfg.username.as_deref() {
+                        proxy = proxy.basic_auth(user, cfg.password.as_deref().unwrap_or(""));
+                    }
+                    if let Some(list) = cfg.no_proxy.as_deref() {
+                        proxy = proxy.no_proxy(reqwest::NoProxy::from_string(list));
+                    }
+                    builder.proxy(proxy)
+                }
+                Err(e) => {
+                    tracing::error!("[Proxy] Invalid proxy '{}': {}", url, e);
+                    builder
+                }
+            }
+        }
+    }
+}
+
+// ─── WebSocket tunnel ───────────────────────────────
+
+pub type WsStream = tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<TcpStream>>;
+
+/// A proxy chosen for one connection
+#[derive(Debug, PartialEq)]
+struct Route {
+    url: url::Url,
+    /// Username and password
+    auth: Option<(String, String)>,
+}
+
+/// Whether `host` matches a `NO_PROXY`-style list (`localhost,.lan,10.0.0.5`)
+fn bypassed(list: &str, host: &str) -> bool {
+    let host = host.trim_start_matches('[').trim_end_matches(']').to_ascii_lowercase();
+    list.split(',').map(|e| e.trim().to_ascii_lowercase()).any(|entry| {
+        let domain = entry.trim_start_matches('.');
+        entry == "*" || (!domain.is_empty() && (host == domain || host.ends_with(&format!(".{}", domain))))
+    })
+}
+
+/// Proxy for a connection to `target`, if any. `env` reads environment
+/// variables, which is all the `system` mode looks at here.
+fn route_for(cfg: &ProxyConfig, target: &url::Url, env: impl Fn(&str) -> Option<String>) -> Option<Route> {
+    let host = target.host_str()?;
+    let (proxy, no_proxy) = match cfg.mode {
+        ProxyMode::None => return None,
+        ProxyMode::Manual => (cfg.url.clone()?, cfg.no_proxy.clone()),
+        ProxyMode::System => {
+            let names: &[&str] = if matches!(target.scheme(), "wss" | "https") {
+                &["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy"]
+            } else {
+                &["HTTP_PROXY", "http_proxy", "ALL_PROXY", "all_proxy"]
+            };
+            let proxy = names.iter().find_map(|n| env(n).filter(|v| !v.trim().is_empty()))?;
+            (proxy, env("NO_PROXY").or_else(|| env("no_proxy")))
+        }
+    };
+    if no_proxy.as_deref().is_some_and(|list| bypassed(list, host)) {
+        return None;
+    }
+    // Environment proxies are often written without a scheme
+    let url = url::Url::parse(&proxy)
+        .ok()
+        .filter(|u| u.has_host())
+        .or_else(|| url::Url::parse(&format!("http://{}", proxy)).ok())?;
+    let auth = match (cfg.mode, cfg.username.as_deref()) {
+        (ProxyMode::Manual, Some(user)) => Some((user.to_string(), cfg.password.clone().unwrap_or_default())),
+        _ if !url.username().is_empty() => Some((url.username().to_string(), url.password().unwrap_or("").to_string())),
+        _ => None,
+    };
+    Some(Route { url, auth })
+}
+
+fn connect_request(host: &str, port: u16, auth: Option<&(String, String)>) -> String {
+    let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+    if let Some((user, password)) = auth {
+        let token = base64::engine::general_purpose::STANDARD.encode(format!("{}:{}", user, password));
+        request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", token));
+    }
+    request.push_str("\r\n");
+    request
+}
+
+/// Check the status line of a `CONNECT` response
+fn connect_status(head: &str) -> Result<(), String> {
+    let status = head
+        .lines()
+        .next()
+        .and_then(|l| l.split_whitespace().nth(1))
+        .and_then(|c| c.parse::<u16>().ok())
+        .ok_or("Malformed proxy response")?;
+    match status {
+        200..=299 => Ok(()),
+        407 => Err("Proxy authentication required — check the proxy credentials".into()),
+        code => Err(format!("Proxy refused the tunnel: HTTP {}", code)),
+    }
+}
+
+async fn http_connect(stream: &mut TcpStream, host: &str, port: u16, auth: Option<&(String, String)>) -> Result<(), String> {
+    let io = |e: std::io::Error| format!("Proxy connection failed: {}", e);
+    stream.write_all(connect_request(host, port, auth).as_bytes()).await.map_err(io)?;
+    // Byte by byte, so nothing past the response head is consumed
+    let mut head = Vec::new();
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() > 8192 {
+            return Err("Malformed proxy response".into());
+        }
+        let mut byte = [0u8];
+        if stream.read(&mut byte).await.map_err(io)? == 0 {
+            return Err("Proxy closed the connection".into());
+        }
+        head.push(byte[0]);
+    }
+    connect_status(&String::from_utf8_lossy(&head))
+}
+
+async fn socks5_connect(
+    stream: &mut TcpStream,
+    host: &str,
+    port: u16,
+    auth: Option<&(String, String)>,
+    remote_dns: bool,
+) -> Result<(), String> {
+    let io = |e: std::io::Error| format!("SOCKS proxy connection failed: {}", e);
+    let methods: &[u8] = if auth.is_some() { &[0x00, 0x02] } else { &[0x00] };
+    let mut hello = vec![5, methods.len() as u8];
+    hello.extend_from_slice(methods);
+    stream.write_all(&hello).await.map_err(io)?;
+    let mut reply = [0u8; 2];
+    stream.read_exact(&mut reply).await.map_err(io)?;
+    match (reply, auth) {
+        ([5, 0x00], _) => {}
+        ([5, 0x02], Some((user, password))) => {
+            if user.len() > 255 || password.len() > 255 {
+                return Err("SOCKS credentials are too long".into());
+            }
+            let mut login = vec![1, user.len() as u8];
+            login.extend_from_slice(user.as_bytes());
+            login.push(password.len() as u8);
+            login.extend_from_slice(password.as_bytes());
+            stream.write_all(&login).await.map_err(io)?;
+            stream.read_exact(&mut reply).await.map_err(io)?;
+            if reply[1] != 0 {
+                return Err("SOCKS proxy rejected the credentials".into());
+            }
+        }
+        _ => return Err("SOCKS proxy offers no supported authentication method".into()),
+    }
+
+    let mut request = vec![5, 1, 0];
+    let bare = host.trim_start_matches('[').trim_end_matches(']');
+    let ip = match bare.parse::<IpAddr>() {
+        Ok(ip) => Some(ip),
+        Err(_) if remote_dns => None,
+        Err(_) => tokio::net::lookup_host((bare, port))
+            .await
+            .map_err(|e| format!("Cannot resolve {}: {}", bare, e))?
+            .next()
+            .map(|a| a.ip()),
+    };
+    match ip {
+        Some(IpAddr::V4(v4)) => {
+            request.push(1);
+            request.extend_from_slice(&v4.octets());
+        }
+        Some(IpAddr::V6(v6)) => {
+            request.push(4);
+            request.extend_from_slice(&v6.octets());
+        }
+        None => {
+            request.push(3);
+            request.push(bare.len().min(255) as u8);
+            request.extend_from_slice(&bare.as_bytes()[..bare.len().min(255)]);
+        }
+    }
+    request.extend_from_slice(&port.to_be_bytes());
+    stream.write_all(&request).await.map_err(io)?;
+
+    let mut head = [0u8; 4];
+    stream.read_exact(&mut head).await.map_err(io)?;
+    if head[1] != 0 {
+        return Err(format!("SOCKS proxy refused the connection (code {})", head[1]));
+    }
+    let address_len = match head[3] {
+        1 => 4,
+        4 => 16,
+        3 => {
+            let mut len = [0u8];
+            stream.read_exact(&mut len).await.map_err(io)?;
+            len[0] as usize
+        }
+        _ => return Err("Malformed SOCKS reply".into()),
+    };
+    let mut rest = vec![0u8; address_len + 2];
+    stream.read_exact(&mut rest).await.map_err(io)?;
+    Ok(())
+}
+
+/// TCP stream to `host:port` through the proxy
+async fn tunnel(route: &Route, host: &str, port: u16) -> Result<TcpStream, String> {
+    let proxy_host = route.url.host_str().ok_or("Proxy URL has no host")?;
+    let proxy_port = route.url.port_or_known_default().unwrap_or(1080);
+    let mut stream = TcpStream::connect(format!("{}:{}", proxy_host, proxy_port))
+        .await
+        .map_err(|e| format!("Proxy connection failed: {}", e))?;
+    match route.url.scheme() {
+        "http" => http_connect(&mut stream, host, port, route.auth.as_ref()).await?,
+        scheme @ ("socks5" | "socks5h") => {
+            socks5_connect(&mut stream, host, port, route.auth.as_ref(), scheme == "socks5h").await?
+        }
+        other => return Err(format!("Cannot tunnel a WebSocket through a {} proxy", other)),
+    }
+    Ok(stream)
+}
+
+/// Open a WebSocket, tunnelled through the configured proxy when one applies
+pub async fn connect_ws(
+    ws_url: &str,
+    connector: Option<tokio_tungstenite::Connector>,
+) -> Result<WsStream, tungstenite::Error> {
+    let fail = |e: String| tungstenite::Error::Io(std::io::Error::other(e));
+    let target = url::Url::parse(ws_url).map_err(|e| fail(format!("Invalid WebSocket URL: {}", e)))?;
+    let host = target.host_str().ok_or_else(|| fail("WebSocket URL has no host".into()))?;
+    let port = target.port_or_known_default().ok_or_else(|| fail("WebSocket URL has no port".into()))?;
+
+    let stream = match route_for(&config(), &target, |name| std::env::var(name).ok()) {
+        Some(route) => {
+            tracing::debug!("[Proxy] Tunnelling WebSocket to {}:{} via {}", host, port, route.url.host_str().unwrap_or(""));
+            tunnel(&route, host, port).await.map_err(fail)?
+        }
+        None => TcpStream::connect(format!("{}:{}", host, port)).await?,
+    };
+    let (ws, _) = tokio_tungstenite::client_async_tls_with_config(ws_url, stream, None, connector).await?;
+    Ok(ws)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn manual(url: &str) -> ProxyConfig {
+        ProxyConfig { mode: ProxyMode::Manual, url: Some(url.into()), ..Default::default() }
+    }
+
+    #[test]
+    fn test_validate_and_redact() {
+        assert!(ProxyConfig::default().validate().is_ok());
+        assert!(manual("http://proxy.corp:8080").validate().is_ok());
+        assert!(manual("socks5h://127.0.0.1:1080").validate().is_ok());
+        assert!(manual("ftp://proxy").validate().is_err());
+        assert!(manual("not a url").validate().is_err());
+        assert!(ProxyConfig { mode: ProxyMode::Manual, ..Default::default() }.validate().is_err());
+
+        let cfg = ProxyConfig { password: Some("hunter2".into()), username: Some("me".into()), ..manual("http://p:1") };
+        let redacted = cfg.redacted();
+        assert_eq!(redacted.password.as_deref(), Some("********"));
+        assert_eq!(redacted.username.as_deref(), Some("me"));
+        assert_eq!(ProxyConfig::default().redacted().password, None);
+    }
+
+    #[test]
+    fn test_route_selection() {
+        let target = url::Url::parse("wss://gw.corp.example:3000/ws").unwrap();
+        let no_env = |_: &str| None;
+
+        assert_eq!(route_for(&ProxyConfig { mode: ProxyMode::None, ..Default::default() }, &target, no_env), None);
+        assert_eq!(route_for(&ProxyConfig::default(), &target, no_env), None);
+
+        let cfg = ProxyConfig { username: Some("me".into()), password: Some("pw".into()), ..manual("http://proxy:8080") };
+        let route = route_for(&cfg, &target, no_env).unwrap();
+        assert_eq!(route.url.as_str(), "http://proxy:8080/");
+        assert_eq!(route.auth, Some(("me".into(), "pw".into())));
+
+        let cfg = ProxyConfig { no_proxy: Some("localhost, .example".into()), ..manual("http://proxy:8080") };
+        assert_eq!(route_for(&cfg, &target, no_env), None);
+        assert!(bypassed("localhost", "localhost"));
+        assert!(!bypassed("ample", "gw.corp.example"));
+        assert!(bypassed("*", "anything"));
+
+        // System mode: scheme-specific variable, scheme-less values, NO_PROXY
+        let env = |name: &str| match name {
+            "HTTPS_PROXY" => Some("user:secret@proxy.corp:3128".to_string()),
+            "HTTP_PROXY" => Some("http://plain.corp:80".to_string()),
+            _ => None,
+        };
+        let route = route_for(&ProxyConfig::default(), &target, env).unwrap();
+        assert_eq!(route.url.host_str(), Some("proxy.corp"));
+        assert_eq!(route.auth, Some(("user".into(), "secret".into())));
+        let plain = url::Url::parse("ws://gw.lan/ws").unwrap();
+        assert_eq!(route_for(&ProxyConfig::default(), &plain, env).unwrap().url.host_str(), Some("plain.corp"));
+        let env_bypass = |name: &str| match name {
+            "HTTPS_PROXY" => Some("http://proxy.corp:3128".to_string()),
+            "no_proxy" => Some("corp.example".to_string()),
+            _ => None,
+        };
+        assert_eq!(route_for(&ProxyConfig::default(), &target, env_bypass), None);
+
+        let request = connect_request("gw", 443, Some(&("me".into(), "pw".into())));
+        assert!(request.starts_with("CONNECT gw:443 HTTP/1.1\r\nHost: gw:443\r\n"));
+        assert!(request.contains("Proxy-Authorization: Basic bWU6cHc=\r\n"));
+        assert!(request.ends_with("\r\n\r\n"));
+        assert!(connect_status("HTTP/1.1 200 Connection established\r\n\r\n").is_ok());
+        assert!(connect_status("HTTP/1.1 407 Proxy Authentication Required\r\n\r\n").is_err());
+        assert!(connect_status("garbage").is_err());
+    }
+
+    #[test]
+    fn test_tunnels() {
+        let rt = tokio::runtime::Builder::new_current_thread().enable_io().build().unwrap();
+        rt.block_on(async {
+            // HTTP CONNECT proxy that answers 200 and then echoes
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let proxy = tokio::spawn(async move {
+                let (mut s, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0u8; 1024];
+                let n = s.read(&mut buf).await.unwrap();
+                let head = String::from_utf8_lossy(&buf[..n]).to_string();
+                s.write_all(b"HTTP/1.1 200 Connection established\r\n\r\nhello").await.unwrap();
+                head
+            });
+            let route = Route { url: url::Url::parse(&format!("http://{}", addr)).unwrap(), auth: None };
+            let mut stream = tunnel(&route, "gw.lan", 3000).await.unwrap();
+            let mut greeting = [0u8; 5];
+            stream.read_exact(&mut greeting).await.unwrap();
+            assert_eq!(&greeting, b"hello");
+            assert!(proxy.await.unwrap().starts_with("CONNECT gw.lan:3000 "));
+
+            // SOCKS5 proxy with username/password, domain resolved remotely
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            let proxy = tokio::spawn(async move {
+                let (mut s, _) = listener.accept().await.unwrap();
+                let mut hello = [0u8; 4];
+                s.read_exact(&mut hello).await.unwrap();
+                s.write_all(&[5, 2]).await.unwrap();
+                let mut login = [0u8; 7];
+                s.read_exact(&mut login).await.unwrap();
+                s.write_all(&[1, 0]).await.unwrap();
+                let mut request = [0u8; 5 + 6 + 2];
+                s.read_exact(&mut request).await.unwrap();
+                s.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0x0b, 0xb8]).await.unwrap();
+                (hello, login, request)
+            });
+            let route = Route {
+                url: url::Url::parse(&format!("socks5h://{}", addr)).unwrap(),
+                auth: Some(("me".into(), "pw".into())),
+            };
+            tunnel(&route, "gw.lan", 3000).await.unwrap();
+            let (hello, login, request) = proxy.await.unwrap();
+            assert_eq!(hello, [5, 2, 0, 2]);
+            assert_eq!(&login, b"\x01\x02me\x02pw");
+            assert_eq!(&request[..5], &[5, 1, 0, 3, 6]);
+            assert_eq!(&request[5..11], b"gw.lan");
+            assert_eq!(&request[11..], &3000u16.to_be_bytes());
+        });
+    }
+}
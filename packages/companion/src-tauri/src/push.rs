@@ -0,0 +1,192 @@
+//! # Gateway Push Events
+//!
+//! Subscribes to Gateway-originated events over the live channel and
+//! surfaces them to the user. Once the Gateway's capability manifest lists
+//! `event_push`, the companion sends
+//!
+//! ```json
+//! {"type": "events.subscribe", "topics": ["chat.message", "reminder.due", "task.finished"]}
+//! ```
+//!
+//! and the Gateway pushes
+//!
+//! ```json
+//! {"type": "event", "id": "...", "topic": "reminder.due", "title": "...", "body": "...", "data": {}}
+//! ```
+//!
+//! Every event is re-emitted to the frontend as `gateway-event`; while the
+//! window is not in focus an OS notification is shown as well. Events are
+//! acknowledged (`event.ack`) so the Gateway stops redelivering them, and
+//! duplicate deliveries are dropped.
+
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Mutex, OnceLock};
+
+/// Event ids remembered for de-duplication
+const SEEN_CAPACITY: usize = 200;
+
+pub mod topic {
+    /// New chat message addressed to this device
+    pub const CHAT_MESSAGE: &str = "chat.message";
+    /// A reminder is due
+    pub const REMINDER_DUE: &str = "reminder.due";
+    /// A long-running agent task finished
+    pub const TASK_FINISHED: &str = "task.finished";
+}
+
+static CONFIG: OnceLock<Mutex<PushConfig>> = OnceLock::new();
+static SEEN: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PushConfig {
+    /// Topics to subscribe to
+    pub topics: Vec<String>,
+    /// Show OS notifications for events
+    pub notifications: bool,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            topics: vec![
+                topic::CHAT_MESSAGE.into(),
+                topic::REMINDER_DUE.into(),
+                topic::TASK_FINISHED.into(),
+            ],
+            notifications: true,
+        }
+    }
+}
+
+/// Event pushed by the Gateway, as forwarded to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatewayEvent {
+    #[serde(default)]
+    pub id: String,
+    pub topic: String,
+    #[serde(default)]
+    pub title: String,
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub data: serde_json::Value,
+}
+
+fn state() -> &'static Mutex<PushConfig> {
+    CONFIG.get_or_init(|| Mutex::new(PushConfig::default()))
+}
+
+/// Current push settings
+pub fn config() -> PushConfig {
+    state().lock().map(|c| c.clone()).unwrap_or_default()
+}
+
+/// Activate new push settings (persisted by `settings`) and resubscribe if connected
+pub fn set_config(new: PushConfig) -> Result<(), String> {
+    *state().lock().map_err(|e| e.to_string())? = new;
+    if crate::connection::is_live()
+        && crate::capabilities::gateway_supports(crate::capabilities::feature::EVENT_PUSH, false)
+    {
+        crate::connection::send_live(subscribe_message().to_string())?;
+    }
+    Ok(())
+}
+
+/// The `events.subscribe` message sent when the live channel connects
+pub fn subscribe_message() -> serde_json::Value {
+    serde_json::json!({
+        "type": "events.subscribe",
+        "topics": config().topics,
+    })
+}
+
+/// Record an event id; false if it was already delivered
+fn first_delivery(id: &str) -> bool {
+    if id.is_empty() {
+        return true;
+    }
+    let Ok(mut seen) = SEEN.lock() else {
+        return true;
+    };
+    if seen.iter().any(|s| s == id) {
+        return false;
+    }
+    if seen.len() >= SEEN_CAPACITY {
+        seen.pop_front();
+    }
+    seen.push_back(id.to_string());
+    true
+}
+
+/// Default notification title when the Gateway sends none
+fn default_title(topic: &str) -> &'static str {
+    match topic {
+        topic::CHAT_MESSAGE => "New message",
+        topic::REMINDER_DUE => "Reminder",
+        topic::TASK_FINISHED => "Task finished",
+        _ => "ForgeAI",
+    }
+}
+
+/// Handle an `event` message from the Gateway
+pub fn handle_gateway_event(raw: &serde_json::Value) {
+    let event: GatewayEvent = match serde_json::from_value(raw.clone()) {
+        Ok(e) => e,
+        Err(e) => {
+            tracing::warn!("[Push] Malformed event: {}", e);
+            return;
+        }
+    };
+
+    if !event.id.is_empty() {
+        let ack = serde_json::json!({ "type": "event.ack", "id": event.id });
+        let _ = crate::connection::send_live(ack.to_string());
+    }
+    if !first_delivery(&event.id) {
+        tracing::debug!("[Push] Duplicate event {} dropped", event.id);
+        return;
+    }
+
+    let cfg = config();
+    if !cfg.topics.iter().any(|t| t == &event.topic) {
+        tracing::debug!("[Push] Ignoring unsubscribed topic {}", event.topic);
+        return;
+    }
+
+    tracing::info!("[Push] {} event received", event.topic);
+    if event.topic == topic::CHAT_MESSAGE {
+        crate::history::sync_in_background();
+    }
+    if cfg.notifications && !crate::events::window_focused() {
+        let title = if event.title.is_empty() { default_title(&event.topic) } else { &event.title };
+        crate::events::notify(title, &event.body);
+    }
+    crate::events::emit("gateway-event", event);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_events_dropped() {
+        assert!(first_delivery("evt-1"));
+        assert!(!first_delivery("evt-1"));
+        assert!(first_delivery("evt-2"));
+        // Events without id cannot be de-duplicated
+        assert!(first_delivery(""));
+        assert!(first_delivery(""));
+    }
+
+    #[test]
+    fn test_event_defaults() {
+        let raw = serde_json::json!({ "type": "event", "topic": "task.finished" });
+        let event: GatewayEvent = serde_json::from_value(raw).unwrap();
+        assert_eq!(event.topic, topic::TASK_FINISHED);
+        assert!(event.id.is_empty());
+        assert_eq!(default_title(&event.topic), "Task finished");
+    }
+}
@@ -0,0 +1,1165 @@
+//! # QR Code Decoding
+//!
+//! Reads the Dashboard's pairing QR from an image — a screenshot or a photo
+//! of the screen — so pairing does not need a camera library in the
+//! frontend. The pipeline is the classic one:
+//!
+//! 1. Binarize (global Otsu threshold, then a local-mean threshold as fallback)
+//! 2. Locate the three finder patterns and work out the orientation
+//! 3. Sample the module grid through a perspective transform, anchored on
+//!    the bottom-right alignment pattern when there is one
+//! 4. Read format and version info, undo the mask, de-interleave the blocks
+//! 5. Correct errors with Reed-Solomon over GF(256)
+//! 6. Decode the numeric, alphanumeric and byte segments
+//!
+//! Model 2 codes of every version and error correction level are handled.
+//! Kanji segments and Micro QR are not; the Dashboard never emits them.
+
+/// Larger images are scaled down before scanning
+const MAX_SIDE: u32 = 1600;
+
+/// Decode the first readable QR code in an encoded image (PNG or JPEG)
+pub fn decode_image(bytes: &[u8]) -> Result<String, String> {
+    let img = image::load_from_memory(bytes).map_err(|e| format!("Unreadable QR image: {}", e))?;
+    let mut luma = img.to_luma8();
+    let (w, h) = luma.dimensions();
+    if w.max(h) > MAX_SIDE {
+        let scale = MAX_SIDE as f64 / w.max(h) as f64;
+        let (nw, nh) = (((w as f64 * scale) as u32).max(1), ((h as f64 * scale) as u32).max(1));
+        luma = image::imageops::resize(&luma, nw, nh, image::imageops::FilterType::Triangle);
+    }
+    let (w, h) = luma.dimensions();
+    decode_luma(w as usize, h as usize, luma.as_raw())
+}
+
+/// Decode the first readable QR code in 8-bit grayscale pixels (row-major)
+pub fn decode_luma(width: usize, height: usize, pixels: &[u8]) -> Result<String, String> {
+    if width == 0 || height == 0 || pixels.len() < width * height {
+        return Err("Unreadable QR image: empty".into());
+    }
+    let mut last_error = "No QR code found in the image".to_string();
+    for bitmap in [Bitmap::global(width, height, pixels), Bitmap::adaptive(width, height, pixels)] {
+        let finders = find_finders(&bitmap);
+        for (tl, tr, bl) in finder_triples(&finders).into_iter().take(3) {
+            for dim in dimensions(&tl, &tr, &bl) {
+                let Some(grid) = sample(&bitmap, &tl, &tr, &bl, dim) else {
+                    continue;
+                };
+                match decode_grid(&grid) {
+                    Ok(text) => return Ok(text),
+                    Err(e) => last_error = format!("QR code could not be read: {}", e),
+                }
+            }
+        }
+    }
+    Err(last_error)
+}
+
+// ─── Binarization ───────────────────────────────────
+
+struct Bitmap {
+    width: usize,
+    height: usize,
+    dark: Vec<bool>,
+}
+
+impl Bitmap {
+    /// Threshold every pixel against one Otsu level
+    fn global(width: usize, height: usize, pixels: &[u8]) -> Self {
+        let mut histogram = [0u64; 256];
+        for &p in &pixels[..width * height] {
+            histogram[p as usize] += 1;
+        }
+        let total = (width * height) as f64;
+        let sum_all: f64 = histogram.iter().enumerate().map(|(i, &n)| i as f64 * n as f64).sum();
+        let (mut weight_bg, mut sum_bg, mut best, mut threshold) = (0.0, 0.0, 0.0, 128u8);
+        for (level, &n) in histogram.iter().enumerate() {
+            weight_bg += n as f64;
+            if weight_bg == 0.0 || weight_bg == total {
+                continue;
+            }
+            sum_bg += level as f64 * n as f64;
+            let mean_bg = sum_bg / weight_bg;
+            let mean_fg = (sum_all - sum_bg) / (total - weight_bg);
+            let between = weight_bg * (total - weight_bg) * (mean_bg - mean_fg).powi(2);
+            if between > best {
+                best = between;
+                threshold = level as u8;
+            }
+        }
+        Self { width, height, dark: pixels[..width * height].iter().map(|&p| p <= threshold).collect() }
+    }
+
+    /// Threshold every pixel against the mean of its neighbourhood
+    /// (copes with uneven lighting in photos)
+    fn adaptive(width: usize, height: usize, pixels: &[u8]) -> Self {
+        let mut integral = vec![0u64; (width + 1) * (height + 1)];
+        for y in 0..height {
+            let mut row = 0u64;
+            for x in 0..width {
+                row += pixels[y * width + x] as u64;
+                integral[(y + 1) * (width + 1) + x + 1] = integral[y * (width + 1) + x + 1] + row;
+            }
+        }
+        let radius = (width.min(height) / 8).max(8);
+        let mut dark = Vec::with_capacity(width * height);
+        for y in 0..height {
+            let (y0, y1) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+            for x in 0..width {
+                let (x0, x1) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+                let sum = integral[y1 * (width + 1) + x1] + integral[y0 * (width + 1) + x0]
+                    - integral[y0 * (width + 1) + x1]
+                    - integral[y1 * (width + 1) + x0];
+                let area = ((x1 - x0) * (y1 - y0)) as u64;
+                // Dark when clearly below the local mean (7% margin)
+                dark.push((pixels[y * width + x] as u64) * area * 100 < sum * 93);
+            }
+        }
+        Self { width, height, dark }
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.dark[y * self.width + x]
+    }
+}
+
+// ─── Finder patterns ────────────────────────────────
+
+#[derive(Debug, Clone, Copy)]
+struct Finder {
+    x: f64,
+    y: f64,
+    /// Estimated module size in pixels
+    module: f64,
+    /// Scan lines that confirmed this pattern
+    hits: u32,
+}
+
+/// Whether run lengths look like a finder's 1:1:3:1:1 dark/light profile
+fn finder_ratio(counts: &[usize; 5]) -> bool {
+    if counts.contains(&0) {
+        return false;
+    }
+    let total: usize = counts.iter().sum();
+    if total < 7 {
+        return false;
+    }
+    let module = total as f64 / 7.0;
+    let variance = module / 2.0;
+    (counts[0] as f64 - module).abs() < variance
+        && (counts[1] as f64 - module).abs() < variance
+        && (counts[2] as f64 - 3.0 * module).abs() < 3.0 * variance
+        && (counts[3] as f64 - module).abs() < variance
+        && (counts[4] as f64 - module).abs() < variance
+}
+
+/// Re-measure a candidate along one axis through its center; returns the
+/// refined center coordinate on that axis and the pattern's total width
+fn cross_check(bitmap: &Bitmap, cx: usize, cy: usize, vertical: bool, max_count: usize) -> Option<(f64, usize)> {
+    let (pos, len) = if vertical { (cy, bitmap.height) } else { (cx, bitmap.width) };
+    let at = |i: usize| if vertical { bitmap.get(cx, i) } else { bitmap.get(i, cy) };
+    let mut counts = [0usize; 5];
+
+    let mut i = pos as isize;
+    while i >= 0 && at(i as usize) {
+        counts[2] += 1;
+        i -= 1;
+    }
+    while i >= 0 && !at(i as usize) && counts[1] <= max_count {
+        counts[1] += 1;
+        i -= 1;
+    }
+    if i < 0 || counts[1] > max_count {
+        return None;
+    }
+    while i >= 0 && at(i as usize) && counts[0] <= max_count {
+        counts[0] += 1;
+        i -= 1;
+    }
+    if counts[0] > max_count {
+        return None;
+    }
+
+    let mut i = pos + 1;
+    while i < len && at(i) {
+        counts[2] += 1;
+        i += 1;
+    }
+    while i < len && !at(i) && counts[3] <= max_count {
+        counts[3] += 1;
+        i += 1;
+    }
+    if i == len || counts[3] > max_count {
+        return None;
+    }
+    while i < len && at(i) && counts[4] <= max_count {
+        counts[4] += 1;
+        i += 1;
+    }
+    if counts[4] > max_count || !finder_ratio(&counts) {
+        return None;
+    }
+    let center = i as f64 - counts[4] as f64 - counts[3] as f64 - counts[2] as f64 / 2.0;
+    Some((center, counts.iter().sum()))
+}
+
+/// Confirm a horizontal hit vertically and horizontally, then merge it into
+/// `finders` (nearby hits are the same pattern)
+fn add_finder(bitmap: &Bitmap, finders: &mut Vec<Finder>, counts: &[usize; 5], end: usize, y: usize) -> bool {
+    let total: usize = counts.iter().sum();
+    let cx = end as f64 - counts[4] as f64 - counts[3] as f64 - counts[2] as f64 / 2.0;
+    let Some((cy, total_v)) = cross_check(bitmap, cx as usize, y, true, counts[2]) else {
+        return false;
+    };
+    if 5 * total_v.abs_diff(total) >= 2 * total {
+        return false;
+    }
+    let Some((cx, total_h)) = cross_check(bitmap, cx as usize, cy as usize, false, counts[2]) else {
+        return false;
+    };
+    let module = (total_v + total_h) as f64 / 14.0;
+    if let Some(f) = finders
+        .iter_mut()
+        .find(|f| (f.x - cx).abs() <= f.module.max(module) && (f.y - cy).abs() <= f.module.max(module))
+    {
+        let n = f.hits as f64;
+        f.x = (f.x * n + cx) / (n + 1.0);
+        f.y = (f.y * n + cy) / (n + 1.0);
+        f.module = (f.module * n + module) / (n + 1.0);
+        f.hits += 1;
+    } else {
+        finders.push(Finder { x: cx, y: cy, module, hits: 1 });
+    }
+    true
+}
+
+/// Scan every row for 1:1:3:1:1 runs
+fn find_finders(bitmap: &Bitmap) -> Vec<Finder> {
+    let mut finders = Vec::new();
+    for y in 0..bitmap.height {
+        let mut counts = [0usize; 5];
+        let mut state = 0;
+        for x in 0..bitmap.width {
+            if bitmap.get(x, y) {
+                if state == 1 || state == 3 {
+                    state += 1;
+                }
+                counts[state] += 1;
+            } else if state == 1 || state == 3 {
+                counts[state] += 1;
+            } else if state == 0 {
+                if counts[0] > 0 {
+                    state = 1;
+                    counts[1] = 1;
+                }
+            } else if state == 2 {
+                state = 3;
+                counts[3] = 1;
+            } else if finder_ratio(&counts) && add_finder(bitmap, &mut finders, &counts, x, y) {
+                counts = [0; 5];
+                state = 0;
+            } else {
+                // Slide by one dark/light pair and keep looking
+                counts = [counts[2], counts[3], counts[4], 1, 0];
+                state = 3;
+            }
+        }
+        if state == 4 && finder_ratio(&counts) {
+            add_finder(bitmap, &mut finders, &counts, bitmap.width, y);
+        }
+    }
+    finders
+}
+
+fn distance(a: &Finder, b: &Finder) -> f64 {
+    ((a.x - b.x).powi(2) + (a.y - b.y).powi(2)).sqrt()
+}
+
+/// Candidate (top-left, top-right, bottom-left) triples, most plausible first
+fn finder_triples(finders: &[Finder]) -> Vec<(Finder, Finder, Finder)> {
+    let mut candidates: Vec<Finder> = finders.iter().filter(|f| f.hits >= 2).copied().collect();
+    if candidates.len() < 3 {
+        candidates = finders.to_vec();
+    }
+    candidates.sort_by_key(|f| std::cmp::Reverse(f.hits));
+    candidates.truncate(12);
+
+    let mut triples = Vec::new();
+    for i in 0..candidates.len() {
+        for j in i + 1..candidates.len() {
+            for k in j + 1..candidates.len() {
+                let mut p = [candidates[i], candidates[j], candidates[k]];
+                let modules = p.map(|f| f.module);
+                let (min, max) = (modules.iter().copied().fold(f64::MAX, f64::min), modules.iter().copied().fold(0.0, f64::max));
+                if max > min * 1.5 {
+                    continue;
+                }
+                // The top-left finder is opposite the longest side
+                let sides = [distance(&p[1], &p[2]), distance(&p[0], &p[2]), distance(&p[0], &p[1])];
+                let corner = (0..3).max_by(|&a, &b| sides[a].total_cmp(&sides[b])).unwrap_or(0);
+                p.swap(0, corner);
+                let (tl, mut tr, mut bl) = (p[0], p[1], p[2]);
+                let (a, b, c) = (distance(&tl, &tr), distance(&tl, &bl), distance(&tr, &bl));
+                let module = (min + max) / 2.0;
+                if a.min(b) < 10.0 * module {
+                    continue;
+                }
+                let legs = (a - b).abs() / a.max(b);
+                let square = (c * c - (a * a + b * b)).abs() / (c * c);
+                if legs > 0.3 || square > 0.35 {
+                    continue;
+                }
+                // Image y points down: top-right → bottom-left turns clockwise
+                if (tr.x - tl.x) * (bl.y - tl.y) - (tr.y - tl.y) * (bl.x - tl.x) < 0.0 {
+                    std::mem::swap(&mut tr, &mut bl);
+                }
+                triples.push((legs + square, (tl, tr, bl)));
+            }
+        }
+    }
+    triples.sort_by(|a, b| a.0.total_cmp(&b.0));
+    triples.into_iter().map(|(_, t)| t).collect()
+}
+
+/// Plausible grid sizes (4·version + 17) for the finder spacing, closest first
+fn dimensions(tl: &Finder, tr: &Finder, bl: &Finder) -> Vec<usize> {
+    let module = (tl.module + tr.module + bl.module) / 3.0;
+    let estimate = (distance(tl, tr) + distance(tl, bl)) / 2.0 / module + 7.0;
+    let mut versions: Vec<usize> = (1..=40).collect();
+    versions.sort_by(|&a, &b| ((4 * a + 17) as f64 - estimate).abs().total_cmp(&((4 * b + 17) as f64 - estimate).abs()));
+    versions.into_iter().take(2).map(|v| 4 * v + 17).collect()
+}
+
+// ─── Sampling ───────────────────────────────────────
+
+/// Projective map from module coordinates to pixels
+struct Homography([f64; 8]);
+
+impl Homography {
+    /// Solve for the map sending each `from` point to the matching `to` point
+    fn from_points(from: &[(f64, f64); 4], to: &[(f64, f64); 4]) -> Option<Self> {
+        let mut m = [[0.0f64; 9]; 8];
+        for (i, (&(u, v), &(x, y))) in from.iter().zip(to).enumerate() {
+            m[2 * i] = [u, v, 1.0, 0.0, 0.0, 0.0, -u * x, -v * x, x];
+            m[2 * i + 1] = [0.0, 0.0, 0.0, u, v, 1.0, -u * y, -v * y, y];
+        }
+        for col in 0..8 {
+            let pivot = (col..8).max_by(|&a, &b| m[a][col].abs().total_cmp(&m[b][col].abs()))?;
+            if m[pivot][col].abs() < 1e-12 {
+                return None;
+            }
+            m.swap(col, pivot);
+            for row in 0..8 {
+                if row != col {
+                    let pivot_row = m[col];
+                    let factor = m[row][col] / pivot_row[col];
+                    for (k, value) in m[row].iter_mut().enumerate().skip(col) {
+                        *value -= factor * pivot_row[k];
+                    }
+                }
+            }
+        }
+        let mut h = [0.0; 8];
+        for (i, value) in h.iter_mut().enumerate() {
+            *value = m[i][8] / m[i][i];
+        }
+        Some(Self(h))
+    }
+
+    fn map(&self, u: f64, v: f64) -> (f64, f64) {
+        let h = &self.0;
+        let w = h[6] * u + h[7] * v + 1.0;
+        ((h[0] * u + h[1] * v + h[2]) / w, (h[3] * u + h[4] * v + h[5]) / w)
+    }
+}
+
+/// Module grid read from the image, `true` = dark
+struct Grid {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl Grid {
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+}
+
+/// Look for the alignment pattern near `(x, y)`; `col`/`row` are the pixel
+/// steps of one module along the grid axes
+fn find_alignment(bitmap: &Bitmap, (x, y): (f64, f64), col: (f64, f64), row: (f64, f64), module: f64) -> Option<(f64, f64)> {
+    let radius = (4.0 * module).ceil() as isize;
+    let mut best: Option<(usize, f64, (f64, f64))> = None;
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let (px, py) = (x + dx as f64, y + dy as f64);
+            let mut score = 0;
+            for j in -2i32..=2 {
+                for i in -2i32..=2 {
+                    let sx = px + i as f64 * col.0 + j as f64 * row.0;
+                    let sy = py + i as f64 * col.1 + j as f64 * row.1;
+                    if sx < 0.0 || sy < 0.0 || sx >= bitmap.width as f64 || sy >= bitmap.height as f64 {
+                        continue;
+                    }
+                    let expected = i.abs().max(j.abs()) != 1;
+                    if bitmap.get(sx as usize, sy as usize) == expected {
+                        score += 1;
+                    }
+                }
+            }
+            let offset = (dx * dx + dy * dy) as f64;
+            if best.is_none_or(|(s, o, _)| score > s || (score == s && offset < o)) {
+                best = Some((score, offset, (px, py)));
+            }
+        }
+    }
+    best.filter(|(score, _, _)| *score >= 23).map(|(_, _, p)| p)
+}
+
+/// Read a `dim`×`dim` grid given the three finder centers
+fn sample(bitmap: &Bitmap, tl: &Finder, tr: &Finder, bl: &Finder, dim: usize) -> Option<Grid> {
+    let span = dim as f64 - 7.0;
+    let col = ((tr.x - tl.x) / span, (tr.y - tl.y) / span);
+    let row = ((bl.x - tl.x) / span, (bl.y - tl.y) / span);
+    let affine = |u: f64, v: f64| (tl.x + (u - 3.5) * col.0 + (v - 3.5) * row.0, tl.y + (u - 3.5) * col.1 + (v - 3.5) * row.1);
+
+    let far = dim as f64 - 3.5;
+    let mut from = [(3.5, 3.5), (far, 3.5), (3.5, far), (far, far)];
+    let mut to = [(tl.x, tl.y), (tr.x, tr.y), (bl.x, bl.y), affine(far, far)];
+    if dim > 21 {
+        // Version 2+ has an alignment pattern 3 modules in from the far corner
+        let center = dim as f64 - 6.5;
+        let module = (tl.module + tr.module + bl.module) / 3.0;
+        if let Some(found) = find_alignment(bitmap, affine(center, center), col, row, module) {
+            from[3] = (center, center);
+            to[3] = found;
+        }
+    }
+    let h = Homography::from_points(&from, &to)?;
+
+    let mut modules = Vec::with_capacity(dim * dim);
+    for y in 0..dim {
+        for x in 0..dim {
+            let (px, py) = h.map(x as f64 + 0.5, y as f64 + 0.5);
+            if px < 0.0 || py < 0.0 || px >= bitmap.width as f64 || py >= bitmap.height as f64 {
+                return None;
+            }
+            modules.push(bitmap.get(px as usize, py as usize));
+        }
+    }
+    Some(Grid { size: dim, modules })
+}
+
+// ─── Code structure ─────────────────────────────────
+
+/// Error correction codewords per block, by level (L, M, Q, H) and version
+const ECC_CODEWORDS_PER_BLOCK: [[u8; 41]; 4] = [
+    [0, 7, 10, 15, 20, 26, 18, 20, 24, 30, 18, 20, 24, 26, 30, 22, 24, 28, 30, 28, 28, 28, 28, 30, 30, 26, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+    [0, 10, 16, 26, 18, 24, 16, 18, 22, 22, 26, 30, 22, 22, 24, 24, 28, 28, 26, 26, 26, 26, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28, 28],
+    [0, 13, 22, 18, 26, 18, 24, 18, 22, 20, 24, 28, 26, 24, 20, 30, 24, 28, 28, 26, 30, 28, 30, 30, 30, 30, 28, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+    [0, 17, 28, 22, 16, 22, 28, 26, 26, 24, 28, 24, 28, 22, 24, 24, 30, 28, 28, 26, 28, 30, 24, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30, 30],
+];
+
+/// Error correction blocks, by level (L, M, Q, H) and version
+const ERROR_CORRECTION_BLOCKS: [[u8; 41]; 4] = [
+    [0, 1, 1, 1, 1, 1, 2, 2, 2, 2, 4, 4, 4, 4, 4, 6, 6, 6, 6, 7, 8, 8, 9, 9, 10, 12, 12, 12, 13, 14, 15, 16, 17, 18, 19, 19, 20, 21, 22, 24, 25],
+    [0, 1, 1, 1, 2, 2, 4, 4, 4, 5, 5, 5, 8, 9, 9, 10, 10, 11, 13, 14, 16, 17, 17, 18, 20, 21, 23, 25, 26, 28, 29, 31, 33, 35, 37, 38, 40, 43, 45, 47, 49],
+    [0, 1, 1, 2, 2, 4, 4, 6, 6, 8, 8, 8, 10, 12, 16, 12, 17, 16, 18, 21, 20, 23, 23, 25, 27, 29, 34, 34, 35, 38, 40, 43, 45, 48, 51, 53, 56, 59, 62, 65, 68],
+    [0, 1, 1, 2, 4, 4, 4, 5, 6, 8, 8, 11, 11, 16, 16, 18, 16, 19, 21, 25, 25, 25, 34, 30, 32, 35, 37, 40, 42, 45, 48, 51, 54, 57, 60, 63, 66, 70, 74, 77, 81],
+];
+
+/// Modules left for data and error correction once function patterns are placed
+fn raw_data_modules(version: usize) -> usize {
+    let mut n = (16 * version + 128) * version + 64;
+    if version >= 2 {
+        let align = version / 7 + 2;
+        n -= (25 * align - 10) * align - 55;
+        if version >= 7 {
+            n -= 36;
+        }
+    }
+    n
+}
+
+/// Row/column centers of the alignment patterns
+fn alignment_positions(version: usize) -> Vec<usize> {
+    if version == 1 {
+        return Vec::new();
+    }
+    let count = version / 7 + 2;
+    let size = 4 * version + 17;
+    let step = if version == 32 { 26 } else { (version * 4 + count * 2 + 1) / (count * 2 - 2) * 2 };
+    let mut positions: Vec<usize> = (0..count - 1).map(|i| size - 7 - i * step).collect();
+    positions.push(6);
+    positions.reverse();
+    positions
+}
+
+/// Which modules belong to finder, timing, alignment, format and version patterns
+fn function_modules(version: usize) -> Vec<bool> {
+    let size = 4 * version + 17;
+    let mut map = vec![false; size * size];
+    let mut fill = |x0: usize, y0: usize, w: usize, h: usize| {
+        for y in y0..y0 + h {
+            for x in x0..x0 + w {
+                map[y * size + x] = true;
+            }
+        }
+    };
+    // Finders with separators and format info
+    fill(0, 0, 9, 9);
+    fill(size - 8, 0, 8, 9);
+    fill(0, size - 8, 9, 8);
+    // Timing
+    fill(6, 0, 1, size);
+    fill(0, 6, size, 1);
+    let positions = alignment_positions(version);
+    let last = positions.len().saturating_sub(1);
+    for (i, &y) in positions.iter().enumerate() {
+        for (j, &x) in positions.iter().enumerate() {
+            if (i, j) == (0, 0) || (i, j) == (0, last) || (i, j) == (last, 0) {
+                continue;
+            }
+            fill(x - 2, y - 2, 5, 5);
+        }
+    }
+    if version >= 7 {
+        fill(size - 11, 0, 3, 6);
+        fill(0, size - 11, 6, 3);
+    }
+    map
+}
+
+fn mask_bit(mask: u8, x: usize, y: usize) -> bool {
+    match mask {
+        0 => (x + y).is_multiple_of(2),
+        1 => y.is_multiple_of(2),
+        2 => x.is_multiple_of(3),
+        3 => (x + y).is_multiple_of(3),
+        4 => (x / 3 + y / 2).is_multiple_of(2),
+        5 => x * y % 2 + x * y % 3 == 0,
+        6 => (x * y % 2 + x * y % 3).is_multiple_of(2),
+        _ => ((x + y) % 2 + x * y % 3).is_multiple_of(2),
+    }
+}
+
+/// 15-bit format word for an error correction level index and mask
+fn format_word(level: usize, mask: u8) -> u32 {
+    // Level bits in the code are L=01, M=00, Q=11, H=10
+    let data = ([1u32, 0, 3, 2][level] << 3) | mask as u32;
+    let mut rem = data;
+    for _ in 0..10 {
+        rem = (rem << 1) ^ ((rem >> 9) * 0x537);
+    }
+    ((data << 10) | rem) ^ 0x5412
+}
+
+/// Error correction level index (L, M, Q, H) and mask from the format info
+fn read_format(grid: &Grid) -> Result<(usize, u8), String> {
+    let size = grid.size;
+    let bit = |x: usize, y: usize, i: usize| (grid.get(x, y) as u32) << i;
+    let mut first = 0;
+    for i in 0..6 {
+        first |= bit(8, i, i);
+    }
+    first |= bit(8, 7, 6) | bit(8, 8, 7) | bit(7, 8, 8);
+    for i in 9..15 {
+        first |= bit(14 - i, 8, i);
+    }
+    let mut second = 0;
+    for i in 0..8 {
+        second |= bit(size - 1 - i, 8, i);
+    }
+    for i in 8..15 {
+        second |= bit(8, size - 15 + i, i);
+    }
+
+    let mut best = (u32::MAX, 0, 0);
+    for level in 0..4 {
+        for mask in 0..8u8 {
+            let word = format_word(level, mask);
+            let errors = (word ^ first).count_ones().min((word ^ second).count_ones());
+            if errors < best.0 {
+                best = (errors, level, mask);
+            }
+        }
+    }
+    if best.0 > 3 {
+        return Err("format information damaged".into());
+    }
+    Ok((best.1, best.2))
+}
+
+/// Version from the version info blocks (versions 7 and up)
+fn read_version(grid: &Grid) -> Option<usize> {
+    let size = grid.size;
+    let (mut first, mut second) = (0u32, 0u32);
+    for i in 0..18 {
+        let (a, b) = (size - 11 + i % 3, i / 3);
+        first |= (grid.get(a, b) as u32) << i;
+        second |= (grid.get(b, a) as u32) << i;
+    }
+    (7..=40u32)
+        .map(|v| {
+            let mut rem = v;
+            for _ in 0..12 {
+                rem = (rem << 1) ^ ((rem >> 11) * 0x1F25);
+            }
+            let word = (v << 12) | rem;
+            (v as usize, (word ^ first).count_ones().min((word ^ second).count_ones()))
+        })
+        .min_by_key(|(_, errors)| *errors)
+        .filter(|(_, errors)| *errors <= 3)
+        .map(|(v, _)| v)
+}
+
+fn decode_grid(grid: &Grid) -> Result<String, String> {
+    let version = (grid.size - 17) / 4;
+    if version >= 7 && read_version(grid) != Some(version) {
+        return Err("version information does not match the grid".into());
+    }
+    let (level, mask) = read_format(grid)?;
+
+    // Codewords in placement order: two-column strips, zigzagging up and down
+    let size = grid.size;
+    let function = function_modules(version);
+    let mut codewords = vec![0u8; raw_data_modules(version) / 8];
+    let mut i = 0;
+    let mut right = size as isize - 1;
+    while right >= 1 {
+        if right == 6 {
+            right = 5;
+        }
+        for vert in 0..size {
+            for j in 0..2 {
+                let x = right as usize - j;
+                let upward = (right + 1) & 2 == 0;
+                let y = if upward { size - 1 - vert } else { vert };
+                if !function[y * size + x] && i < codewords.len() * 8 {
+                    if grid.get(x, y) ^ mask_bit(mask, x, y) {
+                        codewords[i >> 3] |= 1 << (7 - (i & 7));
+                    }
+                    i += 1;
+                }
+            }
+        }
+        right -= 2;
+    }
+
+    let data = deinterleave(&codewords, version, level)?;
+    decode_segments(&data, version)
+}
+
+/// Undo block interleaving and correct each block; returns the data codewords
+fn deinterleave(codewords: &[u8], version: usize, level: usize) -> Result<Vec<u8>, String> {
+    let num_blocks = ERROR_CORRECTION_BLOCKS[level][version] as usize;
+    let ecc_len = ECC_CODEWORDS_PER_BLOCK[level][version] as usize;
+    let num_short = num_blocks - codewords.len() % num_blocks;
+    let short_len = codewords.len() / num_blocks;
+    let short_data = short_len - ecc_len;
+
+    // Long blocks carry one extra data codeword, which short blocks skip
+    let mut blocks: Vec<Vec<u8>> = (0..num_blocks).map(|j| vec![0; short_len + usize::from(j >= num_short)]).collect();
+    let mut next = codewords.iter();
+    for i in 0..=short_len {
+        for (j, block) in blocks.iter_mut().enumerate() {
+            if i == short_data && j < num_short {
+                continue;
+            }
+            let index = if j < num_short && i > short_data { i - 1 } else { i };
+            block[index] = *next.next().ok_or("codewords truncated")?;
+        }
+    }
+
+    let gf = Gf::new();
+    let mut data = Vec::new();
+    for block in &mut blocks {
+        gf.correct(block, ecc_len)?;
+        data.extend_from_slice(&block[..block.len() - ecc_len]);
+    }
+    Ok(data)
+}
+
+// ─── Reed-Solomon ───────────────────────────────────
+
+/// GF(256) with the QR polynomial x⁸ + x⁴ + x³ + x² + 1
+struct Gf {
+    exp: [u8; 512],
+    log: [u8; 256],
+}
+
+impl Gf {
+    fn new() -> Self {
+        let (mut exp, mut log) = ([0u8; 512], [0u8; 256]);
+        let mut x = 1u16;
+        for (i, e) in exp.iter_mut().take(255).enumerate() {
+            *e = x as u8;
+            log[x as usize] = i as u8;
+            x <<= 1;
+            if x & 0x100 != 0 {
+                x ^= 0x11D;
+            }
+        }
+        exp.copy_within(0..257, 255);
+        Self { exp, log }
+    }
+
+    fn mul(&self, a: u8, b: u8) -> u8 {
+        if a == 0 || b == 0 {
+            0
+        } else {
+            self.exp[self.log[a as usize] as usize + self.log[b as usize] as usize]
+        }
+    }
+
+    fn div(&self, a: u8, b: u8) -> u8 {
+        if a == 0 {
+            0
+        } else {
+            self.exp[self.log[a as usize] as usize + 255 - self.log[b as usize] as usize]
+        }
+    }
+
+    /// α^e
+    fn alpha(&self, e: usize) -> u8 {
+        self.exp[e % 255]
+    }
+
+    /// Evaluate a polynomial stored lowest degree first
+    fn eval(&self, poly: &[u8], x: u8) -> u8 {
+        poly.iter().rev().fold(0, |acc, &c| self.mul(acc, x) ^ c)
+    }
+
+    /// Correct `block` (data then `ecc_len` check codewords) in place
+    fn correct(&self, block: &mut [u8], ecc_len: usize) -> Result<(), String> {
+        let n = block.len();
+        // The codeword is stored highest degree first
+        let syndromes = |block: &[u8]| -> Vec<u8> {
+            (0..ecc_len)
+                .map(|i| block.iter().fold(0, |acc, &c| self.mul(acc, self.alpha(i)) ^ c))
+                .collect()
+        };
+        let s = syndromes(block);
+        if s.iter().all(|&v| v == 0) {
+            return Ok(());
+        }
+
+        // Berlekamp-Massey: error locator Λ
+        let (mut locator, mut previous) = (vec![1u8], vec![1u8]);
+        let (mut errors, mut shift, mut last) = (0usize, 1usize, 1u8);
+        for k in 0..ecc_len {
+            let mut d = s[k];
+            for i in 1..=errors.min(locator.len() - 1) {
+                d ^= self.mul(locator[i], s[k - i]);
+            }
+            if d == 0 {
+                shift += 1;
+                continue;
+            }
+            let coef = self.div(d, last);
+            let mut next = locator.clone();
+            next.resize(next.len().max(previous.len() + shift), 0);
+            for (i, &p) in previous.iter().enumerate() {
+                next[i + shift] ^= self.mul(coef, p);
+            }
+            if 2 * errors <= k {
+                previous = std::mem::replace(&mut locator, next);
+                errors = k + 1 - errors;
+                last = d;
+                shift = 1;
+            } else {
+                locator = next;
+                shift += 1;
+            }
+        }
+        locator.resize(errors + 1, 0);
+        if 2 * errors > ecc_len {
+            return Err("too many errors".into());
+        }
+
+        // Chien search: position p has degree n-1-p, a root at α^-(n-1-p)
+        let positions: Vec<usize> = (0..n).filter(|&p| self.eval(&locator, self.alpha(255 - (n - 1 - p) % 255)) == 0).collect();
+        if positions.len() != errors {
+            return Err("too many errors".into());
+        }
+
+        // Forney: magnitude = X·Ω(X⁻¹) / Λ'(X⁻¹), with Ω = S·Λ mod x^ecc_len
+        let mut omega = vec![0u8; ecc_len];
+        for (i, &si) in s.iter().enumerate() {
+            for (j, &lj) in locator.iter().enumerate() {
+                if i + j < ecc_len {
+                    omega[i + j] ^= self.mul(si, lj);
+                }
+            }
+        }
+        for p in positions {
+            let x = self.alpha(n - 1 - p);
+            let x_inv = self.div(1, x);
+            let mut derivative = 0;
+            for i in (1..locator.len()).step_by(2) {
+                let mut power = 1;
+                for _ in 0..i - 1 {
+                    power = self.mul(power, x_inv);
+                }
+                derivative ^= self.mul(locator[i], power);
+            }
+            if derivative == 0 {
+                return Err("too many errors".into());
+            }
+            block[p] ^= self.div(self.mul(x, self.eval(&omega, x_inv)), derivative);
+        }
+        if syndromes(block).iter().any(|&v| v != 0) {
+            return Err("too many errors".into());
+        }
+        Ok(())
+    }
+}
+
+// ─── Segments ───────────────────────────────────────
+
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl BitReader<'_> {
+    fn remaining(&self) -> usize {
+        self.data.len() * 8 - self.pos
+    }
+
+    fn read(&mut self, bits: usize) -> Result<u32, String> {
+        if bits > self.remaining() {
+            return Err("data ends mid-segment".into());
+        }
+        let mut value = 0;
+        for _ in 0..bits {
+            value = (value << 1) | ((self.data[self.pos >> 3] >> (7 - (self.pos & 7))) & 1) as u32;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+}
+
+const ALPHANUMERIC: &[u8] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ $%*+-./:";
+
+fn decode_segments(data: &[u8], version: usize) -> Result<String, String> {
+    let group = match version {
+        1..=9 => 0,
+        10..=26 => 1,
+        _ => 2,
+    };
+    let mut reader = BitReader { data, pos: 0 };
+    let mut out = Vec::new();
+    while reader.remaining() >= 4 {
+        match reader.read(4)? {
+            0 => break,
+            // Numeric: 3 digits per 10 bits
+            1 => {
+                let mut count = reader.read([10, 12, 14][group])? as usize;
+                while count > 0 {
+                    let digits = count.min(3);
+                    let value = reader.read([4, 7, 10][digits - 1])?;
+                    if value >= 10u32.pow(digits as u32) {
+                        return Err("invalid numeric segment".into());
+                    }
+                    out.extend(format!("{:0width$}", value, width = digits).bytes());
+                    count -= digits;
+                }
+            }
+            // Alphanumeric: 2 characters per 11 bits
+            2 => {
+                let mut count = reader.read([9, 11, 13][group])? as usize;
+                while count > 0 {
+                    let chars = count.min(2);
+                    let value = reader.read(if chars == 2 { 11 } else { 6 })? as usize;
+                    if chars == 2 {
+                        let (a, b) = (value / 45, value % 45);
+                        out.push(*ALPHANUMERIC.get(a).ok_or("invalid alphanumeric segment")?);
+                        out.push(ALPHANUMERIC[b]);
+                    } else {
+                        out.push(*ALPHANUMERIC.get(value).ok_or("invalid alphanumeric segment")?);
+                    }
+                    count -= chars;
+                }
+            }
+            4 => {
+                let count = reader.read([8, 16, 16][group])?;
+                for _ in 0..count {
+                    out.push(reader.read(8)? as u8);
+                }
+            }
+            // ECI designator: the text is treated as UTF-8 regardless
+            7 => {
+                let first = reader.read(8)?;
+                if first & 0x80 != 0 {
+                    reader.read(if first & 0x40 == 0 { 8 } else { 16 })?;
+                }
+            }
+            // Structured append header
+            3 => {
+                reader.read(16)?;
+            }
+            // FNC1 markers
+            5 => {}
+            9 => {
+                reader.read(8)?;
+            }
+            mode => return Err(format!("unsupported segment mode {}", mode)),
+        }
+    }
+    Ok(String::from_utf8(out).unwrap_or_else(|e| e.into_bytes().iter().map(|&b| b as char).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qrcode::bits::Bits;
+    use qrcode::canvas::{Canvas, MaskPattern};
+    use qrcode::ec::{construct_codewords, create_error_correction_code};
+    use qrcode::{Color, EcLevel, QrCode, Version};
+
+    const LEVELS: [EcLevel; 4] = [EcLevel::L, EcLevel::M, EcLevel::Q, EcLevel::H];
+
+    /// Render `code` as grayscale pixels with a 4-module quiet zone, rotated
+    /// by `degrees` around the center
+    fn render(code: &QrCode, scale: usize, degrees: f64) -> (usize, usize, Vec<u8>) {
+        render_colors(&code.to_colors(), scale, degrees)
+    }
+
+    fn render_colors(colors: &[Color], scale: usize, degrees: f64) -> (usize, usize, Vec<u8>) {
+        let width = (colors.len() as f64).sqrt() as usize;
+        let side = (width + 8) * scale;
+        let canvas = side * 3 / 2;
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let c = canvas as f64 / 2.0;
+        let mut pixels = vec![255u8; canvas * canvas];
+        for y in 0..canvas {
+            for x in 0..canvas {
+                // Inverse rotation back into the unrotated code
+                let (dx, dy) = (x as f64 - c, y as f64 - c);
+                let u = cos * dx + sin * dy + side as f64 / 2.0;
+                let v = -sin * dx + cos * dy + side as f64 / 2.0;
+                if u < 0.0 || v < 0.0 {
+                    continue;
+                }
+                let (mx, my) = ((u as usize / scale).wrapping_sub(4), (v as usize / scale).wrapping_sub(4));
+                if mx < width && my < width && colors[my * width + mx] == Color::Dark {
+                    pixels[y * canvas + x] = 20;
+                }
+            }
+        }
+        (canvas, canvas, pixels)
+    }
+
+    /// Invert the given modules of an unrotated render
+    fn invert(pixels: &mut [u8], w: usize, code: &QrCode, scale: usize, modules: impl IntoIterator<Item = (usize, usize)>) {
+        let offset = (w - (code.width() + 8) * scale) / 2 + 4 * scale;
+        for (mx, my) in modules {
+            for py in 0..scale {
+                for px in 0..scale {
+                    let i = (offset + my * scale + py) * w + offset + mx * scale + px;
+                    pixels[i] = 255 - pixels[i];
+                }
+            }
+        }
+    }
+
+    fn png(w: usize, h: usize, pixels: Vec<u8>) -> Vec<u8> {
+        let mut png = Vec::new();
+        image::GrayImage::from_raw(w as u32, h as u32, pixels)
+            .unwrap()
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        png
+    }
+
+    #[test]
+    fn test_decode_rendered_codes() {
+        let cases: [(&str, EcLevel); 4] = [
+            ("forgeai://pair?url=https%3A%2F%2Fgw.local%3A3000&code=K7M2PX", EcLevel::M),
+            ("0123456789012345", EcLevel::L),
+            ("HTTPS://GW.LOCAL:3000/PAIR?CODE=ABC123", EcLevel::Q),
+            (r#"{"url":"https://forge.example.com:3000","code":"ABCDEFGHJK","note":"pairing via the Dashboard, version 7 or larger with H"}"#, EcLevel::H),
+        ];
+        for (text, level) in cases {
+            let code = QrCode::with_error_correction_level(text, level).unwrap();
+            for degrees in [0.0, 90.0, 17.0] {
+                let (w, h, pixels) = render(&code, 4, degrees);
+                assert_eq!(decode_luma(w, h, &pixels).as_deref(), Ok(text), "{} at {}°", text, degrees);
+            }
+        }
+
+        // Encoded images go through the same path
+        let code = QrCode::with_version("https://gw.local/pair?code=XYZ", Version::Normal(4), EcLevel::H).unwrap();
+        let (w, h, pixels) = render(&code, 3, 0.0);
+        assert_eq!(decode_image(&png(w, h, pixels)).as_deref(), Ok("https://gw.local/pair?code=XYZ"));
+    }
+
+    #[test]
+    fn test_decode_versions_and_levels() {
+        // Both sides of the version info (7) and count length (10, 27) boundaries
+        for v in [1, 2, 6, 7, 9, 10, 14, 26, 27, 40] {
+            for level in LEVELS {
+                let text = match level {
+                    EcLevel::M => (31_415_926_535 * v as u64).to_string(),
+                    EcLevel::Q => format!("GW.LOCAL/V{}", v),
+                    _ => format!("pair v{}", v),
+                };
+                let code = QrCode::with_version(&text, Version::Normal(v), level).unwrap();
+                let (w, h, pixels) = render(&code, 3, 0.0);
+                assert_eq!(decode_luma(w, h, &pixels).as_deref(), Ok(text.as_str()), "version {} {:?}", v, level);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_every_mask() {
+        let masks = [
+            MaskPattern::Checkerboard,
+            MaskPattern::HorizontalLines,
+            MaskPattern::VerticalLines,
+            MaskPattern::DiagonalLines,
+            MaskPattern::LargeCheckerboard,
+            MaskPattern::Fields,
+            MaskPattern::Diamonds,
+            MaskPattern::Meadow,
+        ];
+        for v in [2, 7] {
+            for (level, mask) in LEVELS.into_iter().cycle().zip(masks) {
+                let version = Version::Normal(v);
+                let text = format!("forgeai mask {}", mask as u8);
+                let mut bits = Bits::new(version);
+                bits.push_byte_data(text.as_bytes()).unwrap();
+                bits.push_terminator(level).unwrap();
+                let (data, ec) = construct_codewords(&bits.into_bytes(), version, level).unwrap();
+                let mut canvas = Canvas::new(version, level);
+                canvas.draw_all_functional_patterns();
+                canvas.draw_data(&data, &ec);
+                canvas.apply_mask(mask);
+
+                let (w, h, pixels) = render_colors(&canvas.into_colors(), 3, 0.0);
+                assert_eq!(decode_luma(w, h, &pixels).as_deref(), Ok(text.as_str()), "version {} {:?}", v, mask);
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_damaged_codes() {
+        let text = "https://gw.local/pair?code=XYZ";
+        let patch = |at: usize, side: usize| (at..at + side).flat_map(move |y| (at..at + side).map(move |x| (x, y)));
+
+        // A small patch is within every level's correction capacity
+        for level in LEVELS {
+            let code = QrCode::with_version(text, Version::Normal(4), level).unwrap();
+            let (w, h, mut pixels) = render(&code, 4, 0.0);
+            invert(&mut pixels, w, &code, 4, patch(12, 2));
+            assert_eq!(decode_luma(w, h, &pixels).as_deref(), Ok(text), "{:?}", level);
+        }
+
+        // A larger one only at H
+        let code = QrCode::with_version(text, Version::Normal(4), EcLevel::H).unwrap();
+        let (w, h, mut pixels) = render(&code, 4, 0.0);
+        invert(&mut pixels, w, &code, 4, patch(10, 3));
+        assert_eq!(decode_luma(w, h, &pixels).as_deref(), Ok(text));
+
+        // Damage past the correction capacity is an error, never other text
+        let code = QrCode::with_version(text, Version::Normal(4), EcLevel::L).unwrap();
+        let (w, h, mut pixels) = render(&code, 4, 0.0);
+        invert(&mut pixels, w, &code, 4, patch(9, 14));
+        assert!(decode_luma(w, h, &pixels).is_err());
+
+        // Either copy of the format info is enough
+        let first_format = (0..6).map(|i| (8, i)).chain([(8, 7), (8, 8), (7, 8)]).chain((0..6).map(|i| (i, 8)));
+        let (w, h, mut pixels) = render(&code, 4, 0.0);
+        invert(&mut pixels, w, &code, 4, first_format);
+        assert_eq!(decode_luma(w, h, &pixels).as_deref(), Ok(text));
+
+        // And either copy of the version info
+        let code = QrCode::with_version(text, Version::Normal(7), EcLevel::M).unwrap();
+        let size = code.width();
+        let (w, h, mut pixels) = render(&code, 4, 0.0);
+        invert(&mut pixels, w, &code, 4, (0..18).map(|i| (size - 11 + i % 3, i / 3)));
+        assert_eq!(decode_luma(w, h, &pixels).as_deref(), Ok(text));
+    }
+
+    #[test]
+    fn test_reject_invalid_input() {
+        assert!(decode_luma(0, 0, &[]).is_err());
+        assert!(decode_luma(100, 100, &[255; 50]).is_err());
+        assert!(decode_image(b"not an image").is_err());
+
+        let code = QrCode::new("forgeai://pair").unwrap();
+        let (w, h, pixels) = render(&code, 3, 0.0);
+        let encoded = png(w, h, pixels.clone());
+        assert!(decode_image(&encoded[..encoded.len() / 2]).is_err());
+
+        // Uniform images and noise hold no finder patterns
+        for fill in [0u8, 255] {
+            assert!(decode_luma(100, 100, &[fill; 100 * 100]).is_err());
+        }
+        let mut seed = 0x2545_f491u32;
+        let noise: Vec<u8> = (0..200 * 200)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 24) as u8
+            })
+            .collect();
+        assert!(decode_luma(200, 200, &noise).is_err());
+
+        // A lone finder pattern is not a code
+        let offset = (w - (code.width() + 8) * 3) / 2 + 4 * 3;
+        let finder = offset..offset + 7 * 3;
+        let lone: Vec<u8> = pixels
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| if finder.contains(&(i % w)) && finder.contains(&(i / w)) { p } else { 255 })
+            .collect();
+        assert!(decode_luma(w, h, &lone).is_err());
+    }
+
+    #[test]
+    fn test_reed_solomon_capacity() {
+        let gf = Gf::new();
+        let data: Vec<u8> = (0..40u8).map(|i| i.wrapping_mul(37)).collect();
+        for ecc_len in [7, 10, 18, 30] {
+            let mut clean = data.clone();
+            clean.extend(create_error_correction_code(&data, ecc_len));
+            let damage = |errors: usize| {
+                let mut block = clean.clone();
+                for k in 0..errors {
+                    block[k * 3] ^= 0x5a + k as u8;
+                }
+                block
+            };
+
+            // Up to ecc_len / 2 wrong codewords are corrected
+            let mut block = damage(ecc_len / 2);
+            gf.correct(&mut block, ecc_len).unwrap();
+            assert_eq!(block, clean);
+
+            // One more is detected rather than miscorrected
+            let mut block = damage(ecc_len / 2 + 1);
+            assert!(gf.correct(&mut block, ecc_len).is_err(), "{} check codewords", ecc_len);
+        }
+    }
+
+    #[test]
+    fn test_decode_segments() {
+        // Count lengths differ for versions 1-9, 10-26 and 27-40
+        for v in [1, 10, 27] {
+            let mut bits = Bits::new(Version::Normal(v));
+            bits.push_numeric_data(b"0123456").unwrap();
+            bits.push_alphanumeric_data(b"AB-C1").unwrap();
+            bits.push_byte_data("é/x".as_bytes()).unwrap();
+            bits.push_terminator(EcLevel::L).unwrap();
+            assert_eq!(decode_segments(&bits.into_bytes(), v as usize).as_deref(), Ok("0123456AB-C1é/x"));
+        }
+
+        // Three digits worth 1000
+        assert!(decode_segments(&[0x10, 0x0f, 0xe8], 1).is_err());
+        // Five bytes announced, one present
+        assert!(decode_segments(&[0x40, 0x50, 0x41], 1).is_err());
+        // Kanji
+        assert!(decode_segments(&[0x80, 0x00], 1).is_err());
+    }
+}
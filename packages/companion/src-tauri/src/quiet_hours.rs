@@ -0,0 +1,146 @@
+//! # Quiet Hours
+//!
+//! Times when the companion keeps its voice down: a daily window
+//! (`quietHours.start`–`end`, local time, may span midnight) and a
+//! do-not-disturb switch that holds until turned off. While either is on,
+//! the playback queue drops speech and earcons; spoken text is shown as a
+//! notification instead, unless `notify` is off. Alarms still ring unless
+//! `muteAlarms` is set, and device tests always play.
+
+use chrono::{NaiveTime, Timelike};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+static CONFIG: Mutex<Option<QuietHoursConfig>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuietHoursConfig {
+    /// Apply the daily window
+    pub enabled: bool,
+    /// Start of the window, `HH:MM`
+    pub start: String,
+    /// End of the window, `HH:MM`
+    pub end: String,
+    /// Quiet regardless of the time
+    pub do_not_disturb: bool,
+    /// Show muted speech as a notification
+    pub notify: bool,
+    /// Silence alarms too
+    pub mute_alarms: bool,
+}
+
+impl Default for QuietHoursConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            start: "22:00".into(),
+            end: "07:00".into(),
+            do_not_disturb: false,
+            notify: true,
+            mute_alarms: false,
+        }
+    }
+}
+
+fn parse_time(text: &str) -> Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(text.trim(), "%H:%M").map_err(|_| format!("Invalid quiet hours time '{}' (use HH:MM)", text))
+}
+
+impl QuietHoursConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if parse_time(&self.start)? == parse_time(&self.end)? {
+            return Err("Quiet hours must not start and end at the same time".into());
+        }
+        Ok(())
+    }
+
+    /// Whether output is muted at `now`
+    fn quiet_at(&self, now: NaiveTime) -> bool {
+        if self.do_not_disturb {
+            return true;
+        }
+        let (Ok(start), Ok(end)) = (parse_time(&self.start), parse_time(&self.end)) else {
+            return false;
+        };
+        // Minute resolution, like the settings
+        let now = NaiveTime::from_hms_opt(now.hour(), now.minute(), 0).unwrap_or(now);
+        self.enabled
+            && if start < end {
+                start <= now && now < end
+            } else {
+                now >= start || now < end
+            }
+    }
+}
+
+/// Use `config` from now on
+pub fn set_config(config: &QuietHoursConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+fn config() -> QuietHoursConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+/// What the playback queue is about to play
+#[derive(Debug, Clone, Copy)]
+pub enum Output<'a> {
+    /// Spoken audio, with its text when known
+    Speech(Option<&'a str>),
+    Earcon,
+    Alarm,
+    /// A device test the user started
+    Test,
+}
+
+/// Quiet right now
+pub fn active() -> bool {
+    config().quiet_at(chrono::Local::now().time())
+}
+
+/// Whether `output` may play now; muted speech with known text is shown as
+/// a notification instead
+pub fn allows(output: Output) -> bool {
+    let config = config();
+    let exempt = match output {
+        Output::Test => true,
+        Output::Alarm => !config.mute_alarms,
+        Output::Speech(_) | Output::Earcon => false,
+    };
+    if exempt || !config.quiet_at(chrono::Local::now().time()) {
+        return true;
+    }
+    match output {
+        Output::Speech(Some(text)) if config.notify && !text.trim().is_empty() => {
+            tracing::debug!("[QuietHours] Speech shown as a notification");
+            crate::events::notify("ForgeAI", text);
+        }
+        _ => tracing::debug!("[QuietHours] {:?} muted", output),
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quiet_window() {
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 30).unwrap();
+        let night = QuietHoursConfig { enabled: true, ..Default::default() };
+        assert!(night.quiet_at(at(23, 15)) && night.quiet_at(at(0, 0)) && night.quiet_at(at(6, 59)));
+        assert!(!night.quiet_at(at(7, 0)) && !night.quiet_at(at(21, 59)));
+
+        let lunch = QuietHoursConfig { enabled: true, start: "12:00".into(), end: "13:30".into(), ..Default::default() };
+        assert!(lunch.quiet_at(at(12, 0)) && !lunch.quiet_at(at(13, 30)));
+        assert!(!QuietHoursConfig { enabled: false, ..lunch.clone() }.quiet_at(at(12, 30)));
+        assert!(QuietHoursConfig { do_not_disturb: true, ..Default::default() }.quiet_at(at(15, 0)));
+
+        assert!(night.validate().is_ok());
+        assert!(QuietHoursConfig { start: "25:00".into(), ..Default::default() }.validate().is_err());
+        assert!(QuietHoursConfig { end: "22:00".into(), ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,260 @@
+//! # File Quota
+//!
+//! Keeps a chat loop from filling the disk. Every file the assistant
+//! creates — `write_file` to a new path, `copy_file`, `download_file` — is
+//! recorded in `created_files.db` with its size, and reported to the
+//! Gateway as an `audit_event` with outcome `file_created`. Overwriting a
+//! recorded file updates its size; files the user already had are never
+//! counted.
+//!
+//! Once the recorded files reach `quota.maxMb`, writes that would create
+//! more fail with an error that points at `quota_cleanup`, which deletes
+//! them (all of them, or those older than some hours). `quota_status`
+//! reports the space used; files deleted or moved by other means stop
+//! counting the next time it is measured.
+
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+static DB: OnceLock<Mutex<Connection>> = OnceLock::new();
+static CONFIG: Mutex<Option<QuotaConfig>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct QuotaConfig {
+    pub enabled: bool,
+    /// Space the assistant's files may take, in megabytes
+    pub max_mb: u64,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        Self { enabled: true, max_mb: 2048 }
+    }
+}
+
+impl QuotaConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=1_048_576).contains(&self.max_mb) {
+            return Err("The file quota must be between 1 MB and 1 TB".into());
+        }
+        Ok(())
+    }
+}
+
+fn config() -> QuotaConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+pub fn set_config(config: &QuotaConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QuotaStatus {
+    pub enabled: bool,
+    pub used_bytes: u64,
+    pub max_bytes: u64,
+    pub files: usize,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CleanupReport {
+    pub deleted: usize,
+    pub freed_bytes: u64,
+    /// Files that could not be deleted, with why
+    pub failed: Vec<String>,
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS created_files (
+             path TEXT PRIMARY KEY,
+             action TEXT NOT NULL,
+             bytes INTEGER NOT NULL,
+             created_at TEXT NOT NULL
+         );",
+    )
+}
+
+fn with_db<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let db = match DB.get() {
+        Some(db) => db,
+        None => {
+            let dir = crate::users::data_dir().ok_or("Cannot determine data directory")?;
+            let _ = std::fs::create_dir_all(&dir);
+            let conn = Connection::open(dir.join("created_files.db")).map_err(|e| format!("Quota DB error: {}", e))?;
+            init_schema(&conn).map_err(|e| format!("Quota DB error: {}", e))?;
+            DB.get_or_init(|| Mutex::new(conn))
+        }
+    };
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    f(&conn).map_err(|e| format!("Quota DB error: {}", e))
+}
+
+/// The key a file is recorded under
+fn key(path: &str) -> String {
+    std::fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path)).to_string_lossy().to_string()
+}
+
+/// Recorded files, oldest first, with the size they have now; rows of
+/// files that are gone are dropped
+fn measure(conn: &Connection) -> rusqlite::Result<Vec<(String, String, u64)>> {
+    let mut stmt = conn.prepare("SELECT path, created_at FROM created_files ORDER BY created_at")?;
+    let rows: Vec<(String, String)> = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?.collect::<Result<_, _>>()?;
+    let mut files = Vec::new();
+    for (path, created_at) in rows {
+        match std::fs::metadata(&path) {
+            Ok(meta) if meta.is_file() => files.push((path, created_at, meta.len())),
+            _ => {
+                conn.execute("DELETE FROM created_files WHERE path = ?1", params![path])?;
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn used_bytes() -> Result<u64, String> {
+    with_db(|conn| Ok(measure(conn)?.iter().map(|(_, _, bytes)| bytes).sum()))
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Whether `adding` more bytes fits in the quota
+pub fn check(adding: u64) -> Result<(), String> {
+    let config = config();
+    if !config.enabled {
+        return Ok(());
+    }
+    let max = config.max_mb * 1024 * 1024;
+    let used = used_bytes()?;
+    if used + adding > max {
+        return Err(format!(
+            "File quota reached: files created by the assistant use {} of {}. Free space with quota_cleanup first.",
+            crate::local_actions::format_size(used),
+            crate::local_actions::format_size(max)
+        ));
+    }
+    Ok(())
+}
+
+/// Whether writing `len` bytes to `path` fits in the quota; only growth of
+/// the assistant's own files counts
+pub fn check_write(path: &str, len: u64) -> Result<(), String> {
+    let existing = std::fs::metadata(path).ok();
+    if existing.is_some() && !is_recorded(path) {
+        return Ok(());
+    }
+    check(len.saturating_sub(existing.map_or(0, |m| m.len())))
+}
+
+/// Whether `path` is a file the assistant created
+pub fn is_recorded(path: &str) -> bool {
+    let key = key(path);
+    with_db(|conn| conn.query_row("SELECT COUNT(*) FROM created_files WHERE path = ?1", params![key], |r| r.get::<_, i64>(0)))
+        .is_ok_and(|n| n > 0)
+}
+
+/// Record that `action` wrote `path`. `existed` is whether something was
+/// there before; a file the user had is not the assistant's.
+pub fn record(action: &str, path: &str, existed: bool) {
+    if existed && !is_recorded(path) {
+        return;
+    }
+    let Ok(meta) = std::fs::metadata(path) else { return };
+    let key = key(path);
+    let now = chrono::Utc::now().to_rfc3339();
+    let recorded = with_db(|conn| {
+        conn.execute(
+            "INSERT INTO created_files (path, action, bytes, created_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (path) DO UPDATE SET bytes = excluded.bytes",
+            params![key, action, meta.len() as i64, now],
+        )
+    });
+    if let Err(e) = recorded {
+        tracing::warn!("[Quota] {} not recorded: {}", key, e);
+        return;
+    }
+    if existed {
+        return;
+    }
+    crate::outbox::send_or_queue(
+        &format!("audit:file_created:{}:{}", key, now),
+        crate::e2e::wrap_outgoing(serde_json::json!({
+            "type": "audit_event",
+            "action": action,
+            "outcome": "file_created",
+            "path": key,
+            "bytes": meta.len(),
+            "at": now,
+        })),
+    );
+}
+
+pub fn status() -> Result<QuotaStatus, String> {
+    let config = config();
+    let (files, used_bytes) = with_db(|conn| {
+        let files = measure(conn)?;
+        Ok((files.len(), files.iter().map(|(_, _, bytes)| bytes).sum()))
+    })?;
+    Ok(QuotaStatus { enabled: config.enabled, used_bytes, max_bytes: config.max_mb * 1024 * 1024, files })
+}
+
+/// Delete the files the assistant created, or only those older than
+/// `older_than_hours`
+pub fn cleanup(older_than_hours: Option<u32>) -> Result<CleanupReport, String> {
+    let cutoff = older_than_hours.map(|h| (chrono::Utc::now() - chrono::Duration::hours(h as i64)).to_rfc3339());
+    let files = with_db(measure)?;
+    let mut report = CleanupReport::default();
+    for (path, created_at, bytes) in files {
+        if cutoff.as_ref().is_some_and(|cutoff| created_at >= *cutoff) {
+            continue;
+        }
+        if !crate::safety::check_file_operation("delete", &path).allowed {
+            report.failed.push(format!("{}: not allowed to delete", path));
+            continue;
+        }
+        match std::fs::remove_file(Path::new(&path)) {
+            Ok(()) => {
+                report.deleted += 1;
+                report.freed_bytes += bytes;
+                let _ = with_db(|conn| conn.execute("DELETE FROM created_files WHERE path = ?1", params![path]));
+            }
+            Err(e) => report.failed.push(format!("{}: {}", path, e)),
+        }
+    }
+    tracing::info!("[Quota] Cleanup deleted {} files ({} bytes)", report.deleted, report.freed_bytes);
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure_drops_missing_files() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        let file = std::env::temp_dir().join(format!("quota-test-{}.txt", std::process::id()));
+        std::fs::write(&file, b"hello").unwrap();
+        let path = file.to_string_lossy().to_string();
+        for (p, at) in [(path.as_str(), "2026-01-02"), ("/no/such/file.txt", "2026-01-01")] {
+            conn.execute("INSERT INTO created_files VALUES (?1, 'write_file', 1, ?2)", params![p, at]).unwrap();
+        }
+
+        let files = measure(&conn).unwrap();
+        assert_eq!(files, vec![(path, "2026-01-02".to_string(), 5)]);
+        let rows: i64 = conn.query_row("SELECT COUNT(*) FROM created_files", [], |r| r.get(0)).unwrap();
+        assert_eq!(rows, 1);
+        let _ = std::fs::remove_file(file);
+
+        assert!(QuotaConfig::default().validate().is_ok());
+        assert!(QuotaConfig { max_mb: 0, ..Default::default() }.validate().is_err());
+    }
+}
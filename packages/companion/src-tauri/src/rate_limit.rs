@@ -0,0 +1,115 @@
+//! # Gateway Rate Limits
+//!
+//! When the Gateway answers `429 Too Many Requests`, idempotent calls
+//! (GET, PUT, DELETE, ...) are retried transparently once the `Retry-After`
+//! delay has passed — as long as that delay is short. Non-idempotent calls
+//! (a chat message, a voice upload) are never replayed: they fail with a
+//! typed [`RateLimited`] error and a `rate-limited` event lets the UI show
+//! when to try again.
+
+use crate::connection::GatewayError;
+use serde::Serialize;
+use std::time::Duration;
+
+/// Transparent retries per call
+const MAX_RETRIES: u32 = 2;
+/// Longer waits are surfaced to the caller instead of blocking the command
+const MAX_TRANSPARENT_WAIT: Duration = Duration::from_secs(10);
+/// Delay used when a 429 carries no (valid) `Retry-After`
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(2);
+
+/// The Gateway refused a request because of rate limiting
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RateLimited {
+    pub endpoint: String,
+    /// Seconds until the Gateway accepts requests again, if it said so
+    pub retry_after_secs: Option<u64>,
+}
+
+impl std::fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.retry_after_secs {
+            Some(secs) => write!(f, "Gateway rate limit reached — try again in {}s", secs),
+            None => f.write_str("Gateway rate limit reached — try again shortly"),
+        }
+    }
+}
+
+/// Parse a `Retry-After` value: delta-seconds or an HTTP date
+fn parse_retry_after(value: &str, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    // A date in the past means "now"
+    Some((at.with_timezone(&chrono::Utc) - now).to_std().unwrap_or_default())
+}
+
+/// `Retry-After` of a response, if present and valid
+pub fn retry_after(resp: &reqwest::Response) -> Option<Duration> {
+    resp.headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| parse_retry_after(v, chrono::Utc::now()))
+}
+
+/// Send a request built by `build`, handling 429 responses. Idempotent
+/// requests are rebuilt and retried after `Retry-After`; others fail with
+/// [`GatewayError::RateLimited`].
+pub async fn send<F>(build: F) -> Result<reqwest::Response, GatewayError>
+where
+    F: Fn() -> reqwest::RequestBuilder,
+{
+    let mut attempt = 0;
+    loop {
+        let (client, request) = build().build_split();
+        let request = request.map_err(|e| format!("Invalid Gateway request: {}", e))?;
+        let idempotent = request.method().is_idempotent();
+        let endpoint = format!("{} {}", request.method(), request.url().path());
+
+        let resp = crate::netstats::send(reqwest::RequestBuilder::from_parts(client, request))
+            .await
+            .map_err(|e| format!("Gateway request failed: {}", e))?;
+        if resp.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Ok(resp);
+        }
+
+        let wait = retry_after(&resp);
+        let delay = wait.unwrap_or(DEFAULT_RETRY_AFTER);
+        if idempotent && attempt < MAX_RETRIES && delay <= MAX_TRANSPARENT_WAIT {
+            attempt += 1;
+            tracing::warn!("[RateLimit] {} limited, retrying in {:?} ({}/{})", endpoint, delay, attempt, MAX_RETRIES);
+            tokio::time::sleep(delay).await;
+            continue;
+        }
+
+        let limited = RateLimited {
+            endpoint,
+            retry_after_secs: wait.map(|d| d.as_secs().max(1)),
+        };
+        tracing::warn!("[RateLimit] {}: {}", limited.endpoint, limited);
+        crate::events::emit("rate-limited", &limited);
+        return Err(GatewayError::RateLimited(limited));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2026 07:28:00 GMT")
+            .unwrap()
+            .with_timezone(&chrono::Utc);
+        assert_eq!(parse_retry_after("120", now), Some(Duration::from_secs(120)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2026 07:28:30 GMT", now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2026 07:00:00 GMT", now), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+}
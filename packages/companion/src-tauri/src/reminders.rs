@@ -0,0 +1,199 @@
+//! # Reminders
+//!
+//! "Remind me to call Ana at five": reminders are kept in `reminders.json`
+//! and fired by the companion itself, so they go off even when the Gateway
+//! is offline. A due reminder shows an OS notification, emits
+//! `reminder-fired` and is announced out loud (see `voice::announce`),
+//! queued behind whatever is playing.
+//!
+//! Reminders that fell due while the companion was not running fire as
+//! soon as it starts. Fired reminders are removed.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Longest sleep between checks, so clock changes and system sleep are noticed
+const MAX_WAIT: Duration = Duration::from_secs(60);
+
+/// Serializes access to the reminders file
+static LOCK: Mutex<()> = Mutex::new(());
+/// Wakes the scheduler when reminders change
+static CHANGED: OnceLock<Notify> = OnceLock::new();
+
+fn changed() -> &'static Notify {
+    CHANGED.get_or_init(Notify::new)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reminder {
+    pub id: String,
+    pub text: String,
+    /// RFC 3339 (UTC)
+    pub due_at: String,
+    pub created_at: String,
+}
+
+impl Reminder {
+    fn due(&self) -> Option<DateTime<Utc>> {
+        DateTime::parse_from_rfc3339(&self.due_at).ok().map(|t| t.with_timezone(&Utc))
+    }
+}
+
+fn reminders_path() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("reminders.json"))
+}
+
+fn load() -> Vec<Reminder> {
+    reminders_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn store(items: &[Reminder]) -> Result<(), String> {
+    let path = reminders_path().ok_or("Cannot determine data directory")?;
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let json = serde_json::to_string_pretty(items).map_err(|e| format!("Serialize error: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("Reminders write error: {}", e))
+}
+
+/// Split off the reminders due at `now` (unparseable ones count as due)
+fn take_due(items: &mut Vec<Reminder>, now: DateTime<Utc>) -> Vec<Reminder> {
+    let (due, pending) = std::mem::take(items).into_iter().partition(|r| r.due().is_none_or(|t| t <= now));
+    *items = pending;
+    due
+}
+
+/// How long to sleep until the next reminder, capped at `MAX_WAIT`
+fn wait_for(items: &[Reminder], now: DateTime<Utc>) -> Duration {
+    items
+        .iter()
+        .filter_map(Reminder::due)
+        .min()
+        .and_then(|t| (t - now).to_std().ok())
+        .map_or(MAX_WAIT, |d| d.min(MAX_WAIT))
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Schedule `text` for `due_at` (RFC 3339, in the future)
+pub fn add(text: &str, due_at: &str) -> Result<Reminder, String> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("Reminder text is empty".into());
+    }
+    let due = DateTime::parse_from_rfc3339(due_at)
+        .map_err(|e| format!("Invalid time '{}': {}", due_at, e))?
+        .with_timezone(&Utc);
+    let now = Utc::now();
+    if due <= now {
+        return Err("The reminder time is in the past".into());
+    }
+    let reminder = Reminder {
+        id: format!("rem-{}", now.timestamp_millis()),
+        text: text.to_string(),
+        due_at: due.to_rfc3339(),
+        created_at: now.to_rfc3339(),
+    };
+    {
+        let _guard = LOCK.lock().map_err(|e| e.to_string())?;
+        let mut items = load();
+        items.push(reminder.clone());
+        items.sort_by(|a, b| a.due_at.cmp(&b.due_at));
+        store(&items)?;
+    }
+    tracing::info!("[Reminders] Set {} for {}", reminder.id, reminder.due_at);
+    changed().notify_one();
+    Ok(reminder)
+}
+
+/// Pending reminders, soonest first
+pub fn list() -> Vec<Reminder> {
+    let _guard = LOCK.lock();
+    load()
+}
+
+/// Cancel a pending reminder; false when there is none with `id`
+pub fn cancel(id: &str) -> Result<bool, String> {
+    let _guard = LOCK.lock().map_err(|e| e.to_string())?;
+    let mut items = load();
+    let before = items.len();
+    items.retain(|r| r.id != id);
+    if items.len() == before {
+        return Ok(false);
+    }
+    store(&items)?;
+    changed().notify_one();
+    Ok(true)
+}
+
+async fn fire(reminder: Reminder) {
+    tracing::info!("[Reminders] Firing {}", reminder.id);
+    crate::events::notify("Reminder", &reminder.text);
+    crate::events::emit("reminder-fired", &reminder);
+    crate::webhooks::fire("reminder_fired", &reminder);
+    if let Err(e) = crate::voice::announce(&format!("Reminder: {}", reminder.text)).await {
+        tracing::warn!("[Reminders] Could not announce {}: {}", reminder.id, e);
+    }
+}
+
+/// Fire reminders as they fall due
+pub fn spawn_scheduler() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            let (due, wait) = match LOCK.lock() {
+                Ok(_guard) => {
+                    let mut items = load();
+                    let now = Utc::now();
+                    let due = take_due(&mut items, now);
+                    if !due.is_empty() {
+                        if let Err(e) = store(&items) {
+                            tracing::warn!("[Reminders] {}", e);
+                        }
+                    }
+                    (due, wait_for(&items, now))
+                }
+                Err(_) => (Vec::new(), MAX_WAIT),
+            };
+            for reminder in due {
+                tauri::async_runtime::spawn(fire(reminder));
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(wait) => {}
+                _ = changed().notified() => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reminder(id: &str, due_at: &str) -> Reminder {
+        Reminder { id: id.into(), text: "Call Ana".into(), due_at: due_at.into(), created_at: String::new() }
+    }
+
+    #[test]
+    fn test_due_reminders_and_wait() {
+        let now = DateTime::parse_from_rfc3339("2026-05-01T17:00:00Z").unwrap().with_timezone(&Utc);
+        let mut items = vec![
+            reminder("missed", "2026-05-01T09:00:00+00:00"),
+            reminder("later", "2026-05-01T17:00:30+00:00"),
+            reminder("tomorrow", "2026-05-02T08:00:00+00:00"),
+        ];
+        let due = take_due(&mut items, now);
+        assert_eq!(due.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(), ["missed"]);
+        assert_eq!(items.len(), 2);
+        assert_eq!(wait_for(&items, now), Duration::from_secs(30));
+        assert_eq!(wait_for(&items[1..], now), MAX_WAIT);
+        assert_eq!(wait_for(&[], now), MAX_WAIT);
+    }
+}
@@ -0,0 +1,410 @@
+//! # Gateway-Pushed Actions
+//!
+//! The Gateway pushes `action_request` messages over the live channel.
+//! Each one is routed through the same safety checks as a local request
+//! (desktop automation included): actions that need confirmation are parked
+//! here, a prompt is sent to both the Gateway and the frontend, and the
+//! action only runs once the local user approves it. The Gateway cannot
+//! pre-confirm an action or approve a parked one — an `action_confirm` from
+//! it can only cancel, unless it relays an approval signed by the user's
+//! phone (see `remote_approval`). With speaker verification on, a high-risk action
+//! asked for by an unrecognized voice is refused instead of parked (see
+//! `speaker`).
+//!
+//! Messages sent back on the channel:
+//! - `action_status`                — `running` ack once past the confirmation gate
+//! - `action_confirmation_required` — the action is waiting for approval
+//! - `action_result`                — final result (or denial)
+//! - `audit_event`                  — the action was blocked, approved, denied or expired
+//!
+//! Parked actions form an approval inbox: `list_pending_approvals` returns
+//! them oldest first, so a batch can be reviewed together and a reloaded UI
+//! picks up where it left off, and `approve_actions` / `deny_actions`
+//! resolve several at once; the files an approved batch touches are
+//! snapshotted first so it can be rolled back (see `rollback`). An action
+//! nobody answers within [`CONFIRMATION_TTL`] expires: the Gateway gets a
+//! failed result, the audit log an `expired` event, and the frontend
+//! `action-confirmation-resolved`.
+//!
+//! Results and audit events go through the outbox, so they survive the
+//! Gateway being unreachable.
+//! Payloads are end-to-end encrypted when a pairing key exists (see `e2e`).
+
+use crate::compression;
+use crate::connection;
+use crate::e2e;
+use crate::events;
+use crate::local_actions::{self, ActionRequest, ActionResult};
+use crate::outbox;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// How long a parked action waits for approval
+pub const CONFIRMATION_TTL: Duration = Duration::from_secs(5 * 60);
+
+static PENDING: OnceLock<Mutex<HashMap<String, PendingAction>>> = OnceLock::new();
+
+struct PendingAction {
+    request: ActionRequest,
+    /// Raw params of a parked `desktop` action
+    desktop: Option<serde_json::Value>,
+    accept_encoding: Vec<String>,
+    verdict: crate::safety::SafetyVerdict,
+    prompt: ConfirmationPrompt,
+    created: Instant,
+}
+
+/// Payload of the `action-confirmation-request` frontend event, and an
+/// entry of the approval inbox
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfirmationPrompt {
+    pub request_id: String,
+    pub action: String,
+    pub target: Option<String>,
+    pub reason: String,
+    pub risk: String,
+    pub requested_at: String,
+    pub expires_at: String,
+}
+
+fn pending() -> &'static Mutex<HashMap<String, PendingAction>> {
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn send(message: serde_json::Value) {
+    let message = e2e::wrap_outgoing(message);
+    if let Err(e) = connection::send_live(message.to_string()) {
+        tracing::error!("[RemoteActions] Failed to send {}: {}", message["type"], e);
+    }
+}
+
+/// Results are queued in the outbox if the channel drops mid-action
+fn send_result(request_id: &str, result: &ActionResult, accept_encoding: &[String]) {
+    let mut message = serde_json::json!({
+        "type": "action_result",
+        "requestId": request_id,
+        "success": result.success,
+        "output": result.output,
+        "risk": result.safety.risk,
+        "continuation": result.continuation,
+        "totalLen": result.total_len,
+    });
+    if let Some((encoded, encoding)) = compression::encode_output(&result.output, accept_encoding) {
+        message["output"] = encoded.into();
+        message["outputEncoding"] = encoding.into();
+    }
+    outbox::send_or_queue(&format!("action_result:{}", request_id), e2e::wrap_outgoing(message));
+}
+
+/// Report a safety decision on a remote action for the Gateway's audit log
+fn audit(request_id: &str, action: &str, outcome: &str, verdict: &crate::safety::SafetyVerdict) {
+    outbox::send_or_queue(
+        &format!("audit:{}:{}", request_id, outcome),
+        e2e::wrap_outgoing(serde_json::json!({
+            "type": "audit_event",
+            "requestId": request_id,
+            "action": action,
+            "outcome": outcome,
+            "risk": verdict.risk,
+            "reason": verdict.reason,
+            "at": chrono::Utc::now().to_rfc3339(),
+        })),
+    );
+}
+
+/// Handle an `action_request` message pushed by the Gateway.
+/// Execution happens on a separate task so the channel loop never stalls.
+pub fn handle_action_request(raw: &serde_json::Value) {
+    let raw = match e2e::unwrap_incoming(raw) {
+        Ok(v) => v,
+        Err(e) => {
+            tracing::warn!("[RemoteActions] Rejected action request: {}", e);
+            send(serde_json::json!({
+                "type": "action_result",
+                "requestId": raw.get("requestId").cloned().unwrap_or_default(),
+                "success": false,
+                "output": format!("REJECTED: {}", e),
+            }));
+            return;
+        }
+    };
+    let request_id = raw.get("requestId").and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let action = raw.get("action").and_then(|v| v.as_str()).unwrap_or("").to_string();
+    let params = raw.get("params").cloned().unwrap_or(serde_json::json!({}));
+    // Codecs the Gateway can decode for large outputs (e.g. ["zstd", "gzip"])
+    let accept_encoding: Vec<String> = raw
+        .get("acceptEncoding")
+        .and_then(|v| v.as_array())
+        .map(|a| a.iter().filter_map(|v| v.as_str().map(String::from)).collect())
+        .unwrap_or_default();
+
+    tracing::info!("[RemoteActions] >>> Action request: {} (id={})", action, request_id);
+
+    // Any `confirmed` flag from the Gateway is ignored: only the local user confirms
+    let request = ActionRequest::from_params(&action, &params, false);
+    let desktop = (action == "desktop").then_some(params);
+
+    tauri::async_runtime::spawn(async move {
+        let gate = match &desktop {
+            Some(params) => local_actions::desktop_confirmation_needed(params),
+            None => local_actions::confirmation_needed(&request),
+        };
+        if let Some(verdict) = gate {
+            if let Err(reason) = crate::speaker::check(&verdict) {
+                let refusal = crate::safety::SafetyVerdict { allowed: false, reason, ..verdict };
+                refuse_unrecognized_voice(&request_id, &request.action, &refusal);
+                return;
+            }
+            park(request_id, request, desktop, accept_encoding, &verdict);
+            return;
+        }
+        run(request_id, request, desktop, accept_encoding, false).await;
+    });
+}
+
+/// Answer a high-risk voice request from someone other than the enrolled speaker
+fn refuse_unrecognized_voice(request_id: &str, action: &str, verdict: &crate::safety::SafetyVerdict) {
+    tracing::warn!("[RemoteActions] Action {} refused: {}", request_id, verdict.reason);
+    audit(request_id, action, "blocked", verdict);
+    outbox::send_or_queue(&format!("action_result:{}", request_id), e2e::wrap_outgoing(serde_json::json!({
+        "type": "action_result",
+        "requestId": request_id,
+        "success": false,
+        "output": format!("DENIED: {}", verdict.reason),
+    })));
+    events::emit("action-blocked", serde_json::json!({ "requestId": request_id, "action": action, "reason": verdict.reason }));
+}
+
+/// Ack and execute an action that is past the confirmation gate
+async fn run(request_id: String, request: ActionRequest, desktop: Option<serde_json::Value>, accept_encoding: Vec<String>, confirmed: bool) {
+    send(serde_json::json!({
+        "type": "action_status",
+        "requestId": request_id,
+        "status": "running",
+    }));
+
+    let result = match desktop.clone() {
+        Some(params) => local_actions::execute_desktop_async(params, confirmed).await,
+        None => local_actions::execute_async(ActionRequest { confirmed, ..request.clone() }).await,
+    };
+    tracing::info!(
+        "[RemoteActions] <<< Action result: {} success={} output_len={}",
+        request.action,
+        result.success,
+        result.output.len()
+    );
+
+    if !result.safety.allowed {
+        audit(&request_id, &request.action, "blocked", &result.safety);
+    } else if !result.awaiting_confirmation() {
+        crate::webhooks::fire("action_executed", serde_json::json!({
+            "source": "gateway",
+            "requestId": request_id,
+            "action": request.action,
+            "success": result.success,
+        }));
+    }
+
+    // Safety net in case an action gates on something the pre-check missed
+    if !confirmed && result.awaiting_confirmation() {
+        park(request_id, request, desktop, accept_encoding, &result.safety);
+    } else {
+        send_result(&request_id, &result, &accept_encoding);
+    }
+}
+
+/// Park an action until it is approved or denied, and prompt for it
+fn park(
+    request_id: String,
+    request: ActionRequest,
+    desktop: Option<serde_json::Value>,
+    accept_encoding: Vec<String>,
+    verdict: &crate::safety::SafetyVerdict,
+) {
+    let target = match &desktop {
+        Some(params) => Some(local_actions::desktop_input(params).0.to_string()),
+        None => request
+            .command
+            .clone()
+            .or_else(|| request.path.clone())
+            .or_else(|| request.process_name.clone()),
+    };
+    let now = chrono::Utc::now();
+    let prompt = ConfirmationPrompt {
+        request_id: request_id.clone(),
+        action: request.action.clone(),
+        target,
+        reason: verdict.reason.clone(),
+        risk: format!("{:?}", verdict.risk),
+        requested_at: now.to_rfc3339(),
+        expires_at: (now + CONFIRMATION_TTL).to_rfc3339(),
+    };
+
+    expire_stale();
+    if let Ok(mut map) = pending().lock() {
+        map.insert(
+            request_id.clone(),
+            PendingAction {
+                request,
+                desktop,
+                accept_encoding,
+                verdict: verdict.clone(),
+                prompt: prompt.clone(),
+                created: Instant::now(),
+            },
+        );
+    }
+    tauri::async_runtime::spawn(async {
+        tokio::time::sleep(CONFIRMATION_TTL).await;
+        expire_stale();
+    });
+
+    tracing::info!("[RemoteActions] Action {} awaiting confirmation", request_id);
+    send(serde_json::json!({
+        "type": "action_confirmation_required",
+        "requestId": request_id,
+        "reason": prompt.reason,
+        "risk": prompt.risk,
+    }));
+    crate::remote_approval::request(&prompt, &verdict.risk);
+    events::emit("action-confirmation-request", prompt);
+}
+
+/// Approve or deny a parked action. Approval must come from the local user
+/// (the frontend); the Gateway may only cancel through [`cancel`].
+pub fn resolve_confirmation(request_id: &str, approved: bool) -> Result<(), String> {
+    if approved {
+        snapshot(&[request_id.to_string()]);
+    }
+    resolve(request_id, approved)
+}
+
+/// Snapshot the files the approved actions `request_ids` will touch, so
+/// they can be rolled back together (see `rollback`)
+fn snapshot(request_ids: &[String]) {
+    let requests: Vec<ActionRequest> = match pending().lock() {
+        Ok(map) => request_ids
+            .iter()
+            .filter_map(|id| map.get(id))
+            .filter(|p| p.desktop.is_none())
+            .map(|p| p.request.clone())
+            .collect(),
+        Err(_) => return,
+    };
+    crate::rollback::snapshot(&requests);
+}
+
+fn resolve(request_id: &str, approved: bool) -> Result<(), String> {
+    expire_stale();
+    let parked = {
+        let mut map = pending().lock().map_err(|e| e.to_string())?;
+        map.remove(request_id)
+            .ok_or(format!("No pending action with id {}", request_id))?
+    };
+    crate::remote_approval::withdraw(request_id);
+
+    let request_id = request_id.to_string();
+    audit(&request_id, &parked.request.action, if approved { "approved" } else { "denied" }, &parked.verdict);
+    if !approved {
+        tracing::info!("[RemoteActions] Action {} denied", request_id);
+        outbox::send_or_queue(&format!("action_result:{}", request_id), e2e::wrap_outgoing(serde_json::json!({
+            "type": "action_result",
+            "requestId": request_id,
+            "success": false,
+            "output": "DENIED: The user declined this action.",
+        })));
+        events::emit("action-confirmation-resolved", serde_json::json!({ "requestId": request_id, "approved": false }));
+        return Ok(());
+    }
+
+    tracing::info!("[RemoteActions] Action {} approved, executing", request_id);
+    events::emit("action-confirmation-resolved", serde_json::json!({ "requestId": request_id, "approved": true }));
+    tauri::async_runtime::spawn(run(request_id, parked.request, parked.desktop, parked.accept_encoding, true));
+    Ok(())
+}
+
+/// Handle an `action_confirm` from the Gateway: a denial cancels the parked
+/// action, an approval only counts with the phone's `signature`
+pub fn handle_gateway_confirm(request_id: &str, approved: bool, signature: Option<&str>) -> Result<(), String> {
+    if !approved {
+        return resolve_confirmation(request_id, false);
+    }
+    let Some(signature) = signature else {
+        tracing::warn!("[RemoteActions] Ignoring Gateway approval of {} — only the local user can approve", request_id);
+        return Ok(());
+    };
+    if let Err(e) = crate::remote_approval::verify(request_id, true, signature) {
+        tracing::warn!("[RemoteActions] Refusing remote approval of {}: {}", request_id, e);
+        return Err(e);
+    }
+    tracing::info!("[RemoteActions] Action {} approved from the phone", request_id);
+    resolve_confirmation(request_id, true)
+}
+
+fn expire_parked(request_id: &str, parked: PendingAction, reason: &str) {
+    tracing::info!("[RemoteActions] Action {} expired: {}", request_id, reason);
+    crate::remote_approval::withdraw(request_id);
+    audit(request_id, &parked.request.action, "expired", &parked.verdict);
+    outbox::send_or_queue(&format!("action_result:{}", request_id), e2e::wrap_outgoing(serde_json::json!({
+        "type": "action_result",
+        "requestId": request_id,
+        "success": false,
+        "output": format!("EXPIRED: {}.", reason),
+    })));
+    events::emit(
+        "action-confirmation-resolved",
+        serde_json::json!({ "requestId": request_id, "approved": false, "expired": true }),
+    );
+}
+
+/// Give up on parked action `request_id` before its time is up
+pub fn expire(request_id: &str, reason: &str) {
+    let parked = pending().lock().ok().and_then(|mut map| map.remove(request_id));
+    if let Some(parked) = parked {
+        expire_parked(request_id, parked, reason);
+    }
+}
+
+/// Drop parked actions older than [`CONFIRMATION_TTL`], telling the Gateway
+/// and the frontend they will not run
+fn expire_stale() {
+    let expired: Vec<(String, PendingAction)> = match pending().lock() {
+        Ok(mut map) => {
+            let stale: Vec<String> = map
+                .iter()
+                .filter(|(_, p)| p.created.elapsed() >= CONFIRMATION_TTL)
+                .map(|(id, _)| id.clone())
+                .collect();
+            stale.into_iter().filter_map(|id| map.remove(&id).map(|p| (id, p))).collect()
+        }
+        Err(_) => return,
+    };
+    for (request_id, parked) in expired {
+        expire_parked(&request_id, parked, "Nobody approved this action in time");
+    }
+}
+
+/// Actions waiting for approval, oldest first
+pub fn pending_approvals() -> Vec<ConfirmationPrompt> {
+    expire_stale();
+    let Ok(map) = pending().lock() else { return Vec::new() };
+    let mut parked: Vec<&PendingAction> = map.values().collect();
+    parked.sort_by_key(|p| p.created);
+    parked.into_iter().map(|p| p.prompt.clone()).collect()
+}
+
+/// Approve or deny each of `request_ids`; ids no longer waiting (answered
+/// elsewhere, or expired) are skipped. Returns the actions still waiting.
+pub fn resolve_all(request_ids: &[String], approved: bool) -> Vec<ConfirmationPrompt> {
+    if approved {
+        snapshot(request_ids);
+    }
+    for request_id in request_ids {
+        if let Err(e) = resolve(request_id, approved) {
+            tracing::info!("[RemoteActions] {}", e);
+        }
+    }
+    pending_approvals()
+}
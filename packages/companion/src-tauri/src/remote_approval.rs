@@ -0,0 +1,242 @@
+//! # Remote Approval
+//!
+//! Lets a phone answer a confirmation when nobody is at the desktop: a
+//! headless machine, or a user across the room. With
+//! `remoteApproval.enabled`, every `High` risk action parked for
+//! confirmation (see `remote_actions`) is also posted to the Gateway as an
+//! `approval_request`, which its mobile UI shows. The action still runs on
+//! one answer only — whichever of the desktop or the phone comes first.
+//!
+//! The Gateway relays the answer but cannot forge it. `remote_approval_enroll`
+//! creates an approval key the user enters once in the mobile UI; the key
+//! stays on the phone and in this profile (`approval_key.bin`, encrypted)
+//! and never passes through the Gateway. An approval arrives as an
+//! `action_confirm` carrying `signature`:
+//!
+//! ```text
+//! base64url(HMAC-SHA256(key, "<requestId>:<nonce>:approve"))
+//! ```
+//!
+//! where `nonce` is the one sent with that `approval_request`, so an answer
+//! cannot be replayed for another action. An unsigned approval is ignored as
+//! before; a denial needs no signature, since the Gateway may always cancel.
+//!
+//! When the phone does not answer within `timeoutSecs`, the action is
+//! denied as expired; once it is answered on the desktop, an
+//! `approval_withdrawn` tells the phone to drop it.
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ring::hmac;
+use ring::rand::SecureRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+static CONFIG: Mutex<Option<RemoteApprovalConfig>> = Mutex::new(None);
+/// Nonce of each request posted to the phone and not answered yet
+static OPEN: Mutex<Option<HashMap<String, String>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RemoteApprovalConfig {
+    pub enabled: bool,
+    /// How long the phone has to answer
+    pub timeout_secs: u64,
+}
+
+impl Default for RemoteApprovalConfig {
+    fn default() -> Self {
+        Self { enabled: false, timeout_secs: 120 }
+    }
+}
+
+impl RemoteApprovalConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        let most = crate::remote_actions::CONFIRMATION_TTL.as_secs();
+        if !(15..=most).contains(&self.timeout_secs) {
+            return Err(format!("Remote approval timeout must be between 15 and {} seconds", most));
+        }
+        Ok(())
+    }
+}
+
+fn config() -> RemoteApprovalConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+pub fn set_config(config: &RemoteApprovalConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+fn key_path() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("approval_key.bin"))
+}
+
+fn key() -> Option<Vec<u8>> {
+    crate::secure_store::read_encrypted(&key_path()?).ok()
+}
+
+fn random(len: usize) -> Result<Vec<u8>, String> {
+    let mut bytes = vec![0u8; len];
+    ring::rand::SystemRandom::new()
+        .fill(&mut bytes)
+        .map_err(|_| "No secure random source".to_string())?;
+    Ok(bytes)
+}
+
+/// What the phone signs to answer `request_id`
+fn signed_message(request_id: &str, nonce: &str, approved: bool) -> String {
+    format!("{}:{}:{}", request_id, nonce, if approved { "approve" } else { "deny" })
+}
+
+fn check_signature(key: &[u8], request_id: &str, nonce: &str, approved: bool, signature: &str) -> Result<(), String> {
+    let tag = URL_SAFE_NO_PAD
+        .decode(signature.trim().trim_end_matches('='))
+        .map_err(|_| "Malformed approval signature".to_string())?;
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::verify(&key, signed_message(request_id, nonce, approved).as_bytes(), &tag)
+        .map_err(|_| "The approval signature does not match the enrolled phone".to_string())
+}
+
+fn send(message: serde_json::Value) {
+    let message = crate::e2e::wrap_outgoing(message);
+    if let Err(e) = crate::connection::send_live(message.to_string()) {
+        tracing::warn!("[RemoteApproval] Failed to send {}: {}", message["type"], e);
+    }
+}
+
+// ─── API ────────────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteApprovalStatus {
+    pub enabled: bool,
+    pub enrolled: bool,
+    pub timeout_secs: u64,
+}
+
+pub fn status() -> RemoteApprovalStatus {
+    let config = config();
+    RemoteApprovalStatus { enabled: config.enabled, enrolled: key().is_some(), timeout_secs: config.timeout_secs }
+}
+
+/// Create a new approval key, replacing any previous one (the phone it was
+/// entered on can no longer approve). Returns it for the mobile UI.
+pub fn enroll() -> Result<String, String> {
+    let path = key_path().ok_or("Cannot determine data directory")?;
+    let key = random(32)?;
+    crate::secure_store::write_encrypted(&path, &key)?;
+    tracing::info!("[RemoteApproval] New approval key created");
+    Ok(URL_SAFE_NO_PAD.encode(key))
+}
+
+/// Delete the approval key; phones can no longer approve
+pub fn forget() -> Result<(), String> {
+    if let Some(path) = key_path().filter(|p| p.exists()) {
+        std::fs::remove_file(path).map_err(|e| format!("Failed to delete the approval key: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Post a parked action to the phone when remote approval applies to it
+pub fn request(prompt: &crate::remote_actions::ConfirmationPrompt, risk: &crate::safety::RiskLevel) {
+    let config = config();
+    if !config.enabled || *risk != crate::safety::RiskLevel::High || key().is_none() {
+        return;
+    }
+    let nonce = match random(16) {
+        Ok(bytes) => URL_SAFE_NO_PAD.encode(bytes),
+        Err(e) => {
+            tracing::warn!("[RemoteApproval] Not posted: {}", e);
+            return;
+        }
+    };
+    if let Ok(mut open) = OPEN.lock() {
+        open.get_or_insert_with(HashMap::new).insert(prompt.request_id.clone(), nonce.clone());
+    }
+    let timeout = Duration::from_secs(config.timeout_secs);
+    tracing::info!("[RemoteApproval] Action {} posted for approval from the phone", prompt.request_id);
+    send(serde_json::json!({
+        "type": "approval_request",
+        "requestId": prompt.request_id,
+        "action": prompt.action,
+        "target": prompt.target,
+        "reason": prompt.reason,
+        "risk": prompt.risk,
+        "nonce": nonce,
+        "expiresAt": (chrono::Utc::now() + timeout).to_rfc3339(),
+    }));
+
+    let request_id = prompt.request_id.clone();
+    tauri::async_runtime::spawn(async move {
+        tokio::time::sleep(timeout).await;
+        if take_nonce(&request_id).is_some() {
+            crate::remote_actions::expire(&request_id, "No answer from the phone in time");
+        }
+    });
+}
+
+fn take_nonce(request_id: &str) -> Option<String> {
+    OPEN.lock().ok()?.as_mut()?.remove(request_id)
+}
+
+/// Close `request_id` in `nonces` if `signature` is the phone's answer to it
+fn settle(nonces: &mut HashMap<String, String>, key: &[u8], request_id: &str, approved: bool, signature: &str) -> Result<(), String> {
+    let nonce = nonces.get(request_id).ok_or(format!("No remote approval is open for {}", request_id))?;
+    check_signature(key, request_id, nonce, approved, signature)?;
+    nonces.remove(request_id);
+    Ok(())
+}
+
+/// Check the phone's `signature` on an answer to `request_id`. The request
+/// can only be answered once; an answer with a bad signature leaves it open,
+/// so a forged one cannot keep the phone from answering.
+pub fn verify(request_id: &str, approved: bool, signature: &str) -> Result<(), String> {
+    let key = key().ok_or("No phone is enrolled for remote approval")?;
+    let mut open = OPEN.lock().map_err(|e| e.to_string())?;
+    let nonces = open.get_or_insert_with(HashMap::new);
+    settle(nonces, &key, request_id, approved, signature)
+}
+
+/// `request_id` was answered elsewhere; let the phone drop it
+pub fn withdraw(request_id: &str) {
+    if take_nonce(request_id).is_some() {
+        send(serde_json::json!({ "type": "approval_withdrawn", "requestId": request_id }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signed_answers() {
+        let key = b"0123456789abcdef0123456789abcdef";
+        let sign = |message: &str| {
+            let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), message.as_bytes());
+            URL_SAFE_NO_PAD.encode(tag.as_ref())
+        };
+        let signature = sign("req-1:n0nce:approve");
+        assert!(check_signature(key, "req-1", "n0nce", true, &signature).is_ok());
+        // Not valid as a denial, for another action, or with another nonce
+        assert!(check_signature(key, "req-1", "n0nce", false, &signature).is_err());
+        assert!(check_signature(key, "req-2", "n0nce", true, &signature).is_err());
+        assert!(check_signature(key, "req-1", "other", true, &signature).is_err());
+        assert!(check_signature(b"another key", "req-1", "n0nce", true, &signature).is_err());
+        assert!(check_signature(key, "req-1", "n0nce", true, "not base64!").is_err());
+
+        // A forged answer leaves the request open for the real one, which closes it
+        let mut nonces = HashMap::from([("req-1".to_string(), "n0nce".to_string())]);
+        assert!(settle(&mut nonces, key, "req-1", true, &sign("req-1:n0nce:deny")).is_err());
+        assert!(settle(&mut nonces, key, "req-1", true, &signature).is_ok());
+        assert!(settle(&mut nonces, key, "req-1", true, &signature).is_err());
+
+        assert!(RemoteApprovalConfig::default().validate().is_ok());
+        assert!(RemoteApprovalConfig { timeout_secs: 5, ..Default::default() }.validate().is_err());
+    }
+}
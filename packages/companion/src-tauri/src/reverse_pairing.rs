@@ -0,0 +1,220 @@
+//! # Reverse Pairing
+//!
+//! The usual flow has the Dashboard show a code that is typed into the
+//! companion. When the Gateway UI is on a phone and the desktop is the
+//! device being added, it is easier the other way around: the companion
+//! generates a short-lived code (plus its E2E public key), registers it as
+//! an offer with the Gateway, and shows it. The user enters the code on the
+//! Gateway, which claims the offer; the companion polls until then and
+//! stores the resulting credentials.
+//!
+//! The code doubles as the pairing secret of the E2E key exchange: it is
+//! mixed into the key derivation (see `e2e`), and the claim must carry the
+//! Gateway's `e2eConfirm` for it, just like code pairing.
+//!
+//! Progress is reported through `reverse-pairing` events with a `status` of
+//! `paired`, `expired`, `cancelled` or `failed`.
+
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How long a generated code stays valid
+const CODE_TTL: Duration = Duration::from_secs(5 * 60);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Code of the offer currently being polled
+static ACTIVE: Mutex<Option<String>> = Mutex::new(None);
+
+/// What a poll response means for the offer
+#[derive(Debug, PartialEq)]
+enum PollOutcome {
+    /// Not claimed yet (or a transient error): poll again
+    Pending,
+    Expired,
+    Claimed,
+    Rejected(String),
+}
+
+fn poll_outcome(status: reqwest::StatusCode, body: &serde_json::Value) -> PollOutcome {
+    if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::GONE {
+        return PollOutcome::Expired;
+    }
+    if !status.is_success() {
+        tracing::warn!("[ReversePairing] Poll returned HTTP {}", status);
+        return PollOutcome::Pending;
+    }
+    match body["status"].as_str() {
+        Some("claimed") => PollOutcome::Claimed,
+        Some("rejected") => PollOutcome::Rejected(body["message"].as_str().unwrap_or("Rejected on the Gateway").to_string()),
+        _ => PollOutcome::Pending,
+    }
+}
+
+/// Code shown to the user, to be entered on the Gateway
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ReverseOffer {
+    pub code: String,
+    pub gateway_url: String,
+    pub expires_in_secs: u64,
+}
+
+fn is_active(code: &str) -> bool {
+    ACTIVE.lock().is_ok_and(|a| a.as_deref() == Some(code))
+}
+
+fn finish(code: &str, status: &str, message: Option<String>) {
+    if let Ok(mut a) = ACTIVE.lock() {
+        if a.as_deref() == Some(code) {
+            *a = None;
+        }
+    }
+    crate::events::emit(
+        "reverse-pairing",
+        serde_json::json!({ "code": code, "status": status, "message": message }),
+    );
+}
+
+fn random_code() -> String {
+    use chacha20poly1305::aead::{rand_core::RngCore, OsRng};
+    let mut bytes = [0u8; 8];
+    OsRng.fill_bytes(&mut bytes);
+    crate::pairing::generate_code(&bytes)
+}
+
+/// Register a new offer with the Gateway and start waiting for it to be claimed.
+/// Any previous offer is abandoned.
+pub async fn start(gateway_url: &str) -> Result<ReverseOffer, String> {
+    let gateway_url = gateway_url.trim_end_matches('/').to_string();
+    let handshake = crate::e2e::Handshake::new();
+    let code = random_code();
+
+    let gw = crate::http::gateway(&gateway_url)?;
+    let request = gw
+        .post("/api/companion/pair/offer")
+        .json(&crate::device::pairing_body(serde_json::json!({
+            "code": code,
+            "e2ePublicKey": handshake.public_key,
+            "expiresIn": CODE_TTL.as_secs(),
+        })))
+        .timeout(Duration::from_secs(10));
+    let resp = crate::netstats::send(request)
+        .await
+        .map_err(|e| format!("Connection failed: {}", e))?;
+
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        return Err("This Gateway does not support pairing with a companion-generated code".into());
+    }
+    if !resp.status().is_success() {
+        return Err(format!("Gateway returned HTTP {}", resp.status()));
+    }
+
+    *ACTIVE.lock().map_err(|e| e.to_string())? = Some(code.clone());
+    tracing::info!("[ReversePairing] Offer {} registered with {}", code, gateway_url);
+
+    let offer = ReverseOffer {
+        code: code.clone(),
+        gateway_url: gateway_url.clone(),
+        expires_in_secs: CODE_TTL.as_secs(),
+    };
+    tauri::async_runtime::spawn(poll(gateway_url, code, handshake));
+    Ok(offer)
+}
+
+/// Wait until the Gateway reports the offer as claimed (or it expires)
+async fn poll(gateway_url: String, code: String, handshake: crate::e2e::Handshake) {
+    let deadline = tokio::time::Instant::now() + CODE_TTL;
+    let path = format!("/api/companion/pair/offer/{}", code);
+
+    while tokio::time::Instant::now() < deadline {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        if !is_active(&code) {
+            return;
+        }
+
+        let gw = match crate::http::gateway(&gateway_url) {
+            Ok(gw) => gw,
+            Err(e) => return finish(&code, "failed", Some(e)),
+        };
+        let resp = match crate::netstats::send(gw.get(&path).timeout(Duration::from_secs(10))).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                // Transient network trouble: keep polling until the deadline
+                tracing::warn!("[ReversePairing] Poll failed: {}", e);
+                continue;
+            }
+        };
+
+        let status = resp.status();
+        let body: serde_json::Value = if status.is_success() {
+            resp.json().await.unwrap_or_default()
+        } else {
+            serde_json::Value::Null
+        };
+        match poll_outcome(status, &body) {
+            PollOutcome::Pending => {}
+            PollOutcome::Expired => return finish(&code, "expired", None),
+            PollOutcome::Rejected(reason) => return finish(&code, "failed", Some(reason)),
+            PollOutcome::Claimed => {
+                let result = crate::commands::finish_pairing(&gateway_url, &body, handshake, &code);
+                return match result {
+                    Ok(()) => {
+                        crate::commands::request_ws_reconnect();
+                        finish(&code, "paired", None)
+                    }
+                    Err(e) => finish(&code, "failed", Some(e)),
+                };
+            }
+        }
+    }
+
+    if is_active(&code) {
+        finish(&code, "expired", None);
+    }
+}
+
+/// Stop waiting for the current offer; the Gateway lets it expire
+pub fn cancel() {
+    let Some(code) = ACTIVE.lock().ok().and_then(|mut a| a.take()) else {
+        return;
+    };
+    crate::events::emit(
+        "reverse-pairing",
+        serde_json::json!({ "code": code, "status": "cancelled" }),
+    );
+    tracing::info!("[ReversePairing] Offer {} cancelled", code);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    #[test]
+    fn test_offer_state_machine() {
+        assert_eq!(poll_outcome(StatusCode::OK, &serde_json::json!({ "status": "pending" })), PollOutcome::Pending);
+        assert_eq!(poll_outcome(StatusCode::OK, &serde_json::json!({ "status": "claimed" })), PollOutcome::Claimed);
+        assert_eq!(
+            poll_outcome(StatusCode::OK, &serde_json::json!({ "status": "rejected", "message": "no" })),
+            PollOutcome::Rejected("no".into())
+        );
+        assert_eq!(poll_outcome(StatusCode::GONE, &serde_json::Value::Null), PollOutcome::Expired);
+        assert_eq!(poll_outcome(StatusCode::NOT_FOUND, &serde_json::Value::Null), PollOutcome::Expired);
+        assert_eq!(poll_outcome(StatusCode::BAD_GATEWAY, &serde_json::Value::Null), PollOutcome::Pending);
+
+        // Only the current offer can be finished; a new offer replaces the old one
+        let (first, second) = (random_code(), random_code());
+        assert_ne!(first, second);
+        *ACTIVE.lock().unwrap() = Some(first.clone());
+        assert!(is_active(&first));
+        *ACTIVE.lock().unwrap() = Some(second.clone());
+        finish(&first, "expired", None);
+        assert!(is_active(&second) && !is_active(&first));
+        cancel();
+        assert!(!is_active(&second));
+        *ACTIVE.lock().unwrap() = Some(first.clone());
+        finish(&first, "paired", None);
+        assert!(ACTIVE.lock().unwrap().is_none());
+    }
+}
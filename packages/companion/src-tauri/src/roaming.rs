@@ -0,0 +1,159 @@
+//! # LAN / Remote Route Roaming
+//!
+//! A Gateway profile may hold both a LAN URL (fast, only reachable at home)
+//! and a remote URL (tunnel / reverse proxy). The heartbeat probes the LAN
+//! URL first and falls back to the remote one; everything else simply uses
+//! `CompanionCredentials::gateway_url`, which `load_credentials` rewrites to
+//! the active route. Until a probe has picked a route the paired URL is
+//! used, and the rewrite is undone before credentials are saved, so the
+//! stored profile never changes because of roaming.
+//!
+//! Route changes emit a `gateway-route-changed` event and reconnect the
+//! live channel on the new URL.
+
+use crate::connection::CompanionCredentials;
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// LAN probes must answer quickly, otherwise we are not really "local"
+const LAN_PROBE_TIMEOUT: Duration = Duration::from_millis(1500);
+const REMOTE_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+static ACTIVE: Mutex<Option<Route>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Route {
+    Lan,
+    Remote,
+}
+
+/// Payload of the `gateway-route-changed` event
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteChange {
+    pub route: Route,
+    pub url: String,
+}
+
+fn url_for(creds: &CompanionCredentials, route: Route) -> Option<&str> {
+    match route {
+        Route::Lan => creds.lan_url.as_deref(),
+        Route::Remote => creds.remote_url.as_deref(),
+    }
+}
+
+/// Point `gateway_url` at the active route (no-op for single-URL profiles
+/// and before the first probe)
+pub fn apply(creds: &mut CompanionCredentials) {
+    let Some(url) = active().and_then(|route| url_for(creds, route)).map(String::from) else {
+        return;
+    };
+    let stored = std::mem::replace(&mut creds.gateway_url, url);
+    creds.stored_gateway_url.get_or_insert(stored);
+}
+
+/// `creds` as they should be stored, with the active route undone
+pub fn unapplied(creds: &CompanionCredentials) -> CompanionCredentials {
+    let mut stored = creds.clone();
+    if let Some(url) = stored.stored_gateway_url.take() {
+        stored.gateway_url = url;
+    }
+    stored
+}
+
+/// Currently active route, if the profile has more than one
+pub fn active() -> Option<Route> {
+    ACTIVE.lock().ok().and_then(|r| *r)
+}
+
+async fn reachable(base_url: &str, timeout: Duration) -> bool {
+    match crate::http::gateway(base_url) {
+        Ok(gw) => crate::netstats::send(gw.get("/health").timeout(timeout))
+            .await
+            .is_ok_and(|r| r.status().is_success()),
+        Err(_) => false,
+    }
+}
+
+/// Probe the routes (LAN first) and switch if needed. Called by the heartbeat.
+pub async fn probe(creds: &CompanionCredentials) {
+    let (Some(lan), Some(remote)) = (creds.lan_url.as_deref(), creds.remote_url.as_deref()) else {
+        return;
+    };
+
+    let route = if reachable(lan, LAN_PROBE_TIMEOUT).await {
+        Route::Lan
+    } else if reachable(remote, REMOTE_PROBE_TIMEOUT).await {
+        Route::Remote
+    } else {
+        // Neither answers — keep the current route; the heartbeat reports offline
+        return;
+    };
+
+    let previous = ACTIVE.lock().ok().and_then(|mut r| r.replace(route));
+    if previous == Some(route) {
+        return;
+    }
+
+    let url = if route == Route::Lan { lan } else { remote }.to_string();
+    tracing::info!("[Roaming] Gateway route: {:?} ({})", route, url);
+    crate::events::emit("gateway-route-changed", RouteChange { route, url });
+    if previous.is_some() {
+        crate::commands::request_ws_reconnect();
+    }
+}
+
+/// Forget the active route (after the profile's URLs change)
+pub fn reset() {
+    if let Ok(mut r) = ACTIVE.lock() {
+        *r = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile() -> CompanionCredentials {
+        serde_json::from_value(serde_json::json!({
+            "gateway_url": "https://gw.example.com",
+            "companion_id": "c1",
+            "role": "user",
+            "lan_url": "http://192.168.1.10:3000",
+            "remote_url": "https://tunnel.example.com",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_route_selection() {
+        // Before any probe the paired URL stays in use
+        reset();
+        let mut creds = profile();
+        apply(&mut creds);
+        assert_eq!(creds.gateway_url, "https://gw.example.com");
+        assert_eq!(creds.stored_gateway_url, None);
+
+        *ACTIVE.lock().unwrap() = Some(Route::Lan);
+        apply(&mut creds);
+        assert_eq!(creds.gateway_url, "http://192.168.1.10:3000");
+        *ACTIVE.lock().unwrap() = Some(Route::Remote);
+        apply(&mut creds);
+        assert_eq!(creds.gateway_url, "https://tunnel.example.com");
+
+        // Saving always writes the paired URL back, and nothing else changes
+        let stored = unapplied(&creds);
+        assert_eq!(stored.gateway_url, "https://gw.example.com");
+        assert_eq!(stored.remote_url.as_deref(), Some("https://tunnel.example.com"));
+        let json = serde_json::to_value(&stored).unwrap();
+        assert!(json.get("stored_gateway_url").is_none());
+
+        // A route without a URL keeps the current one
+        let mut single = profile();
+        single.remote_url = None;
+        apply(&mut single);
+        assert_eq!(single.gateway_url, "https://gw.example.com");
+        reset();
+    }
+}
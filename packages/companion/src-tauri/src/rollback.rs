@@ -0,0 +1,324 @@
+//! # Rollback
+//!
+//! Snapshots the files a confirmed batch of file actions is about to touch,
+//! so the batch can be undone. When the user approves several parked
+//! actions together (`approve_actions`), or confirms a single one, and the
+//! write, delete, move, copy, create and download actions among them reach
+//! at least `rollback.minFiles` files (a directory counts for every file
+//! in it), each of those files is copied to `rollback/<batchId>/` in the
+//! profile first, and `rollback-available` is emitted with the batch.
+//!
+//! `rollback(batchId)` puts the copies back and removes what the batch
+//! created where nothing was before. A batch can be rolled back once, for
+//! `rollback.retentionHours`; older snapshots are deleted.
+//!
+//! Snapshots are plain copies: volume snapshots (VSS) need an elevated
+//! process, which the companion never is. Batches larger than
+//! `rollback.maxMb` are run without a snapshot, with a warning. Shell
+//! commands are not covered: which files they touch is not known upfront.
+
+use crate::local_actions::ActionRequest;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Files past this many in one batch are not snapshotted
+const MAX_FILES: usize = 5_000;
+
+static CONFIG: Mutex<Option<RollbackConfig>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RollbackConfig {
+    pub enabled: bool,
+    /// Files a batch must touch to be snapshotted
+    pub min_files: usize,
+    /// How long a batch can be rolled back
+    pub retention_hours: u32,
+    /// Largest snapshot taken, in megabytes
+    pub max_mb: u64,
+}
+
+impl Default for RollbackConfig {
+    fn default() -> Self {
+        Self { enabled: true, min_files: 5, retention_hours: 24, max_mb: 512 }
+    }
+}
+
+impl RollbackConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_files == 0 {
+            return Err("Rollback needs a batch to touch at least one file".into());
+        }
+        if !(1..=168).contains(&self.retention_hours) {
+            return Err("Rollback retention must be between 1 and 168 hours".into());
+        }
+        if !(1..=10_240).contains(&self.max_mb) {
+            return Err("Rollback snapshots must be limited to between 1 MB and 10 GB".into());
+        }
+        Ok(())
+    }
+}
+
+fn config() -> RollbackConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+pub fn set_config(config: &RollbackConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+/// A snapshotted batch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackBatch {
+    pub id: String,
+    pub created_at: String,
+    pub expires_at: String,
+    /// Actions of the batch, e.g. `delete_file C:\Users\me\logs`
+    pub actions: Vec<String>,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+/// A path the batch touches, and where its copy is
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Entry {
+    path: String,
+    /// File name under `files/`; `None` when nothing was at `path`
+    stored: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    batch: RollbackBatch,
+    entries: Vec<Entry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RollbackReport {
+    pub restored: usize,
+    pub removed: usize,
+    /// Paths that could not be put back, with why
+    pub failed: Vec<String>,
+}
+
+fn root() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("rollback"))
+}
+
+/// Paths `request` writes to, moves or deletes
+fn touched(request: &ActionRequest) -> Vec<String> {
+    let path = request.path.clone();
+    // `move_file` and `copy_file` take the destination in `content`
+    let destination = request.content.clone();
+    match request.action.as_str() {
+        "write_file" | "delete_file" | "create_dir" | "download_file" => path.into_iter().collect(),
+        "move_file" => path.into_iter().chain(destination).collect(),
+        "copy_file" => destination.into_iter().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Files under `path` (itself when it is a file)
+fn files_in(path: &Path, out: &mut Vec<PathBuf>) {
+    if out.len() > MAX_FILES {
+        return;
+    }
+    if path.is_file() {
+        out.push(path.to_path_buf());
+    } else if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            files_in(&entry.path(), out);
+        }
+    }
+}
+
+fn read_manifest(dir: &Path) -> Option<Manifest> {
+    let json = std::fs::read_to_string(dir.join("manifest.json")).ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// Delete snapshots past their retention
+fn prune() {
+    let Some(root) = root() else { return };
+    let Ok(dirs) = std::fs::read_dir(&root) else { return };
+    let now = chrono::Utc::now();
+    for dir in dirs.flatten().map(|e| e.path()) {
+        let expired = read_manifest(&dir)
+            .and_then(|m| chrono::DateTime::parse_from_rfc3339(&m.batch.expires_at).ok())
+            .is_none_or(|expires| expires < now);
+        if expired {
+            let _ = std::fs::remove_dir_all(&dir);
+        }
+    }
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Snapshot what `requests` will touch, when that is enough files to be
+/// worth it. Returns the batch that can be rolled back.
+pub fn snapshot(requests: &[ActionRequest]) -> Option<RollbackBatch> {
+    let config = config();
+    if !config.enabled || crate::simulation::enabled() {
+        return None;
+    }
+    let mut paths: Vec<String> = requests.iter().flat_map(touched).collect();
+    paths.sort();
+    paths.dedup();
+    let mut files = Vec::new();
+    for path in &paths {
+        files_in(Path::new(path), &mut files);
+    }
+    if files.len() < config.min_files && paths.len() < config.min_files {
+        return None;
+    }
+    if files.len() > MAX_FILES {
+        tracing::warn!("[Rollback] Batch touches more than {} files, running it without a snapshot", MAX_FILES);
+        return None;
+    }
+    let bytes: u64 = files.iter().filter_map(|f| std::fs::metadata(f).ok()).map(|m| m.len()).sum();
+    if bytes > config.max_mb * 1024 * 1024 {
+        tracing::warn!("[Rollback] Batch touches {} MB, over the {} MB limit; running it without a snapshot", bytes / (1024 * 1024), config.max_mb);
+        return None;
+    }
+
+    prune();
+    let now = chrono::Utc::now();
+    let id = format!("rb-{}", chrono::Local::now().format("%Y%m%d-%H%M%S-%3f"));
+    let dir = root()?.join(&id);
+    let stored_dir = dir.join("files");
+    if let Err(e) = std::fs::create_dir_all(&stored_dir) {
+        tracing::warn!("[Rollback] No snapshot: {}", e);
+        return None;
+    }
+
+    let mut entries = Vec::new();
+    for (i, file) in files.iter().enumerate() {
+        let name = format!("{:05}", i);
+        if let Err(e) = std::fs::copy(file, stored_dir.join(&name)) {
+            tracing::warn!("[Rollback] No snapshot, could not copy {}: {}", file.display(), e);
+            let _ = std::fs::remove_dir_all(&dir);
+            return None;
+        }
+        entries.push(Entry { path: file.to_string_lossy().to_string(), stored: Some(name) });
+    }
+    // Paths the batch will create, to remove on rollback
+    for path in paths.iter().filter(|p| !Path::new(p).exists()) {
+        entries.push(Entry { path: path.clone(), stored: None });
+    }
+
+    let batch = RollbackBatch {
+        id,
+        created_at: now.to_rfc3339(),
+        expires_at: (now + chrono::Duration::hours(config.retention_hours as i64)).to_rfc3339(),
+        actions: requests
+            .iter()
+            .filter(|r| !touched(r).is_empty())
+            .map(|r| format!("{} {}", r.action, touched(r).join(" → ")))
+            .collect(),
+        files: files.len(),
+        bytes,
+    };
+    let manifest = Manifest { batch: batch.clone(), entries };
+    let json = serde_json::to_string_pretty(&manifest).ok()?;
+    if let Err(e) = std::fs::write(dir.join("manifest.json"), json) {
+        tracing::warn!("[Rollback] No snapshot: {}", e);
+        let _ = std::fs::remove_dir_all(&dir);
+        return None;
+    }
+    tracing::info!("[Rollback] Snapshot {}: {} files ({} bytes)", batch.id, batch.files, batch.bytes);
+    crate::events::emit("rollback-available", &batch);
+    Some(batch)
+}
+
+/// Batches that can still be rolled back, newest first
+pub fn list() -> Vec<RollbackBatch> {
+    prune();
+    let Some(root) = root() else { return Vec::new() };
+    let mut batches: Vec<RollbackBatch> = std::fs::read_dir(root)
+        .map(|dirs| dirs.flatten().filter_map(|e| read_manifest(&e.path())).map(|m| m.batch).collect())
+        .unwrap_or_default();
+    batches.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    batches
+}
+
+/// Put back what batch `batch_id` changed. The snapshot is deleted once
+/// every file is back.
+pub fn rollback(batch_id: &str) -> Result<RollbackReport, String> {
+    prune();
+    if batch_id.contains(['/', '\\', '.']) {
+        return Err(format!("No rollback {}", batch_id));
+    }
+    let dir = root().ok_or("Cannot determine data directory")?.join(batch_id);
+    let manifest = read_manifest(&dir).ok_or(format!("No rollback {} (it may have expired)", batch_id))?;
+
+    let mut report = RollbackReport::default();
+    // Remove what the batch created first, in case a file goes back inside it
+    for entry in manifest.entries.iter().filter(|e| e.stored.is_none()) {
+        let path = Path::new(&entry.path);
+        if !path.exists() {
+            continue;
+        }
+        if !crate::safety::check_file_operation("delete", &entry.path).allowed {
+            report.failed.push(format!("{}: not allowed to remove", entry.path));
+            continue;
+        }
+        let removed = if path.is_dir() { std::fs::remove_dir_all(path) } else { std::fs::remove_file(path) };
+        match removed {
+            Ok(()) => report.removed += 1,
+            Err(e) => report.failed.push(format!("{}: {}", entry.path, e)),
+        }
+    }
+    for entry in &manifest.entries {
+        let Some(stored) = &entry.stored else { continue };
+        if !crate::safety::check_file_operation("write", &entry.path).allowed {
+            report.failed.push(format!("{}: not allowed to write", entry.path));
+            continue;
+        }
+        if let Some(parent) = Path::new(&entry.path).parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        match std::fs::copy(dir.join("files").join(stored), &entry.path) {
+            Ok(_) => report.restored += 1,
+            Err(e) => report.failed.push(format!("{}: {}", entry.path, e)),
+        }
+    }
+
+    tracing::info!(
+        "[Rollback] {} rolled back: {} restored, {} removed, {} failed",
+        batch_id,
+        report.restored,
+        report.removed,
+        report.failed.len()
+    );
+    if report.failed.is_empty() {
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(action: &str, path: &str, content: Option<&str>) -> ActionRequest {
+        ActionRequest::from_params(action, &serde_json::json!({ "path": path, "content": content }), true)
+    }
+
+    #[test]
+    fn test_touched_paths() {
+        assert_eq!(touched(&request("delete_file", "C:\\logs", None)), vec!["C:\\logs"]);
+        assert_eq!(touched(&request("move_file", "a.txt", Some("b.txt"))), vec!["a.txt", "b.txt"]);
+        assert_eq!(touched(&request("copy_file", "a.txt", Some("b.txt"))), vec!["b.txt"]);
+        // Writing a file's content is not a destination
+        assert_eq!(touched(&request("write_file", "a.txt", Some("hello"))), vec!["a.txt"]);
+        assert!(touched(&request("read_file", "a.txt", None)).is_empty());
+
+        assert!(RollbackConfig::default().validate().is_ok());
+        assert!(RollbackConfig { retention_hours: 0, ..Default::default() }.validate().is_err());
+    }
+}
@@ -0,0 +1,243 @@
+//! # Screen Context
+//!
+//! What the user is looking at, so "summarize this" has something to point
+//! at: the focused application and window title, and optionally the text
+//! of that window read by OCR.
+//!
+//! With `screenContext.enabled` a tracker remembers the last focused window
+//! that is not the companion's own, and chat and voice requests carry it as
+//! `screenContext`. OCR text is attached only with `screenContext.includeOcr`; when
+//! the Gateway asks for it through the `get_screen_context` action it needs
+//! the user's confirmation every time.
+//!
+//! | OS      | Focused window                                   | OCR                  |
+//! |---------|--------------------------------------------------|----------------------|
+//! | Windows | `GetForegroundWindow`                            | Windows.Media.Ocr    |
+//! | macOS   | System Events via `osascript` (needs Accessibility) | —                 |
+//! | Linux   | `xdotool` (X11)                                  | —                    |
+
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Interval between focus checks while enabled
+const TRACK_INTERVAL: Duration = Duration::from_secs(2);
+
+static CONFIG: Mutex<Option<ContextConfig>> = Mutex::new(None);
+/// Last focused window outside the companion
+static LAST_EXTERNAL: Mutex<Option<ScreenContext>> = Mutex::new(None);
+/// Wakes the tracker when the configuration changes
+static CHANGED: OnceLock<Notify> = OnceLock::new();
+
+fn changed() -> &'static Notify {
+    CHANGED.get_or_init(Notify::new)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ContextConfig {
+    /// Attach the focused app and window title to chat and voice requests
+    pub enabled: bool,
+    /// Also attach the window's text (Windows OCR)
+    pub include_ocr: bool,
+    /// OCR text beyond this is cut off
+    pub ocr_max_chars: usize,
+}
+
+impl Default for ContextConfig {
+    fn default() -> Self {
+        Self { enabled: false, include_ocr: false, ocr_max_chars: 2000 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScreenContext {
+    /// Process or application name
+    pub app: String,
+    pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pid: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ocr: Option<String>,
+}
+
+fn config() -> ContextConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+/// Use `config` from now on
+pub fn set_config(config: &ContextConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+    if !config.enabled {
+        if let Ok(mut last) = LAST_EXTERNAL.lock() {
+            *last = None;
+        }
+    }
+    changed().notify_one();
+}
+
+// ─── Platform readers ───────────────────────────────
+
+/// `xdotool getactivewindow getwindowname getwindowpid`: title, then pid
+#[cfg_attr(not(any(target_os = "linux", test)), allow(dead_code))]
+fn parse_xdotool(output: &str) -> Option<(String, Option<u32>)> {
+    let mut lines = output.lines();
+    let title = lines.next()?.trim().to_string();
+    Some((title, lines.next().and_then(|p| p.trim().parse().ok())))
+}
+
+/// The osascript below: app name, pid, then the window title (may be missing)
+#[cfg_attr(not(any(target_os = "macos", test)), allow(dead_code))]
+fn parse_osascript(output: &str) -> Option<ScreenContext> {
+    let mut lines = output.lines();
+    let app = lines.next()?.trim().to_string();
+    let pid = lines.next().and_then(|p| p.trim().parse().ok());
+    let title = lines.next().unwrap_or_default().trim().to_string();
+    (!app.is_empty()).then_some(ScreenContext { app, title, pid, ocr: None })
+}
+
+#[cfg_attr(target_os = "macos", allow(dead_code))]
+fn process_name(pid: u32) -> String {
+    use sysinfo::{Pid, ProcessesToUpdate, System};
+    let pid = Pid::from_u32(pid);
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    sys.process(pid).map(|p| p.name().to_string_lossy().to_string()).unwrap_or_default()
+}
+
+#[cfg(target_os = "windows")]
+fn read() -> Option<ScreenContext> {
+    #[link(name = "user32")]
+    extern "system" {
+        fn GetForegroundWindow() -> isize;
+        fn GetWindowTextW(hwnd: isize, text: *mut u16, max: i32) -> i32;
+        fn GetWindowThreadProcessId(hwnd: isize, pid: *mut u32) -> u32;
+    }
+    let hwnd = unsafe { GetForegroundWindow() };
+    if hwnd == 0 {
+        return None;
+    }
+    let mut buf = [0u16; 512];
+    let len = unsafe { GetWindowTextW(hwnd, buf.as_mut_ptr(), buf.len() as i32) };
+    let mut pid = 0u32;
+    unsafe { GetWindowThreadProcessId(hwnd, &mut pid) };
+    Some(ScreenContext {
+        app: process_name(pid),
+        title: String::from_utf16_lossy(&buf[..len.max(0) as usize]),
+        pid: Some(pid),
+        ocr: None,
+    })
+}
+
+#[cfg(target_os = "macos")]
+fn read() -> Option<ScreenContext> {
+    const SCRIPT: &str = r#"tell application "System Events"
+    set p to first application process whose frontmost is true
+    set t to ""
+    try
+        set t to name of front window of p
+    end try
+    return (name of p) & linefeed & (unix id of p) & linefeed & t
+end tell"#;
+    let out = std::process::Command::new("osascript").args(["-e", SCRIPT]).output().ok()?;
+    out.status.success().then(|| parse_osascript(&String::from_utf8_lossy(&out.stdout))).flatten()
+}
+
+#[cfg(target_os = "linux")]
+fn read() -> Option<ScreenContext> {
+    let out = std::process::Command::new("xdotool")
+        .args(["getactivewindow", "getwindowname", "getwindowpid"])
+        .output()
+        .ok()?;
+    let (title, pid) = out.status.success().then(|| parse_xdotool(&String::from_utf8_lossy(&out.stdout))).flatten()?;
+    Some(ScreenContext { app: pid.map(process_name).unwrap_or_default(), title, pid, ocr: None })
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+fn read() -> Option<ScreenContext> {
+    None
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// The focused window right now, with its OCR text when `with_ocr`. Blocking.
+pub fn capture(with_ocr: bool) -> Result<ScreenContext, String> {
+    let mut context = read().ok_or("Cannot determine the focused window")?;
+    if with_ocr {
+        context.ocr = Some(ocr(&context.title)?);
+    }
+    Ok(context)
+}
+
+fn ocr(title: &str) -> Result<String, String> {
+    let max = config().ocr_max_chars;
+    let text = crate::local_actions::read_screen_text(title)?;
+    Ok(text.chars().take(max).collect())
+}
+
+/// What to attach to a chat or voice request: the last focused window
+/// outside the companion, or None when disabled or unknown
+pub async fn for_request() -> Option<ScreenContext> {
+    let config = config();
+    if !config.enabled {
+        return None;
+    }
+    let mut context = LAST_EXTERNAL.lock().ok()?.clone()?;
+    if config.include_ocr {
+        let title = context.title.clone();
+        match tauri::async_runtime::spawn_blocking(move || ocr(&title)).await {
+            Ok(Ok(text)) => context.ocr = Some(text),
+            Ok(Err(e)) => tracing::warn!("[Context] OCR failed: {}", e),
+            Err(_) => {}
+        }
+    }
+    Some(context)
+}
+
+/// Follow the focus while enabled, remembering the last window that is
+/// not the companion's own
+pub fn spawn_tracker() {
+    tauri::async_runtime::spawn(async {
+        let own_pid = std::process::id();
+        loop {
+            if config().enabled {
+                if let Ok(Some(context)) = tauri::async_runtime::spawn_blocking(read).await {
+                    if context.pid != Some(own_pid) && !context.title.is_empty() {
+                        if let Ok(mut last) = LAST_EXTERNAL.lock() {
+                            *last = Some(context);
+                        }
+                    }
+                }
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(TRACK_INTERVAL) => {}
+                _ = changed().notified() => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_focus_parsers() {
+        assert_eq!(parse_xdotool("main.rs - Visual Studio Code\n4242\n"), Some(("main.rs - Visual Studio Code".into(), Some(4242))));
+        assert_eq!(parse_xdotool("Terminal\n"), Some(("Terminal".into(), None)));
+        assert_eq!(parse_xdotool(""), None);
+
+        let context = parse_osascript("Safari\n812\nForgeAI — Docs\n").unwrap();
+        assert_eq!((context.app.as_str(), context.pid, context.title.as_str()), ("Safari", Some(812), "ForgeAI — Docs"));
+        // No Accessibility permission: app and pid, no title
+        assert_eq!(parse_osascript("Finder\n301\n\n").unwrap().title, "");
+        assert_eq!(parse_osascript("\n"), None);
+
+        let json = serde_json::to_value(&context).unwrap();
+        assert!(json.get("ocr").is_none());
+    }
+}
@@ -0,0 +1,357 @@
+//! # User Scripts
+//!
+//! Users add their own actions by dropping [Rhai](https://rhai.rs) files
+//! into the `scripts` folder of their profile's data directory. Each
+//! `<name>.rhai` becomes the action `script.<name>`, advertised to the
+//! Gateway with its description:
+//!
+//! ```rhai
+//! const RISK = "medium";
+//! const DESCRIPTION = "Turn off the office lights";
+//!
+//! fn run(params) {
+//!     action("mqtt_publish", #{ topic: "home/office/light/set", content: "OFF" });
+//!     "The office lights are off"
+//! }
+//! ```
+//!
+//! `RISK` (`safe`, `low`, `medium` or `high`) is required, and high-risk
+//! scripts need confirmation like any other high-risk action. A script has
+//! no file, process or network access of its own: `action(name, params)`
+//! calls one of the built-in actions in [`CALLABLE`] (never shell, delete
+//! or another script) with its usual safety checks, and only when that
+//! action is no riskier than the script declares. `context()` returns the
+//! user's home, the selected project directory, the OS and the shell (see
+//! `exec_context`). Runs are capped in operations, so a runaway loop ends
+//! with an error.
+//!
+//! The folder is polled, and scripts reload whenever a file is added,
+//! changed or removed. Every reload is emitted as `scripts-reloaded` and
+//! the Gateway is sent the new manifest.
+
+use crate::local_actions::{self, ActionRequest};
+use crate::safety::RiskLevel;
+use rhai::{Dynamic, Engine, EvalAltResult, ImmutableString, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// Prefix of script action names
+pub const PREFIX: &str = "script.";
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Rhai operations one run may take
+const MAX_OPERATIONS: u64 = 5_000_000;
+
+/// Built-in actions scripts may call, with their risk
+pub const CALLABLE: &[(&str, RiskLevel)] = &[
+    ("read_file", RiskLevel::Safe),
+    ("list_dir", RiskLevel::Safe),
+    ("file_exists", RiskLevel::Safe),
+    ("file_info", RiskLevel::Safe),
+    ("list_processes", RiskLevel::Safe),
+    ("system_info", RiskLevel::Safe),
+    ("disk_usage", RiskLevel::Safe),
+    ("registry_read", RiskLevel::Safe),
+    ("media_status", RiskLevel::Safe),
+    ("semantic_search", RiskLevel::Safe),
+    ("mqtt_subscribe", RiskLevel::Safe),
+    ("open_url", RiskLevel::Low),
+    ("media_control", RiskLevel::Low),
+    ("open_app", RiskLevel::Medium),
+    ("run_shortcut", RiskLevel::Medium),
+    ("mqtt_publish", RiskLevel::Medium),
+];
+
+static LOADED: Mutex<Loaded> = Mutex::new(Loaded { scripts: Vec::new(), errors: Vec::new() });
+
+struct Loaded {
+    scripts: Vec<Script>,
+    errors: Vec<ScriptError>,
+}
+
+#[derive(Debug)]
+struct Script {
+    name: String,
+    description: String,
+    risk: RiskLevel,
+    ast: AST,
+}
+
+/// A loaded script action, as listed in the capability manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptInfo {
+    /// Action name, `script.<file name>`
+    pub action: String,
+    pub description: String,
+    pub risk: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptError {
+    pub file: String,
+    pub error: String,
+}
+
+/// Payload of `list_scripts` and the `scripts-reloaded` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScriptStatus {
+    pub dir: Option<String>,
+    pub scripts: Vec<ScriptInfo>,
+    /// Files that did not load
+    pub errors: Vec<ScriptError>,
+}
+
+fn parse_risk(risk: &str) -> Option<RiskLevel> {
+    match risk.to_lowercase().as_str() {
+        "safe" => Some(RiskLevel::Safe),
+        "low" => Some(RiskLevel::Low),
+        "medium" => Some(RiskLevel::Medium),
+        "high" => Some(RiskLevel::High),
+        _ => None,
+    }
+}
+
+fn rank(risk: &RiskLevel) -> u8 {
+    match risk {
+        RiskLevel::Safe => 0,
+        RiskLevel::Low => 1,
+        RiskLevel::Medium => 2,
+        RiskLevel::High => 3,
+        RiskLevel::Blocked => 4,
+    }
+}
+
+// ─── Engine ─────────────────────────────────────────
+
+/// What `action()` may do in one engine
+#[derive(Clone)]
+struct RunContext {
+    script: String,
+    risk: RiskLevel,
+    /// The user confirmed the script action (high-risk scripts only)
+    confirmed: bool,
+    /// False while a script is being loaded
+    running: bool,
+}
+
+fn call(ctx: &RunContext, action: &str, params: rhai::Map) -> Result<rhai::Map, Box<EvalAltResult>> {
+    if !ctx.running {
+        return Err("action() can only be called from run()".into());
+    }
+    let Some((_, risk)) = CALLABLE.iter().find(|(name, _)| *name == action) else {
+        return Err(format!("Scripts cannot call '{}'", action).into());
+    };
+    if rank(risk) > rank(&ctx.risk) {
+        return Err(format!("'{}' is {:?} risk, more than the script declares ({:?})", action, risk, ctx.risk).into());
+    }
+    let params: serde_json::Value = rhai::serde::from_dynamic(&Dynamic::from_map(params))?;
+    let result = local_actions::execute(&ActionRequest::from_params(action, &params, ctx.confirmed));
+    if !result.safety.allowed || result.awaiting_confirmation() {
+        return Err(format!("'{}' refused: {}", action, result.safety.reason).into());
+    }
+    let mut map = rhai::Map::new();
+    map.insert("success".into(), result.success.into());
+    map.insert("output".into(), result.output.into());
+    Ok(map)
+}
+
+fn engine(ctx: RunContext) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_string_size(1 << 20);
+    engine.set_max_array_size(10_000);
+    engine.set_max_map_size(10_000);
+    engine.disable_symbol("eval");
+    let name = ctx.script.clone();
+    engine.on_print(move |text| tracing::info!("[Scripts] {}: {}", name, text));
+    let name = ctx.script.clone();
+    engine.on_debug(move |text, _, _| tracing::debug!("[Scripts] {}: {}", name, text));
+    let without_params = ctx.clone();
+    engine.register_fn("action", move |action: &str| call(&without_params, action, rhai::Map::new()));
+    engine.register_fn("action", move |action: &str, params: rhai::Map| call(&ctx, action, params));
+    engine.register_fn("context", crate::exec_context::script_map);
+    engine
+}
+
+fn load(path: &Path) -> Result<Script, String> {
+    let name = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default().to_string();
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_' || c == '-') {
+        return Err("File names may only use a-z, 0-9, '_' and '-'".into());
+    }
+    let source = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let engine = engine(RunContext { script: name.clone(), risk: RiskLevel::Safe, confirmed: false, running: false });
+    let ast = engine.compile(&source).map_err(|e| e.to_string())?;
+    let mut scope = Scope::new();
+    engine.run_ast_with_scope(&mut scope, &ast).map_err(|e| e.to_string())?;
+    let risk = scope
+        .get_value::<ImmutableString>("RISK")
+        .and_then(|r| parse_risk(&r))
+        .ok_or("RISK must be declared as \"safe\", \"low\", \"medium\" or \"high\"")?;
+    let description = scope.get_value::<ImmutableString>("DESCRIPTION").map(|d| d.to_string()).unwrap_or_default();
+    if !ast.iter_functions().any(|f| f.name == "run" && f.params.len() == 1) {
+        return Err("A script needs a `fn run(params)`".into());
+    }
+    Ok(Script { name, description, risk, ast })
+}
+
+// ─── Folder ─────────────────────────────────────────
+
+pub fn dir() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("scripts"))
+}
+
+fn script_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .map(|entries| entries.flatten().map(|e| e.path()).collect())
+        .unwrap_or_default();
+    files.retain(|p| p.is_file() && p.extension().is_some_and(|e| e == "rhai"));
+    files.sort();
+    files
+}
+
+/// Script files and their modification times, to notice changes
+fn snapshot() -> Vec<(PathBuf, Option<SystemTime>)> {
+    let Some(dir) = dir() else { return Vec::new() };
+    script_files(&dir)
+        .into_iter()
+        .map(|p| {
+            let modified = p.metadata().and_then(|m| m.modified()).ok();
+            (p, modified)
+        })
+        .collect()
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Loaded script actions
+pub fn list() -> Vec<ScriptInfo> {
+    let Ok(loaded) = LOADED.lock() else { return Vec::new() };
+    loaded
+        .scripts
+        .iter()
+        .map(|s| ScriptInfo {
+            action: format!("{}{}", PREFIX, s.name),
+            description: s.description.clone(),
+            risk: format!("{:?}", s.risk).to_lowercase(),
+        })
+        .collect()
+}
+
+pub fn status() -> ScriptStatus {
+    let errors = LOADED.lock().map(|l| l.errors.clone()).unwrap_or_default();
+    ScriptStatus { dir: dir().map(|d| d.display().to_string()), scripts: list(), errors }
+}
+
+/// Declared risk of the script behind the action `script.<name>`
+pub fn risk(name: &str) -> Option<RiskLevel> {
+    LOADED.lock().ok()?.scripts.iter().find(|s| s.name == name).map(|s| s.risk.clone())
+}
+
+/// Run the script `name` with the action's params. Blocking; the safety
+/// verdict on the script itself is the caller's.
+pub fn run(name: &str, params: &serde_json::Value, confirmed: bool) -> Result<String, String> {
+    let (risk, ast) = {
+        let loaded = LOADED.lock().map_err(|e| e.to_string())?;
+        let script = loaded.scripts.iter().find(|s| s.name == name).ok_or_else(|| format!("No script named '{}'", name))?;
+        (script.risk.clone(), script.ast.clone())
+    };
+    let engine = engine(RunContext { script: name.to_string(), risk, confirmed, running: true });
+    let params = match params {
+        serde_json::Value::Null => Dynamic::from_map(rhai::Map::new()),
+        params => rhai::serde::to_dynamic(params).map_err(|e| e.to_string())?,
+    };
+    let output: Dynamic = engine.call_fn(&mut Scope::new(), &ast, "run", (params,)).map_err(|e| e.to_string())?;
+    if output.is_unit() {
+        return Ok("Done".into());
+    }
+    if output.is_string() {
+        return Ok(output.into_string().unwrap_or_default());
+    }
+    let value: serde_json::Value = rhai::serde::from_dynamic(&output).map_err(|e| e.to_string())?;
+    Ok(serde_json::to_string_pretty(&value).unwrap_or_default())
+}
+
+/// Load every script in the folder again
+pub fn reload() -> ScriptStatus {
+    let mut scripts = Vec::new();
+    let mut errors = Vec::new();
+    if let Some(dir) = dir() {
+        for path in script_files(&dir) {
+            match load(&path) {
+                Ok(script) => scripts.push(script),
+                Err(error) => {
+                    let file = path.file_name().map(|f| f.to_string_lossy().into_owned()).unwrap_or_default();
+                    tracing::warn!("[Scripts] {} not loaded: {}", file, error);
+                    errors.push(ScriptError { file, error });
+                }
+            }
+        }
+    }
+    tracing::info!("[Scripts] {} script action(s) loaded", scripts.len());
+    if let Ok(mut loaded) = LOADED.lock() {
+        *loaded = Loaded { scripts, errors };
+    }
+    let status = status();
+    crate::events::emit("scripts-reloaded", &status);
+    // Tell the Gateway about the new actions; ignored while offline
+    let _ = crate::connection::send_live(crate::capabilities::hello_message().to_string());
+    status
+}
+
+/// Create the scripts folder and reload whenever its files change
+pub fn spawn_watcher() {
+    if let Some(dir) = dir() {
+        let _ = std::fs::create_dir_all(dir);
+    }
+    tauri::async_runtime::spawn(async {
+        let mut known = None;
+        loop {
+            let now = tauri::async_runtime::spawn_blocking(snapshot).await.unwrap_or_default();
+            if known.as_ref() != Some(&now) {
+                known = Some(now);
+                let _ = tauri::async_runtime::spawn_blocking(reload).await;
+            }
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_script_loading_and_sandbox() {
+        let dir = std::env::temp_dir().join(format!("forgeai-scripts-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |file: &str, source: &str| {
+            let path = dir.join(file);
+            std::fs::write(&path, source).unwrap();
+            path
+        };
+
+        let greet = write("greet.rhai", "const RISK = \"safe\";\nconst DESCRIPTION = \"Say hi\";\nfn run(params) { `Hi ${params.name}` }");
+        let script = load(&greet).unwrap();
+        assert_eq!((script.name.as_str(), script.description.as_str(), script.risk.clone()), ("greet", "Say hi", RiskLevel::Safe));
+
+        assert!(load(&write("norisk.rhai", "fn run(params) { 1 }")).unwrap_err().contains("RISK"));
+        assert!(load(&write("norun.rhai", "const RISK = \"low\";")).unwrap_err().contains("run"));
+        assert!(load(&write("eager.rhai", "const RISK = \"low\";\naction(\"system_info\");\nfn run(params) {}")).is_err());
+        assert!(load(&write("Bad Name.rhai", "const RISK = \"low\";\nfn run(params) {}")).is_err());
+
+        let running = |risk| engine(RunContext { script: "t".into(), risk, confirmed: false, running: true });
+        let err = running(RiskLevel::High).eval::<Dynamic>("action(\"shell\", #{ command: \"whoami\" })").unwrap_err();
+        assert!(err.to_string().contains("cannot call"));
+        let err = running(RiskLevel::Low).eval::<Dynamic>("action(\"open_app\", #{ app_name: \"calc\" })").unwrap_err();
+        assert!(err.to_string().contains("more than the script declares"));
+        assert!(running(RiskLevel::Safe).eval::<Dynamic>("eval(\"1\")").is_err());
+        assert!(running(RiskLevel::Safe).eval::<Dynamic>("loop {}").is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
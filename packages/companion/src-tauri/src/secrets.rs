@@ -0,0 +1,212 @@
+//! # Secrets Vault
+//!
+//! API keys and passwords that actions need, kept out of the conversation.
+//! `secret_set` stores a value under a name in `secrets.bin` (encrypted, in
+//! the profile); actions then refer to it as `{{secret:NAME}}` in their
+//! command, content or params — a user script's params included:
+//!
+//! ```json
+//! { "action": "script.deploy", "params": { "token": "{{secret:DEPLOY_TOKEN}}" } }
+//! ```
+//!
+//! Placeholders are resolved on this machine just before the action runs.
+//! An action naming a secret always asks for confirmation first, showing
+//! which secrets it uses: once resolved, the action could send the value
+//! anywhere. Every stored value is masked back to its placeholder in the
+//! result and its safety verdict, and the safety history keeps the
+//! placeholder, not the value. Masking only catches the value as stored —
+//! an action that encodes it (base64, a URL) gets past it — so it guards
+//! against echoes, not against an action the user should not have
+//! confirmed. `secret_list` returns names only; values cannot be read back.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+/// Values shorter than this are not masked in results: they would match
+/// too much ordinary text
+const MIN_MASKED_LEN: usize = 4;
+
+/// The vault, loaded on first use
+static VAULT: Mutex<Option<HashMap<String, Secret>>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Secret {
+    value: String,
+    updated_at: String,
+}
+
+/// A stored secret, without its value
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecretInfo {
+    pub name: String,
+    pub updated_at: String,
+}
+
+fn placeholder() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"\{\{\s*secret:([A-Za-z0-9_]+)\s*\}\}").unwrap())
+}
+
+fn path() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("secrets.bin"))
+}
+
+/// Run `f` on the vault, loading it first if needed
+fn with_vault<T>(f: impl FnOnce(&mut HashMap<String, Secret>) -> T) -> Result<T, String> {
+    let mut vault = VAULT.lock().map_err(|e| e.to_string())?;
+    if vault.is_none() {
+        let loaded = match path().filter(|p| p.exists()) {
+            Some(path) => {
+                let json = crate::secure_store::read_encrypted(&path)?;
+                serde_json::from_slice(&json).map_err(|e| format!("Unreadable secrets vault: {}", e))?
+            }
+            None => HashMap::new(),
+        };
+        *vault = Some(loaded);
+    }
+    Ok(f(vault.get_or_insert_with(HashMap::new)))
+}
+
+fn save(vault: &HashMap<String, Secret>) -> Result<(), String> {
+    let path = path().ok_or("Cannot determine data directory")?;
+    let json = serde_json::to_vec(vault).map_err(|e| e.to_string())?;
+    crate::secure_store::write_encrypted(&path, &json)
+}
+
+/// `text` with each placeholder replaced by its value
+fn substitute(text: &str, vault: &HashMap<String, Secret>) -> Result<String, String> {
+    if let Some(missing) = placeholder().captures_iter(text).find(|c| !vault.contains_key(&c[1])) {
+        return Err(format!("No secret named {} in the vault", &missing[1]));
+    }
+    Ok(placeholder().replace_all(text, |c: &regex::Captures| vault[&c[1]].value.clone()).into_owned())
+}
+
+/// `text` with each stored value replaced by its placeholder
+fn mask(text: &str, vault: &HashMap<String, Secret>) -> String {
+    let mut secrets: Vec<(&String, &Secret)> = vault.iter().filter(|(_, s)| s.value.len() >= MIN_MASKED_LEN).collect();
+    // Longest first, so a value containing another is masked whole
+    secrets.sort_by_key(|(_, s)| std::cmp::Reverse(s.value.len()));
+    let mut text = text.to_string();
+    for (name, secret) in secrets {
+        if text.contains(&secret.value) {
+            text = text.replace(&secret.value, &format!("{{{{secret:{}}}}}", name));
+        }
+    }
+    text
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Store `value` as `name`, replacing any previous value
+pub fn set(name: &str, value: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > 64 || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err("Secret names are up to 64 letters, digits and underscores".into());
+    }
+    if value.is_empty() {
+        return Err("A secret needs a value".into());
+    }
+    with_vault(|vault| {
+        vault.insert(
+            name.to_string(),
+            Secret { value: value.to_string(), updated_at: chrono::Utc::now().to_rfc3339() },
+        );
+        save(vault)
+    })??;
+    tracing::info!("[Secrets] Stored {}", name);
+    Ok(())
+}
+
+/// Delete `name`; false when there was no such secret
+pub fn delete(name: &str) -> Result<bool, String> {
+    let removed = with_vault(|vault| match vault.remove(name) {
+        Some(_) => save(vault).map(|_| true),
+        None => Ok(false),
+    })??;
+    if removed {
+        tracing::info!("[Secrets] Deleted {}", name);
+    }
+    Ok(removed)
+}
+
+/// Stored secrets by name
+pub fn list() -> Result<Vec<SecretInfo>, String> {
+    let mut secrets = with_vault(|vault| {
+        vault
+            .iter()
+            .map(|(name, s)| SecretInfo { name: name.clone(), updated_at: s.updated_at.clone() })
+            .collect::<Vec<_>>()
+    })?;
+    secrets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(secrets)
+}
+
+/// Names of the secrets `text` refers to
+pub fn referenced(text: &str) -> Vec<String> {
+    placeholder().captures_iter(text).map(|c| c[1].to_string()).collect()
+}
+
+/// Names of the secrets every string of `value` refers to
+pub fn referenced_json(value: &serde_json::Value) -> Vec<String> {
+    match value {
+        serde_json::Value::String(s) => referenced(s),
+        serde_json::Value::Array(items) => items.iter().flat_map(referenced_json).collect(),
+        serde_json::Value::Object(map) => map.values().flat_map(referenced_json).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Resolve the placeholders in `text`
+pub fn resolve(text: &str) -> Result<String, String> {
+    if !placeholder().is_match(text) {
+        return Ok(text.to_string());
+    }
+    with_vault(|vault| substitute(text, vault))?
+}
+
+/// Resolve the placeholders in every string of `value`
+pub fn resolve_json(value: &serde_json::Value) -> Result<serde_json::Value, String> {
+    Ok(match value {
+        serde_json::Value::String(s) => serde_json::Value::String(resolve(s)?),
+        serde_json::Value::Array(items) => serde_json::Value::Array(items.iter().map(resolve_json).collect::<Result<_, _>>()?),
+        serde_json::Value::Object(map) => serde_json::Value::Object(
+            map.iter().map(|(k, v)| Ok((k.clone(), resolve_json(v)?))).collect::<Result<_, String>>()?,
+        ),
+        other => other.clone(),
+    })
+}
+
+/// Mask every stored value in `text`
+pub fn redact(text: &str) -> String {
+    with_vault(|vault| if vault.is_empty() { text.to_string() } else { mask(text, vault) }).unwrap_or_else(|_| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vault() -> HashMap<String, Secret> {
+        let secret = |value: &str| Secret { value: value.into(), updated_at: String::new() };
+        HashMap::from([
+            ("API_KEY".to_string(), secret("sk-live-123456")),
+            ("PIN".to_string(), secret("42")),
+        ])
+    }
+
+    #[test]
+    fn test_substitute_and_mask() {
+        let vault = vault();
+        let command = substitute("curl -H 'Authorization: Bearer {{secret:API_KEY}}' {{ secret:PIN }}", &vault).unwrap();
+        assert_eq!(command, "curl -H 'Authorization: Bearer sk-live-123456' 42");
+        assert!(substitute("{{secret:MISSING}}", &vault).unwrap_err().contains("MISSING"));
+
+        assert_eq!(mask("token=sk-live-123456; pin 42", &vault), "token={{secret:API_KEY}}; pin 42");
+        let params = serde_json::json!({ "headers": ["Bearer {{secret:API_KEY}}"], "pin": "{{ secret:PIN }}", "n": 1 });
+        let mut names = referenced_json(&params);
+        names.sort();
+        assert_eq!(names, ["API_KEY", "PIN"]);
+    }
+}
@@ -0,0 +1,192 @@
+//! # Encrypted Local Storage
+//!
+//! Fallback at-rest encryption for secrets when no OS keychain is available
+//! (e.g. headless Linux without a Secret Service). Data is sealed with
+//! ChaCha20-Poly1305 under a random 256-bit key stored next to it in
+//! `secure-store.key`, created readable by the current user only (0600).
+//!
+//! This is weaker than the keychain. Anything that can read the key file —
+//! other processes of the same user, root, a backup that includes the data
+//! directory — can decrypt the secrets. It keeps them out of plain sight and
+//! makes a copied secrets file useless on its own, nothing more.
+//!
+//! File format: `FGE2` magic | 12-byte nonce | ciphertext+tag. Files from
+//! older versions (`FGE1`), sealed under a key anyone on the machine could
+//! recompute, are not read: their secrets have to be entered again (for the
+//! credentials, by pairing again).
+
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const MAGIC: &[u8; 4] = b"FGE2";
+const NONCE_LEN: usize = 12;
+
+static KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+fn key_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("forgeai-companion").join("secure-store.key"))
+}
+
+/// Read the key at `path`, generating it (mode 0600) on first use
+fn load_or_create_key(path: &Path) -> Result<[u8; 32], String> {
+    let mut key = [0u8; 32];
+    match std::fs::read(path) {
+        Ok(bytes) if bytes.len() == key.len() => {
+            key.copy_from_slice(&bytes);
+            return Ok(key);
+        }
+        Ok(_) => return Err(format!("Secure store key {} is corrupted", path.display())),
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(format!("Key read error: {}", e)),
+        Err(_) => {}
+    }
+
+    key.copy_from_slice(&ChaCha20Poly1305::generate_key(&mut OsRng));
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    match options.open(path) {
+        Ok(mut file) => {
+            file.write_all(&key).map_err(|e| format!("Key write error: {}", e))?;
+            tracing::info!("[SecureStore] Generated a new key at {}", path.display());
+            Ok(key)
+        }
+        // Another instance created it first
+        Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => load_or_create_key(path),
+        Err(e) => Err(format!("Key write error: {}", e)),
+    }
+}
+
+fn store_key() -> Result<Key, String> {
+    let mut cached = KEY.lock().map_err(|_| "Key cache poisoned".to_string())?;
+    let key = match *cached {
+        Some(key) => key,
+        None => {
+            let key = load_or_create_key(&key_path().ok_or("Cannot determine data directory")?)?;
+            *cached = Some(key);
+            key
+        }
+    };
+    Ok(*Key::from_slice(&key))
+}
+
+fn seal(key: &Key, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = ChaCha20Poly1305::new(key)
+        .encrypt(&nonce, plaintext)
+        .map_err(|e| format!("Encrypt error: {}", e))?;
+
+    let mut out = Vec::with_capacity(MAGIC.len() + NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+    Ok(out)
+}
+
+fn open(key: &Key, data: &[u8]) -> Result<Vec<u8>, String> {
+    let (nonce, ciphertext) = data[MAGIC.len()..].split_at(NONCE_LEN);
+    ChaCha20Poly1305::new(key)
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Decrypt failed (key file replaced or data corrupted)".to_string())
+}
+
+fn is_sealed(data: &[u8]) -> bool {
+    data.len() >= MAGIC.len() + NONCE_LEN && &data[..MAGIC.len()] == MAGIC
+}
+
+/// Encrypt `plaintext` with the store key
+pub fn encrypt(plaintext: &[u8]) -> Result<Vec<u8>, String> {
+    seal(&store_key()?, plaintext)
+}
+
+/// Decrypt data produced by [`encrypt`]
+pub fn decrypt(data: &[u8]) -> Result<Vec<u8>, String> {
+    if is_sealed(data) {
+        open(&store_key()?, data)
+    } else {
+        Err("Not an encrypted companion file".into())
+    }
+}
+
+/// Encrypt and write a file readable only by the current user
+pub fn write_encrypted(path: &Path, plaintext: &[u8]) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    std::fs::write(path, encrypt(plaintext)?).map_err(|e| format!("File save error: {}", e))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600));
+    }
+    Ok(())
+}
+
+/// Read and decrypt a file written by [`write_encrypted`]
+pub fn read_encrypted(path: &Path) -> Result<Vec<u8>, String> {
+    let data = std::fs::read(path).map_err(|e| format!("File read error: {}", e))?;
+    decrypt(&data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_key() -> Key {
+        *Key::from_slice(&[7u8; 32])
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let sealed = seal(&test_key(), b"secret").unwrap();
+        assert!(is_sealed(&sealed));
+        assert_ne!(&sealed[MAGIC.len() + NONCE_LEN..], b"secret");
+        assert_eq!(open(&test_key(), &sealed).unwrap(), b"secret");
+        assert!(open(Key::from_slice(&[8u8; 32]), &sealed).is_err());
+
+        // Files from older versions are not read
+        let mut legacy = sealed.clone();
+        legacy[..4].copy_from_slice(b"FGE1");
+        assert!(!is_sealed(&legacy));
+    }
+
+    #[test]
+    fn test_tampered_data_rejected() {
+        let mut sealed = seal(&test_key(), b"secret").unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 0xff;
+        assert!(open(&test_key(), &sealed).is_err());
+        assert!(decrypt(b"plain json").is_err());
+    }
+
+    #[test]
+    fn test_key_file_is_random_and_private() {
+        let dir = std::env::temp_dir().join(format!("forgeai-secure-store-{}", std::process::id()));
+        let path = dir.join("secure-store.key");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let key = load_or_create_key(&path).unwrap();
+        assert_eq!(load_or_create_key(&path).unwrap(), key);
+        assert_ne!(key, [0u8; 32]);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        std::fs::write(&path, b"short").unwrap();
+        assert!(load_or_create_key(&path).is_err());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,461 @@
+//! # Settings
+//!
+//! One typed configuration file, `settings.json`, for everything the user
+//! can tune: voice capture, audio profiles and the preferred output devices,
+//! per-microphone noise gate and gain profiles, input monitoring, the STT
+//! and TTS providers, automatic reply language, streamed spoken replies, the
+//! TTS voice and its pronunciation lexicon, quiet hours, wake word, payload
+//! compression, push events, webhooks, the MQTT broker, the clipboard
+//! history, global hotkeys, shell job limits, the metrics endpoint, crash
+//! report uploads, when to pause listening (battery, idle), how long voice
+//! turns are remembered, which documents are indexed for semantic search,
+//! what screen context chats carry, whether trivial requests and voice
+//! shortcuts are answered locally, the custom vocabulary and masking of
+//! transcripts, the fallback STT backend, speaker verification, approving
+//! high-risk actions from the phone, rollback snapshots, the quota on files
+//! the assistant creates, the selected project directory, and simulation
+//! mode. The owning modules keep the live values in memory; this module
+//! persists them and pushes changes into the running engines.
+//!
+//! Changes are validated as a whole before anything is applied, and every
+//! accepted change is emitted as `settings-changed`. The proxy configuration
+//! and the MQTT password stay in their own encrypted files (see `proxy`,
+//! `mqtt`).
+//!
+//! On first start the older per-feature files (`push.json`, `hotkeys.json`)
+//! are folded into `settings.json`.
+
+use crate::clipboard_history::ClipboardHistoryConfig;
+use crate::compression::CompressionConfig;
+use crate::crash::CrashConfig;
+use crate::embeddings::EmbeddingConfig;
+use crate::hotkeys::HotkeyConfig;
+use crate::idle::IdleConfig;
+use crate::input_profiles::InputProfile;
+use crate::intents::IntentConfig;
+use crate::jobs::JobLimits;
+use crate::language::AutoLanguageConfig;
+use crate::lexicon::LexiconConfig;
+use crate::memory::MemoryConfig;
+use crate::metrics::MetricsConfig;
+use crate::monitor::MonitorConfig;
+use crate::mqtt::MqttConfig;
+use crate::power::PowerPolicy;
+use crate::profiles::AudioProfile;
+use crate::push::PushConfig;
+use crate::quota::QuotaConfig;
+use crate::quiet_hours::QuietHoursConfig;
+use crate::remote_approval::RemoteApprovalConfig;
+use crate::rollback::RollbackConfig;
+use crate::screen_context::ContextConfig;
+use crate::shortcuts::VoiceShortcut;
+use crate::speaker::SpeakerConfig;
+use crate::stt_fallback::SttFallbackConfig;
+use crate::transcript_filter::FilterConfig;
+use crate::vocabulary::VocabularyConfig;
+use crate::voice::VoiceProviders;
+use crate::webhooks::Webhook;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+static CURRENT: Mutex<Option<Settings>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VoiceSettings {
+    /// Capture device name (system default when unset)
+    pub input_device: Option<String>,
+    /// Playback device name (system default when unset)
+    pub output_device: Option<String>,
+    /// Longest recording before it is cut off
+    pub max_duration_secs: u32,
+    /// RMS level below which input counts as silence
+    pub silence_threshold: f32,
+    /// Silence that ends a recording
+    pub silence_timeout_ms: u64,
+    /// Audio recorded before silence can end a recording
+    pub min_utterance_ms: u64,
+    /// Give up when nothing is said this long after the start
+    pub no_speech_timeout_ms: Option<u64>,
+    /// Silence kept after the last speech (unset: all of it)
+    pub trailing_padding_ms: Option<u64>,
+}
+
+impl Default for VoiceSettings {
+    fn default() -> Self {
+        Self {
+            input_device: None,
+            output_device: None,
+            max_duration_secs: 30,
+            silence_threshold: 0.01,
+            silence_timeout_ms: 800,
+            min_utterance_ms: 500,
+            no_speech_timeout_ms: None,
+            trailing_padding_ms: None,
+        }
+    }
+}
+
+impl VoiceSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=300).contains(&self.max_duration_secs) {
+            return Err("Maximum recording length must be between 1 and 300 seconds".into());
+        }
+        if !(0.0..=1.0).contains(&self.silence_threshold) {
+            return Err("Silence threshold must be between 0 and 1".into());
+        }
+        if !(100..=10_000).contains(&self.silence_timeout_ms) {
+            return Err("Silence timeout must be between 100 and 10000 ms".into());
+        }
+        if self.min_utterance_ms >= self.max_duration_secs as u64 * 1000 {
+            return Err("Minimum utterance length must be shorter than the maximum recording length".into());
+        }
+        if self.no_speech_timeout_ms.is_some_and(|t| !(500..=60_000).contains(&t)) {
+            return Err("No-speech timeout must be between 500 and 60000 ms".into());
+        }
+        if self.trailing_padding_ms.is_some_and(|p| p > self.silence_timeout_ms) {
+            return Err("Trailing padding cannot be longer than the silence timeout".into());
+        }
+        Ok(())
+    }
+
+    /// Utterance bounds for the voice engine
+    pub fn endpointing(&self) -> crate::voice::Endpointing {
+        crate::voice::Endpointing {
+            min_utterance_ms: self.min_utterance_ms,
+            no_speech_timeout_ms: self.no_speech_timeout_ms,
+            trailing_padding_ms: self.trailing_padding_ms,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WakeWordSettings {
+    /// 0.0 (hard to trigger) – 1.0 (very sensitive)
+    pub sensitivity: f32,
+}
+
+impl Default for WakeWordSettings {
+    fn default() -> Self {
+        Self { sensitivity: 0.5 }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub voice: VoiceSettings,
+    pub wake_word: WakeWordSettings,
+    pub compression: CompressionConfig,
+    pub push: PushConfig,
+    pub hotkeys: HotkeyConfig,
+    pub jobs: JobLimits,
+    pub metrics: MetricsConfig,
+    pub crash_reports: CrashConfig,
+    /// When to suspend listening to save battery
+    pub power: PowerPolicy,
+    /// What to pause while the user is away
+    pub idle: IdleConfig,
+    /// Local store of voice turns
+    pub memory: MemoryConfig,
+    /// Documents indexed for `semantic_search`
+    pub embeddings: EmbeddingConfig,
+    /// Focused window attached to chat and voice requests
+    pub screen_context: ContextConfig,
+    /// Trivial requests answered on the device
+    pub intents: IntentConfig,
+    /// Phrases that run a local action without the Gateway
+    pub voice_shortcuts: Vec<VoiceShortcut>,
+    /// Names and terms the speech recognizer should get right
+    pub vocabulary: VocabularyConfig,
+    /// What to mask in transcripts
+    pub transcript_filter: FilterConfig,
+    /// Voice match required for high-risk actions asked for by voice
+    pub speaker_verification: SpeakerConfig,
+    /// Answering high-risk confirmations from the phone
+    pub remote_approval: RemoteApprovalConfig,
+    /// Second STT backend for when the Gateway's fails
+    pub stt_fallback: SttFallbackConfig,
+    /// Output devices in order of preference, matched by (part of) their name
+    pub output_priority: Vec<String>,
+    /// Gateway TTS voice id (the Gateway's default when unset)
+    pub tts_voice: Option<String>,
+    /// Outbound hooks on companion events
+    pub webhooks: Vec<Webhook>,
+    /// Broker for the `mqtt_publish` / `mqtt_subscribe` actions
+    pub mqtt: MqttConfig,
+    /// Opt-in record of copied text
+    pub clipboard_history: ClipboardHistoryConfig,
+    /// Speak voice turn replies sentence by sentence as the Gateway streams them
+    pub stream_replies: bool,
+    /// Fabricate the results of actions that would change the machine
+    pub simulation: bool,
+    /// Snapshots of confirmed file batches, for rolling them back
+    pub rollback: RollbackConfig,
+    /// Space files created by the assistant may take
+    pub quota: QuotaConfig,
+    /// Project shell actions and scripts are told about (`FORGEAI_PROJECT_DIR`)
+    pub project_dir: Option<String>,
+    /// Noise gate, threshold and gain control per input device
+    pub input_profiles: Vec<InputProfile>,
+    /// Play the microphone back while recording
+    pub monitor: MonitorConfig,
+    /// Tag utterances with the language spoken and reply in it
+    pub auto_language: AutoLanguageConfig,
+    /// Gateway STT/TTS providers (the Gateway's defaults when unset)
+    pub voice_providers: VoiceProviders,
+    /// How the synthesizer should say particular words
+    pub lexicon: LexiconConfig,
+    /// When spoken output and earcons are muted
+    pub quiet_hours: QuietHoursConfig,
+    pub audio_profiles: Vec<AudioProfile>,
+    /// Profile last switched to
+    pub active_audio_profile: Option<String>,
+    /// Language for user-facing messages (`en`, `pt-BR`, …); the OS language when unset
+    pub locale: Option<String>,
+}
+
+impl Settings {
+    fn validate(&self) -> Result<(), String> {
+        self.voice.validate()?;
+        for (i, profile) in self.audio_profiles.iter().enumerate() {
+            profile.validate()?;
+            if self.audio_profiles[..i].iter().any(|p| p.name == profile.name) {
+                return Err(format!("Duplicate audio profile '{}'", profile.name));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.wake_word.sensitivity) {
+            return Err("Wake word sensitivity must be between 0 and 1".into());
+        }
+        if let Some(locale) = &self.locale {
+            if !crate::i18n::is_supported(locale) {
+                return Err(format!("Unsupported language '{}'", locale));
+            }
+        }
+        for (i, shortcut) in self.voice_shortcuts.iter().enumerate() {
+            shortcut.validate()?;
+            let phrase = crate::intents::normalize(&shortcut.phrase);
+            if self.voice_shortcuts[..i].iter().any(|s| crate::intents::normalize(&s.phrase) == phrase) {
+                return Err(format!("Duplicate voice shortcut '{}'", shortcut.phrase));
+            }
+        }
+        if self.output_priority.iter().any(|d| d.trim().is_empty()) {
+            return Err("Output device names must not be empty".into());
+        }
+        for (i, hook) in self.webhooks.iter().enumerate() {
+            hook.validate()?;
+            if self.webhooks[..i].iter().any(|h| h.name == hook.name) {
+                return Err(format!("Duplicate webhook '{}'", hook.name));
+            }
+        }
+        self.mqtt.validate()?;
+        self.clipboard_history.validate()?;
+        self.auto_language.validate()?;
+        self.monitor.validate()?;
+        for profile in &self.input_profiles {
+            profile.validate()?;
+        }
+        self.vocabulary.validate()?;
+        self.voice_providers.validate()?;
+        self.stt_fallback.validate()?;
+        self.speaker_verification.validate()?;
+        self.remote_approval.validate()?;
+        self.rollback.validate()?;
+        self.quota.validate()?;
+        crate::exec_context::validate_project_dir(&self.project_dir)?;
+        self.lexicon.validate()?;
+        self.quiet_hours.validate()?;
+        self.idle.validate()?;
+        self.embeddings.validate()?;
+        if self.power.min_battery_percent > 100 {
+            return Err("Battery threshold must be between 0 and 100%".into());
+        }
+        if self.metrics.prometheus_port.is_some_and(|p| p < 1024) {
+            return Err("Metrics port must be 1024 or higher".into());
+        }
+        Ok(())
+    }
+}
+
+fn data_dir() -> Option<PathBuf> {
+    crate::users::data_dir()
+}
+
+/// Read `name` from the data directory as JSON
+fn read_json<T: serde::de::DeserializeOwned>(name: &str) -> Option<T> {
+    let json = std::fs::read_to_string(data_dir()?.join(name)).ok()?;
+    match serde_json::from_str(&json) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            tracing::warn!("[Settings] Ignoring unreadable {}: {}", name, e);
+            None
+        }
+    }
+}
+
+fn load() -> Settings {
+    if let Some(settings) = read_json("settings.json") {
+        return settings;
+    }
+    // First start with unified settings — pick up the per-feature files
+    let mut settings = Settings::default();
+    if let Some(push) = read_json("push.json") {
+        settings.push = push;
+    }
+    if let Some(hotkeys) = read_json("hotkeys.json") {
+        settings.hotkeys = hotkeys;
+    }
+    settings
+}
+
+fn save(settings: &Settings) -> Result<(), String> {
+    let dir = data_dir().ok_or("Cannot determine data directory")?;
+    let _ = std::fs::create_dir_all(&dir);
+    let json = serde_json::to_string_pretty(settings).map_err(|e| format!("Serialize error: {}", e))?;
+    std::fs::write(dir.join("settings.json"), json).map_err(|e| format!("Settings write error: {}", e))
+}
+
+/// Current settings
+pub fn get() -> Settings {
+    CURRENT.lock().ok().and_then(|s| s.clone()).unwrap_or_else(load)
+}
+
+/// Push `settings` into the modules that need no window
+fn apply_engines(settings: &Settings) -> Result<(), String> {
+    crate::compression::set_config(settings.compression.clone());
+    crate::jobs::set_limits(settings.jobs.clone());
+    crate::push::set_config(settings.push.clone())?;
+    crate::metrics::set_config(&settings.metrics);
+    crate::crash::set_config(&settings.crash_reports);
+    crate::power::set_config(&settings.power);
+    crate::idle::set_config(&settings.idle);
+    crate::memory::set_config(&settings.memory);
+    crate::embeddings::set_config(&settings.embeddings);
+    crate::screen_context::set_config(&settings.screen_context);
+    crate::intents::set_config(&settings.intents);
+    crate::vocabulary::set_config(&settings.vocabulary);
+    crate::stt_fallback::set_config(&settings.stt_fallback);
+    crate::transcript_filter::set_config(&settings.transcript_filter);
+    crate::speaker::set_config(&settings.speaker_verification);
+    crate::remote_approval::set_config(&settings.remote_approval);
+    crate::i18n::set_locale(settings.locale.clone());
+    crate::voice::set_devices(settings.voice.input_device.clone(), settings.voice.output_device.clone());
+    crate::output_priority::set_priority(&settings.output_priority);
+    crate::voice::set_tts_voice(settings.tts_voice.clone());
+    crate::voice::set_providers(&settings.voice_providers);
+    crate::speech_stream::set_enabled(settings.stream_replies);
+    crate::simulation::set_enabled(settings.simulation);
+    crate::rollback::set_config(&settings.rollback);
+    crate::quota::set_config(&settings.quota);
+    crate::exec_context::set_project_dir(settings.project_dir.clone());
+    crate::language::set_config(&settings.auto_language);
+    crate::monitor::set_config(&settings.monitor);
+    crate::input_profiles::set_profiles(&settings.input_profiles);
+    crate::webhooks::set_hooks(&settings.webhooks);
+    crate::mqtt::set_config(&settings.mqtt);
+    crate::clipboard_history::set_config(&settings.clipboard_history);
+    crate::lexicon::set_config(&settings.lexicon);
+    crate::quiet_hours::set_config(&settings.quiet_hours);
+    Ok(())
+}
+
+/// Push `settings` into the running engines. Hotkeys are registered by
+/// the caller, since that is the step that can fail.
+fn apply(app: &AppHandle, settings: &Settings) -> Result<(), String> {
+    apply_engines(settings)?;
+    if let Ok(mut engine) = app.state::<crate::commands::WakeWordState>().0.lock() {
+        engine.set_sensitivity(settings.wake_word.sensitivity);
+    }
+    // The voice engine is locked for the whole of a recording — apply once it is free
+    let app = app.clone();
+    let voice = settings.voice.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Ok(mut engine) = app.state::<crate::commands::VoiceState>().0.lock() {
+            engine.configure(voice.max_duration_secs, voice.silence_threshold, voice.silence_timeout_ms);
+            engine.set_endpointing(voice.endpointing());
+        }
+    });
+    Ok(())
+}
+
+/// Load the persisted settings and apply them (called once from `setup`)
+pub fn init(app: &AppHandle) {
+    let settings = load();
+    crate::hotkeys::init(app, &settings.hotkeys);
+    if let Err(e) = apply(app, &settings) {
+        tracing::warn!("[Settings] {}", e);
+    }
+    if let Ok(mut current) = CURRENT.lock() {
+        *current = Some(settings);
+    }
+}
+
+/// Load and apply the persisted settings without a window (see `headless`)
+pub fn init_headless() -> Settings {
+    let settings = load();
+    if let Err(e) = apply_engines(&settings) {
+        tracing::warn!("[Settings] {}", e);
+    }
+    if let Ok(mut current) = CURRENT.lock() {
+        *current = Some(settings.clone());
+    }
+    settings
+}
+
+/// Validate, apply and persist new settings
+pub fn set(app: &AppHandle, new: Settings) -> Result<Settings, String> {
+    new.validate()?;
+    let old = get();
+    if new.hotkeys != old.hotkeys {
+        crate::hotkeys::set_config(app, new.hotkeys.clone())?;
+    }
+    apply(app, &new)?;
+    save(&new)?;
+    *CURRENT.lock().map_err(|e| e.to_string())? = Some(new.clone());
+
+    tracing::info!("[Settings] Updated");
+    crate::events::emit("settings-changed", &new);
+    Ok(new)
+}
+
+/// Change part of the settings
+pub fn update(app: &AppHandle, change: impl FnOnce(&mut Settings)) -> Result<Settings, String> {
+    let mut settings = get();
+    change(&mut settings);
+    set(app, settings)
+}
+
+/// Restore every setting to its default
+pub fn reset(app: &AppHandle) -> Result<Settings, String> {
+    set(app, Settings::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_file_and_validation() {
+        let parsed: Settings =
+            serde_json::from_str(r#"{"voice": {"silenceTimeoutMs": 1200}, "unknown": 1}"#).unwrap();
+        assert_eq!(parsed.voice.silence_timeout_ms, 1200);
+        assert_eq!(parsed.voice.max_duration_secs, 30);
+        assert_eq!(parsed.wake_word, WakeWordSettings::default());
+        assert!(parsed.validate().is_ok());
+
+        let mut bad = Settings::default();
+        bad.wake_word.sensitivity = 1.5;
+        assert!(bad.validate().unwrap_err().contains("sensitivity"));
+
+        let mut bad = Settings::default();
+        bad.voice.max_duration_secs = 0;
+        assert!(bad.validate().is_err());
+
+        let mut bad = Settings::default();
+        bad.voice.trailing_padding_ms = Some(2000);
+        assert!(bad.validate().unwrap_err().contains("padding"));
+    }
+}
@@ -0,0 +1,293 @@
+//! # Setup Checks
+//!
+//! `run_setup_checks` walks through what the companion needs before its
+//! first conversation and returns a checklist the onboarding wizard renders
+//! as-is:
+//!
+//! | Id          | Passes when                                                 |
+//! |-------------|-------------------------------------------------------------|
+//! | `microphone`| the input device opens and delivers real audio              |
+//! | `speaker`   | a short test tone plays on the configured output            |
+//! | `gateway`   | the paired Gateway answers `/health`                        |
+//! | `wakeWord`  | the keyword model (or the built-in detector) loads          |
+//!
+//! Failed checks carry a `fix` the wizard can show next to them.
+//!
+//! Pairing runs a second checklist against the new credentials right away
+//! ([`after_pairing`]), so a companion that paired but cannot talk is found
+//! out before the first conversation:
+//!
+//! | Id         | Passes when                                                  |
+//! |------------|--------------------------------------------------------------|
+//! | `gateway`  | the Gateway answers `/health`                                |
+//! | `identity` | `/api/companion/me` accepts the session (skipped when absent) |
+//! | `stt`      | a half-second of silence is transcribed                      |
+//! | `tts`      | a word is synthesized                                        |
+
+use crate::connection::{CompanionCredentials, GatewayConnection};
+use forgeai_companion_core::audio;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// Gateway probe timeout
+const GATEWAY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Speech probes wait longer, as a provider may have to wake up
+const SPEECH_TIMEOUT: Duration = Duration::from_secs(20);
+/// Frequency and length of the speaker test tone
+const TONE_HZ: f32 = 660.0;
+const TONE_SECS: f32 = 0.4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckState {
+    Passed,
+    Failed,
+    /// Not run, e.g. the Gateway check before pairing
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupCheck {
+    pub id: &'static str,
+    pub label: &'static str,
+    pub state: CheckState,
+    pub detail: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fix: Option<String>,
+}
+
+impl SetupCheck {
+    fn passed(id: &'static str, label: &'static str, detail: impl Into<String>) -> Self {
+        Self { id, label, state: CheckState::Passed, detail: detail.into(), fix: None }
+    }
+
+    fn failed(id: &'static str, label: &'static str, detail: impl Into<String>, fix: impl Into<String>) -> Self {
+        Self { id, label, state: CheckState::Failed, detail: detail.into(), fix: Some(fix.into()) }
+    }
+
+    fn skipped(id: &'static str, label: &'static str, detail: impl Into<String>) -> Self {
+        Self { id, label, state: CheckState::Skipped, detail: detail.into(), fix: None }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SetupReport {
+    /// No check failed (skipped ones don't count)
+    pub ready: bool,
+    pub checks: Vec<SetupCheck>,
+}
+
+impl SetupReport {
+    fn new(checks: Vec<SetupCheck>) -> Self {
+        Self { ready: checks.iter().all(|c| c.state != CheckState::Failed), checks }
+    }
+}
+
+// ─── Checks ─────────────────────────────────────────
+
+/// Blocking (opens the microphone briefly)
+fn check_microphone() -> SetupCheck {
+    use crate::permissions::{PermissionKind, PermissionState};
+    const ID: &str = "microphone";
+    const LABEL: &str = "Microphone";
+    if crate::voice::mic_muted() {
+        return SetupCheck::failed(ID, LABEL, "The microphone is muted in the companion", "Unmute it from the tray menu");
+    }
+    let device = crate::voice::input_device().and_then(|d| cpal::traits::DeviceTrait::name(&d).ok());
+    let status = crate::permissions::status(PermissionKind::Microphone);
+    match (status.state, device) {
+        (PermissionState::Granted, Some(name)) => SetupCheck::passed(ID, LABEL, format!("Recording from '{}'", name)),
+        (_, None) => SetupCheck::failed(ID, LABEL, "No microphone found", "Connect a microphone, or pick one under Settings → Audio"),
+        (PermissionState::Denied, _) => SetupCheck::failed(
+            ID,
+            LABEL,
+            "Microphone access is denied",
+            status.hint.unwrap_or_else(|| "Allow microphone access in the system privacy settings".into()),
+        ),
+        (_, Some(name)) => SetupCheck::failed(ID, LABEL, format!("'{}' delivered no audio", name), "Check that the microphone is plugged in and not in use"),
+    }
+}
+
+/// Blocking (waits until the tone has played)
+fn check_speaker() -> SetupCheck {
+    const ID: &str = "speaker";
+    const LABEL: &str = "Speaker";
+    use forgeai_companion_core::audio;
+    let tone = audio::sine(TONE_HZ, audio::TARGET_RATE, TONE_SECS);
+    match audio::encode_wav(&tone, audio::TARGET_RATE).and_then(|wav| crate::voice::play_output(&wav, crate::quiet_hours::Output::Test)) {
+        Ok(()) => SetupCheck::passed(ID, LABEL, "Played a test tone"),
+        Err(e) => SetupCheck::failed(ID, LABEL, e, "Pick another output device under Settings → Audio"),
+    }
+}
+
+async fn check_gateway() -> SetupCheck {
+    match GatewayConnection::load_credentials() {
+        Some(creds) => check_health(&creds).await,
+        None => SetupCheck::skipped("gateway", "Gateway", "Not paired yet"),
+    }
+}
+
+async fn check_health(creds: &CompanionCredentials) -> SetupCheck {
+    const ID: &str = "gateway";
+    const LABEL: &str = "Gateway";
+    let started = Instant::now();
+    let result = match crate::http::gateway(&creds.gateway_url) {
+        Ok(gw) => crate::netstats::send(gw.get("/health").timeout(GATEWAY_TIMEOUT)).await.map_err(|e| e.to_string()),
+        Err(e) => Err(e),
+    };
+    let fix = "Check that the Gateway is running and reachable from this machine";
+    match result {
+        Ok(resp) if resp.status().is_success() => SetupCheck::passed(
+            ID,
+            LABEL,
+            format!("{} answered in {} ms", creds.gateway_url, started.elapsed().as_millis()),
+        ),
+        Ok(resp) => SetupCheck::failed(ID, LABEL, format!("Gateway health returned {}", resp.status()), fix),
+        Err(e) => SetupCheck::failed(ID, LABEL, format!("Cannot reach {}: {}", creds.gateway_url, e), fix),
+    }
+}
+
+async fn check_identity(creds: &CompanionCredentials) -> SetupCheck {
+    const ID: &str = "identity";
+    const LABEL: &str = "Credentials";
+    let fix = "Pair again with a new code from the Dashboard";
+    let gw = match crate::http::gateway(&creds.gateway_url) {
+        Ok(gw) => gw,
+        Err(e) => return SetupCheck::failed(ID, LABEL, e, fix),
+    };
+    let build = || gw.get("/api/companion/me").timeout(GATEWAY_TIMEOUT);
+    match GatewayConnection::send_authenticated(creds, build).await {
+        Ok(resp) if resp.status().is_success() => {
+            let body: serde_json::Value = resp.json().await.unwrap_or_default();
+            let id = body["companionId"].as_str().unwrap_or(&creds.companion_id);
+            let role = body["role"].as_str().unwrap_or(&creds.role);
+            SetupCheck::passed(ID, LABEL, format!("Signed in as {} ({})", id, role))
+        }
+        Ok(resp) if resp.status() == reqwest::StatusCode::NOT_FOUND => {
+            SetupCheck::skipped(ID, LABEL, "The Gateway cannot report who is signed in; the voice checks use the session")
+        }
+        Ok(resp) => SetupCheck::failed(ID, LABEL, format!("The Gateway refused the session ({})", resp.status()), fix),
+        Err(e) => SetupCheck::failed(ID, LABEL, e.to_string(), fix),
+    }
+}
+
+/// Whether the Gateway answers a speech request built by `build` with success
+async fn probe_speech<F>(creds: &CompanionCredentials, build: F) -> Result<Duration, String>
+where
+    F: Fn(&crate::http::GatewayClient) -> reqwest::RequestBuilder,
+{
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let started = Instant::now();
+    let resp = GatewayConnection::send_authenticated(creds, || build(&gw).timeout(SPEECH_TIMEOUT)).await?;
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        return Err(match text.trim() {
+            "" => format!("HTTP {}", status),
+            text => format!("HTTP {}: {}", status, text),
+        });
+    }
+    Ok(started.elapsed())
+}
+
+async fn check_stt(creds: &CompanionCredentials) -> SetupCheck {
+    const ID: &str = "stt";
+    const LABEL: &str = "Speech recognition";
+    let silence = match audio::encode_wav(&vec![0.0; audio::TARGET_RATE as usize / 2], audio::TARGET_RATE) {
+        Ok(wav) => wav,
+        Err(e) => return SetupCheck::failed(ID, LABEL, e, "Try again"),
+    };
+    let provider = crate::voice::providers().stt;
+    let result = probe_speech(creds, |gw| {
+        let part = reqwest::multipart::Part::bytes(silence.clone())
+            .file_name("audio.wav")
+            .mime_str("audio/wav")
+            .expect("static MIME type is valid");
+        let mut form = reqwest::multipart::Form::new().part("audio", part);
+        if let Some(provider) = &provider {
+            form = form.text("provider", provider.clone());
+        }
+        gw.post("/api/voice/transcribe").multipart(form)
+    })
+    .await;
+    match result {
+        Ok(took) => SetupCheck::passed(ID, LABEL, format!("Transcribed a test clip in {} ms", took.as_millis())),
+        Err(e) => SetupCheck::failed(ID, LABEL, e, "Enable a speech-to-text provider on the Gateway, or set up an STT fallback"),
+    }
+}
+
+async fn check_tts(creds: &CompanionCredentials) -> SetupCheck {
+    const ID: &str = "tts";
+    const LABEL: &str = "Speech synthesis";
+    let mut body = serde_json::json!({ "text": "Ready." });
+    if let Some(provider) = crate::voice::providers().tts {
+        body["provider"] = provider.into();
+    }
+    match probe_speech(creds, |gw| gw.post("/api/voice/synthesize").json(&body)).await {
+        Ok(took) => SetupCheck::passed(ID, LABEL, format!("Synthesized a test phrase in {} ms", took.as_millis())),
+        Err(e) => SetupCheck::failed(ID, LABEL, e, "Enable a text-to-speech provider on the Gateway, or use offline speech"),
+    }
+}
+
+fn check_wake_word(model: Result<String, String>) -> SetupCheck {
+    const ID: &str = "wakeWord";
+    const LABEL: &str = "Wake word";
+    match model {
+        Ok(detail) => SetupCheck::passed(ID, LABEL, detail),
+        Err(e) => SetupCheck::failed(ID, LABEL, e, "Choose another keyword file, or remove it to use the built-in detector"),
+    }
+}
+
+/// Run every check. `wake_model` is the result of
+/// `WakeWordEngine::check_model`; the tone is skipped unless `play_tone`.
+pub async fn run(wake_model: Result<String, String>, play_tone: bool) -> SetupReport {
+    let microphone = tauri::async_runtime::spawn_blocking(check_microphone);
+    let speaker = tauri::async_runtime::spawn_blocking(move || {
+        if play_tone {
+            check_speaker()
+        } else {
+            SetupCheck::skipped("speaker", "Speaker", "Test tone not requested")
+        }
+    });
+    let gateway = check_gateway().await;
+    let failed = |id, label| SetupCheck::failed(id, label, "The check did not finish", "Try again");
+    let microphone = microphone.await.unwrap_or_else(|_| failed("microphone", "Microphone"));
+    let speaker = speaker.await.unwrap_or_else(|_| failed("speaker", "Speaker"));
+    SetupReport::new(vec![microphone, speaker, gateway, check_wake_word(wake_model)])
+}
+
+/// Check freshly paired `creds`: the Gateway, the session and both speech
+/// directions
+pub async fn after_pairing(creds: &CompanionCredentials) -> SetupReport {
+    let (gateway, identity, stt, tts) =
+        tokio::join!(check_health(creds), check_identity(creds), check_stt(creds), check_tts(creds));
+    let report = SetupReport::new(vec![gateway, identity, stt, tts]);
+    for check in report.checks.iter().filter(|c| c.state == CheckState::Failed) {
+        tracing::warn!("[Setup] After pairing, {} failed: {}", check.id, check.detail);
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checklist() {
+        let report = SetupReport::new(vec![
+            SetupCheck::passed("microphone", "Microphone", "Recording from 'USB'"),
+            SetupCheck::skipped("gateway", "Gateway", "Not paired yet"),
+        ]);
+        assert!(report.ready);
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["checks"][1]["state"], "skipped");
+        assert!(json["checks"][0].get("fix").is_none());
+
+        let wake = check_wake_word(Err("Keyword file not found".into()));
+        assert_eq!(wake.id, "wakeWord");
+        assert!(wake.fix.is_some());
+        assert!(!SetupReport::new(vec![wake]).ready);
+    }
+}
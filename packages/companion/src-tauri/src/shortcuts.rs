@@ -0,0 +1,139 @@
+//! # Voice Shortcuts
+//!
+//! User-defined "when I say X, run action Y" mappings, kept in the settings
+//! as `voiceShortcuts`. Transcripts and typed messages are checked against
+//! them before anything is sent to the Gateway, so a deterministic
+//! automation does not depend on how the model interprets the phrase.
+//!
+//! A phrase matches when the whole utterance is the phrase, ignoring case,
+//! accents, punctuation and fillers like "please" (see `intents`). The
+//! action runs through `local_actions` with the usual safety checks; one
+//! that needs confirmation is not run and the reply says so.
+
+use crate::local_actions::{self, ActionRequest};
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoiceShortcut {
+    /// What the user says, e.g. "good night"
+    pub phrase: String,
+    /// A local action (`shell`, `open_app`, …) or `desktop`
+    pub action: String,
+    /// Parameters of the action, as the Gateway would send them
+    #[serde(default)]
+    pub params: serde_json::Value,
+    /// Said when the action succeeds; "Done." when unset
+    #[serde(default)]
+    pub reply: Option<String>,
+}
+
+impl VoiceShortcut {
+    pub fn validate(&self) -> Result<(), String> {
+        if crate::intents::normalize(&self.phrase).is_empty() {
+            return Err("Shortcut phrase must not be empty".into());
+        }
+        if self.action != "desktop" && !local_actions::SUPPORTED_ACTIONS.contains(&self.action.as_str()) {
+            return Err(format!("Unknown action '{}' for shortcut '{}'", self.action, self.phrase));
+        }
+        if !self.params.is_null() && !self.params.is_object() {
+            return Err(format!("Parameters of shortcut '{}' must be an object", self.phrase));
+        }
+        Ok(())
+    }
+
+    fn matches(&self, normalized: &str) -> bool {
+        crate::intents::normalize(&self.phrase) == normalized
+    }
+}
+
+/// The shortcut `text` triggers, if any
+fn find_in(shortcuts: &[VoiceShortcut], text: &str) -> Option<VoiceShortcut> {
+    let text = crate::intents::normalize(text);
+    shortcuts.iter().find(|s| s.matches(&text)).cloned()
+}
+
+// ─── API ────────────────────────────────────────────
+
+pub fn list() -> Vec<VoiceShortcut> {
+    crate::settings::get().voice_shortcuts
+}
+
+/// Whether any shortcut is defined
+pub fn configured() -> bool {
+    !list().is_empty()
+}
+
+/// The shortcut `text` triggers, if any
+pub fn find(text: &str) -> Option<VoiceShortcut> {
+    find_in(&list(), text)
+}
+
+/// Create a shortcut, or replace the one with the same phrase
+pub fn save(app: &AppHandle, shortcut: VoiceShortcut) -> Result<Vec<VoiceShortcut>, String> {
+    shortcut.validate()?;
+    let phrase = crate::intents::normalize(&shortcut.phrase);
+    let settings = crate::settings::update(app, |s| {
+        match s.voice_shortcuts.iter_mut().find(|v| v.matches(&phrase)) {
+            Some(existing) => *existing = shortcut.clone(),
+            None => s.voice_shortcuts.push(shortcut.clone()),
+        }
+    })?;
+    Ok(settings.voice_shortcuts)
+}
+
+pub fn delete(app: &AppHandle, phrase: &str) -> Result<Vec<VoiceShortcut>, String> {
+    let phrase = crate::intents::normalize(phrase);
+    let settings = crate::settings::update(app, |s| s.voice_shortcuts.retain(|v| !v.matches(&phrase)))?;
+    Ok(settings.voice_shortcuts)
+}
+
+/// Run the shortcut's action; returns what to tell the user
+pub async fn run(shortcut: &VoiceShortcut) -> String {
+    tracing::info!("[Shortcuts] '{}' → {}", shortcut.phrase, shortcut.action);
+    let params = if shortcut.params.is_null() { serde_json::json!({}) } else { shortcut.params.clone() };
+    let result = if shortcut.action == "desktop" {
+        local_actions::execute_desktop_async(params, false).await
+    } else {
+        local_actions::execute_async(ActionRequest::from_params(&shortcut.action, &params, false)).await
+    };
+    if result.safety.allowed && !result.awaiting_confirmation() {
+        crate::webhooks::fire("action_executed", serde_json::json!({
+            "source": "shortcut",
+            "phrase": shortcut.phrase,
+            "action": shortcut.action,
+            "success": result.success,
+        }));
+    }
+    if result.awaiting_confirmation() {
+        format!("'{}' needs your confirmation: {}", shortcut.phrase, result.safety.reason)
+    } else if !result.success {
+        format!("'{}' failed: {}", shortcut.phrase, result.output)
+    } else {
+        shortcut.reply.clone().unwrap_or_else(|| "Done.".into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shortcut_matching_and_validation() {
+        let shortcut = |phrase: &str, action: &str| VoiceShortcut {
+            phrase: phrase.into(),
+            action: action.into(),
+            params: serde_json::json!({ "app_name": "spotify" }),
+            reply: None,
+        };
+        let shortcuts = [shortcut("Good night", "shell"), shortcut("music time", "open_app")];
+        assert_eq!(find_in(&shortcuts, "good night, please!").unwrap().action, "shell");
+        assert_eq!(find_in(&shortcuts, "Music time").unwrap().action, "open_app");
+        assert!(find_in(&shortcuts, "good night moon").is_none());
+
+        assert!(shortcuts[1].validate().is_ok());
+        assert!(shortcut("  ", "shell").validate().is_err());
+        assert!(shortcut("format", "format_disk").validate().is_err());
+    }
+}
@@ -0,0 +1,86 @@
+//! # Simulation Mode
+//!
+//! With `simulation` on, every local action that would change the machine —
+//! writing, moving or deleting files, shell commands, launching apps,
+//! ending processes, typing and clicking, publishing to MQTT, user scripts,
+//! downloads — passes its safety checks as usual and then, instead of
+//! running, returns the result it would have had. Reading actions still
+//! run, so answers stay grounded in the real machine.
+//!
+//! It is meant for demoing the assistant and for running Gateway-side
+//! integration tests against a real companion. Fabricated results look like
+//! real ones but carry `simulated: true`, and each is logged here (see
+//! [`log`]) with what would have happened.
+
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Mutex;
+
+/// Simulated actions kept for `simulation_log`
+const LOG_SIZE: usize = 100;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static LOG: Mutex<VecDeque<SimulatedAction>> = Mutex::new(VecDeque::new());
+/// Source of fabricated process ids and file ids
+static NEXT_ID: AtomicU32 = AtomicU32::new(4120);
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedAction {
+    pub timestamp: String,
+    pub action: String,
+    /// The result returned in place of running it
+    pub output: String,
+}
+
+pub fn set_enabled(enabled: bool) {
+    if ENABLED.swap(enabled, Ordering::Relaxed) != enabled {
+        tracing::info!("[Simulation] {}", if enabled { "On: actions are simulated" } else { "Off" });
+    }
+}
+
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+/// A plausible id for a fabricated process or file
+pub fn fake_id() -> u32 {
+    NEXT_ID.fetch_add(4, Ordering::Relaxed)
+}
+
+/// Note that `action` was simulated with `output` as its result
+pub fn record(action: &str, output: &str) {
+    tracing::info!("[Simulation] {} not run; would have returned: {}", action, output);
+    if let Ok(mut log) = LOG.lock() {
+        if log.len() >= LOG_SIZE {
+            log.pop_front();
+        }
+        log.push_back(SimulatedAction {
+            timestamp: chrono::Local::now().to_rfc3339(),
+            action: action.to_string(),
+            output: output.to_string(),
+        });
+    }
+}
+
+/// Simulated actions, oldest first
+pub fn log() -> Vec<SimulatedAction> {
+    LOG.lock().map(|log| log.iter().cloned().collect()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_keeps_the_latest() {
+        for i in 0..LOG_SIZE + 5 {
+            record("write_file", &format!("Written {} bytes to C:\\demo.txt", i));
+        }
+        let log = log();
+        assert_eq!(log.len(), LOG_SIZE);
+        assert_eq!(log.last().unwrap().output, format!("Written {} bytes to C:\\demo.txt", LOG_SIZE + 4));
+        assert_ne!(fake_id(), fake_id());
+    }
+}
@@ -0,0 +1,204 @@
+//! # Speaker Verification
+//!
+//! An opt-in voice match for high-risk actions asked for by voice, so a
+//! guest or a TV cannot command the machine. The user enrolls by saying a
+//! few phrases; their voiceprint (see `audio::voiceprint`, computed on the
+//! device) is kept encrypted in the profile's data directory as
+//! `voiceprint.bin` and never sent anywhere.
+//!
+//! Every voice turn is scored against it. A Gateway-pushed action of risk
+//! `High` that arrives while the last utterance is recent
+//! ([`VOICE_WINDOW`]) counts as voice-triggered and is refused unless that
+//! utterance matched. Typing a message in the window ends the voice
+//! context, since the user is then at the keyboard.
+//!
+//! Settings (`speakerVerification`): `enabled`, and `threshold`, the
+//! similarity (0.5–0.99) an utterance needs to pass.
+
+use crate::safety::{RiskLevel, SafetyVerdict};
+use crate::voice::{CapturedAudio, VoiceEngine};
+use base64::Engine;
+use forgeai_companion_core::audio;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Phrases recorded to enroll
+const ENROLL_UTTERANCES: usize = 3;
+/// How long after an utterance an action counts as asked for by voice
+pub const VOICE_WINDOW: Duration = Duration::from_secs(60);
+
+static CONFIG: Mutex<Option<SpeakerConfig>> = Mutex::new(None);
+/// Score of the last utterance and when it was heard
+static LAST_HEARD: Mutex<Option<(Instant, f32)>> = Mutex::new(None);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SpeakerConfig {
+    pub enabled: bool,
+    /// Similarity an utterance needs to count as the enrolled speaker
+    pub threshold: f32,
+}
+
+impl Default for SpeakerConfig {
+    fn default() -> Self {
+        Self { enabled: false, threshold: 0.9 }
+    }
+}
+
+impl SpeakerConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(0.5..=0.99).contains(&self.threshold) {
+            return Err("Speaker verification threshold must be between 0.5 and 0.99".into());
+        }
+        Ok(())
+    }
+}
+
+/// Use `config` from now on
+pub fn set_config(config: &SpeakerConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+/// Whether high-risk voice actions need the enrolled voice
+pub fn enabled() -> bool {
+    config().enabled
+}
+
+fn config() -> SpeakerConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+// ─── Enrollment ─────────────────────────────────────
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Enrollment {
+    enrolled_at: String,
+    voiceprint: Vec<f32>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SpeakerStatus {
+    pub enabled: bool,
+    pub enrolled: bool,
+    pub enrolled_at: Option<String>,
+    /// Similarity of the last utterance, while it is recent
+    pub last_score: Option<f32>,
+}
+
+pub fn enrollment_path() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("voiceprint.bin"))
+}
+
+fn load() -> Option<Enrollment> {
+    let bytes = crate::secure_store::read_encrypted(&enrollment_path()?).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+fn voiceprint_of(captured: &CapturedAudio) -> Result<Vec<f32>, String> {
+    let wav = base64::engine::general_purpose::STANDARD
+        .decode(&captured.wav_base64)
+        .map_err(|e| format!("Base64 decode error: {}", e))?;
+    let (samples, rate) = audio::decode_wav(&wav)?;
+    audio::voiceprint(&samples, rate).ok_or_else(|| "Too little speech to recognize the voice".into())
+}
+
+/// Record a few phrases and store the user's voiceprint. Blocking; emits
+/// `speaker-enrollment` with the phrase being recorded.
+pub fn enroll(engine: &Mutex<VoiceEngine>) -> Result<SpeakerStatus, String> {
+    let path = enrollment_path().ok_or("Cannot determine data directory")?;
+    let mut prints = Vec::with_capacity(ENROLL_UTTERANCES);
+    while prints.len() < ENROLL_UTTERANCES {
+        crate::events::emit(
+            "speaker-enrollment",
+            serde_json::json!({ "phrase": prints.len() + 1, "of": ENROLL_UTTERANCES }),
+        );
+        let captured = engine.lock().map_err(|e| e.to_string())?.record()?;
+        match voiceprint_of(&captured) {
+            Ok(print) => prints.push(print),
+            // Asked again rather than failing the whole enrollment
+            Err(e) => tracing::info!("[Speaker] Enrollment phrase rejected: {}", e),
+        }
+    }
+    let voiceprint: Vec<f32> = (0..prints[0].len())
+        .map(|i| prints.iter().map(|p| p[i]).sum::<f32>() / prints.len() as f32)
+        .collect();
+    let enrollment = Enrollment { enrolled_at: chrono::Utc::now().to_rfc3339(), voiceprint };
+    let json = serde_json::to_vec(&enrollment).map_err(|e| format!("Serialize error: {}", e))?;
+    crate::secure_store::write_encrypted(&path, &json)?;
+    tracing::info!("[Speaker] Voice enrolled");
+    Ok(status())
+}
+
+/// Delete the voiceprint
+pub fn forget() -> Result<(), String> {
+    if let Some(path) = enrollment_path().filter(|p| p.exists()) {
+        std::fs::remove_file(path).map_err(|e| format!("Failed to delete voiceprint: {}", e))?;
+    }
+    if let Ok(mut last) = LAST_HEARD.lock() {
+        *last = None;
+    }
+    Ok(())
+}
+
+fn recent_score() -> Option<f32> {
+    LAST_HEARD
+        .lock()
+        .ok()
+        .and_then(|l| *l)
+        .filter(|(at, _)| at.elapsed() < VOICE_WINDOW)
+        .map(|(_, score)| score)
+}
+
+pub fn status() -> SpeakerStatus {
+    let enrollment = load();
+    SpeakerStatus {
+        enabled: config().enabled,
+        enrolled: enrollment.is_some(),
+        enrolled_at: enrollment.map(|e| e.enrolled_at),
+        last_score: recent_score(),
+    }
+}
+
+// ─── Verification ───────────────────────────────────
+
+/// Score a voice turn's recording against the enrolled voice. Too little
+/// speech scores 0.
+pub fn observe(captured: &CapturedAudio) {
+    if !config().enabled {
+        return;
+    }
+    let Some(enrollment) = load() else { return };
+    let score = voiceprint_of(captured).map(|p| audio::similarity(&enrollment.voiceprint, &p)).unwrap_or(0.0);
+    tracing::debug!("[Speaker] Utterance scored {:.2}", score);
+    if let Ok(mut last) = LAST_HEARD.lock() {
+        *last = Some((Instant::now(), score));
+    }
+}
+
+/// The user typed: what follows is not asked for by voice
+pub fn end_voice_context() {
+    if let Ok(mut last) = LAST_HEARD.lock() {
+        *last = None;
+    }
+}
+
+/// Refuse a high-risk action asked for by a voice other than the enrolled one
+pub fn check(verdict: &SafetyVerdict) -> Result<(), String> {
+    let config = config();
+    if !config.enabled || verdict.risk != RiskLevel::High {
+        return Ok(());
+    }
+    match recent_score() {
+        Some(score) if score < config.threshold && load().is_some() => Err(format!(
+            "The voice asking for this was not recognized as the enrolled speaker ({:.0}% match)",
+            score * 100.0
+        )),
+        _ => Ok(()),
+    }
+}
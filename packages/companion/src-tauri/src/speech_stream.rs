@@ -0,0 +1,288 @@
+//! # Sentence-by-Sentence Speech
+//!
+//! Speaks a chat reply while the Gateway is still writing it. With
+//! `streamReplies` on, a voice turn is transcribed first and the words
+//! sent to `/api/chat/stream`, whose server-sent events carry the reply in
+//! `chunk`s. A [`Splitter`] cuts the text into sentences as it arrives;
+//! each is synthesized and handed to the playback queue, so the first
+//! sentence plays while the rest is still being generated and the next
+//! one is synthesized while the current one plays.
+//!
+//! Code blocks and markdown markup are not read aloud. Gateways without
+//! the streaming route answer 404; the turn then goes through
+//! `/api/chat/voice` as usual and streaming is not tried again until
+//! restart. `stop_playback` ends the spoken reply too.
+
+use crate::connection::{CompanionCredentials, GatewayConnection};
+use crate::quiet_hours::Output;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::mpsc;
+
+/// Shorter sentences are joined with the next one (fewer TTS requests)
+const MIN_SENTENCE_CHARS: usize = 12;
+/// Sentences synthesized ahead of the one playing
+const SYNTH_AHEAD: usize = 2;
+/// Words ending in a period that do not end a sentence
+const ABBREVIATIONS: &[&str] = &["mr", "mrs", "ms", "dr", "sr", "sra", "st", "vs", "e.g", "i.e", "p.ex"];
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+/// Cleared when the Gateway turns out not to have the streaming route
+static SUPPORTED: AtomicBool = AtomicBool::new(true);
+
+pub fn set_enabled(enabled: bool) {
+    ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Whether voice turns should stream their reply
+pub fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed) && SUPPORTED.load(Ordering::Relaxed)
+}
+
+// ─── Sentence splitting ─────────────────────────────
+
+/// Cuts streamed text into speakable sentences
+#[derive(Debug, Default)]
+pub struct Splitter {
+    pending: String,
+    in_code: bool,
+}
+
+impl Splitter {
+    /// Add streamed text; returns the sentences it completed
+    pub fn push(&mut self, text: &str) -> Vec<String> {
+        self.pending.push_str(text);
+        let mut sentences = Vec::new();
+        while let Some(end) = self.boundary() {
+            let piece: String = self.pending.drain(..end).collect();
+            sentences.extend(self.speakable(&piece));
+        }
+        sentences
+    }
+
+    /// What is left once the stream ended
+    pub fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.pending);
+        self.speakable(&rest)
+    }
+
+    /// End of the first complete sentence in `pending`: after a line break,
+    /// or after `.`, `!`, `?` or `…` followed by whitespace
+    fn boundary(&self) -> Option<usize> {
+        let text = &self.pending;
+        let mut chars = text.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let end = i + c.len_utf8();
+            if c == '\n' {
+                return Some(end);
+            }
+            let Some(&(_, next)) = chars.peek() else { break };
+            let terminal = matches!(c, '.' | '!' | '?' | '…') && next.is_whitespace();
+            if !terminal || text[..end].trim().chars().count() < MIN_SENTENCE_CHARS {
+                continue;
+            }
+            if c == '.' {
+                let word = text[..i].rsplit(char::is_whitespace).next().unwrap_or("").to_lowercase();
+                if ABBREVIATIONS.contains(&word.as_str()) {
+                    continue;
+                }
+            }
+            return Some(end);
+        }
+        None
+    }
+
+    /// `piece` without markup, or None when nothing in it should be said
+    fn speakable(&mut self, piece: &str) -> Option<String> {
+        let line = piece.trim();
+        if line.starts_with("```") {
+            self.in_code = !self.in_code;
+            return None;
+        }
+        if self.in_code {
+            return None;
+        }
+        let line = line.trim_start_matches('#').trim_start();
+        let line = line.strip_prefix("- ").unwrap_or(line);
+        let text: String = line.chars().filter(|c| !matches!(c, '*' | '`')).collect();
+        let text = text.trim();
+        text.chars().any(char::is_alphanumeric).then(|| text.to_string())
+    }
+}
+
+// ─── Streaming a reply ──────────────────────────────
+
+#[derive(Debug)]
+pub enum StreamError {
+    /// The Gateway has no `/api/chat/stream`
+    Unsupported,
+    Failed(String),
+}
+
+impl From<String> for StreamError {
+    fn from(e: String) -> Self {
+        StreamError::Failed(e)
+    }
+}
+
+/// A reply that was spoken as it streamed in
+pub struct StreamedReply {
+    pub content: String,
+    pub session_id: Option<String>,
+}
+
+/// Synthesize sentences in order and play them as they are ready
+async fn speak_sentences(
+    creds: CompanionCredentials,
+    language: Option<String>,
+    mut sentences: mpsc::UnboundedReceiver<String>,
+) {
+    let generation = crate::voice::playback_generation();
+    let (audio_tx, mut audio_rx) = mpsc::channel::<(Vec<u8>, String)>(SYNTH_AHEAD);
+    let player = tauri::async_runtime::spawn(async move {
+        let mut first = true;
+        while let Some((bytes, text)) = audio_rx.recv().await {
+            if crate::voice::stopped_since(generation) {
+                break;
+            }
+            if first {
+                crate::events::emit("voice-state", serde_json::json!({ "state": "speaking" }));
+                first = false;
+            }
+            let played = tauri::async_runtime::spawn_blocking(move || {
+                crate::voice::play_output(&bytes, Output::Speech(Some(&text)))
+            })
+            .await;
+            if let Ok(Err(e)) = played {
+                tracing::warn!("[SpeechStream] Playback failed: {}", e);
+            }
+        }
+    });
+    while let Some(sentence) = sentences.recv().await {
+        if crate::voice::stopped_since(generation) {
+            break;
+        }
+        match crate::voice::synthesize_speech(&creds, &sentence, language.as_deref()).await {
+            Ok(bytes) => {
+                if audio_tx.send((bytes, sentence)).await.is_err() {
+                    break;
+                }
+            }
+            Err(e) => tracing::warn!("[SpeechStream] Sentence skipped, TTS failed: {}", e),
+        }
+    }
+    drop(audio_tx);
+    let _ = player.await;
+}
+
+/// Send `message` to the Gateway and speak the reply sentence by sentence
+/// as it streams in, asking for it in `language` when given. Returns once
+/// the whole reply was spoken.
+pub async fn reply(
+    creds: &CompanionCredentials,
+    message: &str,
+    language: Option<&str>,
+    session_id: Option<String>,
+) -> Result<StreamedReply, StreamError> {
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let mut payload = serde_json::json!({
+        "message": message,
+        "sessionId": session_id,
+        "userId": creds.companion_id,
+        "channelType": "companion",
+    });
+    if let Some(language) = language {
+        payload["language"] = language.into();
+    }
+    if let Some(context) = crate::screen_context::for_request().await {
+        payload["screenContext"] = serde_json::to_value(context).unwrap_or_default();
+    }
+    let build = || gw.post("/api/chat/stream").json(&payload);
+    crate::latency::mark(crate::latency::Stage::RequestSent);
+    let mut resp = GatewayConnection::send_authenticated(creds, build)
+        .await
+        .map_err(|e| StreamError::Failed(e.to_string()))?;
+    if matches!(resp.status().as_u16(), 404 | 405) {
+        tracing::info!("[SpeechStream] Gateway has no streaming chat, using whole replies");
+        SUPPORTED.store(false, Ordering::Relaxed);
+        return Err(StreamError::Unsupported);
+    }
+    if !resp.status().is_success() {
+        let status = resp.status();
+        let body = resp.text().await.unwrap_or_default();
+        return Err(format!("Gateway HTTP {}: {}", status, body).into());
+    }
+
+    let (sentence_tx, sentence_rx) = mpsc::unbounded_channel();
+    let speaker = tauri::async_runtime::spawn(speak_sentences(creds.clone(), language.map(str::to_string), sentence_rx));
+    let mut splitter = Splitter::default();
+    let mut content = String::new();
+    let mut session_id = session_id;
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut failure = None;
+
+    // Server-sent events: `data: {json}` lines
+    'read: while let Some(bytes) = resp.chunk().await.map_err(|e| format!("Reply stream broke off: {}", e))? {
+        buffer.extend_from_slice(&bytes);
+        while let Some(pos) = buffer.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=pos).collect();
+            let line = String::from_utf8_lossy(&line);
+            let Some(data) = line.trim().strip_prefix("data:") else { continue };
+            let Ok(event) = serde_json::from_str::<serde_json::Value>(data.trim()) else { continue };
+            match event["type"].as_str() {
+                Some("chunk") => {
+                    let text = event["content"].as_str().unwrap_or("");
+                    if content.is_empty() {
+                        crate::latency::mark(crate::latency::Stage::Replied);
+                    }
+                    content.push_str(text);
+                    for sentence in splitter.push(text) {
+                        let _ = sentence_tx.send(sentence);
+                    }
+                }
+                Some("done") => {
+                    if let Some(id) = event["sessionId"].as_str() {
+                        session_id = Some(id.to_string());
+                    }
+                    break 'read;
+                }
+                Some("error") => {
+                    failure = Some(event["error"].as_str().unwrap_or("Stream failed").to_string());
+                    break 'read;
+                }
+                _ => {}
+            }
+        }
+    }
+    if let Some(rest) = splitter.finish() {
+        let _ = sentence_tx.send(rest);
+    }
+    drop(sentence_tx);
+    let _ = speaker.await;
+
+    match failure {
+        Some(e) if content.is_empty() => Err(format!("Gateway error: {}", e).into()),
+        _ => Ok(StreamedReply { content, session_id }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sentence_splitting() {
+        let mut splitter = Splitter::default();
+        let mut spoken = Vec::new();
+        for chunk in ["Sure! Dr. Silva arrives at 3", ". The meeting", " is at 10.30 today. Then", " lunch"] {
+            spoken.extend(splitter.push(chunk));
+        }
+        spoken.extend(splitter.finish());
+        assert_eq!(spoken, ["Sure! Dr. Silva arrives at 3.", "The meeting is at 10.30 today.", "Then lunch"]);
+
+        let mut splitter = Splitter::default();
+        let text = "Here is the **fix**:\n```rust\nlet x = 1;\n```\n- Run `cargo test` again.\n";
+        let spoken = splitter.push(text);
+        assert_eq!(spoken, ["Here is the fix:", "Run cargo test again."]);
+        assert_eq!(splitter.finish(), None);
+    }
+}
@@ -0,0 +1,203 @@
+//! # Companion Status
+//!
+//! A single snapshot of everything the UI and tray display — pairing,
+//! Gateway link, safety mode, recording, wake word, microphone mute and
+//! idle state. `get_status` returns it on demand; [`publish`] emits it as
+//! `companion-status` whenever one of those changes, so the frontend does
+//! not have to poll.
+//!
+//! Below the flat fields the dashboard badges use, `subsystems` reports
+//! the health of each part, so one call renders the whole dashboard:
+//!
+//! - `connection` — link state, latency, the last heartbeat, queued messages
+//! - `voice` — the input and output devices found, the last audio error
+//! - `wake_word` — running or suspended, the keyword model, the last detection
+//! - `safety` — enforcement mode and the version of the guardrail rules
+//!
+//! Recording and wake-word state are read from the engines' shared flags,
+//! never from their locks, so publishing is safe from any thread and while
+//! an engine is locked.
+
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, OnceLock};
+use tauri::{AppHandle, Manager};
+
+/// Shared flags of the managed voice and wake-word engines
+struct EngineFlags {
+    recording: Arc<AtomicBool>,
+    wake_word: Arc<AtomicBool>,
+}
+
+static FLAGS: OnceLock<EngineFlags> = OnceLock::new();
+
+/// Payload of `get_status` and the `companion-status` event
+#[derive(Debug, Clone, Serialize)]
+pub struct CompanionStatus {
+    pub connected: bool,
+    pub paired: bool,
+    pub link: crate::heartbeat::ConnectionStatus,
+    pub queued_messages: usize,
+    pub gateway_route: Option<crate::roaming::Route>,
+    pub gateway_url: Option<String>,
+    pub companion_id: Option<String>,
+    pub auth_token: Option<String>,
+    pub safety_active: bool,
+    pub recording: bool,
+    pub wake_word: bool,
+    pub mic_muted: bool,
+    /// No keyboard or mouse input for the configured idle time
+    pub idle: bool,
+    pub version: String,
+    pub subsystems: Subsystems,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Subsystems {
+    pub connection: ConnectionHealth,
+    pub voice: VoiceHealth,
+    pub wake_word: WakeWordHealth,
+    pub safety: SafetyHealth,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ConnectionHealth {
+    pub state: crate::heartbeat::LinkState,
+    pub latency_ms: Option<u64>,
+    pub last_heartbeat: Option<String>,
+    pub live_channel: bool,
+    pub consecutive_failures: u32,
+    pub queued_messages: usize,
+    pub detail: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct VoiceHealth {
+    /// Both an input and an output device are available
+    pub devices_ok: bool,
+    pub input_device: Option<String>,
+    pub output_device: Option<String>,
+    pub recording_failures: u64,
+    pub playback_failures: u64,
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WakeWordHealth {
+    pub running: bool,
+    /// Why listening is paused by a policy
+    pub suspended: Option<String>,
+    pub model: String,
+    pub last_detection: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SafetyMode {
+    /// The guardrails, with confirmation of risky actions
+    Standard,
+    /// As standard, and high-risk voice actions need the enrolled voice
+    SpeakerVerified,
+    /// Checks run, but actions that would change the machine are only
+    /// simulated
+    Simulation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SafetyHealth {
+    pub mode: SafetyMode,
+    pub policy_version: u32,
+    /// Actions refused among the recent safety verdicts
+    pub recent_blocks: usize,
+}
+
+/// Pick up the engines' flags (called once from `setup`)
+pub fn init(app: &AppHandle) {
+    let voice = app.state::<crate::commands::VoiceState>();
+    let wake = app.state::<crate::commands::WakeWordState>();
+    let recording = voice.0.lock().ok().map(|v| v.stop_handle());
+    let wake_word = wake.0.lock().ok().map(|w| w.running_handle());
+    if let (Some(recording), Some(wake_word)) = (recording, wake_word) {
+        let _ = FLAGS.set(EngineFlags { recording, wake_word });
+    }
+}
+
+fn flag(select: impl Fn(&EngineFlags) -> &Arc<AtomicBool>) -> bool {
+    FLAGS.get().is_some_and(|f| select(f).load(Ordering::Relaxed))
+}
+
+fn voice_health() -> VoiceHealth {
+    use cpal::traits::DeviceTrait;
+    let input_device = crate::voice::input_device().and_then(|d| d.name().ok());
+    let output_device = crate::voice::output_device().and_then(|d| d.name().ok());
+    let stats = crate::voice::stats();
+    VoiceHealth {
+        devices_ok: input_device.is_some() && output_device.is_some(),
+        input_device,
+        output_device,
+        recording_failures: stats.recording_failures,
+        playback_failures: stats.playback_failures,
+        last_error: stats.last_error,
+    }
+}
+
+fn subsystems(link: &crate::heartbeat::ConnectionStatus, queued_messages: usize) -> Subsystems {
+    Subsystems {
+        connection: ConnectionHealth {
+            state: link.state,
+            latency_ms: link.latency_ms,
+            last_heartbeat: link.last_checked.clone(),
+            live_channel: link.live_channel,
+            consecutive_failures: link.consecutive_failures,
+            queued_messages,
+            detail: link.detail.clone(),
+        },
+        voice: voice_health(),
+        wake_word: WakeWordHealth {
+            running: flag(|f| &f.wake_word),
+            suspended: crate::wake_word::suspension(),
+            model: crate::wake_word::model(),
+            last_detection: crate::wake_word::last_detection(),
+        },
+        safety: SafetyHealth {
+            mode: if crate::simulation::enabled() {
+                SafetyMode::Simulation
+            } else if crate::speaker::enabled() {
+                SafetyMode::SpeakerVerified
+            } else {
+                SafetyMode::Standard
+            },
+            policy_version: crate::safety::POLICY_VERSION,
+            recent_blocks: crate::safety::recent_verdicts().iter().filter(|v| !v.verdict.allowed).count(),
+        },
+    }
+}
+
+/// Current status
+pub fn snapshot() -> CompanionStatus {
+    let creds = crate::connection::GatewayConnection::load_credentials();
+    let link = crate::heartbeat::current();
+    let queued_messages = crate::outbox::pending();
+    CompanionStatus {
+        subsystems: subsystems(&link, queued_messages),
+        connected: creds.is_some() && link.state != crate::heartbeat::LinkState::Offline,
+        paired: creds.is_some(),
+        link,
+        queued_messages,
+        gateway_route: crate::roaming::active(),
+        gateway_url: creds.as_ref().map(|c| c.gateway_url.clone()),
+        companion_id: creds.as_ref().map(|c| c.companion_id.clone()),
+        auth_token: creds.as_ref().and_then(|c| c.auth_token.clone()),
+        safety_active: true,
+        recording: flag(|f| &f.recording),
+        wake_word: flag(|f| &f.wake_word),
+        mic_muted: crate::voice::mic_muted(),
+        idle: crate::idle::is_idle(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    }
+}
+
+/// Emit the current status as `companion-status`
+pub fn publish() {
+    crate::events::emit("companion-status", snapshot());
+}
@@ -0,0 +1,174 @@
+//! # STT Fallback
+//!
+//! A second speech-to-text backend for when the Gateway's
+//! `/api/voice/transcribe` fails or does not answer within
+//! `primaryTimeoutSecs`: another route on the same Gateway (one backed by a
+//! different provider, say), or a Whisper server reachable from this
+//! machine that speaks the `/stt` API of the Gateway's `whisper-vps`
+//! backend (multipart `audio`, `{ text, language }` back).
+//!
+//! Transcripts carry the backend that produced them: `gateway`, the
+//! fallback route, or `whisper`. Every fallback is logged, emitted as
+//! `stt-fallback` and timed under its backend's label in `stt_ms`.
+
+use crate::connection::{CompanionCredentials, GatewayConnection};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Gateway timeout without a fallback to turn to
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const FALLBACK_TIMEOUT: Duration = Duration::from_secs(60);
+
+static CONFIG: Mutex<Option<SttFallbackConfig>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SttBackend {
+    /// No fallback
+    #[default]
+    None,
+    /// `route` on the paired Gateway
+    GatewayRoute,
+    /// The Whisper server at `whisperUrl`
+    Whisper,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SttFallbackConfig {
+    pub backend: SttBackend,
+    /// Gateway route for `gatewayRoute`, e.g. `/api/voice/transcribe?provider=whisper-local`
+    pub route: String,
+    /// Base URL for `whisper`, e.g. `http://127.0.0.1:5051`
+    pub whisper_url: String,
+    /// How long the Gateway gets before the fallback is tried
+    pub primary_timeout_secs: u64,
+}
+
+impl Default for SttFallbackConfig {
+    fn default() -> Self {
+        Self { backend: SttBackend::None, route: String::new(), whisper_url: String::new(), primary_timeout_secs: 15 }
+    }
+}
+
+impl SttFallbackConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        if !(1..=120).contains(&self.primary_timeout_secs) {
+            return Err("STT timeout must be between 1 and 120 seconds".into());
+        }
+        match self.backend {
+            SttBackend::None => {}
+            SttBackend::GatewayRoute if !self.route.starts_with('/') => {
+                return Err("The fallback STT route must be a Gateway path starting with '/'".into());
+            }
+            SttBackend::GatewayRoute => {}
+            SttBackend::Whisper => {
+                let url = reqwest::Url::parse(&self.whisper_url).map_err(|e| format!("Invalid Whisper URL: {}", e))?;
+                if !matches!(url.scheme(), "http" | "https") {
+                    return Err("The Whisper URL must be http or https".into());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Use `config` from now on
+pub fn set_config(config: &SttFallbackConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+fn config() -> SttFallbackConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+/// How long to wait for the Gateway's own transcription
+pub fn primary_timeout() -> Duration {
+    let config = config();
+    match config.backend {
+        SttBackend::None => DEFAULT_TIMEOUT,
+        _ => Duration::from_secs(config.primary_timeout_secs),
+    }
+}
+
+fn audio_form(wav: &[u8]) -> reqwest::multipart::Form {
+    let part = reqwest::multipart::Part::bytes(wav.to_vec())
+        .file_name("audio.wav")
+        .mime_str("audio/wav")
+        .expect("static MIME type is valid");
+    reqwest::multipart::Form::new().part("audio", part)
+}
+
+async fn parse(resp: reqwest::Response) -> Result<serde_json::Value, String> {
+    if !resp.status().is_success() {
+        let status = resp.status();
+        return Err(format!("HTTP {}: {}", status, resp.text().await.unwrap_or_default()));
+    }
+    resp.json().await.map_err(|e| format!("Parse error: {}", e))
+}
+
+/// Transcribe `wav` with the fallback backend after the Gateway failed with
+/// `error`. None when no fallback is configured; otherwise the response
+/// body and the backend's name.
+pub async fn transcribe(creds: &CompanionCredentials, wav: &[u8], error: &str) -> Option<Result<(serde_json::Value, String), String>> {
+    let config = config();
+    let (result, backend) = match config.backend {
+        SttBackend::None => return None,
+        SttBackend::GatewayRoute => {
+            let gw = match crate::http::gateway(&creds.gateway_url) {
+                Ok(gw) => gw,
+                Err(e) => return Some(Err(e)),
+            };
+            let build = || {
+                let mut form = audio_form(wav);
+                let vocabulary = crate::vocabulary::hint();
+                if !vocabulary.is_empty() {
+                    form = form.text("vocabulary", vocabulary.join(", "));
+                }
+                gw.post(&config.route).multipart(form).timeout(FALLBACK_TIMEOUT)
+            };
+            let result = match GatewayConnection::send_authenticated(creds, build).await {
+                Ok(resp) => parse(resp).await,
+                Err(e) => Err(e.to_string()),
+            };
+            (result, config.route.clone())
+        }
+        SttBackend::Whisper => {
+            let url = format!("{}/stt", config.whisper_url.trim_end_matches('/'));
+            let result = match crate::proxy::apply(reqwest::Client::builder()).build() {
+                Ok(client) => match client.post(&url).multipart(audio_form(wav)).timeout(FALLBACK_TIMEOUT).send().await {
+                    Ok(resp) => parse(resp).await,
+                    Err(e) => Err(format!("Whisper unreachable: {}", e)),
+                },
+                Err(e) => Err(format!("HTTP client error: {}", e)),
+            };
+            (result, "whisper".to_string())
+        }
+    };
+    match &result {
+        Ok(_) => tracing::warn!("[STT] Gateway transcription failed ({}), transcribed by '{}'", error, backend),
+        Err(e) => tracing::warn!("[STT] Gateway transcription failed ({}), fallback '{}' failed too: {}", error, backend, e),
+    }
+    crate::events::emit("stt-fallback", serde_json::json!({ "backend": backend, "error": error, "ok": result.is_ok() }));
+    Some(result.map(|data| (data, backend)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_config() {
+        assert!(SttFallbackConfig::default().validate().is_ok());
+        let route = SttFallbackConfig { backend: SttBackend::GatewayRoute, route: "/api/voice/transcribe?provider=whisper-local".into(), ..Default::default() };
+        assert!(route.validate().is_ok());
+        assert!(SttFallbackConfig { route: "api/stt".into(), ..route.clone() }.validate().is_err());
+        let whisper = SttFallbackConfig { backend: SttBackend::Whisper, whisper_url: "http://127.0.0.1:5051".into(), ..Default::default() };
+        assert!(whisper.validate().is_ok());
+        assert!(SttFallbackConfig { whisper_url: "ftp://host".into(), ..whisper.clone() }.validate().is_err());
+        assert!(SttFallbackConfig { primary_timeout_secs: 0, ..whisper }.validate().is_err());
+    }
+}
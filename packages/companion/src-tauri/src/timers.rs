@@ -0,0 +1,219 @@
+//! # Timers and Alarms
+//!
+//! Named countdown timers ("pasta, ten minutes") and alarms at a time of
+//! day, run entirely on the companion. While any is active,
+//! `timer-progress` is emitted every second with the remaining time of
+//! each; when one ends, `timer-finished` is emitted, an OS notification is
+//! shown and its earcon plays.
+//!
+//! Timers live in memory only and do not survive a restart; use reminders
+//! for anything that must.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+/// Interval of `timer-progress` events
+const TICK: Duration = Duration::from_secs(1);
+/// Longest countdown accepted
+const MAX_SECS: u64 = 24 * 3600;
+
+static TIMERS: Mutex<Vec<Timer>> = Mutex::new(Vec::new());
+/// Wakes the ticker when a timer is added or cancelled
+static CHANGED: OnceLock<Notify> = OnceLock::new();
+
+fn changed() -> &'static Notify {
+    CHANGED.get_or_init(Notify::new)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimerKind {
+    /// Counts down a duration
+    Timer,
+    /// Goes off at a point in time
+    Alarm,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timer {
+    pub id: String,
+    pub name: String,
+    pub kind: TimerKind,
+    /// RFC 3339
+    pub started_at: String,
+    /// RFC 3339
+    pub ends_at: String,
+    /// Seconds left, as of the last tick or listing
+    pub remaining_secs: u64,
+    #[serde(skip)]
+    deadline: DateTime<Utc>,
+}
+
+impl Timer {
+    fn remaining(&self, now: DateTime<Utc>) -> u64 {
+        // Round up so a timer shows 1 s until it is really over
+        let ms = (self.deadline - now).num_milliseconds().max(0) as u64;
+        ms.div_ceil(1000)
+    }
+}
+
+/// Refresh `remaining_secs` and take out the timers that are over
+fn tick(timers: &mut Vec<Timer>, now: DateTime<Utc>) -> Vec<Timer> {
+    for timer in timers.iter_mut() {
+        timer.remaining_secs = timer.remaining(now);
+    }
+    let (done, running) = std::mem::take(timers).into_iter().partition(|t| t.deadline <= now);
+    *timers = running;
+    done
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Start a countdown of `seconds`, or an alarm at `at` (RFC 3339)
+pub fn start(name: Option<&str>, seconds: Option<u64>, at: Option<&str>) -> Result<Timer, String> {
+    let now = Utc::now();
+    let (kind, ends_at) = match (seconds, at) {
+        (Some(0), None) => return Err("A timer needs at least one second".into()),
+        (Some(secs), None) if secs > MAX_SECS => return Err("Timers can run for at most 24 hours".into()),
+        (Some(secs), None) => (TimerKind::Timer, now + chrono::Duration::seconds(secs as i64)),
+        (None, Some(at)) => {
+            let at = DateTime::parse_from_rfc3339(at)
+                .map_err(|e| format!("Invalid time '{}': {}", at, e))?
+                .with_timezone(&Utc);
+            if at <= now {
+                return Err("The alarm time is in the past".into());
+            }
+            (TimerKind::Alarm, at)
+        }
+        _ => return Err("Give either a duration in seconds or an alarm time".into()),
+    };
+    let name = name.map(str::trim).filter(|n| !n.is_empty()).map(str::to_string).unwrap_or_else(|| {
+        match kind {
+            TimerKind::Timer => "Timer",
+            TimerKind::Alarm => "Alarm",
+        }
+        .to_string()
+    });
+    let mut timer = Timer {
+        id: format!("timer-{}", now.timestamp_millis()),
+        name,
+        kind,
+        started_at: now.to_rfc3339(),
+        ends_at: ends_at.to_rfc3339(),
+        remaining_secs: 0,
+        deadline: ends_at,
+    };
+    timer.remaining_secs = timer.remaining(now);
+    TIMERS.lock().map_err(|e| e.to_string())?.push(timer.clone());
+    tracing::info!("[Timers] Started {} '{}' until {}", timer.id, timer.name, timer.ends_at);
+    changed().notify_one();
+    Ok(timer)
+}
+
+/// Active timers and alarms, soonest first
+pub fn list() -> Vec<Timer> {
+    let now = Utc::now();
+    let mut timers = TIMERS.lock().map(|t| t.clone()).unwrap_or_default();
+    for timer in timers.iter_mut() {
+        timer.remaining_secs = timer.remaining(now);
+    }
+    timers.sort_by_key(|t| t.deadline);
+    timers
+}
+
+/// Cancel by id, or by name (case-insensitive) when no id matches; false
+/// when nothing matched
+pub fn cancel(id_or_name: &str) -> bool {
+    let Ok(mut timers) = TIMERS.lock() else {
+        return false;
+    };
+    let before = timers.len();
+    if timers.iter().any(|t| t.id == id_or_name) {
+        timers.retain(|t| t.id != id_or_name);
+    } else {
+        timers.retain(|t| !t.name.eq_ignore_ascii_case(id_or_name.trim()));
+    }
+    let cancelled = timers.len() < before;
+    drop(timers);
+    if cancelled {
+        changed().notify_one();
+    }
+    cancelled
+}
+
+fn finish(timer: Timer) {
+    tracing::info!("[Timers] {} '{}' finished", timer.id, timer.name);
+    crate::events::notify(&timer.name, match timer.kind {
+        TimerKind::Timer => "Time's up",
+        TimerKind::Alarm => "Alarm",
+    });
+    crate::events::emit("timer-finished", &timer);
+    crate::webhooks::fire("timer_finished", &timer);
+    let earcon = match timer.kind {
+        TimerKind::Timer => crate::earcons::Earcon::Timer,
+        TimerKind::Alarm => crate::earcons::Earcon::Alarm,
+    };
+    tauri::async_runtime::spawn_blocking(move || {
+        if let Err(e) = crate::earcons::play(earcon) {
+            tracing::warn!("[Timers] Could not play the alert: {}", e);
+        }
+    });
+}
+
+/// Emit progress every second while timers run and alert when they end
+pub fn spawn_ticker() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            let (active, done) = match TIMERS.lock() {
+                Ok(mut timers) => {
+                    let done = tick(&mut timers, Utc::now());
+                    (timers.clone(), done)
+                }
+                Err(_) => (Vec::new(), Vec::new()),
+            };
+            done.into_iter().for_each(finish);
+            if active.is_empty() {
+                changed().notified().await;
+                continue;
+            }
+            crate::events::emit("timer-progress", &active);
+            tokio::select! {
+                _ = tokio::time::sleep(TICK) => {}
+                _ = changed().notified() => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_counts_down_and_finishes() {
+        let start = DateTime::parse_from_rfc3339("2026-05-01T12:00:00Z").unwrap().with_timezone(&Utc);
+        let timer = |id: &str, secs: i64| Timer {
+            id: id.into(),
+            name: id.into(),
+            kind: TimerKind::Timer,
+            started_at: start.to_rfc3339(),
+            ends_at: String::new(),
+            remaining_secs: 0,
+            deadline: start + chrono::Duration::seconds(secs),
+        };
+        let mut timers = vec![timer("eggs", 5), timer("pasta", 600)];
+
+        assert!(tick(&mut timers, start + chrono::Duration::milliseconds(4100)).is_empty());
+        assert_eq!(timers[0].remaining_secs, 1);
+        assert_eq!(timers[1].remaining_secs, 596);
+
+        let done = tick(&mut timers, start + chrono::Duration::seconds(5));
+        assert_eq!(done.len(), 1);
+        assert_eq!(done[0].name, "eggs");
+        assert_eq!(timers.len(), 1);
+    }
+}
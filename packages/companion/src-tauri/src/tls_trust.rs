@@ -0,0 +1,237 @@
+//! # Self-Signed Certificate Trust & Pinning
+//!
+//! Home-lab Gateways often serve HTTPS with a self-signed certificate,
+//! which the system trust store rejects. At pairing time the frontend can
+//! inspect the Gateway's certificate, show its SHA-256 fingerprint, and let
+//! the user trust it. The certificate is then pinned: for that Gateway it
+//! becomes the *only* accepted trust anchor, for both HTTP and WebSocket.
+//!
+//! Pins are stored per origin (`host:port`) in `pinned_certs.json` and kept
+//! in memory after the first read, since every client build consults them.
+
+use base64::Engine as _;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Pins as last read from or written to disk
+static PINS: Mutex<Option<HashMap<String, PinnedCert>>> = Mutex::new(None);
+
+/// A Gateway certificate as shown to the user
+#[derive(Debug, Clone, Serialize)]
+pub struct CertInfo {
+    pub origin: String,
+    pub fingerprint: String,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct PinnedCert {
+    fingerprint: String,
+    der_base64: String,
+    trusted_at: String,
+}
+
+fn pins_path() -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("pinned_certs.json"))
+}
+
+fn read_pins() -> HashMap<String, PinnedCert> {
+    pins_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+fn load_pins() -> HashMap<String, PinnedCert> {
+    match PINS.lock() {
+        Ok(mut cached) => cached.get_or_insert_with(read_pins).clone(),
+        Err(_) => read_pins(),
+    }
+}
+
+fn save_pins(pins: &HashMap<String, PinnedCert>) -> Result<(), String> {
+    let path = pins_path().ok_or("Cannot determine data directory")?;
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let json = serde_json::to_string_pretty(pins).map_err(|e| format!("Serialize error: {}", e))?;
+    std::fs::write(&path, json).map_err(|e| format!("File save error: {}", e))?;
+    if let Ok(mut cached) = PINS.lock() {
+        *cached = Some(pins.clone());
+    }
+    Ok(())
+}
+
+/// `host:port` of an https Gateway URL (None for plain http)
+fn origin(gateway_url: &str) -> Option<String> {
+    let url = url::Url::parse(gateway_url).ok()?;
+    if url.scheme() != "https" && url.scheme() != "wss" {
+        return None;
+    }
+    Some(format!("{}:{}", url.host_str()?, url.port_or_known_default()?))
+}
+
+/// SHA-256 fingerprint of a DER certificate as `AB:CD:...`
+fn fingerprint(der: &[u8]) -> String {
+    Sha256::digest(der)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+/// Whether a presented fingerprint is the one the user approved
+fn fingerprint_matches(actual: &str, expected: &str) -> bool {
+    actual.eq_ignore_ascii_case(expected.trim())
+}
+
+/// DER of the certificate pinned for `gateway_url` in `pins`
+fn pinned_in(pins: &HashMap<String, PinnedCert>, gateway_url: &str) -> Option<Vec<u8>> {
+    let pin = pins.get(&origin(gateway_url)?)?;
+    base64::engine::general_purpose::STANDARD.decode(&pin.der_base64).ok()
+}
+
+fn pinned_der(gateway_url: &str) -> Option<Vec<u8>> {
+    pinned_in(&load_pins(), gateway_url)
+}
+
+/// HTTP client builder for a Gateway, restricted to the pinned certificate if any
+/// and routed through the configured proxy
+pub fn client_builder(gateway_url: &str) -> reqwest::ClientBuilder {
+    let builder = crate::proxy::apply(reqwest::Client::builder());
+    let Some(der) = pinned_der(gateway_url) else {
+        return builder;
+    };
+    match reqwest::Certificate::from_der(&der) {
+        // Self-signed certs rarely match the LAN IP/hostname; the pin is what matters
+        Ok(cert) => builder
+            .tls_built_in_root_certs(false)
+            .add_root_certificate(cert)
+            .danger_accept_invalid_hostnames(true),
+        Err(e) => {
+            tracing::error!("[TLS] Pinned certificate unusable: {}", e);
+            builder
+        }
+    }
+}
+
+/// WebSocket TLS connector for a Gateway, or None to use the system defaults
+pub fn ws_connector(gateway_url: &str) -> Option<tokio_tungstenite::Connector> {
+    let der = pinned_der(gateway_url)?;
+    let cert = native_tls::Certificate::from_der(&der).ok()?;
+    let connector = native_tls::TlsConnector::builder()
+        .disable_built_in_roots(true)
+        .add_root_certificate(cert)
+        .danger_accept_invalid_hostnames(true)
+        .build()
+        .map_err(|e| tracing::error!("[TLS] WebSocket connector error: {}", e))
+        .ok()?;
+    Some(tokio_tungstenite::Connector::NativeTls(connector))
+}
+
+/// Fetch the certificate a Gateway presents, without validating it
+async fn fetch_certificate(gateway_url: &str) -> Result<Vec<u8>, String> {
+    origin(gateway_url).ok_or("Gateway does not use HTTPS")?;
+    let client = crate::proxy::apply(reqwest::Client::builder())
+        .danger_accept_invalid_certs(true)
+        .tls_info(true)
+        .timeout(std::time::Duration::from_secs(10))
+        .build()
+        .map_err(|e| format!("HTTP client error: {}", e))?;
+    let resp = client
+        .get(format!("{}/health", gateway_url.trim_end_matches('/')))
+        .send()
+        .await
+        .map_err(|e| format!("Connection failed: {}", e))?;
+    resp.extensions()
+        .get::<reqwest::tls::TlsInfo>()
+        .and_then(|info| info.peer_certificate())
+        .map(|der| der.to_vec())
+        .ok_or_else(|| "Gateway presented no certificate".into())
+}
+
+/// Show the Gateway's certificate fingerprint so the user can verify it
+pub async fn inspect(gateway_url: &str) -> Result<CertInfo, String> {
+    let der = fetch_certificate(gateway_url).await?;
+    let fp = fingerprint(&der);
+    let origin = origin(gateway_url).unwrap_or_default();
+    let pinned = load_pins().get(&origin).is_some_and(|p| p.fingerprint == fp);
+    Ok(CertInfo { origin, fingerprint: fp, pinned })
+}
+
+/// Trust and pin the Gateway's certificate. `expected_fingerprint` is the
+/// one the user approved — the pin is refused if the Gateway now presents
+/// a different certificate.
+pub async fn trust(gateway_url: &str, expected_fingerprint: &str) -> Result<CertInfo, String> {
+    let der = fetch_certificate(gateway_url).await?;
+    let fp = fingerprint(&der);
+    if !fingerprint_matches(&fp, expected_fingerprint) {
+        return Err("Certificate changed since it was inspected — refusing to trust it".into());
+    }
+
+    let origin = origin(gateway_url).unwrap_or_default();
+    let mut pins = load_pins();
+    pins.insert(
+        origin.clone(),
+        PinnedCert {
+            fingerprint: fp.clone(),
+            der_base64: base64::engine::general_purpose::STANDARD.encode(&der),
+            trusted_at: chrono::Utc::now().to_rfc3339(),
+        },
+    );
+    save_pins(&pins)?;
+    crate::http::invalidate();
+    tracing::info!("[TLS] Pinned certificate for {} ({})", origin, fp);
+    Ok(CertInfo { origin, fingerprint: fp, pinned: true })
+}
+
+/// Remove the pinned certificate for a Gateway
+pub fn untrust(gateway_url: &str) -> Result<(), String> {
+    let origin = origin(gateway_url).ok_or("Gateway does not use HTTPS")?;
+    let mut pins = load_pins();
+    if pins.remove(&origin).is_some() {
+        save_pins(&pins)?;
+        crate::http::invalidate();
+        tracing::info!("[TLS] Removed pinned certificate for {}", origin);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_origin_fingerprint_and_pins() {
+        assert_eq!(origin("https://gw.local").as_deref(), Some("gw.local:443"));
+        assert_eq!(origin("https://192.168.1.10:3000/api").as_deref(), Some("192.168.1.10:3000"));
+        assert_eq!(origin("wss://gw.local:8443").as_deref(), Some("gw.local:8443"));
+        assert_eq!(origin("http://gw.local:3000"), None);
+        assert_eq!(origin("not a url"), None);
+
+        let fp = fingerprint(b"abc");
+        assert_eq!(
+            fp,
+            "BA:78:16:BF:8F:01:CF:EA:41:41:40:DE:5D:AE:22:23:B0:03:61:A3:96:17:7A:9C:B4:10:FF:61:F2:00:15:AD"
+        );
+        assert!(fingerprint_matches(&fp, &format!(" {} ", fp.to_lowercase())));
+        assert!(!fingerprint_matches(&fp, &fingerprint(b"abd")));
+
+        let mut pins = HashMap::new();
+        pins.insert(
+            "gw.local:3000".to_string(),
+            PinnedCert {
+                fingerprint: fp,
+                der_base64: base64::engine::general_purpose::STANDARD.encode(b"abc"),
+                trusted_at: String::new(),
+            },
+        );
+        assert_eq!(pinned_in(&pins, "https://gw.local:3000/").as_deref(), Some(&b"abc"[..]));
+        // Pins are per origin: another port or plain http gets no pin
+        assert_eq!(pinned_in(&pins, "https://gw.local"), None);
+        assert_eq!(pinned_in(&pins, "http://gw.local:3000"), None);
+    }
+}
@@ -0,0 +1,223 @@
+//! # Transcript Export
+//!
+//! `export_transcript(sessionId, format, path)` writes a conversation or a
+//! meeting to a file:
+//!
+//! - a `meeting-…` id exports that meeting's diarized transcript
+//! - any other id exports the chat session from the local history,
+//!   falling back to the voice turns remembered for it
+//!
+//! Formats are Markdown (`md`), plain text (`txt`) and SubRip subtitles
+//! (`srt`, timed from the start of the conversation). The target path goes
+//! through the same safety check as the `write_file` action, so protected
+//! system locations are refused.
+
+use chrono::{DateTime, Utc};
+use std::path::Path;
+
+/// Most messages exported from one chat session
+const MAX_MESSAGES: u32 = 100_000;
+/// How long a subtitle stays up when the next one does not follow sooner
+const MAX_CUE_MS: u64 = 6000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Text,
+    Srt,
+}
+
+impl ExportFormat {
+    pub fn parse(name: &str) -> Result<Self, String> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Ok(Self::Markdown),
+            "txt" | "text" => Ok(Self::Text),
+            "srt" => Ok(Self::Srt),
+            other => Err(format!("Unknown transcript format '{}' (md, txt or srt)", other)),
+        }
+    }
+}
+
+/// One utterance, timed from the start of the transcript
+#[derive(Debug, Clone, PartialEq)]
+struct Entry {
+    start_ms: u64,
+    end_ms: Option<u64>,
+    speaker: Option<String>,
+    text: String,
+}
+
+struct Transcript {
+    title: String,
+    started_at: String,
+    entries: Vec<Entry>,
+}
+
+fn parse_time(rfc3339: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(rfc3339).ok().map(|t| t.with_timezone(&Utc))
+}
+
+/// Timed entries from `(timestamp, speaker, text)` in order
+fn timed(items: Vec<(String, String, String)>) -> Vec<Entry> {
+    let origin = items.iter().find_map(|(at, _, _)| parse_time(at));
+    items
+        .into_iter()
+        .filter(|(_, _, text)| !text.trim().is_empty())
+        .map(|(at, speaker, text)| Entry {
+            start_ms: match (origin, parse_time(&at)) {
+                (Some(origin), Some(at)) => (at - origin).num_milliseconds().max(0) as u64,
+                _ => 0,
+            },
+            end_ms: None,
+            speaker: Some(speaker),
+            text: text.trim().to_string(),
+        })
+        .collect()
+}
+
+fn load(session_id: &str) -> Result<Transcript, String> {
+    if session_id.starts_with("meeting-") {
+        let meeting = crate::meeting::get(session_id)?;
+        let mut entries: Vec<Entry> = meeting
+            .chunks
+            .iter()
+            .flat_map(|c| &c.lines)
+            .map(|l| Entry { start_ms: l.start_ms, end_ms: Some(l.end_ms), speaker: l.speaker.clone(), text: l.text.clone() })
+            .collect();
+        entries.sort_by_key(|e| e.start_ms);
+        return Ok(Transcript { title: meeting.title, started_at: meeting.started_at, entries });
+    }
+
+    let messages = crate::history::messages(session_id, None, MAX_MESSAGES)?;
+    let items: Vec<(String, String, String)> = if !messages.is_empty() {
+        messages.into_iter().map(|m| (m.created_at, m.role, m.content)).collect()
+    } else {
+        crate::memory::session_turns(session_id)?
+            .into_iter()
+            .flat_map(|t| [(t.started_at, "user".to_string(), t.transcript), (t.finished_at, "assistant".to_string(), t.reply)])
+            .collect()
+    };
+    if items.is_empty() {
+        return Err(format!("No conversation '{}' in the local history", session_id));
+    }
+    let title = crate::history::sessions(MAX_MESSAGES, 0)?
+        .into_iter()
+        .find(|s| s.id == session_id)
+        .map(|s| s.title)
+        .filter(|t| !t.is_empty())
+        .unwrap_or_else(|| format!("Conversation {}", session_id));
+    let started_at = items[0].0.clone();
+    Ok(Transcript { title, started_at, entries: timed(items) })
+}
+
+// ─── Rendering ──────────────────────────────────────
+
+fn clock(ms: u64) -> String {
+    let secs = ms / 1000;
+    format!("{:02}:{:02}:{:02}", secs / 3600, secs % 3600 / 60, secs % 60)
+}
+
+fn srt_time(ms: u64) -> String {
+    format!("{},{:03}", clock(ms), ms % 1000)
+}
+
+fn speaker_name(speaker: &Option<String>) -> Option<String> {
+    speaker.as_deref().map(|s| match s {
+        "user" => "You".to_string(),
+        "assistant" => "ForgeAI".to_string(),
+        other => other.to_string(),
+    })
+}
+
+fn render(transcript: &Transcript, format: ExportFormat) -> String {
+    let mut out = String::new();
+    match format {
+        ExportFormat::Markdown => {
+            out.push_str(&format!("# {}\n\n_{}_\n\n", transcript.title, transcript.started_at));
+            for entry in &transcript.entries {
+                match speaker_name(&entry.speaker) {
+                    Some(name) => out.push_str(&format!("**[{}] {}:** {}\n\n", clock(entry.start_ms), name, entry.text)),
+                    None => out.push_str(&format!("**[{}]** {}\n\n", clock(entry.start_ms), entry.text)),
+                }
+            }
+        }
+        ExportFormat::Text => {
+            out.push_str(&format!("{}\n{}\n\n", transcript.title, transcript.started_at));
+            for entry in &transcript.entries {
+                match speaker_name(&entry.speaker) {
+                    Some(name) => out.push_str(&format!("[{}] {}: {}\n", clock(entry.start_ms), name, entry.text)),
+                    None => out.push_str(&format!("[{}] {}\n", clock(entry.start_ms), entry.text)),
+                }
+            }
+        }
+        ExportFormat::Srt => {
+            for (i, entry) in transcript.entries.iter().enumerate() {
+                let next = transcript.entries.get(i + 1).map(|n| n.start_ms);
+                let end = entry
+                    .end_ms
+                    .unwrap_or(entry.start_ms + MAX_CUE_MS)
+                    .min(next.filter(|n| *n > entry.start_ms).unwrap_or(u64::MAX))
+                    .max(entry.start_ms + 500);
+                let text = match speaker_name(&entry.speaker) {
+                    Some(name) => format!("{}: {}", name, entry.text),
+                    None => entry.text.clone(),
+                };
+                out.push_str(&format!("{}\n{} --> {}\n{}\n\n", i + 1, srt_time(entry.start_ms), srt_time(end), text));
+            }
+        }
+    }
+    out
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Write the transcript of `session_id` to `path` as `format`; returns the
+/// number of bytes written
+pub fn export(session_id: &str, format: &str, path: &str) -> Result<usize, String> {
+    let format = ExportFormat::parse(format)?;
+    let verdict = crate::safety::check_file_operation("write", path);
+    if !verdict.allowed {
+        return Err(verdict.reason);
+    }
+    let document = render(&load(session_id)?, format);
+    if let Some(parent) = Path::new(path).parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    std::fs::write(path, &document).map_err(|e| format!("Failed to write: {}", e))?;
+    tracing::info!("[Export] {} written to {} ({} bytes)", session_id, path, document.len());
+    Ok(document.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_formats() {
+        let item = |at: &str, who: &str, text: &str| (at.to_string(), who.to_string(), text.to_string());
+        let transcript = Transcript {
+            title: "Trip".into(),
+            started_at: "2026-05-01T10:00:00Z".into(),
+            entries: timed(vec![
+                item("2026-05-01T10:00:00Z", "user", "Book a train to Porto"),
+                item("2026-05-01T10:00:02.500Z", "assistant", "Which day?"),
+                item("2026-05-01T10:00:03Z", "user", "   "),
+                item("2026-05-01T10:01:30Z", "user", "Friday"),
+            ]),
+        };
+        assert_eq!(transcript.entries.len(), 3);
+        assert_eq!(
+            render(&transcript, ExportFormat::Text),
+            "Trip\n2026-05-01T10:00:00Z\n\n[00:00:00] You: Book a train to Porto\n[00:00:02] ForgeAI: Which day?\n[00:01:30] You: Friday\n"
+        );
+        assert!(render(&transcript, ExportFormat::Markdown).contains("**[00:00:02] ForgeAI:** Which day?\n"));
+        assert_eq!(
+            render(&transcript, ExportFormat::Srt),
+            "1\n00:00:00,000 --> 00:00:02,500\nYou: Book a train to Porto\n\n\
+             2\n00:00:02,500 --> 00:00:08,500\nForgeAI: Which day?\n\n\
+             3\n00:01:30,000 --> 00:01:36,000\nYou: Friday\n\n"
+        );
+        assert_eq!(ExportFormat::parse("SRT"), Ok(ExportFormat::Srt));
+        assert!(ExportFormat::parse("docx").is_err());
+    }
+}
@@ -0,0 +1,145 @@
+//! # Transcript Filter
+//!
+//! Optional masking of transcripts before they are shown, remembered or
+//! forwarded to the Gateway. Each category is switched on separately in the
+//! settings (`transcriptFilter`):
+//!
+//! | Category      | Detects                                    | Becomes         |
+//! |---------------|--------------------------------------------|-----------------|
+//! | `profanity`   | common swear words (English, Portuguese)   | `s***`          |
+//! | `emails`      | email addresses                            | `[email]`       |
+//! | `cardNumbers` | 13–19 digit numbers passing the Luhn check | `[card number]` |
+//!
+//! The recorded audio still reaches the Gateway when it transcribes the turn
+//! itself; only the text is filtered.
+
+use regex::{Captures, Regex};
+use serde::{Deserialize, Serialize};
+use std::sync::{Mutex, OnceLock};
+
+static CONFIG: Mutex<Option<FilterConfig>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FilterConfig {
+    /// Mask swear words, keeping their first letter
+    pub profanity: bool,
+    /// Replace email addresses
+    pub emails: bool,
+    /// Replace payment card numbers
+    pub card_numbers: bool,
+}
+
+impl FilterConfig {
+    fn any(&self) -> bool {
+        self.profanity || self.emails || self.card_numbers
+    }
+}
+
+/// Use `config` from now on
+pub fn set_config(config: &FilterConfig) {
+    if let Ok(mut current) = CONFIG.lock() {
+        *current = Some(config.clone());
+    }
+}
+
+fn config() -> FilterConfig {
+    CONFIG.lock().ok().and_then(|c| c.clone()).unwrap_or_default()
+}
+
+// ─── Detection ──────────────────────────────────────
+
+/// Swear words, also matched with common English endings ("-ing", "-ed", …)
+const PROFANITY: &[&str] = &[
+    "fuck", "motherfucker", "shit", "bullshit", "bitch", "bastard", "asshole", "dick", "cunt",
+    "porra", "caralho", "merda", "puta", "foda", "foder", "fodido", "cacete", "buceta", "arrombado",
+];
+
+fn profanity_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(&format!(r"(?i)\b(?:{})(?:s|es|ed|ing|er|ers|y)?\b", PROFANITY.join("|"))).expect("static pattern is valid")
+    })
+}
+
+fn email_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}").expect("static pattern is valid")
+    })
+}
+
+fn card_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| Regex::new(r"\b\d(?:[ -]?\d){12,18}\b").expect("static pattern is valid"))
+}
+
+/// Whether `digits` passes the Luhn checksum used by payment cards
+fn luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| if i % 2 == 1 { if d * 2 > 9 { d * 2 - 9 } else { d * 2 } } else { d })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+fn filter_with(config: &FilterConfig, text: &str) -> String {
+    let mut text = text.to_string();
+    if config.card_numbers {
+        text = card_pattern()
+            .replace_all(&text, |c: &Captures| {
+                let digits: Vec<u32> = c[0].chars().filter_map(|ch| ch.to_digit(10)).collect();
+                if luhn(&digits) { "[card number]".to_string() } else { c[0].to_string() }
+            })
+            .into_owned();
+    }
+    if config.emails {
+        text = email_pattern().replace_all(&text, "[email]").into_owned();
+    }
+    if config.profanity {
+        text = profanity_pattern()
+            .replace_all(&text, |c: &Captures| {
+                let mut chars = c[0].chars();
+                let first = chars.next().map(String::from).unwrap_or_default();
+                first + "*".repeat(chars.count()).as_str()
+            })
+            .into_owned();
+    }
+    text
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// `transcript` with the enabled categories masked
+pub fn apply(transcript: &str) -> String {
+    let config = config();
+    if !config.any() {
+        return transcript.to_string();
+    }
+    filter_with(&config, transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_masking() {
+        let all = FilterConfig { profanity: true, emails: true, card_numbers: true };
+        assert_eq!(
+            filter_with(&all, "Oh shit, mail ana.s@example.com.br my card 4111 1111 1111 1111"),
+            "Oh s***, mail [email] my card [card number]"
+        );
+        // Numbers failing the checksum (phone numbers, order ids) are left alone
+        assert_eq!(filter_with(&all, "order 1234567890123"), "order 1234567890123");
+        assert_eq!(filter_with(&all, "Que porra é essa"), "Que p**** é essa");
+        assert_eq!(filter_with(&all, "FUCKING hell"), "F****** hell");
+        // Whole words only
+        assert_eq!(filter_with(&all, "Scunthorpe and Dickens"), "Scunthorpe and Dickens");
+
+        let emails_only = FilterConfig { emails: true, ..Default::default() };
+        assert_eq!(filter_with(&emails_only, "shit, a@b.io"), "shit, [email]");
+    }
+}
@@ -0,0 +1,312 @@
+//! # File Transfer
+//!
+//! Chunked, resumable file transfer between the companion and the Gateway,
+//! so "send this file to my server" and "pull that report down" work for
+//! files of any size over flaky links.
+//!
+//! **Upload** (companion → Gateway)
+//! 1. `POST /api/companion/files/uploads` `{name, size, sha256}` →
+//!    `{uploadId, received}`. The Gateway keys unfinished uploads by
+//!    checksum, so `received` > 0 resumes an interrupted transfer.
+//! 2. `PUT /api/companion/files/uploads/{id}?offset=N` with each chunk,
+//!    compressed with `Content-Encoding` when worthwhile (see `compression`);
+//!    offsets always count uncompressed bytes.
+//! 3. `POST /api/companion/files/uploads/{id}/complete` → `{fileId, path}`.
+//!
+//! **Download** (Gateway → companion)
+//! 1. `GET /api/companion/files/{fileId}/meta` → `{name, size, sha256}`.
+//! 2. `GET /api/companion/files/{fileId}` with `Range` for each chunk,
+//!    appended to `<dest>.part` — an existing `.part` file is resumed.
+//! 3. The checksum is verified before the file is moved into place.
+//!
+//! The receiving side confirms: the Gateway asks its user before accepting
+//! an upload, and a Gateway-pushed download always needs approval here
+//! (see `local_actions`). Progress is emitted as `file-transfer-progress`,
+//! and also as `action-progress` when the transfer runs as an action.
+
+use crate::connection::{CompanionCredentials, GatewayConnection};
+use crate::progress::Tracker;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Bytes per request
+const CHUNK_SIZE: u64 = 1024 * 1024;
+const CHUNK_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Upload,
+    Download,
+}
+
+/// Payload of the `file-transfer-progress` event
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferProgress {
+    pub direction: Direction,
+    pub name: String,
+    pub transferred: u64,
+    pub total: u64,
+}
+
+/// Outcome of a finished transfer
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferResult {
+    /// Gateway file id (uploads) or the requested id (downloads)
+    pub file_id: String,
+    /// Path on the Gateway (uploads) or the local destination (downloads)
+    pub path: String,
+    pub size: u64,
+    /// Bytes skipped because an earlier attempt already transferred them
+    pub resumed_from: u64,
+}
+
+fn progress(direction: Direction, name: &str, transferred: u64, total: u64) {
+    crate::events::emit(
+        "file-transfer-progress",
+        TransferProgress { direction, name: name.to_string(), transferred, total },
+    );
+}
+
+fn sha256_file(path: &Path) -> Result<String, String> {
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Open error: {}", e))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).map_err(|e| format!("Read error: {}", e))?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+fn check(resp: &reqwest::Response, what: &str) -> Result<(), String> {
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        Err(format!("{} failed: Gateway HTTP {}", what, resp.status()))
+    }
+}
+
+/// Upload a local file to the Gateway
+pub async fn upload(
+    creds: &CompanionCredentials,
+    path: &str,
+    tracker: Option<&Tracker>,
+) -> Result<TransferResult, String> {
+    let local = Path::new(path);
+    let size = std::fs::metadata(local)
+        .map_err(|e| format!("Cannot read {}: {}", path, e))?
+        .len();
+    let name = local
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or("Path has no file name")?;
+    if let Some(tracker) = tracker {
+        tracker.update(None, "Computing checksum");
+    }
+    let sha256 = sha256_file(local)?;
+
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let build = || gw
+        .post("/api/companion/files/uploads")
+        .json(&serde_json::json!({ "name": name, "size": size, "sha256": sha256 }))
+        .timeout(Duration::from_secs(15));
+    let resp = GatewayConnection::send_authenticated(creds, build).await?;
+    check(&resp, "Upload")?;
+    let session: serde_json::Value = resp.json().await.map_err(|e| format!("Invalid response: {}", e))?;
+    let upload_id = session["uploadId"].as_str().ok_or("Upload response has no uploadId")?.to_string();
+    let resumed_from = session["received"].as_u64().unwrap_or(0).min(size);
+    if resumed_from > 0 {
+        tracing::info!("[Transfer] Resuming upload of {} at {} / {} bytes", name, resumed_from, size);
+    }
+
+    let mut file = std::fs::File::open(local).map_err(|e| format!("Open error: {}", e))?;
+    let mut offset = resumed_from;
+    while offset < size {
+        let len = CHUNK_SIZE.min(size - offset);
+        let mut chunk = vec![0u8; len as usize];
+        file.seek(SeekFrom::Start(offset))
+            .and_then(|_| file.read_exact(&mut chunk))
+            .map_err(|e| format!("Read error: {}", e))?;
+
+        let chunk_path = format!("/api/companion/files/uploads/{}?offset={}", upload_id, offset);
+        let mut body = crate::compression::bytes_upload(chunk.clone());
+        let resp = loop {
+            let build = || crate::compression::with_body(
+                gw.put(&chunk_path).timeout(CHUNK_TIMEOUT),
+                "application/octet-stream",
+                &body,
+            );
+            let resp = GatewayConnection::send_authenticated(creds, build).await?;
+            if !crate::compression::upload_rejected(resp.status(), &body) {
+                break resp;
+            }
+            // Compression is now off, so this resends the chunk as-is
+            body = crate::compression::bytes_upload(chunk.clone());
+        };
+        check(&resp, "Chunk upload")?;
+
+        offset += len;
+        progress(Direction::Upload, &name, offset, size);
+        if let Some(tracker) = tracker {
+            tracker.bytes("Uploading", offset, size);
+        }
+    }
+
+    let complete_path = format!("/api/companion/files/uploads/{}/complete", upload_id);
+    let build = || gw.post(&complete_path).timeout(Duration::from_secs(30));
+    let resp = GatewayConnection::send_authenticated(creds, build).await?;
+    check(&resp, "Upload")?;
+    let done: serde_json::Value = resp.json().await.map_err(|e| format!("Invalid response: {}", e))?;
+
+    tracing::info!("[Transfer] Uploaded {} ({} bytes)", name, size);
+    Ok(TransferResult {
+        file_id: done["fileId"].as_str().unwrap_or(&upload_id).to_string(),
+        path: done["path"].as_str().unwrap_or(&name).to_string(),
+        size,
+        resumed_from,
+    })
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(".part");
+    dest.with_file_name(name)
+}
+
+/// Open `<dest>.part` for appending; returns it and the offset to resume at
+fn open_part(part: &Path, size: u64) -> Result<(std::fs::File, u64), String> {
+    let file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(part)
+        .map_err(|e| format!("Cannot write {}: {}", part.display(), e))?;
+    let offset = file.metadata().map(|m| m.len()).unwrap_or(0);
+    if offset > size {
+        // Stale partial file from a different version — start over
+        file.set_len(0).map_err(|e| format!("Write error: {}", e))?;
+        return Ok((file, 0));
+    }
+    Ok((file, offset))
+}
+
+/// Verify the checksum of a finished `.part` file and move it into place.
+/// A mismatching file is deleted so the next attempt starts clean.
+fn finish_part(part: &Path, dest: &Path, expected_sha256: Option<&str>) -> Result<(), String> {
+    if let Some(expected) = expected_sha256 {
+        let actual = sha256_file(part)?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            let _ = std::fs::remove_file(part);
+            return Err("Checksum mismatch — the download was discarded".into());
+        }
+    }
+    std::fs::rename(part, dest).map_err(|e| format!("Cannot move file into place: {}", e))
+}
+
+/// Download a Gateway file to `dest`. Callers are responsible for the
+/// safety check and confirmation of writing `dest`.
+pub async fn download(
+    creds: &CompanionCredentials,
+    file_id: &str,
+    dest: &str,
+    tracker: Option<&Tracker>,
+) -> Result<TransferResult, String> {
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let meta_path = format!("/api/companion/files/{}/meta", file_id);
+    let build = || gw.get(&meta_path).timeout(Duration::from_secs(15));
+    let resp = GatewayConnection::send_authenticated(creds, build).await?;
+    check(&resp, "Download")?;
+    let meta: serde_json::Value = resp.json().await.map_err(|e| format!("Invalid response: {}", e))?;
+    let size = meta["size"].as_u64().ok_or("File metadata has no size")?;
+    let name = meta["name"].as_str().unwrap_or(file_id).to_string();
+
+    let dest = Path::new(dest);
+    if let Some(parent) = dest.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    let part = part_path(dest);
+    let (mut file, mut offset) = open_part(&part, size)?;
+    let resumed_from = offset;
+    if resumed_from > 0 {
+        tracing::info!("[Transfer] Resuming download of {} at {} / {} bytes", name, resumed_from, size);
+    }
+
+    let file_path = format!("/api/companion/files/{}", file_id);
+    while offset < size {
+        let end = (offset + CHUNK_SIZE).min(size) - 1;
+        let build = || gw
+            .get(&file_path)
+            .header(reqwest::header::RANGE, format!("bytes={}-{}", offset, end))
+            .timeout(CHUNK_TIMEOUT);
+        let resp = GatewayConnection::send_authenticated(creds, build).await?;
+        check(&resp, "Chunk download")?;
+        if resp.status() != reqwest::StatusCode::PARTIAL_CONTENT && offset > 0 {
+            return Err("Gateway does not support resumable downloads (no Range support)".into());
+        }
+        let bytes = resp.bytes().await.map_err(|e| format!("Read error: {}", e))?;
+        if bytes.is_empty() {
+            return Err("Gateway returned an empty chunk".into());
+        }
+        file.write_all(&bytes).map_err(|e| format!("Write error: {}", e))?;
+
+        offset += bytes.len() as u64;
+        progress(Direction::Download, &name, offset.min(size), size);
+        if let Some(tracker) = tracker {
+            tracker.bytes("Downloading", offset, size);
+        }
+    }
+    drop(file);
+
+    if let Some(tracker) = tracker {
+        tracker.update(Some(100), "Verifying checksum");
+    }
+    finish_part(&part, dest, meta["sha256"].as_str())?;
+
+    tracing::info!("[Transfer] Downloaded {} to {}", name, dest.display());
+    Ok(TransferResult {
+        file_id: file_id.to_string(),
+        path: dest.display().to_string(),
+        size,
+        resumed_from,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_files() {
+        assert_eq!(part_path(Path::new("/tmp/dl/report.pdf")), Path::new("/tmp/dl/report.pdf.part"));
+        assert_eq!(part_path(Path::new("notes")), Path::new("notes.part"));
+
+        let dir = std::env::temp_dir().join(format!("forgeai-transfer-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let dest = dir.join("file.txt");
+        let part = part_path(&dest);
+
+        // Resume where the previous attempt stopped
+        std::fs::write(&part, b"hello").unwrap();
+        let (mut file, offset) = open_part(&part, 11).unwrap();
+        assert_eq!(offset, 5);
+        file.write_all(b" world").unwrap();
+        drop(file);
+
+        // A part larger than the file is stale and restarts from zero
+        std::fs::write(dir.join("big.part"), b"0123456789").unwrap();
+        let (_, offset) = open_part(&dir.join("big.part"), 4).unwrap();
+        assert_eq!(offset, 0);
+        assert_eq!(std::fs::metadata(dir.join("big.part")).unwrap().len(), 0);
+
+        // Wrong checksum discards the part, the right one moves it into place
+        assert!(finish_part(&part, &dest, Some("00")).is_err());
+        assert!(!part.exists() && !dest.exists());
+        std::fs::write(&part, b"hello world").unwrap();
+        let sha = sha256_file(&part).unwrap();
+        finish_part(&part, &dest, Some(&sha.to_uppercase())).unwrap();
+        assert_eq!(std::fs::read(&dest).unwrap(), b"hello world");
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
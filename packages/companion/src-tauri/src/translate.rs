@@ -0,0 +1,131 @@
+//! # Translation Mode
+//!
+//! `voice_translate(targetLang)` turns the companion into an interpreter:
+//! the recording is transcribed (the Gateway detects the spoken language),
+//! translated by the Gateway (`POST /api/voice/translate`) and spoken back
+//! with a voice for the target language. Speech already in the target
+//! language is repeated as is.
+//!
+//! Emits the same `voice-state` events as a voice turn.
+
+use crate::connection::{CompanionCredentials, GatewayConnection};
+use crate::voice::VoiceEngine;
+use serde::Serialize;
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Translation {
+    /// What was said
+    pub transcript: String,
+    /// Detected language of the speech, when known
+    pub source_lang: Option<String>,
+    pub target_lang: String,
+    pub translation: String,
+    /// What transcribed the speech (see `stt_fallback`)
+    pub stt_backend: String,
+}
+
+/// Whether `tag` looks like a BCP 47 language tag ("pt", "pt-BR", "zh-Hant")
+fn valid_language(tag: &str) -> bool {
+    let mut parts = tag.split('-');
+    let primary = parts.next().unwrap_or("");
+    (2..=3).contains(&primary.len())
+        && primary.chars().all(|c| c.is_ascii_alphabetic())
+        && parts.all(|p| (2..=8).contains(&p.len()) && p.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Whether `a` and `b` name the same language ("pt", "pt-BR" and the
+/// locale-style "pt_BR" do)
+pub fn same_language(a: &str, b: &str) -> bool {
+    let primary = |tag: &str| tag.split(['-', '_']).next().unwrap_or("").to_ascii_lowercase();
+    primary(a) == primary(b)
+}
+
+/// Translate `text` into `target` through the Gateway; returns the
+/// translation and the source language the Gateway settled on
+pub async fn translate(
+    creds: &CompanionCredentials,
+    text: &str,
+    source: Option<&str>,
+    target: &str,
+) -> Result<(String, Option<String>), String> {
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let body = serde_json::json!({ "text": text, "sourceLang": source, "targetLang": target });
+    let build = || gw.post("/api/voice/translate").json(&body).timeout(std::time::Duration::from_secs(30));
+    let resp = GatewayConnection::send_authenticated(creds, build).await?;
+    if !resp.status().is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(format!("Translation failed: {}", text));
+    }
+    let data: serde_json::Value = resp.json().await.map_err(|e| format!("Parse error: {}", e))?;
+    let translation = data["text"].as_str().ok_or("No translation in response")?.to_string();
+    let detected = data["sourceLang"].as_str().map(str::to_string).or(source.map(str::to_string));
+    Ok((translation, detected))
+}
+
+fn emit_voice_state(state: &str) {
+    crate::events::emit("voice-state", serde_json::json!({ "state": state }));
+}
+
+async fn run(engine: &Mutex<VoiceEngine>, creds: &CompanionCredentials, target: &str) -> Result<Translation, String> {
+    let audio = {
+        let engine = engine.lock().map_err(|e| e.to_string())?;
+        engine.record_with_events()?
+    };
+    emit_voice_state("processing");
+    let transcript = VoiceEngine::new().transcribe_detailed(creds, &audio).await?;
+    if transcript.text.trim().is_empty() {
+        return Err("Nothing was heard".into());
+    }
+    let already_there = transcript.language.as_deref().is_some_and(|l| same_language(l, target));
+    let (translation, source_lang) = if already_there {
+        (transcript.text.clone(), transcript.language.clone())
+    } else {
+        translate(creds, &transcript.text, transcript.language.as_deref(), target).await?
+    };
+    tracing::info!("[Translate] {} → {}: {} chars", source_lang.as_deref().unwrap_or("auto"), target, translation.len());
+
+    emit_voice_state("speaking");
+    if let Err(e) = VoiceEngine::new().speak_in(creds, &translation, Some(target)).await {
+        tracing::error!("[Translate] Playback failed: {}", e);
+    }
+    Ok(Translation {
+        transcript: transcript.text,
+        source_lang,
+        target_lang: target.to_string(),
+        translation,
+        stt_backend: transcript.backend,
+    })
+}
+
+/// Record speech, translate it into `target_lang` and speak the translation
+pub async fn voice_translate(engine: &Mutex<VoiceEngine>, target_lang: &str) -> Result<Translation, String> {
+    let target = target_lang.trim();
+    if !valid_language(target) {
+        return Err(format!("Invalid language '{}'", target_lang));
+    }
+    let creds = GatewayConnection::load_credentials().ok_or("Not paired with a Gateway")?;
+    emit_voice_state("listening");
+    let result = run(engine, &creds, target).await;
+    emit_voice_state("idle");
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_tags() {
+        assert!(valid_language("pt"));
+        assert!(valid_language("pt-BR"));
+        assert!(valid_language("zh-Hant"));
+        assert!(!valid_language(""));
+        assert!(!valid_language("portuguese"));
+        assert!(!valid_language("en-"));
+        assert!(same_language("pt-BR", "PT"));
+        assert!(same_language("pt_BR", "pt-PT"));
+        assert!(!same_language("pt", "es"));
+    }
+}
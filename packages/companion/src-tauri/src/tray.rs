@@ -0,0 +1,180 @@
+//! # System Tray
+//!
+//! The tray icon is the companion's main surface while the window is
+//! hidden. Its tooltip and a status line at the top of the menu show the
+//! Gateway link and whether the companion is listening; the menu can mute
+//! the microphone, toggle the wake word, open the window and disconnect.
+//!
+//! Menu actions go through the same code paths as the UI and emit the same
+//! events (`wake-word-toggled`, `mic-muted`, `disconnected`), so the window
+//! stays in sync with changes made from the tray.
+
+use std::sync::{Mutex, OnceLock};
+use tauri::menu::{CheckMenuItem, Menu, MenuItem, PredefinedMenuItem};
+use tauri::tray::{TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{App, AppHandle, Listener, Manager};
+
+static TRAY: OnceLock<TrayHandles> = OnceLock::new();
+static STATUS: Mutex<TrayStatus> = Mutex::new(TrayStatus {
+    link: String::new(),
+    voice: String::new(),
+    wake_word: false,
+    muted: false,
+});
+
+struct TrayHandles {
+    icon: TrayIcon,
+    status: MenuItem<tauri::Wry>,
+    mute: CheckMenuItem<tauri::Wry>,
+    wake_word: CheckMenuItem<tauri::Wry>,
+}
+
+struct TrayStatus {
+    /// `online` / `degraded` / `offline` from the heartbeat
+    link: String,
+    /// `listening` / `processing` / `speaking` / `idle` from the voice pipeline
+    voice: String,
+    wake_word: bool,
+    muted: bool,
+}
+
+impl TrayStatus {
+    fn summary(&self) -> String {
+        let link = if self.link.is_empty() { "offline" } else { &self.link };
+        let activity = if self.muted {
+            "mic muted"
+        } else if !self.voice.is_empty() && self.voice != "idle" {
+            &self.voice
+        } else if self.wake_word {
+            "waiting for wake word"
+        } else {
+            "idle"
+        };
+        format!("Gateway {} · {}", link, activity)
+    }
+}
+
+fn show_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+fn toggle_window(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window("main") {
+        if window.is_visible().unwrap_or(false) {
+            let _ = window.hide();
+        } else {
+            show_window(app);
+        }
+    }
+}
+
+/// Update one field of the status and refresh tooltip, status line and checkmarks
+fn update(change: impl FnOnce(&mut TrayStatus)) {
+    let Ok(mut status) = STATUS.lock() else {
+        return;
+    };
+    change(&mut status);
+    if let Some(tray) = TRAY.get() {
+        let summary = status.summary();
+        let _ = tray.icon.set_tooltip(Some(format!("ForgeAI Companion — {}", summary)));
+        let _ = tray.status.set_text(summary);
+        let _ = tray.mute.set_checked(status.muted);
+        let _ = tray.wake_word.set_checked(status.wake_word);
+    }
+}
+
+fn payload(event: &tauri::Event) -> serde_json::Value {
+    serde_json::from_str(event.payload()).unwrap_or_default()
+}
+
+fn on_menu(app: &AppHandle, id: &str) {
+    match id {
+        "toggle" => toggle_window(app),
+        "open" => show_window(app),
+        "mute" => {
+            let muted = !crate::voice::mic_muted();
+            crate::commands::apply_mic_mute(app, muted);
+        }
+        "wake_word" => {
+            if let Err(e) = crate::commands::toggle_wake_word(app) {
+                tracing::warn!("[Tray] Wake word could not start: {}", e);
+                // Undo the checkmark the click already toggled
+                update(|_| {});
+            }
+        }
+        "disconnect" => match crate::commands::disconnect() {
+            Ok(_) => tracing::info!("[Tray] Disconnected from Gateway"),
+            Err(e) => tracing::error!("[Tray] Disconnect failed: {}", e),
+        },
+        "quit" => app.exit(0),
+        _ => {}
+    }
+}
+
+/// Build the tray icon and keep it in sync with the app state
+pub fn build(app: &App) -> tauri::Result<()> {
+    let status = MenuItem::with_id(app, "status", "Gateway offline · idle", false, None::<&str>)?;
+    let open = MenuItem::with_id(app, "open", "Abrir janela", true, None::<&str>)?;
+    let toggle = MenuItem::with_id(app, "toggle", "Mostrar/ocultar janela", true, None::<&str>)?;
+    let mute = CheckMenuItem::with_id(app, "mute", "Silenciar microfone", true, crate::voice::mic_muted(), None::<&str>)?;
+    let wake_word = CheckMenuItem::with_id(app, "wake_word", "Palavra de ativação", true, false, None::<&str>)?;
+    let disconnect = MenuItem::with_id(app, "disconnect", "Desconectar", true, None::<&str>)?;
+    let quit = MenuItem::with_id(app, "quit", "Sair", true, None::<&str>)?;
+    let separator = || PredefinedMenuItem::separator(app);
+
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &separator()?,
+            &open,
+            &toggle,
+            &separator()?,
+            &mute,
+            &wake_word,
+            &separator()?,
+            &disconnect,
+            &quit,
+        ],
+    )?;
+
+    let icon = TrayIconBuilder::with_id("main")
+        .menu(&menu)
+        .show_menu_on_left_click(false)
+        .tooltip("ForgeAI Companion")
+        .on_menu_event(|app, event| on_menu(app, event.id.as_ref()))
+        .on_tray_icon_event(|tray, event| {
+            if matches!(event, TrayIconEvent::Click { .. }) {
+                toggle_window(tray.app_handle());
+            }
+        })
+        .build(app)?;
+
+    let _ = TRAY.set(TrayHandles { icon, status, mute, wake_word });
+
+    // Follow the same events the UI consumes
+    app.listen("connection-status", |event| {
+        let state = payload(&event)["state"].as_str().unwrap_or("offline").to_string();
+        update(|s| s.link = state);
+    });
+    app.listen("voice-state", |event| {
+        let state = payload(&event)["state"].as_str().unwrap_or("idle").to_string();
+        update(|s| s.voice = state);
+    });
+    app.listen("wake-word-toggled", |event| {
+        let enabled = payload(&event)["enabled"].as_bool().unwrap_or(false);
+        update(|s| s.wake_word = enabled);
+    });
+    app.listen("mic-muted", |event| {
+        let muted = payload(&event)["muted"].as_bool().unwrap_or(false);
+        update(|s| s.muted = muted);
+    });
+    app.listen("disconnected", |_| update(|s| s.link = "offline".into()));
+    update(|s| s.muted = crate::voice::mic_muted());
+
+    Ok(())
+}
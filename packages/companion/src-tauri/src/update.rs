@@ -0,0 +1,288 @@
+//! # Self-Update
+//!
+//! Companions spread over many machines are kept current by the Gateway
+//! they are paired with:
+//!
+//! 1. `GET /api/companion/update?current=<version>&target=<os>-<arch>` →
+//!    `204` when up to date, otherwise
+//!    `{version, notes, pubDate, url, sha256, signature}`.
+//! 2. The installer at `url` (Gateway-relative or absolute) is downloaded
+//!    to the temp directory, emitting `update-progress`.
+//! 3. `signature` — base64 Ed25519 over the signed manifest
+//!    `forgeai-companion-update\n<version>\n<target>\n<sha256>` — is
+//!    verified against the release key compiled into this build
+//!    (`FORGEAI_UPDATE_PUBLIC_KEY`, base64), and the installer must hash to
+//!    `sha256`. Binding the version and target means a validly signed older
+//!    release, or one built for another platform, cannot be replayed; the
+//!    signed version must also be newer than the running one. Builds
+//!    without a key refuse to install updates.
+//! 4. The installer is launched and the companion exits so it can be
+//!    replaced (Windows); elsewhere the package is opened for the user.
+//!
+//! Only the update found by the last check can be installed, so the
+//! frontend never supplies a download URL.
+
+use crate::connection::{CompanionCredentials, GatewayConnection};
+use base64::Engine as _;
+use ring::signature::{UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Release signing key, set at build time
+const PUBLIC_KEY: Option<&str> = option_env!("FORGEAI_UPDATE_PUBLIC_KEY");
+
+static AVAILABLE: Mutex<Option<UpdateInfo>> = Mutex::new(None);
+
+/// An update offered by the Gateway
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateInfo {
+    pub version: String,
+    #[serde(default)]
+    pub current_version: String,
+    /// Changelog (markdown)
+    #[serde(default)]
+    pub notes: String,
+    #[serde(default)]
+    pub pub_date: Option<String>,
+    pub url: String,
+    /// Hex SHA-256 of the installer
+    pub sha256: String,
+    pub signature: String,
+}
+
+/// Numeric `major.minor.patch` (pre-release suffixes are ignored)
+fn parse_version(v: &str) -> Vec<u64> {
+    v.trim_start_matches('v')
+        .split(['-', '+'])
+        .next()
+        .unwrap_or_default()
+        .split('.')
+        .map(|p| p.parse().unwrap_or(0))
+        .collect()
+}
+
+fn is_newer(candidate: &str, current: &str) -> bool {
+    let (mut a, mut b) = (parse_version(candidate), parse_version(current));
+    let len = a.len().max(b.len());
+    a.resize(len, 0);
+    b.resize(len, 0);
+    a > b
+}
+
+fn target() -> String {
+    format!("{}-{}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+/// The bytes the release key signs for an update
+fn signed_manifest(version: &str, target: &str, sha256: &str) -> Vec<u8> {
+    format!("forgeai-companion-update\n{}\n{}\n{}", version, target, sha256.to_ascii_lowercase()).into_bytes()
+}
+
+/// Check a downloaded installer against its manifest: signature over
+/// version, target and hash, the hash itself, and no downgrade
+fn verify_update(public_key: &str, info: &UpdateInfo, target: &str, installer: &[u8]) -> Result<(), String> {
+    verify(public_key, &signed_manifest(&info.version, target, &info.sha256), &info.signature)?;
+    let actual = format!("{:x}", Sha256::digest(installer));
+    if !actual.eq_ignore_ascii_case(info.sha256.trim()) {
+        return Err("Installer checksum does not match the signed manifest — the download was discarded".into());
+    }
+    if !is_newer(&info.version, env!("CARGO_PKG_VERSION")) {
+        return Err(format!("Refusing to install {} over {} (not newer)", info.version, env!("CARGO_PKG_VERSION")));
+    }
+    Ok(())
+}
+
+/// Check an Ed25519 `signature` (base64) of `data` against `public_key` (base64)
+fn verify(public_key: &str, data: &[u8], signature: &str) -> Result<(), String> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let key = b64.decode(public_key.trim()).map_err(|e| format!("Invalid update key: {}", e))?;
+    let sig = b64.decode(signature.trim()).map_err(|e| format!("Invalid update signature: {}", e))?;
+    UnparsedPublicKey::new(&ED25519, key)
+        .verify(data, &sig)
+        .map_err(|_| "Update signature verification failed — the download was discarded".to_string())
+}
+
+/// Ask the Gateway whether a newer companion is available
+pub async fn check(creds: &CompanionCredentials) -> Result<Option<UpdateInfo>, String> {
+    let current = env!("CARGO_PKG_VERSION");
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let path = format!("/api/companion/update?current={}&target={}", current, target());
+    let build = || gw.get(&path).timeout(Duration::from_secs(15));
+    let resp = GatewayConnection::send_authenticated(creds, build).await?;
+
+    let found = match resp.status() {
+        reqwest::StatusCode::NO_CONTENT | reqwest::StatusCode::NOT_FOUND => None,
+        s if s.is_success() => {
+            let mut info: UpdateInfo = resp.json().await.map_err(|e| format!("Invalid update manifest: {}", e))?;
+            info.current_version = current.to_string();
+            Some(info).filter(|i| is_newer(&i.version, current))
+        }
+        s => return Err(format!("Update check failed: Gateway HTTP {}", s)),
+    };
+
+    match &found {
+        Some(info) => tracing::info!("[Update] {} available (running {})", info.version, current),
+        None => tracing::info!("[Update] Up to date ({})", current),
+    }
+    *AVAILABLE.lock().map_err(|e| e.to_string())? = found.clone();
+    Ok(found)
+}
+
+async fn download(creds: &CompanionCredentials, info: &UpdateInfo) -> Result<Vec<u8>, String> {
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let mut resp = if info.url.starts_with('/') {
+        let build = || gw.get(&info.url).timeout(Duration::from_secs(600));
+        GatewayConnection::send_authenticated(creds, build).await?
+    } else {
+        let client = crate::proxy::apply(reqwest::Client::builder())
+            .build()
+            .map_err(|e| format!("HTTP client error: {}", e))?;
+        client
+            .get(&info.url)
+            .timeout(Duration::from_secs(600))
+            .send()
+            .await
+            .map_err(|e| format!("Download failed: {}", e))?
+    };
+    if !resp.status().is_success() {
+        return Err(format!("Download failed: HTTP {}", resp.status()));
+    }
+
+    let total = resp.content_length();
+    let mut bytes = Vec::with_capacity(total.unwrap_or(0) as usize);
+    while let Some(chunk) = resp.chunk().await.map_err(|e| format!("Download failed: {}", e))? {
+        bytes.extend_from_slice(&chunk);
+        crate::events::emit(
+            "update-progress",
+            serde_json::json!({ "downloaded": bytes.len(), "total": total }),
+        );
+    }
+    Ok(bytes)
+}
+
+/// `version` restricted to `[0-9A-Za-z.-]` so it cannot escape the temp directory
+fn safe_version(version: &str) -> String {
+    version
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '.' || *c == '-')
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+fn installer_path(info: &UpdateInfo) -> PathBuf {
+    let name = info
+        .url
+        .rsplit('/')
+        .next()
+        .map(|n| n.split('?').next().unwrap_or(n))
+        .filter(|n| !n.is_empty())
+        .unwrap_or("forgeai-companion-update");
+    std::env::temp_dir().join(format!("{}-{}", safe_version(&info.version), name))
+}
+
+/// Launch the installer. Returns true when the app should exit so it can be replaced.
+fn launch(path: &std::path::Path) -> Result<bool, String> {
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_ascii_lowercase();
+    let spawned = if cfg!(target_os = "windows") && ext == "msi" {
+        std::process::Command::new("msiexec").arg("/i").arg(path).arg("/passive").spawn()
+    } else if cfg!(target_os = "windows") {
+        std::process::Command::new(path).arg("/S").spawn()
+    } else if cfg!(target_os = "macos") {
+        std::process::Command::new("open").arg(path).spawn()
+    } else {
+        std::process::Command::new("xdg-open").arg(path).spawn()
+    };
+    spawned.map_err(|e| format!("Cannot start installer: {}", e))?;
+    Ok(cfg!(target_os = "windows"))
+}
+
+/// Download, verify and launch the update found by the last check.
+/// Returns true when the app should exit for the installer.
+pub async fn install(creds: &CompanionCredentials) -> Result<bool, String> {
+    let public_key = PUBLIC_KEY.ok_or("This build has no update signing key — install updates manually")?;
+    let info = AVAILABLE
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone()
+        .ok_or("No update available — check for updates first")?;
+
+    tracing::info!("[Update] Downloading {}", info.version);
+    let bytes = download(creds, &info).await?;
+    verify_update(public_key, &info, &target(), &bytes)?;
+
+    let path = installer_path(&info);
+    std::fs::write(&path, &bytes).map_err(|e| format!("Cannot save installer: {}", e))?;
+    tracing::info!("[Update] Verified {} — launching {}", info.version, path.display());
+    launch(&path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    #[test]
+    fn test_version_ordering() {
+        assert!(is_newer("1.3.0", "1.2.0"));
+        assert!(is_newer("v1.10.0", "1.9.9"));
+        assert!(is_newer("2.0", "1.99.99"));
+        assert!(!is_newer("1.2.0", "1.2.0"));
+        assert!(!is_newer("1.2.0-beta.1", "1.2.0"));
+        assert!(!is_newer("1.1.9", "1.2.0"));
+    }
+
+    #[test]
+    fn test_signature_verification() {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let key = b64.encode(pair.public_key().as_ref());
+
+        let installer = b"installer bytes";
+        let sha256 = format!("{:x}", Sha256::digest(installer));
+        let signed = |version: &str, target: &str| {
+            b64.encode(pair.sign(&signed_manifest(version, target, &sha256)).as_ref())
+        };
+        let info = |version: &str, signature: String| UpdateInfo {
+            version: version.into(),
+            current_version: String::new(),
+            notes: String::new(),
+            pub_date: None,
+            url: "/updates/setup.exe".into(),
+            sha256: sha256.clone(),
+            signature,
+        };
+
+        assert!(verify_update(&key, &info("99.0.0", signed("99.0.0", "windows-x86_64")), "windows-x86_64", installer).is_ok());
+        assert!(verify_update(&key, &info("99.0.0", signed("99.0.0", "windows-x86_64")), "windows-x86_64", b"tampered").is_err());
+        // Signature is bound to the version and target it was made for
+        assert!(verify_update(&key, &info("99.0.1", signed("99.0.0", "windows-x86_64")), "windows-x86_64", installer).is_err());
+        assert!(verify_update(&key, &info("99.0.0", signed("99.0.0", "linux-x86_64")), "windows-x86_64", installer).is_err());
+        // A correctly signed older release is still a rollback
+        assert!(verify_update(&key, &info("0.0.1", signed("0.0.1", "windows-x86_64")), "windows-x86_64", installer).is_err());
+        assert!(verify(&key, installer, "not base64!").is_err());
+    }
+
+    #[test]
+    fn test_installer_path_stays_in_temp() {
+        assert_eq!(safe_version("1.4.0-beta.2"), "1.4.0-beta.2");
+        assert_eq!(safe_version("../../etc/1.0"), "etc1.0");
+        assert_eq!(safe_version("..\\..\\x"), "x");
+        let path = installer_path(&UpdateInfo {
+            version: "../../../evil".into(),
+            current_version: String::new(),
+            notes: String::new(),
+            pub_date: None,
+            url: "/u/setup.msi".into(),
+            sha256: String::new(),
+            signature: String::new(),
+        });
+        assert_eq!(path.parent(), Some(std::env::temp_dir().as_path()));
+    }
+}
@@ -0,0 +1,216 @@
+//! # Usage Statistics
+//!
+//! A local, per-day record of how much the companion listened and spoke,
+//! so users can audit how much audio actually leaves the machine:
+//!
+//! - `recordingMinutes` — microphone recordings (voice turns, dictation,
+//!   translation, enrollment)
+//! - `audioSentMinutes` — audio uploaded for transcription or a voice turn,
+//!   including duplex streaming and recordings deferred while offline
+//! - `speechMinutes` — synthesized speech played back
+//! - `wakeDetections` and the number of local actions run, by action
+//!
+//! Nothing here is sent anywhere; `get_usage_stats` returns the last days
+//! from `usage.db` in the user profile. Days older than a year are dropped.
+
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+
+const RETENTION_DAYS: i64 = 366;
+const ACTION_PREFIX: &str = "action:";
+
+static DB: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+/// What is counted
+#[derive(Debug, Clone, Copy)]
+pub enum Metric {
+    RecordingMs,
+    AudioSentMs,
+    SpeechMs,
+    WakeDetections,
+}
+
+impl Metric {
+    fn key(self) -> &'static str {
+        match self {
+            Metric::RecordingMs => "recording_ms",
+            Metric::AudioSentMs => "audio_sent_ms",
+            Metric::SpeechMs => "speech_ms",
+            Metric::WakeDetections => "wake_detections",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DayUsage {
+    /// Local date, `YYYY-MM-DD`
+    pub date: String,
+    pub recording_minutes: f64,
+    pub audio_sent_minutes: f64,
+    pub speech_minutes: f64,
+    pub wake_detections: u64,
+    /// Runs per local action
+    pub actions: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageStats {
+    /// Newest first; days without any use are left out
+    pub days: Vec<DayUsage>,
+    /// The same counts summed over `days`
+    pub total: DayUsage,
+}
+
+fn init_schema(conn: &Connection) -> rusqlite::Result<()> {
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS usage (
+             day TEXT NOT NULL,
+             metric TEXT NOT NULL,
+             value INTEGER NOT NULL,
+             PRIMARY KEY (day, metric)
+         );",
+    )
+}
+
+fn with_db<T>(f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T, String> {
+    let db = match DB.get() {
+        Some(db) => db,
+        None => {
+            let dir = crate::users::data_dir().ok_or("Cannot determine data directory")?;
+            let _ = std::fs::create_dir_all(&dir);
+            let conn = Connection::open(dir.join("usage.db")).map_err(|e| format!("Usage DB error: {}", e))?;
+            init_schema(&conn).map_err(|e| format!("Usage DB error: {}", e))?;
+            let cutoff = (chrono::Local::now() - chrono::Duration::days(RETENTION_DAYS)).format("%Y-%m-%d").to_string();
+            let _ = conn.execute("DELETE FROM usage WHERE day < ?1", params![cutoff]);
+            DB.get_or_init(|| Mutex::new(conn))
+        }
+    };
+    let conn = db.lock().map_err(|e| e.to_string())?;
+    f(&conn).map_err(|e| format!("Usage DB error: {}", e))
+}
+
+fn add_to(conn: &Connection, day: &str, metric: &str, amount: u64) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT INTO usage (day, metric, value) VALUES (?1, ?2, ?3)
+         ON CONFLICT (day, metric) DO UPDATE SET value = value + excluded.value",
+        params![day, metric, amount as i64],
+    )?;
+    Ok(())
+}
+
+fn minutes(ms: u64) -> f64 {
+    (ms as f64 / 600.0).round() / 100.0
+}
+
+/// Per-day usage from `since` (a `YYYY-MM-DD` date) on, newest first
+fn days_since(conn: &Connection, since: &str) -> rusqlite::Result<Vec<DayUsage>> {
+    let mut stmt = conn.prepare("SELECT day, metric, value FROM usage WHERE day >= ?1 ORDER BY day DESC")?;
+    let rows = stmt.query_map(params![since], |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, i64>(2)?)))?;
+    let mut days: Vec<(DayUsage, [u64; 3])> = Vec::new();
+    for row in rows {
+        let (day, metric, value) = row?;
+        let value = value.max(0) as u64;
+        if days.last().is_none_or(|(d, _)| d.date != day) {
+            days.push((DayUsage { date: day, ..Default::default() }, [0; 3]));
+        }
+        let (usage, ms) = days.last_mut().expect("pushed above");
+        match metric.as_str() {
+            "recording_ms" => ms[0] = value,
+            "audio_sent_ms" => ms[1] = value,
+            "speech_ms" => ms[2] = value,
+            "wake_detections" => usage.wake_detections = value,
+            other => {
+                if let Some(action) = other.strip_prefix(ACTION_PREFIX) {
+                    usage.actions.insert(action.to_string(), value);
+                }
+            }
+        }
+    }
+    Ok(days
+        .into_iter()
+        .map(|(usage, ms)| DayUsage {
+            recording_minutes: minutes(ms[0]),
+            audio_sent_minutes: minutes(ms[1]),
+            speech_minutes: minutes(ms[2]),
+            ..usage
+        })
+        .collect())
+}
+
+fn sum(days: &[DayUsage]) -> DayUsage {
+    let mut total = DayUsage { date: "total".into(), ..Default::default() };
+    for day in days {
+        total.recording_minutes += day.recording_minutes;
+        total.audio_sent_minutes += day.audio_sent_minutes;
+        total.speech_minutes += day.speech_minutes;
+        total.wake_detections += day.wake_detections;
+        for (action, runs) in &day.actions {
+            *total.actions.entry(action.clone()).or_default() += runs;
+        }
+    }
+    let round = |m: f64| (m * 100.0).round() / 100.0;
+    total.recording_minutes = round(total.recording_minutes);
+    total.audio_sent_minutes = round(total.audio_sent_minutes);
+    total.speech_minutes = round(total.speech_minutes);
+    total
+}
+
+fn record_key(metric: &str, amount: u64) {
+    if amount == 0 {
+        return;
+    }
+    let today = chrono::Local::now().format("%Y-%m-%d").to_string();
+    if let Err(e) = with_db(|conn| add_to(conn, &today, metric, amount)) {
+        tracing::debug!("[Usage] Not recorded: {}", e);
+    }
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Add `amount` to today's `metric`
+pub fn record(metric: Metric, amount: u64) {
+    record_key(metric.key(), amount);
+}
+
+/// Count one run of local action `name`
+pub fn record_action(name: &str) {
+    record_key(&format!("{}{}", ACTION_PREFIX, name), 1);
+}
+
+/// Usage over the last `days` days, today included
+pub fn stats(days: u32) -> Result<UsageStats, String> {
+    let since = (chrono::Local::now() - chrono::Duration::days(days.saturating_sub(1) as i64)).format("%Y-%m-%d").to_string();
+    let days = with_db(|conn| days_since(conn, &since))?;
+    Ok(UsageStats { total: sum(&days), days })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daily_totals() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+        add_to(&conn, "2026-05-01", Metric::RecordingMs.key(), 90_000).unwrap();
+        add_to(&conn, "2026-05-01", Metric::RecordingMs.key(), 30_000).unwrap();
+        add_to(&conn, "2026-05-01", "action:open_app", 2).unwrap();
+        add_to(&conn, "2026-05-02", Metric::AudioSentMs.key(), 45_000).unwrap();
+        add_to(&conn, "2026-05-02", Metric::WakeDetections.key(), 3).unwrap();
+        add_to(&conn, "2026-05-02", "action:open_app", 1).unwrap();
+
+        let days = days_since(&conn, "2026-05-01").unwrap();
+        assert_eq!(days.iter().map(|d| d.date.as_str()).collect::<Vec<_>>(), ["2026-05-02", "2026-05-01"]);
+        assert_eq!(days[1].recording_minutes, 2.0);
+        assert_eq!((days[0].audio_sent_minutes, days[0].wake_detections), (0.75, 3));
+
+        let total = sum(&days);
+        assert_eq!(total.actions["open_app"], 3);
+        assert_eq!(total.recording_minutes, 2.0);
+        assert_eq!(days_since(&conn, "2026-05-02").unwrap().len(), 1);
+    }
+}
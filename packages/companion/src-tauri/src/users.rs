@@ -0,0 +1,163 @@
+//! # User Profiles
+//!
+//! One desktop, several people: each user profile is a separate companion
+//! identity with its own pairing (credentials), settings, chat and voice
+//! history, reminders and Gateway outbox, so every family member can be
+//! paired to their own Gateway account.
+//!
+//! The `default` profile lives directly in the data directory, where
+//! everything was kept before profiles existed; others live under
+//! `profiles/<name>`. Logs, crash reports, the proxy and the secure-store
+//! key are shared by all profiles.
+//!
+//! The active profile is read once at startup. `switch_profile` records the
+//! new one and restarts the companion, so no module keeps state of the
+//! previous user.
+
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::AppHandle;
+
+pub const DEFAULT_PROFILE: &str = "default";
+const MAX_NAME_LEN: usize = 32;
+
+static ACTIVE: OnceLock<String> = OnceLock::new();
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserProfiles {
+    pub active: String,
+    /// `default` first, then the others by name
+    pub profiles: Vec<String>,
+}
+
+/// Data shared by every profile
+pub fn shared_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|d| d.join("forgeai-companion"))
+}
+
+fn profile_dir(base: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        base.to_path_buf()
+    } else {
+        base.join("profiles").join(name)
+    }
+}
+
+fn active_file() -> Option<PathBuf> {
+    shared_dir().map(|d| d.join("active-profile"))
+}
+
+/// The profile this process runs as
+pub fn active() -> &'static str {
+    ACTIVE.get_or_init(|| {
+        let name = active_file()
+            .and_then(|p| std::fs::read_to_string(p).ok())
+            .map(|n| n.trim().to_string())
+            .unwrap_or_default();
+        // A deleted or hand-edited profile falls back to the default
+        let exists = shared_dir().is_some_and(|base| profile_dir(&base, &name).is_dir());
+        if validate_name(&name).is_ok() && exists {
+            name
+        } else {
+            DEFAULT_PROFILE.to_string()
+        }
+    })
+}
+
+/// Data of the active profile
+pub fn data_dir() -> Option<PathBuf> {
+    shared_dir().map(|base| profile_dir(&base, active()))
+}
+
+/// Keychain account holding the credentials of `profile`
+pub fn credentials_account(profile: &str) -> String {
+    if profile == DEFAULT_PROFILE {
+        "credentials".into()
+    } else {
+        format!("credentials:{}", profile)
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_NAME_LEN {
+        return Err(format!("Profile names must be 1 to {} characters", MAX_NAME_LEN));
+    }
+    if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Profile names may only contain letters, digits, '-' and '_'".into());
+    }
+    Ok(())
+}
+
+// ─── API ────────────────────────────────────────────
+
+pub fn list() -> UserProfiles {
+    let mut others: Vec<String> = shared_dir()
+        .and_then(|base| std::fs::read_dir(base.join("profiles")).ok())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|n| validate_name(n).is_ok() && n != DEFAULT_PROFILE)
+        .collect();
+    others.sort();
+    UserProfiles {
+        active: active().to_string(),
+        profiles: std::iter::once(DEFAULT_PROFILE.to_string()).chain(others).collect(),
+    }
+}
+
+/// Make `name` the active profile (created if new) and restart into it
+pub fn switch(app: &AppHandle, name: &str) -> Result<(), String> {
+    let name = name.trim();
+    validate_name(name)?;
+    if name == active() {
+        return Ok(());
+    }
+    let base = shared_dir().ok_or("Cannot determine data directory")?;
+    std::fs::create_dir_all(profile_dir(&base, name)).map_err(|e| format!("Cannot create profile: {}", e))?;
+    let file = active_file().ok_or("Cannot determine data directory")?;
+    std::fs::write(file, name).map_err(|e| format!("Cannot save active profile: {}", e))?;
+    tracing::info!("[Users] Switching to profile '{}' — restarting", name);
+    app.restart()
+}
+
+/// Delete an inactive profile with its pairing and data
+pub fn delete(name: &str) -> Result<UserProfiles, String> {
+    validate_name(name)?;
+    if name == DEFAULT_PROFILE {
+        return Err("The default profile cannot be deleted".into());
+    }
+    if name == active() {
+        return Err("Switch to another profile before deleting this one".into());
+    }
+    crate::connection::GatewayConnection::delete_profile_credentials(name);
+    let base = shared_dir().ok_or("Cannot determine data directory")?;
+    let dir = profile_dir(&base, name);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir).map_err(|e| format!("Cannot delete profile: {}", e))?;
+    }
+    tracing::info!("[Users] Deleted profile '{}'", name);
+    Ok(list())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profile_layout() {
+        let base = Path::new("/data/forgeai-companion");
+        assert_eq!(profile_dir(base, DEFAULT_PROFILE), base);
+        assert_eq!(profile_dir(base, "ana"), base.join("profiles").join("ana"));
+        assert_eq!(credentials_account(DEFAULT_PROFILE), "credentials");
+        assert_eq!(credentials_account("ana"), "credentials:ana");
+
+        assert!(validate_name("kids_room-2").is_ok());
+        assert!(validate_name("").is_err());
+        assert!(validate_name("../etc").is_err());
+        assert!(validate_name(&"x".repeat(33)).is_err());
+    }
+}
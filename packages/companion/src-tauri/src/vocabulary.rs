@@ -0,0 +1,131 @@
+//! # Custom Vocabulary
+//!
+//! Names and domain terms the speech recognizer keeps getting wrong, kept in
+//! the settings as `vocabulary.terms`. They help in two places:
+//!
+//! - **Biasing** — the terms travel with every transcription request
+//!   (`vocabulary`), for Gateways whose STT accepts a prompt or phrase hints.
+//! - **Post-correction** — each transcript is corrected locally: the term's
+//!   `soundsLike` variants ("communities" for "Kubernetes") are replaced by
+//!   the term, and the term itself gets its proper spelling and case.
+//!
+//! Matching is case-insensitive on whole words.
+
+use regex::{NoExpand, Regex};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+/// Longest accepted term or variant
+const MAX_TERM_LEN: usize = 100;
+
+/// Compiled replacements of the current vocabulary
+static RULES: Mutex<Vec<(Regex, String)>> = Mutex::new(Vec::new());
+static TERMS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VocabularyConfig {
+    pub terms: Vec<VocabularyTerm>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VocabularyTerm {
+    /// Correct spelling, e.g. "Kubernetes"
+    pub term: String,
+    /// What the recognizer hears instead, e.g. "communities"
+    #[serde(default)]
+    pub sounds_like: Vec<String>,
+}
+
+impl VocabularyConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        for term in &self.terms {
+            for text in std::iter::once(&term.term).chain(&term.sounds_like) {
+                if text.trim().is_empty() || text.len() > MAX_TERM_LEN {
+                    return Err(format!("Vocabulary entries must be 1 to {} characters", MAX_TERM_LEN));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn word_pattern(text: &str) -> Option<Regex> {
+    let words: Vec<String> = text.split_whitespace().map(regex::escape).collect();
+    Regex::new(&format!(r"(?i)\b{}\b", words.join(r"\s+"))).ok()
+}
+
+/// Replacement rules for `config`: variants first, then the terms
+/// themselves, longest first so multi-word entries win
+fn compile(config: &VocabularyConfig) -> Vec<(Regex, String)> {
+    let mut sources: Vec<(&str, &str)> = config
+        .terms
+        .iter()
+        .flat_map(|t| t.sounds_like.iter().map(move |v| (v.as_str(), t.term.as_str())))
+        .collect();
+    sources.sort_by_key(|(from, _)| std::cmp::Reverse(from.len()));
+    let mut terms: Vec<&str> = config.terms.iter().map(|t| t.term.as_str()).collect();
+    terms.sort_by_key(|t| std::cmp::Reverse(t.len()));
+    sources.extend(terms.into_iter().map(|t| (t, t)));
+    sources
+        .into_iter()
+        .filter_map(|(from, to)| Some((word_pattern(from)?, to.trim().to_string())))
+        .collect()
+}
+
+fn apply(rules: &[(Regex, String)], text: &str) -> String {
+    rules
+        .iter()
+        .fold(text.to_string(), |text, (pattern, term)| pattern.replace_all(&text, NoExpand(term)).into_owned())
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Use `config` from now on
+pub fn set_config(config: &VocabularyConfig) {
+    if let Ok(mut rules) = RULES.lock() {
+        *rules = compile(config);
+    }
+    if let Ok(mut terms) = TERMS.lock() {
+        *terms = config.terms.iter().map(|t| t.term.trim().to_string()).collect();
+    }
+}
+
+/// The terms to send as a biasing hint
+pub fn hint() -> Vec<String> {
+    TERMS.lock().map(|t| t.clone()).unwrap_or_default()
+}
+
+/// `transcript` with the vocabulary's spellings
+pub fn correct(transcript: &str) -> String {
+    match RULES.lock() {
+        Ok(rules) if !rules.is_empty() => apply(&rules, transcript),
+        _ => transcript.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_post_correction() {
+        let term = |term: &str, sounds_like: &[&str]| VocabularyTerm {
+            term: term.into(),
+            sounds_like: sounds_like.iter().map(|s| s.to_string()).collect(),
+        };
+        let config = VocabularyConfig {
+            terms: vec![term("Kubernetes", &["communities", "cooper netties"]), term("Ana Luíza", &[]), term("$5 plan", &["five dollar plan"])],
+        };
+        let rules = compile(&config);
+        assert_eq!(apply(&rules, "Deploy it to Communities, then cooper  netties again"), "Deploy it to Kubernetes, then Kubernetes again");
+        assert_eq!(apply(&rules, "call ana luíza about kubernetes"), "call Ana Luíza about Kubernetes");
+        // Whole words only, and the term is inserted literally
+        assert_eq!(apply(&rules, "communitiesque"), "communitiesque");
+        assert_eq!(apply(&rules, "the five dollar plan"), "the $5 plan");
+
+        assert!(config.validate().is_ok());
+        assert!(VocabularyConfig { terms: vec![term(" ", &[])] }.validate().is_err());
+    }
+}
@@ -3,12 +3,318 @@
 //! Handles microphone capture → WAV encoding → send to Gateway STT,
 //! and receives TTS audio from Gateway → plays back via speakers.
 //! Uses cpal for capture and rodio for playback.
+//!
+//! [`mic_test`] checks the whole chain locally: it records a few seconds,
+//! plays them straight back and reports the measured input level.
+//!
+//! A recording ends after `silenceTimeoutMs` of silence once it is at
+//! least `minUtteranceMs` long, or at `maxDurationSecs`. With
+//! `noSpeechTimeoutMs` set, a recording in which nothing is said gives up
+//! instead of ending on the first silence; `trailingPaddingMs` trims the
+//! silence after the last speech down to that much (see [`Endpointing`]).
+//!
+//! A recording whose microphone disappears (unplugged, Bluetooth out of
+//! range) keeps what it captured so far and carries on with the default or
+//! another connected input device; the switch is emitted as
+//! `voice-input-lost`.
+//!
+//! Playbacks queue behind each other instead of overlapping, so a reminder
+//! announced during a reply waits for the reply to finish. [`announce`]
+//! speaks through the Gateway TTS and falls back to the OS speech
+//! synthesizer when the Gateway is unreachable. [`stop_playback`] cuts off
+//! what is playing and everything queued behind it. During quiet hours
+//! (see `quiet_hours`) the queue drops what it would play.
+//!
+//! The Gateway's TTS voices are listed with [`list_tts_voices`]; the one
+//! selected in the settings (`ttsVoice`) is used by every synthesis
+//! request, including voice turns and duplex conversations. So are the STT
+//! and TTS providers picked in `voiceProviders` (e.g. whisper or deepgram,
+//! piper or elevenlabs), among those the Gateway's manifest lists; Gateways
+//! without a choice ignore them.
 
+use crate::connection::{CompanionCredentials, GatewayConnection};
+use crate::quiet_hours::Output;
 use base64::Engine as _;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use forgeai_companion_core::audio;
 use std::io::Cursor;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Microphone muted from the tray: no recording or wake-word capture starts
+static MIC_MUTED: AtomicBool = AtomicBool::new(false);
+
+static RECORDINGS: AtomicU64 = AtomicU64::new(0);
+static RECORDING_FAILURES: AtomicU64 = AtomicU64::new(0);
+static RECORDED_MS: AtomicU64 = AtomicU64::new(0);
+static PLAYBACKS: AtomicU64 = AtomicU64::new(0);
+static PLAYBACK_FAILURES: AtomicU64 = AtomicU64::new(0);
+static LAST_INPUT: Mutex<Option<InputFormat>> = Mutex::new(None);
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+/// Devices chosen in the settings (None: system default)
+static INPUT_DEVICE: Mutex<Option<String>> = Mutex::new(None);
+static OUTPUT_DEVICE: Mutex<Option<String>> = Mutex::new(None);
+/// Gateway TTS voice chosen in the settings (None: the Gateway's default)
+static TTS_VOICE: Mutex<Option<String>> = Mutex::new(None);
+/// Gateway STT/TTS providers chosen in the settings (None: the Gateway's default)
+static PROVIDERS: Mutex<Option<VoiceProviders>> = Mutex::new(None);
+/// Held while something plays; later playbacks wait their turn
+static PLAYBACK_QUEUE: Mutex<()> = Mutex::new(());
+/// Bumped by [`stop_playback`]; playbacks queued before the bump end early
+static PLAYBACK_GENERATION: AtomicU64 = AtomicU64::new(0);
+/// Silence from the input stream after which the device counts as gone
+const INPUT_STALL: std::time::Duration = std::time::Duration::from_secs(3);
+/// Devices a recording moves to when its input disappears
+const MAX_INPUT_REOPENS: usize = 2;
+/// How often a playback checks whether it was stopped
+const STOP_POLL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// Native format of the last capture
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct InputFormat {
+    pub device: String,
+    pub sample_rate: u32,
+    pub channels: usize,
+}
+
+/// Audio pipeline counters since startup
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AudioStats {
+    pub recordings: u64,
+    pub recording_failures: u64,
+    pub recorded_ms: u64,
+    pub playbacks: u64,
+    pub playback_failures: u64,
+    pub last_input: Option<InputFormat>,
+    pub last_error: Option<String>,
+}
+
+/// Audio pipeline counters since startup
+pub fn stats() -> AudioStats {
+    AudioStats {
+        recordings: RECORDINGS.load(Ordering::Relaxed),
+        recording_failures: RECORDING_FAILURES.load(Ordering::Relaxed),
+        recorded_ms: RECORDED_MS.load(Ordering::Relaxed),
+        playbacks: PLAYBACKS.load(Ordering::Relaxed),
+        playback_failures: PLAYBACK_FAILURES.load(Ordering::Relaxed),
+        last_input: LAST_INPUT.lock().ok().and_then(|l| l.clone()),
+        last_error: LAST_ERROR.lock().ok().and_then(|l| l.clone()),
+    }
+}
+
+fn count<T>(result: &Result<T, String>, ok: &AtomicU64, failed: &AtomicU64) {
+    match result {
+        Ok(_) => ok.fetch_add(1, Ordering::Relaxed),
+        Err(e) => {
+            if let Ok(mut last) = LAST_ERROR.lock() {
+                *last = Some(e.clone());
+            }
+            failed.fetch_add(1, Ordering::Relaxed)
+        }
+    };
+}
+
+/// Whether the microphone is muted
+pub fn mic_muted() -> bool {
+    MIC_MUTED.load(Ordering::Relaxed)
+}
+
+/// Mute or unmute the microphone
+pub fn set_mic_muted(muted: bool) {
+    MIC_MUTED.store(muted, Ordering::Relaxed);
+}
+
+/// Outcome of [`mic_test`]
+#[derive(Debug, Clone, serde::Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MicTestResult {
+    pub input_device: String,
+    pub output_device: String,
+    pub duration_ms: u64,
+    /// Linear 0..1
+    pub peak: f32,
+    pub rms: f32,
+    /// dBFS; -inf for digital silence is reported as -120
+    pub peak_db: f32,
+    pub rms_db: f32,
+    /// Share of samples at or near full scale (0..1)
+    pub clipped: f32,
+    /// Nothing above the silence threshold was heard
+    pub silent: bool,
+}
+
+/// Use the named devices for capture and playback (None: system default)
+pub fn set_devices(input: Option<String>, output: Option<String>) {
+    if let Ok(mut d) = INPUT_DEVICE.lock() {
+        *d = input;
+    }
+    if let Ok(mut d) = OUTPUT_DEVICE.lock() {
+        *d = output;
+    }
+}
+
+/// The configured input device, or the default one when it is not
+/// configured or not connected
+pub fn input_device() -> Option<cpal::Device> {
+    let host = cpal::default_host();
+    let wanted = INPUT_DEVICE.lock().ok().and_then(|d| d.clone());
+    if let Some(name) = wanted {
+        let found = host
+            .input_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().is_ok_and(|n| n == name)));
+        match found {
+            Some(device) => return Some(device),
+            None => tracing::warn!("Voice: input device '{}' not connected, using the default", name),
+        }
+    }
+    host.default_input_device()
+}
+
+/// An open capture stream and its native format
+struct InputStream {
+    name: String,
+    rate: u32,
+    channels: usize,
+    rx: std::sync::mpsc::Receiver<Vec<f32>>,
+    /// Set by the stream's error callback, e.g. when the device is unplugged
+    failed: Arc<AtomicBool>,
+    _stream: cpal::Stream,
+}
+
+impl InputStream {
+    /// Length of `native_len` native samples once converted to 16kHz mono
+    fn target_len(&self, native_len: usize) -> usize {
+        (native_len as u64 * audio::TARGET_RATE as u64 / (self.rate as u64 * self.channels.max(1) as u64)) as usize
+    }
+
+    /// `samples` in this stream's format as 16kHz mono
+    fn to_target(&self, samples: &[f32]) -> Vec<f32> {
+        audio::resample(&audio::downmix(samples, self.channels), self.rate, audio::TARGET_RATE)
+    }
+}
+
+/// Start capturing from `device` in its native config
+fn open_input(device: cpal::Device) -> Result<InputStream, String> {
+    // Use device's default config instead of forcing 16kHz
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("No supported input config: {}", e))?;
+
+    let name = device.name().unwrap_or_default();
+    let rate = supported.sample_rate().0;
+    let channels = supported.channels() as usize;
+    if let Ok(mut last) = LAST_INPUT.lock() {
+        *last = Some(InputFormat { device: name.clone(), sample_rate: rate, channels });
+    }
+
+    tracing::info!("Voice: using native config: {}Hz, {} channels", rate, channels);
+
+    let config = cpal::StreamConfig {
+        channels: channels as u16,
+        sample_rate: cpal::SampleRate(rate),
+        buffer_size: cpal::BufferSize::Default,
+    };
+
+    let (tx, rx) = std::sync::mpsc::sync_channel::<Vec<f32>>(128);
+    let failed = Arc::new(AtomicBool::new(false));
+    let on_error = failed.clone();
+    let stream = device
+        .build_input_stream(
+            &config,
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                let _ = tx.try_send(data.to_vec());
+            },
+            move |err| {
+                tracing::error!("Audio capture error: {}", err);
+                on_error.store(true, Ordering::Relaxed);
+            },
+            None,
+        )
+        .map_err(|e| format!("Failed to build input stream: {}", e))?;
+    stream.play().map_err(|e| format!("Failed to start recording: {}", e))?;
+
+    Ok(InputStream { name, rate, channels, rx, failed, _stream: stream })
+}
+
+/// An input device to carry on with after `lost` went away: the default
+/// one, or any other that is connected
+fn fallback_input(lost: &str) -> Option<cpal::Device> {
+    let host = cpal::default_host();
+    let usable = |d: &cpal::Device| d.name().is_ok_and(|n| n != lost);
+    host.default_input_device()
+        .filter(usable)
+        .or_else(|| host.input_devices().ok()?.find(usable))
+}
+
+/// Use the Gateway TTS voice `voice` from now on (None: its default)
+pub fn set_tts_voice(voice: Option<String>) {
+    if let Ok(mut v) = TTS_VOICE.lock() {
+        *v = voice;
+    }
+}
+
+/// The selected Gateway TTS voice
+pub fn tts_voice() -> Option<String> {
+    TTS_VOICE.lock().ok().and_then(|v| v.clone())
+}
+
+/// Speech providers to ask the Gateway for (`voiceProviders` in the settings)
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VoiceProviders {
+    /// e.g. `whisper`, `deepgram`
+    pub stt: Option<String>,
+    /// e.g. `piper`, `elevenlabs`
+    pub tts: Option<String>,
+}
+
+impl VoiceProviders {
+    pub fn validate(&self) -> Result<(), String> {
+        for name in [&self.stt, &self.tts].into_iter().flatten() {
+            let valid = (1..=40).contains(&name.len())
+                && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '-' | '_' | '.'));
+            if !valid {
+                return Err(format!("Invalid speech provider '{}'", name));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Ask the Gateway for `providers` from now on
+pub fn set_providers(providers: &VoiceProviders) {
+    if let Ok(mut p) = PROVIDERS.lock() {
+        *p = Some(providers.clone());
+    }
+}
+
+pub fn providers() -> VoiceProviders {
+    PROVIDERS.lock().ok().and_then(|p| p.clone()).unwrap_or_default()
+}
+
+/// The highest-priority connected device (see `output_priority`), else the
+/// one chosen in the settings
+fn configured_output() -> Option<String> {
+    crate::output_priority::selected().or_else(|| OUTPUT_DEVICE.lock().ok().and_then(|d| d.clone()))
+}
+
+/// The preferred or configured output device, or the default one when
+/// neither is connected
+pub fn output_device() -> Option<cpal::Device> {
+    let host = cpal::default_host();
+    if let Some(name) = configured_output() {
+        let found = host
+            .output_devices()
+            .ok()
+            .and_then(|mut devices| devices.find(|d| d.name().is_ok_and(|n| n == name)));
+        match found {
+            Some(device) => return Some(device),
+            None => tracing::warn!("Voice: output device '{}' not connected, using the default", name),
+        }
+    }
+    host.default_output_device()
+}
 
 /// Captured audio result
 #[derive(Clone, serde::Serialize)]
@@ -20,12 +326,48 @@ pub struct CapturedAudio {
     pub wav_base64: String,
 }
 
+/// Gateway transcription of a recording
+#[derive(Debug, Clone)]
+pub struct Transcript {
+    pub text: String,
+    /// Spoken language (BCP 47), when the Gateway reports it or `language`
+    /// detects it
+    pub language: Option<String>,
+    /// What transcribed it: `gateway`, or the fallback (see `stt_fallback`)
+    pub backend: String,
+}
+
+/// When a recording may end, besides the silence timeout and maximum length
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Endpointing {
+    /// Audio recorded before silence can end the recording
+    pub min_utterance_ms: u64,
+    /// Give up when nothing is said this long after the start (None: the
+    /// silence timeout applies from the start)
+    pub no_speech_timeout_ms: Option<u64>,
+    /// Silence kept after the last speech, the rest is trimmed (None: keep it all)
+    pub trailing_padding_ms: Option<u64>,
+}
+
+impl Default for Endpointing {
+    fn default() -> Self {
+        Self { min_utterance_ms: 500, no_speech_timeout_ms: None, trailing_padding_ms: None }
+    }
+}
+
+/// Cut `samples` (16kHz) to `padding_ms` after the last speech at `voice_end`
+fn trim_trailing(samples: &mut Vec<f32>, voice_end: usize, padding_ms: u64) {
+    let keep = voice_end + (padding_ms as usize * audio::TARGET_RATE as usize / 1000);
+    samples.truncate(keep);
+}
+
 /// Voice engine for capture and playback
 pub struct VoiceEngine {
     recording: Arc<AtomicBool>,
     max_duration_secs: u32,
     silence_threshold: f32,
     silence_timeout_ms: u64,
+    endpointing: Endpointing,
 }
 
 impl VoiceEngine {
@@ -35,6 +377,7 @@ impl VoiceEngine {
             max_duration_secs: 30,
             silence_threshold: 0.01,
             silence_timeout_ms: 800,
+            endpointing: Endpointing::default(),
         }
     }
 
@@ -45,11 +388,22 @@ impl VoiceEngine {
         self.silence_timeout_ms = silence_timeout_ms;
     }
 
+    /// Set the utterance bounds used from the next recording on
+    pub fn set_endpointing(&mut self, endpointing: Endpointing) {
+        self.endpointing = endpointing;
+    }
+
     /// Is currently recording?
     pub fn is_recording(&self) -> bool {
         self.recording.load(Ordering::Relaxed)
     }
 
+    /// Shared recording flag: clearing it stops the recording in progress
+    /// without taking the engine lock (held for the whole recording)
+    pub fn stop_handle(&self) -> Arc<AtomicBool> {
+        self.recording.clone()
+    }
+
     /// Stop recording
     pub fn stop_recording(&self) {
         self.recording.store(false, Ordering::Relaxed);
@@ -58,15 +412,10 @@ impl VoiceEngine {
     /// Record audio with real-time level events emitted to the frontend.
     /// Sends `voice-audio-level` events with { level: f32 } every ~50ms
     /// so the UI can render a live waveform visualization.
-    pub fn record_with_events(&self, app_handle: &tauri::AppHandle) -> Result<CapturedAudio, String> {
-        use tauri::Emitter;
-        let handle = app_handle.clone();
-
-        // We'll collect levels and emit them during recording
-        let emit_handle = handle.clone();
-        let result = self.record_internal(Some(emit_handle));
+    pub fn record_with_events(&self) -> Result<CapturedAudio, String> {
+        let result = self.record_internal(true);
         // Signal recording ended
-        let _ = handle.emit("voice-audio-level", serde_json::json!({ "level": 0.0, "done": true }));
+        crate::events::emit("voice-audio-level", serde_json::json!({ "level": 0.0, "done": true }));
         result
     }
 
@@ -74,107 +423,87 @@ impl VoiceEngine {
     /// Returns base64-encoded WAV data ready to send to Gateway STT.
     /// Uses device's native config and resamples to 16kHz mono.
     pub fn record(&self) -> Result<CapturedAudio, String> {
-        self.record_internal(None)
+        self.record_internal(false)
     }
 
-    fn record_internal(&self, app_handle: Option<tauri::AppHandle>) -> Result<CapturedAudio, String> {
+    fn record_internal(&self, emit_levels: bool) -> Result<CapturedAudio, String> {
+        let result = self.capture(emit_levels);
+        count(&result, &RECORDINGS, &RECORDING_FAILURES);
+        if let Ok(audio) = &result {
+            RECORDED_MS.fetch_add(audio.duration_ms, Ordering::Relaxed);
+            crate::usage::record(crate::usage::Metric::RecordingMs, audio.duration_ms);
+            crate::metrics::observe(crate::metrics::timing::RECORDING, "", audio.duration_ms as f64);
+        }
+        result
+    }
+
+    fn capture(&self, emit_levels: bool) -> Result<CapturedAudio, String> {
+        if mic_muted() {
+            return Err("Microphone is muted".into());
+        }
         if self.recording.load(Ordering::Relaxed) {
             // Force-reset if stuck
             self.recording.store(false, Ordering::Relaxed);
             std::thread::sleep(std::time::Duration::from_millis(100));
         }
+        let _mic = crate::mic_arbiter::claim(crate::mic_arbiter::Owner::Recording, "recording")?;
 
-        self.recording.store(true, Ordering::Relaxed);
         let recording = self.recording.clone();
         let silence_threshold = self.silence_threshold;
         let silence_timeout_ms = self.silence_timeout_ms;
         let max_duration_secs = self.max_duration_secs;
+        let endpointing = self.endpointing;
 
-        let host = cpal::default_host();
-        let device = host
-            .default_input_device()
-            .ok_or("No audio input device")?;
-
-        // Use device's default config instead of forcing 16kHz
-        let supported = device
-            .default_input_config()
-            .map_err(|e| format!("No supported input config: {}", e))?;
-
-        let native_rate = supported.sample_rate().0;
-        let native_channels = supported.channels() as usize;
-
-        log::info!(
-            "Voice: using native config: {}Hz, {} channels",
-            native_rate,
-            native_channels
-        );
-
-        let config = cpal::StreamConfig {
-            channels: native_channels as u16,
-            sample_rate: cpal::SampleRate(native_rate),
-            buffer_size: cpal::BufferSize::Default,
-        };
-
-        let max_native_samples = (native_rate as usize * max_duration_secs as usize) * native_channels;
-        let (tx, rx) = std::sync::mpsc::sync_channel::<Vec<f32>>(128);
+        let mut input = open_input(input_device().ok_or("No audio input device")?)?;
+        let mut processor = crate::input_profiles::Processor::for_device(&input.name);
+        let monitor = crate::monitor::Monitor::start();
+        recording.store(true, Ordering::Relaxed);
+        crate::status::publish();
 
-        let result = device.build_input_stream(
-            &config,
-            move |data: &[f32], _: &cpal::InputCallbackInfo| {
-                let _ = tx.try_send(data.to_vec());
-            },
-            |err| log::error!("Audio capture error: {}", err),
-            None,
-        );
-
-        let stream = match result {
-            Ok(s) => s,
-            Err(e) => {
-                recording.store(false, Ordering::Relaxed);
-                return Err(format!("Failed to build input stream: {}", e));
-            }
-        };
-
-        if let Err(e) = stream.play() {
-            recording.store(false, Ordering::Relaxed);
-            return Err(format!("Failed to start recording: {}", e));
-        }
+        tracing::info!("Voice: recording started");
 
-        log::info!("Voice: recording started");
-
-        let mut all_samples: Vec<f32> = Vec::with_capacity(max_native_samples);
+        let target_rate = audio::TARGET_RATE as usize;
+        let max_samples = target_rate * max_duration_secs as usize;
+        let min_samples = target_rate * endpointing.min_utterance_ms as usize / 1000;
+        // 16kHz mono from devices already done with, and native samples from the current one
+        let mut final_samples: Vec<f32> = Vec::with_capacity(max_samples);
+        let mut samples: Vec<f32> = Vec::new();
+        let mut reopened = 0;
         let mut last_voice_time = std::time::Instant::now();
+        // 16kHz samples recorded up to the last speech, if any was heard
+        let mut voice_end: Option<usize> = None;
+        let mut no_speech = false;
+        let mut last_data = std::time::Instant::now();
         let start = std::time::Instant::now();
 
         let mut last_emit = std::time::Instant::now();
 
         // Capture loop — stops on silence, max duration, or manual stop
         while recording.load(Ordering::Relaxed) {
-            match rx.recv_timeout(std::time::Duration::from_millis(50)) {
-                Ok(samples) => {
+            let lost = match input.rx.recv_timeout(std::time::Duration::from_millis(50)) {
+                Ok(mut data) => {
+                    last_data = std::time::Instant::now();
+                    if let Some(processor) = processor.as_mut() {
+                        processor.process(&mut data, input.channels, input.rate);
+                    }
                     // Downmix to mono for RMS check
-                    let mono: Vec<f32> = if native_channels > 1 {
-                        samples.chunks(native_channels)
-                            .map(|ch| ch.iter().sum::<f32>() / native_channels as f32)
-                            .collect()
-                    } else {
-                        samples.clone()
-                    };
-
-                    let rms: f32 = (mono.iter().map(|s| s * s).sum::<f32>()
-                        / mono.len().max(1) as f32)
-                        .sqrt();
-
-                    if rms > silence_threshold {
+                    let mono = audio::downmix(&data, input.channels);
+                    let rms = audio::rms(&mono);
+                    if let Some(monitor) = &monitor {
+                        monitor.push(&mono, input.rate);
+                    }
+
+                    let threshold = processor.as_ref().and_then(|p| p.silence_threshold()).unwrap_or(silence_threshold);
+                    let voiced = rms > threshold;
+                    if voiced {
                         last_voice_time = std::time::Instant::now();
                     }
 
                     // Emit audio level to frontend for waveform visualization (~20fps)
                     if last_emit.elapsed().as_millis() >= 50 {
-                        if let Some(ref handle) = app_handle {
-                            use tauri::Emitter;
+                        if emit_levels {
                             let level = (rms * 10.0).min(1.0); // normalize to 0..1
-                            let _ = handle.emit("voice-audio-level", serde_json::json!({
+                            crate::events::emit("voice-audio-level", serde_json::json!({
                                 "level": level,
                                 "done": false
                             }));
@@ -182,53 +511,94 @@ impl VoiceEngine {
                         last_emit = std::time::Instant::now();
                     }
 
-                    all_samples.extend_from_slice(&samples);
+                    samples.extend_from_slice(&data);
+                    let total = final_samples.len() + input.target_len(samples.len());
+                    if voiced {
+                        voice_end = Some(total);
+                    }
 
-                    if all_samples.len() >= max_native_samples {
-                        log::info!("Voice: max duration reached");
+                    if total >= max_samples {
+                        tracing::info!("Voice: max duration reached");
                         break;
                     }
 
-                    // Need at least 0.5s of audio before checking silence
-                    let min_samples = native_rate as usize * native_channels / 2;
-                    if last_voice_time.elapsed().as_millis() as u64 > silence_timeout_ms
-                        && all_samples.len() > min_samples
-                    {
-                        log::info!("Voice: silence detected, stopping");
-                        break;
+                    let silent_ms = last_voice_time.elapsed().as_millis() as u64;
+                    match endpointing.no_speech_timeout_ms.filter(|_| voice_end.is_none()) {
+                        Some(timeout) if silent_ms > timeout => {
+                            tracing::info!("Voice: no speech within {}ms, giving up", timeout);
+                            no_speech = true;
+                            break;
+                        }
+                        Some(_) => {}
+                        // Need the minimum utterance before checking silence
+                        None if silent_ms > silence_timeout_ms && total > min_samples => {
+                            tracing::info!("Voice: silence detected, stopping");
+                            break;
+                        }
+                        None => {}
                     }
+                    false
                 }
                 Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
                     if start.elapsed().as_secs() >= max_duration_secs as u64 {
                         break;
                     }
-                    continue;
+                    input.failed.load(Ordering::Relaxed) || last_data.elapsed() >= INPUT_STALL
                 }
-                Err(_) => break,
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => true,
+            };
+            if !lost {
+                continue;
             }
-        }
 
-        drop(stream);
-        recording.store(false, Ordering::Relaxed);
+            // Keep what the lost device captured and carry on with another one
+            final_samples.extend(input.to_target(&samples));
+            samples.clear();
+            let lost_name = input.name.clone();
+            let next = if reopened < MAX_INPUT_REOPENS { fallback_input(&lost_name) } else { None };
+            match next.map(open_input) {
+                Some(Ok(next)) => {
+                    tracing::warn!("Voice: input device '{}' lost mid-recording, continuing on '{}'", lost_name, next.name);
+                    crate::events::emit("voice-input-lost", serde_json::json!({ "device": lost_name, "fallback": next.name }));
+                    reopened += 1;
+                    processor = crate::input_profiles::Processor::for_device(&next.name);
+                    input = next;
+                    last_data = std::time::Instant::now();
+                }
+                failed => {
+                    if let Some(Err(e)) = failed {
+                        tracing::warn!("Voice: fallback input failed: {}", e);
+                    }
+                    tracing::warn!("Voice: input device '{}' lost mid-recording, keeping what was captured", lost_name);
+                    crate::events::emit("voice-input-lost", serde_json::json!({ "device": lost_name, "fallback": null }));
+                    break;
+                }
+            }
+        }
 
+        crate::latency::mark_at(crate::latency::Stage::SpeechEnd, last_voice_time);
+        crate::latency::mark(crate::latency::Stage::CaptureStop);
         // Convert to 16kHz mono
-        let mono_samples: Vec<f32> = if native_channels > 1 {
-            all_samples.chunks(native_channels)
-                .map(|ch| ch.iter().sum::<f32>() / native_channels as f32)
-                .collect()
-        } else {
-            all_samples
-        };
+        final_samples.extend(input.to_target(&samples));
+        drop(monitor);
+        drop(input);
+        recording.store(false, Ordering::Relaxed);
+        crate::status::publish();
 
-        // Resample to 16kHz if needed
-        let final_samples = if native_rate != 16000 {
-            resample(&mono_samples, native_rate, 16000)
-        } else {
-            mono_samples
-        };
+        // A denied microphone (macOS TCC, Windows privacy) yields pure zeros
+        if !final_samples.is_empty() && final_samples.iter().all(|s| *s == 0.0) {
+            crate::events::emit("permission-needed", serde_json::json!({ "kind": "microphone" }));
+            return Err("The microphone delivered only silence — microphone access may be denied".into());
+        }
+        if no_speech {
+            return Err("No speech heard".into());
+        }
+        if let (Some(end), Some(padding)) = (voice_end, endpointing.trailing_padding_ms) {
+            trim_trailing(&mut final_samples, end, padding);
+        }
 
         let duration_ms = (final_samples.len() as f64 / 16.0) as u64;
-        log::info!(
+        tracing::info!(
             "Voice: recorded {} samples ({}ms) after resample",
             final_samples.len(),
             duration_ms
@@ -239,8 +609,9 @@ impl VoiceEngine {
         }
 
         // Encode to WAV
-        let wav_data = encode_wav(&final_samples, 16000)?;
+        let wav_data = audio::encode_wav(&final_samples, audio::TARGET_RATE)?;
         let wav_base64 = base64::engine::general_purpose::STANDARD.encode(&wav_data);
+        crate::latency::mark(crate::latency::Stage::Encoded);
 
         Ok(CapturedAudio {
             duration_ms,
@@ -253,140 +624,371 @@ impl VoiceEngine {
     /// Send recorded audio to Gateway for STT transcription
     pub async fn transcribe(
         &self,
-        gateway_url: &str,
-        jwt_token: &str,
+        creds: &CompanionCredentials,
         audio: &CapturedAudio,
     ) -> Result<String, String> {
-        let url = format!("{}/api/voice/transcribe", gateway_url.trim_end_matches('/'));
+        self.transcribe_detailed(creds, audio).await.map(|t| t.text)
+    }
 
+    /// Like `transcribe`, with the spoken language when the Gateway detects it
+    pub async fn transcribe_detailed(
+        &self,
+        creds: &CompanionCredentials,
+        audio: &CapturedAudio,
+    ) -> Result<Transcript, String> {
         let wav_bytes = base64::engine::general_purpose::STANDARD
             .decode(&audio.wav_base64)
             .map_err(|e| format!("Base64 decode error: {}", e))?;
 
-        // Build multipart form
-        let part = reqwest::multipart::Part::bytes(wav_bytes)
-            .file_name("audio.wav")
-            .mime_str("audio/wav")
-            .map_err(|e| format!("MIME error: {}", e))?;
-
-        let form = reqwest::multipart::Form::new().part("audio", part);
-
-        let client = reqwest::Client::new();
-        let resp = client
-            .post(&url)
-            .header("Cookie", format!("forgeai_session={}", jwt_token))
-            .multipart(form)
-            .timeout(std::time::Duration::from_secs(30))
-            .send()
-            .await
-            .map_err(|e| format!("Transcribe request failed: {}", e))?;
-
-        if !resp.status().is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            return Err(format!("Transcription failed: {}", text));
+        // Multipart forms are consumed on send, so the builder recreates it for a retry
+        let started = std::time::Instant::now();
+        let gw = crate::http::gateway(&creds.gateway_url)?;
+        let build = || {
+            let part = reqwest::multipart::Part::bytes(wav_bytes.clone())
+                .file_name("audio.wav")
+                .mime_str("audio/wav")
+                .expect("static MIME type is valid");
+            let mut form = reqwest::multipart::Form::new().part("audio", part);
+            let vocabulary = crate::vocabulary::hint();
+            if !vocabulary.is_empty() {
+                form = form.text("vocabulary", vocabulary.join(", "));
+            }
+            if let Some(provider) = providers().stt {
+                form = form.text("provider", provider);
+            }
+            gw.post("/api/voice/transcribe")
+                .multipart(form)
+                .timeout(crate::stt_fallback::primary_timeout())
+        };
+        let primary = async {
+            let resp = GatewayConnection::send_authenticated(creds, build).await?;
+            if !resp.status().is_success() {
+                let text = resp.text().await.unwrap_or_default();
+                return Err(format!("Transcription failed: {}", text));
+            }
+            resp.json::<serde_json::Value>()
+                .await
+                .map_err(|e| format!("Parse error: {}", e))
         }
+        .await;
 
-        let data: serde_json::Value = resp
-            .json()
-            .await
-            .map_err(|e| format!("Parse error: {}", e))?;
+        let (data, backend) = match primary {
+            Ok(data) => (data, "gateway".to_string()),
+            Err(e) => match crate::stt_fallback::transcribe(creds, &wav_bytes, &e).await {
+                Some(fallback) => fallback.map_err(|f| format!("{} (fallback: {})", e, f))?,
+                None => return Err(e),
+            },
+        };
+        crate::usage::record(crate::usage::Metric::AudioSentMs, audio.duration_ms);
 
-        data["text"]
-            .as_str()
-            .map(|s| s.to_string())
-            .ok_or("No transcription text in response".into())
+        let label = if backend == "gateway" { "" } else { backend.as_str() };
+        crate::metrics::observe_since(crate::metrics::timing::STT, label, started);
+        crate::latency::mark(crate::latency::Stage::Transcribed);
+        let text = data["text"].as_str().ok_or("No transcription text in response")?;
+        let text = crate::transcript_filter::apply(&crate::vocabulary::correct(text));
+        Ok(Transcript {
+            language: crate::language::resolve(data["language"].as_str(), &text),
+            text,
+            backend,
+        })
     }
 
     /// Request TTS from Gateway and play the audio
     pub async fn speak(
         &self,
-        gateway_url: &str,
-        jwt_token: &str,
+        creds: &CompanionCredentials,
         text: &str,
     ) -> Result<(), String> {
-        let url = format!(
-            "{}/api/voice/synthesize",
-            gateway_url.trim_end_matches('/')
-        );
-
-        let client = reqwest::Client::new();
-        let resp = client
-            .post(&url)
-            .header("Cookie", format!("forgeai_session={}", jwt_token))
-            .json(&serde_json::json!({ "text": text }))
-            .timeout(std::time::Duration::from_secs(30))
-            .send()
-            .await
-            .map_err(|e| format!("TTS request failed: {}", e))?;
-
-        if !resp.status().is_success() {
-            let text = resp.text().await.unwrap_or_default();
-            return Err(format!("TTS failed: {}", text));
-        }
+        self.speak_in(creds, text, None).await
+    }
 
-        let audio_bytes = resp
-            .bytes()
-            .await
-            .map_err(|e| format!("Read audio failed: {}", e))?;
+    /// Like `speak`, with a voice for `language` (BCP 47) when given
+    pub async fn speak_in(
+        &self,
+        creds: &CompanionCredentials,
+        text: &str,
+        language: Option<&str>,
+    ) -> Result<(), String> {
+        let audio_bytes = synthesize(creds, text, language, tts_voice().as_deref()).await?;
 
         // Play audio using rodio
-        play_audio_bytes(&audio_bytes)?;
+        play_output(&audio_bytes, Output::Speech(Some(text)))?;
 
         Ok(())
     }
 }
 
-/// Simple linear interpolation resampler (from_rate → to_rate)
-fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
-    if from_rate == to_rate {
-        return samples.to_vec();
-    }
-    let ratio = from_rate as f64 / to_rate as f64;
-    let out_len = (samples.len() as f64 / ratio) as usize;
-    let mut output = Vec::with_capacity(out_len);
-    for i in 0..out_len {
-        let src_pos = i as f64 * ratio;
-        let idx = src_pos as usize;
-        let frac = src_pos - idx as f64;
-        let s0 = samples[idx.min(samples.len() - 1)];
-        let s1 = samples[(idx + 1).min(samples.len() - 1)];
-        output.push(s0 + (s1 - s0) * frac as f32);
-    }
-    output
-}
-
-/// Encode f32 samples to WAV bytes
-fn encode_wav(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>, String> {
-    let mut buffer = Vec::new();
-    {
-        let cursor = Cursor::new(&mut buffer);
-        let spec = hound::WavSpec {
-            channels: 1,
-            sample_rate,
-            bits_per_sample: 16,
-            sample_format: hound::SampleFormat::Int,
-        };
-        let mut writer =
-            hound::WavWriter::new(cursor, spec).map_err(|e| format!("WAV writer error: {}", e))?;
-
-        for &sample in samples {
-            let s16 = (sample * 32767.0).clamp(-32768.0, 32767.0) as i16;
-            writer
-                .write_sample(s16)
-                .map_err(|e| format!("WAV write error: {}", e))?;
+/// Gateway TTS of `text` in the selected voice (or one for `language`),
+/// for callers that schedule the playback themselves
+pub async fn synthesize_speech(creds: &CompanionCredentials, text: &str, language: Option<&str>) -> Result<Vec<u8>, String> {
+    synthesize(creds, text, language, tts_voice().as_deref()).await
+}
+
+/// Gateway TTS of `text`, as encoded audio
+async fn synthesize(
+    creds: &CompanionCredentials,
+    text: &str,
+    language: Option<&str>,
+    voice: Option<&str>,
+) -> Result<Vec<u8>, String> {
+    let started = std::time::Instant::now();
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let mut body = serde_json::json!({ "text": crate::lexicon::pronounce(text) });
+    if let Some(language) = language {
+        body["language"] = language.into();
+    }
+    if let Some(voice) = voice {
+        body["voice"] = voice.into();
+    }
+    if let Some(provider) = providers().tts {
+        body["provider"] = provider.into();
+    }
+    let build = || gw
+        .post("/api/voice/synthesize")
+        .json(&body)
+        .timeout(std::time::Duration::from_secs(30));
+    let resp = GatewayConnection::send_authenticated(creds, build).await?;
+
+    if !resp.status().is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(format!("TTS failed: {}", text));
+    }
+
+    let audio_bytes = resp
+        .bytes()
+        .await
+        .map_err(|e| format!("Read audio failed: {}", e))?;
+    crate::metrics::observe_since(crate::metrics::timing::TTS, "", started);
+    crate::latency::mark(crate::latency::Stage::Synthesized);
+    Ok(audio_bytes.to_vec())
+}
+
+// ─── Voice catalog ──────────────────────────────────
+
+/// What a preview says when the voice has no sample
+const PREVIEW_TEXT: &str = "Hello, this is how I sound.";
+
+/// A Gateway TTS voice
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TtsVoice {
+    pub id: String,
+    pub name: String,
+    /// BCP 47 tag, e.g. `en-US`
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Gateway path or https URL of a short sample
+    #[serde(default)]
+    pub sample_url: Option<String>,
+}
+
+/// The voices the Gateway can synthesize with (`GET /api/voice/voices`)
+pub async fn list_tts_voices(creds: &CompanionCredentials) -> Result<Vec<TtsVoice>, String> {
+    let gw = crate::http::gateway(&creds.gateway_url)?;
+    let build = || gw.get("/api/voice/voices").timeout(std::time::Duration::from_secs(15));
+    let resp = GatewayConnection::send_authenticated(creds, build).await?;
+    if !resp.status().is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return Err(format!("Listing voices failed: {}", text));
+    }
+    let data: serde_json::Value = resp.json().await.map_err(|e| format!("Parse error: {}", e))?;
+    serde_json::from_value(data.get("voices").cloned().unwrap_or(data))
+        .map_err(|e| format!("Unexpected voice list: {}", e))
+}
+
+/// Play a sample of `voice_id`: its sample when the Gateway offers one,
+/// otherwise a sentence synthesized with it
+pub async fn preview_tts_voice(creds: &CompanionCredentials, voice_id: &str) -> Result<(), String> {
+    let voices = list_tts_voices(creds).await?;
+    let voice = voices
+        .iter()
+        .find(|v| v.id == voice_id)
+        .ok_or_else(|| format!("The Gateway has no voice '{}'", voice_id))?;
+    let sample = match voice.sample_url.as_deref() {
+        Some(path) if path.starts_with('/') => {
+            let gw = crate::http::gateway(&creds.gateway_url)?;
+            let build = || gw.get(path).timeout(std::time::Duration::from_secs(30));
+            let resp = GatewayConnection::send_authenticated(creds, build).await?;
+            if !resp.status().is_success() {
+                return Err(format!("Voice sample unavailable ({})", resp.status()));
+            }
+            Some(resp.bytes().await.map_err(|e| format!("Read audio failed: {}", e))?.to_vec())
+        }
+        // Samples hosted by the TTS provider; the session cookie is not sent there
+        Some(url) if url.starts_with("https://") => {
+            let client = crate::proxy::apply(reqwest::Client::builder())
+                .build()
+                .map_err(|e| format!("HTTP client error: {}", e))?;
+            let resp = client
+                .get(url)
+                .timeout(std::time::Duration::from_secs(30))
+                .send()
+                .await
+                .map_err(|e| format!("Voice sample unavailable: {}", e))?;
+            if !resp.status().is_success() {
+                return Err(format!("Voice sample unavailable ({})", resp.status()));
+            }
+            Some(resp.bytes().await.map_err(|e| format!("Read audio failed: {}", e))?.to_vec())
+        }
+        _ => None,
+    };
+    let audio_bytes = match sample {
+        Some(bytes) => bytes,
+        None => synthesize(creds, PREVIEW_TEXT, voice.language.as_deref(), Some(&voice.id)).await?,
+    };
+    play_audio_bytes(&audio_bytes)
+}
+
+/// Sample rate and channel count the input device records in
+pub fn native_input_format() -> Option<(u32, usize)> {
+    let config = input_device()?.default_input_config().ok()?;
+    Some((config.sample_rate().0, config.channels() as usize))
+}
+
+/// Seconds recorded by [`mic_test`]
+const MIC_TEST_SECS: u64 = 3;
+/// Samples at or above this magnitude count as clipped
+const CLIP_LEVEL: f32 = 0.99;
+
+fn to_db(level: f32) -> f32 {
+    if level > 0.0 {
+        (20.0 * level.log10()).max(-120.0)
+    } else {
+        -120.0
+    }
+}
+
+/// Level statistics of mono samples
+fn level_stats(samples: &[f32], silence_threshold: f32) -> (f32, f32, f32, bool) {
+    let peak = samples.iter().fold(0.0f32, |m, s| m.max(s.abs()));
+    let rms = audio::rms(samples);
+    let clipped = samples.iter().filter(|s| s.abs() >= CLIP_LEVEL).count() as f32 / samples.len().max(1) as f32;
+    (peak, rms, clipped, peak <= silence_threshold)
+}
+
+/// Record from the default input for a fixed time; returns mono samples,
+/// their rate and the device name
+fn record_fixed(duration: std::time::Duration) -> Result<(Vec<f32>, u32, String), String> {
+    let device = input_device().ok_or("No audio input device")?;
+    let supported = device
+        .default_input_config()
+        .map_err(|e| format!("No supported input config: {}", e))?;
+    let rate = supported.sample_rate().0;
+    let channels = supported.channels() as usize;
+    let config = cpal::StreamConfig {
+        channels: channels as u16,
+        sample_rate: cpal::SampleRate(rate),
+        buffer_size: cpal::BufferSize::Default,
+    };
+
+    let (tx, rx) = std::sync::mpsc::sync_channel::<Vec<f32>>(256);
+    let stream = device
+        .build_input_stream(
+            &config,
+            move |data: &[f32], _: &cpal::InputCallbackInfo| {
+                let _ = tx.try_send(data.to_vec());
+            },
+            |err| tracing::error!("Audio capture error: {}", err),
+            None,
+        )
+        .map_err(|e| format!("Failed to build input stream: {}", e))?;
+    stream.play().map_err(|e| format!("Failed to start recording: {}", e))?;
+
+    let wanted = rate as usize * channels * duration.as_millis() as usize / 1000;
+    let deadline = std::time::Instant::now() + duration + std::time::Duration::from_secs(1);
+    let mut samples = Vec::with_capacity(wanted);
+    while samples.len() < wanted && std::time::Instant::now() < deadline {
+        if let Ok(chunk) = rx.recv_timeout(std::time::Duration::from_millis(50)) {
+            samples.extend_from_slice(&chunk);
         }
+    }
+    drop(stream);
+
+    Ok((audio::downmix(&samples, channels), rate, device.name().unwrap_or_default()))
+}
 
-        writer
-            .finalize()
-            .map_err(|e| format!("WAV finalize error: {}", e))?;
+/// Record three seconds, play them back on `output_device` (the configured
+/// output when None) and report the input level. Blocking.
+pub fn mic_test(output_device: Option<&str>, silence_threshold: f32) -> Result<MicTestResult, String> {
+    if mic_muted() {
+        return Err("Microphone is muted".into());
+    }
+    tracing::info!("Voice: microphone test started");
+    let (samples, rate, input_device) = record_fixed(std::time::Duration::from_secs(MIC_TEST_SECS))?;
+    if samples.is_empty() {
+        return Err("The input device delivered no audio".into());
     }
-    Ok(buffer)
+    let (peak, rms, clipped, silent) = level_stats(&samples, silence_threshold);
+
+    let output_device = output_device.map(str::to_string).or_else(configured_output);
+    let output = output_device.clone().unwrap_or_else(|| {
+        cpal::default_host()
+            .default_output_device()
+            .and_then(|d| d.name().ok())
+            .unwrap_or_default()
+    });
+    let result = play_on(&audio::encode_wav(&samples, rate)?, output_device.as_deref());
+    count(&result, &PLAYBACKS, &PLAYBACK_FAILURES);
+    result?;
+
+    Ok(MicTestResult {
+        input_device,
+        output_device: output,
+        duration_ms: samples.len() as u64 * 1000 / rate as u64,
+        peak,
+        rms,
+        peak_db: to_db(peak),
+        rms_db: to_db(rms),
+        clipped,
+        silent,
+    })
 }
 
-/// Play audio bytes (WAV/MP3 format) through the default output device
+/// Play audio bytes (WAV/MP3 format) through the configured output device
 pub fn play_audio_bytes(audio_bytes: &[u8]) -> Result<(), String> {
-    let (_stream, stream_handle) = rodio::OutputStream::try_default()
-        .map_err(|e| format!("Audio output error: {}", e))?;
+    play_output(audio_bytes, Output::Speech(None))
+}
+
+/// Play `output`'s audio, unless quiet hours mute it
+pub fn play_output(audio_bytes: &[u8], output: Output) -> Result<(), String> {
+    if !crate::quiet_hours::allows(output) {
+        return Ok(());
+    }
+    let result = play(audio_bytes);
+    count(&result, &PLAYBACKS, &PLAYBACK_FAILURES);
+    result
+}
+
+fn play(audio_bytes: &[u8]) -> Result<(), String> {
+    match configured_output() {
+        Some(name) => play_on(audio_bytes, Some(&name)).or_else(|e| {
+            tracing::warn!("Voice: {} — playing on the default output", e);
+            play_on(audio_bytes, None)
+        }),
+        None => play_on(audio_bytes, None),
+    }
+}
+
+/// Play on the output device called `device_name`, or the default one
+fn play_on(audio_bytes: &[u8], device_name: Option<&str>) -> Result<(), String> {
+    let generation = PLAYBACK_GENERATION.load(Ordering::Relaxed);
+    let _turn = PLAYBACK_QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+    if stopped_since(generation) {
+        return Ok(());
+    }
+    let _speaking = crate::mic_arbiter::speaking();
+    let (_stream, stream_handle) = match device_name {
+        Some(name) => {
+            let device = cpal::default_host()
+                .output_devices()
+                .map_err(|e| format!("Audio output error: {}", e))?
+                .find(|d| d.name().is_ok_and(|n| n == name))
+                .ok_or_else(|| format!("Output device '{}' not found", name))?;
+            rodio::OutputStream::try_from_device(&device)
+        }
+        None => rodio::OutputStream::try_default(),
+    }
+    .map_err(|e| format!("Audio output error: {}", e))?;
 
     let cursor = Cursor::new(audio_bytes.to_vec());
     let source = rodio::Decoder::new(cursor)
@@ -396,11 +998,101 @@ pub fn play_audio_bytes(audio_bytes: &[u8]) -> Result<(), String> {
         .map_err(|e| format!("Sink error: {}", e))?;
 
     sink.append(source);
-    sink.sleep_until_end();
+    crate::latency::mark(crate::latency::Stage::FirstAudio);
+    let started = std::time::Instant::now();
+    while !sink.empty() {
+        if stopped_since(generation) {
+            sink.stop();
+            break;
+        }
+        std::thread::sleep(STOP_POLL);
+    }
+    crate::usage::record(crate::usage::Metric::SpeechMs, started.elapsed().as_millis() as u64);
 
     Ok(())
 }
 
+/// Speak `text` with the OS speech synthesizer (no Gateway needed). Blocking.
+pub fn speak_offline(text: &str) -> Result<(), String> {
+    if !crate::quiet_hours::allows(Output::Speech(Some(text))) {
+        return Ok(());
+    }
+    let text = &crate::lexicon::pronounce(text);
+    let generation = PLAYBACK_GENERATION.load(Ordering::Relaxed);
+    let _turn = PLAYBACK_QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+    if stopped_since(generation) {
+        return Ok(());
+    }
+    let _speaking = crate::mic_arbiter::speaking();
+    let mut command = if cfg!(target_os = "windows") {
+        let mut c = std::process::Command::new("powershell");
+        c.args([
+            "-NoProfile",
+            "-NonInteractive",
+            "-Command",
+            &format!(
+                "Add-Type -AssemblyName System.Speech; (New-Object System.Speech.Synthesis.SpeechSynthesizer).Speak('{}')",
+                text.replace('\'', "''")
+            ),
+        ]);
+        c
+    } else if cfg!(target_os = "macos") {
+        let mut c = std::process::Command::new("say");
+        c.arg(text);
+        c
+    } else {
+        let mut c = std::process::Command::new("spd-say");
+        c.args(["--wait", text]);
+        c
+    };
+    let mut child = command.spawn().map_err(|e| format!("Speech synthesizer unavailable: {}", e))?;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| format!("Speech synthesizer error: {}", e))? {
+            break status;
+        }
+        if stopped_since(generation) {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(());
+        }
+        std::thread::sleep(STOP_POLL);
+    };
+    let result = if status.success() { Ok(()) } else { Err(format!("Speech synthesizer failed ({})", status)) };
+    count(&result, &PLAYBACKS, &PLAYBACK_FAILURES);
+    result
+}
+
+/// Current playback generation, to check later whether [`stop_playback`] ran
+pub fn playback_generation() -> u64 {
+    PLAYBACK_GENERATION.load(Ordering::Relaxed)
+}
+
+/// Whether [`stop_playback`] ran since `generation`
+pub fn stopped_since(generation: u64) -> bool {
+    PLAYBACK_GENERATION.load(Ordering::Relaxed) != generation
+}
+
+/// Stop what is playing and drop everything queued
+pub fn stop_playback() {
+    PLAYBACK_GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Say `text` out loud: Gateway TTS when paired and reachable, the OS
+/// synthesizer otherwise
+pub async fn announce(text: &str) -> Result<(), String> {
+    // Offline, the Gateway attempt would only delay the fallback
+    if let Some(creds) = GatewayConnection::load_credentials().filter(|_| !crate::offline::is_offline()) {
+        match VoiceEngine::new().speak(&creds, text).await {
+            Ok(()) => return Ok(()),
+            Err(e) => tracing::warn!("Voice: Gateway TTS failed, using the local synthesizer: {}", e),
+        }
+    }
+    let text = text.to_string();
+    tauri::async_runtime::spawn_blocking(move || speak_offline(&text))
+        .await
+        .map_err(|e| format!("Speech failed: {}", e))?
+}
+
 /// List available audio output devices
 pub fn list_output_devices() -> Vec<String> {
     let host = cpal::default_host();
@@ -408,3 +1100,32 @@ pub fn list_output_devices() -> Vec<String> {
         .map(|devices| devices.filter_map(|d| d.name().ok()).collect())
         .unwrap_or_default()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mic_test_levels() {
+        let (peak, rms, clipped, silent) = level_stats(&[0.5, -0.5, 0.5, -0.5], 0.01);
+        assert_eq!((peak, rms, clipped, silent), (0.5, 0.5, 0.0, false));
+        assert!((to_db(0.5) + 6.02).abs() < 0.01);
+
+        let (_, _, clipped, _) = level_stats(&[1.0, -1.0, 0.1, 0.0], 0.01);
+        assert_eq!(clipped, 0.5);
+
+        let (peak, _, _, silent) = level_stats(&[0.001; 100], 0.01);
+        assert!(silent && peak > 0.0);
+        assert_eq!(to_db(0.0), -120.0);
+    }
+
+    #[test]
+    fn test_trailing_padding() {
+        let mut samples = vec![0.0; 32_000];
+        trim_trailing(&mut samples, 8_000, 250);
+        assert_eq!(samples.len(), 12_000);
+        // Never extends a recording that stopped sooner
+        trim_trailing(&mut samples, 11_000, 500);
+        assert_eq!(samples.len(), 12_000);
+    }
+}
@@ -0,0 +1,221 @@
+//! # Wake Word Training Samples
+//!
+//! Collects the recordings needed to train a custom wake word ("Hey
+//! Jarvis" instead of "Hey Forge"). `wake_word_collect_samples` walks the
+//! user through saying the phrase a number of times, emitting
+//! `wake-word-sample` before each take and after a take is rejected (no
+//! speech, or longer than [`MAX_SAMPLE_MS`]), which is then asked for again.
+//!
+//! The takes are converted to the format wake word trainers expect —
+//! 16 kHz, mono, 16-bit PCM WAV — and packaged as `samples.zip`:
+//!
+//! | File                     | Contents                                           |
+//! |--------------------------|----------------------------------------------------|
+//! | `manifest.json`          | phrase, label, audio format and one entry per take |
+//! | `samples/<label>_NN.wav` | the takes                                          |
+//!
+//! The package is stored in the profile's data directory under
+//! `wake_words/<label>/`, next to `<label>.ppn`, the path the trained model
+//! is expected at and the one to pass to `wake_word_configure`.
+
+use crate::voice::VoiceEngine;
+use base64::Engine;
+use forgeai_companion_core::audio;
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+/// Takes recorded when the caller does not say how many
+pub const DEFAULT_SAMPLES: usize = 10;
+const MAX_SAMPLES: usize = 100;
+/// Longest take accepted; a wake phrase is said in a second or two
+pub const MAX_SAMPLE_MS: u64 = 3_000;
+/// RMS a take must reach somewhere to contain speech
+const SPEECH_LEVEL: f32 = 0.01;
+/// Rejected takes allowed per sample before giving up
+const ATTEMPTS_PER_SAMPLE: usize = 3;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleEntry {
+    pub file: String,
+    pub label: String,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Manifest {
+    pub format: &'static str,
+    pub version: u32,
+    pub phrase: String,
+    pub label: String,
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub bits_per_sample: u16,
+    pub created_at: String,
+    pub samples: Vec<SampleEntry>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SampleSet {
+    /// The `samples.zip` written
+    pub package_path: String,
+    /// Where the model trained from it is looked for
+    pub model_path: String,
+    pub samples: usize,
+    pub rejected: usize,
+}
+
+/// File-safe label of `phrase`: "Hey Jarvis!" is `hey-jarvis`
+fn label_of(phrase: &str) -> String {
+    let mut label = String::new();
+    for c in phrase.trim().to_lowercase().chars() {
+        if c.is_alphanumeric() {
+            label.push(c);
+        } else if !label.is_empty() && !label.ends_with('-') {
+            label.push('-');
+        }
+    }
+    label.trim_end_matches('-').to_string()
+}
+
+/// A take as 16 kHz mono samples, or why it is not usable
+fn check_take(samples: &[f32], rate: u32) -> Result<Vec<f32>, String> {
+    let samples = audio::resample(samples, rate, audio::TARGET_RATE);
+    let duration_ms = samples.len() as u64 * 1000 / audio::TARGET_RATE as u64;
+    if duration_ms > MAX_SAMPLE_MS {
+        return Err(format!("Too long ({} ms); say only the phrase", duration_ms));
+    }
+    // 50 ms windows, so a short phrase in silence still counts
+    if !samples.chunks(audio::TARGET_RATE as usize / 20).any(|w| audio::rms(w) >= SPEECH_LEVEL) {
+        return Err("No speech heard".into());
+    }
+    Ok(samples)
+}
+
+fn package(manifest: &Manifest, takes: &[Vec<f32>]) -> Result<Vec<u8>, String> {
+    let mut entries = vec![(
+        "manifest.json",
+        serde_json::to_vec_pretty(manifest).map_err(|e| format!("Serialize error: {}", e))?,
+    )];
+    for (entry, take) in manifest.samples.iter().zip(takes) {
+        entries.push((entry.file.as_str(), audio::encode_wav(take, audio::TARGET_RATE)?));
+    }
+    crate::diagnostics::zip(&entries)
+}
+
+fn dir_of(label: &str) -> Option<PathBuf> {
+    crate::users::data_dir().map(|d| d.join("wake_words").join(label))
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Record `count` takes of `phrase` and store them as a training package.
+/// Blocking.
+pub fn collect(engine: &Mutex<VoiceEngine>, phrase: &str, count: Option<usize>) -> Result<SampleSet, String> {
+    let label = label_of(phrase);
+    if label.is_empty() {
+        return Err("The wake phrase needs at least one letter or digit".into());
+    }
+    let count = count.unwrap_or(DEFAULT_SAMPLES);
+    if !(1..=MAX_SAMPLES).contains(&count) {
+        return Err(format!("Between 1 and {} samples can be recorded", MAX_SAMPLES));
+    }
+    let dir = dir_of(&label).ok_or("Cannot determine data directory")?;
+
+    let mut takes = Vec::with_capacity(count);
+    let mut entries = Vec::with_capacity(count);
+    let mut rejected = 0;
+    while takes.len() < count {
+        if rejected >= count * ATTEMPTS_PER_SAMPLE {
+            return Err(format!("Too many takes rejected ({}); check the microphone", rejected));
+        }
+        crate::events::emit(
+            "wake-word-sample",
+            serde_json::json!({ "phrase": phrase, "sample": takes.len() + 1, "of": count }),
+        );
+        let captured = engine.lock().map_err(|e| e.to_string())?.record()?;
+        let wav = base64::engine::general_purpose::STANDARD
+            .decode(&captured.wav_base64)
+            .map_err(|e| format!("Base64 decode error: {}", e))?;
+        let (samples, rate) = audio::decode_wav(&wav)?;
+        match check_take(&samples, rate) {
+            Ok(take) => {
+                entries.push(SampleEntry {
+                    file: format!("samples/{}_{:02}.wav", label, takes.len() + 1),
+                    label: label.clone(),
+                    duration_ms: take.len() as u64 * 1000 / audio::TARGET_RATE as u64,
+                });
+                takes.push(take);
+            }
+            Err(e) => {
+                rejected += 1;
+                tracing::info!("[WakeSamples] Take rejected: {}", e);
+                crate::events::emit(
+                    "wake-word-sample",
+                    serde_json::json!({ "phrase": phrase, "sample": takes.len() + 1, "of": count, "rejected": e }),
+                );
+            }
+        }
+    }
+
+    let manifest = Manifest {
+        format: "forgeai-wake-samples",
+        version: 1,
+        phrase: phrase.trim().to_string(),
+        label: label.clone(),
+        sample_rate: audio::TARGET_RATE,
+        channels: 1,
+        bits_per_sample: 16,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        samples: entries,
+    };
+    let zip = package(&manifest, &takes)?;
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = dir.join("samples.zip");
+    std::fs::write(&path, zip).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    tracing::info!("[WakeSamples] {} takes of '{}' saved to {}", count, label, path.display());
+    Ok(SampleSet {
+        package_path: path.to_string_lossy().into_owned(),
+        model_path: dir.join(format!("{}.ppn", label)).to_string_lossy().into_owned(),
+        samples: count,
+        rejected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_takes_and_package() {
+        assert_eq!(label_of("  Hey Jarvis! "), "hey-jarvis");
+        assert_eq!(label_of("Olá, Computador"), "olá-computador");
+        assert_eq!(label_of("?!"), "");
+
+        let rate = 48_000;
+        assert!(check_take(&vec![0.0; rate as usize], rate).unwrap_err().contains("No speech"));
+        assert!(check_take(&audio::speech(rate, 4.0), rate).unwrap_err().contains("Too long"));
+        let take = check_take(&audio::speech(rate, 1.0), rate).unwrap();
+        assert_eq!(take.len(), audio::TARGET_RATE as usize);
+
+        let manifest = Manifest {
+            format: "forgeai-wake-samples",
+            version: 1,
+            phrase: "Hey Jarvis".into(),
+            label: "hey-jarvis".into(),
+            sample_rate: audio::TARGET_RATE,
+            channels: 1,
+            bits_per_sample: 16,
+            created_at: String::new(),
+            samples: vec![SampleEntry { file: "samples/hey-jarvis_01.wav".into(), label: "hey-jarvis".into(), duration_ms: 1000 }],
+        };
+        let zip = package(&manifest, &[take]).unwrap();
+        assert_eq!(&zip[..4], b"PK\x03\x04");
+        for name in ["manifest.json", "samples/hey-jarvis_01.wav"] {
+            assert!(zip.windows(name.len()).any(|w| w == name.as_bytes()));
+        }
+    }
+}
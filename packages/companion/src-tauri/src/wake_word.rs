@@ -4,21 +4,49 @@
 //! When speech is detected above the sensitivity threshold, emits
 //! a `wake-word-detected` Tauri event to activate the companion.
 //!
+//! Detections go through the microphone arbiter (see `mic_arbiter`), which
+//! drops those made during a recording or the echo of a reply, and lets a
+//! loud one interrupt speech that is playing.
+//!
+//! Listening can be suspended by policies (see `power`) without losing the
+//! user's choice: the engine stops while any suspension is active and
+//! restarts once the last one is lifted, if it was running before.
+//!
 //! Architecture note: Picovoice Porcupine support can be added as
 //! an optional feature once the `pv_porcupine` crate is republished
 //! on crates.io (all v3.x versions are currently yanked).
 
+use crate::mic_arbiter::WakeDecision;
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::collections::BTreeMap;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use tauri::{AppHandle, Emitter};
 
+/// Active suspensions by source, and whether to restart when they end
+struct Suspensions {
+    reasons: BTreeMap<&'static str, String>,
+    resume: bool,
+}
+
+static SUSPENSIONS: Mutex<Suspensions> = Mutex::new(Suspensions { reasons: BTreeMap::new(), resume: false });
+/// The custom keyword file, kept outside the engine so the status can read
+/// it while the engine is locked
+static KEYWORD_PATH: Mutex<Option<String>> = Mutex::new(None);
+/// When the last detection went through
+static LAST_DETECTION: Mutex<Option<String>> = Mutex::new(None);
+
+/// Why listening is suspended, if it is
+pub fn suspension() -> Option<String> {
+    let s = SUSPENSIONS.lock().ok()?;
+    (!s.reasons.is_empty()).then(|| s.reasons.values().cloned().collect::<Vec<_>>().join("; "))
+}
+
 /// Wake word engine state
 pub struct WakeWordEngine {
     running: Arc<AtomicBool>,
     sensitivity: f32,
     access_key: Option<String>,
-    keyword_path: Option<String>,
 }
 
 /// Event emitted when wake word is detected
@@ -36,6 +64,8 @@ pub struct WakeWordStatus {
     pub has_access_key: bool,
     pub keyword: String,
     pub audio_device: Option<String>,
+    /// Why listening is paused by a policy (e.g. low battery)
+    pub suspended: Option<String>,
 }
 
 impl WakeWordEngine {
@@ -44,7 +74,6 @@ impl WakeWordEngine {
             running: Arc::new(AtomicBool::new(false)),
             sensitivity: 0.5,
             access_key: None,
-            keyword_path: None,
         }
     }
 
@@ -54,16 +83,36 @@ impl WakeWordEngine {
         self.sensitivity = sensitivity.clamp(0.0, 1.0);
     }
 
+    /// Detection sensitivity, used the next time listening starts
+    pub fn set_sensitivity(&mut self, sensitivity: f32) {
+        self.sensitivity = sensitivity.clamp(0.0, 1.0);
+    }
+
     /// Set custom keyword model path (reserved for future Porcupine support)
     pub fn set_keyword_path(&mut self, path: String) {
-        self.keyword_path = Some(path);
+        if let Ok(mut current) = KEYWORD_PATH.lock() {
+            *current = Some(path);
+        }
+    }
+
+    /// Whether the keyword model loads: the custom keyword file when one is
+    /// set, else the input device the built-in detector listens on
+    pub fn check_model(&self) -> Result<String, String> {
+        match KEYWORD_PATH.lock().ok().and_then(|p| p.clone()) {
+            Some(path) => check_keyword_file(std::path::Path::new(&path)),
+            None => {
+                let device = crate::voice::input_device().ok_or("No audio input device found")?;
+                device
+                    .default_input_config()
+                    .map_err(|e| format!("No supported input config: {}", e))?;
+                Ok(format!("Built-in detector ready on '{}'", device.name().unwrap_or_default()))
+            }
+        }
     }
 
     /// Get current status
     pub fn status(&self) -> WakeWordStatus {
-        let audio_device = cpal::default_host()
-            .default_input_device()
-            .and_then(|d| d.name().ok());
+        let audio_device = crate::voice::input_device().and_then(|d| d.name().ok());
 
         WakeWordStatus {
             running: self.running.load(Ordering::Relaxed),
@@ -71,6 +120,7 @@ impl WakeWordEngine {
             has_access_key: self.access_key.is_some(),
             keyword: "Hey Forge".to_string(),
             audio_device,
+            suspended: suspension(),
         }
     }
 
@@ -79,6 +129,12 @@ impl WakeWordEngine {
         if self.running.load(Ordering::Relaxed) {
             return Err("Wake word engine already running".into());
         }
+        if crate::voice::mic_muted() {
+            return Err("Microphone is muted".into());
+        }
+        if let Some(reason) = suspension() {
+            return Err(format!("Listening is paused: {}", reason));
+        }
 
         let sensitivity = self.sensitivity;
         let running = self.running.clone();
@@ -87,12 +143,14 @@ impl WakeWordEngine {
 
         std::thread::spawn(move || {
             if let Err(e) = run_detection_loop(sensitivity, &running, &app_handle) {
-                log::error!("Wake word engine error: {}", e);
+                tracing::error!("Wake word engine error: {}", e);
                 running.store(false, Ordering::Relaxed);
+                crate::status::publish();
             }
         });
+        crate::status::publish();
 
-        log::info!(
+        tracing::info!(
             "Wake word engine started (sensitivity: {}, mode: energy-VAD)",
             self.sensitivity
         );
@@ -102,7 +160,42 @@ impl WakeWordEngine {
     /// Stop listening
     pub fn stop(&self) {
         self.running.store(false, Ordering::Relaxed);
-        log::info!("Wake word engine stopped");
+        tracing::info!("Wake word engine stopped");
+        crate::status::publish();
+    }
+
+    /// Suspend listening on behalf of `source`. Returns true when this
+    /// stopped the engine.
+    pub fn suspend(&self, source: &'static str, reason: String) -> bool {
+        let Ok(mut s) = SUSPENSIONS.lock() else {
+            return false;
+        };
+        let first = s.reasons.is_empty();
+        s.reasons.insert(source, reason);
+        if first && self.is_running() {
+            s.resume = true;
+            drop(s);
+            self.stop();
+            return true;
+        }
+        false
+    }
+
+    /// Lift the suspension of `source`. Returns true when this restarted
+    /// the engine.
+    pub fn resume(&self, source: &'static str, app_handle: AppHandle) -> Result<bool, String> {
+        let mut s = SUSPENSIONS.lock().map_err(|e| e.to_string())?;
+        if s.reasons.remove(source).is_none() || !s.reasons.is_empty() || !std::mem::take(&mut s.resume) {
+            return Ok(false);
+        }
+        drop(s);
+        self.start(app_handle)?;
+        Ok(true)
+    }
+
+    /// Shared running flag, readable without taking the engine lock
+    pub fn running_handle(&self) -> Arc<AtomicBool> {
+        self.running.clone()
     }
 
     /// Check if running
@@ -111,6 +204,35 @@ impl WakeWordEngine {
     }
 }
 
+/// The keyword model in use: the custom keyword file's name, or the
+/// built-in detector
+pub fn model() -> String {
+    match KEYWORD_PATH.lock().ok().and_then(|p| p.clone()) {
+        Some(path) => std::path::Path::new(&path)
+            .file_name()
+            .map_or(path.clone(), |n| n.to_string_lossy().into_owned()),
+        None => "built-in energy detector".into(),
+    }
+}
+
+/// When the last wake word went through (RFC 3339)
+pub fn last_detection() -> Option<String> {
+    LAST_DETECTION.lock().ok().and_then(|l| l.clone())
+}
+
+/// A Porcupine keyword file (`.ppn`) that exists and is not empty
+fn check_keyword_file(path: &std::path::Path) -> Result<String, String> {
+    let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    if !path.extension().is_some_and(|e| e.eq_ignore_ascii_case("ppn")) {
+        return Err(format!("'{}' is not a keyword file (.ppn)", name));
+    }
+    let size = std::fs::metadata(path).map_err(|e| format!("Keyword file '{}' cannot be read: {}", name, e))?.len();
+    if size == 0 {
+        return Err(format!("Keyword file '{}' is empty", name));
+    }
+    Ok(format!("Keyword model '{}' ({} KB)", name, size.div_ceil(1024)))
+}
+
 /// Energy-based voice activity detection loop.
 /// Detects sustained speech energy above threshold and emits activation event.
 /// This serves as a working fallback until Porcupine crate is available again.
@@ -119,12 +241,9 @@ fn run_detection_loop(
     running: &Arc<AtomicBool>,
     app_handle: &AppHandle,
 ) -> Result<(), String> {
-    let host = cpal::default_host();
-    let device = host
-        .default_input_device()
-        .ok_or("No audio input device found")?;
+    let device = crate::voice::input_device().ok_or("No audio input device found")?;
 
-    log::info!(
+    tracing::info!(
         "Wake word: using input device '{}'",
         device.name().unwrap_or_default()
     );
@@ -150,7 +269,7 @@ fn run_detection_loop(
                 let _ = tx.try_send(data.to_vec());
             },
             |err| {
-                log::error!("Audio stream error: {}", err);
+                tracing::error!("Audio stream error: {}", err);
             },
             None,
         )
@@ -160,7 +279,7 @@ fn run_detection_loop(
         .play()
         .map_err(|e| format!("Failed to start audio stream: {}", e))?;
 
-    log::info!("Wake word: audio stream active, listening (energy-VAD)...");
+    tracing::info!("Wake word: audio stream active, listening (energy-VAD)...");
 
     // Energy threshold: lower sensitivity = harder to trigger
     // sensitivity 0.0 → threshold 0.10 (hard)
@@ -194,17 +313,28 @@ fn run_detection_loop(
                 }
 
                 if sustained_count >= sustained_frames_required {
-                    log::info!("Wake word: voice activity detected (RMS: {:.4})", rms);
+                    sustained_count = 0;
+                    let decision = crate::mic_arbiter::on_wake(rms / energy_threshold);
+                    if !matches!(decision, WakeDecision::Accept | WakeDecision::BargeIn) {
+                        tracing::debug!("Wake word: detection dropped ({:?})", decision);
+                        continue;
+                    }
+                    tracing::info!("Wake word: voice activity detected (RMS: {:.4})", rms);
 
                     let event = WakeWordEvent {
                         keyword: "Hey Forge".to_string(),
                         timestamp: chrono::Utc::now().to_rfc3339(),
                     };
+                    if let Ok(mut last) = LAST_DETECTION.lock() {
+                        *last = Some(event.timestamp.clone());
+                    }
 
+                    crate::webhooks::fire("wake_word", &event);
                     let _ = app_handle.emit("wake-word-detected", event);
+                    crate::metrics::increment(crate::metrics::counter::WAKE_DETECTIONS);
+                    crate::usage::record(crate::usage::Metric::WakeDetections, 1);
 
                     // Cooldown to prevent rapid re-triggers
-                    sustained_count = 0;
                     std::thread::sleep(std::time::Duration::from_secs(3));
                 }
             }
@@ -214,7 +344,7 @@ fn run_detection_loop(
     }
 
     drop(stream);
-    log::info!("Wake word: detection loop ended");
+    tracing::info!("Wake word: detection loop ended");
     Ok(())
 }
 
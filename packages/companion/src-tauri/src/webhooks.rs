@@ -0,0 +1,182 @@
+//! # Webhooks
+//!
+//! Outbound hooks for wiring the companion into Home Assistant, n8n and
+//! the like without touching the Gateway. Each hook (`webhooks` in the
+//! settings) names the events it wants and either a `url`, which gets the
+//! event POSTed as JSON, or a local `command`, which is run with the same
+//! JSON on stdin and the event name in `FORGEAI_EVENT`.
+//!
+//! | Event             | When                                                |
+//! |-------------------|-----------------------------------------------------|
+//! | `wake_word`       | the wake word was heard                             |
+//! | `action_executed` | a Gateway-pushed action or voice shortcut finished  |
+//! | `reminder_fired`  | a reminder went off                                 |
+//! | `timer_finished`  | a timer or alarm ended                              |
+//!
+//! Hooks run in the background and never hold up the companion; failures
+//! are logged. `test_webhook` sends a `test` event to one hook.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Events a hook can subscribe to
+pub const EVENTS: &[&str] = &["wake_word", "action_executed", "reminder_fired", "timer_finished"];
+/// Longest a POST or a command may take
+const HOOK_TIMEOUT: Duration = Duration::from_secs(15);
+
+static HOOKS: Mutex<Vec<Webhook>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhook {
+    pub name: String,
+    /// Subscribed events (see [`EVENTS`])
+    pub events: Vec<String>,
+    /// POST the event here...
+    #[serde(default)]
+    pub url: Option<String>,
+    /// ...or run this program
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl Webhook {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Webhooks need a name".into());
+        }
+        if let Some(event) = self.events.iter().find(|e| !EVENTS.contains(&e.as_str())) {
+            return Err(format!("Unknown webhook event '{}' (use {})", event, EVENTS.join(", ")));
+        }
+        match (&self.url, &self.command) {
+            (Some(url), None) => {
+                let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid webhook URL '{}': {}", url, e))?;
+                if !matches!(parsed.scheme(), "http" | "https") {
+                    return Err(format!("Webhook '{}' must use http or https", self.name));
+                }
+            }
+            (None, Some(command)) if !command.trim().is_empty() => {}
+            _ => return Err(format!("Webhook '{}' needs either a URL or a command", self.name)),
+        }
+        Ok(())
+    }
+}
+
+/// Use `hooks` from now on
+pub fn set_hooks(hooks: &[Webhook]) {
+    if let Ok(mut current) = HOOKS.lock() {
+        *current = hooks.to_vec();
+    }
+}
+
+fn body(event: &str, data: serde_json::Value) -> serde_json::Value {
+    serde_json::json!({
+        "event": event,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+        "companionId": crate::connection::GatewayConnection::load_credentials().map(|c| c.companion_id),
+        "data": data,
+    })
+}
+
+async fn deliver(hook: &Webhook, event: &str, body: &serde_json::Value) -> Result<(), String> {
+    if let Some(url) = &hook.url {
+        let client = crate::proxy::apply(reqwest::Client::builder())
+            .build()
+            .map_err(|e| format!("HTTP client error: {}", e))?;
+        let resp = client
+            .post(url)
+            .json(body)
+            .timeout(HOOK_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| format!("POST failed: {}", e))?;
+        if !resp.status().is_success() {
+            return Err(format!("HTTP {}", resp.status()));
+        }
+        return Ok(());
+    }
+    let Some(command) = &hook.command else { return Ok(()) };
+    let mut child = tokio::process::Command::new(command)
+        .args(&hook.args)
+        .env("FORGEAI_EVENT", event)
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| format!("Cannot run '{}': {}", command, e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        use tokio::io::AsyncWriteExt;
+        let _ = stdin.write_all(body.to_string().as_bytes()).await;
+    }
+    match tokio::time::timeout(HOOK_TIMEOUT, child.wait()).await {
+        Ok(Ok(status)) if status.success() => Ok(()),
+        Ok(Ok(status)) => Err(format!("'{}' exited with {}", command, status)),
+        Ok(Err(e)) => Err(e.to_string()),
+        Err(_) => Err(format!("'{}' timed out", command)),
+    }
+}
+
+// ─── API ────────────────────────────────────────────
+
+/// Send `event` to every enabled hook subscribed to it, in the background
+pub fn fire<S: Serialize>(event: &'static str, data: S) {
+    let hooks: Vec<Webhook> = HOOKS
+        .lock()
+        .map(|h| h.iter().filter(|h| h.enabled && h.events.iter().any(|e| e == event)).cloned().collect())
+        .unwrap_or_default();
+    if hooks.is_empty() {
+        return;
+    }
+    let body = body(event, serde_json::to_value(data).unwrap_or_default());
+    tauri::async_runtime::spawn(async move {
+        for hook in hooks {
+            match deliver(&hook, event, &body).await {
+                Ok(()) => tracing::debug!("[Webhooks] '{}' got {}", hook.name, event),
+                Err(e) => tracing::warn!("[Webhooks] '{}' failed for {}: {}", hook.name, event, e),
+            }
+        }
+    });
+}
+
+/// Send a `test` event to the hook called `name` and wait for it
+pub async fn test(name: &str) -> Result<(), String> {
+    let hook = HOOKS
+        .lock()
+        .ok()
+        .and_then(|h| h.iter().find(|h| h.name == name).cloned())
+        .ok_or_else(|| format!("No webhook named '{}'", name))?;
+    deliver(&hook, "test", &body("test", serde_json::json!({}))).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_webhook_validation() {
+        let hook = Webhook {
+            name: "Home Assistant".into(),
+            events: vec!["wake_word".into(), "reminder_fired".into()],
+            url: Some("http://homeassistant.local:8123/api/webhook/forgeai".into()),
+            command: None,
+            args: Vec::new(),
+            enabled: true,
+        };
+        assert!(hook.validate().is_ok());
+        assert!(Webhook { events: vec!["boot".into()], ..hook.clone() }.validate().is_err());
+        assert!(Webhook { url: Some("file:///etc/passwd".into()), ..hook.clone() }.validate().is_err());
+        assert!(Webhook { command: Some("notify.sh".into()), ..hook.clone() }.validate().is_err());
+        assert!(Webhook { url: None, command: Some("notify.sh".into()), ..hook.clone() }.validate().is_ok());
+        assert!(Webhook { url: None, ..hook }.validate().is_err());
+    }
+}
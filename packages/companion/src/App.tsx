@@ -16,13 +16,84 @@ declare global {
 const invoke = (cmd: string, args?: Record<string, unknown>) =>
   window.__TAURI__?.core.invoke(cmd, args) ?? Promise.reject('Tauri not available');
 
+/** Rejection from a companion command: translated message plus technical details */
+/** `CompanionError` kinds (src-tauri/src/i18n.rs) */
+type ErrorCode =
+  | 'not_paired' | 'session_expired' | 'rate_limited' | 'gateway_unreachable' | 'gateway_error'
+  | 'mic_muted' | 'device_unavailable' | 'recording_too_short' | 'safety_denied' | 'pairing_failed'
+  | 'update_unavailable' | 'update_failed' | 'invalid_setting' | 'storage_error' | 'unexpected';
+
+interface CommandError {
+  code: ErrorCode;
+  message: string;
+  details: string;
+}
+
+const isCommandError = (e: unknown): e is CommandError =>
+  typeof e === 'object' && e !== null && 'code' in e && 'message' in e;
+
+/** User-facing text for a rejected invoke (details go to the console) */
+const errorText = (e: unknown): string => {
+  if (!isCommandError(e)) return String(e);
+  console.warn(`[ForgeAI] ${e.code}: ${e.details}`);
+  return e.message;
+};
+
+interface ConnectionStatus {
+  state: 'online' | 'degraded' | 'offline';
+  latency_ms: number | null;
+  live_channel: boolean;
+  consecutive_failures: number;
+  last_checked: string | null;
+  detail: string | null;
+}
+
 interface CompanionStatus {
   connected: boolean;
+  paired: boolean;
+  link: ConnectionStatus;
   gateway_url: string | null;
   companion_id: string | null;
   auth_token: string | null;
   safety_active: boolean;
+  recording: boolean;
+  wake_word: boolean;
+  mic_muted: boolean;
   version: string;
+  subsystems: {
+    connection: {
+      state: ConnectionStatus['state'];
+      latency_ms: number | null;
+      last_heartbeat: string | null;
+      live_channel: boolean;
+      consecutive_failures: number;
+      queued_messages: number;
+      detail: string | null;
+    };
+    voice: {
+      devices_ok: boolean;
+      input_device: string | null;
+      output_device: string | null;
+      recording_failures: number;
+      playback_failures: number;
+      last_error: string | null;
+    };
+    wake_word: { running: boolean; suspended: string | null; model: string; last_detection: string | null };
+    safety: { mode: 'standard' | 'speaker_verified' | 'simulation'; policy_version: number; recent_blocks: number };
+  };
+}
+
+interface ReadinessCheck {
+  id: string;
+  label: string;
+  state: 'passed' | 'failed' | 'skipped';
+  detail: string;
+  fix?: string;
+}
+
+interface ReadinessReport {
+  ready: boolean;
+  checks: ReadinessCheck[];
 }
 
 interface AgentStep {
@@ -106,14 +177,19 @@ export default function App() {
   const [pairingCode, setPairingCode] = useState('');
   const [pairing, setPairing] = useState(false);
   const [pairError, setPairError] = useState('');
+  const [reverseCode, setReverseCode] = useState<string | null>(null);
   const [sessionId, setSessionId] = useState<string | null>(() => {
     try { return localStorage.getItem('forgeai_session_id'); } catch { return null; }
   });
   const [recording, setRecording] = useState(false);
   const [voiceMode, setVoiceMode] = useState<'idle' | 'listening' | 'processing' | 'speaking'>('idle');
   const [wakeWordEnabled, setWakeWordEnabled] = useState(false);
+  const [micMuted, setMicMuted] = useState(false);
+  const [listeningPaused, setListeningPaused] = useState<string | null>(null);
   const [wakePhrase, setWakePhrase] = useState('Hey Forge');
   const [alwaysListening, setAlwaysListening] = useState(false);
+  const [micTest, setMicTest] = useState<string | null>(null);
+  const [micTesting, setMicTesting] = useState(false);
   const [audioLevels, setAudioLevels] = useState<number[]>([0,0,0,0,0,0,0,0,0,0,0,0]);
   const [expandedImage, setExpandedImage] = useState<string | null>(null);
   // Config Sync state
@@ -124,6 +200,7 @@ export default function App() {
   const [syncGeneratedCode, setSyncGeneratedCode] = useState('');
   // Real-time agent progress via WebSocket
   const [agentProgress, setAgentProgress] = useState<{ tool?: string; status?: string } | null>(null);
+  const [actionProgress, setActionProgress] = useState<{ jobId: string; action: string; percent: number | null; phase: string } | null>(null);
   const [stepsExpanded, setStepsExpanded] = useState<Record<number, boolean>>({});
   // Session history state
   const [sessions, setSessions] = useState<Array<{ id: string; title: string; messageCount: number; updatedAt: string; lastMessage?: string }>>([]);
@@ -173,6 +250,83 @@ export default function App() {
           }
         });
         cleanups.push(u3 as unknown as () => void);
+
+        // Pairing, Gateway link, recording and wake word — pushed on every change
+        const u4 = await listen<CompanionStatus>('companion-status', (ev) => {
+          setStatus(ev.payload);
+          setWakeWordEnabled(ev.payload.wake_word);
+          setMicMuted(ev.payload.mic_muted);
+        });
+        cleanups.push(u4 as unknown as () => void);
+
+        // Gateway removed this companion — credentials are already cleared
+        const u5 = await listen<{ reason: string }>('paired-revoked', (ev) => {
+          setStatus(null);
+          setSessionId(null);
+          setPairError(`Companion was revoked by the Gateway (${ev.payload.reason}). Pair again to reconnect.`);
+          setView('setup');
+        });
+        cleanups.push(u5 as unknown as () => void);
+
+        // Reverse pairing: the code shown here was entered on the Gateway (or expired)
+        const u6 = await listen<{ status: string; message?: string }>('reverse-pairing', async (ev) => {
+          setReverseCode(null);
+          if (ev.payload.status === 'paired') {
+            setPairError('');
+            await loadStatus();
+            setView('chat');
+          } else if (ev.payload.status === 'expired') {
+            setPairError('The code expired before it was entered on the Gateway.');
+          } else if (ev.payload.status === 'failed') {
+            setPairError(ev.payload.message || 'Pairing failed');
+          }
+        });
+        cleanups.push(u6 as unknown as () => void);
+
+        // Global hotkeys (handled in Rust, the voice pipeline runs here)
+        const u7 = await listen('hotkey-push-to-talk', () => {
+          if (voiceModeRef.current === 'idle') {
+            handleVoiceJarvis();
+          }
+        });
+        cleanups.push(u7 as unknown as () => void);
+
+        const u8 = await listen<{ enabled: boolean }>('wake-word-toggled', (ev) => {
+          setWakeWordEnabled(ev.payload.enabled);
+        });
+        cleanups.push(u8 as unknown as () => void);
+
+        // Disconnect can also come from the tray menu
+        const u9 = await listen('disconnected', () => {
+          setStatus(null);
+          setSessionId(null);
+          setView('setup');
+          setMessages([]);
+        });
+        cleanups.push(u9 as unknown as () => void);
+
+        // Mute from the tray or hotkey — Rust already stopped any recording
+        const u10 = await listen<{ muted: boolean }>('mic-muted', (ev) => {
+          setMicMuted(ev.payload.muted);
+          if (ev.payload.muted) {
+            setVoiceMode('idle');
+            setRecording(false);
+            setAudioLevels([0,0,0,0,0,0,0,0,0,0,0,0]);
+          }
+        });
+        cleanups.push(u10 as unknown as () => void);
+
+        // Long-running local actions (copies, transfers, shell commands)
+        const u11 = await listen<{ jobId: string; action: string; percent: number | null; phase: string; done: boolean }>('action-progress', (ev) => {
+          setActionProgress(ev.payload.done ? null : ev.payload);
+        });
+        cleanups.push(u11 as unknown as () => void);
+
+        // Battery policy paused or resumed listening
+        const u12 = await listen<{ reason: string | null }>('listening-suspended', (ev) => {
+          setListeningPaused(ev.payload.reason);
+        });
+        cleanups.push(u12 as unknown as () => void);
       } catch {
         // Tauri event API not available
       }
@@ -194,7 +348,7 @@ export default function App() {
 
   // Load session list from Gateway via Rust backend
   const loadSessions = useCallback(async () => {
-    if (!status?.connected) return;
+    if (!status?.paired) return;
     setSessionsLoading(true);
     try {
       const data = (await invoke('list_sessions')) as { sessions?: Array<{ id: string; title: string; messageCount: number; updatedAt: string; lastMessage?: string }> };
@@ -206,7 +360,7 @@ export default function App() {
     } finally {
       setSessionsLoading(false);
     }
-  }, [status?.connected]);
+  }, [status?.paired]);
 
   // Load session history when selecting a session via Rust backend
   const loadSessionHistory = useCallback(async (sid: string) => {
@@ -257,14 +411,14 @@ export default function App() {
 
   // Load last session history and session list on connect
   useEffect(() => {
-    if (status?.connected) {
+    if (status?.paired) {
       loadSessions();
       if (sessionId) {
         loadSessionHistory(sessionId);
       }
     }
   // eslint-disable-next-line react-hooks/exhaustive-deps
-  }, [status?.connected]);
+  }, [status?.paired]);
 
   useEffect(() => {
     messagesEnd.current?.scrollIntoView({ behavior: 'smooth' });
@@ -330,7 +484,7 @@ export default function App() {
                   type: 'action_result',
                   requestId: msg.requestId,
                   success: false,
-                  output: `Companion error: ${err}`,
+                  output: `Companion error: ${isCommandError(err) ? err.details : err}`,
                 }));
               });
               return;
@@ -383,20 +537,34 @@ export default function App() {
     try {
       const s = (await invoke('get_status')) as CompanionStatus;
       setStatus(s);
-      if (!s.connected) setView('setup');
+      if (!s.paired) setView('setup');
     } catch {
       setView('setup');
     }
   };
 
-  const handlePair = async () => {
+  const handlePair = () =>
+    runPairing(() =>
+      invoke('pair_with_gateway', {
+        gatewayUrl: gatewayUrl.trim(),
+        pairingCode: pairingCode.trim(),
+      }),
+    );
+
+  // Screenshot or photo of the Dashboard's pairing QR, decoded on the Rust side
+  const handlePairImage = (file: File) => {
+    const reader = new FileReader();
+    reader.onload = () => runPairing(() => invoke('pair_with_qr_image', { image: reader.result as string }));
+    reader.onerror = () => setPairError('Could not read the image file');
+    reader.readAsDataURL(file);
+  };
+
+  const runPairing = async (pair: () => Promise<unknown>) => {
     setPairing(true);
     setPairError('');
     try {
-      await invoke('pair_with_gateway', {
-        gatewayUrl: gatewayUrl.trim(),
-        pairingCode: pairingCode.trim(),
-      });
+      const report = (await pair()) as ReadinessReport;
+      const failed = report.checks.filter((c) => c.state === 'failed');
       // Force the Rust WS loop to reconnect with fresh credentials
       await invoke('force_reconnect_gateway_ws').catch(() => {});
       await loadStatus();
@@ -404,17 +572,36 @@ export default function App() {
       setMessages([
         {
           role: 'system',
-          content: 'Connected to ForgeAI Gateway! Say something or type a command.',
+          content: report.ready
+            ? 'Connected to ForgeAI Gateway! Say something or type a command.'
+            : `Paired, but not everything works yet:\n${failed
+                .map((c) => `• ${c.label}: ${c.detail}${c.fix ? ` — ${c.fix}` : ''}`)
+                .join('\n')}`,
           timestamp: Date.now(),
         },
       ]);
       // Wake word is OFF by default — user can enable in Settings
     } catch (e) {
-      setPairError(String(e));
+      setPairError(errorText(e));
     }
     setPairing(false);
   };
 
+  const handleReversePair = async () => {
+    setPairError('');
+    try {
+      const offer = (await invoke('start_reverse_pairing', { gatewayUrl: gatewayUrl.trim() })) as { code: string };
+      setReverseCode(offer.code);
+    } catch (e) {
+      setPairError(errorText(e));
+    }
+  };
+
+  const cancelReversePair = () => {
+    invoke('cancel_reverse_pairing').catch(() => {});
+    setReverseCode(null);
+  };
+
   const handleDisconnect = async () => {
     try {
       await invoke('disconnect');
@@ -596,7 +783,7 @@ export default function App() {
     } catch (e) {
       setMessages((prev) => [
         ...prev,
-        { role: 'assistant', content: `Error: ${e}`, timestamp: Date.now() },
+        { role: 'assistant', content: `Error: ${errorText(e)}`, timestamp: Date.now() },
       ]);
     }
     setLoading(false);
@@ -636,11 +823,10 @@ export default function App() {
         ]);
       }
     } catch (e) {
-      const errMsg = String(e);
-      if (!errMsg.includes('too short')) {
+      if (!(isCommandError(e) && e.code === 'recording_too_short')) {
         setMessages((prev) => [
           ...prev,
-          { role: 'assistant', content: `Voice error: ${errMsg}`, timestamp: Date.now() },
+          { role: 'assistant', content: `Voice error: ${errorText(e)}`, timestamp: Date.now() },
         ]);
       }
     }
@@ -657,6 +843,22 @@ export default function App() {
     }
   };
 
+  // Record 3 s, play it back and show the measured level
+  const handleMicTest = async () => {
+    setMicTesting(true);
+    setMicTest('Recording — speak now...');
+    try {
+      const r = (await invoke('voice_mic_test')) as { peakDb: number; rmsDb: number; clipped: number; silent: boolean; inputDevice: string };
+      if (r.silent) setMicTest(`No sound from ${r.inputDevice || 'the microphone'}`);
+      else if (r.clipped > 0.01) setMicTest(`Too loud — peak ${r.peakDb.toFixed(1)} dB, lower the input gain`);
+      else setMicTest(`OK — peak ${r.peakDb.toFixed(1)} dB, average ${r.rmsDb.toFixed(1)} dB`);
+    } catch (e) {
+      setMicTest(errorText(e));
+    } finally {
+      setMicTesting(false);
+    }
+  };
+
   // Start wake word detection when connected
   const startWakeWord = async () => {
     try {
@@ -697,7 +899,7 @@ export default function App() {
           </div>
           <p className="text-[10px] text-zinc-600 mt-6">getforgeai.com</p>
           <button
-            onClick={() => setView(status?.connected ? 'chat' : 'setup')}
+            onClick={() => setView(status?.paired ? 'chat' : 'setup')}
             className="mt-4 px-6 py-2 rounded-lg text-xs font-medium text-zinc-400 hover:text-white bg-zinc-800 hover:bg-zinc-700 transition-all"
           >
             Back
@@ -780,6 +982,40 @@ export default function App() {
             )}
           </button>
 
+          <label className="text-xs text-zinc-500 hover:text-zinc-300 mt-3 cursor-pointer">
+            Or pair from a screenshot of the QR code
+            <input
+              type="file"
+              accept="image/png,image/jpeg"
+              className="hidden"
+              disabled={pairing}
+              onChange={(e) => {
+                const file = e.target.files?.[0];
+                if (file) handlePairImage(file);
+                e.target.value = '';
+              }}
+            />
+          </label>
+
+          {/* Reverse pairing: show a code to type on the Gateway */}
+          {reverseCode ? (
+            <div className="setup-card setup-card-mt text-center">
+              <p className="text-sm text-zinc-400">Enter this code on the Gateway</p>
+              <p className="text-2xl font-mono font-bold text-white tracking-widest mt-2">{reverseCode}</p>
+              <button onClick={cancelReversePair} className="text-xs text-zinc-500 hover:text-zinc-300 mt-3">
+                Cancel
+              </button>
+            </div>
+          ) : (
+            <button
+              onClick={handleReversePair}
+              disabled={pairing || !gatewayUrl.trim()}
+              className="text-xs text-zinc-500 hover:text-zinc-300 disabled:opacity-40 mt-3"
+            >
+              Pairing from your phone? Show a code instead
+            </button>
+          )}
+
           {/* Status indicator */}
           <div className="flex items-center gap-2 mt-4">
             <div className="w-2 h-2 rounded-full bg-emerald-500" />
@@ -899,7 +1135,7 @@ export default function App() {
             <div className="settings-row">
               <div className="settings-row-left">
                 <span className="settings-label">Wake Word</span>
-                <span className="settings-hint">Activate by voice command</span>
+                <span className="settings-hint">{listeningPaused ? `Paused — ${listeningPaused}` : 'Activate by voice command'}</span>
               </div>
               <label className="settings-toggle">
                 <input
@@ -951,6 +1187,20 @@ export default function App() {
                 <span className="settings-toggle-slider" />
               </label>
             </div>
+
+            <div className="settings-row">
+              <div className="settings-row-left">
+                <span className="settings-label">Microphone Test</span>
+                <span className="settings-hint">{micTest ?? 'Record 3 seconds and play them back'}</span>
+              </div>
+              <button
+                className="settings-btn settings-btn-secondary"
+                onClick={handleMicTest}
+                disabled={micMuted || micTesting}
+              >
+                Test
+              </button>
+            </div>
           </div>
 
           {/* Connection Section */}
@@ -1110,6 +1360,17 @@ export default function App() {
           </div>
         ))}
 
+        {actionProgress && (
+          <div className="chat-bubble chat-bubble-assistant loading-bubble">
+            <div className="agent-progress">
+              <div className="agent-progress-dot" />
+              <span className="agent-progress-text">
+                {actionProgress.phase}{actionProgress.percent !== null ? ` — ${actionProgress.percent}%` : ''}
+              </span>
+            </div>
+          </div>
+        )}
+
         {loading && (
           <div className="chat-bubble chat-bubble-assistant loading-bubble">
             {agentProgress?.tool ? (
@@ -1184,8 +1445,9 @@ export default function App() {
       <div className="chat-input-bar">
         <button
           onClick={handleMicToggle}
+          disabled={micMuted && voiceMode === 'idle'}
           className={`chat-mic-btn ${voiceMode !== 'idle' ? 'recording' : ''}`}
-          title={voiceMode !== 'idle' ? 'Stop' : 'Voice input (Jarvis mode)'}
+          title={micMuted ? 'Microphone muted' : voiceMode !== 'idle' ? 'Stop' : 'Voice input (Jarvis mode)'}
         >
           {voiceMode !== 'idle' ? <MicOff style={{ width: 16, height: 16 }} /> : <Mic style={{ width: 16, height: 16 }} />}
         </button>